@@ -0,0 +1,137 @@
+//! Operation Cancellation
+//!
+//! Cooperative cancellation for long-running commands. A command registers
+//! its operation id, passes the token down to the service loop, and the loop
+//! checks it between units of work and returns a partial result marked
+//! `cancelled` once it is set.
+
+use crate::domain::errors::DomainError;
+use dashmap::DashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Flag shared between a running operation and whoever may cancel it.
+///
+/// The default token is never cancelled, for callers that run an operation
+/// without giving it an id.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Asks the operation to stop at its next check.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Release);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+}
+
+/// Tokens of running operations by operation id.
+#[derive(Default)]
+pub struct CancellationRegistry {
+    tokens: Arc<DashMap<String, CancellationToken>>,
+}
+
+impl CancellationRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers an operation and returns the guard that holds its token.
+    /// The id is released when the guard is dropped. Without an id the
+    /// operation runs with a token nothing else can reach.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an operation with the same id is still running.
+    pub fn register(&self, operation_id: Option<String>) -> Result<OperationGuard, DomainError> {
+        let token = CancellationToken::new();
+        if let Some(id) = &operation_id {
+            match self.tokens.entry(id.clone()) {
+                dashmap::Entry::Occupied(_) => {
+                    return Err(DomainError::ValidationError(format!(
+                        "Operation {} is already running",
+                        id
+                    )));
+                }
+                dashmap::Entry::Vacant(entry) => {
+                    entry.insert(token.clone());
+                }
+            }
+        }
+
+        Ok(OperationGuard {
+            token,
+            operation_id,
+            tokens: self.tokens.clone(),
+        })
+    }
+
+    /// Cancels a running operation. Returns false if none has this id.
+    pub fn cancel(&self, operation_id: &str) -> bool {
+        match self.tokens.get(operation_id) {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Keeps an operation registered for as long as it runs.
+pub struct OperationGuard {
+    token: CancellationToken,
+    operation_id: Option<String>,
+    tokens: Arc<DashMap<String, CancellationToken>>,
+}
+
+impl OperationGuard {
+    pub fn token(&self) -> &CancellationToken {
+        &self.token
+    }
+}
+
+impl Drop for OperationGuard {
+    fn drop(&mut self) {
+        if let Some(id) = &self.operation_id {
+            self.tokens.remove(id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancel_reaches_registered_operation_until_it_finishes() {
+        let registry = CancellationRegistry::new();
+        let guard = registry.register(Some("scan".to_string())).unwrap();
+        assert!(!guard.token().is_cancelled());
+
+        // The id is taken while the operation runs
+        assert!(registry.register(Some("scan".to_string())).is_err());
+
+        assert!(registry.cancel("scan"));
+        assert!(guard.token().is_cancelled());
+
+        drop(guard);
+        assert!(!registry.cancel("scan"));
+        assert!(registry.register(Some("scan".to_string())).is_ok());
+    }
+
+    #[test]
+    fn anonymous_operations_cannot_be_cancelled() {
+        let registry = CancellationRegistry::new();
+        let guard = registry.register(None).unwrap();
+        assert!(!registry.cancel(""));
+        assert!(!guard.token().is_cancelled());
+    }
+}
@@ -15,6 +15,76 @@ pub struct ItemDto {
     pub modified_time: Option<i64>,
     pub created_at: i64,
     pub updated_at: i64,
+    /// Last time the item's tags changed (None if never tagged).
+    pub tags_updated_at: Option<i64>,
+    /// Star rating from 0 to 5 (None if unrated).
+    pub rating: Option<u8>,
+    /// Number of tags on the item; only filled in when the caller asks for it.
+    #[serde(default)]
+    pub tag_count: Option<u64>,
+    /// NTFS File Reference Number, if one is recorded. Serialized as a string
+    /// because it can exceed JavaScript's safe integer range. Only filled in
+    /// for items loaded through the item service; search results always carry
+    /// `None`.
+    #[serde(default, with = "optional_u64_string")]
+    pub file_reference_number: Option<u64>,
+    /// Rename tracking status. Only filled in by single-item lookups
+    /// (`get_item`, `refresh_item_frn`); lists and search results carry `None`.
+    #[serde(default)]
+    pub frn_status: Option<FrnStatus>,
+}
+
+/// Whether USN refresh can follow an item's renames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FrnStatus {
+    /// A File Reference Number is recorded.
+    Tracked,
+    /// On an NTFS drive, but no File Reference Number is recorded yet.
+    Untracked,
+    /// Not on a local NTFS drive, so there is none to record.
+    NotNtfs,
+}
+
+/// (De)serializes an `Option<u64>` as an optional decimal string.
+mod optional_u64_string {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &Option<u64>, serializer: S) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(value) => serializer.serialize_str(&value.to_string()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<u64>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|value| value.parse().map_err(serde::de::Error::custom))
+            .transpose()
+    }
+}
+
+/// DTO pairing a requested path with its item record, if registered.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathCheckDto {
+    pub path: String,
+    pub item: Option<ItemDto>,
+}
+
+/// Result of a bulk path prefix update.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BulkUpdateResultDto {
+    pub updated_count: usize,
+}
+
+/// Result of normalizing stored item paths.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathNormalizationDto {
+    pub renamed_count: usize,
+    /// Items deleted after their tags moved to the item with the same path
+    pub merged_count: usize,
 }
 
 /// DTO for creating a new item.
@@ -40,7 +110,11 @@ pub struct TagGroupDto {
     pub id: i64,
     pub name: String,
     pub color: Option<String>,
+    /// CSS name of `color` when it is exactly a named color, e.g. "tomato".
+    #[serde(default)]
+    pub color_name: Option<String>,
     pub display_order: i32,
+    pub exclusive: bool,
     pub created_at: i64,
     pub updated_at: i64,
 }
@@ -50,6 +124,8 @@ pub struct TagGroupDto {
 pub struct CreateTagGroupDto {
     pub name: String,
     pub color: Option<String>,
+    #[serde(default)]
+    pub exclusive: bool,
 }
 
 /// DTO for updating a tag group.
@@ -57,6 +133,16 @@ pub struct CreateTagGroupDto {
 pub struct UpdateTagGroupDto {
     pub name: Option<String>,
     pub color: Option<String>,
+    pub exclusive: Option<bool>,
+}
+
+/// DTO for an item carrying more than one tag of a group, i.e. a violation
+/// of that group's exclusive mode.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExclusiveViolationDto {
+    pub item_id: i64,
+    pub path: String,
+    pub tag_ids: Vec<i64>,
 }
 
 /// DTO for Tag data transfer.
@@ -69,6 +155,43 @@ pub struct TagDto {
     pub updated_at: i64,
 }
 
+/// DTO for one page of tags, with the number of tags across all pages.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PagedTagsDto {
+    pub tags: Vec<TagDto>,
+    pub total_count: u64,
+}
+
+/// DTO for a tag with the number of items carrying it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagCountDto {
+    pub tag: TagDto,
+    pub count: i64,
+}
+
+/// DTO for two tags carried together, with the number of items carrying both.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagPairDto {
+    pub tag_id_a: i64,
+    pub tag_id_b: i64,
+    pub count: i64,
+}
+
+/// Output format for the tag vocabulary export.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum VocabularyFormat {
+    Markdown,
+    Csv,
+}
+
+/// DTO summarizing a tag vocabulary export.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VocabularyExportDto {
+    pub groups: usize,
+    pub tags: usize,
+}
+
 /// DTO for creating a new tag.
 #[derive(Debug, Clone, Deserialize)]
 pub struct CreateTagDto {
@@ -83,21 +206,75 @@ pub struct UpdateTagDto {
     pub group_id: Option<i64>,
 }
 
+/// DTO naming a template tag by group and value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TagSpecDto {
+    pub group_name: String,
+    pub value: String,
+}
+
 /// DTO for TagTemplate data transfer.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TagTemplateDto {
     pub id: i64,
     pub name: String,
     pub tag_ids: Vec<i64>,
+    /// Tags named by group and value, created on apply if missing.
+    pub tag_specs: Vec<TagSpecDto>,
     pub created_at: i64,
     pub updated_at: i64,
 }
 
+/// DTO previewing what applying a template to an item would change.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplatePreviewDto {
+    pub to_add: Vec<TagDto>,
+    pub already_present: Vec<TagDto>,
+    /// Specs naming tags that applying would create.
+    pub to_create: Vec<TagSpecDto>,
+}
+
+/// DTO describing how a template relates to an item's current tags.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateApplicabilityDto {
+    pub template_id: i64,
+    pub name: String,
+    pub would_add: Vec<TagDto>,
+    pub already_has: Vec<TagDto>,
+    /// Specs naming tags that applying would create.
+    pub would_create: Vec<TagSpecDto>,
+    pub fully_applied: bool,
+}
+
+/// DTO for one path in a template apply preview.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathApplyPreviewDto {
+    pub path: String,
+    pub exists: bool,
+    /// None when the path is not registered.
+    pub item_id: Option<i64>,
+    /// Template tags the item does not carry yet, in template order.
+    pub missing_tag_ids: Vec<i64>,
+}
+
+/// DTO for previewing a template against many paths.
+///
+/// `token` can be passed back when applying to make the apply fail if the
+/// template or any previewed item's tags changed in the meantime.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateApplyPreviewDto {
+    pub token: String,
+    pub items: Vec<PathApplyPreviewDto>,
+    /// Specs naming tags that applying would create, added to every item.
+    pub to_create: Vec<TagSpecDto>,
+}
+
 /// DTO for creating a new tag template.
 #[derive(Debug, Clone, Deserialize)]
 pub struct CreateTagTemplateDto {
     pub name: String,
     pub tag_ids: Vec<i64>,
+    pub tag_specs: Vec<TagSpecDto>,
 }
 
 /// DTO for updating a tag template.
@@ -105,6 +282,73 @@ pub struct CreateTagTemplateDto {
 pub struct UpdateTagTemplateDto {
     pub name: Option<String>,
     pub tag_ids: Option<Vec<i64>>,
+    pub tag_specs: Option<Vec<TagSpecDto>>,
+}
+
+/// DTO for AutoTagRule data transfer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoTagRuleDto {
+    pub id: i64,
+    pub path_prefix: String,
+    pub template_id: i64,
+    pub enabled: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// DTO for creating an auto tag rule.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateAutoTagRuleDto {
+    pub path_prefix: String,
+    pub template_id: i64,
+    pub enabled: bool,
+}
+
+/// DTO for updating an auto tag rule.
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpdateAutoTagRuleDto {
+    pub path_prefix: Option<String>,
+    pub template_id: Option<i64>,
+    pub enabled: Option<bool>,
+}
+
+/// DTO for Collection data transfer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollectionDto {
+    pub id: i64,
+    pub name: String,
+    pub cql_query: String,
+    pub icon: Option<String>,
+    pub display_order: i32,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// A collection with the number of items its query currently matches.
+///
+/// `count` is None and `error` set when the stored query no longer parses or
+/// fails to run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollectionWithCountDto {
+    pub collection: CollectionDto,
+    pub count: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// DTO for creating a collection.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateCollectionDto {
+    pub name: String,
+    pub cql_query: String,
+    pub icon: Option<String>,
+}
+
+/// DTO for updating a collection. An empty `icon` clears it.
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpdateCollectionDto {
+    pub name: Option<String>,
+    pub cql_query: Option<String>,
+    pub icon: Option<String>,
 }
 
 /// Search mode for tag-based queries.
@@ -115,6 +359,179 @@ pub enum SearchMode {
     Or,
 }
 
+/// Column used to order item search results.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortField {
+    #[default]
+    Path,
+    Size,
+    Modified,
+    Name,
+    /// When the item was registered.
+    Created,
+}
+
+/// Direction for ordering search results.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortDirection {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// How a filename query is matched.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FilenameMatchMode {
+    /// The full path contains the query anywhere.
+    #[default]
+    Contains,
+    /// The file name starts with the query (case-insensitive).
+    Prefix,
+    /// The file name equals the query (case-insensitive).
+    Exact,
+}
+
+/// Timestamp a time window search filters on.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeField {
+    /// File modification time; items without one never match.
+    #[default]
+    Modified,
+    /// When the item was registered.
+    Created,
+}
+
+/// Ordering for item search results. Defaults to path ascending.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ItemSort {
+    #[serde(default)]
+    pub field: SortField,
+    #[serde(default)]
+    pub direction: SortDirection,
+}
+
+/// A search request covering every search mode.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SearchRequest {
+    /// Items carrying all (AND) or any (OR) of the given tags.
+    Tags {
+        tag_ids: Vec<i64>,
+        mode: SearchMode,
+        #[serde(default)]
+        sort: ItemSort,
+        /// Fill in `tag_count` on each result.
+        #[serde(default)]
+        include_tag_count: bool,
+    },
+    /// Items whose path or file name matches the query.
+    Filename {
+        query: String,
+        #[serde(default)]
+        match_mode: FilenameMatchMode,
+        #[serde(default)]
+        sort: ItemSort,
+    },
+    /// Items whose file name fuzzy-matches the query, best match first.
+    FuzzyFilename { query: String },
+    /// Tag search with an optional filename filter.
+    Combined {
+        tag_ids: Vec<i64>,
+        mode: SearchMode,
+        filename_query: Option<String>,
+        #[serde(default)]
+        sort: ItemSort,
+    },
+    /// CQL query string.
+    Cql { query: String },
+}
+
+/// Grouping applied to CQL search results.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", content = "group_id", rename_all = "snake_case")]
+pub enum GroupBy {
+    /// Flat result list.
+    #[default]
+    None,
+    /// Group by the item's parent directory.
+    ParentDir,
+    /// Group by the item's tag(s) within the given tag group.
+    TopTagGroup(i64),
+}
+
+/// DTO for one group of search results.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResultGroupDto {
+    /// Parent directory path, or tag value (empty for items without a tag in the group).
+    pub key: String,
+    pub count: i64,
+    pub sample_items: Vec<ItemDto>,
+}
+
+/// DTO for grouped search results.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupedResultsDto {
+    pub groups: Vec<ResultGroupDto>,
+}
+
+/// DTO for an item together with its tags, for searches and listing views.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemWithTagsDto {
+    pub item: ItemDto,
+    pub tags: Vec<TagDto>,
+}
+
+/// DTO for an item sharing tags with another item, with the number it shares.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimilarItemDto {
+    pub item: ItemDto,
+    pub shared_tags: u32,
+}
+
+/// DTO for a recommended item with its weighted shared-tag score.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecommendationDto {
+    pub item: ItemDto,
+    pub score: f64,
+}
+
+/// DTO for flat search output: bare items, or items with their tags when requested.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SearchResultsDto {
+    Items(Vec<ItemDto>),
+    ItemsWithTags(Vec<ItemWithTagsDto>),
+    /// A CQL query matched more items than `cql_max_rows`.
+    Truncated(TruncatedResultsDto<ItemDto>),
+    TruncatedWithTags(TruncatedResultsDto<ItemWithTagsDto>),
+}
+
+/// DTO for CQL search output: a flat list, or groups when grouping is requested.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum CqlSearchResultDto {
+    Items(Vec<ItemDto>),
+    ItemsWithTags(Vec<ItemWithTagsDto>),
+    Grouped(GroupedResultsDto),
+    /// More items matched than `cql_max_rows`; only the first are included.
+    Truncated(TruncatedResultsDto<ItemDto>),
+    TruncatedWithTags(TruncatedResultsDto<ItemWithTagsDto>),
+}
+
+/// DTO for a flat CQL result cut off at the `cql_max_rows` ceiling: the
+/// first rows in path order and the number of items that matched.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TruncatedResultsDto<T> {
+    pub items: Vec<T>,
+    /// Always `true`; tells this shape apart from grouped results.
+    pub truncated: bool,
+    pub total_count: u64,
+}
+
 /// DTO for search criteria.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchCriteriaDto {
@@ -131,12 +548,141 @@ pub struct SearchHistoryDto {
     pub last_used_at: i64,
 }
 
+/// DTO for a setting's effective value and where it sits in the settings schema.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettingDto {
+    pub value: String,
+    /// `None` for stored keys the schema doesn't know.
+    pub category: Option<String>,
+    pub is_default: bool,
+}
+
+/// DTO for `setting-changed` events.
+#[derive(Debug, Clone, Serialize)]
+pub struct SettingChangedDto {
+    pub key: String,
+    /// The value now in effect; `None` when a key without a default was removed.
+    pub value: Option<String>,
+}
+
+/// DTO for overall library statistics.
+///
+/// Item counts exclude soft-deleted items, which are counted separately in
+/// `deleted_items`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryStatsDto {
+    pub total_items: u64,
+    pub tagged_items: u64,
+    pub untagged_items: u64,
+    pub deleted_items: u64,
+    pub total_tags: u64,
+    pub total_groups: u64,
+    pub db_size_bytes: u64,
+}
+
+/// A CQL query in structured form, as built by the visual filter: clauses
+/// joined with AND.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuerySpecDto {
+    pub clauses: Vec<QueryClauseDto>,
+}
+
+/// One clause of a [`QuerySpecDto`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueryClauseDto {
+    /// CQL field keyword, e.g. `tag` or `size`.
+    pub field: String,
+    /// Comparison operator (`=`, `!=`, `~`, `>`, `<`, `>=`, `<=`) or `in`.
+    pub op: String,
+    /// Unquoted values: one, or several for `in`. Sizes may take a unit
+    /// (`10MB`) and dates are `YYYY-MM-DD`.
+    pub values: Vec<String>,
+    /// Wraps the clause in NOT.
+    #[serde(default)]
+    pub negated: bool,
+}
+
+/// Result of `parse_to_spec`. `spec` is `None` and `not_representable`
+/// set when the query uses OR or nesting the clause list can't express.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParsedQuerySpecDto {
+    pub spec: Option<QuerySpecDto>,
+    pub not_representable: bool,
+}
+
+/// DTO for `benchmark_cql` timings, in milliseconds. Parsing and SQL
+/// generation are not included.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkDto {
+    pub iterations: u32,
+    pub row_count: u64,
+    pub min_ms: f64,
+    pub median_ms: f64,
+    pub max_ms: f64,
+}
+
+/// DTO for `execute_readonly_sql`. Blobs are summarized as
+/// `"<blob: N bytes>"`; `truncated` is set when rows beyond the limit were
+/// dropped.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SqlQueryResultDto {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<serde_json::Value>>,
+    pub truncated: bool,
+}
+
 /// DTO for thumbnail cache statistics.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CacheStatsDto {
-    pub total_size_bytes: u64,
-    pub file_count: u64,
+    pub memory_entries: u64,
+    pub memory_bytes: u64,
+    pub disk_file_count: u64,
+    pub disk_size_bytes: u64,
     pub max_size_bytes: u64,
+    pub cache_dir: String,
+    pub warming: bool,
+    pub warmed_count: u64,
+}
+
+/// DTO for thumbnail cache relocation progress events.
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheMigrationProgressDto {
+    pub processed: u64,
+    pub total: u64,
+    pub done: bool,
+    pub error: Option<String>,
+}
+
+/// DTO returned when an HTML gallery export starts.
+#[derive(Debug, Clone, Serialize)]
+pub struct GalleryExportJobDto {
+    pub job_id: u64,
+    /// Items that will be exported, after the cap.
+    pub item_count: usize,
+    /// Items the query matched before the cap.
+    pub matched_count: usize,
+}
+
+/// DTO for a file whose thumbnail could not be exported.
+#[derive(Debug, Clone, Serialize)]
+pub struct GalleryExportFailureDto {
+    pub path: String,
+    pub error: String,
+}
+
+/// DTO for HTML gallery export progress events.
+#[derive(Debug, Clone, Serialize)]
+pub struct GalleryExportProgressDto {
+    pub job_id: u64,
+    pub processed: usize,
+    pub total: usize,
+    pub failed: usize,
+    pub done: bool,
+    pub cancelled: bool,
+    /// Per-file failures, filled in on the final event only.
+    pub failures: Vec<GalleryExportFailureDto>,
+    /// Set when the export as a whole failed, e.g. `index.html` could not be written.
+    pub error: Option<String>,
 }
 
 /// DTO for a single item's refresh result.
@@ -148,6 +694,20 @@ pub struct RefreshedItemDto {
     pub action: String, // "renamed" | "moved" | "deleted"
 }
 
+/// DTO for the result of backfilling missing File Reference Numbers.
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct FrnBackfillResultDto {
+    /// Items whose FRN was resolved and stored.
+    pub backfilled: usize,
+    /// Items on NTFS drives whose file could not be opened (usually missing).
+    pub unresolved: usize,
+    /// Items not on a local NTFS drive, which have no FRN to record, or
+    /// matching `index_exclude_patterns`.
+    pub skipped: usize,
+    /// The backfill was cancelled; the counts cover the items processed.
+    pub cancelled: bool,
+}
+
 /// DTO for the overall refresh result.
 #[derive(Debug, Clone, Serialize, Default)]
 pub struct RefreshResultDto {
@@ -157,6 +717,12 @@ pub struct RefreshResultDto {
     pub journal_inactive: Vec<String>,
     pub first_time_drives: Vec<String>,
     pub errors: Vec<String>,
+    /// Items created before FRN tracking that had their File Reference
+    /// Number recorded during this refresh.
+    pub frns_backfilled: usize,
+    /// The refresh was cancelled. Changes already applied are kept, but no
+    /// journal position is saved, so the next refresh reads the same records.
+    pub cancelled: bool,
 }
 
 /// DTO for per-drive USN Journal status.
@@ -168,10 +734,146 @@ pub struct DriveUsnStatusDto {
     pub last_synced_at: i64,
 }
 
+/// DTO for a drive's USN Journal sizes, in bytes.
+#[derive(Debug, Clone, Serialize)]
+pub struct JournalConfigDto {
+    pub drive: String,
+    pub maximum_size: u64,
+    pub allocation_delta: u64,
+}
+
+/// DTO for `create_items` results. `item_ids` has one entry per input path,
+/// in input order, whether the item was created or already registered.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CreateItemsResultDto {
+    pub created_count: usize,
+    pub skipped_count: usize,
+    pub item_ids: Vec<i64>,
+}
+
+/// DTO for the result of re-reading item metadata from disk.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct MetadataRefreshResultDto {
+    /// Items whose file was found and compared.
+    pub checked: usize,
+    /// Items whose stored size or modification time changed.
+    pub updated: usize,
+    /// Items whose file no longer exists; left untouched.
+    pub missing: usize,
+    /// The refresh was cancelled; the counts cover the items processed.
+    pub cancelled: bool,
+}
+
+/// Result of tagging the files under a directory.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct DirectoryTagResultDto {
+    /// Files found by the scan.
+    pub scanned_count: usize,
+    /// Files and directories skipped by `index_exclude_patterns`.
+    pub excluded_count: usize,
+    /// Files registered as new items.
+    pub created_count: usize,
+    /// Items the tag was applied to, including ones that already had it.
+    pub tagged_count: usize,
+    /// The operation was cancelled; batches tagged before it stay tagged.
+    pub cancelled: bool,
+}
+
 /// DTO for batch tag operation results.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct BatchTagResult {
     pub success_count: usize,
+    /// Items skipped because they changed since the caller read them.
     pub failed_count: usize,
     pub created_count: usize,
+    /// The skipped items with their current version.
+    #[serde(default)]
+    pub conflicts: Vec<TagConflictDto>,
+    /// Paths that could not be tagged: invalid, or with no item to tag.
+    #[serde(default)]
+    pub skipped_paths: Vec<String>,
+    /// Set when the operation was cancelled before every item was written.
+    #[serde(default)]
+    pub cancelled: bool,
+}
+
+/// DTO for batch tag progress events, sent after each chunk of items.
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchProgressDto {
+    /// Id the command was started with, if any.
+    pub operation_id: Option<String>,
+    /// `"add"` or `"remove"`.
+    pub operation: String,
+    pub processed: u64,
+    pub total: u64,
+}
+
+/// An item a tag write skipped because it changed since the caller read it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagConflictDto {
+    pub item_id: i64,
+    pub path: String,
+    /// The item's current `tags_updated_at`, to retry against after merging.
+    pub tags_updated_at: i64,
+}
+
+/// Outcome of replacing an item's tags.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum TagUpdateResultDto {
+    /// The tags were written; `tags_updated_at` is the item's new version.
+    Updated { tags_updated_at: i64 },
+    /// The item changed since the expected version and nothing was written.
+    /// Carries its current tags and version so the caller can merge and retry.
+    Conflict {
+        tags_updated_at: i64,
+        current_tags: Vec<TagDto>,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_reference_numbers_round_trip_as_strings() {
+        let item = ItemDto {
+            id: 1,
+            path: "C:\\a.txt".to_string(),
+            is_directory: false,
+            size: None,
+            modified_time: None,
+            created_at: 0,
+            updated_at: 0,
+            tags_updated_at: None,
+            rating: None,
+            tag_count: None,
+            file_reference_number: Some(u64::MAX),
+            frn_status: Some(FrnStatus::Tracked),
+        };
+
+        let json = serde_json::to_value(&item).unwrap();
+        assert_eq!(json["file_reference_number"], "18446744073709551615");
+        assert_eq!(json["frn_status"], "tracked");
+
+        let back: ItemDto = serde_json::from_value(json).unwrap();
+        assert_eq!(back.file_reference_number, Some(u64::MAX));
+
+        let mut untracked = serde_json::to_value(ItemDto {
+            file_reference_number: None,
+            frn_status: Some(FrnStatus::NotNtfs),
+            ..item
+        })
+        .unwrap();
+        assert!(untracked["file_reference_number"].is_null());
+        assert_eq!(untracked["frn_status"], "not_ntfs");
+
+        // Older payloads without the fields still deserialize
+        let object = untracked.as_object_mut().unwrap();
+        object.remove("file_reference_number");
+        object.remove("frn_status");
+        let back: ItemDto = serde_json::from_value(untracked).unwrap();
+        assert_eq!(back.file_reference_number, None);
+        assert_eq!(back.frn_status, None);
+    }
 }
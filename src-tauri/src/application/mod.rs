@@ -3,5 +3,8 @@
 //! This module contains application services that orchestrate domain operations.
 //! It acts as a facade for the domain layer and handles use cases.
 
+pub mod cancellation;
 pub mod dto;
+pub mod recent_directories;
 pub mod services;
+pub mod write_queue;
@@ -0,0 +1,57 @@
+//! Recent Directories
+//!
+//! Directories the user browsed most recently, recorded by `read_directory`.
+//! Idle thumbnail warming pre-generates the thumbnails of these first, since
+//! they are the ones likely to be scrolled through again.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// Number of directories remembered.
+const MAX_RECENT_DIRECTORIES: usize = 20;
+
+/// Most recently browsed directories, shared between commands and
+/// background tasks.
+#[derive(Debug, Clone, Default)]
+pub struct RecentDirectories(Arc<Mutex<VecDeque<String>>>);
+
+impl RecentDirectories {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Moves `dir` to the front, dropping the oldest entry past the limit.
+    pub fn record(&self, dir: &str) {
+        let mut dirs = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        dirs.retain(|d| !d.eq_ignore_ascii_case(dir));
+        dirs.push_front(dir.to_string());
+        dirs.truncate(MAX_RECENT_DIRECTORIES);
+    }
+
+    /// The remembered directories, most recent first.
+    pub fn snapshot(&self) -> Vec<String> {
+        let dirs = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        dirs.iter().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn most_recent_directory_comes_first_once() {
+        let recent = RecentDirectories::new();
+        recent.record(r"C:\Photos");
+        recent.record(r"D:\Scans");
+        recent.record(r"c:\photos");
+        assert_eq!(recent.snapshot(), vec![r"c:\photos", r"D:\Scans"]);
+
+        for i in 0..MAX_RECENT_DIRECTORIES {
+            recent.record(&format!(r"E:\{}", i));
+        }
+        let dirs = recent.snapshot();
+        assert_eq!(dirs.len(), MAX_RECENT_DIRECTORIES);
+        assert_eq!(dirs[0], format!(r"E:\{}", MAX_RECENT_DIRECTORIES - 1));
+    }
+}
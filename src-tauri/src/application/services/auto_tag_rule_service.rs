@@ -0,0 +1,275 @@
+//! AutoTagRule Application Service
+//!
+//! Manages the rules that tag items created under a directory with a template.
+
+use crate::application::dto::{AutoTagRuleDto, CreateAutoTagRuleDto, UpdateAutoTagRuleDto};
+use crate::domain::entities::AutoTagRule;
+use crate::domain::errors::DomainError;
+use crate::domain::repositories::{AutoTagRuleRepository, TagTemplateRepository};
+use std::sync::Arc;
+
+/// Service for auto tag rule operations.
+pub struct AutoTagRuleService {
+    rule_repo: Arc<dyn AutoTagRuleRepository>,
+    template_repo: Arc<dyn TagTemplateRepository>,
+}
+
+impl AutoTagRuleService {
+    pub fn new(
+        rule_repo: Arc<dyn AutoTagRuleRepository>,
+        template_repo: Arc<dyn TagTemplateRepository>,
+    ) -> Self {
+        Self {
+            rule_repo,
+            template_repo,
+        }
+    }
+
+    /// Creates a rule. Fails if the template does not exist or another rule
+    /// already covers the same directory.
+    pub async fn create(&self, dto: CreateAutoTagRuleDto) -> Result<i64, DomainError> {
+        self.ensure_template_exists(dto.template_id).await?;
+        let mut rule = AutoTagRule::new(&dto.path_prefix, dto.template_id, dto.enabled)?;
+        self.rule_repo.save(&mut rule).await
+    }
+
+    /// Gets all rules, ordered by prefix.
+    pub async fn get_all(&self) -> Result<Vec<AutoTagRuleDto>, DomainError> {
+        let rules = self.rule_repo.find_all().await?;
+        Ok(rules.into_iter().map(Self::to_dto).collect())
+    }
+
+    /// Updates a rule.
+    pub async fn update(&self, id: i64, dto: UpdateAutoTagRuleDto) -> Result<(), DomainError> {
+        let mut rule = self
+            .rule_repo
+            .find_by_id(id)
+            .await?
+            .ok_or_else(|| DomainError::AutoTagRuleNotFound(id.to_string()))?;
+
+        if let Some(path_prefix) = dto.path_prefix {
+            rule.update_path_prefix(&path_prefix)?;
+        }
+
+        if let Some(template_id) = dto.template_id {
+            self.ensure_template_exists(template_id).await?;
+            rule.update_template(template_id);
+        }
+
+        if let Some(enabled) = dto.enabled {
+            rule.set_enabled(enabled);
+        }
+
+        self.rule_repo.update(&rule).await
+    }
+
+    /// Deletes a rule. Items already tagged by it keep their tags.
+    pub async fn delete(&self, id: i64) -> Result<(), DomainError> {
+        self.rule_repo.delete(id).await
+    }
+
+    async fn ensure_template_exists(&self, template_id: i64) -> Result<(), DomainError> {
+        self.template_repo
+            .find_by_id(template_id)
+            .await?
+            .map(|_| ())
+            .ok_or_else(|| DomainError::TagTemplateNotFound(template_id.to_string()))
+    }
+
+    fn to_dto(rule: AutoTagRule) -> AutoTagRuleDto {
+        AutoTagRuleDto {
+            id: rule.id().unwrap_or(0),
+            path_prefix: rule.path_prefix().to_string(),
+            template_id: rule.template_id(),
+            enabled: rule.enabled(),
+            created_at: rule.created_at().unwrap_or(0),
+            updated_at: rule.updated_at().unwrap_or(0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::dto::CreateItemDto;
+    use crate::application::services::{ItemService, SettingsService};
+    use crate::domain::repositories::{ItemRepository, TagRepository};
+    use crate::infrastructure::persistence::{
+        SqliteAutoTagRuleRepository, SqliteItemRepository, SqliteSettingsRepository,
+        SqliteTagRepository, SqliteTagTemplateRepository, open_database,
+    };
+    use rusqlite::Connection;
+
+    struct Fixture {
+        _dir: tempfile::TempDir,
+        rules: AutoTagRuleService,
+        items: ItemService,
+        item_repo: Arc<dyn ItemRepository>,
+    }
+
+    /// Template 1 tags `inbox`, template 2 tags `screenshot`.
+    async fn setup() -> Fixture {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = Arc::new(open_database(&dir.path().join("test.db")).await.unwrap());
+        let conn = pool.get().await.unwrap();
+        conn.interact(|conn: &mut Connection| {
+            conn.execute_batch(
+                "INSERT INTO tag_groups (id, name) VALUES (1, 'Source');
+                 INSERT INTO tags (id, group_id, value) VALUES (1, 1, 'inbox'), (2, 1, 'screenshot');
+                 INSERT INTO tag_templates (id, name) VALUES (1, 'Inbox'), (2, 'Screenshots');
+                 INSERT INTO template_tags (template_id, tag_id) VALUES (1, 1), (2, 2);",
+            )
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+        let item_repo: Arc<dyn ItemRepository> = Arc::new(SqliteItemRepository::new(pool.clone()));
+        let tag_repo: Arc<dyn TagRepository> = Arc::new(SqliteTagRepository::new(pool.clone()));
+        let settings_service = Arc::new(SettingsService::new(Arc::new(
+            SqliteSettingsRepository::new(pool.clone()),
+        )));
+        let rules = AutoTagRuleService::new(
+            Arc::new(SqliteAutoTagRuleRepository::new(pool.clone())),
+            Arc::new(SqliteTagTemplateRepository::new(pool)),
+        );
+        Fixture {
+            _dir: dir,
+            rules,
+            items: ItemService::new(item_repo.clone(), tag_repo, settings_service),
+            item_repo,
+        }
+    }
+
+    async fn add_rule(fixture: &Fixture, prefix: &str, template_id: i64, enabled: bool) -> i64 {
+        fixture
+            .rules
+            .create(CreateAutoTagRuleDto {
+                path_prefix: prefix.to_string(),
+                template_id,
+                enabled,
+            })
+            .await
+            .unwrap()
+    }
+
+    async fn create_item(fixture: &Fixture, path: &str) -> Vec<i64> {
+        let id = fixture
+            .items
+            .create(CreateItemDto {
+                path: path.to_string(),
+                is_directory: false,
+                size: None,
+                modified_time: None,
+            })
+            .await
+            .unwrap();
+        fixture.item_repo.get_tag_ids(id).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn overlapping_prefixes_apply_the_longest_on_create() {
+        let fixture = setup().await;
+        add_rule(&fixture, "D:\\Inbox", 1, true).await;
+        add_rule(&fixture, "D:\\Inbox\\Screenshots\\", 2, true).await;
+        add_rule(&fixture, "D:\\Inbox\\Screenshots\\Old", 1, false).await;
+
+        assert_eq!(create_item(&fixture, "D:\\Inbox\\a.zip").await, vec![1]);
+        assert_eq!(
+            create_item(&fixture, "d:\\inbox\\screenshots\\b.png").await,
+            vec![2]
+        );
+        // Disabled rules are skipped in favour of the next longest prefix
+        assert_eq!(
+            create_item(&fixture, "D:\\Inbox\\Screenshots\\Old\\c.png").await,
+            vec![2]
+        );
+        assert!(create_item(&fixture, "D:\\Inbox2\\d.png").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn create_many_tags_only_newly_created_items() {
+        let fixture = setup().await;
+        let existing = fixture
+            .items
+            .create(CreateItemDto {
+                path: "D:\\Inbox\\old.txt".to_string(),
+                is_directory: false,
+                size: None,
+                modified_time: None,
+            })
+            .await
+            .unwrap();
+        add_rule(&fixture, "D:\\Inbox", 1, true).await;
+        add_rule(&fixture, "D:\\Inbox\\Screenshots", 2, true).await;
+
+        let result = fixture
+            .items
+            .create_many(vec![
+                "D:\\Inbox\\old.txt".to_string(),
+                "D:\\Inbox\\new.txt".to_string(),
+                "D:\\Inbox\\Screenshots\\shot.png".to_string(),
+            ])
+            .await
+            .unwrap();
+
+        assert_eq!(result.created_count, 2);
+        let tags_of = |id| fixture.item_repo.get_tag_ids(id);
+        assert!(tags_of(existing).await.unwrap().is_empty());
+        assert_eq!(tags_of(result.item_ids[1]).await.unwrap(), vec![1]);
+        assert_eq!(tags_of(result.item_ids[2]).await.unwrap(), vec![2]);
+    }
+
+    #[tokio::test]
+    async fn rules_validate_template_and_reject_duplicate_prefixes() {
+        let fixture = setup().await;
+        let id = add_rule(&fixture, "D:\\Inbox", 1, true).await;
+
+        let missing_template = fixture
+            .rules
+            .create(CreateAutoTagRuleDto {
+                path_prefix: "D:\\Other".to_string(),
+                template_id: 99,
+                enabled: true,
+            })
+            .await;
+        assert!(matches!(
+            missing_template,
+            Err(DomainError::TagTemplateNotFound(_))
+        ));
+
+        let duplicate = fixture
+            .rules
+            .create(CreateAutoTagRuleDto {
+                path_prefix: "d:\\inbox\\".to_string(),
+                template_id: 2,
+                enabled: true,
+            })
+            .await;
+        assert!(matches!(duplicate, Err(DomainError::DuplicateEntry(_))));
+
+        fixture
+            .rules
+            .update(
+                id,
+                UpdateAutoTagRuleDto {
+                    path_prefix: None,
+                    template_id: Some(2),
+                    enabled: Some(false),
+                },
+            )
+            .await
+            .unwrap();
+        let rules = fixture.rules.get_all().await.unwrap();
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].template_id, 2);
+        assert!(!rules[0].enabled);
+        assert!(create_item(&fixture, "D:\\Inbox\\a.png").await.is_empty());
+
+        fixture.rules.delete(id).await.unwrap();
+        assert!(matches!(
+            fixture.rules.delete(id).await,
+            Err(DomainError::AutoTagRuleNotFound(_))
+        ));
+    }
+}
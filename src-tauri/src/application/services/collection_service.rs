@@ -0,0 +1,343 @@
+//! Collection Application Service
+//!
+//! Manages collections and counts the items each one matches.
+
+use crate::application::dto::{
+    CollectionDto, CollectionWithCountDto, CreateCollectionDto, UpdateCollectionDto,
+};
+use crate::domain::entities::Collection;
+use crate::domain::errors::DomainError;
+use crate::domain::repositories::CollectionRepository;
+use crate::domain::search::parse_cql;
+use crate::infrastructure::persistence::SqliteSearchRepository;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// How long a collection's count is reused before its query runs again.
+const COUNT_CACHE_TTL: Duration = Duration::from_secs(10);
+
+/// A count computed for a collection's query at a point in time.
+struct CachedCount {
+    cql_query: String,
+    computed_at: Instant,
+    result: Result<u64, String>,
+}
+
+/// Service for collection operations.
+pub struct CollectionService {
+    repo: Arc<dyn CollectionRepository>,
+    search_repo: Arc<SqliteSearchRepository>,
+    /// Counts by collection ID, so sidebar renders don't rerun every query.
+    count_cache: Mutex<HashMap<i64, CachedCount>>,
+}
+
+impl CollectionService {
+    pub fn new(
+        repo: Arc<dyn CollectionRepository>,
+        search_repo: Arc<SqliteSearchRepository>,
+    ) -> Self {
+        Self {
+            repo,
+            search_repo,
+            count_cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Creates a collection at the end of the list. Fails if the query does
+    /// not parse.
+    pub async fn create(&self, dto: CreateCollectionDto) -> Result<i64, DomainError> {
+        Self::ensure_query_parses(&dto.cql_query)?;
+
+        let collections = self.repo.find_all().await?;
+        let max_order = collections
+            .iter()
+            .map(|c| c.display_order())
+            .max()
+            .unwrap_or(0);
+
+        let mut collection = Collection::new(&dto.name, &dto.cql_query, dto.icon, max_order + 1)?;
+        self.repo.save(&mut collection).await
+    }
+
+    /// Gets all collections in display order.
+    pub async fn get_all(&self) -> Result<Vec<CollectionDto>, DomainError> {
+        let collections = self.repo.find_all().await?;
+        Ok(collections.into_iter().map(Self::to_dto).collect())
+    }
+
+    /// Gets all collections with the number of items each matches.
+    ///
+    /// Counts younger than [`COUNT_CACHE_TTL`] are reused; the rest are
+    /// recomputed together on one connection. A collection whose query fails
+    /// gets no count and an error message instead of failing the listing.
+    pub async fn get_all_with_counts(&self) -> Result<Vec<CollectionWithCountDto>, DomainError> {
+        let collections = self.repo.find_all().await?;
+        let now = Instant::now();
+
+        let stale: Vec<(i64, String)> = {
+            let cache = self.lock_count_cache();
+            collections
+                .iter()
+                .filter_map(|c| {
+                    let id = c.id()?;
+                    let fresh = cache.get(&id).is_some_and(|cached| {
+                        cached.cql_query == c.cql_query()
+                            && now.duration_since(cached.computed_at) < COUNT_CACHE_TTL
+                    });
+                    (!fresh).then(|| (id, c.cql_query().to_string()))
+                })
+                .collect()
+        };
+
+        let counts = if stale.is_empty() {
+            Vec::new()
+        } else {
+            let queries = stale.iter().map(|(_, query)| query.clone()).collect();
+            self.search_repo.count_cql_batch(queries).await?
+        };
+
+        let mut cache = self.lock_count_cache();
+        for ((id, cql_query), result) in stale.into_iter().zip(counts) {
+            cache.insert(
+                id,
+                CachedCount {
+                    cql_query,
+                    computed_at: now,
+                    result,
+                },
+            );
+        }
+        // Drop entries of collections deleted since they were cached
+        cache.retain(|id, _| collections.iter().any(|c| c.id() == Some(*id)));
+
+        Ok(collections
+            .into_iter()
+            .map(|c| {
+                let cached = c.id().and_then(|id| cache.get(&id));
+                let (count, error) = match cached.map(|cached| &cached.result) {
+                    Some(Ok(count)) => (Some(*count), None),
+                    Some(Err(e)) => (None, Some(e.clone())),
+                    None => (None, None),
+                };
+                CollectionWithCountDto {
+                    collection: Self::to_dto(c),
+                    count,
+                    error,
+                }
+            })
+            .collect())
+    }
+
+    /// Updates a collection. Fails if the new query does not parse.
+    pub async fn update(&self, id: i64, dto: UpdateCollectionDto) -> Result<(), DomainError> {
+        let mut collection = self
+            .repo
+            .find_by_id(id)
+            .await?
+            .ok_or_else(|| DomainError::CollectionNotFound(id.to_string()))?;
+
+        if let Some(name) = dto.name {
+            collection.update_name(&name)?;
+        }
+
+        if let Some(cql_query) = dto.cql_query {
+            Self::ensure_query_parses(&cql_query)?;
+            collection.update_query(&cql_query)?;
+        }
+
+        if dto.icon.is_some() {
+            collection.update_icon(dto.icon);
+        }
+
+        self.repo.update(&collection).await
+    }
+
+    /// Deletes a collection.
+    pub async fn delete(&self, id: i64) -> Result<(), DomainError> {
+        self.repo.delete(id).await?;
+        self.lock_count_cache().remove(&id);
+        Ok(())
+    }
+
+    /// Reorders collections.
+    pub async fn reorder(&self, orders: Vec<(i64, i32)>) -> Result<(), DomainError> {
+        self.repo.reorder(orders).await
+    }
+
+    fn ensure_query_parses(cql_query: &str) -> Result<(), DomainError> {
+        parse_cql(cql_query)
+            .map(|_| ())
+            .map_err(|e| DomainError::ValidationError(e.to_string()))
+    }
+
+    /// Locks the count cache, recovering it if a panicking holder poisoned it;
+    /// the worst a stale entry can do is show an old count until it expires.
+    fn lock_count_cache(&self) -> MutexGuard<'_, HashMap<i64, CachedCount>> {
+        match self.count_cache.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    fn to_dto(collection: Collection) -> CollectionDto {
+        CollectionDto {
+            id: collection.id().unwrap_or(0),
+            name: collection.name().to_string(),
+            cql_query: collection.cql_query().to_string(),
+            icon: collection.icon().map(str::to_string),
+            display_order: collection.display_order(),
+            created_at: collection.created_at().unwrap_or(0),
+            updated_at: collection.updated_at().unwrap_or(0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::persistence::{SqliteCollectionRepository, open_database};
+    use rusqlite::Connection;
+
+    async fn setup() -> (
+        tempfile::TempDir,
+        CollectionService,
+        Arc<deadpool_sqlite::Pool>,
+    ) {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = Arc::new(open_database(&dir.path().join("test.db")).await.unwrap());
+        let service = CollectionService::new(
+            Arc::new(SqliteCollectionRepository::new(pool.clone())),
+            Arc::new(SqliteSearchRepository::new(pool.clone())),
+        );
+        (dir, service, pool)
+    }
+
+    async fn exec(pool: &deadpool_sqlite::Pool, sql: &'static str) {
+        let conn = pool.get().await.unwrap();
+        conn.interact(move |conn: &mut Connection| conn.execute_batch(sql))
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    fn create_dto(name: &str, cql_query: &str) -> CreateCollectionDto {
+        CreateCollectionDto {
+            name: name.to_string(),
+            cql_query: cql_query.to_string(),
+            icon: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn counts_are_cached_and_refreshed_when_the_query_changes() {
+        let (_dir, service, pool) = setup().await;
+        exec(
+            &pool,
+            "INSERT INTO items (id, path, is_directory) VALUES
+                (1, 'C:\\a.txt', 0), (2, 'C:\\b.png', 0);",
+        )
+        .await;
+        let id = service
+            .create(create_dto("Images", r#"type = "image""#))
+            .await
+            .unwrap();
+
+        let listed = service.get_all_with_counts().await.unwrap();
+        assert_eq!(listed[0].count, Some(1));
+
+        // Within the TTL a new matching item is not counted yet
+        exec(
+            &pool,
+            "INSERT INTO items (id, path, is_directory) VALUES (3, 'C:\\c.png', 0);",
+        )
+        .await;
+        assert_eq!(
+            service.get_all_with_counts().await.unwrap()[0].count,
+            Some(1)
+        );
+
+        // Changing the query invalidates the cached count
+        service
+            .update(
+                id,
+                UpdateCollectionDto {
+                    name: None,
+                    cql_query: Some(r#"NOT type = "directory""#.to_string()),
+                    icon: None,
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            service.get_all_with_counts().await.unwrap()[0].count,
+            Some(3)
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_stored_query_reports_an_error_without_failing_the_listing() {
+        let (_dir, service, pool) = setup().await;
+        service
+            .create(create_dto("Images", r#"type = "image""#))
+            .await
+            .unwrap();
+        assert!(matches!(
+            service.create(create_dto("Broken", "tag =")).await,
+            Err(DomainError::ValidationError(_))
+        ));
+        assert!(matches!(
+            service.create(create_dto("Mistyped", "group = 5")).await,
+            Err(DomainError::ValidationError(_))
+        ));
+        // Queries stored before the grammar stopped accepting them
+        exec(
+            &pool,
+            "INSERT INTO collections (name, cql_query, display_order) VALUES
+                ('Old', 'tag =', 5), ('Typed', 'group = 5', 6);",
+        )
+        .await;
+
+        let listed = service.get_all_with_counts().await.unwrap();
+        assert_eq!(listed.len(), 3);
+        assert_eq!(listed[0].count, Some(0));
+        assert!(listed[0].error.is_none());
+        for (collection, name) in listed[1..].iter().zip(["Old", "Typed"]) {
+            assert_eq!(collection.collection.name, name);
+            assert_eq!(collection.count, None);
+            assert!(collection.error.is_some());
+        }
+    }
+
+    #[tokio::test]
+    async fn reorder_changes_listing_order() {
+        let (_dir, service, _pool) = setup().await;
+        let first = service
+            .create(create_dto("First", "rating > 3"))
+            .await
+            .unwrap();
+        let second = service
+            .create(create_dto("Second", "rating > 4"))
+            .await
+            .unwrap();
+
+        service
+            .reorder(vec![(first, 2), (second, 1)])
+            .await
+            .unwrap();
+        let names: Vec<String> = service
+            .get_all()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|c| c.name)
+            .collect();
+        assert_eq!(names, vec!["Second", "First"]);
+
+        service.delete(first).await.unwrap();
+        assert!(matches!(
+            service.delete(first).await,
+            Err(DomainError::CollectionNotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,372 @@
+//! Gallery Export Application Service
+//!
+//! Exports CQL search results as a static HTML page of thumbnails that link
+//! back to the original files.
+
+use crate::application::cancellation::{CancellationToken, OperationGuard};
+use crate::application::dto::{
+    GalleryExportFailureDto, GalleryExportJobDto, GalleryExportProgressDto, ItemWithTagsDto,
+};
+use crate::application::services::{SearchService, ThumbnailService};
+use crate::domain::errors::DomainError;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Subdirectory of the output directory that receives the thumbnails.
+pub const GALLERY_THUMBS_DIR: &str = "thumbs";
+
+/// Accepted thumbnail edge lengths, in pixels.
+const THUMB_SIZE_RANGE: std::ops::RangeInclusive<u32> = 32..=1024;
+
+/// Service for exporting search results as an HTML gallery.
+pub struct GalleryExportService {
+    search_service: Arc<SearchService>,
+    next_job_id: AtomicU64,
+}
+
+impl GalleryExportService {
+    pub fn new(search_service: Arc<SearchService>) -> Self {
+        Self {
+            search_service,
+            next_job_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Starts exporting the results of `query` into `output_dir`, which must
+    /// already contain a `thumbs` subdirectory.
+    ///
+    /// The search runs before this returns, so query errors surface directly,
+    /// and at most `max_items` results are exported. Thumbnails are then
+    /// generated (or read from the cache) on a background task that writes
+    /// `thumbs/{item_id}.webp` and finally `index.html`, reporting through
+    /// `on_progress`. A file whose thumbnail fails is listed in the final
+    /// report and shown without an image. The task holds `operation` until it
+    /// finishes; cancelling its token stops the task before the next file,
+    /// and no `index.html` is written.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn start<F>(
+        &self,
+        query: &str,
+        output_dir: PathBuf,
+        thumb_size: u32,
+        max_items: usize,
+        thumbnails: Arc<ThumbnailService>,
+        operation: OperationGuard,
+        on_progress: F,
+    ) -> Result<GalleryExportJobDto, DomainError>
+    where
+        F: Fn(GalleryExportProgressDto) + Send + Sync + 'static,
+    {
+        if query.trim().is_empty() {
+            return Err(DomainError::ValidationError(
+                "Gallery query must not be empty".to_string(),
+            ));
+        }
+        if !THUMB_SIZE_RANGE.contains(&thumb_size) {
+            return Err(DomainError::ValidationError(format!(
+                "Thumbnail size must be between {} and {}",
+                THUMB_SIZE_RANGE.start(),
+                THUMB_SIZE_RANGE.end()
+            )));
+        }
+        if max_items == 0 {
+            return Err(DomainError::ValidationError(
+                "Gallery item limit must be at least 1".to_string(),
+            ));
+        }
+
+        let (items, matched_count) = self
+            .search_service
+            .search_cql_with_tags(query, max_items)
+            .await?;
+
+        let job_id = self.next_job_id.fetch_add(1, Ordering::Relaxed);
+
+        let job = GalleryJob {
+            job_id,
+            query: query.trim().to_string(),
+            output_dir,
+            thumb_size,
+            items,
+        };
+        let item_count = job.items.len();
+        tokio::spawn(async move {
+            job.run(&thumbnails, operation.token(), &on_progress).await;
+        });
+
+        Ok(GalleryExportJobDto {
+            job_id,
+            item_count,
+            matched_count,
+        })
+    }
+}
+
+/// State of one export, moved onto its background task.
+struct GalleryJob {
+    job_id: u64,
+    query: String,
+    output_dir: PathBuf,
+    thumb_size: u32,
+    items: Vec<ItemWithTagsDto>,
+}
+
+impl GalleryJob {
+    async fn run<F>(
+        self,
+        thumbnails: &ThumbnailService,
+        cancel: &CancellationToken,
+        on_progress: &F,
+    ) where
+        F: Fn(GalleryExportProgressDto),
+    {
+        let total = self.items.len();
+        let mut entries = Vec::with_capacity(total);
+        let mut failures = Vec::new();
+        let report = |processed, failed, done, cancelled, failures, error| {
+            on_progress(GalleryExportProgressDto {
+                job_id: self.job_id,
+                processed,
+                total,
+                failed,
+                done,
+                cancelled,
+                failures,
+                error,
+            })
+        };
+
+        for (index, item) in self.items.iter().enumerate() {
+            if cancel.is_cancelled() {
+                report(index, failures.len(), true, true, failures, None);
+                return;
+            }
+
+            let thumb = match self.export_thumbnail(thumbnails, item).await {
+                Ok(relative) => Some(relative),
+                Err(error) => {
+                    failures.push(GalleryExportFailureDto {
+                        path: item.item.path.clone(),
+                        error,
+                    });
+                    None
+                }
+            };
+            entries.push(GalleryEntry { item, thumb });
+            report(index + 1, failures.len(), false, false, Vec::new(), None);
+        }
+
+        let html = render_gallery(&self.query, &entries);
+        let error = std::fs::write(self.output_dir.join("index.html"), html)
+            .err()
+            .map(|e| format!("Failed to write index.html: {}", e));
+        report(total, failures.len(), true, false, failures, error);
+    }
+
+    /// Writes the item's thumbnail and returns its path relative to the output directory.
+    async fn export_thumbnail(
+        &self,
+        thumbnails: &ThumbnailService,
+        item: &ItemWithTagsDto,
+    ) -> Result<String, String> {
+        let path = &item.item.path;
+        let metadata = std::fs::metadata(path).map_err(|e| e.to_string())?;
+        let mtime = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+
+        let webp = thumbnails
+            .get_thumbnail(path, mtime, metadata.len(), self.thumb_size)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let relative = format!("{}/{}.webp", GALLERY_THUMBS_DIR, item.item.id);
+        tokio::fs::write(self.output_dir.join(&relative), webp)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(relative)
+    }
+}
+
+/// One gallery tile: the item and its thumbnail path, if one was exported.
+struct GalleryEntry<'a> {
+    item: &'a ItemWithTagsDto,
+    thumb: Option<String>,
+}
+
+/// Renders the gallery page. Every value taken from the library is escaped.
+fn render_gallery(query: &str, entries: &[GalleryEntry]) -> String {
+    let mut html = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str(&format!("<title>{}</title>\n", escape_html(query)));
+    html.push_str(
+        "<style>\n\
+         body { font-family: sans-serif; margin: 16px; }\n\
+         .grid { display: grid; grid-template-columns: repeat(auto-fill, minmax(200px, 1fr)); gap: 12px; }\n\
+         .tile { border: 1px solid #ddd; border-radius: 4px; padding: 8px; overflow: hidden; }\n\
+         .tile img { width: 100%; height: 180px; object-fit: contain; background: #f4f4f4; }\n\
+         .missing { height: 180px; background: #f4f4f4; }\n\
+         .name { display: block; margin-top: 6px; word-break: break-all; }\n\
+         .tags { margin-top: 4px; font-size: 12px; color: #555; }\n\
+         </style>\n</head>\n<body>\n",
+    );
+    html.push_str(&format!(
+        "<h1>{}</h1>\n<p>{} items</p>\n<div class=\"grid\">\n",
+        escape_html(query),
+        entries.len()
+    ));
+
+    for entry in entries {
+        let path = &entry.item.item.path;
+        let name = Path::new(path)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.clone());
+        let href = escape_html(&file_url(path));
+
+        html.push_str("<div class=\"tile\">\n");
+        match &entry.thumb {
+            Some(thumb) => html.push_str(&format!(
+                "<a href=\"{}\"><img src=\"{}\" alt=\"{}\" loading=\"lazy\"></a>\n",
+                href,
+                escape_html(thumb),
+                escape_html(&name)
+            )),
+            None => html.push_str("<div class=\"missing\"></div>\n"),
+        }
+        html.push_str(&format!(
+            "<a class=\"name\" href=\"{}\" title=\"{}\">{}</a>\n",
+            href,
+            escape_html(path),
+            escape_html(&name)
+        ));
+        if !entry.item.tags.is_empty() {
+            let tags: Vec<String> = entry
+                .item
+                .tags
+                .iter()
+                .map(|t| escape_html(&t.value))
+                .collect();
+            html.push_str(&format!("<div class=\"tags\">{}</div>\n", tags.join(", ")));
+        }
+        html.push_str("</div>\n");
+    }
+
+    html.push_str("</div>\n</body>\n</html>\n");
+    html
+}
+
+/// Escapes text for use in HTML content and quoted attribute values.
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Builds a `file://` URL for a Windows path, percent-encoding everything
+/// outside the unreserved set so `#`, `?`, `%` and spaces survive.
+fn file_url(path: &str) -> String {
+    let normalized = path.replace('\\', "/");
+    let mut url = if normalized.starts_with("//") {
+        // UNC path: the server becomes the URL host
+        String::from("file:")
+    } else {
+        String::from("file:///")
+    };
+    for byte in normalized.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                url.push(byte as char)
+            }
+            _ => url.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    url
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::dto::{ItemDto, TagDto};
+
+    fn item(id: i64, path: &str, tags: &[&str]) -> ItemWithTagsDto {
+        ItemWithTagsDto {
+            item: ItemDto {
+                id,
+                path: path.to_string(),
+                is_directory: false,
+                size: None,
+                modified_time: None,
+                created_at: 0,
+                updated_at: 0,
+                tags_updated_at: None,
+                rating: None,
+                tag_count: None,
+                file_reference_number: None,
+                frn_status: None,
+            },
+            tags: tags
+                .iter()
+                .map(|value| TagDto {
+                    id: 0,
+                    group_id: 0,
+                    value: value.to_string(),
+                    created_at: 0,
+                    updated_at: 0,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn file_urls_are_percent_encoded() {
+        assert_eq!(
+            file_url(r"C:\Photos\a b#1%.jpg"),
+            "file:///C:/Photos/a%20b%231%25.jpg"
+        );
+        assert_eq!(file_url(r"\\nas\share\x.png"), "file://nas/share/x.png");
+        assert_eq!(
+            file_url(r"C:\写真\猫.png"),
+            "file:///C:/%E5%86%99%E7%9C%9F/%E7%8C%AB.png"
+        );
+    }
+
+    #[test]
+    fn gallery_escapes_paths_tags_and_query() {
+        let tagged = item(1, r#"C:\x\<b>"&'.png"#, &["<script>"]);
+        let plain = item(2, r"C:\x\plain.png", &[]);
+        let entries = vec![
+            GalleryEntry {
+                item: &tagged,
+                thumb: Some("thumbs/1.webp".to_string()),
+            },
+            GalleryEntry {
+                item: &plain,
+                thumb: None,
+            },
+        ];
+
+        let html = render_gallery(r#"tag = "<a>""#, &entries);
+        assert!(!html.contains("<b>"));
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("&lt;b&gt;&quot;&amp;&#39;.png"));
+        assert!(html.contains("&lt;script&gt;"));
+        assert!(html.contains("<title>tag = &quot;&lt;a&gt;&quot;</title>"));
+        assert!(html.contains("src=\"thumbs/1.webp\""));
+        assert!(html.contains("href=\"file:///C:/x/%3Cb%3E%22%26%27.png\""));
+        assert_eq!(html.matches("class=\"missing\"").count(), 1);
+        assert!(html.contains("<p>2 items</p>"));
+    }
+}
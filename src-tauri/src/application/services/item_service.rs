@@ -2,34 +2,103 @@
 //!
 //! Orchestrates item-related operations.
 
-use crate::application::dto::{BatchTagResult, CreateItemDto, ItemDto, TagDto, UpdateItemDto};
-use crate::domain::entities::Item;
+use crate::application::cancellation::CancellationToken;
+use crate::application::dto::{
+    BatchTagResult, BulkUpdateResultDto, CreateItemDto, CreateItemsResultDto,
+    DirectoryTagResultDto, FrnStatus, ItemDto, ItemWithTagsDto, MetadataRefreshResultDto,
+    PathCheckDto, PathNormalizationDto, TagConflictDto, TagDto, TagUpdateResultDto, UpdateItemDto,
+};
+use crate::application::services::SettingsService;
+use crate::application::services::usn_refresh_service::drive_letter;
+use crate::application::write_queue::WriteQueue;
+use crate::domain::entities::{Item, Tag};
 use crate::domain::errors::DomainError;
 use crate::domain::repositories::{ItemRepository, TagRepository};
 use crate::domain::value_objects::FilePath;
+use crate::infrastructure::directory_scan::{ExcludePatterns, scan_directory};
+use crate::infrastructure::exif::read_taken_at;
+use crate::infrastructure::usn_journal::{get_file_reference_number, is_ntfs};
 use std::collections::HashMap;
+use std::path::Path;
 use std::sync::Arc;
 
+/// Number of items `get_recently_tagged` returns when no limit is given.
+const RECENTLY_TAGGED_DEFAULT_LIMIT: usize = 50;
+
+/// Number of changed items `refresh_metadata` writes per transaction.
+const METADATA_REFRESH_BATCH_SIZE: usize = 500;
+
+/// Number of files `tag_directory` registers and tags per batch.
+const TAG_DIRECTORY_BATCH_SIZE: usize = 500;
+
+/// Number of items a batch tag write covers per chunk; progress is
+/// reported and cancellation checked between chunks.
+const BATCH_TAG_CHUNK_SIZE: usize = 100;
+
+/// Setting with the globs `tag_directory` skips while scanning and USN
+/// refresh does not start tracking.
+pub(crate) const INDEX_EXCLUDE_PATTERNS_KEY: &str = "index_exclude_patterns";
+
+/// Number of items under a directory `get_inherited_tags` looks at.
+const INHERITED_TAGS_SCAN_LIMIT: usize = 10_000;
+
+/// How long after its soft delete an item's tags can pass to a new item at
+/// the same path, when `rehydrate_tags_on_path_reuse` is on.
+const PATH_REUSE_WINDOW_SECS: i64 = 7 * 24 * 60 * 60;
+
+/// Direction of a chunked batch tag write.
+#[derive(Clone, Copy)]
+enum TagWrite {
+    Add,
+    Remove,
+}
+
 /// Service for item operations.
 pub struct ItemService {
     item_repo: Arc<dyn ItemRepository>,
     tag_repo: Arc<dyn TagRepository>,
+    settings_service: Arc<SettingsService>,
+    write_queue: WriteQueue,
 }
 
 impl ItemService {
-    pub fn new(item_repo: Arc<dyn ItemRepository>, tag_repo: Arc<dyn TagRepository>) -> Self {
+    pub fn new(
+        item_repo: Arc<dyn ItemRepository>,
+        tag_repo: Arc<dyn TagRepository>,
+        settings_service: Arc<SettingsService>,
+    ) -> Self {
         Self {
+            write_queue: WriteQueue::new(item_repo.clone()),
             item_repo,
             tag_repo,
+            settings_service,
         }
     }
 
     /// Creates a new item.
+    ///
+    /// With `rehydrate_tags_on_path_reuse` on, a soft-deleted item at the same
+    /// path is permanently deleted to make room. If it was deleted within
+    /// `PATH_REUSE_WINDOW_SECS`, the new item inherits its tags; in exclusive
+    /// groups they replace any tag the folder's auto tag rule added. An active
+    /// item at the path still fails with `DuplicateEntry`.
     pub async fn create(&self, dto: CreateItemDto) -> Result<i64, DomainError> {
         let path = FilePath::new(&dto.path)?;
         let frn = Self::get_frn(path.as_str());
+        let taken_at = read_taken_at(path.as_str());
         let mut item = Item::new(path, dto.is_directory, dto.size, dto.modified_time, frn);
-        self.item_repo.save(&mut item).await
+        item.update_taken_at(taken_at);
+        if self
+            .settings_service
+            .get_bool("rehydrate_tags_on_path_reuse", false)
+            .await
+        {
+            self.item_repo
+                .save_reusing_path(&mut item, PATH_REUSE_WINDOW_SECS)
+                .await
+        } else {
+            self.item_repo.save(&mut item).await
+        }
     }
 
     /// Gets the NTFS File Reference Number for a path. Returns 0 on error.
@@ -40,17 +109,46 @@ impl ItemService {
     }
 
     /// Gets an item by ID.
-    pub async fn get_by_id(&self, id: i64) -> Result<Option<ItemDto>, DomainError> {
-        let item = self.item_repo.find_by_id(id).await?;
-        Ok(item.map(Self::to_dto))
+    ///
+    /// With `include_tag_count`, also fills in `tag_count`.
+    pub async fn get_by_id(
+        &self,
+        id: i64,
+        include_tag_count: bool,
+    ) -> Result<Option<ItemDto>, DomainError> {
+        let item = match self.item_repo.find_by_id(id).await? {
+            Some(item) => Some(with_frn_status(Self::to_dto(item)).await),
+            None => None,
+        };
+        self.with_tag_count(item, include_tag_count).await
+    }
+
+    /// Gets the items whose tags changed most recently, newest first.
+    /// Defaults to `RECENTLY_TAGGED_DEFAULT_LIMIT` items.
+    pub async fn get_recently_tagged(
+        &self,
+        limit: Option<usize>,
+    ) -> Result<Vec<ItemDto>, DomainError> {
+        let items = self
+            .item_repo
+            .find_recently_tagged(limit.unwrap_or(RECENTLY_TAGGED_DEFAULT_LIMIT))
+            .await?;
+        Ok(items.into_iter().map(Self::to_dto).collect())
     }
 
     /// Gets an item by path.
-    pub async fn get_by_path(&self, path: &str) -> Result<Option<ItemDto>, DomainError> {
+    ///
+    /// With `include_tag_count`, also fills in `tag_count`.
+    pub async fn get_by_path(
+        &self,
+        path: &str,
+        include_tag_count: bool,
+    ) -> Result<Option<ItemDto>, DomainError> {
         // Validate path first
         let validated_path = FilePath::new(path)?;
         let item = self.item_repo.find_by_path(validated_path.as_str()).await?;
-        Ok(item.map(Self::to_dto))
+        self.with_tag_count(item.map(Self::to_dto), include_tag_count)
+            .await
     }
 
     /// Gets multiple items by paths (batch query to avoid N+1).
@@ -70,6 +168,67 @@ impl ItemService {
         Ok(items.into_iter().map(Self::to_dto).collect())
     }
 
+    /// Checks which paths have an item record.
+    ///
+    /// Returns one entry per input path, in input order, with `item` set to
+    /// `None` for paths that are not registered.
+    pub async fn check_by_paths(
+        &self,
+        paths: Vec<String>,
+    ) -> Result<Vec<PathCheckDto>, DomainError> {
+        if paths.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Validate all paths first
+        let validated_paths: Result<Vec<String>, _> = paths
+            .iter()
+            .map(|p| FilePath::new(p).map(|fp| fp.as_str().to_string()))
+            .collect();
+        let validated_paths = validated_paths?;
+
+        let items = self.item_repo.find_by_paths(&validated_paths).await?;
+        let by_path: HashMap<String, ItemDto> = items
+            .into_iter()
+            .map(|item| (item.path().to_string(), Self::to_dto(item)))
+            .collect();
+
+        Ok(paths
+            .into_iter()
+            .zip(validated_paths)
+            .map(|(path, validated)| PathCheckDto {
+                path,
+                item: by_path.get(&validated).cloned(),
+            })
+            .collect())
+    }
+
+    /// Reads an item's File Reference Number from its file again and records
+    /// it, e.g. when rename tracking missed the item. Returns the item as
+    /// stored afterwards.
+    pub async fn refresh_frn(&self, id: i64) -> Result<ItemDto, DomainError> {
+        let item = self
+            .item_repo
+            .find_by_id(id)
+            .await?
+            .ok_or_else(|| DomainError::ItemNotFound(id.to_string()))?;
+
+        let frn = get_file_reference_number(item.path().as_str())?.ok_or_else(|| {
+            DomainError::ValidationError(format!("File not found: {}", item.path().as_str()))
+        })?;
+        if frn == item.file_reference_number() {
+            return Ok(with_frn_status(Self::to_dto(item)).await);
+        }
+
+        self.item_repo.update_file_reference_number(id, frn).await?;
+        let item = self
+            .item_repo
+            .find_by_id(id)
+            .await?
+            .ok_or_else(|| DomainError::ItemNotFound(id.to_string()))?;
+        Ok(with_frn_status(Self::to_dto(item)).await)
+    }
+
     /// Updates an item.
     pub async fn update(&self, id: i64, dto: UpdateItemDto) -> Result<(), DomainError> {
         let item = self
@@ -96,6 +255,107 @@ impl ItemService {
         self.item_repo.update(&item).await
     }
 
+    /// Sets an item's star rating (0-5), or clears it with `None`.
+    pub async fn set_rating(&self, id: i64, rating: Option<u8>) -> Result<(), DomainError> {
+        let mut item = self
+            .item_repo
+            .find_by_id(id)
+            .await?
+            .ok_or_else(|| DomainError::ItemNotFound(id.to_string()))?;
+
+        item.set_rating(rating)?;
+        self.item_repo.set_rating(id, item.rating()).await
+    }
+
+    /// Rewrites the path prefix of every item under a relocated folder.
+    ///
+    /// Matching is a plain prefix match, so pass a trailing separator
+    /// (`D:\Old\`) to avoid also matching siblings like `D:\Older`.
+    /// Returns the number of items updated.
+    pub async fn rebase_paths(
+        &self,
+        old_prefix: &str,
+        new_prefix: &str,
+    ) -> Result<usize, DomainError> {
+        let old_prefix = normalized_prefix(old_prefix)?;
+        let new_prefix = normalized_prefix(new_prefix)?;
+
+        if old_prefix == new_prefix {
+            return Ok(0);
+        }
+
+        self.item_repo.rebase_paths(&old_prefix, &new_prefix).await
+    }
+
+    /// Updates item paths after a folder rename the USN Journal could not observe
+    /// (non-NTFS drives, or the journal is disabled).
+    ///
+    /// Both prefixes are folders: trailing separators are ignored and only whole
+    /// path components match, so renaming `D:\Photo` leaves `D:\Photos` untouched.
+    pub async fn bulk_path_update(
+        &self,
+        old_prefix: String,
+        new_prefix: String,
+    ) -> Result<BulkUpdateResultDto, DomainError> {
+        let old_prefix = FilePath::new(old_prefix)?;
+        let new_prefix = FilePath::new(new_prefix)?;
+        let old_dir = old_prefix.as_str().trim_end_matches(['\\', '/']);
+        let new_dir = new_prefix.as_str().trim_end_matches(['\\', '/']);
+
+        if old_dir.is_empty() || new_dir.is_empty() {
+            return Err(DomainError::InvalidFilePath(
+                "Cannot rename a filesystem root".to_string(),
+            ));
+        }
+
+        let updated_count = if old_dir == new_dir {
+            0
+        } else {
+            self.item_repo.rebase_folder(old_dir, new_dir).await?
+        };
+
+        Ok(BulkUpdateResultDto { updated_count })
+    }
+
+    /// Rewrites every stored item path to its normalized form (see
+    /// `FilePath::normalize`), merging items that turn out to be the same
+    /// path. Paths written before normalization existed may differ in drive
+    /// letter case or separators.
+    pub async fn normalize_paths(&self) -> Result<PathNormalizationDto, DomainError> {
+        let (renamed_count, merged_count) = self.item_repo.normalize_paths().await?;
+        if renamed_count + merged_count > 0 {
+            log::info!(
+                "Normalized item paths: {} renamed, {} merged",
+                renamed_count,
+                merged_count
+            );
+        }
+        Ok(PathNormalizationDto {
+            renamed_count,
+            merged_count,
+        })
+    }
+
+    /// Soft-deletes every item carrying the tag. Returns the number of items affected.
+    pub async fn soft_delete_by_tag(&self, tag_id: i64) -> Result<u64, DomainError> {
+        self.ensure_tag_exists(tag_id).await?;
+        self.item_repo.soft_delete_by_tag(tag_id).await
+    }
+
+    /// Restores every soft-deleted item carrying the tag. Returns the number of items affected.
+    pub async fn restore_by_tag(&self, tag_id: i64) -> Result<u64, DomainError> {
+        self.ensure_tag_exists(tag_id).await?;
+        self.item_repo.restore_by_tag(tag_id).await
+    }
+
+    async fn ensure_tag_exists(&self, tag_id: i64) -> Result<(), DomainError> {
+        self.tag_repo
+            .find_by_id(tag_id)
+            .await?
+            .ok_or_else(|| DomainError::TagNotFound(tag_id.to_string()))?;
+        Ok(())
+    }
+
     /// Deletes an item.
     pub async fn delete(&self, id: i64) -> Result<(), DomainError> {
         self.item_repo.delete(id).await
@@ -106,6 +366,32 @@ impl ItemService {
         self.item_repo.add_tag(item_id, tag_id).await
     }
 
+    /// Queues adding a tag to an item. Queued writes are batched into
+    /// shared transactions and land within a short window; call
+    /// `flush_writes` to wait for them.
+    pub async fn enqueue_tag(&self, item_id: i64, tag_id: i64) {
+        self.write_queue.enqueue_tag(item_id, tag_id).await;
+    }
+
+    /// Queues registering a path as an item, batched like `enqueue_tag`.
+    /// Registered paths are skipped when the batch is written.
+    pub async fn enqueue_create(&self, path: &str) -> Result<(), DomainError> {
+        let path = FilePath::new(path)?;
+        let dto = Self::read_metadata(path.as_str());
+        let frn = Self::get_frn(path.as_str());
+        let taken_at = read_taken_at(path.as_str());
+        let mut item = Item::new(path, dto.is_directory, dto.size, dto.modified_time, frn);
+        item.update_taken_at(taken_at);
+        self.write_queue.enqueue_item(item).await;
+        Ok(())
+    }
+
+    /// Writes all queued items and tags. Writes of background batches that
+    /// failed are retried here; an error means this write failed too.
+    pub async fn flush_writes(&self) -> Result<(), DomainError> {
+        self.write_queue.flush().await
+    }
+
     /// Removes a tag from an item.
     pub async fn remove_tag(&self, item_id: i64, tag_id: i64) -> Result<(), DomainError> {
         self.item_repo.remove_tag(item_id, tag_id).await
@@ -117,6 +403,23 @@ impl ItemService {
         Ok(tags.into_iter().map(TagDto::from).collect())
     }
 
+    /// Gets the tags a directory inherits: the distinct tags of the active
+    /// items anywhere under it. These are computed, never stored as the
+    /// directory's own tags. At most `INHERITED_TAGS_SCAN_LIMIT` items are
+    /// considered, so a huge tree may report a partial set.
+    pub async fn get_inherited_tags(
+        &self,
+        directory_path: &str,
+    ) -> Result<Vec<TagDto>, DomainError> {
+        let path = FilePath::new(directory_path)?;
+        let directory = path.as_str().trim_end_matches(['\\', '/']);
+        let tags = self
+            .tag_repo
+            .find_under_directory(directory, INHERITED_TAGS_SCAN_LIMIT)
+            .await?;
+        Ok(tags.into_iter().map(TagDto::from).collect())
+    }
+
     /// Gets tags for multiple items at once (batch query to avoid N+1).
     pub async fn get_tags_batch(
         &self,
@@ -129,18 +432,132 @@ impl ItemService {
             .collect())
     }
 
+    /// Gets items together with their tags, in `item_ids` order. Unknown IDs
+    /// are skipped. Items and tags are loaded concurrently.
+    pub async fn get_with_tags_batch(
+        &self,
+        item_ids: Vec<i64>,
+    ) -> Result<Vec<ItemWithTagsDto>, DomainError> {
+        if item_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let (items, tags_map) = tokio::try_join!(
+            self.item_repo.find_by_ids(&item_ids),
+            self.tag_repo.find_by_items(&item_ids)
+        )?;
+
+        let mut by_id: HashMap<i64, Item> = items
+            .into_iter()
+            .filter_map(|item| Some((item.id()?, item)))
+            .collect();
+        let items = item_ids.iter().filter_map(|id| by_id.remove(id)).collect();
+        Ok(Self::with_tags(items, tags_map))
+    }
+
+    /// Gets the active items directly inside `directory`, or anywhere under it
+    /// with `recursive`, together with their tags. Ordered by path.
+    pub async fn get_in_directory_with_tags(
+        &self,
+        directory: &str,
+        recursive: bool,
+    ) -> Result<Vec<ItemWithTagsDto>, DomainError> {
+        let path = FilePath::new(directory)?;
+        let directory = path.as_str().trim_end_matches(['\\', '/']);
+        let items = self
+            .item_repo
+            .find_in_directory(directory, recursive)
+            .await?;
+        let item_ids: Vec<i64> = items.iter().filter_map(|item| item.id()).collect();
+        let tags_map = self.tag_repo.find_by_items(&item_ids).await?;
+        Ok(Self::with_tags(items, tags_map))
+    }
+
+    /// Pairs each item with its tags from `tags_map`.
+    fn with_tags(items: Vec<Item>, mut tags_map: HashMap<i64, Vec<Tag>>) -> Vec<ItemWithTagsDto> {
+        items
+            .into_iter()
+            .map(|item| {
+                let tags = item
+                    .id()
+                    .and_then(|id| tags_map.remove(&id))
+                    .unwrap_or_default();
+                ItemWithTagsDto {
+                    item: Self::to_dto(item),
+                    tags: tags.into_iter().map(TagDto::from).collect(),
+                }
+            })
+            .collect()
+    }
+
     /// Replaces all tags for an item.
-    pub async fn update_tags(&self, item_id: i64, tag_ids: Vec<i64>) -> Result<(), DomainError> {
-        self.item_repo.replace_tags(item_id, tag_ids).await
+    ///
+    /// Passing the `tags_updated_at` the caller last saw turns the write into
+    /// a `Conflict` carrying the current tags if another window changed the
+    /// item's tags since.
+    pub async fn update_tags(
+        &self,
+        item_id: i64,
+        tag_ids: Vec<i64>,
+        expected_tags_updated_at: Option<i64>,
+    ) -> Result<TagUpdateResultDto, DomainError> {
+        match self
+            .item_repo
+            .replace_tags(item_id, tag_ids, expected_tags_updated_at)
+            .await
+        {
+            Ok(tags_updated_at) => Ok(TagUpdateResultDto::Updated { tags_updated_at }),
+            Err(DomainError::ConcurrentModification(_)) => {
+                let item = self
+                    .item_repo
+                    .find_by_id(item_id)
+                    .await?
+                    .ok_or_else(|| DomainError::ItemNotFound(item_id.to_string()))?;
+                Ok(TagUpdateResultDto::Conflict {
+                    tags_updated_at: item.tags_updated_at().unwrap_or(0),
+                    current_tags: self.get_tags(item_id).await?,
+                })
+            }
+            Err(e) => Err(e),
+        }
     }
 
     /// Batch adds a tag to multiple items by path.
-    /// Creates items in DB if they don't exist.
+    /// Creates items in DB if they don't exist; unregistered paths missing
+    /// from disk are reported in `skipped_paths` instead.
+    ///
+    /// Registered items listed in `expected_tags_updated_at` (by path) whose
+    /// tags changed since are skipped and reported in `conflicts`.
     pub async fn batch_add_tag(
         &self,
         paths: Vec<String>,
         tag_id: i64,
+        expected_tags_updated_at: HashMap<String, i64>,
     ) -> Result<BatchTagResult, DomainError> {
+        self.batch_add_tag_with_progress(
+            paths,
+            tag_id,
+            expected_tags_updated_at,
+            &CancellationToken::new(),
+            |_, _| {},
+        )
+        .await
+    }
+
+    /// `batch_add_tag` reporting `(processed, total)` items after each chunk
+    /// of tag writes. Once `cancel` is set the remaining chunks are skipped
+    /// and the result is marked `cancelled`; chunks already written stay.
+    pub async fn batch_add_tag_with_progress<F>(
+        &self,
+        paths: Vec<String>,
+        tag_id: i64,
+        expected_tags_updated_at: HashMap<String, i64>,
+        cancel: &CancellationToken,
+        on_progress: F,
+    ) -> Result<BatchTagResult, DomainError>
+    where
+        F: Fn(usize, usize) + Send + Sync,
+    {
         let mut result = BatchTagResult::default();
 
         if paths.is_empty() {
@@ -148,10 +565,8 @@ impl ItemService {
         }
 
         // Validate and collect paths
-        let validated_paths: Vec<String> = paths
-            .iter()
-            .filter_map(|p| FilePath::new(p).ok().map(|fp| fp.as_str().to_string()))
-            .collect();
+        let (validated_paths, skipped_paths) = Self::validate_paths(paths);
+        result.skipped_paths = skipped_paths;
 
         // Find existing items
         let existing_items = self.item_repo.find_by_paths(&validated_paths).await?;
@@ -161,52 +576,349 @@ impl ItemService {
             .collect();
 
         let mut item_ids: Vec<i64> = existing_items.iter().filter_map(|i| i.id()).collect();
+        let expected = Self::expected_by_id(&existing_items, &expected_tags_updated_at);
 
-        // Create missing items
+        // Create missing items, but only for files that are actually there
         for path in &validated_paths {
             if !existing_paths.contains(path) {
-                let metadata = std::fs::metadata(path).ok();
-                let is_directory = metadata.as_ref().map(|m| m.is_dir()).unwrap_or(false);
-                let size = metadata.as_ref().and_then(|m| m.len().try_into().ok());
-                let modified = metadata.as_ref().and_then(|m| {
-                    m.modified()
-                        .ok()?
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .ok()?
-                        .as_secs()
-                        .try_into()
-                        .ok()
-                });
+                if !Path::new(path).exists() {
+                    result.skipped_paths.push(path.clone());
+                    continue;
+                }
+                match self.create(Self::read_metadata(path)).await {
+                    Ok(id) => {
+                        item_ids.push(id);
+                        result.created_count += 1;
+                    }
+                    // Created since the lookup above (e.g. by a USN refresh), so reuse it
+                    Err(DomainError::DuplicateEntry(_)) => {
+                        if let Some(id) = self
+                            .item_repo
+                            .find_by_path(path)
+                            .await?
+                            .and_then(|i| i.id())
+                        {
+                            item_ids.push(id);
+                        }
+                    }
+                    Err(_) => result.skipped_paths.push(path.clone()),
+                }
+            }
+        }
+
+        self.write_tag_in_chunks(
+            TagWrite::Add,
+            &item_ids,
+            tag_id,
+            &expected,
+            cancel,
+            &on_progress,
+            &mut result,
+        )
+        .await?;
 
-                let dto = CreateItemDto {
-                    path: path.clone(),
-                    is_directory,
-                    size,
-                    modified_time: modified,
-                };
+        Ok(result)
+    }
 
-                if let Ok(id) = self.create(dto).await {
-                    item_ids.push(id);
-                    result.created_count += 1;
+    /// Adds or removes a tag in chunks of `BATCH_TAG_CHUNK_SIZE`, filling the
+    /// counts and conflicts of `result`.
+    #[allow(clippy::too_many_arguments)]
+    async fn write_tag_in_chunks<F>(
+        &self,
+        write: TagWrite,
+        item_ids: &[i64],
+        tag_id: i64,
+        expected: &HashMap<i64, i64>,
+        cancel: &CancellationToken,
+        on_progress: &F,
+        result: &mut BatchTagResult,
+    ) -> Result<(), DomainError>
+    where
+        F: Fn(usize, usize) + Send + Sync,
+    {
+        let mut stale = Vec::new();
+        let mut processed = 0;
+        for chunk in item_ids.chunks(BATCH_TAG_CHUNK_SIZE) {
+            if cancel.is_cancelled() {
+                result.cancelled = true;
+                break;
+            }
+            let chunk_stale = match write {
+                TagWrite::Add => {
+                    self.item_repo
+                        .batch_add_tag(chunk, tag_id, expected)
+                        .await?
+                }
+                TagWrite::Remove => {
+                    self.item_repo
+                        .batch_remove_tag(chunk, tag_id, expected)
+                        .await?
                 }
+            };
+            processed += chunk.len();
+            result.success_count += chunk.len() - chunk_stale.len();
+            stale.extend(chunk_stale);
+            on_progress(processed, item_ids.len());
+        }
+
+        result.conflicts = self.conflicts(&stale).await?;
+        result.failed_count = result.conflicts.len();
+        Ok(())
+    }
+
+    /// Splits paths into validated ones and the raw paths that failed validation.
+    fn validate_paths(paths: Vec<String>) -> (Vec<String>, Vec<String>) {
+        let mut validated = Vec::with_capacity(paths.len());
+        let mut invalid = Vec::new();
+        for path in paths {
+            match FilePath::new(path.as_str()) {
+                Ok(fp) => validated.push(fp.as_str().to_string()),
+                Err(_) => invalid.push(path),
+            }
+        }
+        (validated, invalid)
+    }
+
+    /// Maps the expected versions given by path to the IDs of `items`.
+    fn expected_by_id(
+        items: &[Item],
+        expected_tags_updated_at: &HashMap<String, i64>,
+    ) -> HashMap<i64, i64> {
+        if expected_tags_updated_at.is_empty() {
+            return HashMap::new();
+        }
+        let expected_tags_updated_at: HashMap<String, i64> = expected_tags_updated_at
+            .iter()
+            .filter_map(|(path, &v)| Some((FilePath::new(path).ok()?.as_str().to_string(), v)))
+            .collect();
+        items
+            .iter()
+            .filter_map(|item| {
+                Some((
+                    item.id()?,
+                    *expected_tags_updated_at.get(item.path().as_str())?,
+                ))
+            })
+            .collect()
+    }
+
+    /// Describes the items a batch write skipped, with their current version.
+    async fn conflicts(&self, stale: &[i64]) -> Result<Vec<TagConflictDto>, DomainError> {
+        let mut conflicts = Vec::with_capacity(stale.len());
+        for &item_id in stale {
+            if let Some(item) = self.item_repo.find_by_id(item_id).await? {
+                conflicts.push(TagConflictDto {
+                    item_id,
+                    path: item.path().to_string(),
+                    tags_updated_at: item.tags_updated_at().unwrap_or(0),
+                });
             }
         }
+        Ok(conflicts)
+    }
+
+    /// Tags every file under `root`, registering the ones that are not items
+    /// yet. See [`scan_directory`] for how `recursive` and `include_hidden`
+    /// shape the scan; files and directories matching `index_exclude_patterns`
+    /// are skipped.
+    ///
+    /// Files are tagged in batches of `TAG_DIRECTORY_BATCH_SIZE`; if a batch
+    /// fails, earlier batches stay tagged. `cancel` is checked before each
+    /// batch.
+    pub async fn tag_directory(
+        &self,
+        root: &str,
+        tag_id: i64,
+        recursive: bool,
+        include_hidden: bool,
+        cancel: &CancellationToken,
+    ) -> Result<DirectoryTagResultDto, DomainError> {
+        let root = FilePath::new(root)?;
+        let root_path = std::path::PathBuf::from(root.as_str());
+        if !root_path.is_dir() {
+            return Err(DomainError::ValidationError(format!(
+                "Not a directory: {}",
+                root.as_str()
+            )));
+        }
+        if self.tag_repo.find_by_id(tag_id).await?.is_none() {
+            return Err(DomainError::TagNotFound(tag_id.to_string()));
+        }
 
-        // Batch add tag
-        match self.item_repo.batch_add_tag(&item_ids, tag_id).await {
-            Ok(_) => result.success_count = item_ids.len(),
-            Err(e) => return Err(e),
+        let exclude = ExcludePatterns::new(
+            &self
+                .settings_service
+                .get_string_list(INDEX_EXCLUDE_PATTERNS_KEY)
+                .await,
+        );
+        let scan = tokio::task::spawn_blocking(move || {
+            scan_directory(&root_path, recursive, include_hidden, &exclude)
+        })
+        .await
+        .map_err(|e| DomainError::ValidationError(format!("Directory scan failed: {}", e)))?;
+
+        let mut result = DirectoryTagResultDto {
+            scanned_count: scan.paths.len(),
+            excluded_count: scan.excluded_count,
+            ..Default::default()
+        };
+        for batch in scan.paths.chunks(TAG_DIRECTORY_BATCH_SIZE) {
+            if cancel.is_cancelled() {
+                result.cancelled = true;
+                break;
+            }
+            let tagged = self
+                .batch_add_tag(batch.to_vec(), tag_id, HashMap::new())
+                .await?;
+            result.created_count += tagged.created_count;
+            result.tagged_count += tagged.success_count;
         }
 
         Ok(result)
     }
 
-    /// Batch removes a tag from multiple items by path.
+    /// Registers paths as items without tagging them.
+    ///
+    /// Every path is validated first. Paths that are not registered yet are
+    /// inserted with their file metadata in a single transaction; registered
+    /// ones are counted as skipped.
+    pub async fn create_many(
+        &self,
+        paths: Vec<String>,
+    ) -> Result<CreateItemsResultDto, DomainError> {
+        let items: Vec<Item> = paths
+            .iter()
+            .map(|p| {
+                let path = FilePath::new(p)?;
+                let dto = Self::read_metadata(path.as_str());
+                let frn = Self::get_frn(path.as_str());
+                let taken_at = read_taken_at(path.as_str());
+                let mut item = Item::new(path, dto.is_directory, dto.size, dto.modified_time, frn);
+                item.update_taken_at(taken_at);
+                Ok(item)
+            })
+            .collect::<Result<_, DomainError>>()?;
+
+        let saved = self.item_repo.save_missing(&items).await?;
+        let created_count = saved.iter().filter(|(_, created)| *created).count();
+        Ok(CreateItemsResultDto {
+            created_count,
+            skipped_count: saved.len() - created_count,
+            item_ids: saved.into_iter().map(|(id, _)| id).collect(),
+        })
+    }
+
+    /// Re-reads size, modification time and EXIF capture time from disk for
+    /// active items at or under the directory `prefix` (all items when `None`)
+    /// and stores the ones that changed. EXIF is only re-read for files that
+    /// changed or have no capture time yet.
+    ///
+    /// Items whose file is gone are counted as missing and left for USN
+    /// refresh to resolve. Changes are written in batches of
+    /// `METADATA_REFRESH_BATCH_SIZE`, one transaction each. `cancel` is
+    /// checked before each item; changes found before cancellation are saved.
+    pub async fn refresh_metadata(
+        &self,
+        prefix: Option<String>,
+        cancel: &CancellationToken,
+    ) -> Result<MetadataRefreshResultDto, DomainError> {
+        let directory = match prefix {
+            Some(prefix) => FilePath::new(&prefix)?.into_string(),
+            None => String::new(),
+        };
+        let items = self.item_repo.find_active_under(&directory).await?;
+
+        let mut result = MetadataRefreshResultDto::default();
+        let mut changes = Vec::new();
+        for item in &items {
+            if cancel.is_cancelled() {
+                result.cancelled = true;
+                break;
+            }
+            let Some(id) = item.id() else {
+                continue;
+            };
+            let path = item.path().as_str();
+            if std::fs::metadata(path).is_err() {
+                result.missing += 1;
+                continue;
+            }
+            result.checked += 1;
+
+            let current = Self::read_metadata(path);
+            let file_changed =
+                current.size != item.size() || current.modified_time != item.modified_time();
+            let taken_at = if file_changed || item.taken_at().is_none() {
+                read_taken_at(path)
+            } else {
+                item.taken_at()
+            };
+            if file_changed || taken_at != item.taken_at() {
+                changes.push((id, current.size, current.modified_time, taken_at));
+            }
+        }
+
+        for batch in changes.chunks(METADATA_REFRESH_BATCH_SIZE) {
+            result.updated += self.item_repo.update_metadata(batch).await?;
+        }
+        Ok(result)
+    }
+
+    /// Reads what an item records about a path from the file system. A path
+    /// that can't be read is recorded as a file without size or time.
+    fn read_metadata(path: &str) -> CreateItemDto {
+        let metadata = std::fs::metadata(path).ok();
+        let is_directory = metadata.as_ref().map(|m| m.is_dir()).unwrap_or(false);
+        let size = metadata.as_ref().and_then(|m| m.len().try_into().ok());
+        let modified = metadata.as_ref().and_then(|m| {
+            m.modified()
+                .ok()?
+                .duration_since(std::time::UNIX_EPOCH)
+                .ok()?
+                .as_secs()
+                .try_into()
+                .ok()
+        });
+
+        CreateItemDto {
+            path: path.to_string(),
+            is_directory,
+            size,
+            modified_time: modified,
+        }
+    }
+
+    /// Batch removes a tag from multiple items by path, skipping stale items
+    /// like `batch_add_tag`.
     pub async fn batch_remove_tag(
         &self,
         paths: Vec<String>,
         tag_id: i64,
+        expected_tags_updated_at: HashMap<String, i64>,
     ) -> Result<BatchTagResult, DomainError> {
+        self.batch_remove_tag_with_progress(
+            paths,
+            tag_id,
+            expected_tags_updated_at,
+            &CancellationToken::new(),
+            |_, _| {},
+        )
+        .await
+    }
+
+    /// `batch_remove_tag` with progress and cancellation like
+    /// `batch_add_tag_with_progress`.
+    pub async fn batch_remove_tag_with_progress<F>(
+        &self,
+        paths: Vec<String>,
+        tag_id: i64,
+        expected_tags_updated_at: HashMap<String, i64>,
+        cancel: &CancellationToken,
+        on_progress: F,
+    ) -> Result<BatchTagResult, DomainError>
+    where
+        F: Fn(usize, usize) + Send + Sync,
+    {
         let mut result = BatchTagResult::default();
 
         if paths.is_empty() {
@@ -214,24 +926,36 @@ impl ItemService {
         }
 
         // Validate and collect paths
-        let validated_paths: Vec<String> = paths
-            .iter()
-            .filter_map(|p| FilePath::new(p).ok().map(|fp| fp.as_str().to_string()))
-            .collect();
+        let (validated_paths, skipped_paths) = Self::validate_paths(paths);
+        result.skipped_paths = skipped_paths;
 
-        // Find existing items
+        // Find existing items; paths without one have nothing to remove
         let existing_items = self.item_repo.find_by_paths(&validated_paths).await?;
+        let existing_paths: std::collections::HashSet<&str> =
+            existing_items.iter().map(|i| i.path().as_str()).collect();
+        result.skipped_paths.extend(
+            validated_paths
+                .iter()
+                .filter(|p| !existing_paths.contains(p.as_str()))
+                .cloned(),
+        );
         let item_ids: Vec<i64> = existing_items.iter().filter_map(|i| i.id()).collect();
 
         if item_ids.is_empty() {
             return Ok(result);
         }
+        let expected = Self::expected_by_id(&existing_items, &expected_tags_updated_at);
 
-        // Batch remove tag
-        match self.item_repo.batch_remove_tag(&item_ids, tag_id).await {
-            Ok(_) => result.success_count = item_ids.len(),
-            Err(e) => return Err(e),
-        }
+        self.write_tag_in_chunks(
+            TagWrite::Remove,
+            &item_ids,
+            tag_id,
+            &expected,
+            cancel,
+            &on_progress,
+            &mut result,
+        )
+        .await?;
 
         Ok(result)
     }
@@ -285,6 +1009,20 @@ impl ItemService {
             .collect())
     }
 
+    async fn with_tag_count(
+        &self,
+        item: Option<ItemDto>,
+        include_tag_count: bool,
+    ) -> Result<Option<ItemDto>, DomainError> {
+        match item {
+            Some(mut item) if include_tag_count => {
+                item.tag_count = Some(self.item_repo.count_tags(item.id).await?);
+                Ok(Some(item))
+            }
+            other => Ok(other),
+        }
+    }
+
     fn to_dto(item: Item) -> ItemDto {
         ItemDto {
             id: item.id().unwrap_or(0),
@@ -294,8 +1032,47 @@ impl ItemService {
             modified_time: item.modified_time(),
             created_at: item.created_at().unwrap_or(0),
             updated_at: item.updated_at().unwrap_or(0),
+            tags_updated_at: item.tags_updated_at(),
+            rating: item.rating(),
+            tag_count: None,
+            file_reference_number: (item.file_reference_number() != 0)
+                .then(|| item.file_reference_number()),
+            frn_status: None,
+        }
+    }
+}
+
+/// Fills in the rename tracking status of an item. Only items without an
+/// FRN need the drive's file system looked up, which is a blocking volume
+/// query, so single-item lookups are the only ones that ask for it.
+async fn with_frn_status(mut dto: ItemDto) -> ItemDto {
+    let status = if dto.file_reference_number.is_some() {
+        FrnStatus::Tracked
+    } else {
+        let path = dto.path.clone();
+        let on_ntfs = tokio::task::spawn_blocking(move || {
+            drive_letter(&path).is_some_and(|drive| is_ntfs(drive).unwrap_or(false))
+        })
+        .await
+        .unwrap_or(false);
+        if on_ntfs {
+            FrnStatus::Untracked
+        } else {
+            FrnStatus::NotNtfs
         }
+    };
+    dto.frn_status = Some(status);
+    dto
+}
+
+/// Normalizes a path prefix, keeping a trailing separator if it had one:
+/// it limits a prefix match to whole path components.
+fn normalized_prefix(raw: &str) -> Result<String, DomainError> {
+    let mut prefix = FilePath::new(raw)?.into_string();
+    if raw.trim_end().ends_with(['\\', '/']) && !prefix.ends_with('\\') {
+        prefix.push('\\');
     }
+    Ok(prefix)
 }
 
 impl From<crate::domain::entities::Tag> for TagDto {
@@ -309,3 +1086,1014 @@ impl From<crate::domain::entities::Tag> for TagDto {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::persistence::{
+        SqliteItemRepository, SqliteSettingsRepository, SqliteTagRepository, open_database,
+    };
+
+    async fn setup() -> (tempfile::TempDir, ItemService) {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = Arc::new(open_database(&dir.path().join("test.db")).await.unwrap());
+        let item_repo: Arc<dyn ItemRepository> = Arc::new(SqliteItemRepository::new(pool.clone()));
+        let tag_repo: Arc<dyn TagRepository> = Arc::new(SqliteTagRepository::new(pool.clone()));
+        let settings_service = Arc::new(SettingsService::new(Arc::new(
+            SqliteSettingsRepository::new(pool),
+        )));
+        (dir, ItemService::new(item_repo, tag_repo, settings_service))
+    }
+
+    async fn create_item(service: &ItemService, path: &str) -> i64 {
+        service
+            .create(CreateItemDto {
+                path: path.to_string(),
+                is_directory: false,
+                size: Some(1),
+                modified_time: Some(1),
+            })
+            .await
+            .unwrap()
+    }
+
+    async fn path_of(service: &ItemService, id: i64) -> String {
+        service.get_by_id(id, false).await.unwrap().unwrap().path
+    }
+
+    #[tokio::test]
+    async fn set_rating_validates_range_and_round_trips() {
+        let (_dir, service) = setup().await;
+        let id = create_item(&service, "C:\\data\\a.jpg").await;
+        assert_eq!(
+            service.get_by_id(id, false).await.unwrap().unwrap().rating,
+            None
+        );
+
+        service.set_rating(id, Some(4)).await.unwrap();
+        assert_eq!(
+            service.get_by_id(id, false).await.unwrap().unwrap().rating,
+            Some(4)
+        );
+
+        assert!(matches!(
+            service.set_rating(id, Some(6)).await,
+            Err(DomainError::ValidationError(_))
+        ));
+        assert_eq!(
+            service.get_by_id(id, false).await.unwrap().unwrap().rating,
+            Some(4)
+        );
+
+        service.set_rating(id, None).await.unwrap();
+        assert_eq!(
+            service.get_by_id(id, false).await.unwrap().unwrap().rating,
+            None
+        );
+
+        assert!(matches!(
+            service.set_rating(999, Some(1)).await,
+            Err(DomainError::ItemNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn batch_add_tag_reuses_item_created_during_the_batch() {
+        let (dir, service) = setup().await;
+        let pool = open_database(&dir.path().join("test.db")).await.unwrap();
+        let conn = pool.get().await.unwrap();
+        conn.interact(|conn: &mut rusqlite::Connection| {
+            conn.execute_batch(
+                "INSERT INTO tag_groups (id, name) VALUES (1, 'Group');
+                 INSERT INTO tags (id, group_id, value) VALUES (1, 1, 'a');",
+            )
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+        // The second occurrence misses the up-front lookup and hits the existing row
+        let path = touch(&dir, "a.txt");
+        let result = service
+            .batch_add_tag(vec![path.clone(), path.clone()], 1, HashMap::new())
+            .await
+            .unwrap();
+
+        assert_eq!(result.created_count, 1);
+        let item = service.get_by_path(&path, false).await.unwrap().unwrap();
+        assert_eq!(
+            service.item_repo.get_tag_ids(item.id).await.unwrap(),
+            vec![1]
+        );
+    }
+
+    async fn insert_tags(dir: &tempfile::TempDir) {
+        let pool = open_database(&dir.path().join("test.db")).await.unwrap();
+        let conn = pool.get().await.unwrap();
+        conn.interact(|conn: &mut rusqlite::Connection| {
+            conn.execute_batch(
+                "INSERT INTO tag_groups (id, name) VALUES (1, 'Group');
+                 INSERT INTO tags (id, group_id, value) VALUES (1, 1, 'a'), (2, 1, 'b');",
+            )
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    }
+
+    /// Creates an empty file in `dir`, returning its path.
+    fn touch(dir: &tempfile::TempDir, name: &str) -> String {
+        let path = dir.path().join(name);
+        std::fs::write(&path, b"").unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    async fn version_of(service: &ItemService, id: i64) -> i64 {
+        service
+            .get_by_id(id, false)
+            .await
+            .unwrap()
+            .unwrap()
+            .tags_updated_at
+            .unwrap_or(0)
+    }
+
+    #[tokio::test]
+    async fn tag_count_is_only_filled_in_when_requested() {
+        let (dir, service) = setup().await;
+        insert_tags(&dir).await;
+        let id = create_item(&service, "C:\\data\\a.txt").await;
+        service.update_tags(id, vec![1, 2], None).await.unwrap();
+
+        let item = service.get_by_id(id, false).await.unwrap().unwrap();
+        assert_eq!(item.tag_count, None);
+        let item = service.get_by_id(id, true).await.unwrap().unwrap();
+        assert_eq!(item.tag_count, Some(2));
+        let item = service
+            .get_by_path("C:\\data\\a.txt", true)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(item.tag_count, Some(2));
+        assert!(service.get_by_id(999, true).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn path_reuse_inherits_tags_of_recently_deleted_item() {
+        let (dir, service) = setup().await;
+        insert_tags(&dir).await;
+        let recent = create_item(&service, "C:\\data\\a.txt").await;
+        service.update_tags(recent, vec![1], None).await.unwrap();
+        let old = create_item(&service, "C:\\data\\b.txt").await;
+        service.update_tags(old, vec![2], None).await.unwrap();
+
+        let pool = open_database(&dir.path().join("test.db")).await.unwrap();
+        let conn = pool.get().await.unwrap();
+        conn.interact(move |conn: &mut rusqlite::Connection| {
+            conn.execute(
+                "UPDATE items SET is_deleted = 1, deleted_at = unixepoch() WHERE id = ?1",
+                [recent],
+            )?;
+            conn.execute(
+                "UPDATE items SET is_deleted = 1, deleted_at = 0 WHERE id = ?1",
+                [old],
+            )
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+        let dto = |path: &str| CreateItemDto {
+            path: path.to_string(),
+            is_directory: false,
+            size: Some(1),
+            modified_time: Some(1),
+        };
+
+        // Off by default: the deleted row still holds the path
+        assert!(matches!(
+            service.create(dto("C:\\data\\a.txt")).await,
+            Err(DomainError::DuplicateEntry(_))
+        ));
+
+        service
+            .settings_service
+            .set_bool("rehydrate_tags_on_path_reuse", true)
+            .await
+            .unwrap();
+        let reused = service.create(dto("C:\\data\\a.txt")).await.unwrap();
+        assert_ne!(reused, recent);
+        assert_eq!(
+            service.item_repo.get_tag_ids(reused).await.unwrap(),
+            vec![1]
+        );
+        assert!(service.get_by_id(recent, false).await.unwrap().is_none());
+
+        // Deleted outside the window: the path is freed but the tags are not kept
+        let replaced = service.create(dto("C:\\data\\b.txt")).await.unwrap();
+        assert!(
+            service
+                .item_repo
+                .get_tag_ids(replaced)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        // An active item at the path is never replaced
+        assert!(matches!(
+            service.create(dto("C:\\data\\a.txt")).await,
+            Err(DomainError::DuplicateEntry(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn inherited_tags_cover_active_descendants_only() {
+        let (dir, service) = setup().await;
+        insert_tags(&dir).await;
+        let deep = create_item(&service, "C:\\photos\\2024\\a.jpg").await;
+        service.update_tags(deep, vec![1], None).await.unwrap();
+        let sibling = create_item(&service, "C:\\photos2\\b.jpg").await;
+        service.update_tags(sibling, vec![2], None).await.unwrap();
+
+        let values = |tags: Vec<TagDto>| tags.into_iter().map(|t| t.value).collect::<Vec<_>>();
+        assert_eq!(
+            values(service.get_inherited_tags("C:\\photos").await.unwrap()),
+            vec!["a"]
+        );
+        assert_eq!(
+            values(service.get_inherited_tags("C:\\").await.unwrap()),
+            vec!["a", "b"]
+        );
+        assert!(
+            service
+                .get_inherited_tags("C:\\photos\\2024\\a.jpg")
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn items_with_tags_keep_request_order() {
+        let (dir, service) = setup().await;
+        insert_tags(&dir).await;
+        let a = create_item(&service, "C:\\data\\a.txt").await;
+        let b = create_item(&service, "C:\\data\\b.txt").await;
+        service.update_tags(b, vec![1, 2], None).await.unwrap();
+
+        let result = service.get_with_tags_batch(vec![b, 999, a]).await.unwrap();
+        let ids: Vec<i64> = result.iter().map(|r| r.item.id).collect();
+        assert_eq!(ids, vec![b, a]);
+        assert_eq!(result[0].tags.len(), 2);
+        assert!(result[1].tags.is_empty());
+        assert!(
+            service
+                .get_with_tags_batch(Vec::new())
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn directory_items_with_tags_are_direct_or_recursive() {
+        let (dir, service) = setup().await;
+        insert_tags(&dir).await;
+        let top = create_item(&service, "C:\\photos\\a.jpg").await;
+        service.update_tags(top, vec![1], None).await.unwrap();
+        let nested = create_item(&service, "C:\\photos\\2024\\b.jpg").await;
+        create_item(&service, "C:\\photos2\\c.jpg").await;
+
+        let ids = |items: Vec<ItemWithTagsDto>| items.iter().map(|i| i.item.id).collect::<Vec<_>>();
+        let direct = service
+            .get_in_directory_with_tags("C:\\photos\\", false)
+            .await
+            .unwrap();
+        assert_eq!(direct[0].tags.len(), 1);
+        assert_eq!(ids(direct), vec![top]);
+        let recursive = service
+            .get_in_directory_with_tags("C:\\photos", true)
+            .await
+            .unwrap();
+        assert_eq!(ids(recursive), vec![nested, top]);
+    }
+
+    /// Times the combined call against one request per item at 100 and 1,000
+    /// items. Run with `cargo test bench_items_with_tags -- --ignored --nocapture`.
+    #[tokio::test]
+    #[ignore = "benchmark"]
+    async fn bench_items_with_tags() {
+        let (dir, service) = setup().await;
+        insert_tags(&dir).await;
+        let pool = open_database(&dir.path().join("test.db")).await.unwrap();
+        let conn = pool.get().await.unwrap();
+        conn.interact(|conn: &mut rusqlite::Connection| {
+            conn.execute_batch(
+                "WITH RECURSIVE n(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM n WHERE x < 1000)
+                 INSERT INTO items (id, path, is_directory) SELECT x, 'C:\\bench\\' || x || '.jpg', 0 FROM n;
+                 INSERT INTO item_tags (item_id, tag_id) SELECT id, 1 FROM items;
+                 INSERT INTO item_tags (item_id, tag_id) SELECT id, 2 FROM items WHERE id % 2 = 0;",
+            )
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+        for count in [100, 1_000] {
+            let item_ids: Vec<i64> = (1..=count).collect();
+
+            let start = std::time::Instant::now();
+            let combined = service.get_with_tags_batch(item_ids.clone()).await.unwrap();
+            let combined_elapsed = start.elapsed();
+            assert_eq!(combined.len(), count as usize);
+
+            let start = std::time::Instant::now();
+            for &id in &item_ids {
+                service.get_by_id(id, false).await.unwrap();
+                service.get_tags(id).await.unwrap();
+            }
+            let per_item_elapsed = start.elapsed();
+
+            eprintln!(
+                "{} items: combined {:?}, per item {:?}",
+                count, combined_elapsed, per_item_elapsed
+            );
+        }
+    }
+
+    async fn insert_numbered_tags(dir: &tempfile::TempDir, count: u32) {
+        let pool = open_database(&dir.path().join("test.db")).await.unwrap();
+        let conn = pool.get().await.unwrap();
+        conn.interact(move |conn: &mut rusqlite::Connection| {
+            conn.execute_batch(&format!(
+                "INSERT INTO tag_groups (id, name) VALUES (1, 'Group');
+                 WITH RECURSIVE n(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM n WHERE x < {})
+                 INSERT INTO tags (id, group_id, value) SELECT x, 1, 'tag' || x FROM n;",
+                count
+            ))
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    }
+
+    async fn link_count(dir: &tempfile::TempDir) -> i64 {
+        let pool = open_database(&dir.path().join("test.db")).await.unwrap();
+        let conn = pool.get().await.unwrap();
+        conn.interact(|conn: &mut rusqlite::Connection| {
+            conn.query_row("SELECT COUNT(*) FROM item_tags", [], |row| row.get(0))
+        })
+        .await
+        .unwrap()
+        .unwrap()
+    }
+
+    /// Registers `count` queued items and returns their IDs.
+    async fn enqueue_items(service: &ItemService, count: usize) -> Vec<i64> {
+        let paths: Vec<String> = (0..count)
+            .map(|i| format!("C:\\import\\{}.jpg", i))
+            .collect();
+        for path in &paths {
+            service.enqueue_create(path).await.unwrap();
+        }
+        service.flush_writes().await.unwrap();
+        service
+            .get_by_paths(paths)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|item| item.id)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn queued_creates_and_tags_land_on_flush() {
+        let (dir, service) = setup().await;
+        insert_numbered_tags(&dir, 3).await;
+
+        let item_ids = enqueue_items(&service, 20).await;
+        assert_eq!(item_ids.len(), 20);
+
+        for tag_id in 1..=3 {
+            for &item_id in &item_ids {
+                service.enqueue_tag(item_id, tag_id).await;
+            }
+        }
+        service.flush_writes().await.unwrap();
+        assert_eq!(link_count(&dir).await, 60);
+    }
+
+    #[tokio::test]
+    async fn ten_thousand_queued_tag_writes_all_land() {
+        let (dir, service) = setup().await;
+        insert_numbered_tags(&dir, 10).await;
+        let item_ids = enqueue_items(&service, 1_000).await;
+
+        for tag_id in 1..=10 {
+            for &item_id in &item_ids {
+                service.enqueue_tag(item_id, tag_id).await;
+            }
+        }
+        service.flush_writes().await.unwrap();
+        assert_eq!(link_count(&dir).await, 10_000);
+    }
+
+    #[tokio::test]
+    #[ignore = "benchmark"]
+    async fn bench_queued_tag_writes() {
+        let (dir, service) = setup().await;
+        insert_numbered_tags(&dir, 11).await;
+        let item_ids = enqueue_items(&service, 1_000).await;
+
+        let start = std::time::Instant::now();
+        for tag_id in 1..=10 {
+            for &item_id in &item_ids {
+                service.enqueue_tag(item_id, tag_id).await;
+            }
+        }
+        service.flush_writes().await.unwrap();
+        let queued_elapsed = start.elapsed();
+
+        let start = std::time::Instant::now();
+        for &item_id in &item_ids[..500] {
+            service.add_tag(item_id, 11).await.unwrap();
+        }
+        let direct_elapsed = start.elapsed();
+
+        eprintln!(
+            "10000 queued tag writes: {:?}; 500 direct: {:?}",
+            queued_elapsed, direct_elapsed
+        );
+    }
+
+    #[tokio::test]
+    async fn update_tags_returns_current_tags_on_conflict() {
+        let (dir, service) = setup().await;
+        insert_tags(&dir).await;
+        let id = create_item(&service, "C:\\data\\a.txt").await;
+        let read = version_of(&service, id).await;
+
+        let TagUpdateResultDto::Updated { tags_updated_at } =
+            service.update_tags(id, vec![1], Some(read)).await.unwrap()
+        else {
+            panic!("expected the first write to succeed");
+        };
+        assert_eq!(version_of(&service, id).await, tags_updated_at);
+
+        // A second window still holding the old version gets the merged state back
+        match service.update_tags(id, vec![2], Some(read)).await.unwrap() {
+            TagUpdateResultDto::Conflict {
+                tags_updated_at: current,
+                current_tags,
+            } => {
+                assert_eq!(current, tags_updated_at);
+                let ids: Vec<i64> = current_tags.iter().map(|t| t.id).collect();
+                assert_eq!(ids, vec![1]);
+            }
+            other => panic!("expected a conflict: {:?}", other),
+        }
+        assert_eq!(service.item_repo.get_tag_ids(id).await.unwrap(), vec![1]);
+
+        // Retrying against the returned version succeeds
+        assert!(matches!(
+            service
+                .update_tags(id, vec![1, 2], Some(tags_updated_at))
+                .await,
+            Ok(TagUpdateResultDto::Updated { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn batch_tag_reports_conflicts_per_item() {
+        let (dir, service) = setup().await;
+        insert_tags(&dir).await;
+        let a = create_item(&service, "C:\\data\\a.txt").await;
+        let b = create_item(&service, "C:\\data\\b.txt").await;
+        let expected = HashMap::from([
+            ("C:\\data\\a.txt".to_string(), version_of(&service, a).await),
+            ("C:\\data\\b.txt".to_string(), version_of(&service, b).await),
+        ]);
+        service.add_tag(a, 2).await.unwrap();
+
+        let paths = vec![
+            "C:\\data\\a.txt".to_string(),
+            "C:\\data\\b.txt".to_string(),
+            touch(&dir, "c.txt"),
+        ];
+        let result = service
+            .batch_add_tag(paths.clone(), 1, expected.clone())
+            .await
+            .unwrap();
+        assert_eq!(result.success_count, 2);
+        assert_eq!(result.created_count, 1);
+        assert_eq!(result.failed_count, 1);
+        assert_eq!(result.conflicts.len(), 1);
+        assert_eq!(result.conflicts[0].item_id, a);
+        assert_eq!(result.conflicts[0].path, "C:\\data\\a.txt");
+        assert_eq!(
+            result.conflicts[0].tags_updated_at,
+            version_of(&service, a).await
+        );
+        assert_eq!(service.item_repo.get_tag_ids(a).await.unwrap(), vec![2]);
+        assert_eq!(service.item_repo.get_tag_ids(b).await.unwrap(), vec![1]);
+
+        // Without expected versions nothing conflicts
+        let result = service
+            .batch_remove_tag(paths, 1, HashMap::new())
+            .await
+            .unwrap();
+        assert_eq!(result.success_count, 3);
+        assert!(result.conflicts.is_empty());
+        assert!(service.item_repo.get_tag_ids(b).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn batch_tag_reports_skipped_paths() {
+        let (dir, service) = setup().await;
+        insert_tags(&dir).await;
+        create_item(&service, "C:\\data\\a.txt").await;
+
+        let paths = vec![
+            "C:\\data\\a.txt".to_string(),
+            "   ".to_string(),
+            "C:\\data\\..\\secret.txt".to_string(),
+        ];
+        let result = service
+            .batch_add_tag(paths.clone(), 1, HashMap::new())
+            .await
+            .unwrap();
+        assert_eq!(result.success_count, 1);
+        assert_eq!(result.skipped_paths, paths[1..].to_vec());
+
+        // An unregistered path that isn't on disk gets no item
+        let missing = dir.path().join("missing.txt");
+        let missing = missing.to_string_lossy().to_string();
+        let result = service
+            .batch_add_tag(vec![missing.clone()], 1, HashMap::new())
+            .await
+            .unwrap();
+        assert_eq!(result.created_count, 0);
+        assert_eq!(result.skipped_paths, vec![missing.clone()]);
+        assert!(
+            service
+                .get_by_path(&missing, false)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        let mut paths = paths;
+        paths.push("C:\\data\\unregistered.txt".to_string());
+        let result = service
+            .batch_remove_tag(paths.clone(), 1, HashMap::new())
+            .await
+            .unwrap();
+        assert_eq!(result.success_count, 1);
+        assert_eq!(result.skipped_paths, paths[1..].to_vec());
+    }
+
+    #[tokio::test]
+    async fn batch_tag_reports_progress_per_chunk_and_stops_when_cancelled() {
+        let (dir, service) = setup().await;
+        insert_tags(&dir).await;
+        let paths: Vec<String> = (0..250).map(|i| format!("C:\\data\\{}.txt", i)).collect();
+        for path in &paths {
+            create_item(&service, path).await;
+        }
+
+        let progress = std::sync::Mutex::new(Vec::new());
+        let result = service
+            .batch_add_tag_with_progress(
+                paths.clone(),
+                1,
+                HashMap::new(),
+                &CancellationToken::new(),
+                |processed, total| progress.lock().unwrap().push((processed, total)),
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.success_count, 250);
+        assert!(!result.cancelled);
+        assert_eq!(
+            *progress.lock().unwrap(),
+            vec![(100, 250), (200, 250), (250, 250)]
+        );
+
+        // Cancelling after the first chunk leaves the rest tagged
+        let cancel = CancellationToken::new();
+        let result = service
+            .batch_remove_tag_with_progress(paths, 1, HashMap::new(), &cancel, |_, _| {
+                cancel.cancel()
+            })
+            .await
+            .unwrap();
+        assert_eq!(result.success_count, 100);
+        assert!(result.cancelled);
+        let pool = open_database(&dir.path().join("test.db")).await.unwrap();
+        let conn = pool.get().await.unwrap();
+        let remaining: i64 = conn
+            .interact(|conn: &mut rusqlite::Connection| {
+                conn.query_row(
+                    "SELECT COUNT(*) FROM item_tags WHERE tag_id = 1",
+                    [],
+                    |row| row.get(0),
+                )
+            })
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(remaining, 150);
+    }
+
+    #[tokio::test]
+    async fn tag_directory_tags_every_file_in_the_tree() {
+        let (dir, service) = setup().await;
+        let pool = open_database(&dir.path().join("test.db")).await.unwrap();
+        let conn = pool.get().await.unwrap();
+        conn.interact(|conn: &mut rusqlite::Connection| {
+            conn.execute_batch(
+                "INSERT INTO tag_groups (id, name) VALUES (1, 'Group');
+                 INSERT INTO tags (id, group_id, value) VALUES (1, 1, 'a');",
+            )
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+        let root = dir.path().join("tree");
+        std::fs::create_dir_all(root.join("sub").join("deeper")).unwrap();
+        let files = [
+            root.join("top.txt"),
+            root.join("sub").join("mid.txt"),
+            root.join("sub").join("deeper").join("leaf.txt"),
+        ];
+        for file in &files {
+            std::fs::write(file, "x").unwrap();
+        }
+        let existing = create_item(&service, &files[0].to_string_lossy()).await;
+        let root = root.to_string_lossy().to_string();
+
+        let shallow = service
+            .tag_directory(&root, 1, false, false, &CancellationToken::new())
+            .await
+            .unwrap();
+        assert_eq!(shallow.scanned_count, 1);
+        assert_eq!(shallow.created_count, 0);
+        assert_eq!(shallow.tagged_count, 1);
+
+        let result = service
+            .tag_directory(&root, 1, true, false, &CancellationToken::new())
+            .await
+            .unwrap();
+        assert_eq!(result.scanned_count, 3);
+        assert_eq!(result.created_count, 2);
+        assert_eq!(result.tagged_count, 3);
+        for file in &files {
+            let item = service
+                .get_by_path(&file.to_string_lossy(), false)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(
+                service.item_repo.get_tag_ids(item.id).await.unwrap(),
+                vec![1]
+            );
+        }
+        assert_eq!(
+            service.item_repo.get_tag_ids(existing).await.unwrap(),
+            vec![1]
+        );
+
+        assert!(matches!(
+            service
+                .tag_directory(&root, 99, true, false, &CancellationToken::new())
+                .await,
+            Err(DomainError::TagNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn tag_directory_skips_excluded_entries() {
+        let (dir, service) = setup().await;
+        insert_tags(&dir).await;
+
+        let root = dir.path().join("project");
+        std::fs::create_dir_all(root.join("node_modules").join("pkg")).unwrap();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        for file in [
+            root.join("node_modules").join("pkg").join("index.js"),
+            root.join("src").join("main.rs"),
+            root.join("src").join("scratch.tmp"),
+            root.join("README.md"),
+        ] {
+            std::fs::write(file, "x").unwrap();
+        }
+        let root = root.to_string_lossy().to_string();
+
+        // The built-in list covers node_modules and temp files
+        let result = service
+            .tag_directory(&root, 1, true, false, &CancellationToken::new())
+            .await
+            .unwrap();
+        assert_eq!(result.scanned_count, 2);
+        assert_eq!(result.excluded_count, 2);
+
+        service
+            .settings_service
+            .set(INDEX_EXCLUDE_PATTERNS_KEY, r#"["*.md"]"#)
+            .await
+            .unwrap();
+        let result = service
+            .tag_directory(&root, 1, true, false, &CancellationToken::new())
+            .await
+            .unwrap();
+        assert_eq!(result.scanned_count, 3);
+        assert_eq!(result.excluded_count, 1);
+
+        // A cancelled operation stops before its first batch
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let result = service
+            .tag_directory(&root, 2, true, false, &cancel)
+            .await
+            .unwrap();
+        assert!(result.cancelled);
+        assert_eq!(result.tagged_count, 0);
+    }
+
+    #[tokio::test]
+    async fn check_by_paths_marks_unregistered_paths() {
+        let (_dir, service) = setup().await;
+        let a = create_item(&service, "C:\\data\\a.txt").await;
+        let c = create_item(&service, "C:\\data\\c.txt").await;
+
+        let result = service
+            .check_by_paths(vec![
+                "C:\\data\\c.txt".to_string(),
+                "C:\\data\\b.txt".to_string(),
+                "C:\\data\\a.txt".to_string(),
+            ])
+            .await
+            .unwrap();
+
+        let summary: Vec<(&str, Option<i64>)> = result
+            .iter()
+            .map(|r| (r.path.as_str(), r.item.as_ref().map(|i| i.id)))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("C:\\data\\c.txt", Some(c)),
+                ("C:\\data\\b.txt", None),
+                ("C:\\data\\a.txt", Some(a)),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn check_by_paths_keeps_original_input_path() {
+        let (_dir, service) = setup().await;
+        let id = create_item(&service, "C:\\data\\a.txt").await;
+
+        let result = service
+            .check_by_paths(vec!["  C:\\data\\a.txt ".to_string()])
+            .await
+            .unwrap();
+
+        assert_eq!(result[0].path, "  C:\\data\\a.txt ");
+        assert_eq!(result[0].item.as_ref().map(|i| i.id), Some(id));
+    }
+
+    #[tokio::test]
+    async fn rebase_paths_rewrites_prefix() {
+        let (_dir, service) = setup().await;
+        let a = create_item(&service, "D:\\Old\\a.txt").await;
+        let b = create_item(&service, "D:\\Old\\sub\\b.txt").await;
+        let other = create_item(&service, "D:\\Older\\c.txt").await;
+
+        let count = service
+            .rebase_paths("D:\\Old\\", "E:\\New\\")
+            .await
+            .unwrap();
+
+        assert_eq!(count, 2);
+        assert_eq!(path_of(&service, a).await, "E:\\New\\a.txt");
+        assert_eq!(path_of(&service, b).await, "E:\\New\\sub\\b.txt");
+        assert_eq!(path_of(&service, other).await, "D:\\Older\\c.txt");
+    }
+
+    #[tokio::test]
+    async fn rebase_paths_treats_like_wildcards_literally() {
+        let (_dir, service) = setup().await;
+        create_item(&service, "D:\\a_b\\x.txt").await;
+        create_item(&service, "D:\\axb\\y.txt").await;
+
+        let count = service.rebase_paths("D:\\a_b\\", "D:\\c\\").await.unwrap();
+
+        assert_eq!(count, 1);
+    }
+
+    #[tokio::test]
+    async fn rebase_paths_rejects_collisions_without_changes() {
+        let (_dir, service) = setup().await;
+        let a = create_item(&service, "D:\\Old\\a.txt").await;
+        create_item(&service, "E:\\New\\a.txt").await;
+
+        let result = service.rebase_paths("D:\\Old\\", "E:\\New\\").await;
+
+        assert!(matches!(result, Err(DomainError::DuplicateEntry(_))));
+        assert_eq!(path_of(&service, a).await, "D:\\Old\\a.txt");
+    }
+
+    #[tokio::test]
+    async fn check_by_paths_with_no_registered_items() {
+        let (_dir, service) = setup().await;
+
+        let result = service
+            .check_by_paths(vec!["C:\\missing.txt".to_string()])
+            .await
+            .unwrap();
+
+        assert_eq!(result.len(), 1);
+        assert!(result[0].item.is_none());
+    }
+
+    #[tokio::test]
+    async fn bulk_path_update_renames_drive_root() {
+        let (_dir, service) = setup().await;
+        let a = create_item(&service, "D:\\a.txt").await;
+        let b = create_item(&service, "D:\\sub\\b.txt").await;
+        let other = create_item(&service, "C:\\D:\\c.txt").await;
+
+        let result = service
+            .bulk_path_update("D:\\".to_string(), "E:\\".to_string())
+            .await
+            .unwrap();
+
+        assert_eq!(result.updated_count, 2);
+        assert_eq!(path_of(&service, a).await, "E:\\a.txt");
+        assert_eq!(path_of(&service, b).await, "E:\\sub\\b.txt");
+        assert_eq!(path_of(&service, other).await, "C:\\D:\\c.txt");
+    }
+
+    #[tokio::test]
+    async fn bulk_path_update_renames_subfolder_and_folder_item() {
+        let (_dir, service) = setup().await;
+        let folder = create_item(&service, "D:\\Photos\\2023").await;
+        let child = create_item(&service, "D:\\Photos\\2023\\beach.jpg").await;
+
+        let result = service
+            .bulk_path_update(
+                "D:\\Photos\\2023".to_string(),
+                "D:\\Photos\\2023 Trips\\".to_string(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(result.updated_count, 2);
+        assert_eq!(path_of(&service, folder).await, "D:\\Photos\\2023 Trips");
+        assert_eq!(
+            path_of(&service, child).await,
+            "D:\\Photos\\2023 Trips\\beach.jpg"
+        );
+    }
+
+    #[tokio::test]
+    async fn bulk_path_update_ignores_partial_component_matches() {
+        let (_dir, service) = setup().await;
+        let inside = create_item(&service, "D:\\Photo\\a.jpg").await;
+        let sibling = create_item(&service, "D:\\Photos\\b.jpg").await;
+        let file = create_item(&service, "D:\\Photo.txt").await;
+
+        let result = service
+            .bulk_path_update("D:\\Photo".to_string(), "D:\\Pictures".to_string())
+            .await
+            .unwrap();
+
+        assert_eq!(result.updated_count, 1);
+        assert_eq!(path_of(&service, inside).await, "D:\\Pictures\\a.jpg");
+        assert_eq!(path_of(&service, sibling).await, "D:\\Photos\\b.jpg");
+        assert_eq!(path_of(&service, file).await, "D:\\Photo.txt");
+    }
+
+    #[tokio::test]
+    async fn create_many_inserts_only_unregistered_paths() {
+        let (dir, service) = setup().await;
+        let file = dir.path().join("new.txt");
+        std::fs::write(&file, b"hello").unwrap();
+        let file = file.to_string_lossy().to_string();
+        let existing = create_item(&service, "C:\\existing.txt").await;
+
+        let result = service
+            .create_many(vec![
+                file.clone(),
+                "C:\\existing.txt".to_string(),
+                "C:\\missing.txt".to_string(),
+                file.clone(),
+            ])
+            .await
+            .unwrap();
+
+        assert_eq!(result.created_count, 2);
+        assert_eq!(result.skipped_count, 2);
+        assert_eq!(result.item_ids.len(), 4);
+        assert_eq!(result.item_ids[1], existing);
+        assert_eq!(result.item_ids[0], result.item_ids[3]);
+
+        let created = service
+            .get_by_id(result.item_ids[0], false)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(created.size, Some(5));
+        assert!(created.modified_time.is_some());
+        let missing = service
+            .get_by_id(result.item_ids[2], false)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(missing.path, "C:\\missing.txt");
+        assert_eq!(missing.size, None);
+
+        // Validation fails the whole call before anything is inserted
+        assert!(
+            service
+                .create_many(vec!["C:\\other.txt".to_string(), "  ".to_string()])
+                .await
+                .is_err()
+        );
+        assert!(
+            service
+                .get_by_path("C:\\other.txt", false)
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn refresh_metadata_stores_changed_size_and_mtime() {
+        let (dir, service) = setup().await;
+        let file = dir.path().join("photos").join("a.jpg");
+        std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+        std::fs::write(&file, b"hello").unwrap();
+        let file = file.to_string_lossy().to_string();
+        // Stored with size 1 and mtime 1, neither matching the file
+        let id = create_item(&service, &file).await;
+        create_item(&service, "C:\\gone\\missing.jpg").await;
+
+        let result = service
+            .refresh_metadata(None, &CancellationToken::new())
+            .await
+            .unwrap();
+        assert_eq!((result.checked, result.updated, result.missing), (1, 1, 1));
+        let item = service.get_by_id(id, false).await.unwrap().unwrap();
+        assert_eq!(item.size, Some(5));
+        assert!(item.modified_time.unwrap() > 1);
+
+        // Unchanged files are not rewritten
+        let result = service
+            .refresh_metadata(None, &CancellationToken::new())
+            .await
+            .unwrap();
+        assert_eq!(result.updated, 0);
+
+        // A sibling sharing the directory's name as a prefix is not under it
+        let sibling = dir.path().join("photos2").join("b.jpg");
+        std::fs::create_dir_all(sibling.parent().unwrap()).unwrap();
+        std::fs::write(&sibling, b"hi").unwrap();
+        create_item(&service, &sibling.to_string_lossy()).await;
+
+        std::fs::write(&file, b"hello world").unwrap();
+        let prefix = dir.path().join("photos").to_string_lossy().to_string();
+        let result = service
+            .refresh_metadata(Some(prefix), &CancellationToken::new())
+            .await
+            .unwrap();
+        assert_eq!((result.checked, result.updated, result.missing), (1, 1, 0));
+        assert_eq!(
+            service.get_by_id(id, false).await.unwrap().unwrap().size,
+            Some(11)
+        );
+    }
+
+    #[tokio::test]
+    async fn cancelled_metadata_refresh_stops_before_the_next_item() {
+        let (dir, service) = setup().await;
+        let file = dir.path().join("a.jpg");
+        std::fs::write(&file, b"hello").unwrap();
+        let id = create_item(&service, &file.to_string_lossy()).await;
+
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let result = service.refresh_metadata(None, &cancel).await.unwrap();
+
+        assert!(result.cancelled);
+        assert_eq!((result.checked, result.updated, result.missing), (0, 0, 0));
+        assert_eq!(
+            service.get_by_id(id, false).await.unwrap().unwrap().size,
+            Some(1)
+        );
+    }
+}
@@ -2,20 +2,28 @@
 //!
 //! Services that orchestrate domain operations and implement use cases.
 
+mod auto_tag_rule_service;
+mod collection_service;
+mod gallery_export_service;
 mod item_service;
 mod search_service;
 mod settings_service;
+mod similarity_service;
 mod tag_group_service;
 mod tag_service;
 mod tag_template_service;
 mod thumbnail_service;
 mod usn_refresh_service;
 
+pub use auto_tag_rule_service::AutoTagRuleService;
+pub use collection_service::CollectionService;
+pub use gallery_export_service::{GALLERY_THUMBS_DIR, GalleryExportService};
 pub use item_service::ItemService;
 pub use search_service::SearchService;
 pub use settings_service::SettingsService;
+pub use similarity_service::SimilarityService;
 pub use tag_group_service::TagGroupService;
 pub use tag_service::TagService;
 pub use tag_template_service::TagTemplateService;
-pub use thumbnail_service::ThumbnailService;
+pub use thumbnail_service::{CacheStats, THUMB_SIZE_CEILING, ThumbnailService, clamp_thumb_size};
 pub use usn_refresh_service::UsnRefreshService;
@@ -2,106 +2,504 @@
 //!
 //! Orchestrates search operations across items.
 
-use crate::application::dto::{ItemDto, SearchCriteriaDto, SearchHistoryDto};
+use crate::application::dto::{
+    BenchmarkDto, CqlSearchResultDto, GroupBy, GroupedResultsDto, ItemDto, ItemWithTagsDto,
+    LibraryStatsDto, ParsedQuerySpecDto, QueryClauseDto, QuerySpecDto, SearchCriteriaDto,
+    SearchHistoryDto, SearchMode, SearchRequest, SearchResultsDto, SimilarItemDto, TagDto,
+    TimeField, TruncatedResultsDto,
+};
+use crate::application::services::SettingsService;
 use crate::domain::entities::SearchCriteria;
 use crate::domain::errors::DomainError;
-use crate::domain::repositories::SearchHistoryRepository;
+use crate::domain::repositories::{SearchHistoryRepository, TagRepository};
+use crate::domain::search::{ComparisonOp, Expr, Field, Value, parse_cql, value_from_text};
 use crate::infrastructure::persistence::{SqliteSearchHistoryRepository, SqliteSearchRepository};
 use std::sync::Arc;
+use std::time::Duration;
+
+/// Number of sample items returned per group in grouped results.
+const GROUP_SAMPLE_SIZE: usize = 5;
+
+/// Fallback for the `search_history_max_entries` setting.
+const DEFAULT_HISTORY_MAX_ENTRIES: usize = 100;
+
+/// Fallback for the `cql_max_rows` setting.
+const DEFAULT_CQL_MAX_ROWS: u32 = 50_000;
+
+/// Upper bound on `benchmark_cql` iterations, so one call can't hold a
+/// connection indefinitely.
+const MAX_BENCHMARK_ITERATIONS: u32 = 1000;
 
 /// Service for search operations.
 pub struct SearchService {
     search_repo: Arc<SqliteSearchRepository>,
     history_repo: Arc<SqliteSearchHistoryRepository>,
+    tag_repo: Arc<dyn TagRepository>,
+    settings_service: Arc<SettingsService>,
 }
 
 impl SearchService {
     pub fn new(
         search_repo: Arc<SqliteSearchRepository>,
         history_repo: Arc<SqliteSearchHistoryRepository>,
+        tag_repo: Arc<dyn TagRepository>,
+        settings_service: Arc<SettingsService>,
     ) -> Self {
         Self {
             search_repo,
             history_repo,
+            tag_repo,
+            settings_service,
         }
     }
 
-    /// Searches items by tags with AND logic.
-    pub async fn search_by_tags_and(&self, tag_ids: Vec<i64>) -> Result<Vec<ItemDto>, DomainError> {
-        if tag_ids.is_empty() {
-            return Ok(Vec::new());
-        }
-        self.search_repo.search_by_tags_and(tag_ids).await
+    /// Runs a search of any mode and records it in search history.
+    ///
+    /// Empty requests return no results and are not recorded. CQL queries are
+    /// not recorded because history criteria have no representation for them,
+    /// and stop at the `cql_max_rows` setting like `search_cql`.
+    pub async fn search(&self, request: SearchRequest) -> Result<Vec<ItemDto>, DomainError> {
+        Ok(self.search_with_total(request).await?.0)
     }
 
-    /// Searches items by tags with OR logic.
-    pub async fn search_by_tags_or(&self, tag_ids: Vec<i64>) -> Result<Vec<ItemDto>, DomainError> {
-        if tag_ids.is_empty() {
-            return Ok(Vec::new());
+    /// Like `search`, but also returns the number of matches when a CQL query
+    /// went past `cql_max_rows`.
+    async fn search_with_total(
+        &self,
+        request: SearchRequest,
+    ) -> Result<(Vec<ItemDto>, Option<u64>), DomainError> {
+        let mut total = None;
+        let (results, history_criteria) = match request {
+            SearchRequest::Tags {
+                tag_ids,
+                mode,
+                sort,
+                include_tag_count,
+            } => {
+                if tag_ids.is_empty() {
+                    return Ok((Vec::new(), None));
+                }
+                let results = match mode {
+                    SearchMode::And => {
+                        self.search_repo
+                            .search_by_tags_and(tag_ids.clone(), sort, include_tag_count)
+                            .await?
+                    }
+                    SearchMode::Or => {
+                        self.search_repo
+                            .search_by_tags_or(tag_ids.clone(), sort, include_tag_count)
+                            .await?
+                    }
+                };
+                (results, Some(SearchCriteria::new(None, tag_ids, mode)))
+            }
+            SearchRequest::Filename {
+                query,
+                match_mode,
+                sort,
+            } => {
+                let query = query.trim();
+                if query.is_empty() {
+                    return Ok((Vec::new(), None));
+                }
+                let results = self
+                    .search_repo
+                    .search_by_filename(query, match_mode, sort)
+                    .await?;
+                let criteria =
+                    SearchCriteria::new(Some(query.to_string()), Vec::new(), SearchMode::And);
+                (results, Some(criteria))
+            }
+            SearchRequest::FuzzyFilename { query } => {
+                let query = query.trim();
+                if query.is_empty() {
+                    return Ok((Vec::new(), None));
+                }
+                let results = self.search_repo.search_filename_fuzzy(query).await?;
+                let criteria =
+                    SearchCriteria::new(Some(query.to_string()), Vec::new(), SearchMode::And);
+                (results, Some(criteria))
+            }
+            SearchRequest::Combined {
+                tag_ids,
+                mode,
+                filename_query,
+                sort,
+            } => {
+                let has_tags = !tag_ids.is_empty();
+                let has_filename = filename_query
+                    .as_ref()
+                    .map(|q| !q.trim().is_empty())
+                    .unwrap_or(false);
+
+                if !has_tags && !has_filename {
+                    return Ok((Vec::new(), None));
+                }
+
+                let results = self
+                    .search_repo
+                    .search_combined(tag_ids.clone(), mode, filename_query.clone(), sort)
+                    .await?;
+                (
+                    results,
+                    Some(SearchCriteria::new(filename_query, tag_ids, mode)),
+                )
+            }
+            SearchRequest::Cql { query } => {
+                let query = query.trim();
+                if query.is_empty() {
+                    return Ok((Vec::new(), None));
+                }
+                let timeout = self.search_timeout().await;
+                let max_rows = self.cql_max_rows().await;
+                let (results, matched) = self
+                    .search_repo
+                    .search_cql_limited(query, None, timeout, max_rows)
+                    .await?;
+                total = matched;
+                (results, None)
+            }
+        };
+
+        if let Some(criteria) = history_criteria {
+            // History is best-effort; a failed save must not fail the search
+            let max_entries = self.history_max_entries().await;
+            if let Err(e) = self.history_repo.save(criteria, max_entries).await {
+                log::warn!("Failed to save search history: {}", e);
+            }
         }
-        self.search_repo.search_by_tags_or(tag_ids).await
+
+        Ok((results, total))
+    }
+
+    /// Like `search`, but with `include_tags` each item comes back with its tags,
+    /// fetched in one batched query. CQL results cut off at `cql_max_rows` come
+    /// back truncated with the total number of matches.
+    pub async fn search_with_options(
+        &self,
+        request: SearchRequest,
+        include_tags: bool,
+    ) -> Result<SearchResultsDto, DomainError> {
+        let (items, total) = self.search_with_total(request).await?;
+        Ok(match (total, include_tags) {
+            (None, false) => SearchResultsDto::Items(items),
+            (None, true) => SearchResultsDto::ItemsWithTags(self.attach_tags(items).await?),
+            (Some(total_count), false) => SearchResultsDto::Truncated(TruncatedResultsDto {
+                items,
+                truncated: true,
+                total_count,
+            }),
+            (Some(total_count), true) => SearchResultsDto::TruncatedWithTags(TruncatedResultsDto {
+                items: self.attach_tags(items).await?,
+                truncated: true,
+                total_count,
+            }),
+        })
     }
 
-    /// Searches items by filename.
-    pub async fn search_by_filename(&self, query: &str) -> Result<Vec<ItemDto>, DomainError> {
+    /// Searches items using a CQL query string.
+    ///
+    /// With `GroupBy::None` returns the flat result list (with tags when
+    /// `include_tags` is set); otherwise returns groups with counts and a few
+    /// sample items each. Both honor the `search_timeout_secs` setting and can
+    /// be cancelled by `request_id`.
+    pub async fn search_cql(
+        &self,
+        query: &str,
+        group_by: GroupBy,
+        request_id: Option<String>,
+        include_tags: bool,
+    ) -> Result<CqlSearchResultDto, DomainError> {
         let query = query.trim();
-        if query.is_empty() {
-            return Ok(Vec::new());
+
+        if group_by == GroupBy::None {
+            let (items, total) = if query.is_empty() {
+                (Vec::new(), None)
+            } else {
+                let timeout = self.search_timeout().await;
+                let max_rows = self.cql_max_rows().await;
+                self.search_repo
+                    .search_cql_limited(query, request_id, timeout, max_rows)
+                    .await?
+            };
+            return Ok(match (total, include_tags) {
+                (None, false) => CqlSearchResultDto::Items(items),
+                (None, true) => CqlSearchResultDto::ItemsWithTags(self.attach_tags(items).await?),
+                (Some(total_count), false) => CqlSearchResultDto::Truncated(TruncatedResultsDto {
+                    items,
+                    truncated: true,
+                    total_count,
+                }),
+                (Some(total_count), true) => {
+                    CqlSearchResultDto::TruncatedWithTags(TruncatedResultsDto {
+                        items: self.attach_tags(items).await?,
+                        truncated: true,
+                        total_count,
+                    })
+                }
+            });
         }
-        self.search_repo.search_by_filename(query).await
+
+        let groups = if query.is_empty() {
+            Vec::new()
+        } else {
+            let timeout = self.search_timeout().await;
+            self.search_repo
+                .search_cql_grouped(query, group_by, GROUP_SAMPLE_SIZE, request_id, timeout)
+                .await?
+        };
+        Ok(CqlSearchResultDto::Grouped(GroupedResultsDto { groups }))
     }
 
-    /// Combined search with tags and optional filename filter.
-    pub async fn search(&self, criteria: SearchCriteriaDto) -> Result<Vec<ItemDto>, DomainError> {
-        let has_tags = !criteria.tag_ids.is_empty();
-        let has_filename = criteria
-            .filename_query
-            .as_ref()
-            .map(|q| !q.trim().is_empty())
-            .unwrap_or(false);
+    /// Lists the items carrying none of the given tags. Not recorded in history.
+    pub async fn search_items_excluding_tags(
+        &self,
+        exclude_tag_ids: Vec<i64>,
+    ) -> Result<Vec<ItemDto>, DomainError> {
+        self.search_items_mixed(Vec::new(), exclude_tag_ids, SearchMode::And)
+            .await
+    }
+
+    /// Lists items matching `include_tag_ids` under `mode` and carrying none of
+    /// `exclude_tag_ids`. Both lists empty returns no results. Not recorded in
+    /// history, which has no representation for excluded tags.
+    pub async fn search_items_mixed(
+        &self,
+        include_tag_ids: Vec<i64>,
+        exclude_tag_ids: Vec<i64>,
+        mode: SearchMode,
+    ) -> Result<Vec<ItemDto>, DomainError> {
+        let mut include_tag_ids = include_tag_ids;
+        include_tag_ids.sort_unstable();
+        include_tag_ids.dedup();
+        let mut exclude_tag_ids = exclude_tag_ids;
+        exclude_tag_ids.sort_unstable();
+        exclude_tag_ids.dedup();
 
-        if !has_tags && !has_filename {
+        if include_tag_ids.is_empty() && exclude_tag_ids.is_empty() {
             return Ok(Vec::new());
         }
+        self.search_repo
+            .search_excluding_tags(include_tag_ids, exclude_tag_ids, mode)
+            .await
+    }
 
-        // Result of the search
-        let results = self
-            .search_repo
-            .search_combined(
-                criteria.tag_ids.clone(),
-                criteria.mode,
-                criteria.filename_query.clone(),
-            )
-            .await?;
+    /// Lists the items carrying a single tag, ordered by path. Not recorded in history.
+    pub async fn items_by_tag(&self, tag_id: i64) -> Result<Vec<ItemDto>, DomainError> {
+        self.search_repo.items_by_tag(tag_id).await
+    }
 
-        // Save to history (fire and forget approx, but we await it here for simplicity)
-        // Only save if it's a valid search (which we checked above)
-        let history_criteria =
-            SearchCriteria::new(criteria.filename_query, criteria.tag_ids, criteria.mode);
+    /// Lists items modified or registered within the last `window_hours`,
+    /// newest first, optionally limited to items carrying all `tag_ids`.
+    /// Not recorded in history.
+    pub async fn search_by_time_window(
+        &self,
+        window_hours: u32,
+        time_field: TimeField,
+        tag_ids: Vec<i64>,
+    ) -> Result<Vec<ItemDto>, DomainError> {
+        self.search_repo
+            .search_by_time_window(window_hours, time_field, tag_ids)
+            .await
+    }
 
-        if let Err(e) = self.history_repo.save(history_criteria).await {
-            // Log error but don't fail the search?
-            // For now, let's treat it as non-fatal but maybe log to stderr
-            eprintln!("Failed to save search history: {}", e);
+    /// Lists items sharing at least `min_shared` tags (default 1) with the
+    /// given item, most shared first. Not recorded in history.
+    pub async fn similar_items(
+        &self,
+        item_id: i64,
+        min_shared: Option<u32>,
+    ) -> Result<Vec<SimilarItemDto>, DomainError> {
+        let min_shared = min_shared.unwrap_or(1);
+        if min_shared == 0 {
+            return Err(DomainError::ValidationError(
+                "min_shared must be at least 1".to_string(),
+            ));
         }
+        self.search_repo.similar_items(item_id, min_shared).await
+    }
 
-        Ok(results)
+    /// Returns the SQLite query plan for a CQL query, for diagnosing slow searches.
+    pub async fn explain_cql(&self, query: &str) -> Result<Vec<String>, DomainError> {
+        self.search_repo.explain_cql(query.trim()).await
     }
 
-    /// Searches items using a CQL query string.
-    pub async fn search_cql(&self, query: &str) -> Result<Vec<ItemDto>, DomainError> {
+    /// Times `iterations` runs of a CQL query (1 to 1000), for reporting slow
+    /// queries and measuring index changes.
+    pub async fn benchmark_cql(
+        &self,
+        query: &str,
+        iterations: u32,
+    ) -> Result<BenchmarkDto, DomainError> {
+        if !(1..=MAX_BENCHMARK_ITERATIONS).contains(&iterations) {
+            return Err(DomainError::ValidationError(format!(
+                "iterations must be between 1 and {}",
+                MAX_BENCHMARK_ITERATIONS
+            )));
+        }
+        self.search_repo
+            .benchmark_cql(query.trim(), iterations)
+            .await
+    }
+
+    /// Renders a structured query as canonical CQL, quoting and escaping its
+    /// values. The result is parsed back so field/operator combinations are
+    /// checked like a typed query.
+    pub fn build_cql(&self, spec: &QuerySpecDto) -> Result<String, DomainError> {
+        let mut expr: Option<Expr> = None;
+        for clause in &spec.clauses {
+            let right = clause_to_expr(clause)?;
+            expr = Some(match expr {
+                Some(left) => Expr::And(Box::new(left), Box::new(right)),
+                None => right,
+            });
+        }
+        let expr = expr.ok_or_else(|| {
+            DomainError::ValidationError("A query needs at least one clause".to_string())
+        })?;
+
+        let query = expr.to_string();
+        parse_cql(&query).map_err(|e| DomainError::ValidationError(e.to_string()))?;
+        Ok(query)
+    }
+
+    /// Splits a CQL query into the clauses of a [`QuerySpecDto`], when it is
+    /// an AND of comparisons, `IN` lists and their negations.
+    pub fn parse_cql_to_spec(&self, query: &str) -> Result<ParsedQuerySpecDto, DomainError> {
+        let expr = parse_cql(query).map_err(|e| DomainError::ValidationError(e.to_string()))?;
+        let mut conjuncts = Vec::new();
+        flatten_and(expr, &mut conjuncts);
+
+        let clauses: Option<Vec<QueryClauseDto>> =
+            conjuncts.into_iter().map(expr_to_clause).collect();
+        Ok(ParsedQuerySpecDto {
+            not_representable: clauses.is_none(),
+            spec: clauses.map(|clauses| QuerySpecDto { clauses }),
+        })
+    }
+
+    /// Rebuilds all indexes and refreshes planner statistics, e.g. after a large import.
+    pub async fn rebuild_indexes(&self) -> Result<(), DomainError> {
+        self.search_repo.rebuild_indexes().await
+    }
+
+    /// Returns the database schema version.
+    pub async fn schema_version(&self) -> Result<u32, DomainError> {
+        self.search_repo.schema_version().await
+    }
+
+    /// Returns overall library counts and the database size.
+    pub async fn library_stats(&self) -> Result<LibraryStatsDto, DomainError> {
+        self.search_repo.library_stats().await
+    }
+
+    /// Returns a page of items from one group of a grouped CQL search.
+    pub async fn get_group_items(
+        &self,
+        query: &str,
+        group_by: GroupBy,
+        group_key: String,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<ItemDto>, DomainError> {
+        let query = query.trim();
+        if query.is_empty() || limit == 0 {
+            return Ok(Vec::new());
+        }
+        self.search_repo
+            .search_cql_group_items(query, group_by, group_key, offset, limit)
+            .await
+    }
+
+    /// Runs a flat CQL search and returns at most `limit` results with their
+    /// tags, plus the total number of matches. Only the first `limit` rows are
+    /// read. Honors `search_timeout_secs` and is not recorded in history.
+    pub async fn search_cql_with_tags(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> Result<(Vec<ItemWithTagsDto>, usize), DomainError> {
         let query = query.trim();
         if query.is_empty() {
+            return Ok((Vec::new(), 0));
+        }
+        let timeout = self.search_timeout().await;
+        let (items, total) = self
+            .search_repo
+            .search_cql_limited(query, None, timeout, limit)
+            .await?;
+        let matched = total.map_or(items.len(), |total| total as usize);
+        Ok((self.attach_tags(items).await?, matched))
+    }
+
+    /// Pairs each item with its tags using a single batched lookup.
+    async fn attach_tags(&self, items: Vec<ItemDto>) -> Result<Vec<ItemWithTagsDto>, DomainError> {
+        if items.is_empty() {
             return Ok(Vec::new());
         }
-        self.search_repo.search_cql(query).await
+        let ids: Vec<i64> = items.iter().map(|i| i.id).collect();
+        let mut tags_map = self.tag_repo.find_by_items(&ids).await?;
+        Ok(items
+            .into_iter()
+            .map(|item| {
+                let tags = tags_map
+                    .remove(&item.id)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(TagDto::from)
+                    .collect();
+                ItemWithTagsDto { item, tags }
+            })
+            .collect())
+    }
+
+    /// Cancels a running search by request ID. Returns false if none was running.
+    ///
+    /// Searches keep their own registry of SQLite interrupt handles rather
+    /// than using `CancellationRegistry`: a search is a single statement with
+    /// no loop to check a token in, so stopping it means interrupting the
+    /// connection mid-query.
+    pub fn cancel(&self, request_id: &str) -> bool {
+        self.search_repo.cancel(request_id)
     }
 
-    /// Retrieves recent search history.
+    /// Reads the search timeout from settings (default 30s, 0 = no timeout).
+    async fn search_timeout(&self) -> Option<Duration> {
+        let secs = self
+            .settings_service
+            .get_u32("search_timeout_secs", 30)
+            .await;
+        (secs > 0).then(|| Duration::from_secs(secs.into()))
+    }
+
+    /// Reads the flat CQL result ceiling from settings (default 50000).
+    async fn cql_max_rows(&self) -> usize {
+        self.settings_service
+            .get_u32("cql_max_rows", DEFAULT_CQL_MAX_ROWS)
+            .await
+            .max(1) as usize
+    }
+
+    /// Reads the history size cap from settings (default 100).
+    async fn history_max_entries(&self) -> usize {
+        self.settings_service
+            .get_u32(
+                "search_history_max_entries",
+                DEFAULT_HISTORY_MAX_ENTRIES as u32,
+            )
+            .await as usize
+    }
+
+    /// Retrieves recent search history, up to `limit` entries or the configured
+    /// maximum when no limit is given.
     pub async fn get_recent_history(
         &self,
-        limit: usize,
+        limit: Option<usize>,
     ) -> Result<Vec<SearchHistoryDto>, DomainError> {
+        let limit = match limit {
+            Some(limit) => limit,
+            None => self.history_max_entries().await,
+        };
         let histories = self.history_repo.get_recent(limit).await?;
 
         let dtos = histories
@@ -129,4 +527,439 @@ impl SearchService {
     pub async fn clear_history(&self) -> Result<(), DomainError> {
         self.history_repo.clear_all().await
     }
+
+    /// Counts the stored search history entries.
+    pub async fn history_count(&self) -> Result<u64, DomainError> {
+        self.history_repo.count().await
+    }
+
+    /// Stores a new history size cap and prunes down to it right away.
+    /// Returns the number of entries removed.
+    pub async fn set_history_limit(&self, limit: u32) -> Result<u64, DomainError> {
+        if limit == 0 {
+            return Err(DomainError::ValidationError(
+                "Search history limit must be at least 1".to_string(),
+            ));
+        }
+        self.settings_service
+            .set("search_history_max_entries", &limit.to_string())
+            .await?;
+        self.history_repo.prune(limit as usize).await
+    }
+}
+
+fn clause_to_expr(clause: &QueryClauseDto) -> Result<Expr, DomainError> {
+    let field = Field::from_str(&clause.field)
+        .ok_or_else(|| DomainError::ValidationError(format!("Unknown field: {}", clause.field)))?;
+    let values = clause
+        .values
+        .iter()
+        .map(|text| value_from_text(field, text))
+        .collect::<Result<Vec<Value>, _>>()
+        .map_err(|e| DomainError::ValidationError(e.to_string()))?;
+
+    let expr = if clause.op.eq_ignore_ascii_case("in") {
+        if values.is_empty() {
+            return Err(DomainError::ValidationError(
+                "IN needs at least one value".to_string(),
+            ));
+        }
+        Expr::InExpr { field, values }
+    } else {
+        let op = ComparisonOp::from_str(&clause.op).ok_or_else(|| {
+            DomainError::ValidationError(format!("Unknown operator: {}", clause.op))
+        })?;
+        let [value] = <[Value; 1]>::try_from(values).map_err(|_| {
+            DomainError::ValidationError(format!("Operator {} takes one value", clause.op))
+        })?;
+        Expr::Comparison { field, op, value }
+    };
+
+    Ok(if clause.negated {
+        Expr::Not(Box::new(expr))
+    } else {
+        expr
+    })
+}
+
+fn flatten_and(expr: Expr, conjuncts: &mut Vec<Expr>) {
+    match expr {
+        Expr::And(left, right) => {
+            flatten_and(*left, conjuncts);
+            flatten_and(*right, conjuncts);
+        }
+        other => conjuncts.push(other),
+    }
+}
+
+/// `None` for anything but a possibly negated comparison or `IN` list, and
+/// for values the builder would read back differently, such as a number
+/// compared with a tag.
+fn expr_to_clause(expr: Expr) -> Option<QueryClauseDto> {
+    let (negated, expr) = match expr {
+        Expr::Not(inner) => (true, *inner),
+        other => (false, other),
+    };
+    let (field, op, values) = match expr {
+        Expr::Comparison { field, op, value } => (field, op.as_str().to_string(), vec![value]),
+        Expr::InExpr { field, values } => (field, "in".to_string(), values),
+        _ => return None,
+    };
+
+    let texts: Vec<String> = values.iter().map(Value::to_text).collect();
+    let reads_back = texts
+        .iter()
+        .zip(&values)
+        .all(|(text, value)| value_from_text(field, text).ok().as_ref() == Some(value));
+    reads_back.then(|| QueryClauseDto {
+        field: field.as_str().to_string(),
+        op,
+        values: texts,
+        negated,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::dto::{FilenameMatchMode, ItemSort};
+    use crate::infrastructure::persistence::{
+        SqliteSettingsRepository, SqliteTagRepository, open_database,
+    };
+    use rusqlite::Connection;
+
+    async fn setup() -> (tempfile::TempDir, SearchService) {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = Arc::new(open_database(&dir.path().join("test.db")).await.unwrap());
+        let conn = pool.get().await.unwrap();
+        conn.interact(|conn: &mut Connection| {
+            conn.execute_batch(
+                "INSERT INTO tag_groups (id, name) VALUES (1, 'Group');
+                 INSERT INTO tags (id, group_id, value) VALUES (1, 1, 'red'), (2, 1, 'blue');
+                 INSERT INTO items (id, path, is_directory) VALUES
+                    (1, 'C:\\photos\\sunset.jpg', 0),
+                    (2, 'C:\\photos\\beach.jpg', 0),
+                    (3, 'C:\\docs\\notes.txt', 0);
+                 INSERT INTO item_tags (item_id, tag_id) VALUES (1, 1), (1, 2), (2, 1), (3, 2);",
+            )
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+        let settings_service = Arc::new(SettingsService::new(Arc::new(
+            SqliteSettingsRepository::new(pool.clone()),
+        )));
+        let service = SearchService::new(
+            Arc::new(SqliteSearchRepository::new(pool.clone())),
+            Arc::new(SqliteSearchHistoryRepository::new(pool.clone())),
+            Arc::new(SqliteTagRepository::new(pool)),
+            settings_service,
+        );
+        (dir, service)
+    }
+
+    fn ids(items: &[ItemDto]) -> Vec<i64> {
+        items.iter().map(|i| i.id).collect()
+    }
+
+    #[tokio::test]
+    async fn tags_request_dispatches_by_mode_and_records_history() {
+        let (_dir, service) = setup().await;
+
+        let and = service
+            .search(SearchRequest::Tags {
+                tag_ids: vec![2, 1],
+                mode: SearchMode::And,
+                sort: ItemSort::default(),
+                include_tag_count: false,
+            })
+            .await
+            .unwrap();
+        assert_eq!(ids(&and), vec![1]);
+
+        let or = service
+            .search(SearchRequest::Tags {
+                tag_ids: vec![1, 2],
+                mode: SearchMode::Or,
+                sort: ItemSort::default(),
+                include_tag_count: false,
+            })
+            .await
+            .unwrap();
+        assert_eq!(ids(&or), vec![3, 2, 1]);
+
+        let history = service.get_recent_history(Some(10)).await.unwrap();
+        assert_eq!(history.len(), 2);
+        assert!(history.iter().all(|h| h.criteria.tag_ids == vec![1, 2]));
+    }
+
+    #[tokio::test]
+    async fn filename_request_trims_query_and_records_history() {
+        let (_dir, service) = setup().await;
+
+        let items = service
+            .search(SearchRequest::Filename {
+                query: "  beach ".to_string(),
+                match_mode: FilenameMatchMode::Contains,
+                sort: ItemSort::default(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(ids(&items), vec![2]);
+
+        let history = service.get_recent_history(Some(10)).await.unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].criteria.filename_query.as_deref(), Some("beach"));
+        assert!(history[0].criteria.tag_ids.is_empty());
+    }
+
+    #[tokio::test]
+    async fn combined_request_filters_tags_by_filename() {
+        let (_dir, service) = setup().await;
+
+        let items = service
+            .search(SearchRequest::Combined {
+                tag_ids: vec![1],
+                mode: SearchMode::And,
+                filename_query: Some("sun".to_string()),
+                sort: ItemSort::default(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(ids(&items), vec![1]);
+
+        let history = service.get_recent_history(Some(10)).await.unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].criteria.tag_ids, vec![1]);
+        assert_eq!(history[0].criteria.filename_query.as_deref(), Some("sun"));
+    }
+
+    #[tokio::test]
+    async fn include_tags_attaches_each_items_tags() {
+        let (_dir, service) = setup().await;
+
+        let request = SearchRequest::Combined {
+            tag_ids: vec![1],
+            mode: SearchMode::And,
+            filename_query: Some("photos".to_string()),
+            sort: ItemSort::default(),
+        };
+        let SearchResultsDto::ItemsWithTags(results) =
+            service.search_with_options(request, true).await.unwrap()
+        else {
+            panic!("expected items with tags");
+        };
+        let tag_values: Vec<(i64, Vec<String>)> = results
+            .iter()
+            .map(|r| {
+                let mut values: Vec<String> = r.tags.iter().map(|t| t.value.clone()).collect();
+                values.sort();
+                (r.item.id, values)
+            })
+            .collect();
+        assert_eq!(
+            tag_values,
+            vec![
+                (2, vec!["red".to_string()]),
+                (1, vec!["blue".to_string(), "red".to_string()]),
+            ]
+        );
+
+        let CqlSearchResultDto::ItemsWithTags(results) = service
+            .search_cql("tag = \"blue\"", GroupBy::None, None, true)
+            .await
+            .unwrap()
+        else {
+            panic!("expected items with tags");
+        };
+        assert_eq!(results.len(), 2);
+
+        let lean = service
+            .search_cql("tag = \"blue\"", GroupBy::None, None, false)
+            .await
+            .unwrap();
+        assert!(matches!(lean, CqlSearchResultDto::Items(items) if items.len() == 2));
+    }
+
+    #[tokio::test]
+    async fn flat_cql_results_stop_at_the_row_ceiling() {
+        let (_dir, service) = setup().await;
+        service
+            .settings_service
+            .set("cql_max_rows", "1")
+            .await
+            .unwrap();
+
+        let result = service
+            .search_cql("tag = \"red\"", GroupBy::None, None, false)
+            .await
+            .unwrap();
+        let CqlSearchResultDto::Truncated(truncated) = result else {
+            panic!("expected truncated results");
+        };
+        assert_eq!(ids(&truncated.items), vec![2]);
+        assert!(truncated.truncated);
+        assert_eq!(truncated.total_count, 2);
+
+        let within = service
+            .search_cql(
+                "tag = \"blue\" AND tag = \"red\"",
+                GroupBy::None,
+                None,
+                false,
+            )
+            .await
+            .unwrap();
+        assert!(matches!(within, CqlSearchResultDto::Items(items) if items.len() == 1));
+
+        let request = SearchRequest::Cql {
+            query: "tag = \"red\"".to_string(),
+        };
+        let SearchResultsDto::Truncated(truncated) =
+            service.search_with_options(request, false).await.unwrap()
+        else {
+            panic!("expected truncated results");
+        };
+        assert_eq!(ids(&truncated.items), vec![2]);
+        assert_eq!(truncated.total_count, 2);
+    }
+
+    #[tokio::test]
+    async fn cql_with_tags_reads_up_to_the_limit_and_counts_all_matches() {
+        let (_dir, service) = setup().await;
+
+        let (items, matched) = service
+            .search_cql_with_tags("tag = \"red\"", 1)
+            .await
+            .unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].item.id, 2);
+        assert_eq!(matched, 2);
+
+        let (items, matched) = service
+            .search_cql_with_tags("tag = \"red\"", 10)
+            .await
+            .unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(matched, 2);
+    }
+
+    #[tokio::test]
+    async fn cql_request_is_not_recorded() {
+        let (_dir, service) = setup().await;
+
+        let items = service
+            .search(SearchRequest::Cql {
+                query: "tag = \"blue\"".to_string(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(ids(&items), vec![3, 1]);
+
+        assert!(
+            service
+                .get_recent_history(Some(10))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn empty_requests_return_nothing_and_are_not_recorded() {
+        let (_dir, service) = setup().await;
+
+        let requests = vec![
+            SearchRequest::Tags {
+                tag_ids: Vec::new(),
+                mode: SearchMode::And,
+                sort: ItemSort::default(),
+                include_tag_count: false,
+            },
+            SearchRequest::Filename {
+                query: "   ".to_string(),
+                match_mode: FilenameMatchMode::default(),
+                sort: ItemSort::default(),
+            },
+            SearchRequest::Combined {
+                tag_ids: Vec::new(),
+                mode: SearchMode::Or,
+                filename_query: None,
+                sort: ItemSort::default(),
+            },
+            SearchRequest::Cql {
+                query: String::new(),
+            },
+        ];
+
+        for request in requests {
+            assert!(service.search(request).await.unwrap().is_empty());
+        }
+        assert!(
+            service
+                .get_recent_history(Some(10))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    fn clause(field: &str, op: &str, values: &[&str], negated: bool) -> QueryClauseDto {
+        QueryClauseDto {
+            field: field.to_string(),
+            op: op.to_string(),
+            values: values.iter().map(|v| v.to_string()).collect(),
+            negated,
+        }
+    }
+
+    #[tokio::test]
+    async fn query_specs_round_trip_through_cql() {
+        let (_dir, service) = setup().await;
+        let spec = QuerySpecDto {
+            clauses: vec![
+                clause("tag", "=", &[r#"12" vinyl"#], false),
+                clause("name", "~", &["C:\\music\\*"], false),
+                clause("tag", "in", &["jazz", "blues\tsoul"], true),
+                clause("size", ">=", &["10MB"], false),
+                clause("modified", "<", &["2024-03-01"], false),
+                clause("rating", ">", &["3"], false),
+            ],
+        };
+
+        let query = service.build_cql(&spec).unwrap();
+        let mut expected: Option<Expr> = None;
+        for clause in &spec.clauses {
+            let right = clause_to_expr(clause).unwrap();
+            expected = Some(match expected {
+                Some(left) => Expr::And(Box::new(left), Box::new(right)),
+                None => right,
+            });
+        }
+        assert_eq!(parse_cql(&query).unwrap(), expected.unwrap());
+
+        let parsed = service.parse_cql_to_spec(&query).unwrap();
+        assert!(!parsed.not_representable);
+        assert_eq!(parsed.spec.unwrap(), spec);
+
+        // Field/operator combinations are checked like a typed query
+        let invalid = QuerySpecDto {
+            clauses: vec![clause("size", "~", &["1KB"], false)],
+        };
+        assert!(service.build_cql(&invalid).is_err());
+    }
+
+    #[tokio::test]
+    async fn queries_beyond_a_flat_and_are_not_representable() {
+        let (_dir, service) = setup().await;
+        for query in [
+            r#"tag = "a" OR tag = "b""#,
+            r#"tag = "a" AND NOT (tag = "b" AND tag = "c")"#,
+        ] {
+            let parsed = service.parse_cql_to_spec(query).unwrap();
+            assert!(parsed.not_representable, "{}", query);
+            assert!(parsed.spec.is_none());
+        }
+    }
 }
@@ -2,10 +2,15 @@
 //!
 //! Orchestrates settings-related operations with default value fallback.
 
-use crate::domain::entities::SettingsDefaults;
+use crate::application::dto::SettingDto;
+use crate::domain::entities::{
+    ONBOARDING_SEEDED_KEY, SettingCategory, SettingsDefaults, TAG_SUGGESTIONS_PREFIX,
+};
 use crate::domain::errors::DomainError;
 use crate::domain::repositories::SettingsRepository;
 use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
 use std::sync::Arc;
 
 /// Service for settings operations.
@@ -24,6 +29,55 @@ impl SettingsService {
         Ok(stored.or_else(|| SettingsDefaults::get(key).map(|s| s.to_string())))
     }
 
+    /// Gets a boolean setting (`true` or `false`).
+    ///
+    /// Like the other typed getters, returns `default` when the key is
+    /// unknown, the value does not parse, or the database cannot be read.
+    pub async fn get_bool(&self, key: &str, default: bool) -> bool {
+        self.get_parsed(key, default).await
+    }
+
+    /// Gets an unsigned integer setting.
+    pub async fn get_u32(&self, key: &str, default: u32) -> u32 {
+        self.get_parsed(key, default).await
+    }
+
+    /// Gets a signed integer setting.
+    #[allow(dead_code)]
+    pub async fn get_i64(&self, key: &str, default: i64) -> i64 {
+        self.get_parsed(key, default).await
+    }
+
+    /// Gets a string setting.
+    pub async fn get_string(&self, key: &str, default: &str) -> String {
+        self.get(key)
+            .await
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| default.to_string())
+    }
+
+    /// Gets a setting stored as a JSON list of strings. A malformed value is
+    /// logged and read as an empty list.
+    pub async fn get_string_list(&self, key: &str) -> Vec<String> {
+        match self.get(key).await.ok().flatten() {
+            Some(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                log::warn!("Ignoring malformed {}: {}", key, e);
+                Vec::new()
+            }),
+            None => Vec::new(),
+        }
+    }
+
+    async fn get_parsed<T: FromStr>(&self, key: &str, default: T) -> T {
+        self.get(key)
+            .await
+            .ok()
+            .flatten()
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(default)
+    }
+
     /// Gets all settings, merging stored values with defaults.
     pub async fn get_all(&self) -> Result<HashMap<String, String>, DomainError> {
         let mut settings = SettingsDefaults::all();
@@ -34,13 +88,188 @@ impl SettingsService {
         Ok(settings)
     }
 
+    /// Gets all settings with their category and whether they are at default.
+    pub async fn get_all_detailed(&self) -> Result<HashMap<String, SettingDto>, DomainError> {
+        Ok(self
+            .get_all()
+            .await?
+            .into_iter()
+            .map(|(key, value)| {
+                let is_default = SettingsDefaults::get(&key) == Some(value.as_str());
+                let category = SettingsDefaults::category(&key).map(|c| c.as_str().to_string());
+                let dto = SettingDto {
+                    value,
+                    category,
+                    is_default,
+                };
+                (key, dto)
+            })
+            .collect())
+    }
+
     /// Sets a setting value.
     pub async fn set(&self, key: &str, value: &str) -> Result<(), DomainError> {
         self.repo.set(key, value).await
     }
 
+    /// Sets a boolean setting.
+    #[allow(dead_code)]
+    pub async fn set_bool(&self, key: &str, value: bool) -> Result<(), DomainError> {
+        self.set_display(key, value).await
+    }
+
+    /// Sets an unsigned integer setting.
+    #[allow(dead_code)]
+    pub async fn set_u32(&self, key: &str, value: u32) -> Result<(), DomainError> {
+        self.set_display(key, value).await
+    }
+
+    /// Sets a signed integer setting.
+    #[allow(dead_code)]
+    pub async fn set_i64(&self, key: &str, value: i64) -> Result<(), DomainError> {
+        self.set_display(key, value).await
+    }
+
+    async fn set_display(&self, key: &str, value: impl Display) -> Result<(), DomainError> {
+        self.repo.set(key, &value.to_string()).await
+    }
+
     /// Resets a setting to its default by removing the stored value.
     pub async fn reset(&self, key: &str) -> Result<(), DomainError> {
         self.repo.delete(key).await
     }
+
+    /// Removes the stored overrides in `category`, or every override when
+    /// `None`, in one transaction. Returns the keys that were reset.
+    ///
+    /// `thumbnail_cache_dir` is left alone: changing it moves the cache files,
+    /// which only `ThumbnailService::relocate_cache` does. The onboarding flag
+    /// and per-group tag suggestions are kept too; `reset_onboarding` and
+    /// `TagService::set_suggestions` clear those.
+    pub async fn reset_category(
+        &self,
+        category: Option<SettingCategory>,
+    ) -> Result<Vec<String>, DomainError> {
+        let mut keys: Vec<String> = self
+            .repo
+            .get_all()
+            .await?
+            .into_iter()
+            .map(|(key, _)| key)
+            .filter(|key| key != "thumbnail_cache_dir" && key != ONBOARDING_SEEDED_KEY)
+            .filter(|key| !key.starts_with(TAG_SUGGESTIONS_PREFIX))
+            .filter(|key| category.is_none() || SettingsDefaults::category(key) == category)
+            .collect();
+        keys.sort();
+        self.repo.delete_many(&keys).await?;
+        Ok(keys)
+    }
+
+    /// Re-runs first-run seeding. Returns whether starter tag groups were
+    /// created, which only happens when no tag groups exist.
+    pub async fn reset_onboarding(&self) -> Result<bool, DomainError> {
+        self.repo.reset_onboarding().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::persistence::{SqliteSettingsRepository, open_database};
+
+    async fn setup() -> (tempfile::TempDir, SettingsService) {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = open_database(&dir.path().join("test.db")).await.unwrap();
+        let service = SettingsService::new(Arc::new(SqliteSettingsRepository::new(Arc::new(pool))));
+        (dir, service)
+    }
+
+    #[tokio::test]
+    async fn typed_getters_parse_values_and_fall_back_to_defaults() {
+        let (_dir, service) = setup().await;
+
+        // Missing keys
+        assert!(service.get_bool("missing_bool", true).await);
+        assert_eq!(service.get_u32("missing_u32", 7).await, 7);
+        assert_eq!(service.get_i64("missing_i64", -7).await, -7);
+        assert_eq!(service.get_string("missing_string", "x").await, "x");
+        // Keys with a built-in default ignore the caller's default
+        assert!(!service.get_bool("usn_auto_refresh", true).await);
+
+        // Valid values
+        service.set_bool("b", true).await.unwrap();
+        service.set_u32("u", 42).await.unwrap();
+        service.set_i64("i", -42).await.unwrap();
+        service.set("s", "hello").await.unwrap();
+        assert!(service.get_bool("b", false).await);
+        assert_eq!(service.get_u32("u", 0).await, 42);
+        assert_eq!(service.get_i64("i", 0).await, -42);
+        assert_eq!(service.get_string("s", "x").await, "hello");
+        assert_eq!(service.get("u").await.unwrap().as_deref(), Some("42"));
+
+        // Invalid values
+        service.set("b", "yes").await.unwrap();
+        service.set("u", "-1").await.unwrap();
+        service.set("i", "1.5").await.unwrap();
+        assert!(!service.get_bool("b", false).await);
+        assert!(service.get_bool("b", true).await);
+        assert_eq!(service.get_u32("u", 3).await, 3);
+        assert_eq!(service.get_i64("i", 4).await, 4);
+    }
+
+    #[tokio::test]
+    async fn detailed_settings_report_category_and_default_state() {
+        let (_dir, service) = setup().await;
+        service.set("thumbnail_size", "128").await.unwrap();
+        service.set("usn_auto_refresh", "false").await.unwrap();
+        service.set("legacy_key", "x").await.unwrap();
+
+        let settings = service.get_all_detailed().await.unwrap();
+        let size = &settings["thumbnail_size"];
+        assert_eq!(size.value, "128");
+        assert_eq!(size.category.as_deref(), Some("thumbnail"));
+        assert!(!size.is_default);
+        // An override equal to the default still counts as default
+        assert!(settings["usn_auto_refresh"].is_default);
+        assert!(settings["search_timeout_secs"].is_default);
+        assert_eq!(
+            settings["tag_group_sort_mode"].category.as_deref(),
+            Some("ui")
+        );
+        assert_eq!(settings["legacy_key"].category, None);
+    }
+
+    #[tokio::test]
+    async fn reset_category_removes_only_that_categorys_overrides() {
+        let (_dir, service) = setup().await;
+        service.set("thumbnail_size", "128").await.unwrap();
+        service.set("thumbnail_cache_max_mb", "100").await.unwrap();
+        service
+            .set("thumbnail_cache_dir", "D:\\thumbs")
+            .await
+            .unwrap();
+        service.set("search_timeout_secs", "5").await.unwrap();
+
+        let reset = service
+            .reset_category(Some(SettingCategory::Thumbnail))
+            .await
+            .unwrap();
+        assert_eq!(reset, vec!["thumbnail_cache_max_mb", "thumbnail_size"]);
+        assert_eq!(
+            service.get("thumbnail_size").await.unwrap().as_deref(),
+            Some("256")
+        );
+        assert_eq!(
+            service.get("search_timeout_secs").await.unwrap().as_deref(),
+            Some("5")
+        );
+        assert_eq!(
+            service.get("thumbnail_cache_dir").await.unwrap().as_deref(),
+            Some("D:\\thumbs")
+        );
+
+        let reset = service.reset_category(None).await.unwrap();
+        assert_eq!(reset, vec!["search_timeout_secs"]);
+        assert!(service.reset_category(None).await.unwrap().is_empty());
+    }
 }
@@ -0,0 +1,128 @@
+//! Similarity Application Service
+//!
+//! Recommends items by the tags they share, weighting rare tags above
+//! common ones.
+
+use crate::application::dto::{ItemDto, RecommendationDto};
+use crate::domain::errors::DomainError;
+use crate::domain::repositories::TagRepository;
+use crate::infrastructure::persistence::SqliteSearchRepository;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Service for tag-based item recommendations.
+pub struct SimilarityService {
+    search_repo: Arc<SqliteSearchRepository>,
+    tag_repo: Arc<dyn TagRepository>,
+}
+
+impl SimilarityService {
+    pub fn new(search_repo: Arc<SqliteSearchRepository>, tag_repo: Arc<dyn TagRepository>) -> Self {
+        Self {
+            search_repo,
+            tag_repo,
+        }
+    }
+
+    /// Returns up to `limit` items sharing tags with the item, best first.
+    ///
+    /// Each shared tag adds `1 / n` to a candidate's score, where `n` is the
+    /// number of items carrying the tag, so sharing a rare tag counts for more
+    /// than sharing one that half the library has.
+    pub async fn recommend(
+        &self,
+        item_id: i64,
+        limit: usize,
+    ) -> Result<Vec<RecommendationDto>, DomainError> {
+        let candidates = self.search_repo.co_tagged_items(item_id).await?;
+        if candidates.is_empty() {
+            return Ok(Vec::new());
+        }
+        let frequencies = self.tag_repo.get_usage_counts().await?;
+        Ok(score_candidates(candidates, &frequencies, limit))
+    }
+}
+
+/// Scores candidates by their shared tag ids and keeps the `limit` best,
+/// ordered by score descending, then path.
+fn score_candidates(
+    candidates: Vec<(ItemDto, Vec<i64>)>,
+    frequencies: &HashMap<i64, i64>,
+    limit: usize,
+) -> Vec<RecommendationDto> {
+    let mut scored: Vec<RecommendationDto> = candidates
+        .into_iter()
+        .map(|(item, shared_tag_ids)| {
+            let score = shared_tag_ids
+                .iter()
+                .map(|tag_id| {
+                    let count = frequencies.get(tag_id).copied().unwrap_or(1).max(1);
+                    1.0 / count as f64
+                })
+                .sum();
+            RecommendationDto { item, score }
+        })
+        .collect();
+
+    scored.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.item.path.cmp(&b.item.path))
+    });
+    scored.truncate(limit);
+    scored
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(id: i64, path: &str) -> ItemDto {
+        ItemDto {
+            id,
+            path: path.to_string(),
+            is_directory: false,
+            size: None,
+            modified_time: None,
+            created_at: 0,
+            updated_at: 0,
+            tags_updated_at: None,
+            rating: None,
+            tag_count: None,
+            file_reference_number: None,
+            frn_status: None,
+        }
+    }
+
+    #[test]
+    fn rare_shared_tags_outweigh_common_ones() {
+        // Tag 1 is on 10 items, tag 2 on 5, tag 3 on 2
+        let frequencies = HashMap::from([(1, 10), (2, 5), (3, 2)]);
+        let candidates = vec![
+            (item(2, "C:/common-pair"), vec![1, 2]),
+            (item(3, "C:/rare"), vec![3]),
+            (item(4, "C:/common"), vec![1]),
+        ];
+
+        let ranked = score_candidates(candidates, &frequencies, 10);
+        let ids: Vec<i64> = ranked.iter().map(|r| r.item.id).collect();
+        assert_eq!(ids, vec![3, 2, 4]);
+        assert!((ranked[0].score - 0.5).abs() < 1e-9);
+        assert!((ranked[1].score - 0.3).abs() < 1e-9);
+        assert!((ranked[2].score - 0.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn ties_order_by_path_and_limit_truncates() {
+        let frequencies = HashMap::from([(1, 4)]);
+        let candidates = vec![
+            (item(2, "C:/b"), vec![1]),
+            (item(3, "C:/a"), vec![1]),
+            (item(4, "C:/c"), vec![1]),
+        ];
+
+        let ranked = score_candidates(candidates, &frequencies, 2);
+        let ids: Vec<i64> = ranked.iter().map(|r| r.item.id).collect();
+        assert_eq!(ids, vec![3, 2]);
+    }
+}
@@ -2,8 +2,10 @@
 //!
 //! Orchestrates tag group-related operations.
 
-use crate::application::dto::{CreateTagGroupDto, TagGroupDto, UpdateTagGroupDto};
-use crate::domain::entities::TagGroup;
+use crate::application::dto::{
+    CreateTagGroupDto, ExclusiveViolationDto, TagGroupDto, UpdateTagGroupDto,
+};
+use crate::domain::entities::{TagGroup, TagGroupSortMode};
 use crate::domain::errors::DomainError;
 use crate::domain::repositories::TagGroupRepository;
 use crate::domain::value_objects::Color;
@@ -27,16 +29,20 @@ impl TagGroupService {
         };
 
         // Get current max display_order
-        let groups = self.repo.find_all().await?;
+        let groups = self.repo.find_all(TagGroupSortMode::DisplayOrder).await?;
         let max_order = groups.iter().map(|g| g.display_order()).max().unwrap_or(0);
 
         let mut group = TagGroup::new(dto.name, color, max_order + 1)?;
+        group.set_exclusive(dto.exclusive);
         self.repo.save(&mut group).await
     }
 
-    /// Gets all tag groups.
-    pub async fn get_all(&self) -> Result<Vec<TagGroupDto>, DomainError> {
-        let groups = self.repo.find_all().await?;
+    /// Gets all tag groups in the given order.
+    pub async fn get_all(
+        &self,
+        sort_mode: TagGroupSortMode,
+    ) -> Result<Vec<TagGroupDto>, DomainError> {
+        let groups = self.repo.find_all(sort_mode).await?;
         Ok(groups.into_iter().map(Self::to_dto).collect())
     }
 
@@ -68,9 +74,35 @@ impl TagGroupService {
             group.update_color(color);
         }
 
+        if let Some(exclusive) = dto.exclusive {
+            group.set_exclusive(exclusive);
+        }
+
         self.repo.update(&group).await
     }
 
+    /// Lists items carrying more than one of the group's tags. Switching a
+    /// group to exclusive does not resolve these; each is fixed the next time
+    /// one of the group's tags is added to the item.
+    pub async fn exclusive_violations(
+        &self,
+        group_id: i64,
+    ) -> Result<Vec<ExclusiveViolationDto>, DomainError> {
+        if !self.repo.exists(group_id).await? {
+            return Err(DomainError::TagGroupNotFound(group_id.to_string()));
+        }
+
+        let violations = self.repo.find_exclusive_violations(group_id).await?;
+        Ok(violations
+            .into_iter()
+            .map(|(item_id, path, tag_ids)| ExclusiveViolationDto {
+                item_id,
+                path,
+                tag_ids,
+            })
+            .collect())
+    }
+
     /// Deletes a tag group.
     pub async fn delete(&self, id: i64) -> Result<(), DomainError> {
         self.repo.delete(id).await
@@ -81,12 +113,28 @@ impl TagGroupService {
         self.repo.reorder(orders).await
     }
 
+    /// Renumbers all groups sequentially by display order, then name,
+    /// repairing duplicate or gapped orders. Returns the groups in their new
+    /// order.
+    pub async fn normalize_order(&self) -> Result<Vec<TagGroupDto>, DomainError> {
+        let changed = self.repo.normalize_order().await?;
+        if changed > 0 {
+            log::info!("Renumbered the display order of {} tag groups", changed);
+        }
+        self.get_all(TagGroupSortMode::DisplayOrder).await
+    }
+
     fn to_dto(group: TagGroup) -> TagGroupDto {
         TagGroupDto {
             id: group.id().unwrap_or(0),
             name: group.name().to_string(),
             color: group.color().map(|c| c.to_string()),
+            color_name: group
+                .color()
+                .and_then(|c| c.display_name())
+                .map(str::to_string),
             display_order: group.display_order(),
+            exclusive: group.is_exclusive(),
             created_at: group.created_at().unwrap_or(0),
             updated_at: group.updated_at().unwrap_or(0),
         }
@@ -2,29 +2,51 @@
 //!
 //! Orchestrates tag-related operations.
 
-use crate::application::dto::{CreateTagDto, TagDto, UpdateTagDto};
-use crate::domain::entities::Tag;
+use crate::application::dto::{
+    CreateTagDto, PagedTagsDto, TagCountDto, TagDto, TagPairDto, UpdateTagDto, VocabularyExportDto,
+    VocabularyFormat,
+};
+use crate::application::services::SettingsService;
+use crate::domain::entities::{Tag, TagGroup, TagGroupSortMode, tag_suggestions_key};
 use crate::domain::errors::DomainError;
-use crate::domain::repositories::{TagGroupRepository, TagRepository};
+use crate::domain::repositories::{CollectionRepository, TagGroupRepository, TagRepository};
+use crate::domain::search::rename_tag_value;
 use crate::domain::value_objects::TagValue;
 use std::collections::HashMap;
 use std::sync::Arc;
 
+/// Maximum number of pairs `get_co_occurrence` returns.
+const CO_OCCURRENCE_LIMIT: usize = 500;
+
+/// Setting that makes a tag rename rewrite the collection queries naming the
+/// old value.
+const REWRITE_QUERIES_KEY: &str = "rewrite_queries_on_tag_rename";
+
 /// Service for tag operations.
 pub struct TagService {
     tag_repo: Arc<dyn TagRepository>,
     group_repo: Arc<dyn TagGroupRepository>,
+    collection_repo: Arc<dyn CollectionRepository>,
+    settings_service: Arc<SettingsService>,
 }
 
 impl TagService {
-    pub fn new(tag_repo: Arc<dyn TagRepository>, group_repo: Arc<dyn TagGroupRepository>) -> Self {
+    pub fn new(
+        tag_repo: Arc<dyn TagRepository>,
+        group_repo: Arc<dyn TagGroupRepository>,
+        collection_repo: Arc<dyn CollectionRepository>,
+        settings_service: Arc<SettingsService>,
+    ) -> Self {
         Self {
             tag_repo,
             group_repo,
+            collection_repo,
+            settings_service,
         }
     }
 
-    /// Creates a new tag.
+    /// Creates a new tag. Fails with `DuplicateEntry` if the group already
+    /// has a tag with exactly this value.
     pub async fn create(&self, dto: CreateTagDto) -> Result<i64, DomainError> {
         // Verify group exists
         if !self.group_repo.exists(dto.group_id).await? {
@@ -32,6 +54,17 @@ impl TagService {
         }
 
         let value = TagValue::new(&dto.value)?;
+        if self
+            .tag_repo
+            .find_by_exact_value(dto.group_id, value.as_str())
+            .await?
+            .is_some()
+        {
+            return Err(DomainError::DuplicateEntry(format!(
+                "Tag '{}' already exists in this group",
+                value.as_str()
+            )));
+        }
         let mut tag = Tag::new(dto.group_id, value);
         self.tag_repo.save(&mut tag).await
     }
@@ -48,6 +81,47 @@ impl TagService {
         Ok(tags.into_iter().map(Self::to_dto).collect())
     }
 
+    /// Gets one page of all tags, for lists that load as they scroll.
+    pub async fn get_all_paged(
+        &self,
+        limit: u32,
+        offset: u32,
+    ) -> Result<PagedTagsDto, DomainError> {
+        Self::check_page_limit(limit)?;
+        let (tags, total_count) = self.tag_repo.find_all_paged(limit, offset).await?;
+        Ok(PagedTagsDto {
+            tags: tags.into_iter().map(Self::to_dto).collect(),
+            total_count,
+        })
+    }
+
+    /// Gets one page of a group's tags.
+    pub async fn get_by_group_paged(
+        &self,
+        group_id: i64,
+        limit: u32,
+        offset: u32,
+    ) -> Result<PagedTagsDto, DomainError> {
+        Self::check_page_limit(limit)?;
+        let (tags, total_count) = self
+            .tag_repo
+            .find_by_group_paged(group_id, limit, offset)
+            .await?;
+        Ok(PagedTagsDto {
+            tags: tags.into_iter().map(Self::to_dto).collect(),
+            total_count,
+        })
+    }
+
+    fn check_page_limit(limit: u32) -> Result<(), DomainError> {
+        if limit == 0 {
+            return Err(DomainError::ValidationError(
+                "limit must be at least 1".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
     /// Gets a tag by ID.
     #[allow(dead_code)]
     pub async fn get_by_id(&self, id: i64) -> Result<Option<TagDto>, DomainError> {
@@ -62,6 +136,7 @@ impl TagService {
             .find_by_id(id)
             .await?
             .ok_or_else(|| DomainError::TagNotFound(id.to_string()))?;
+        let old_value = tag.value().as_str().to_string();
 
         if let Some(value) = dto.value {
             let value = TagValue::new(&value)?;
@@ -76,7 +151,49 @@ impl TagService {
             tag.move_to_group(group_id)?;
         }
 
-        self.tag_repo.update(&tag).await
+        self.tag_repo.update(&tag).await?;
+
+        if tag.value().as_str() != old_value {
+            self.rewrite_saved_queries(&old_value, tag.value().as_str())
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Rewrites `tag` values equal to `old` in collection queries to `new`,
+    /// so collections keep matching a renamed tag. Returns the number of
+    /// collections rewritten.
+    ///
+    /// Skipped when `rewrite_queries_on_tag_rename` is off, or when a tag in
+    /// another group still has the value `old`, since the queries match that
+    /// one as well.
+    async fn rewrite_saved_queries(&self, old: &str, new: &str) -> Result<usize, DomainError> {
+        if !self
+            .settings_service
+            .get_bool(REWRITE_QUERIES_KEY, true)
+            .await
+        {
+            return Ok(0);
+        }
+        if !self
+            .tag_repo
+            .find_by_value_across_groups(old)
+            .await?
+            .is_empty()
+        {
+            return Ok(0);
+        }
+
+        let mut rewritten = 0;
+        for mut collection in self.collection_repo.find_all().await? {
+            let Some(query) = rename_tag_value(collection.cql_query(), old, new) else {
+                continue;
+            };
+            collection.update_query(&query)?;
+            self.collection_repo.update(&collection).await?;
+            rewritten += 1;
+        }
+        Ok(rewritten)
     }
 
     /// Merges a source tag into a target tag.
@@ -102,6 +219,92 @@ impl TagService {
         self.tag_repo.delete(source_id).await
     }
 
+    /// Splits a compound tag into several tags (the inverse of `merge`).
+    ///
+    /// Every item carrying the source tag gains all target tags; the source tag is
+    /// deleted when `remove_source` is set. Returns the number of affected items.
+    pub async fn split_tag(
+        &self,
+        tag_id: i64,
+        into_tag_ids: Vec<i64>,
+        remove_source: bool,
+    ) -> Result<usize, DomainError> {
+        self.tag_repo
+            .find_by_id(tag_id)
+            .await?
+            .ok_or_else(|| DomainError::TagNotFound(tag_id.to_string()))?;
+
+        let mut into_tag_ids = into_tag_ids;
+        into_tag_ids.sort_unstable();
+        into_tag_ids.dedup();
+
+        if into_tag_ids.is_empty() {
+            return Err(DomainError::ValidationError(
+                "At least one target tag is required".to_string(),
+            ));
+        }
+        if into_tag_ids.contains(&tag_id) {
+            return Err(DomainError::ValidationError(
+                "A tag cannot be split into itself".to_string(),
+            ));
+        }
+
+        let targets = self.tag_repo.find_by_ids(&into_tag_ids).await?;
+        if let Some(missing) = into_tag_ids
+            .iter()
+            .find(|id| !targets.iter().any(|t| t.id() == Some(**id)))
+        {
+            return Err(DomainError::TagNotFound(missing.to_string()));
+        }
+
+        self.tag_repo
+            .split_into(tag_id, &into_tag_ids, remove_source)
+            .await
+    }
+
+    /// Moves a tag to another group.
+    ///
+    /// If the target group already contains a tag with the same value, fails with
+    /// `DuplicateEntry` unless `merge` is set, in which case the tag's items are
+    /// reassigned to the existing tag and the moved tag is deleted. Returns the ID
+    /// of the tag that ends up in the target group.
+    pub async fn move_to_group(
+        &self,
+        tag_id: i64,
+        target_group_id: i64,
+        merge: bool,
+    ) -> Result<i64, DomainError> {
+        let mut tag = self
+            .tag_repo
+            .find_by_id(tag_id)
+            .await?
+            .ok_or_else(|| DomainError::TagNotFound(tag_id.to_string()))?;
+
+        if tag.group_id() == target_group_id {
+            return Ok(tag_id);
+        }
+
+        if !self.group_repo.exists(target_group_id).await? {
+            return Err(DomainError::TagGroupNotFound(target_group_id.to_string()));
+        }
+        tag.move_to_group(target_group_id)?;
+
+        self.tag_repo
+            .move_to_group(tag_id, target_group_id, merge)
+            .await
+    }
+
+    /// Removes a tag from every item without deleting the tag.
+    /// Returns the number of items it was removed from.
+    pub async fn clear_usage(&self, tag_id: i64) -> Result<usize, DomainError> {
+        self.tag_repo
+            .find_by_id(tag_id)
+            .await?
+            .ok_or_else(|| DomainError::TagNotFound(tag_id.to_string()))?;
+
+        self.tag_repo.clear_usage(tag_id).await
+    }
+
     /// Deletes a tag.
     pub async fn delete(&self, id: i64) -> Result<(), DomainError> {
         self.tag_repo.delete(id).await
@@ -117,11 +320,151 @@ impl TagService {
         Ok(tags.into_iter().map(Self::to_dto).collect())
     }
 
+    /// Returns autocomplete values for a group: its pre-defined suggestions in
+    /// their stored order, then existing tag values not already listed.
+    pub async fn suggestions(&self, group_id: i64) -> Result<Vec<String>, DomainError> {
+        if !self.group_repo.exists(group_id).await? {
+            return Err(DomainError::TagGroupNotFound(group_id.to_string()));
+        }
+
+        let key = tag_suggestions_key(group_id);
+        let mut values: Vec<String> = match self.settings_service.get(&key).await? {
+            Some(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                log::warn!("Ignoring malformed {}: {}", key, e);
+                Vec::new()
+            }),
+            None => Vec::new(),
+        };
+
+        for tag in self.tag_repo.find_by_group(group_id).await? {
+            let value = tag.value().to_string();
+            if !values.contains(&value) {
+                values.push(value);
+            }
+        }
+        Ok(values)
+    }
+
+    /// Replaces the pre-defined suggestions of a group. Each value must be a
+    /// valid tag value; values are trimmed and duplicates dropped. An empty
+    /// list removes the setting. Returns the stored list.
+    pub async fn set_suggestions(
+        &self,
+        group_id: i64,
+        suggestions: Vec<String>,
+    ) -> Result<Vec<String>, DomainError> {
+        if !self.group_repo.exists(group_id).await? {
+            return Err(DomainError::TagGroupNotFound(group_id.to_string()));
+        }
+
+        let mut values: Vec<String> = Vec::with_capacity(suggestions.len());
+        for suggestion in suggestions {
+            let value = TagValue::new(suggestion)?.to_string();
+            if !values.contains(&value) {
+                values.push(value);
+            }
+        }
+
+        let key = tag_suggestions_key(group_id);
+        if values.is_empty() {
+            self.settings_service.reset(&key).await?;
+        } else {
+            let json = serde_json::to_string(&values)
+                .map_err(|e| DomainError::ValidationError(e.to_string()))?;
+            self.settings_service.set(&key, &json).await?;
+        }
+        Ok(values)
+    }
+
     /// Gets usage counts for all tags.
     pub async fn get_usage_counts(&self) -> Result<HashMap<i64, i64>, DomainError> {
         self.tag_repo.get_usage_counts().await
     }
 
+    /// Gets the pairs of tags used together on at least `min_count` items
+    /// (default 1), most frequent first, capped at `CO_OCCURRENCE_LIMIT`.
+    pub async fn get_co_occurrence(
+        &self,
+        min_count: Option<u64>,
+    ) -> Result<Vec<TagPairDto>, DomainError> {
+        let min_count = min_count.unwrap_or(1).max(1);
+        let pairs = self
+            .tag_repo
+            .co_occurrence(min_count, CO_OCCURRENCE_LIMIT)
+            .await?;
+        Ok(pairs
+            .into_iter()
+            .map(|(tag_id_a, tag_id_b, count)| TagPairDto {
+                tag_id_a,
+                tag_id_b,
+                count,
+            })
+            .collect())
+    }
+
+    /// Gets the tags present on any of the given items, with per-tag item counts.
+    pub async fn get_tags_among_items(
+        &self,
+        item_ids: &[i64],
+    ) -> Result<Vec<TagCountDto>, DomainError> {
+        let tags = self.tag_repo.find_tags_among_items(item_ids).await?;
+        Ok(tags
+            .into_iter()
+            .map(|(tag, count)| TagCountDto {
+                tag: Self::to_dto(tag),
+                count,
+            })
+            .collect())
+    }
+
+    /// Renders every tag group (by display order) with its tags and usage
+    /// counts as a Markdown or CSV document.
+    pub async fn render_vocabulary(
+        &self,
+        format: VocabularyFormat,
+    ) -> Result<(String, VocabularyExportDto), DomainError> {
+        let groups = self
+            .group_repo
+            .find_all(TagGroupSortMode::DisplayOrder)
+            .await?;
+        let counts = self.tag_repo.get_usage_counts().await?;
+
+        let mut tags_by_group: HashMap<i64, Vec<(String, i64)>> = HashMap::new();
+        for tag in self.tag_repo.find_all().await? {
+            let count = tag
+                .id()
+                .and_then(|id| counts.get(&id))
+                .copied()
+                .unwrap_or(0);
+            tags_by_group
+                .entry(tag.group_id())
+                .or_default()
+                .push((tag.value().to_string(), count));
+        }
+
+        let vocabulary: Vec<(TagGroup, Vec<(String, i64)>)> = groups
+            .into_iter()
+            .map(|group| {
+                let mut tags = group
+                    .id()
+                    .and_then(|id| tags_by_group.remove(&id))
+                    .unwrap_or_default();
+                tags.sort_by_key(|(value, _)| value.to_lowercase());
+                (group, tags)
+            })
+            .collect();
+
+        let summary = VocabularyExportDto {
+            groups: vocabulary.len(),
+            tags: vocabulary.iter().map(|(_, tags)| tags.len()).sum(),
+        };
+        let document = match format {
+            VocabularyFormat::Markdown => render_markdown(&vocabulary),
+            VocabularyFormat::Csv => render_csv(&vocabulary),
+        };
+        Ok((document, summary))
+    }
+
     fn to_dto(tag: Tag) -> TagDto {
         TagDto {
             id: tag.id().unwrap_or(0),
@@ -132,3 +475,682 @@ impl TagService {
         }
     }
 }
+
+fn render_markdown(vocabulary: &[(TagGroup, Vec<(String, i64)>)]) -> String {
+    let mut out = String::from("# Tag Vocabulary\n");
+    for (group, tags) in vocabulary {
+        out.push_str(&format!("\n## {}\n\n", escape_markdown(group.name())));
+        if let Some(color) = group.color() {
+            out.push_str(&format!("Color: `{}`\n\n", color));
+        }
+        if tags.is_empty() {
+            out.push_str("_No tags_\n");
+            continue;
+        }
+        out.push_str("| Tag | Items |\n| --- | ---: |\n");
+        for (value, count) in tags {
+            out.push_str(&format!("| {} | {} |\n", escape_markdown(value), count));
+        }
+    }
+    out
+}
+
+/// Backslash-escapes characters that Markdown would treat as formatting,
+/// including `|` so values stay inside their table cell.
+fn escape_markdown(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|' | '~'
+        ) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// One row per tag; a group without tags still gets a row with empty tag
+/// columns so it appears in the export.
+fn render_csv(vocabulary: &[(TagGroup, Vec<(String, i64)>)]) -> String {
+    let mut out = String::from("group,color,tag,items\r\n");
+    for (group, tags) in vocabulary {
+        let name = csv_field(group.name());
+        let color = group.color().map(|c| c.to_string()).unwrap_or_default();
+        if tags.is_empty() {
+            out.push_str(&format!("{},{},,\r\n", name, color));
+        }
+        for (value, count) in tags {
+            out.push_str(&format!(
+                "{},{},{},{}\r\n",
+                name,
+                color,
+                csv_field(value),
+                count
+            ));
+        }
+    }
+    out
+}
+
+/// Quotes a CSV field (RFC 4180) when it contains a separator, quote or newline.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::entities::Collection;
+    use crate::infrastructure::persistence::{
+        SqliteCollectionRepository, SqliteSettingsRepository, SqliteTagGroupRepository,
+        SqliteTagRepository, open_database,
+    };
+    use deadpool_sqlite::Pool;
+
+    struct Fixture {
+        _dir: tempfile::TempDir,
+        pool: Arc<Pool>,
+        service: TagService,
+        tag_repo: Arc<dyn TagRepository>,
+        collection_repo: Arc<dyn CollectionRepository>,
+        settings_service: Arc<SettingsService>,
+    }
+
+    async fn setup() -> Fixture {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = Arc::new(open_database(&dir.path().join("test.db")).await.unwrap());
+        let tag_repo: Arc<dyn TagRepository> = Arc::new(SqliteTagRepository::new(pool.clone()));
+        let group_repo: Arc<dyn TagGroupRepository> =
+            Arc::new(SqliteTagGroupRepository::new(pool.clone()));
+        let collection_repo: Arc<dyn CollectionRepository> =
+            Arc::new(SqliteCollectionRepository::new(pool.clone()));
+        let settings_service = Arc::new(SettingsService::new(Arc::new(
+            SqliteSettingsRepository::new(pool.clone()),
+        )));
+        let service = TagService::new(
+            tag_repo.clone(),
+            group_repo,
+            collection_repo.clone(),
+            settings_service.clone(),
+        );
+        Fixture {
+            _dir: dir,
+            pool,
+            service,
+            tag_repo,
+            collection_repo,
+            settings_service,
+        }
+    }
+
+    async fn create_group(fixture: &Fixture, name: &str) -> i64 {
+        let conn = fixture.pool.get().await.unwrap();
+        let name = name.to_string();
+        conn.interact(move |conn| {
+            conn.execute("INSERT INTO tag_groups (name) VALUES (?1)", [name])?;
+            Ok::<i64, rusqlite::Error>(conn.last_insert_rowid())
+        })
+        .await
+        .unwrap()
+        .unwrap()
+    }
+
+    async fn create_tag(fixture: &Fixture, group_id: i64, value: &str) -> i64 {
+        fixture
+            .service
+            .create(CreateTagDto {
+                group_id,
+                value: value.to_string(),
+            })
+            .await
+            .unwrap()
+    }
+
+    async fn tag_item(fixture: &Fixture, path: &str, tag_ids: &[i64]) -> i64 {
+        let conn = fixture.pool.get().await.unwrap();
+        let path = path.to_string();
+        let tag_ids = tag_ids.to_vec();
+        conn.interact(move |conn| {
+            conn.execute(
+                "INSERT INTO items (path, is_directory) VALUES (?1, 0)",
+                [path],
+            )?;
+            let item_id = conn.last_insert_rowid();
+            for tag_id in tag_ids {
+                conn.execute(
+                    "INSERT INTO item_tags (item_id, tag_id) VALUES (?1, ?2)",
+                    [item_id, tag_id],
+                )?;
+            }
+            Ok::<i64, rusqlite::Error>(item_id)
+        })
+        .await
+        .unwrap()
+        .unwrap()
+    }
+
+    async fn save_collection(fixture: &Fixture, query: &str) -> i64 {
+        let mut collection = Collection::new("Saved", query, None, 0).unwrap();
+        fixture.collection_repo.save(&mut collection).await.unwrap()
+    }
+
+    async fn collection_query(fixture: &Fixture, id: i64) -> String {
+        let collection = fixture.collection_repo.find_by_id(id).await.unwrap();
+        collection.unwrap().cql_query().to_string()
+    }
+
+    async fn rename(fixture: &Fixture, id: i64, value: &str) {
+        let dto = UpdateTagDto {
+            value: Some(value.to_string()),
+            group_id: None,
+        };
+        fixture.service.update(id, dto).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn renaming_a_tag_rewrites_collection_queries() {
+        let fixture = setup().await;
+        let colors = create_group(&fixture, "Colors").await;
+        let places = create_group(&fixture, "Places").await;
+        let red = create_tag(&fixture, colors, "red").await;
+        let blue = create_tag(&fixture, colors, "blue").await;
+        create_tag(&fixture, places, "blue").await;
+        let exact = save_collection(&fixture, r#"tag = "red" AND name ~ "red*""#).await;
+        let longer = save_collection(&fixture, r#"tag = "dark red""#).await;
+        let shared = save_collection(&fixture, r#"tag IN ("blue", "red")"#).await;
+
+        rename(&fixture, red, "crimson").await;
+        assert_eq!(
+            collection_query(&fixture, exact).await,
+            r#"tag = "crimson" AND name ~ "red*""#
+        );
+        assert_eq!(
+            collection_query(&fixture, longer).await,
+            r#"tag = "dark red""#
+        );
+
+        // Another group still has a "blue" tag, which the query keeps matching
+        rename(&fixture, blue, "navy").await;
+        assert_eq!(
+            collection_query(&fixture, shared).await,
+            r#"tag IN ("blue", "crimson")"#
+        );
+
+        fixture
+            .settings_service
+            .set(REWRITE_QUERIES_KEY, "false")
+            .await
+            .unwrap();
+        rename(&fixture, red, "scarlet").await;
+        assert_eq!(
+            collection_query(&fixture, exact).await,
+            r#"tag = "crimson" AND name ~ "red*""#
+        );
+    }
+
+    #[tokio::test]
+    async fn paged_tags_cover_every_tag_once() {
+        let fixture = setup().await;
+        let colors = create_group(&fixture, "Colors").await;
+        let places = create_group(&fixture, "Places").await;
+        for i in 0..7 {
+            create_tag(&fixture, colors, &format!("color {}", i)).await;
+        }
+        for i in 0..3 {
+            create_tag(&fixture, places, &format!("place {}", i)).await;
+        }
+        let all: Vec<i64> = fixture
+            .service
+            .get_all()
+            .await
+            .unwrap()
+            .iter()
+            .map(|t| t.id)
+            .collect();
+
+        let mut paged = Vec::new();
+        let mut sizes = Vec::new();
+        for offset in (0..12).step_by(4) {
+            let page = fixture.service.get_all_paged(4, offset).await.unwrap();
+            assert_eq!(page.total_count, 10);
+            sizes.push(page.tags.len());
+            paged.extend(page.tags.iter().map(|t| t.id));
+        }
+        assert_eq!(sizes, vec![4, 4, 2]);
+        assert_eq!(paged, all);
+
+        let page = fixture
+            .service
+            .get_by_group_paged(places, 2, 2)
+            .await
+            .unwrap();
+        assert_eq!(page.total_count, 3);
+        let values: Vec<&str> = page.tags.iter().map(|t| t.value.as_str()).collect();
+        assert_eq!(values, vec!["place 2"]);
+
+        assert!(fixture.service.get_all_paged(0, 0).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn move_to_group_without_collision_keeps_tag_id() {
+        let fixture = setup().await;
+        let source = create_group(&fixture, "Source").await;
+        let target = create_group(&fixture, "Target").await;
+        let tag_id = create_tag(&fixture, source, "red").await;
+
+        let result = fixture
+            .service
+            .move_to_group(tag_id, target, false)
+            .await
+            .unwrap();
+
+        assert_eq!(result, tag_id);
+        let tag = fixture.tag_repo.find_by_id(tag_id).await.unwrap().unwrap();
+        assert_eq!(tag.group_id(), target);
+    }
+
+    #[tokio::test]
+    async fn move_to_group_collision_without_merge_fails_and_rolls_back() {
+        let fixture = setup().await;
+        let source = create_group(&fixture, "Source").await;
+        let target = create_group(&fixture, "Target").await;
+        let tag_id = create_tag(&fixture, source, "red").await;
+        create_tag(&fixture, target, "red").await;
+
+        let result = fixture.service.move_to_group(tag_id, target, false).await;
+
+        assert!(matches!(result, Err(DomainError::DuplicateEntry(_))));
+        let tag = fixture.tag_repo.find_by_id(tag_id).await.unwrap().unwrap();
+        assert_eq!(tag.group_id(), source);
+    }
+
+    #[tokio::test]
+    async fn move_to_group_collision_with_merge_reassigns_items() {
+        let fixture = setup().await;
+        let source = create_group(&fixture, "Source").await;
+        let target = create_group(&fixture, "Target").await;
+        let tag_id = create_tag(&fixture, source, "red").await;
+        let existing_id = create_tag(&fixture, target, "red").await;
+        let only_source = tag_item(&fixture, "C:\\a.txt", &[tag_id]).await;
+        let both = tag_item(&fixture, "C:\\b.txt", &[tag_id, existing_id]).await;
+
+        let result = fixture
+            .service
+            .move_to_group(tag_id, target, true)
+            .await
+            .unwrap();
+
+        assert_eq!(result, existing_id);
+        assert!(fixture.tag_repo.find_by_id(tag_id).await.unwrap().is_none());
+        for item_id in [only_source, both] {
+            let tags = fixture.tag_repo.find_by_item(item_id).await.unwrap();
+            let ids: Vec<i64> = tags.iter().filter_map(|t| t.id()).collect();
+            assert_eq!(ids, vec![existing_id]);
+        }
+    }
+
+    #[tokio::test]
+    async fn clear_usage_removes_associations_but_keeps_tag() {
+        let fixture = setup().await;
+        let group = create_group(&fixture, "Colors").await;
+        let red = create_tag(&fixture, group, "red").await;
+        let blue = create_tag(&fixture, group, "blue").await;
+        let first = tag_item(&fixture, "C:\\a.txt", &[red, blue]).await;
+        let second = tag_item(&fixture, "C:\\b.txt", &[red]).await;
+
+        let removed = fixture.service.clear_usage(red).await.unwrap();
+
+        assert_eq!(removed, 2);
+        assert!(fixture.tag_repo.find_by_id(red).await.unwrap().is_some());
+        let first_tags = fixture.tag_repo.find_by_item(first).await.unwrap();
+        assert_eq!(
+            first_tags.iter().filter_map(|t| t.id()).collect::<Vec<_>>(),
+            vec![blue]
+        );
+        assert!(
+            fixture
+                .tag_repo
+                .find_by_item(second)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(matches!(
+            fixture.service.clear_usage(999).await,
+            Err(DomainError::TagNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn move_to_group_rejects_missing_target_group() {
+        let fixture = setup().await;
+        let source = create_group(&fixture, "Source").await;
+        let tag_id = create_tag(&fixture, source, "red").await;
+
+        let result = fixture.service.move_to_group(tag_id, 999, false).await;
+
+        assert!(matches!(result, Err(DomainError::TagGroupNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn tags_among_items_counts_union_of_selection() {
+        let fixture = setup().await;
+        let group = create_group(&fixture, "Colors").await;
+        let red = create_tag(&fixture, group, "red").await;
+        let blue = create_tag(&fixture, group, "blue").await;
+        let green = create_tag(&fixture, group, "green").await;
+        let a = tag_item(&fixture, "C:\\a.txt", &[red, blue]).await;
+        let b = tag_item(&fixture, "C:\\b.txt", &[red]).await;
+        tag_item(&fixture, "C:\\c.txt", &[green]).await;
+
+        let result = fixture
+            .service
+            .get_tags_among_items(&[a, b, b])
+            .await
+            .unwrap();
+
+        let counts: Vec<(i64, i64)> = result.iter().map(|t| (t.tag.id, t.count)).collect();
+        assert_eq!(counts, vec![(red, 2), (blue, 1)]);
+    }
+
+    #[tokio::test]
+    async fn co_occurrence_counts_pairs_on_active_items() {
+        let fixture = setup().await;
+        let group = create_group(&fixture, "Colors").await;
+        let red = create_tag(&fixture, group, "red").await;
+        let blue = create_tag(&fixture, group, "blue").await;
+        let green = create_tag(&fixture, group, "green").await;
+        tag_item(&fixture, "C:\\a.txt", &[red, blue, green]).await;
+        tag_item(&fixture, "C:\\b.txt", &[red, blue]).await;
+        let deleted = tag_item(&fixture, "C:\\c.txt", &[blue, green]).await;
+        let conn = fixture.pool.get().await.unwrap();
+        conn.interact(move |conn| {
+            conn.execute("UPDATE items SET is_deleted = 1 WHERE id = ?1", [deleted])
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+        let pairs = |min_count| {
+            let service = &fixture.service;
+            async move {
+                service
+                    .get_co_occurrence(min_count)
+                    .await
+                    .unwrap()
+                    .iter()
+                    .map(|p| (p.tag_id_a, p.tag_id_b, p.count))
+                    .collect::<Vec<_>>()
+            }
+        };
+        assert_eq!(
+            pairs(None).await,
+            vec![(red, blue, 2), (red, green, 1), (blue, green, 1)]
+        );
+        assert_eq!(pairs(Some(2)).await, vec![(red, blue, 2)]);
+    }
+
+    #[tokio::test]
+    async fn tags_among_items_empty_selection_returns_nothing() {
+        let fixture = setup().await;
+        let result = fixture.service.get_tags_among_items(&[]).await.unwrap();
+        assert!(result.is_empty());
+    }
+
+    #[tokio::test]
+    async fn split_tag_adds_targets_to_every_item() {
+        let fixture = setup().await;
+        let group = create_group(&fixture, "Topics").await;
+        let compound = create_tag(&fixture, group, "work-2024").await;
+        let work = create_tag(&fixture, group, "work").await;
+        let year = create_tag(&fixture, group, "2024").await;
+        let a = tag_item(&fixture, "C:\\a.txt", &[compound]).await;
+        let b = tag_item(&fixture, "C:\\b.txt", &[compound, work]).await;
+        tag_item(&fixture, "C:\\c.txt", &[work]).await;
+
+        let affected = fixture
+            .service
+            .split_tag(compound, vec![work, year], false)
+            .await
+            .unwrap();
+
+        assert_eq!(affected, 2);
+        for item_id in [a, b] {
+            let tags = fixture.tag_repo.find_by_item(item_id).await.unwrap();
+            let mut ids: Vec<i64> = tags.iter().filter_map(|t| t.id()).collect();
+            ids.sort_unstable();
+            assert_eq!(ids, vec![compound, work, year]);
+        }
+    }
+
+    #[tokio::test]
+    async fn split_tag_can_remove_source() {
+        let fixture = setup().await;
+        let group = create_group(&fixture, "Topics").await;
+        let compound = create_tag(&fixture, group, "work-2024").await;
+        let work = create_tag(&fixture, group, "work").await;
+        let item = tag_item(&fixture, "C:\\a.txt", &[compound]).await;
+
+        fixture
+            .service
+            .split_tag(compound, vec![work], true)
+            .await
+            .unwrap();
+
+        assert!(
+            fixture
+                .tag_repo
+                .find_by_id(compound)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        let tags = fixture.tag_repo.find_by_item(item).await.unwrap();
+        let ids: Vec<i64> = tags.iter().filter_map(|t| t.id()).collect();
+        assert_eq!(ids, vec![work]);
+    }
+
+    #[tokio::test]
+    async fn split_tag_rejects_missing_or_self_targets() {
+        let fixture = setup().await;
+        let group = create_group(&fixture, "Topics").await;
+        let compound = create_tag(&fixture, group, "work-2024").await;
+        let item = tag_item(&fixture, "C:\\a.txt", &[compound]).await;
+
+        let missing = fixture.service.split_tag(compound, vec![999], false).await;
+        assert!(matches!(missing, Err(DomainError::TagNotFound(_))));
+
+        let itself = fixture
+            .service
+            .split_tag(compound, vec![compound], false)
+            .await;
+        assert!(matches!(itself, Err(DomainError::ValidationError(_))));
+
+        let tags = fixture.tag_repo.find_by_item(item).await.unwrap();
+        assert_eq!(tags.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn vocabulary_markdown_lists_groups_in_order_with_counts() {
+        let fixture = setup().await;
+        let status = create_group(&fixture, "Status").await;
+        create_group(&fixture, "Empty").await;
+        let done = create_tag(&fixture, status, "Done").await;
+        create_tag(&fixture, status, "a|b *x*").await;
+        tag_item(&fixture, "C:\\a.txt", &[done]).await;
+        tag_item(&fixture, "C:\\b.txt", &[done]).await;
+        let conn = fixture.pool.get().await.unwrap();
+        conn.interact(|conn| {
+            conn.execute_batch(
+                "UPDATE tag_groups SET display_order = 2 WHERE name = 'Status';
+                 UPDATE tag_groups SET display_order = 1 WHERE name = 'Empty';",
+            )
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+        let (doc, summary) = fixture
+            .service
+            .render_vocabulary(VocabularyFormat::Markdown)
+            .await
+            .unwrap();
+
+        assert_eq!((summary.groups, summary.tags), (2, 2));
+        assert!(doc.contains("| a\\|b \\*x\\* | 0 |\n| Done | 2 |\n"));
+        assert!(doc.contains("## Empty\n\n_No tags_\n"));
+        assert!(doc.find("## Empty").unwrap() < doc.find("## Status").unwrap());
+    }
+
+    #[tokio::test]
+    async fn vocabulary_csv_quotes_fields() {
+        let fixture = setup().await;
+        let group = create_group(&fixture, "Client, Inc").await;
+        create_tag(&fixture, group, "say \"hi\"").await;
+
+        let (doc, summary) = fixture
+            .service
+            .render_vocabulary(VocabularyFormat::Csv)
+            .await
+            .unwrap();
+
+        assert_eq!((summary.groups, summary.tags), (1, 1));
+        assert_eq!(
+            doc,
+            "group,color,tag,items\r\n\"Client, Inc\",,\"say \"\"hi\"\"\",0\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn suggestions_list_predefined_values_before_existing_tags() {
+        let fixture = setup().await;
+        let group_id = create_group(&fixture, "Status").await;
+        create_tag(&fixture, group_id, "Blocked").await;
+        create_tag(&fixture, group_id, "Done").await;
+
+        let stored = fixture
+            .service
+            .set_suggestions(
+                group_id,
+                vec![
+                    " Todo ".to_string(),
+                    "In Progress".to_string(),
+                    "Done".to_string(),
+                    "Todo".to_string(),
+                ],
+            )
+            .await
+            .unwrap();
+        assert_eq!(stored, vec!["Todo", "In Progress", "Done"]);
+
+        // "Done" exists in both sources and is listed once, in the pre-defined position
+        assert_eq!(
+            fixture.service.suggestions(group_id).await.unwrap(),
+            vec!["Todo", "In Progress", "Done", "Blocked"]
+        );
+    }
+
+    #[tokio::test]
+    async fn set_suggestions_validates_values_and_clears_on_empty() {
+        let fixture = setup().await;
+        let group_id = create_group(&fixture, "Status").await;
+        create_tag(&fixture, group_id, "Done").await;
+
+        let err = fixture
+            .service
+            .set_suggestions(group_id, vec!["Todo".to_string(), "  ".to_string()])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, DomainError::InvalidTagValue(_)));
+        assert_eq!(
+            fixture.service.suggestions(group_id).await.unwrap(),
+            vec!["Done"]
+        );
+
+        fixture
+            .service
+            .set_suggestions(group_id, vec!["Todo".to_string()])
+            .await
+            .unwrap();
+        fixture
+            .service
+            .set_suggestions(group_id, Vec::new())
+            .await
+            .unwrap();
+        assert_eq!(
+            fixture.service.suggestions(group_id).await.unwrap(),
+            vec!["Done"]
+        );
+
+        assert!(matches!(
+            fixture.service.suggestions(9999).await,
+            Err(DomainError::TagGroupNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn exact_value_lookup_is_case_sensitive() {
+        let fixture = setup().await;
+        let group = create_group(&fixture, "Group").await;
+        let other = create_group(&fixture, "Other").await;
+        let lower = create_tag(&fixture, group, "cat").await;
+        let upper = create_tag(&fixture, group, "Cat").await;
+        let elsewhere = create_tag(&fixture, other, "cat").await;
+        create_tag(&fixture, group, "cats").await;
+
+        // Creating the same value again is caught by the lookup
+        let duplicate = fixture
+            .service
+            .create(CreateTagDto {
+                group_id: group,
+                value: "cat".to_string(),
+            })
+            .await;
+        assert!(matches!(duplicate, Err(DomainError::DuplicateEntry(_))));
+
+        let found = fixture.tag_repo.find_by_exact_value(group, "cat").await;
+        assert_eq!(found.unwrap().and_then(|t| t.id()), Some(lower));
+        let found = fixture.tag_repo.find_by_exact_value(group, "Cat").await;
+        assert_eq!(found.unwrap().and_then(|t| t.id()), Some(upper));
+        assert!(
+            fixture
+                .tag_repo
+                .find_by_exact_value(group, "CAT")
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            fixture
+                .tag_repo
+                .find_by_exact_value(group, "ca")
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        let across: Vec<Option<i64>> = fixture
+            .tag_repo
+            .find_by_value_across_groups("cat")
+            .await
+            .unwrap()
+            .iter()
+            .map(|t| t.id())
+            .collect();
+        assert_eq!(across, vec![Some(lower), Some(elsewhere)]);
+        assert!(
+            fixture
+                .tag_repo
+                .find_by_value_across_groups("CAT")
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
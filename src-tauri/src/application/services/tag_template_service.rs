@@ -2,32 +2,41 @@
 //!
 //! Orchestrates tag template-related operations.
 
-use crate::application::dto::{CreateTagTemplateDto, TagTemplateDto, UpdateTagTemplateDto};
-use crate::domain::entities::TagTemplate;
+use crate::application::dto::{
+    CreateTagTemplateDto, PathApplyPreviewDto, TagDto, TagSpecDto, TagTemplateDto,
+    TemplateApplicabilityDto, TemplateApplyPreviewDto, TemplatePreviewDto, UpdateTagTemplateDto,
+};
+use crate::domain::entities::{Item, Tag, TagSpec, TagTemplate};
 use crate::domain::errors::DomainError;
-use crate::domain::repositories::{ItemRepository, TagTemplateRepository};
+use crate::domain::repositories::{ItemRepository, TagRepository, TagTemplateRepository};
+use crate::domain::value_objects::FilePath;
+use std::collections::HashMap;
 use std::sync::Arc;
 
 /// Service for tag template operations.
 pub struct TagTemplateService {
     template_repo: Arc<dyn TagTemplateRepository>,
     item_repo: Arc<dyn ItemRepository>,
+    tag_repo: Arc<dyn TagRepository>,
 }
 
 impl TagTemplateService {
     pub fn new(
         template_repo: Arc<dyn TagTemplateRepository>,
         item_repo: Arc<dyn ItemRepository>,
+        tag_repo: Arc<dyn TagRepository>,
     ) -> Self {
         Self {
             template_repo,
             item_repo,
+            tag_repo,
         }
     }
 
     /// Creates a new tag template.
     pub async fn create(&self, dto: CreateTagTemplateDto) -> Result<i64, DomainError> {
-        let mut template = TagTemplate::new(dto.name, dto.tag_ids)?;
+        let tag_specs = to_specs(dto.tag_specs)?;
+        let mut template = TagTemplate::new(dto.name, dto.tag_ids, tag_specs)?;
         self.template_repo.save(&mut template).await
     }
 
@@ -60,6 +69,10 @@ impl TagTemplateService {
             template.update_tags(tag_ids);
         }
 
+        if let Some(tag_specs) = dto.tag_specs {
+            template.update_specs(to_specs(tag_specs)?);
+        }
+
         self.template_repo.update(&template).await
     }
 
@@ -69,24 +82,240 @@ impl TagTemplateService {
     }
 
     /// Applies a template to an item (adds all template tags to the item).
-    pub async fn apply_to_item(&self, template_id: i64, item_id: i64) -> Result<(), DomainError> {
+    ///
+    /// Tag specs naming a missing group or tag create it in the same
+    /// transaction. With a `preview_token` from `preview_apply`, fails with
+    /// `ConcurrentModification` if the template's tags or the item's tags
+    /// changed since the preview, so exactly the previewed tags are added.
+    pub async fn apply_to_item(
+        &self,
+        template_id: i64,
+        item_id: i64,
+        preview_token: Option<&str>,
+    ) -> Result<(), DomainError> {
+        let template = self
+            .template_repo
+            .find_by_id(template_id)
+            .await?
+            .ok_or_else(|| DomainError::TagTemplateNotFound(template_id.to_string()))?;
+
+        let mut expected_tags_updated_at = None;
+        if let Some(token) = preview_token {
+            let item = self
+                .item_repo
+                .find_by_id(item_id)
+                .await?
+                .ok_or_else(|| DomainError::ItemNotFound(item_id.to_string()))?;
+            let (tag_ids, _) = self.resolve_specs(&template).await?;
+            PreviewToken::decode(token)?.verify(&template, &tag_ids, &item)?;
+            // Also catch a change between this check and the write
+            expected_tags_updated_at = Some(item.tags_updated_at().unwrap_or(0));
+        }
+
+        self.item_repo
+            .apply_template(item_id, template_id, expected_tags_updated_at)
+            .await
+            .map(|_| ())
+    }
+
+    /// Reports, per path in input order, whether it is a registered item and
+    /// which template tags applying the template would add.
+    ///
+    /// Items and their tags are loaded with one batch query each. The returned
+    /// token records the template's tags and each found item's
+    /// `tags_updated_at` for `apply_to_item`.
+    pub async fn preview_apply(
+        &self,
+        template_id: i64,
+        paths: Vec<String>,
+    ) -> Result<TemplateApplyPreviewDto, DomainError> {
+        let template = self
+            .template_repo
+            .find_by_id(template_id)
+            .await?
+            .ok_or_else(|| DomainError::TagTemplateNotFound(template_id.to_string()))?;
+
+        let (tag_ids, to_create) = self.resolve_specs(&template).await?;
+        let validated_paths: Vec<String> = paths
+            .iter()
+            .map(|p| FilePath::new(p).map(|fp| fp.as_str().to_string()))
+            .collect::<Result<_, _>>()?;
+
+        let items = self.item_repo.find_by_paths(&validated_paths).await?;
+        let item_ids: Vec<i64> = items.iter().filter_map(|item| item.id()).collect();
+        let tags_by_item = self.tag_repo.find_by_items(&item_ids).await?;
+        let by_path: HashMap<String, &Item> = items
+            .iter()
+            .map(|item| (item.path().to_string(), item))
+            .collect();
+
+        let entries = paths
+            .into_iter()
+            .zip(validated_paths)
+            .map(|(path, validated)| {
+                let item = by_path.get(&validated);
+                let item_id = item.and_then(|item| item.id());
+                let current: Vec<i64> = item_id
+                    .and_then(|id| tags_by_item.get(&id))
+                    .map(|tags| tags.iter().filter_map(|t| t.id()).collect())
+                    .unwrap_or_default();
+                PathApplyPreviewDto {
+                    path,
+                    exists: item.is_some(),
+                    item_id,
+                    missing_tag_ids: tag_ids
+                        .iter()
+                        .copied()
+                        .filter(|id| !current.contains(id))
+                        .collect(),
+                }
+            })
+            .collect();
+
+        let token = PreviewToken {
+            template_id,
+            tag_ids: sorted(&tag_ids),
+            items: items
+                .iter()
+                .filter_map(|item| item.id().map(|id| (id, item.tags_updated_at())))
+                .collect(),
+        };
+        Ok(TemplateApplyPreviewDto {
+            token: token.encode(),
+            items: entries,
+            to_create: to_create.iter().map(TagSpecDto::from).collect(),
+        })
+    }
+
+    /// Splits a template's tags into those applying it would add to the item and
+    /// those the item already carries, in template order.
+    pub async fn preview_for_item(
+        &self,
+        template_id: i64,
+        item_id: i64,
+    ) -> Result<TemplatePreviewDto, DomainError> {
         let template = self
             .template_repo
             .find_by_id(template_id)
             .await?
             .ok_or_else(|| DomainError::TagTemplateNotFound(template_id.to_string()))?;
+        self.item_repo
+            .find_by_id(item_id)
+            .await?
+            .ok_or_else(|| DomainError::ItemNotFound(item_id.to_string()))?;
 
-        // Get existing tags and merge with template tags
         let existing_tag_ids = self.item_repo.get_tag_ids(item_id).await?;
-        let mut all_tags = existing_tag_ids;
+        let (tag_ids, to_create) = self.resolve_specs(&template).await?;
+        let tags = self.tag_repo.find_by_ids(&tag_ids).await?;
+
+        let (to_add, already_present) = Self::split_tags(&tag_ids, &existing_tag_ids, &tags);
+        Ok(TemplatePreviewDto {
+            to_add,
+            already_present,
+            to_create: to_create.iter().map(TagSpecDto::from).collect(),
+        })
+    }
 
-        for tag_id in template.tag_ids() {
-            if !all_tags.contains(tag_id) {
-                all_tags.push(*tag_id);
+    /// Reports, for every template, which tags applying it to the item would
+    /// add and which the item already has.
+    ///
+    /// A template is fully applied when it would add nothing, which includes
+    /// templates without tags.
+    pub async fn find_applicable_templates(
+        &self,
+        item_id: i64,
+    ) -> Result<Vec<TemplateApplicabilityDto>, DomainError> {
+        self.item_repo
+            .find_by_id(item_id)
+            .await?
+            .ok_or_else(|| DomainError::ItemNotFound(item_id.to_string()))?;
+
+        // Item tags and template tags are loaded once for the whole scan
+        let existing_tag_ids = self.item_repo.get_tag_ids(item_id).await?;
+        let templates = self.template_repo.find_all().await?;
+        let all_specs: Vec<TagSpec> = templates
+            .iter()
+            .flat_map(|t| t.tag_specs().iter().cloned())
+            .collect();
+        let found = self.tag_repo.find_by_specs(&all_specs).await?;
+        let resolved: Vec<(Vec<i64>, Vec<TagSpec>)> = templates
+            .iter()
+            .map(|t| Self::split_specs(t, &found))
+            .collect();
+        let mut all_tag_ids: Vec<i64> = resolved
+            .iter()
+            .flat_map(|(tag_ids, _)| tag_ids.iter().copied())
+            .collect();
+        all_tag_ids.sort_unstable();
+        all_tag_ids.dedup();
+        let tags = self.tag_repo.find_by_ids(&all_tag_ids).await?;
+
+        Ok(templates
+            .into_iter()
+            .zip(resolved)
+            .map(|(template, (tag_ids, to_create))| {
+                let (would_add, already_has) = Self::split_tags(&tag_ids, &existing_tag_ids, &tags);
+                TemplateApplicabilityDto {
+                    template_id: template.id().unwrap_or(0),
+                    name: template.name().to_string(),
+                    fully_applied: would_add.is_empty() && to_create.is_empty(),
+                    would_add,
+                    already_has,
+                    would_create: to_create.iter().map(TagSpecDto::from).collect(),
+                }
+            })
+            .collect())
+    }
+
+    /// Resolves a template's specs against the existing tags; see `split_specs`.
+    async fn resolve_specs(
+        &self,
+        template: &TagTemplate,
+    ) -> Result<(Vec<i64>, Vec<TagSpec>), DomainError> {
+        let found = self.tag_repo.find_by_specs(template.tag_specs()).await?;
+        Ok(Self::split_specs(template, &found))
+    }
+
+    /// Returns the template's tag IDs followed by those of its specs naming
+    /// existing tags, and the specs applying it would create a tag for.
+    fn split_specs(
+        template: &TagTemplate,
+        found: &HashMap<TagSpec, Tag>,
+    ) -> (Vec<i64>, Vec<TagSpec>) {
+        let mut tag_ids = template.tag_ids().to_vec();
+        let mut to_create = Vec::new();
+        for spec in template.tag_specs() {
+            match found.get(spec).and_then(|tag| tag.id()) {
+                Some(id) if tag_ids.contains(&id) => {}
+                Some(id) => tag_ids.push(id),
+                None => to_create.push(spec.clone()),
             }
         }
+        (tag_ids, to_create)
+    }
 
-        self.item_repo.replace_tags(item_id, all_tags).await
+    /// Splits a template's resolved tags into (missing from the item, already
+    /// on the item), in template order.
+    fn split_tags(
+        tag_ids: &[i64],
+        existing_tag_ids: &[i64],
+        tags: &[Tag],
+    ) -> (Vec<TagDto>, Vec<TagDto>) {
+        let mut missing = Vec::new();
+        let mut present = Vec::new();
+        for tag_id in tag_ids {
+            // Tags deleted since the template was saved are skipped
+            let Some(tag) = tags.iter().find(|t| t.id() == Some(*tag_id)) else {
+                continue;
+            };
+            let dto = TagDto::from(tag.clone());
+            if existing_tag_ids.contains(tag_id) {
+                present.push(dto);
+            } else {
+                missing.push(dto);
+            }
+        }
+        (missing, present)
     }
 
     fn to_dto(template: TagTemplate) -> TagTemplateDto {
@@ -94,8 +323,473 @@ impl TagTemplateService {
             id: template.id().unwrap_or(0),
             name: template.name().to_string(),
             tag_ids: template.tag_ids().to_vec(),
+            tag_specs: template.tag_specs().iter().map(TagSpecDto::from).collect(),
             created_at: template.created_at().unwrap_or(0),
             updated_at: template.updated_at().unwrap_or(0),
         }
     }
 }
+
+/// State captured by `preview_apply`: the template's tag set and the
+/// `tags_updated_at` of every previewed item.
+///
+/// Encoded as `template_id|tag_id,...|item_id=tags_updated_at,...`, with an
+/// empty timestamp for items never tagged. Clients treat it as opaque.
+#[derive(Debug, PartialEq)]
+struct PreviewToken {
+    template_id: i64,
+    tag_ids: Vec<i64>,
+    items: HashMap<i64, Option<i64>>,
+}
+
+impl PreviewToken {
+    fn encode(&self) -> String {
+        let tag_ids: Vec<String> = self.tag_ids.iter().map(|id| id.to_string()).collect();
+        let mut items: Vec<(&i64, &Option<i64>)> = self.items.iter().collect();
+        items.sort();
+        let items: Vec<String> = items
+            .into_iter()
+            .map(|(id, version)| match version {
+                Some(version) => format!("{}={}", id, version),
+                None => format!("{}=", id),
+            })
+            .collect();
+        format!(
+            "{}|{}|{}",
+            self.template_id,
+            tag_ids.join(","),
+            items.join(",")
+        )
+    }
+
+    fn decode(token: &str) -> Result<Self, DomainError> {
+        let invalid = || DomainError::ValidationError("Invalid preview token".to_string());
+        let mut parts = token.split('|');
+        let (Some(template_id), Some(tag_ids), Some(items), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(invalid());
+        };
+
+        let template_id = template_id.parse().map_err(|_| invalid())?;
+        let tag_ids = tag_ids
+            .split(',')
+            .filter(|s| !s.is_empty())
+            .map(|id| id.parse().map_err(|_| invalid()))
+            .collect::<Result<_, _>>()?;
+        let items = items
+            .split(',')
+            .filter(|s| !s.is_empty())
+            .map(|entry| {
+                let (id, version) = entry.split_once('=').ok_or_else(invalid)?;
+                let id = id.parse().map_err(|_| invalid())?;
+                let version = match version {
+                    "" => None,
+                    v => Some(v.parse().map_err(|_| invalid())?),
+                };
+                Ok((id, version))
+            })
+            .collect::<Result<_, DomainError>>()?;
+
+        Ok(Self {
+            template_id,
+            tag_ids,
+            items,
+        })
+    }
+
+    /// Checks that applying `template`, whose tags currently resolve to
+    /// `tag_ids`, to `item` still matches this preview.
+    fn verify(
+        &self,
+        template: &TagTemplate,
+        tag_ids: &[i64],
+        item: &Item,
+    ) -> Result<(), DomainError> {
+        let item_id = item.id().unwrap_or(0);
+        if template.id() != Some(self.template_id) {
+            return Err(DomainError::ValidationError(
+                "Preview token was issued for another template".to_string(),
+            ));
+        }
+        let Some(previewed) = self.items.get(&item_id) else {
+            return Err(DomainError::ValidationError(format!(
+                "Item {} was not part of the preview",
+                item_id
+            )));
+        };
+        if sorted(tag_ids) != self.tag_ids {
+            return Err(DomainError::ConcurrentModification(format!(
+                "Template {} changed since the preview",
+                self.template_id
+            )));
+        }
+        if *previewed != item.tags_updated_at() {
+            return Err(DomainError::ConcurrentModification(format!(
+                "Tags of item {} changed since the preview",
+                item_id
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn sorted(ids: &[i64]) -> Vec<i64> {
+    let mut ids = ids.to_vec();
+    ids.sort_unstable();
+    ids
+}
+
+fn to_specs(dtos: Vec<TagSpecDto>) -> Result<Vec<TagSpec>, DomainError> {
+    dtos.into_iter()
+        .map(|dto| TagSpec::new(dto.group_name, dto.value))
+        .collect()
+}
+
+impl From<&TagSpec> for TagSpecDto {
+    fn from(spec: &TagSpec) -> Self {
+        TagSpecDto {
+            group_name: spec.group_name().to_string(),
+            value: spec.value().to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::persistence::{
+        SqliteItemRepository, SqliteTagRepository, SqliteTagTemplateRepository, open_database,
+    };
+    use rusqlite::Connection;
+
+    async fn setup() -> (tempfile::TempDir, TagTemplateService) {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = Arc::new(open_database(&dir.path().join("test.db")).await.unwrap());
+        let conn = pool.get().await.unwrap();
+        conn.interact(|conn: &mut Connection| {
+            conn.execute_batch(
+                "INSERT INTO tag_groups (id, name) VALUES (1, 'Group');
+                 INSERT INTO tags (id, group_id, value) VALUES (1, 1, 'a'), (2, 1, 'b'), (3, 1, 'c');
+                 INSERT INTO items (id, path, is_directory) VALUES (1, 'C:\\a.txt', 0);
+                 INSERT INTO item_tags (item_id, tag_id) VALUES (1, 2);",
+            )
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+        let service = TagTemplateService::new(
+            Arc::new(SqliteTagTemplateRepository::new(pool.clone())),
+            Arc::new(SqliteItemRepository::new(pool.clone())),
+            Arc::new(SqliteTagRepository::new(pool)),
+        );
+        (dir, service)
+    }
+
+    fn ids(tags: &[TagDto]) -> Vec<i64> {
+        tags.iter().map(|t| t.id).collect()
+    }
+
+    #[tokio::test]
+    async fn preview_splits_template_tags_by_presence_on_item() {
+        let (_dir, service) = setup().await;
+        let template_id = service
+            .create(CreateTagTemplateDto {
+                name: "Set".to_string(),
+                tag_ids: vec![1, 2, 3],
+                tag_specs: vec![],
+            })
+            .await
+            .unwrap();
+
+        let preview = service.preview_for_item(template_id, 1).await.unwrap();
+        assert_eq!(ids(&preview.to_add), vec![1, 3]);
+        assert_eq!(ids(&preview.already_present), vec![2]);
+
+        // Previewing changes nothing
+        service.apply_to_item(template_id, 1, None).await.unwrap();
+        let preview = service.preview_for_item(template_id, 1).await.unwrap();
+        assert!(preview.to_add.is_empty());
+        assert_eq!(ids(&preview.already_present), vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn preview_rejects_unknown_item_or_template() {
+        let (_dir, service) = setup().await;
+        let template_id = service
+            .create(CreateTagTemplateDto {
+                name: "Set".to_string(),
+                tag_ids: vec![1],
+                tag_specs: vec![],
+            })
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            service.preview_for_item(template_id, 99).await,
+            Err(DomainError::ItemNotFound(_))
+        ));
+        assert!(matches!(
+            service.preview_for_item(99, 1).await,
+            Err(DomainError::TagTemplateNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn applicable_templates_report_partial_full_and_empty() {
+        let (_dir, service) = setup().await;
+        let partial = service
+            .create(CreateTagTemplateDto {
+                name: "Partial".to_string(),
+                tag_ids: vec![1, 2],
+                tag_specs: vec![],
+            })
+            .await
+            .unwrap();
+        let applied = service
+            .create(CreateTagTemplateDto {
+                name: "Applied".to_string(),
+                tag_ids: vec![2],
+                tag_specs: vec![],
+            })
+            .await
+            .unwrap();
+        let empty = service
+            .create(CreateTagTemplateDto {
+                name: "Empty".to_string(),
+                tag_ids: vec![],
+                tag_specs: vec![],
+            })
+            .await
+            .unwrap();
+
+        let result = service.find_applicable_templates(1).await.unwrap();
+        let find = |id: i64| result.iter().find(|t| t.template_id == id).unwrap();
+        assert_eq!(result.len(), 3);
+
+        let partial = find(partial);
+        assert_eq!(partial.name, "Partial");
+        assert_eq!(ids(&partial.would_add), vec![1]);
+        assert_eq!(ids(&partial.already_has), vec![2]);
+        assert!(!partial.fully_applied);
+
+        let applied = find(applied);
+        assert!(applied.would_add.is_empty());
+        assert_eq!(ids(&applied.already_has), vec![2]);
+        assert!(applied.fully_applied);
+
+        let empty = find(empty);
+        assert!(empty.would_add.is_empty() && empty.already_has.is_empty());
+        assert!(empty.fully_applied);
+
+        assert!(matches!(
+            service.find_applicable_templates(99).await,
+            Err(DomainError::ItemNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn apply_swaps_tag_within_exclusive_group() {
+        let (dir, service) = setup().await;
+        let pool = open_database(&dir.path().join("test.db")).await.unwrap();
+        let conn = pool.get().await.unwrap();
+        conn.interact(|conn: &mut Connection| {
+            conn.execute("UPDATE tag_groups SET exclusive = 1 WHERE id = 1", [])
+        })
+        .await
+        .unwrap()
+        .unwrap();
+        let template_id = service
+            .create(CreateTagTemplateDto {
+                name: "Set".to_string(),
+                tag_ids: vec![3],
+                tag_specs: vec![],
+            })
+            .await
+            .unwrap();
+
+        service.apply_to_item(template_id, 1, None).await.unwrap();
+        assert_eq!(service.item_repo.get_tag_ids(1).await.unwrap(), vec![3]);
+    }
+
+    #[tokio::test]
+    async fn preview_apply_reports_missing_tags_per_path() {
+        let (dir, service) = setup().await;
+        let pool = open_database(&dir.path().join("test.db")).await.unwrap();
+        let conn = pool.get().await.unwrap();
+        conn.interact(|conn: &mut Connection| {
+            conn.execute_batch(
+                "INSERT INTO items (id, path, is_directory) VALUES (2, 'C:\\b.txt', 0);
+                 INSERT INTO item_tags (item_id, tag_id) VALUES (2, 1), (2, 3);",
+            )
+        })
+        .await
+        .unwrap()
+        .unwrap();
+        let template_id = service
+            .create(CreateTagTemplateDto {
+                name: "Set".to_string(),
+                tag_ids: vec![3, 1, 2],
+                tag_specs: vec![],
+            })
+            .await
+            .unwrap();
+
+        let preview = service
+            .preview_apply(
+                template_id,
+                vec![
+                    "C:\\b.txt".to_string(),
+                    "C:\\missing.txt".to_string(),
+                    "C:\\a.txt".to_string(),
+                ],
+            )
+            .await
+            .unwrap();
+
+        let rows: Vec<(bool, Option<i64>, Vec<i64>)> = preview
+            .items
+            .iter()
+            .map(|p| (p.exists, p.item_id, p.missing_tag_ids.clone()))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                (true, Some(2), vec![2]),
+                (false, None, vec![1, 2, 3]),
+                (true, Some(1), vec![1, 3]),
+            ]
+        );
+        assert_eq!(preview.items[1].path, "C:\\missing.txt");
+        assert_eq!(PreviewToken::decode(&preview.token).unwrap().items.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn apply_with_preview_token_fails_after_concurrent_change() {
+        let (_dir, service) = setup().await;
+        let template_id = service
+            .create(CreateTagTemplateDto {
+                name: "Set".to_string(),
+                tag_ids: vec![1, 3],
+                tag_specs: vec![],
+            })
+            .await
+            .unwrap();
+        let paths = vec!["C:\\a.txt".to_string()];
+
+        let token = service
+            .preview_apply(template_id, paths.clone())
+            .await
+            .unwrap()
+            .token;
+        service.item_repo.add_tag(1, 1).await.unwrap();
+        assert!(matches!(
+            service.apply_to_item(template_id, 1, Some(&token)).await,
+            Err(DomainError::ConcurrentModification(_))
+        ));
+        assert_eq!(service.item_repo.get_tag_ids(1).await.unwrap(), vec![1, 2]);
+
+        let token = service
+            .preview_apply(template_id, paths)
+            .await
+            .unwrap()
+            .token;
+        service
+            .apply_to_item(template_id, 1, Some(&token))
+            .await
+            .unwrap();
+        assert_eq!(
+            service.item_repo.get_tag_ids(1).await.unwrap(),
+            vec![1, 2, 3]
+        );
+
+        // A token is bound to its template and previewed items
+        assert!(matches!(
+            service.apply_to_item(template_id, 1, Some("garbage")).await,
+            Err(DomainError::ValidationError(_))
+        ));
+        let other = format!("{}|1,3|", template_id);
+        assert!(matches!(
+            service.apply_to_item(template_id, 1, Some(&other)).await,
+            Err(DomainError::ValidationError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn apply_resolves_specs_creating_missing_groups_and_tags() {
+        let (_dir, service) = setup().await;
+        let spec = |group_name: &str, value: &str| TagSpecDto {
+            group_name: group_name.to_string(),
+            value: value.to_string(),
+        };
+        let template_id = service
+            .create(CreateTagTemplateDto {
+                name: "Portable".to_string(),
+                tag_ids: vec![1],
+                tag_specs: vec![
+                    spec("Group", "b"),
+                    spec(" Genre ", "Jazz"),
+                    spec("Genre", "Jazz"),
+                ],
+            })
+            .await
+            .unwrap();
+        let stored = service.get_by_id(template_id).await.unwrap().unwrap();
+        assert_eq!(
+            stored.tag_specs,
+            vec![spec("Group", "b"), spec("Genre", "Jazz")]
+        );
+
+        let preview = service.preview_for_item(template_id, 1).await.unwrap();
+        assert_eq!(ids(&preview.to_add), vec![1]);
+        assert_eq!(ids(&preview.already_present), vec![2]);
+        assert_eq!(preview.to_create, vec![spec("Genre", "Jazz")]);
+
+        service.apply_to_item(template_id, 1, None).await.unwrap();
+        let genre = TagSpec::new("Genre".to_string(), "Jazz".to_string()).unwrap();
+        let created = service
+            .tag_repo
+            .find_by_specs(std::slice::from_ref(&genre))
+            .await
+            .unwrap()[&genre]
+            .id()
+            .unwrap();
+        assert_eq!(
+            service.item_repo.get_tag_ids(1).await.unwrap(),
+            vec![1, 2, created]
+        );
+
+        // Once created, the spec resolves to the same tag
+        service.apply_to_item(template_id, 1, None).await.unwrap();
+        let applicable = service.find_applicable_templates(1).await.unwrap();
+        assert!(applicable[0].fully_applied && applicable[0].would_create.is_empty());
+        assert_eq!(ids(&applicable[0].already_has), vec![1, 2, created]);
+        assert_eq!(service.tag_repo.find_all().await.unwrap().len(), 4);
+
+        assert!(matches!(
+            service
+                .create(CreateTagTemplateDto {
+                    name: "Broken".to_string(),
+                    tag_ids: vec![],
+                    tag_specs: vec![spec("Genre", " ")],
+                })
+                .await,
+            Err(DomainError::InvalidTagValue(_))
+        ));
+    }
+
+    #[test]
+    fn preview_token_round_trips() {
+        let token = PreviewToken {
+            template_id: 7,
+            tag_ids: vec![1, 4],
+            items: HashMap::from([(2, Some(1700000000)), (5, None)]),
+        };
+        let encoded = token.encode();
+        assert_eq!(encoded, "7|1,4|2=1700000000,5=");
+        assert_eq!(PreviewToken::decode(&encoded).unwrap(), token);
+        assert!(PreviewToken::decode("7|1,4").is_err());
+        assert!(PreviewToken::decode("7|x|").is_err());
+    }
+}
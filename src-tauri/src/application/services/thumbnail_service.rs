@@ -2,41 +2,115 @@
 //!
 //! Orchestrates thumbnail generation with caching and concurrency control.
 
+use crate::application::recent_directories::RecentDirectories;
 use crate::application::services::SettingsService;
-use crate::infrastructure::thumbnail::{ComWorkerPool, ThumbnailCache, ThumbnailError};
-use std::path::PathBuf;
-use std::sync::Arc;
+use crate::domain::repositories::ItemRepository;
+use crate::infrastructure::thumbnail::{
+    COLLAGE_TILES, ComWorkerPool, ThumbnailCache, ThumbnailError, compose_collage, downscale_webp,
+    list_images,
+};
+use crate::infrastructure::usn_journal::{FileIdentity, get_file_identity};
+use lru::LruCache;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
+use std::time::{Duration, Instant};
 use tokio::sync::Semaphore;
+use tracing::Instrument;
 
-/// Statistics about the thumbnail cache.
+/// Default number of thumbnails kept in the in-memory cache.
+const DEFAULT_MEMORY_CACHE_ENTRIES: usize = 200;
+
+/// Fallback for the `thumbnail_max_size` setting.
+const DEFAULT_MAX_THUMB_SIZE: u32 = 1024;
+
+/// Largest thumbnail size the `thumbnail_max_size` setting can allow.
+pub const THUMB_SIZE_CEILING: u32 = 4096;
+
+/// Subdirectory a user-chosen cache location is nested in, so the cache never
+/// shares a directory with the user's own files.
+const CUSTOM_CACHE_SUBDIR: &str = "constellation-thumbs";
+
+/// How long no thumbnail may have been requested before idle warming starts.
+const WARM_IDLE_AFTER: Duration = Duration::from_secs(10);
+
+/// How often the idle warmer checks whether it may run.
+const WARM_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Statistics about the thumbnail cache (memory and disk tiers).
 pub struct CacheStats {
-    pub total_size_bytes: u64,
-    pub file_count: u64,
+    pub memory_entries: u64,
+    pub memory_bytes: u64,
+    pub disk_file_count: u64,
+    pub disk_size_bytes: u64,
     pub max_size_bytes: u64,
+    pub cache_dir: String,
+    /// Whether idle warming is generating thumbnails right now.
+    pub warming: bool,
+    /// Thumbnails generated by idle warming since startup.
+    pub warmed_count: u64,
+}
+
+/// Progress of a background cache relocation job.
+pub struct CacheMigrationProgress {
+    pub processed: u64,
+    pub total: u64,
+    pub done: bool,
+    pub error: Option<String>,
 }
 
-/// Service for thumbnail generation with disk caching and concurrency control.
+/// Service for thumbnail generation with two-level (memory + disk) caching
+/// and concurrency control.
 pub struct ThumbnailService {
-    cache: ThumbnailCache,
+    cache: RwLock<Arc<ThumbnailCache>>,
+    default_cache_dir: PathBuf,
+    relocating: Arc<AtomicBool>,
+    memory_cache: Arc<Mutex<LruCache<String, Arc<Vec<u8>>>>>,
     pool: ComWorkerPool,
     semaphore: Arc<Semaphore>,
     settings_service: Arc<SettingsService>,
+    item_repo: Arc<dyn ItemRepository>,
+    /// When `get_thumbnail` or `get_thumbnails` was last called.
+    last_interactive: Mutex<Instant>,
+    warming: AtomicBool,
+    warmed_count: AtomicU64,
+    /// Bumped when the disk cache is cleared or moved, so idle warming revisits
+    /// directories it had finished.
+    cache_generation: AtomicU64,
+}
+
+/// Why an idle warming pass over a directory ended.
+enum WarmOutcome {
+    Done,
+    /// An interactive request arrived or holds the generation permits.
+    Paused,
 }
 
 impl ThumbnailService {
     /// Create a new thumbnail service.
     ///
-    /// Reads worker count and semaphore count from settings.
+    /// Reads worker count, semaphore count and memory cache capacity from settings.
     /// Falls back to CPU-based defaults if not configured (value 0 = auto).
     ///
-    /// - `app_data_dir`: Base AppData directory (thumbnails stored in `{dir}/thumbnails/`)
+    /// - `app_data_dir`: Base AppData directory (thumbnails stored in `{dir}/thumbnails/`
+    ///   unless `thumbnail_cache_dir` points elsewhere)
     /// - `settings_service`: For reading thumbnail-related settings
+    /// - `item_repo`: For preferring tagged images in folder collages
     pub async fn new(
         app_data_dir: PathBuf,
         settings_service: Arc<SettingsService>,
+        item_repo: Arc<dyn ItemRepository>,
     ) -> Result<Self, ThumbnailError> {
-        let cache_dir = app_data_dir.join("thumbnails");
-        let cache = ThumbnailCache::new(cache_dir);
+        let default_cache_dir = app_data_dir.join("thumbnails");
+        let cache_dir = settings_service.get_string("thumbnail_cache_dir", "").await;
+        let cache_dir = if cache_dir.trim().is_empty() {
+            default_cache_dir.clone()
+        } else {
+            PathBuf::from(cache_dir)
+        };
+        let cache = RwLock::new(Arc::new(ThumbnailCache::new(cache_dir)));
 
         // Read worker count: 0 = auto (CPU cores / 2, min 2)
         let worker_count_raw = settings_service
@@ -68,18 +142,45 @@ impl ThumbnailService {
             semaphore_count_raw.clamp(1, 32)
         };
 
+        // Read memory cache capacity (entries, at least 1)
+        let memory_entries = settings_service
+            .get("thumbnail_memory_cache_entries")
+            .await
+            .ok()
+            .flatten()
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(DEFAULT_MEMORY_CACHE_ENTRIES)
+            .clamp(1, 10_000);
+        let memory_capacity = NonZeroUsize::new(memory_entries).unwrap_or(NonZeroUsize::MIN);
+        let memory_cache = Arc::new(Mutex::new(LruCache::new(memory_capacity)));
+
         let pool = ComWorkerPool::spawn(worker_count)?;
         let semaphore = Arc::new(Semaphore::new(semaphore_count));
 
         Ok(Self {
             cache,
+            default_cache_dir,
+            relocating: Arc::new(AtomicBool::new(false)),
+            memory_cache,
             pool,
             semaphore,
             settings_service,
+            item_repo,
+            last_interactive: Mutex::new(Instant::now()),
+            warming: AtomicBool::new(false),
+            warmed_count: AtomicU64::new(0),
+            cache_generation: AtomicU64::new(0),
         })
     }
 
     /// Get or generate a thumbnail. Returns WebP-encoded bytes.
+    ///
+    /// Lookup order: memory cache, disk cache (promoted to memory on hit),
+    /// then generation via the COM worker pool.
+    ///
+    /// With `thumbnail_folder_collage` enabled, directories get a collage of
+    /// the images inside them instead, falling back to the shell icon when
+    /// they hold none.
     pub async fn get_thumbnail(
         &self,
         file_path: &str,
@@ -87,86 +188,878 @@ impl ThumbnailService {
         file_size: u64,
         thumb_size: u32,
     ) -> Result<Vec<u8>, ThumbnailError> {
+        self.mark_interactive();
+        let thumb_size = clamp_thumb_size(thumb_size, self.max_size().await);
+        if self
+            .settings_service
+            .get_bool("thumbnail_folder_collage", false)
+            .await
+            && Path::new(file_path).is_dir()
+        {
+            match self.get_folder_collage(file_path, mtime, thumb_size).await {
+                Ok(Some(webp)) => return Ok(webp),
+                Ok(None) => {}
+                Err(e) => log::warn!("Failed to build folder collage: {}", e),
+            }
+        }
+
+        self.get_file_thumbnail(file_path, mtime, file_size, thumb_size)
+            .await
+    }
+
+    /// Get or generate the shell thumbnail of a single path.
+    async fn get_file_thumbnail(
+        &self,
+        file_path: &str,
+        mtime: i64,
+        file_size: u64,
+        thumb_size: u32,
+    ) -> Result<Vec<u8>, ThumbnailError> {
+        let force_shell = self.is_force_shell_cache().await;
+        let hash = if force_shell {
+            None
+        } else {
+            thumbnail_cache_keys(file_path, mtime, file_size, &[thumb_size])
+                .await
+                .pop_first()
+                .map(|(_, hash)| hash)
+        };
+
+        // Memory hits skip the semaphore — no disk or COM work involved
+        let memory_hit = hash
+            .as_ref()
+            .and_then(|hash| self.lock_memory_cache().get(hash).cloned());
+        if let Some(bytes) = memory_hit {
+            return Ok(bytes.as_ref().clone());
+        }
+
         let _permit = self
             .semaphore
             .acquire()
             .await
             .map_err(|_| ThumbnailError::ChannelClosed)?;
 
+        if let Some(hash) = &hash {
+            if let Some(bytes) = self.read_disk_cache(hash)? {
+                return Ok(bytes);
+            }
+        }
+
+        let webp = self.generate(file_path, thumb_size).await?;
+
+        if let Some(hash) = &hash {
+            self.write_disk_cache(hash, file_path, &webp);
+        }
+
+        Ok(webp)
+    }
+
+    /// Get or generate thumbnails of one path at several sizes, by size.
+    ///
+    /// Cached sizes are served as in `get_thumbnail`. The missing ones come
+    /// from a single generation at the largest of them, scaled down for the
+    /// others, so the source is only decoded once. Folder collages are built
+    /// per size. Sizes above `thumbnail_max_size` are keyed by that maximum.
+    pub async fn get_thumbnails(
+        &self,
+        file_path: &str,
+        mtime: i64,
+        file_size: u64,
+        sizes: Vec<u32>,
+    ) -> Result<BTreeMap<u32, Vec<u8>>, ThumbnailError> {
+        self.mark_interactive();
+        let max_size = self.max_size().await;
+        let sizes: BTreeSet<u32> = sizes
+            .into_iter()
+            .map(|size| clamp_thumb_size(size, max_size))
+            .collect();
+        let mut thumbnails = BTreeMap::new();
+
+        if self
+            .settings_service
+            .get_bool("thumbnail_folder_collage", false)
+            .await
+            && Path::new(file_path).is_dir()
+        {
+            for size in sizes {
+                let webp = self
+                    .get_thumbnail(file_path, mtime, file_size, size)
+                    .await?;
+                thumbnails.insert(size, webp);
+            }
+            return Ok(thumbnails);
+        }
+
         let force_shell = self.is_force_shell_cache().await;
+        let hashes: BTreeMap<u32, String> = if force_shell {
+            BTreeMap::new()
+        } else {
+            let sizes: Vec<u32> = sizes.iter().copied().collect();
+            thumbnail_cache_keys(file_path, mtime, file_size, &sizes).await
+        };
 
-        if !force_shell {
-            let hash = ThumbnailCache::cache_key(file_path, mtime, file_size, thumb_size);
-            if let Some(bytes) = self.cache.get(&hash).map_err(ThumbnailError::Io)? {
-                return Ok(bytes);
+        let _permit = self
+            .semaphore
+            .acquire()
+            .await
+            .map_err(|_| ThumbnailError::ChannelClosed)?;
+
+        let mut missing = Vec::new();
+        for size in sizes {
+            let cached = match hashes.get(&size) {
+                Some(hash) => {
+                    let memory_hit = self.lock_memory_cache().get(hash).cloned();
+                    match memory_hit {
+                        Some(bytes) => Some(bytes.as_ref().clone()),
+                        None => self.read_disk_cache(hash)?,
+                    }
+                }
+                None => None,
+            };
+            match cached {
+                Some(bytes) => {
+                    thumbnails.insert(size, bytes);
+                }
+                None => missing.push(size),
+            }
+        }
+
+        let rendered = render_sizes(&missing, |size| self.generate(file_path, size)).await?;
+        for (size, webp) in rendered {
+            if let Some(hash) = hashes.get(&size) {
+                self.write_disk_cache(hash, file_path, &webp);
             }
+            thumbnails.insert(size, webp);
         }
 
-        let webp = self
-            .pool
+        Ok(thumbnails)
+    }
+
+    /// Generate a thumbnail on the COM worker pool, bypassing the caches.
+    async fn generate(&self, file_path: &str, thumb_size: u32) -> Result<Vec<u8>, ThumbnailError> {
+        self.pool
             .generate(PathBuf::from(file_path), thumb_size)
-            .await?;
+            .instrument(tracing::debug_span!(
+                "thumbnail",
+                path = file_path,
+                size = thumb_size
+            ))
+            .await
+            .inspect_err(
+                |e| tracing::warn!(path = file_path, error = %e, "Thumbnail generation failed"),
+            )
+    }
 
-        if !force_shell {
-            let hash = ThumbnailCache::cache_key(file_path, mtime, file_size, thumb_size);
-            // Best-effort cache store — don't fail the request if caching fails
-            if let Err(e) = self.cache.put(&hash, &webp) {
-                eprintln!("Failed to cache thumbnail: {}", e);
+    /// Read a thumbnail from the disk cache, promoting it to memory on a hit.
+    fn read_disk_cache(&self, hash: &str) -> Result<Option<Vec<u8>>, ThumbnailError> {
+        let cache = self.current_cache();
+        match cache.get(hash) {
+            Ok(Some(bytes)) => {
+                self.lock_memory_cache()
+                    .put(hash.to_string(), Arc::new(bytes.clone()));
+                Ok(Some(bytes))
             }
+            Ok(None) => Ok(None),
+            // The directory was relocated mid-read; regenerate instead of failing
+            Err(_) if !self.is_current_cache(&cache) => Ok(None),
+            Err(e) => Err(ThumbnailError::Io(e)),
         }
+    }
 
-        Ok(webp)
+    /// Write a generated thumbnail to the disk cache.
+    fn write_disk_cache(&self, hash: &str, file_path: &str, webp: &[u8]) {
+        // Re-read the cache so a generation that outlived a relocation lands
+        // in the new directory. Best-effort — don't fail the request if caching fails.
+        let cache = self.current_cache();
+        if let Err(e) = cache
+            .put(hash, webp)
+            .and_then(|_| cache.record_source(hash, file_path))
+        {
+            log::warn!("Failed to cache thumbnail: {}", e);
+        }
     }
 
-    /// Clear all cached thumbnails.
+    /// Get or build a 2×2 collage of the images directly inside `dir`.
+    ///
+    /// Tagged images are picked first, then the rest in path order. Collages
+    /// are always cached, since the shell has no cache of its own for them;
+    /// the key includes the directory mtime so they refresh as the contents
+    /// change. Returns `None` when the folder holds no usable images.
+    async fn get_folder_collage(
+        &self,
+        dir: &str,
+        mtime: i64,
+        thumb_size: u32,
+    ) -> Result<Option<Vec<u8>>, ThumbnailError> {
+        let hash = ThumbnailCache::collage_key(dir, mtime, thumb_size);
+
+        let memory_hit = self.lock_memory_cache().get(&hash).cloned();
+        if let Some(bytes) = memory_hit {
+            return Ok(Some(bytes.as_ref().clone()));
+        }
+        if let Ok(Some(bytes)) = self.current_cache().get(&hash) {
+            self.lock_memory_cache()
+                .put(hash.clone(), Arc::new(bytes.clone()));
+            return Ok(Some(bytes));
+        }
+
+        let dir_path = PathBuf::from(dir);
+        let images = tokio::task::spawn_blocking(move || list_images(&dir_path))
+            .await
+            .map_err(|e| ThumbnailError::Io(std::io::Error::other(e)))?;
+        if images.is_empty() {
+            return Ok(None);
+        }
+
+        let images: Vec<String> = images
+            .iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect();
+        let tagged = self
+            .item_repo
+            .find_tagged_paths(&images)
+            .await
+            .unwrap_or_default();
+        let (mut picks, untagged): (Vec<String>, Vec<String>) =
+            images.into_iter().partition(|p| tagged.contains(p));
+        picks.extend(untagged);
+
+        // Tiles are half the collage size; unreadable images are skipped
+        let tile_size = (thumb_size / 2).max(1);
+        let mut tiles = Vec::with_capacity(COLLAGE_TILES);
+        for path in picks {
+            if tiles.len() == COLLAGE_TILES {
+                break;
+            }
+            let Some((tile_mtime, tile_len)) = file_cache_params(&path) else {
+                continue;
+            };
+            match self
+                .get_file_thumbnail(&path, tile_mtime, tile_len, tile_size)
+                .await
+            {
+                Ok(tile) => tiles.push(tile),
+                Err(e) => log::debug!("Skipping collage tile {}: {}", path, e),
+            }
+        }
+        if tiles.is_empty() {
+            return Ok(None);
+        }
+
+        let webp = tokio::task::spawn_blocking(move || compose_collage(&tiles, thumb_size))
+            .await
+            .map_err(|e| ThumbnailError::Io(std::io::Error::other(e)))??;
+
+        self.lock_memory_cache()
+            .put(hash.clone(), Arc::new(webp.clone()));
+        let cache = self.current_cache();
+        if let Err(e) = cache
+            .put(&hash, &webp)
+            .and_then(|_| cache.record_source(&hash, dir))
+        {
+            log::warn!("Failed to cache folder collage: {}", e);
+        }
+
+        Ok(Some(webp))
+    }
+
+    /// Starts the background task that pre-generates missing thumbnails of
+    /// the recently browsed directories while the app is idle.
+    ///
+    /// With `thumbnail_idle_warm` enabled, once no thumbnail has been
+    /// requested for a while, the images of `recent` are generated at the
+    /// `thumbnail_size` grid size into the disk cache, one at a time. Warming
+    /// only takes a generation permit when one is free and stops as soon as
+    /// an interactive request arrives, resuming at the next idle period.
+    pub fn spawn_idle_warmer(self: &Arc<Self>, recent: RecentDirectories) {
+        let service = self.clone();
+        tokio::spawn(async move {
+            // Directories fully warmed, with their mtime at the time
+            let mut warmed: HashMap<(String, u32), i64> = HashMap::new();
+            let mut generation = service.cache_generation.load(Ordering::Relaxed);
+
+            loop {
+                tokio::time::sleep(WARM_POLL_INTERVAL).await;
+                if service.idle_for() < WARM_IDLE_AFTER
+                    || service.relocating.load(Ordering::Acquire)
+                    || !service
+                        .settings_service
+                        .get_bool("thumbnail_idle_warm", false)
+                        .await
+                    // Nothing is cached to disk in this mode
+                    || service.is_force_shell_cache().await
+                {
+                    continue;
+                }
+
+                let current = service.cache_generation.load(Ordering::Relaxed);
+                if current != generation {
+                    warmed.clear();
+                    generation = current;
+                }
+
+                let size = clamp_thumb_size(
+                    service
+                        .settings_service
+                        .get_u32("thumbnail_size", 256)
+                        .await,
+                    service.max_size().await,
+                );
+                service.warming.store(true, Ordering::Relaxed);
+                for dir in recent.snapshot() {
+                    let Some((mtime, _)) = file_cache_params(&dir) else {
+                        continue;
+                    };
+                    let key = (dir, size);
+                    if warmed.get(&key) == Some(&mtime) {
+                        continue;
+                    }
+                    match service.warm_directory(&key.0, size).await {
+                        WarmOutcome::Done => {
+                            warmed.insert(key, mtime);
+                        }
+                        WarmOutcome::Paused => break,
+                    }
+                }
+                service.warming.store(false, Ordering::Relaxed);
+            }
+        });
+    }
+
+    /// Generates the uncached thumbnails of the images directly inside `dir`
+    /// into the disk cache, leaving the memory cache to interactive requests.
+    async fn warm_directory(&self, dir: &str, thumb_size: u32) -> WarmOutcome {
+        let dir_path = PathBuf::from(dir);
+        let images = tokio::task::spawn_blocking(move || list_images(&dir_path))
+            .await
+            .unwrap_or_default();
+
+        for image in images {
+            if self.idle_for() < WARM_IDLE_AFTER {
+                return WarmOutcome::Paused;
+            }
+            let path = image.to_string_lossy().to_string();
+            let Some((mtime, file_size)) = file_cache_params(&path) else {
+                continue;
+            };
+            let Some((_, hash)) = thumbnail_cache_keys(&path, mtime, file_size, &[thumb_size])
+                .await
+                .pop_first()
+            else {
+                continue;
+            };
+            if self.current_cache().entry_path(&hash).is_some() {
+                continue;
+            }
+
+            // Lowest priority: never queue behind or ahead of interactive requests
+            let Ok(_permit) = self.semaphore.try_acquire() else {
+                return WarmOutcome::Paused;
+            };
+            if let Ok(webp) = self.generate(&path, thumb_size).await {
+                self.write_disk_cache(&hash, &path, &webp);
+                self.warmed_count.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        WarmOutcome::Done
+    }
+
+    /// Records an interactive thumbnail request, pausing idle warming.
+    fn mark_interactive(&self) {
+        *self
+            .last_interactive
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = Instant::now();
+    }
+
+    /// Time since the last interactive thumbnail request.
+    fn idle_for(&self) -> Duration {
+        self.last_interactive
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .elapsed()
+    }
+
+    /// Clear all cached thumbnails (memory and disk).
     pub async fn clear_cache(&self) -> Result<CacheStats, ThumbnailError> {
-        self.cache.clear().map_err(ThumbnailError::Io)?;
+        self.lock_memory_cache().clear();
+        self.current_cache().clear().map_err(ThumbnailError::Io)?;
+        self.cache_generation.fetch_add(1, Ordering::Relaxed);
         Ok(CacheStats {
-            total_size_bytes: 0,
-            file_count: 0,
+            memory_entries: 0,
+            memory_bytes: 0,
+            disk_file_count: 0,
+            disk_size_bytes: 0,
             max_size_bytes: self.cache_max_bytes().await,
+            cache_dir: self.cache_dir(),
+            warming: self.warming.load(Ordering::Relaxed),
+            warmed_count: self.warmed_count.load(Ordering::Relaxed),
         })
     }
 
+    /// Drop the cached thumbnails of one file or folder collage, at every
+    /// size, from memory and disk. Returns the number of entries removed.
+    ///
+    /// Entries are found through the path they were generated for, so a file
+    /// renamed since its thumbnail was generated is invalidated by its old path.
+    pub async fn invalidate(&self, path: &str) -> Result<usize, ThumbnailError> {
+        let cache = self.current_cache();
+        let path = path.to_string();
+        let hashes = tokio::task::spawn_blocking(move || cache.remove_for_path(&path))
+            .await
+            .map_err(|e| ThumbnailError::Io(std::io::Error::other(e)))?
+            .map_err(ThumbnailError::Io)?;
+
+        let mut memory = self.lock_memory_cache();
+        for hash in &hashes {
+            memory.pop(hash);
+        }
+        Ok(hashes.len())
+    }
+
+    /// On-disk path of the cached thumbnail of `path` at `thumb_size`, keyed
+    /// from the file's current modification time and size. `None` if it is
+    /// not cached or the file can't be read.
+    pub async fn cached_path(&self, path: &str, thumb_size: u32) -> Option<PathBuf> {
+        let collage = self
+            .settings_service
+            .get_bool("thumbnail_folder_collage", false)
+            .await;
+        let thumb_size = clamp_thumb_size(thumb_size, self.max_size().await);
+        cached_thumbnail_path(&self.current_cache(), path, thumb_size, collage)
+    }
+
+    /// Clear only the in-memory thumbnail cache, leaving the disk cache intact.
+    pub async fn clear_memory_cache(&self) -> Result<CacheStats, ThumbnailError> {
+        self.lock_memory_cache().clear();
+        self.cache_stats().await
+    }
+
     /// Get cache statistics.
     pub async fn cache_stats(&self) -> Result<CacheStats, ThumbnailError> {
-        let total_size_bytes = self.cache.total_size().map_err(ThumbnailError::Io)?;
-        let file_count = self.cache.file_count().map_err(ThumbnailError::Io)?;
+        let (memory_entries, memory_bytes) = {
+            let memory = self.lock_memory_cache();
+            let bytes: usize = memory.iter().map(|(_, v)| v.len()).sum();
+            (memory.len() as u64, bytes as u64)
+        };
+        let cache = self.current_cache();
+        let disk_size_bytes = cache.total_size().map_err(ThumbnailError::Io)?;
+        let disk_file_count = cache.file_count().map_err(ThumbnailError::Io)?;
         Ok(CacheStats {
-            total_size_bytes,
-            file_count,
+            memory_entries,
+            memory_bytes,
+            disk_file_count,
+            disk_size_bytes,
             max_size_bytes: self.cache_max_bytes().await,
+            cache_dir: self.cache_dir(),
+            warming: self.warming.load(Ordering::Relaxed),
+            warmed_count: self.warmed_count.load(Ordering::Relaxed),
         })
     }
 
+    /// Write the disk cache's counters if they changed since the last write.
+    pub fn flush_cache(&self) {
+        self.current_cache().flush();
+    }
+
     /// Run cache eviction (delete oldest entries until under size limit).
+    ///
+    /// A cache written under an older key scheme is purged first, since none
+    /// of its entries can be hit anymore.
     pub async fn evict_cache(&self) -> Result<u64, ThumbnailError> {
         let max = self.cache_max_bytes().await;
-        self.cache.evict_to_limit(max).map_err(ThumbnailError::Io)
+        let cache = self.current_cache();
+        cache.purge_outdated_format().map_err(ThumbnailError::Io)?;
+        cache.evict_to_limit(max).map_err(ThumbnailError::Io)
+    }
+
+    /// Directory the disk cache currently lives in.
+    fn cache_dir(&self) -> String {
+        self.current_cache()
+            .base_dir()
+            .to_string_lossy()
+            .to_string()
+    }
+
+    /// Move the disk cache to `dir` (`None` = default AppData location).
+    ///
+    /// A custom `dir` gets a `constellation-thumbs` subdirectory, which holds
+    /// the cache; picking that subdirectory itself again is not nested twice.
+    /// Validates that the directory is writable, persists `thumbnail_cache_dir`
+    /// and swaps the cache instance. Existing files are then moved (`migrate`)
+    /// or deleted from the old location on a blocking background task, which
+    /// reports through `on_progress`. Requests already holding the old cache
+    /// finish against it; their writes go to whichever cache is current.
+    ///
+    /// Returns the resolved directory.
+    pub async fn relocate_cache<F>(
+        &self,
+        dir: Option<PathBuf>,
+        migrate: bool,
+        on_progress: F,
+    ) -> Result<PathBuf, ThumbnailError>
+    where
+        F: Fn(CacheMigrationProgress) + Send + 'static,
+    {
+        if self.relocating.swap(true, Ordering::AcqRel) {
+            return Err(ThumbnailError::RelocationInProgress);
+        }
+
+        let target = match dir {
+            Some(dir) if dir != self.default_cache_dir => custom_cache_dir(dir),
+            _ => self.default_cache_dir.clone(),
+        };
+        let (previous, next) = match self.swap_cache(&target).await {
+            Ok(Some(swapped)) => {
+                self.cache_generation.fetch_add(1, Ordering::Relaxed);
+                swapped
+            }
+            Ok(None) => {
+                self.relocating.store(false, Ordering::Release);
+                return Ok(target);
+            }
+            Err(e) => {
+                self.relocating.store(false, Ordering::Release);
+                return Err(e);
+            }
+        };
+
+        let relocating = self.relocating.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut report = |processed, total| {
+                on_progress(CacheMigrationProgress {
+                    processed,
+                    total,
+                    done: false,
+                    error: None,
+                })
+            };
+            let result = if migrate {
+                previous.migrate_into(&next, &mut report)
+            } else {
+                // Files are deleted, not moved; nothing to count
+                previous.clear().map(|_| 0)
+            };
+            relocating.store(false, Ordering::Release);
+            let (processed, error) = match result {
+                Ok(count) => (count, None),
+                Err(e) => (0, Some(e.to_string())),
+            };
+            on_progress(CacheMigrationProgress {
+                processed,
+                total: processed,
+                done: true,
+                error,
+            });
+        });
+
+        Ok(target)
+    }
+
+    /// Validates `target`, persists it and installs a cache there.
+    ///
+    /// Returns the (previous, new) caches, or `None` if `target` is already current.
+    async fn swap_cache(
+        &self,
+        target: &Path,
+    ) -> Result<Option<(Arc<ThumbnailCache>, Arc<ThumbnailCache>)>, ThumbnailError> {
+        if self.current_cache().base_dir() == target {
+            return Ok(None);
+        }
+
+        ThumbnailCache::ensure_writable(target).map_err(ThumbnailError::Io)?;
+
+        // Store "" for the default so the cache follows AppData if it ever moves
+        let setting = if target == self.default_cache_dir {
+            String::new()
+        } else {
+            target.to_string_lossy().to_string()
+        };
+        self.settings_service
+            .set("thumbnail_cache_dir", &setting)
+            .await
+            .map_err(|e| ThumbnailError::Settings(e.to_string()))?;
+
+        let next = Arc::new(ThumbnailCache::new(target.to_path_buf()));
+        let mut guard = match self.cache.write() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let previous = std::mem::replace(&mut *guard, next.clone());
+        Ok(Some((previous, next)))
+    }
+
+    /// Snapshot of the current disk cache.
+    ///
+    /// The `RwLock` only guards the pointer swap during relocation and is never
+    /// held across an `.await`; callers keep their own `Arc` for the whole operation.
+    fn current_cache(&self) -> Arc<ThumbnailCache> {
+        match self.cache.read() {
+            Ok(guard) => guard.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    fn is_current_cache(&self, cache: &Arc<ThumbnailCache>) -> bool {
+        Arc::ptr_eq(cache, &self.current_cache())
+    }
+
+    /// Locks the memory cache.
+    ///
+    /// A poisoned lock only means another request panicked mid-access; the LRU
+    /// itself holds plain byte buffers, so recover the guard and keep serving.
+    fn lock_memory_cache(&self) -> MutexGuard<'_, LruCache<String, Arc<Vec<u8>>>> {
+        match self.memory_cache.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
     }
 
     /// Check if force shell cache mode is enabled.
     async fn is_force_shell_cache(&self) -> bool {
-        match self
-            .settings_service
-            .get("thumbnail_force_shell_cache")
+        self.settings_service
+            .get_bool("thumbnail_force_shell_cache", false)
             .await
-        {
-            Ok(Some(val)) => val == "true",
-            _ => false,
-        }
+    }
+
+    /// Reads the largest thumbnail size from settings (default 1024), capped
+    /// at [`THUMB_SIZE_CEILING`].
+    async fn max_size(&self) -> u32 {
+        self.settings_service
+            .get_u32("thumbnail_max_size", DEFAULT_MAX_THUMB_SIZE)
+            .await
+            .clamp(1, THUMB_SIZE_CEILING)
     }
 
     /// Reads the cache size limit from settings (default 500MB).
     async fn cache_max_bytes(&self) -> u64 {
         let mb = self
             .settings_service
-            .get("thumbnail_cache_max_mb")
+            .get_u32("thumbnail_cache_max_mb", 500)
+            .await;
+        u64::from(mb) * 1024 * 1024
+    }
+}
+
+/// Cache key for a file, keyed by its identity so renames and moves within a
+/// volume keep their thumbnails. Falls back to the path key when the file
+/// system offers no identity (non-NTFS volumes, inaccessible files).
+fn thumbnail_cache_key(file_path: &str, mtime: i64, file_size: u64, thumb_size: u32) -> String {
+    let identity = get_file_identity(file_path).ok().flatten();
+    key_for_identity(file_path, identity, mtime, file_size, thumb_size)
+}
+
+/// `thumbnail_cache_key` at each of `sizes`, for async callers: opening the
+/// file for its identity blocks, so it runs once on the blocking pool.
+async fn thumbnail_cache_keys(
+    file_path: &str,
+    mtime: i64,
+    file_size: u64,
+    sizes: &[u32],
+) -> BTreeMap<u32, String> {
+    let path = file_path.to_string();
+    let identity = tokio::task::spawn_blocking(move || get_file_identity(&path).ok().flatten())
+        .await
+        .ok()
+        .flatten();
+    sizes
+        .iter()
+        .map(|&size| {
+            let key = key_for_identity(file_path, identity, mtime, file_size, size);
+            (size, key)
+        })
+        .collect()
+}
+
+fn key_for_identity(
+    file_path: &str,
+    identity: Option<FileIdentity>,
+    mtime: i64,
+    file_size: u64,
+    thumb_size: u32,
+) -> String {
+    match identity {
+        Some(id) => {
+            ThumbnailCache::identity_key(id.volume_serial, id.frn, mtime, file_size, thumb_size)
+        }
+        None => ThumbnailCache::cache_key(file_path, mtime, file_size, thumb_size),
+    }
+}
+
+/// Cache directory inside a user-chosen `dir`: its `constellation-thumbs`
+/// subdirectory, unless `dir` already is one.
+fn custom_cache_dir(dir: PathBuf) -> PathBuf {
+    if dir.file_name() == Some(CUSTOM_CACHE_SUBDIR.as_ref()) {
+        dir
+    } else {
+        dir.join(CUSTOM_CACHE_SUBDIR)
+    }
+}
+
+/// Limits a requested thumbnail size to `1..=max_size`, so a huge `size`
+/// can't make the shell or the encoder allocate an enormous bitmap.
+pub fn clamp_thumb_size(requested: u32, max_size: u32) -> u32 {
+    requested.clamp(1, max_size.max(1))
+}
+
+/// Renders ascending `sizes` from one call to `generate` at the largest,
+/// scaling its output down for the others.
+async fn render_sizes<G, Fut>(
+    sizes: &[u32],
+    generate: G,
+) -> Result<Vec<(u32, Vec<u8>)>, ThumbnailError>
+where
+    G: FnOnce(u32) -> Fut,
+    Fut: Future<Output = Result<Vec<u8>, ThumbnailError>>,
+{
+    let Some((&largest, smaller)) = sizes.split_last() else {
+        return Ok(Vec::new());
+    };
+    let source = generate(largest).await?;
+
+    let mut rendered = if smaller.is_empty() {
+        Vec::new()
+    } else {
+        let smaller = smaller.to_vec();
+        let source = source.clone();
+        tokio::task::spawn_blocking(move || downscale_webp(&source, &smaller))
             .await
-            .ok()
-            .flatten()
-            .and_then(|v| v.parse::<u64>().ok())
-            .unwrap_or(500);
-        mb * 1024 * 1024
+            .map_err(|e| ThumbnailError::Io(std::io::Error::other(e)))??
+    };
+    rendered.push((largest, source));
+    Ok(rendered)
+}
+
+/// Cache entry `get_thumbnail` would serve for `path`, if one is on disk.
+/// With `collage`, a directory's collage is looked for before its shell icon.
+fn cached_thumbnail_path(
+    cache: &ThumbnailCache,
+    path: &str,
+    thumb_size: u32,
+    collage: bool,
+) -> Option<PathBuf> {
+    let (mtime, file_size) = file_cache_params(path)?;
+    if collage && Path::new(path).is_dir() {
+        let key = ThumbnailCache::collage_key(path, mtime, thumb_size);
+        if let Some(found) = cache.entry_path(&key) {
+            return Some(found);
+        }
+    }
+    cache.entry_path(&thumbnail_cache_key(path, mtime, file_size, thumb_size))
+}
+
+/// Modification time and size of a file, as used in its cache key.
+fn file_cache_params(path: &str) -> Option<(i64, u64)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    let mtime = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    Some((mtime, metadata.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renamed_file_keeps_cache_hit() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ThumbnailCache::new(dir.path().join("thumbnails"));
+        let original = dir.path().join("photo.jpg");
+        let renamed = dir.path().join("renamed.jpg");
+        std::fs::write(&original, b"image").unwrap();
+
+        let key = thumbnail_cache_key(original.to_str().unwrap(), 1_700_000_000, 5, 256);
+        cache.put(&key, b"thumb").unwrap();
+
+        std::fs::rename(&original, &renamed).unwrap();
+        let renamed_key = thumbnail_cache_key(renamed.to_str().unwrap(), 1_700_000_000, 5, 256);
+
+        assert_eq!(renamed_key, key);
+        assert_eq!(cache.get(&renamed_key).unwrap(), Some(b"thumb".to_vec()));
+    }
+
+    #[test]
+    fn cached_thumbnail_path_points_at_the_stored_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ThumbnailCache::new(dir.path().join("thumbnails"));
+        let photo = dir.path().join("photo.jpg");
+        std::fs::write(&photo, b"image").unwrap();
+        let photo = photo.to_str().unwrap();
+
+        assert_eq!(cached_thumbnail_path(&cache, photo, 256, false), None);
+
+        let (mtime, size) = file_cache_params(photo).unwrap();
+        cache
+            .put(&thumbnail_cache_key(photo, mtime, size, 256), b"thumb")
+            .unwrap();
+        let found = cached_thumbnail_path(&cache, photo, 256, false).unwrap();
+        assert!(found.starts_with(cache.base_dir()));
+        assert_eq!(std::fs::read(&found).unwrap(), b"thumb");
+
+        assert_eq!(cached_thumbnail_path(&cache, photo, 128, false), None);
+        let missing = dir.path().join("missing.jpg");
+        assert_eq!(
+            cached_thumbnail_path(&cache, missing.to_str().unwrap(), 256, false),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn several_sizes_share_one_generation() {
+        let calls = std::sync::atomic::AtomicUsize::new(0);
+        let rendered = render_sizes(&[64, 128], |size| {
+            calls.fetch_add(1, Ordering::Relaxed);
+            async move {
+                let image = image::RgbaImage::from_pixel(size, size / 2, image::Rgba([9; 4]));
+                let mut webp = std::io::Cursor::new(Vec::new());
+                image.write_to(&mut webp, image::ImageFormat::WebP).unwrap();
+                Ok(webp.into_inner())
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+        let dimensions: Vec<(u32, (u32, u32))> = rendered
+            .iter()
+            .map(|(size, webp)| {
+                let image = image::load_from_memory_with_format(webp, image::ImageFormat::WebP);
+                (*size, image.unwrap().to_rgba8().dimensions())
+            })
+            .collect();
+        assert_eq!(dimensions, vec![(64, (64, 32)), (128, (128, 64))]);
+    }
+
+    #[test]
+    fn custom_cache_dir_is_nested_once() {
+        let chosen = PathBuf::from("D:").join("Pictures");
+        let nested = custom_cache_dir(chosen.clone());
+        assert_eq!(nested, chosen.join(CUSTOM_CACHE_SUBDIR));
+        assert_eq!(custom_cache_dir(nested.clone()), nested);
+    }
+
+    #[test]
+    fn oversize_thumbnails_are_clamped() {
+        assert_eq!(clamp_thumb_size(256, DEFAULT_MAX_THUMB_SIZE), 256);
+        assert_eq!(clamp_thumb_size(1024, DEFAULT_MAX_THUMB_SIZE), 1024);
+        assert_eq!(clamp_thumb_size(100_000, DEFAULT_MAX_THUMB_SIZE), 1024);
+        assert_eq!(clamp_thumb_size(0, DEFAULT_MAX_THUMB_SIZE), 1);
+        assert_eq!(clamp_thumb_size(512, 0), 1);
+    }
+
+    #[test]
+    fn missing_file_falls_back_to_path_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("missing.jpg");
+        let path = path.to_str().unwrap();
+
+        assert_eq!(
+            thumbnail_cache_key(path, 1, 2, 256),
+            ThumbnailCache::cache_key(path, 1, 2, 256)
+        );
     }
 }
@@ -3,17 +3,25 @@
 //! On-demand file index refresh using the NTFS USN Change Journal.
 //! Supports same-volume path updates and cross-volume move detection.
 
-use crate::application::dto::{DriveUsnStatusDto, RefreshResultDto, RefreshedItemDto};
+use crate::application::cancellation::CancellationToken;
+use crate::application::dto::{
+    DriveUsnStatusDto, FrnBackfillResultDto, RefreshResultDto, RefreshedItemDto,
+};
 use crate::application::services::SettingsService;
+use crate::application::services::item_service::INDEX_EXCLUDE_PATTERNS_KEY;
 use crate::domain::errors::DomainError;
 use crate::domain::repositories::ItemRepository;
+use crate::infrastructure::directory_scan::ExcludePatterns;
 use deadpool_sqlite::Pool;
 use rusqlite::{Connection, OptionalExtension};
 use std::collections::{HashMap, HashSet};
-use std::sync::Arc;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tracing::Instrument;
 
 use crate::infrastructure::usn_journal::{
-    RawUsnRecord, VolumeHandle, is_ntfs, read_journal_records, resolve_path_by_frn,
+    RawUsnRecord, VolumeHandle, get_file_reference_number, is_ntfs, read_journal_records,
+    resolve_path_by_frn,
 };
 
 /// USN reason flags for matching.
@@ -21,6 +29,15 @@ const USN_REASON_FILE_CREATE: u32 = 0x0000_0100;
 const USN_REASON_FILE_DELETE: u32 = 0x0000_0200;
 const USN_REASON_RENAME_NEW_NAME: u32 = 0x0000_2000;
 
+/// Items loaded per query when backfilling File Reference Numbers.
+const FRN_BACKFILL_BATCH_SIZE: usize = 500;
+
+/// Setting capping how many items without an FRN a refresh records one for.
+const FRN_BACKFILL_LIMIT_KEY: &str = "usn_frn_backfill_limit";
+
+/// Default for `usn_frn_backfill_limit`.
+const DEFAULT_FRN_BACKFILL_LIMIT: u32 = 200;
+
 /// Per-drive data collected in phase 1, kept alive for cross-volume resolution.
 struct DriveContext {
     drive: char,
@@ -36,11 +53,49 @@ struct PendingDelete {
     old_path: String,
 }
 
+/// Outcome of looking up one item's File Reference Number.
+enum FrnLookup {
+    Found(u64),
+    /// On an NTFS drive, but the file could not be opened.
+    Unresolved,
+    /// Not on a local NTFS drive, or excluded from indexing.
+    Skipped,
+}
+
+/// Looks up File Reference Numbers for an FRN backfill, remembering which
+/// drives are NTFS.
+struct FrnResolver {
+    exclude: ExcludePatterns,
+    ntfs_drives: HashMap<char, bool>,
+}
+
+impl FrnResolver {
+    /// Opens the file at `path`; blocking.
+    fn lookup(&mut self, path: &str) -> FrnLookup {
+        let on_ntfs = drive_letter(path).is_some_and(|drive| {
+            *self
+                .ntfs_drives
+                .entry(drive)
+                .or_insert_with(|| is_ntfs(drive).unwrap_or(false))
+        });
+        if !on_ntfs || self.exclude.matches(Path::new(path)) {
+            return FrnLookup::Skipped;
+        }
+        match get_file_reference_number(path) {
+            Ok(Some(frn)) if frn != 0 => FrnLookup::Found(frn),
+            _ => FrnLookup::Unresolved,
+        }
+    }
+}
+
 /// Service for on-demand file index refresh via USN Journal.
 pub struct UsnRefreshService {
     pool: Arc<Pool>,
     item_repo: Arc<dyn ItemRepository>,
     settings_service: Arc<SettingsService>,
+    /// The last item ID a refresh tried to record an FRN for, so items that
+    /// can't be resolved don't use up every later refresh's limit.
+    frn_cursor: Mutex<i64>,
 }
 
 impl UsnRefreshService {
@@ -53,6 +108,7 @@ impl UsnRefreshService {
             pool,
             item_repo,
             settings_service,
+            frn_cursor: Mutex::new(0),
         }
     }
 
@@ -61,16 +117,56 @@ impl UsnRefreshService {
     /// Two-phase process:
     /// 1. Read USN records per drive, resolve same-volume renames, collect missing items
     /// 2. Cross-volume matching: search other drives' records for missing items by filename
-    pub async fn refresh(&self, drives: &[char]) -> Result<RefreshResultDto, DomainError> {
+    ///
+    /// `cancel` is checked before each drive and before cross-volume matching.
+    /// A cancelled refresh keeps the renames it applied but marks nothing
+    /// deleted and saves no journal position, so the next refresh re-reads
+    /// the same records.
+    #[tracing::instrument(name = "usn_refresh", skip_all, fields(drives = ?drives))]
+    pub async fn refresh(
+        &self,
+        drives: &[char],
+        cancel: &CancellationToken,
+    ) -> Result<RefreshResultDto, DomainError> {
         let mut result = RefreshResultDto::default();
-        let refresh_on_missing = self.get_setting_bool("usn_refresh_on_missing", true).await;
-        let cross_volume = self.get_setting_bool("usn_cross_volume_match", true).await;
+        let refresh_on_missing = self
+            .settings_service
+            .get_bool("usn_refresh_on_missing", true)
+            .await;
+        let cross_volume = self
+            .settings_service
+            .get_bool("usn_cross_volume_match", true)
+            .await;
+        let apply_rules = self
+            .settings_service
+            .get_bool("usn_apply_auto_tag_rules", false)
+            .await;
+        let frn_limit = self
+            .settings_service
+            .get_u32(FRN_BACKFILL_LIMIT_KEY, DEFAULT_FRN_BACKFILL_LIMIT)
+            .await as usize;
+
+        // Items with no FRN get one recorded first, so later renames of them
+        // are caught. Each refresh continues after the last item it tried.
+        if frn_limit > 0 {
+            let after_id = *self.lock_frn_cursor();
+            match self.backfill_frns(after_id, frn_limit, cancel).await {
+                Ok((backfill, next_cursor)) => {
+                    result.frns_backfilled = backfill.backfilled;
+                    *self.lock_frn_cursor() = next_cursor;
+                }
+                Err(e) => tracing::warn!(error = %e, "FRN backfill failed"),
+            }
+        }
 
         // Phase 1: process each drive, collect pending deletes and drive contexts
         let mut drive_contexts: Vec<DriveContext> = Vec::new();
         let mut all_pending_deletes: Vec<PendingDelete> = Vec::new();
 
         for &drive in drives {
+            if cancel.is_cancelled() {
+                break;
+            }
             match self
                 .process_drive(
                     drive,
@@ -83,27 +179,74 @@ impl UsnRefreshService {
             {
                 Ok(()) => {}
                 Err(e) => {
-                    eprintln!("[USN] refresh: drive {} error: {}", drive, e);
+                    tracing::warn!(%drive, error = %e, "Drive refresh failed");
                     result.errors.push(format!("{}: {}", drive, e));
                 }
             }
         }
 
         // Phase 2: cross-volume matching for pending deletes
-        if cross_volume && !all_pending_deletes.is_empty() && drive_contexts.len() > 1 {
+        if cross_volume
+            && !cancel.is_cancelled()
+            && !all_pending_deletes.is_empty()
+            && drive_contexts.len() > 1
+        {
             self.cross_volume_match(&drive_contexts, &mut all_pending_deletes, &mut result)
                 .await?;
         }
 
-        // Phase 3: mark remaining pending deletes as deleted
-        for pending in &all_pending_deletes {
-            self.mark_item_deleted(pending.item_id).await?;
-            result.items_updated.push(RefreshedItemDto {
-                item_id: pending.item_id,
-                old_path: pending.old_path.clone(),
-                new_path: None,
-                action: "deleted".to_string(),
-            });
+        // Phase 3: mark remaining pending deletes as deleted. Once cancelled,
+        // unmatched items may still turn up on a drive that was not read.
+        result.cancelled = cancel.is_cancelled();
+        let confirmed_deletes = if result.cancelled {
+            &[][..]
+        } else {
+            &all_pending_deletes[..]
+        };
+        async {
+            for pending in confirmed_deletes {
+                self.mark_item_deleted(pending.item_id).await?;
+                result.items_updated.push(RefreshedItemDto {
+                    item_id: pending.item_id,
+                    old_path: pending.old_path.clone(),
+                    new_path: None,
+                    action: "deleted".to_string(),
+                });
+            }
+            Ok::<_, DomainError>(())
+        }
+        .instrument(tracing::debug_span!(
+            "mark_deleted",
+            count = confirmed_deletes.len()
+        ))
+        .await?;
+
+        // Phase 4: tag items that moved into a directory with an auto tag rule
+        if apply_rules {
+            async {
+                for refreshed in &result.items_updated {
+                    if refreshed.new_path.is_none() {
+                        continue;
+                    }
+                    let applied = self
+                        .item_repo
+                        .apply_auto_tag_rules(refreshed.item_id, Some(&refreshed.old_path))
+                        .await;
+                    if let Err(e) = applied {
+                        tracing::warn!(
+                            item_id = refreshed.item_id,
+                            error = %e,
+                            "Auto tag rules failed"
+                        );
+                    }
+                }
+            }
+            .instrument(tracing::debug_span!("auto_tag_rules"))
+            .await;
+        }
+
+        if result.cancelled {
+            return Ok(result);
         }
 
         // Save USN state for all drives that had records processed
@@ -116,6 +259,7 @@ impl UsnRefreshService {
 
     /// Processes a single drive: reads USN records, resolves same-volume renames,
     /// and collects items whose files were not found (for cross-volume matching later).
+    #[tracing::instrument(skip_all, fields(%drive))]
     async fn process_drive(
         &self,
         drive: char,
@@ -202,10 +346,7 @@ impl UsnRefreshService {
 
         // Load tracked items for this drive
         let drive_prefix = format!("{}:\\", drive.to_ascii_uppercase());
-        let tracked_items = self
-            .item_repo
-            .find_active_by_path_prefix(&drive_prefix)
-            .await?;
+        let tracked_items = self.item_repo.find_active_under(&drive_prefix).await?;
 
         if tracked_items.is_empty() {
             // No tracked items but keep drive context for cross-volume resolution
@@ -281,11 +422,26 @@ impl UsnRefreshService {
         Ok(())
     }
 
+    /// Compiles `index_exclude_patterns`, the paths FRN backfills skip.
+    async fn exclude_patterns(&self) -> ExcludePatterns {
+        ExcludePatterns::new(
+            &self
+                .settings_service
+                .get_string_list(INDEX_EXCLUDE_PATTERNS_KEY)
+                .await,
+        )
+    }
+
+    fn lock_frn_cursor(&self) -> std::sync::MutexGuard<'_, i64> {
+        self.frn_cursor.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
     /// Cross-volume matching for pending deletes.
     ///
     /// FSCTL_READ_UNPRIVILEGED_USN_JOURNAL does NOT include filenames in records,
     /// so we resolve FRNs from FILE_CREATE records to full paths via the filesystem,
     /// then match by filename component.
+    #[tracing::instrument(skip_all, fields(pending = pending_deletes.len()))]
     async fn cross_volume_match(
         &self,
         drive_contexts: &[DriveContext],
@@ -395,6 +551,96 @@ impl UsnRefreshService {
         Ok(())
     }
 
+    /// Records the File Reference Number of items that have none, so USN
+    /// refresh can follow them.
+    ///
+    /// Items created before FRN tracking, or while their drive was not NTFS,
+    /// are stored with FRN 0 and skipped by rename detection. See
+    /// [`Self::backfill_frns`] for which items are resolved.
+    pub async fn backfill_file_reference_numbers(
+        &self,
+        cancel: &CancellationToken,
+    ) -> Result<FrnBackfillResultDto, DomainError> {
+        let (result, _) = self.backfill_frns(0, usize::MAX, cancel).await?;
+        log::info!(
+            "FRN backfill: {} backfilled, {} unresolved, {} skipped",
+            result.backfilled,
+            result.unresolved,
+            result.skipped
+        );
+        Ok(result)
+    }
+
+    /// Records the FRN of items that have none, in ID order after `after_id`,
+    /// looking at no more than `limit` items.
+    ///
+    /// Only items on local NTFS drives and outside `index_exclude_patterns`
+    /// are resolved. Items are loaded in batches whose files are opened on the
+    /// blocking thread pool. `cancel` is checked before each item. Returns the
+    /// counts and the ID of the last item looked at, or 0 once every item
+    /// without an FRN has been.
+    async fn backfill_frns(
+        &self,
+        mut after_id: i64,
+        limit: usize,
+        cancel: &CancellationToken,
+    ) -> Result<(FrnBackfillResultDto, i64), DomainError> {
+        let mut result = FrnBackfillResultDto::default();
+        let mut resolver = FrnResolver {
+            exclude: self.exclude_patterns().await,
+            ntfs_drives: HashMap::new(),
+        };
+        let mut remaining = limit;
+
+        while remaining > 0 {
+            let batch_size = remaining.min(FRN_BACKFILL_BATCH_SIZE);
+            let batch = self
+                .item_repo
+                .find_missing_frn(after_id, batch_size)
+                .await?;
+            remaining -= batch.len();
+            let exhausted = batch.len() < batch_size;
+
+            let token = cancel.clone();
+            let (lookups, returned) = tokio::task::spawn_blocking(move || {
+                let lookups: Vec<(i64, FrnLookup)> = batch
+                    .iter()
+                    .take_while(|_| !token.is_cancelled())
+                    .filter_map(|item| Some((item.id()?, resolver.lookup(item.path().as_str()))))
+                    .collect();
+                (lookups, resolver)
+            })
+            .await
+            .map_err(|e| DomainError::ValidationError(format!("FRN lookup failed: {}", e)))?;
+            resolver = returned;
+
+            for (item_id, lookup) in lookups {
+                after_id = item_id;
+                match lookup {
+                    FrnLookup::Found(frn) => {
+                        self.item_repo
+                            .update_file_reference_number(item_id, frn)
+                            .await?;
+                        result.backfilled += 1;
+                    }
+                    FrnLookup::Unresolved => result.unresolved += 1,
+                    FrnLookup::Skipped => result.skipped += 1,
+                }
+            }
+
+            if cancel.is_cancelled() {
+                result.cancelled = true;
+                break;
+            }
+            if exhausted {
+                after_id = 0;
+                break;
+            }
+        }
+
+        Ok((result, after_id))
+    }
+
     /// Updates an item's path.
     async fn update_item_path(&self, item_id: i64, new_path: &str) -> Result<(), DomainError> {
         let mut item = self
@@ -447,17 +693,6 @@ impl UsnRefreshService {
         .map_err(|e| DomainError::DatabaseError(e.to_string()))
     }
 
-    /// Reads a boolean setting with a default value.
-    async fn get_setting_bool(&self, key: &str, default: bool) -> bool {
-        self.settings_service
-            .get(key)
-            .await
-            .ok()
-            .flatten()
-            .map(|v| v == "true")
-            .unwrap_or(default)
-    }
-
     /// Gets the USN status for all NTFS drives.
     pub async fn get_drive_status(&self) -> Result<Vec<DriveUsnStatusDto>, DomainError> {
         let mut results = Vec::new();
@@ -482,6 +717,18 @@ impl UsnRefreshService {
     }
 }
 
+/// Returns the uppercase drive letter of a `X:\...` path, or `None` for
+/// UNC and relative paths.
+pub(crate) fn drive_letter(path: &str) -> Option<char> {
+    let mut chars = path.chars();
+    match (chars.next(), chars.next()) {
+        (Some(letter), Some(':')) if letter.is_ascii_alphabetic() => {
+            Some(letter.to_ascii_uppercase())
+        }
+        _ => None,
+    }
+}
+
 /// Loads USN state (last_usn, journal_id) for a drive.
 async fn load_usn_state(pool: &Pool, drive: char) -> Result<Option<(i64, u64)>, DomainError> {
     let conn = pool
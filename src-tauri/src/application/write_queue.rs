@@ -0,0 +1,242 @@
+//! Write Batching
+//!
+//! Coalesces item creations and tag additions issued in quick succession
+//! into multi-row transactions, so a large import does not pay for one
+//! transaction per write. Queued writes land when `BATCH_WINDOW` has passed
+//! since the first of them, when `MAX_BATCH` are pending, or on `flush`.
+//!
+//! A batch that fails in the background is put back in the queue, so its
+//! writes are retried with the next batch instead of being lost.
+
+use crate::domain::entities::Item;
+use crate::domain::errors::DomainError;
+use crate::domain::repositories::ItemRepository;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// How long the first queued write waits for others to join its batch.
+const BATCH_WINDOW: Duration = Duration::from_millis(50);
+
+/// Number of pending writes that triggers a batch right away.
+const MAX_BATCH: usize = 2_000;
+
+#[derive(Default)]
+struct Pending {
+    items: Vec<Item>,
+    tags: Vec<(i64, i64)>,
+    /// Whether a timer will write the pending batch
+    scheduled: bool,
+}
+
+impl Pending {
+    fn len(&self) -> usize {
+        self.items.len() + self.tags.len()
+    }
+}
+
+struct Inner {
+    item_repo: Arc<dyn ItemRepository>,
+    pending: Mutex<Pending>,
+    /// Keeps batches in enqueue order
+    write_lock: tokio::sync::Mutex<()>,
+}
+
+/// Queue of item and tag writes, written in batches.
+pub struct WriteQueue {
+    inner: Arc<Inner>,
+}
+
+impl WriteQueue {
+    pub fn new(item_repo: Arc<dyn ItemRepository>) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                item_repo,
+                pending: Mutex::new(Pending::default()),
+                write_lock: tokio::sync::Mutex::new(()),
+            }),
+        }
+    }
+
+    /// Queues a new item; an active item at the same path is left as is.
+    pub async fn enqueue_item(&self, item: Item) {
+        self.enqueue(|pending| pending.items.push(item)).await;
+    }
+
+    /// Queues adding `tag_id` to `item_id`.
+    pub async fn enqueue_tag(&self, item_id: i64, tag_id: i64) {
+        self.enqueue(|pending| pending.tags.push((item_id, tag_id)))
+            .await;
+    }
+
+    /// Writes everything queued so far, including writes of failed
+    /// background batches.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the write. The writes that failed are dropped
+    /// rather than queued again, since the caller now knows about them.
+    pub async fn flush(&self) -> Result<(), DomainError> {
+        self.inner.write_pending(false).await
+    }
+
+    async fn enqueue(&self, push: impl FnOnce(&mut Pending)) {
+        let (full, schedule) = {
+            let mut pending = self.inner.lock();
+            push(&mut pending);
+            let full = pending.len() >= MAX_BATCH;
+            let schedule = !full && !pending.scheduled;
+            pending.scheduled |= schedule;
+            (full, schedule)
+        };
+
+        if full {
+            self.inner.write_in_background().await;
+        } else if schedule {
+            let inner = self.inner.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(BATCH_WINDOW).await;
+                inner.write_in_background().await;
+            });
+        }
+    }
+}
+
+impl Inner {
+    fn lock(&self) -> std::sync::MutexGuard<'_, Pending> {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Writes a batch for a timer or a full queue, where nobody waits for
+    /// the result; failed writes stay queued for the next batch.
+    async fn write_in_background(&self) {
+        if let Err(e) = self.write_pending(true).await {
+            log::warn!("Failed to write queued batch, will retry: {}", e);
+        }
+    }
+
+    /// Writes the pending items, then the pending tags, each in one
+    /// transaction. With `requeue`, writes that fail go back to the front of
+    /// the queue; the tags are kept when their items fail, since they may
+    /// refer to those items.
+    async fn write_pending(&self, requeue: bool) -> Result<(), DomainError> {
+        let _writing = self.write_lock.lock().await;
+        let (items, tags) = {
+            let mut pending = self.lock();
+            pending.scheduled = false;
+            (
+                std::mem::take(&mut pending.items),
+                std::mem::take(&mut pending.tags),
+            )
+        };
+
+        if let Err(e) = self.item_repo.save_missing(&items).await {
+            if requeue {
+                self.requeue(items, tags);
+            }
+            return Err(e);
+        }
+        if let Err(e) = self.item_repo.add_tags(&tags).await {
+            if requeue {
+                self.requeue(Vec::new(), tags);
+            }
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Puts failed writes back ahead of anything queued since they were taken.
+    fn requeue(&self, mut items: Vec<Item>, mut tags: Vec<(i64, i64)>) {
+        let mut pending = self.lock();
+        items.append(&mut pending.items);
+        tags.append(&mut pending.tags);
+        pending.items = items;
+        pending.tags = tags;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::persistence::{SqliteItemRepository, open_database};
+
+    async fn execute(pool: &deadpool_sqlite::Pool, sql: &'static str) {
+        let conn = pool.get().await.unwrap();
+        conn.interact(move |conn: &mut rusqlite::Connection| conn.execute_batch(sql))
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    async fn link_count(pool: &deadpool_sqlite::Pool) -> i64 {
+        let conn = pool.get().await.unwrap();
+        conn.interact(|conn: &mut rusqlite::Connection| {
+            conn.query_row("SELECT COUNT(*) FROM item_tags", [], |row| row.get(0))
+        })
+        .await
+        .unwrap()
+        .unwrap()
+    }
+
+    async fn setup() -> (tempfile::TempDir, Arc<deadpool_sqlite::Pool>, WriteQueue) {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = Arc::new(open_database(&dir.path().join("test.db")).await.unwrap());
+        execute(
+            &pool,
+            "INSERT INTO tag_groups (id, name) VALUES (1, 'Group');
+             INSERT INTO tags (id, group_id, value) VALUES (1, 1, 'a'), (2, 1, 'b'), (3, 1, 'c');
+             INSERT INTO items (id, path, is_directory) VALUES (1, 'C:\\a.jpg', 0), (2, 'C:\\b.jpg', 0);",
+        )
+        .await;
+        let queue = WriteQueue::new(Arc::new(SqliteItemRepository::new(pool.clone())));
+        (dir, pool, queue)
+    }
+
+    #[tokio::test]
+    async fn queued_writes_land_after_the_window_or_on_flush() {
+        let (_dir, pool, queue) = setup().await;
+        for item_id in [1, 2] {
+            for tag_id in [1, 2] {
+                queue.enqueue_tag(item_id, tag_id).await;
+            }
+        }
+        queue.flush().await.unwrap();
+        assert_eq!(link_count(&pool).await, 4);
+
+        tokio::time::pause();
+        queue.enqueue_tag(1, 3).await;
+        assert_eq!(link_count(&pool).await, 4);
+        tokio::time::advance(BATCH_WINDOW).await;
+        // The timer's batch runs on a spawned task; wait for it to land
+        while link_count(&pool).await != 5 {
+            tokio::task::yield_now().await;
+        }
+        queue.flush().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn failed_background_batch_is_retried_by_flush() {
+        let (_dir, pool, queue) = setup().await;
+        execute(&pool, "ALTER TABLE item_tags RENAME TO item_tags_away;").await;
+
+        queue.enqueue_tag(1, 1).await;
+        assert!(queue.inner.write_pending(true).await.is_err());
+        queue.enqueue_tag(2, 1).await;
+
+        execute(&pool, "ALTER TABLE item_tags_away RENAME TO item_tags;").await;
+        queue.flush().await.unwrap();
+        assert_eq!(link_count(&pool).await, 2);
+    }
+
+    #[tokio::test]
+    async fn failed_flush_reports_and_drops_its_writes() {
+        let (_dir, pool, queue) = setup().await;
+        execute(&pool, "ALTER TABLE item_tags RENAME TO item_tags_away;").await;
+
+        queue.enqueue_tag(1, 1).await;
+        assert!(queue.flush().await.is_err());
+
+        execute(&pool, "ALTER TABLE item_tags_away RENAME TO item_tags;").await;
+        queue.flush().await.unwrap();
+        assert_eq!(link_count(&pool).await, 0);
+    }
+}
@@ -0,0 +1,67 @@
+//! AutoTagRule Commands
+//!
+//! Thin adapters for auto tag rule operations that delegate to AutoTagRuleService.
+
+use crate::application::dto::{AutoTagRuleDto, CreateAutoTagRuleDto, UpdateAutoTagRuleDto};
+use crate::error::{AppError, AppResult};
+use crate::state::AppState;
+use tauri::State;
+
+#[tauri::command]
+pub async fn get_auto_tag_rules(state: State<'_, AppState>) -> AppResult<Vec<AutoTagRuleDto>> {
+    state
+        .auto_tag_rule_service
+        .get_all()
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn create_auto_tag_rule(
+    path_prefix: String,
+    template_id: i64,
+    enabled: Option<bool>,
+    state: State<'_, AppState>,
+) -> AppResult<i64> {
+    let dto = CreateAutoTagRuleDto {
+        path_prefix,
+        template_id,
+        enabled: enabled.unwrap_or(true),
+    };
+
+    state
+        .auto_tag_rule_service
+        .create(dto)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn update_auto_tag_rule(
+    id: i64,
+    path_prefix: Option<String>,
+    template_id: Option<i64>,
+    enabled: Option<bool>,
+    state: State<'_, AppState>,
+) -> AppResult<()> {
+    let dto = UpdateAutoTagRuleDto {
+        path_prefix,
+        template_id,
+        enabled,
+    };
+
+    state
+        .auto_tag_rule_service
+        .update(id, dto)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn delete_auto_tag_rule(id: i64, state: State<'_, AppState>) -> AppResult<()> {
+    state
+        .auto_tag_rule_service
+        .delete(id)
+        .await
+        .map_err(AppError::from)
+}
@@ -0,0 +1,103 @@
+//! Collection Commands
+//!
+//! Thin adapters for collection operations that delegate to CollectionService.
+
+use crate::application::dto::{
+    CollectionDto, CollectionWithCountDto, CreateCollectionDto, UpdateCollectionDto,
+};
+use crate::error::{AppError, AppResult};
+use crate::state::AppState;
+use tauri::State;
+
+#[tauri::command]
+pub async fn create_collection(
+    name: String,
+    cql_query: String,
+    icon: Option<String>,
+    state: State<'_, AppState>,
+) -> AppResult<i64> {
+    let dto = CreateCollectionDto {
+        name,
+        cql_query,
+        icon,
+    };
+
+    state
+        .collection_service
+        .create(dto)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_collections(state: State<'_, AppState>) -> AppResult<Vec<CollectionDto>> {
+    state
+        .collection_service
+        .get_all()
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_collections_with_counts(
+    state: State<'_, AppState>,
+) -> AppResult<Vec<CollectionWithCountDto>> {
+    state
+        .collection_service
+        .get_all_with_counts()
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn update_collection(
+    id: i64,
+    name: Option<String>,
+    cql_query: Option<String>,
+    icon: Option<String>,
+    state: State<'_, AppState>,
+) -> AppResult<()> {
+    let dto = UpdateCollectionDto {
+        name,
+        cql_query,
+        icon,
+    };
+
+    state
+        .collection_service
+        .update(id, dto)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn delete_collection(id: i64, state: State<'_, AppState>) -> AppResult<()> {
+    state
+        .collection_service
+        .delete(id)
+        .await
+        .map_err(AppError::from)
+}
+
+#[derive(serde::Deserialize)]
+pub struct CollectionOrder {
+    pub id: i64,
+    pub display_order: i32,
+}
+
+#[tauri::command]
+pub async fn reorder_collections(
+    orders: Vec<CollectionOrder>,
+    state: State<'_, AppState>,
+) -> AppResult<()> {
+    let orders: Vec<(i64, i32)> = orders
+        .into_iter()
+        .map(|o| (o.id, o.display_order))
+        .collect();
+
+    state
+        .collection_service
+        .reorder(orders)
+        .await
+        .map_err(AppError::from)
+}
@@ -0,0 +1,75 @@
+//! Data Commands
+//!
+//! Thin adapters for exporting library data to files.
+
+use crate::application::dto::{GalleryExportJobDto, VocabularyExportDto, VocabularyFormat};
+use crate::application::services::GALLERY_THUMBS_DIR;
+use crate::commands::filesystem::validate_path;
+use crate::error::{AppError, AppResult};
+use crate::state::AppState;
+use tauri::{AppHandle, Emitter, State};
+
+/// Event emitted while an HTML gallery export runs.
+const GALLERY_EXPORT_EVENT: &str = "gallery-export-progress";
+
+/// Default cap on the number of files in an exported gallery.
+const DEFAULT_GALLERY_MAX_ITEMS: usize = 500;
+
+#[tauri::command]
+pub async fn export_tag_vocabulary(
+    path: String,
+    format: VocabularyFormat,
+    state: State<'_, AppState>,
+) -> AppResult<VocabularyExportDto> {
+    let path = validate_path(&path)?;
+    let (document, summary) = state.tag_service.render_vocabulary(format).await?;
+
+    std::fs::write(&path, document)?;
+    Ok(summary)
+}
+
+/// Exports the results of a CQL query as `index.html` plus thumbnails in
+/// `output_dir`.
+///
+/// Returns once the search has run; thumbnails and the page are written in
+/// the background, reported via `gallery-export-progress` events. The
+/// background part is cancellable through `cancel_operation(operation_id)`.
+#[tauri::command]
+pub async fn export_html_gallery(
+    query: String,
+    output_dir: String,
+    thumb_size: u32,
+    max_items: Option<usize>,
+    operation_id: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> AppResult<GalleryExportJobDto> {
+    let output_dir = validate_path(&output_dir)?;
+    if !output_dir.is_absolute() {
+        return Err(AppError::InvalidInput(format!(
+            "Output directory must be an absolute path: {}",
+            output_dir.display()
+        )));
+    }
+    std::fs::create_dir_all(output_dir.join(GALLERY_THUMBS_DIR))?;
+
+    let operation = state.cancellation_registry.register(operation_id)?;
+    let thumbnails = state.thumbnail_service().await?;
+    state
+        .gallery_export_service
+        .start(
+            &query,
+            output_dir,
+            thumb_size,
+            max_items.unwrap_or(DEFAULT_GALLERY_MAX_ITEMS),
+            thumbnails,
+            operation,
+            move |progress| {
+                if let Err(e) = app.emit(GALLERY_EXPORT_EVENT, progress) {
+                    log::warn!("Failed to emit gallery export progress: {}", e);
+                }
+            },
+        )
+        .await
+        .map_err(AppError::from)
+}
@@ -0,0 +1,50 @@
+//! Diagnostics Commands
+//!
+//! Power-user tools for inspecting the library database directly.
+
+use crate::application::dto::SqlQueryResultDto;
+use crate::error::{AppError, AppResult};
+use crate::infrastructure::persistence::run_readonly_query;
+use crate::state::AppState;
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::State;
+
+/// Setting that unlocks the SQL console.
+const ADVANCED_MODE_KEY: &str = "advanced_mode";
+
+/// Default and maximum number of rows returned by the SQL console.
+const DEFAULT_MAX_ROWS: usize = 1_000;
+const MAX_ROWS: usize = 10_000;
+
+/// How long a console query may run before it is interrupted.
+const QUERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Runs a single read-only SELECT or WITH query on its own connection and
+/// returns up to `max_rows` rows (default 1000, at most 10000).
+///
+/// Requires the `advanced_mode` setting. Statements that could modify the
+/// database are rejected before they run.
+#[tauri::command]
+pub async fn execute_readonly_sql(
+    sql: String,
+    max_rows: Option<usize>,
+    state: State<'_, AppState>,
+) -> AppResult<SqlQueryResultDto> {
+    if !state
+        .settings_service
+        .get_bool(ADVANCED_MODE_KEY, false)
+        .await
+    {
+        return Err(AppError::InvalidInput(
+            "The SQL console requires advanced mode".to_string(),
+        ));
+    }
+
+    let max_rows = max_rows.unwrap_or(DEFAULT_MAX_ROWS).clamp(1, MAX_ROWS);
+    let db_path = PathBuf::from(&state.config.db_path);
+    tokio::task::spawn_blocking(move || run_readonly_query(&db_path, &sql, max_rows, QUERY_TIMEOUT))
+        .await
+        .map_err(|e| AppError::Domain(e.to_string()))?
+        .map_err(AppError::from)
+}
@@ -2,23 +2,46 @@
 //!
 //! Tauri commands for on-demand USN Journal file index refresh.
 
-use crate::application::dto::{DriveUsnStatusDto, RefreshResultDto};
+use crate::application::dto::{
+    DriveUsnStatusDto, FrnBackfillResultDto, JournalConfigDto, RefreshResultDto,
+};
 use crate::error::{AppError, AppResult};
+use crate::infrastructure::usn_journal::{JournalConfig, VolumeHandle};
 use crate::state::AppState;
 use tauri::State;
 
 /// Refreshes the file index for the specified drives using USN Journal.
+/// Cancellable through `cancel_operation(operation_id)`.
 #[tauri::command]
 pub async fn refresh_file_index(
     drives: Vec<String>,
+    operation_id: Option<String>,
     state: State<'_, AppState>,
 ) -> AppResult<RefreshResultDto> {
     let letters: Vec<char> = drives.iter().filter_map(|d| d.chars().next()).collect();
+    let operation = state.cancellation_registry.register(operation_id)?;
+
+    state
+        .usn_refresh_service()
+        .refresh(&letters, operation.token())
+        .await
+        .map_err(AppError::from)
+}
+
+/// Records File Reference Numbers for items that have none, so USN refresh can
+/// track their renames. Cancellable through `cancel_operation(operation_id)`.
+#[tauri::command]
+pub async fn backfill_file_reference_numbers(
+    operation_id: Option<String>,
+    state: State<'_, AppState>,
+) -> AppResult<FrnBackfillResultDto> {
+    let operation = state.cancellation_registry.register(operation_id)?;
+
     state
-        .usn_refresh_service
-        .refresh(&letters)
+        .usn_refresh_service()
+        .backfill_file_reference_numbers(operation.token())
         .await
-        .map_err(|e| AppError::UsnJournal(e.to_string()))
+        .map_err(AppError::from)
 }
 
 /// Checks whether a drive supports USN Journal (is NTFS).
@@ -29,37 +52,89 @@ pub async fn check_usn_support(drive: String) -> AppResult<bool> {
         .next()
         .ok_or_else(|| AppError::InvalidInput("Empty drive".into()))?;
 
-    crate::infrastructure::usn_journal::is_ntfs(letter)
-        .map_err(|e| AppError::UsnJournal(e.to_string()))
+    crate::infrastructure::usn_journal::is_ntfs(letter).map_err(AppError::from)
 }
 
 /// Gets USN Journal status for all NTFS drives.
 #[tauri::command]
 pub async fn get_usn_drive_status(state: State<'_, AppState>) -> AppResult<Vec<DriveUsnStatusDto>> {
     state
-        .usn_refresh_service
+        .usn_refresh_service()
         .get_drive_status()
         .await
-        .map_err(|e| AppError::UsnJournal(e.to_string()))
+        .map_err(AppError::from)
 }
 
 /// Enables USN Journal on a drive (requires admin — triggers UAC dialog).
 #[tauri::command]
 pub async fn enable_usn_journal(drive: String) -> AppResult<()> {
-    use windows::Win32::UI::Shell::ShellExecuteW;
-    use windows::Win32::UI::WindowsAndMessaging::SW_HIDE;
-    use windows::core::PCWSTR;
+    let letter = drive_letter(&drive)?;
+    // 32 MB maximum with a 4 MB allocation delta
+    launch_elevated_create_journal(letter, 32 * 1024 * 1024, 4 * 1024 * 1024)
+}
 
-    let letter = drive
+/// Creates (or resizes) the USN Journal on a drive with the given sizes in MB,
+/// 32 and 8 by default. Runs the control code directly when the app is
+/// elevated and falls back to `fsutil` through a UAC prompt otherwise.
+#[tauri::command]
+pub async fn enable_usn_journal_with_config(
+    drive: String,
+    max_size_mb: Option<u32>,
+    allocation_delta_mb: Option<u32>,
+) -> AppResult<()> {
+    let letter = drive_letter(&drive)?;
+    let config = JournalConfig::new(
+        max_size_mb.unwrap_or(JournalConfig::DEFAULT_MAX_SIZE_MB),
+        allocation_delta_mb.unwrap_or(JournalConfig::DEFAULT_ALLOCATION_DELTA_MB),
+    )?;
+
+    match VolumeHandle::open_writable(letter) {
+        Ok(volume) => volume
+            .create_journal_with_config(&config)
+            .map_err(AppError::from),
+        Err(_) => launch_elevated_create_journal(
+            letter,
+            config.max_size_bytes(),
+            config.allocation_delta_bytes(),
+        ),
+    }
+}
+
+/// Gets the maximum size and allocation delta of a drive's active journal.
+#[tauri::command]
+pub async fn get_journal_config(drive: String) -> AppResult<JournalConfigDto> {
+    let letter = drive_letter(&drive)?;
+    let journal = VolumeHandle::open(letter).and_then(|volume| volume.query_journal())?;
+
+    Ok(JournalConfigDto {
+        drive: format!("{}:", letter.to_ascii_uppercase()),
+        maximum_size: journal.maximum_size,
+        allocation_delta: journal.allocation_delta,
+    })
+}
+
+fn drive_letter(drive: &str) -> AppResult<char> {
+    drive
         .chars()
         .next()
-        .ok_or_else(|| AppError::InvalidInput("Empty drive".into()))?;
+        .ok_or_else(|| AppError::InvalidInput("Empty drive".into()))
+}
+
+/// Runs `fsutil usn createjournal` elevated, which shows the UAC dialog.
+fn launch_elevated_create_journal(
+    letter: char,
+    max_size_bytes: u64,
+    allocation_delta_bytes: u64,
+) -> AppResult<()> {
+    use windows::Win32::UI::Shell::ShellExecuteW;
+    use windows::Win32::UI::WindowsAndMessaging::SW_HIDE;
+    use windows::core::PCWSTR;
 
     let verb: Vec<u16> = "runas\0".encode_utf16().collect();
     let file: Vec<u16> = "cmd.exe\0".encode_utf16().collect();
     let args = format!(
-        "/c fsutil usn createjournal m=33554432 a=4194304 {}:\0",
-        letter
+        "/c fsutil usn createjournal m={} a={} {}:\0",
+        max_size_bytes, allocation_delta_bytes, letter
     );
     let args_wide: Vec<u16> = args.encode_utf16().collect();
 
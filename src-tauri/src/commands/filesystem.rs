@@ -1,14 +1,26 @@
+use crate::domain::value_objects::FilePath;
 use crate::error::{AppError, AppResult};
+use crate::state::AppState;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Component, Path, PathBuf};
+use tauri::State;
+use walkdir::WalkDir;
 
 use std::os::windows::process::CommandExt;
 
 /// Validate path to prevent path traversal attacks using ./ or ../
-/// Allows access to any directory, but blocks relative path manipulation
-fn validate_path(path: &str) -> AppResult<PathBuf> {
-    let path_buf = PathBuf::from(path);
+/// Allows access to any directory, but blocks relative path manipulation.
+///
+/// The path is normalized first (see [`FilePath::normalize`]) and the
+/// normalized form is returned, so `C:\Photos\`, `c:/Photos` and `C:\Photos`
+/// all name the same directory.
+pub(crate) fn validate_path(path: &str) -> AppResult<PathBuf> {
+    let normalized = FilePath::normalize(path);
+    if normalized.is_empty() {
+        return Err(AppError::InvalidInput("Path is empty".to_string()));
+    }
+    let path_buf = PathBuf::from(&normalized);
 
     // Check for path traversal patterns in components
     for component in path_buf.components() {
@@ -28,7 +40,7 @@ fn validate_path(path: &str) -> AppResult<PathBuf> {
     }
 
     // Also check raw string for encoded or hidden traversal patterns
-    if path.contains("..") || path.contains("./") || path.contains(".\\") {
+    if normalized.contains("..") || normalized.contains(".\\") {
         return Err(AppError::InvalidInput(
             "Path traversal patterns not allowed".to_string(),
         ));
@@ -46,6 +58,20 @@ pub struct DriveInfo {
     pub available_space: Option<u64>,
 }
 
+/// A present drive and whether USN refresh can index it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexableDrive {
+    pub letter: String,
+    pub label: Option<String>,
+    pub drive_type: String,
+    /// File system name (e.g. `NTFS`), None if the volume is unreadable
+    pub filesystem: Option<String>,
+    /// True for NTFS and ReFS, which keep a USN Journal
+    pub usn_capable: bool,
+    pub total_space: Option<u64>,
+    pub available_space: Option<u64>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileEntry {
     pub name: String,
@@ -56,6 +82,15 @@ pub struct FileEntry {
     pub is_hidden: bool,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectoryCountDto {
+    pub file_count: u64,
+    pub directory_count: u64,
+    pub total_size_bytes: u64,
+    /// True when `max_depth` stopped the walk above a non-empty directory.
+    pub truncated: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileMetadata {
     pub path: String,
@@ -69,54 +104,85 @@ pub struct FileMetadata {
 /// Get all available drives on Windows
 #[tauri::command]
 pub async fn get_drives() -> AppResult<Vec<DriveInfo>> {
-    use std::ffi::OsStr;
-    use std::os::windows::ffi::OsStrExt;
-
     let mut drives = Vec::new();
 
+    for letter in present_drive_letters() {
+        let drive_path = format!("{}:\\", letter);
+        let drive_type_str = get_drive_type(&drive_path);
+
+        // Only include fixed and removable drives
+        if drive_type_str == "fixed" || drive_type_str == "removable" {
+            // Try to get drive label and space info
+            let label = get_drive_label(&drive_path);
+            let (total_space, available_space) = get_drive_space(&drive_path);
+
+            drives.push(DriveInfo {
+                letter: letter.to_string(),
+                label,
+                drive_type: drive_type_str.to_string(),
+                total_space,
+                available_space,
+            });
+        }
+    }
+
+    Ok(drives)
+}
+
+/// Lists every present drive with its file system and whether USN refresh
+/// can index it.
+#[tauri::command]
+pub async fn get_indexable_drives() -> AppResult<Vec<IndexableDrive>> {
+    use crate::infrastructure::usn_journal::{filesystem_name, is_usn_capable};
+
+    Ok(present_drive_letters()
+        .map(|letter| {
+            let drive_path = format!("{}:\\", letter);
+            let filesystem = filesystem_name(letter);
+            let (total_space, available_space) = get_drive_space(&drive_path);
+
+            IndexableDrive {
+                letter: letter.to_string(),
+                label: get_drive_label(&drive_path),
+                drive_type: get_drive_type(&drive_path).to_string(),
+                usn_capable: filesystem.as_deref().is_some_and(is_usn_capable),
+                filesystem,
+                total_space,
+                available_space,
+            }
+        })
+        .collect())
+}
+
+/// Letters of the drives currently present, in order.
+fn present_drive_letters() -> impl Iterator<Item = char> {
     // Get logical drives bitmask
     let drives_mask = unsafe { winapi::um::fileapi::GetLogicalDrives() };
 
-    for i in 0..26 {
-        if (drives_mask & (1 << i)) != 0 {
-            let letter = (b'A' + i) as char;
-            let drive_path = format!("{}:\\", letter);
+    (0..26u8)
+        .filter(move |i| (drives_mask & (1 << i)) != 0)
+        .map(|i| (b'A' + i) as char)
+}
 
-            // Get drive type
-            let wide_path: Vec<u16> = OsStr::new(&drive_path)
-                .encode_wide()
-                .chain(std::iter::once(0))
-                .collect();
-
-            let drive_type = unsafe { winapi::um::fileapi::GetDriveTypeW(wide_path.as_ptr()) };
-
-            let drive_type_str = match drive_type {
-                winapi::um::winbase::DRIVE_FIXED => "fixed",
-                winapi::um::winbase::DRIVE_REMOVABLE => "removable",
-                winapi::um::winbase::DRIVE_REMOTE => "network",
-                winapi::um::winbase::DRIVE_CDROM => "cdrom",
-                winapi::um::winbase::DRIVE_RAMDISK => "ramdisk",
-                _ => "unknown",
-            };
+fn get_drive_type(drive_path: &str) -> &'static str {
+    use std::ffi::OsStr;
+    use std::os::windows::ffi::OsStrExt;
 
-            // Only include fixed and removable drives
-            if drive_type_str == "fixed" || drive_type_str == "removable" {
-                // Try to get drive label and space info
-                let label = get_drive_label(&drive_path);
-                let (total_space, available_space) = get_drive_space(&drive_path);
-
-                drives.push(DriveInfo {
-                    letter: letter.to_string(),
-                    label,
-                    drive_type: drive_type_str.to_string(),
-                    total_space,
-                    available_space,
-                });
-            }
-        }
-    }
+    let wide_path: Vec<u16> = OsStr::new(drive_path)
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect();
 
-    Ok(drives)
+    let drive_type = unsafe { winapi::um::fileapi::GetDriveTypeW(wide_path.as_ptr()) };
+
+    match drive_type {
+        winapi::um::winbase::DRIVE_FIXED => "fixed",
+        winapi::um::winbase::DRIVE_REMOVABLE => "removable",
+        winapi::um::winbase::DRIVE_REMOTE => "network",
+        winapi::um::winbase::DRIVE_CDROM => "cdrom",
+        winapi::um::winbase::DRIVE_RAMDISK => "ramdisk",
+        _ => "unknown",
+    }
 }
 
 fn get_drive_label(drive_path: &str) -> Option<String> {
@@ -183,8 +249,10 @@ fn get_drive_space(drive_path: &str) -> (Option<u64>, Option<u64>) {
 }
 
 /// Read directory contents
+///
+/// The directory is remembered for idle thumbnail warming.
 #[tauri::command]
-pub async fn read_directory(path: String) -> AppResult<Vec<FileEntry>> {
+pub async fn read_directory(path: String, state: State<'_, AppState>) -> AppResult<Vec<FileEntry>> {
     // Validate path to prevent traversal attacks
     let path_buf = validate_path(&path)?;
 
@@ -202,6 +270,8 @@ pub async fn read_directory(path: String) -> AppResult<Vec<FileEntry>> {
         )));
     }
 
+    state.recent_directories.record(&path_buf.to_string_lossy());
+
     let mut entries = Vec::new();
 
     match fs::read_dir(&path_buf) {
@@ -339,6 +409,80 @@ pub async fn get_file_metadata(path: String) -> AppResult<FileMetadata> {
     }
 }
 
+/// Get the total size of the files under a directory
+///
+/// `max_depth` bounds recursion; see `get_directory_entry_count`.
+#[tauri::command]
+pub async fn get_directory_size(path: String, max_depth: Option<u32>) -> AppResult<u64> {
+    let counts = count_directory_blocking(&path, max_depth).await?;
+    Ok(counts.total_size_bytes)
+}
+
+/// Count the files and subdirectories under a directory
+///
+/// `max_depth` counts levels below the directory's own entries: `Some(0)`
+/// counts only its direct children, `Some(1)` also their children, and
+/// `None` walks the whole tree.
+#[tauri::command]
+pub async fn get_directory_entry_count(
+    path: String,
+    max_depth: Option<u32>,
+) -> AppResult<DirectoryCountDto> {
+    count_directory_blocking(&path, max_depth).await
+}
+
+async fn count_directory_blocking(
+    path: &str,
+    max_depth: Option<u32>,
+) -> AppResult<DirectoryCountDto> {
+    let path_buf = validate_path(path)?;
+
+    if !path_buf.is_dir() {
+        return Err(AppError::InvalidInput(format!(
+            "Path is not a directory: {}",
+            path
+        )));
+    }
+
+    tokio::task::spawn_blocking(move || count_directory(&path_buf, max_depth))
+        .await
+        .map_err(|e| AppError::Io(std::io::Error::other(e)))
+}
+
+/// Walks `root` without following symlinks. Entries that can't be read are
+/// skipped rather than failing the whole count.
+fn count_directory(root: &Path, max_depth: Option<u32>) -> DirectoryCountDto {
+    // WalkDir depth 0 is `root` itself, so its direct children are depth 1
+    let limit = max_depth.map(|depth| depth as usize + 1);
+    let mut walker = WalkDir::new(root).min_depth(1);
+    if let Some(limit) = limit {
+        walker = walker.max_depth(limit);
+    }
+
+    let mut counts = DirectoryCountDto {
+        file_count: 0,
+        directory_count: 0,
+        total_size_bytes: 0,
+        truncated: false,
+    };
+
+    for entry in walker.into_iter().filter_map(Result::ok) {
+        if entry.file_type().is_dir() {
+            counts.directory_count += 1;
+            if !counts.truncated && Some(entry.depth()) == limit {
+                counts.truncated = fs::read_dir(entry.path())
+                    .map(|mut children| children.next().is_some())
+                    .unwrap_or(false);
+            }
+        } else {
+            counts.file_count += 1;
+            counts.total_size_bytes += entry.metadata().map(|m| m.len()).unwrap_or(0);
+        }
+    }
+
+    counts
+}
+
 /// Open file with default application
 #[tauri::command]
 pub async fn open_file_external(path: String) -> AppResult<()> {
@@ -435,3 +579,71 @@ pub async fn reveal_in_explorer(path: String) -> AppResult<()> {
         ))),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// root/{a.txt (3 bytes), sub/{b.txt (5 bytes), deep/{c.txt (7 bytes)}}}
+    fn tree() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let deep = dir.path().join("sub").join("deep");
+        fs::create_dir_all(&deep).unwrap();
+        fs::write(dir.path().join("a.txt"), b"aaa").unwrap();
+        fs::write(dir.path().join("sub").join("b.txt"), b"bbbbb").unwrap();
+        fs::write(deep.join("c.txt"), b"ccccccc").unwrap();
+        dir
+    }
+
+    #[test]
+    fn depth_zero_counts_only_top_level_entries() {
+        let dir = tree();
+        let counts = count_directory(dir.path(), Some(0));
+        assert_eq!((counts.file_count, counts.directory_count), (1, 1));
+        assert_eq!(counts.total_size_bytes, 3);
+        assert!(counts.truncated);
+    }
+
+    #[test]
+    fn depth_one_includes_one_level_of_children() {
+        let dir = tree();
+        let counts = count_directory(dir.path(), Some(1));
+        assert_eq!((counts.file_count, counts.directory_count), (2, 2));
+        assert_eq!(counts.total_size_bytes, 8);
+        assert!(counts.truncated);
+    }
+
+    #[test]
+    fn unbounded_walk_counts_everything() {
+        let dir = tree();
+        let counts = count_directory(dir.path(), None);
+        assert_eq!((counts.file_count, counts.directory_count), (3, 2));
+        assert_eq!(counts.total_size_bytes, 15);
+        assert!(!counts.truncated);
+        // A limit deeper than the tree is not truncation
+        assert!(!count_directory(dir.path(), Some(2)).truncated);
+    }
+
+    #[test]
+    fn validate_path_normalizes_separators() {
+        let valid = |path: &str| validate_path(path).unwrap();
+        assert_eq!(valid(r"C:\Users\Alice\"), PathBuf::from(r"C:\Users\Alice"));
+        assert_eq!(valid(r"C:\Users\Alice/"), PathBuf::from(r"C:\Users\Alice"));
+        assert_eq!(
+            valid(r"C:/Users\Alice/Pictures"),
+            PathBuf::from(r"C:\Users\Alice\Pictures")
+        );
+        assert_eq!(valid("C:"), PathBuf::from(r"C:\"));
+        assert_eq!(valid(r"C:\"), PathBuf::from(r"C:\"));
+        assert_eq!(valid("C:/"), PathBuf::from(r"C:\"));
+        assert_eq!(valid(r"\\server\share\"), PathBuf::from(r"\\server\share"));
+    }
+
+    #[test]
+    fn validate_path_rejects_traversal_after_normalizing() {
+        assert!(validate_path(r"C:/Users/../Windows/").is_err());
+        assert!(validate_path(r"C:\Users\.\").is_err());
+        assert!(validate_path(r"C:\Users/./Alice").is_err());
+        assert!(validate_path("").is_err());
+    }
+}
@@ -2,11 +2,19 @@
 //!
 //! Thin adapters for item operations that delegate to ItemService.
 
-use crate::application::dto::{BatchTagResult, CreateItemDto, ItemDto, TagDto, UpdateItemDto};
+use crate::application::dto::{
+    BatchProgressDto, BatchTagResult, BulkUpdateResultDto, CreateItemDto, CreateItemsResultDto,
+    DirectoryTagResultDto, ItemDto, ItemWithTagsDto, MetadataRefreshResultDto, PathCheckDto,
+    PathNormalizationDto, TagDto, TagUpdateResultDto, UpdateItemDto,
+};
+use crate::commands::filesystem::validate_path;
 use crate::error::{AppError, AppResult};
 use crate::state::AppState;
 use std::collections::HashMap;
-use tauri::State;
+use tauri::{AppHandle, Emitter, State};
+
+/// Event emitted after each chunk of a batch tag add or remove.
+const BATCH_TAG_PROGRESS_EVENT: &str = "batch-tag-progress";
 
 #[tauri::command]
 pub async fn create_item(
@@ -23,33 +31,69 @@ pub async fn create_item(
         modified_time,
     };
 
+    state.item_service.create(dto).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn create_items(
+    paths: Vec<String>,
+    state: State<'_, AppState>,
+) -> AppResult<CreateItemsResultDto> {
     state
         .item_service
-        .create(dto)
+        .create_many(paths)
         .await
-        .map_err(|e| AppError::InvalidInput(e.to_string()))
+        .map_err(AppError::from)
 }
 
+/// Re-reads size and modification time from disk for items in the directory `prefix`
+/// (all items when omitted) and stores the ones that changed. Cancellable
+/// through `cancel_operation(operation_id)`.
 #[tauri::command]
-pub async fn get_item(id: i64, state: State<'_, AppState>) -> AppResult<ItemDto> {
+pub async fn refresh_item_metadata(
+    prefix: Option<String>,
+    operation_id: Option<String>,
+    state: State<'_, AppState>,
+) -> AppResult<MetadataRefreshResultDto> {
+    let operation = state.cancellation_registry.register(operation_id)?;
+
     state
         .item_service
-        .get_by_id(id)
+        .refresh_metadata(prefix, operation.token())
         .await
-        .map_err(|e| AppError::InvalidInput(e.to_string()))?
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_item(
+    id: i64,
+    include_tag_count: Option<bool>,
+    state: State<'_, AppState>,
+) -> AppResult<ItemDto> {
+    state
+        .item_service
+        .get_by_id(id, include_tag_count.unwrap_or(false))
+        .await?
         .ok_or_else(|| AppError::NotFound(format!("Item with id {}", id)))
 }
 
+/// Same as `get_item` with `include_tag_count` set.
+#[tauri::command]
+pub async fn get_item_with_tag_count(id: i64, state: State<'_, AppState>) -> AppResult<ItemDto> {
+    get_item(id, Some(true), state).await
+}
+
 #[tauri::command]
 pub async fn get_item_by_path(
     path: String,
+    include_tag_count: Option<bool>,
     state: State<'_, AppState>,
 ) -> AppResult<Option<ItemDto>> {
     state
         .item_service
-        .get_by_path(&path)
+        .get_by_path(&path, include_tag_count.unwrap_or(false))
         .await
-        .map_err(|e| AppError::InvalidInput(e.to_string()))
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -61,7 +105,74 @@ pub async fn get_items_by_paths(
         .item_service
         .get_by_paths(paths)
         .await
-        .map_err(|e| AppError::InvalidInput(e.to_string()))
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn check_items_by_paths(
+    paths: Vec<String>,
+    state: State<'_, AppState>,
+) -> AppResult<Vec<PathCheckDto>> {
+    state
+        .item_service
+        .check_by_paths(paths)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn rebase_item_paths(
+    old_prefix: String,
+    new_prefix: String,
+    state: State<'_, AppState>,
+) -> AppResult<usize> {
+    state
+        .item_service
+        .rebase_paths(&old_prefix, &new_prefix)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn bulk_rename_path_prefix(
+    old_prefix: String,
+    new_prefix: String,
+    state: State<'_, AppState>,
+) -> AppResult<BulkUpdateResultDto> {
+    state
+        .item_service
+        .bulk_path_update(old_prefix, new_prefix)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Rewrites stored item paths to their normalized form, merging items whose
+/// paths only differed in drive letter case or separators.
+#[tauri::command]
+pub async fn normalize_item_paths(state: State<'_, AppState>) -> AppResult<PathNormalizationDto> {
+    state
+        .item_service
+        .normalize_paths()
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn soft_delete_items_by_tag(tag_id: i64, state: State<'_, AppState>) -> AppResult<u64> {
+    state
+        .item_service
+        .soft_delete_by_tag(tag_id)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn restore_items_by_tag(tag_id: i64, state: State<'_, AppState>) -> AppResult<u64> {
+    state
+        .item_service
+        .restore_by_tag(tag_id)
+        .await
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -82,16 +193,44 @@ pub async fn update_item(
         .item_service
         .update(id, dto)
         .await
-        .map_err(|e| AppError::InvalidInput(e.to_string()))
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
-pub async fn delete_item(id: i64, state: State<'_, AppState>) -> AppResult<()> {
+pub async fn set_rating(id: i64, rating: Option<u8>, state: State<'_, AppState>) -> AppResult<()> {
+    state
+        .item_service
+        .set_rating(id, rating)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Reads the item's File Reference Number from disk again and records it, so
+/// USN refresh can follow its renames. Returns the updated item.
+#[tauri::command]
+pub async fn refresh_item_frn(item_id: i64, state: State<'_, AppState>) -> AppResult<ItemDto> {
+    state
+        .item_service
+        .refresh_frn(item_id)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_recently_tagged_items(
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> AppResult<Vec<ItemDto>> {
     state
         .item_service
-        .delete(id)
+        .get_recently_tagged(limit)
         .await
-        .map_err(|e| AppError::NotFound(e.to_string()))
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn delete_item(id: i64, state: State<'_, AppState>) -> AppResult<()> {
+    state.item_service.delete(id).await.map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -104,7 +243,39 @@ pub async fn add_tag_to_item(
         .item_service
         .add_tag(item_id, tag_id)
         .await
-        .map_err(|e| AppError::InvalidInput(e.to_string()))
+        .map_err(AppError::from)
+}
+
+/// Queues adding a tag to an item; see `flush_item_writes`.
+#[tauri::command]
+pub async fn enqueue_tag_for_item(
+    item_id: i64,
+    tag_id: i64,
+    state: State<'_, AppState>,
+) -> AppResult<()> {
+    state.item_service.enqueue_tag(item_id, tag_id).await;
+    Ok(())
+}
+
+/// Queues registering a path as an item; see `flush_item_writes`.
+#[tauri::command]
+pub async fn enqueue_create_item(path: String, state: State<'_, AppState>) -> AppResult<()> {
+    state
+        .item_service
+        .enqueue_create(&path)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Writes all queued item and tag writes, retrying failed background
+/// batches; an error means those writes were dropped.
+#[tauri::command]
+pub async fn flush_item_writes(state: State<'_, AppState>) -> AppResult<()> {
+    state
+        .item_service
+        .flush_writes()
+        .await
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -117,7 +288,7 @@ pub async fn remove_tag_from_item(
         .item_service
         .remove_tag(item_id, tag_id)
         .await
-        .map_err(|e| AppError::InvalidInput(e.to_string()))
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -126,7 +297,7 @@ pub async fn get_tags_for_item(item_id: i64, state: State<'_, AppState>) -> AppR
         .item_service
         .get_tags(item_id)
         .await
-        .map_err(|e| AppError::InvalidInput(e.to_string()))
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -138,46 +309,147 @@ pub async fn get_tags_for_items(
         .item_service
         .get_tags_batch(item_ids)
         .await
-        .map_err(|e| AppError::InvalidInput(e.to_string()))
+        .map_err(AppError::from)
+}
+
+/// Gets items and their tags in one call, in `item_ids` order.
+#[tauri::command]
+pub async fn get_items_with_tags(
+    item_ids: Vec<i64>,
+    state: State<'_, AppState>,
+) -> AppResult<Vec<ItemWithTagsDto>> {
+    state
+        .item_service
+        .get_with_tags_batch(item_ids)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Gets the registered items in a directory (and its subdirectories with
+/// `recursive`) together with their tags, for the file browser.
+#[tauri::command]
+pub async fn get_items_in_directory_with_tags(
+    directory: String,
+    recursive: bool,
+    state: State<'_, AppState>,
+) -> AppResult<Vec<ItemWithTagsDto>> {
+    state
+        .item_service
+        .get_in_directory_with_tags(&directory, recursive)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Gets the distinct tags of the active items under a directory, for showing
+/// as the directory's inherited tags.
+#[tauri::command]
+pub async fn get_inherited_tags(
+    directory_path: String,
+    state: State<'_, AppState>,
+) -> AppResult<Vec<TagDto>> {
+    state
+        .item_service
+        .get_inherited_tags(&directory_path)
+        .await
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn update_item_tags(
     item_id: i64,
     tag_ids: Vec<i64>,
+    expected_tags_updated_at: Option<i64>,
     state: State<'_, AppState>,
-) -> AppResult<()> {
+) -> AppResult<TagUpdateResultDto> {
     state
         .item_service
-        .update_tags(item_id, tag_ids)
+        .update_tags(item_id, tag_ids, expected_tags_updated_at)
         .await
-        .map_err(|e| AppError::InvalidInput(e.to_string()))
+        .map_err(AppError::from)
 }
 
+/// `expected_tags_updated_at` maps paths to the `tags_updated_at` the caller
+/// last saw, 0 for never; items whose tags changed since are skipped and
+/// reported as conflicts.
+///
+/// Reports progress via `batch-tag-progress` events every 100 items and is
+/// cancellable through `cancel_operation(operation_id)`.
 #[tauri::command]
 pub async fn batch_add_tag_to_items(
     paths: Vec<String>,
     tag_id: i64,
+    expected_tags_updated_at: Option<HashMap<String, i64>>,
+    operation_id: Option<String>,
+    app: AppHandle,
     state: State<'_, AppState>,
 ) -> AppResult<BatchTagResult> {
+    let operation = state.cancellation_registry.register(operation_id.clone())?;
+
+    state
+        .item_service
+        .batch_add_tag_with_progress(
+            paths,
+            tag_id,
+            expected_tags_updated_at.unwrap_or_default(),
+            operation.token(),
+            emit_batch_progress(app, "add", operation_id),
+        )
+        .await
+        .map_err(AppError::from)
+}
+
+/// Tags every file under a directory, registering missing items.
+/// `recursive` defaults to true and `include_hidden` to false. Cancellable
+/// through `cancel_operation(operation_id)` between batches.
+#[tauri::command]
+pub async fn tag_directory(
+    path: String,
+    tag_id: i64,
+    recursive: Option<bool>,
+    include_hidden: Option<bool>,
+    operation_id: Option<String>,
+    state: State<'_, AppState>,
+) -> AppResult<DirectoryTagResultDto> {
+    let path = validate_path(&path)?;
+    let operation = state.cancellation_registry.register(operation_id)?;
+
     state
         .item_service
-        .batch_add_tag(paths, tag_id)
+        .tag_directory(
+            &path.to_string_lossy(),
+            tag_id,
+            recursive.unwrap_or(true),
+            include_hidden.unwrap_or(false),
+            operation.token(),
+        )
         .await
-        .map_err(|e| AppError::InvalidInput(e.to_string()))
+        .map_err(AppError::from)
 }
 
+/// Skips stale items, reports progress and can be cancelled like
+/// `batch_add_tag_to_items`.
 #[tauri::command]
 pub async fn batch_remove_tag_from_items(
     paths: Vec<String>,
     tag_id: i64,
+    expected_tags_updated_at: Option<HashMap<String, i64>>,
+    operation_id: Option<String>,
+    app: AppHandle,
     state: State<'_, AppState>,
 ) -> AppResult<BatchTagResult> {
+    let operation = state.cancellation_registry.register(operation_id.clone())?;
+
     state
         .item_service
-        .batch_remove_tag(paths, tag_id)
+        .batch_remove_tag_with_progress(
+            paths,
+            tag_id,
+            expected_tags_updated_at.unwrap_or_default(),
+            operation.token(),
+            emit_batch_progress(app, "remove", operation_id),
+        )
         .await
-        .map_err(|e| AppError::InvalidInput(e.to_string()))
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -189,5 +461,24 @@ pub async fn get_common_tags_for_paths(
         .item_service
         .get_common_tags(paths)
         .await
-        .map_err(|e| AppError::InvalidInput(e.to_string()))
+        .map_err(AppError::from)
+}
+
+/// Progress callback emitting `batch-tag-progress` events for one operation.
+fn emit_batch_progress(
+    app: AppHandle,
+    operation: &'static str,
+    operation_id: Option<String>,
+) -> impl Fn(usize, usize) + Send + Sync {
+    move |processed, total| {
+        let payload = BatchProgressDto {
+            operation_id: operation_id.clone(),
+            operation: operation.to_string(),
+            processed: processed as u64,
+            total: total as u64,
+        };
+        if let Err(e) = app.emit(BATCH_TAG_PROGRESS_EVENT, payload) {
+            log::warn!("Failed to emit batch tag progress: {}", e);
+        }
+    }
 }
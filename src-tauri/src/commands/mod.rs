@@ -1,6 +1,11 @@
+pub mod auto_tag_rules;
+pub mod collections;
+pub mod data;
+pub mod diagnostics;
 pub mod file_monitor;
 pub mod filesystem;
 pub mod items;
+pub mod operations;
 pub mod search;
 pub mod settings;
 pub mod tag_groups;
@@ -0,0 +1,14 @@
+//! Operation Commands
+//!
+//! Control of long-running commands started with an `operation_id`.
+
+use crate::error::AppResult;
+use crate::state::AppState;
+use tauri::State;
+
+/// Cancels a running operation. It stops at its next check and returns a
+/// partial result marked `cancelled`. Returns false if no operation has this id.
+#[tauri::command]
+pub async fn cancel_operation(operation_id: String, state: State<'_, AppState>) -> AppResult<bool> {
+    Ok(state.cancellation_registry.cancel(&operation_id))
+}
@@ -2,59 +2,161 @@
 //!
 //! Thin adapters for search operations that delegate to SearchService.
 
-use crate::application::dto::{ItemDto, SearchCriteriaDto, SearchHistoryDto, SearchMode};
+use crate::application::cancellation::CancellationToken;
+use crate::application::dto::{
+    CqlSearchResultDto, FilenameMatchMode, GroupBy, ItemDto, ItemSort, ParsedQuerySpecDto,
+    QuerySpecDto, RecommendationDto, SearchHistoryDto, SearchMode, SearchRequest, SearchResultsDto,
+    SimilarItemDto, TimeField,
+};
 use crate::error::{AppError, AppResult};
 use crate::state::AppState;
 use tauri::State;
 
 /// Runs USN refresh on all NTFS drives before a search so results reflect recent file moves/deletes.
 async fn refresh_before_search(state: &AppState) {
-    let drives: Vec<char> = ('A'..='Z')
-        .filter(|&c| crate::infrastructure::usn_journal::is_ntfs(c).unwrap_or(false))
-        .collect();
+    let drives = crate::infrastructure::usn_journal::usn_capable_drives();
 
-    if let Err(e) = state.usn_refresh_service.refresh(&drives).await {
-        eprintln!("[USN] pre-search refresh failed: {}", e);
+    if let Err(e) = state
+        .usn_refresh_service()
+        .refresh(&drives, &CancellationToken::new())
+        .await
+    {
+        log::warn!("USN pre-search refresh failed: {}", e);
     }
 }
 
+#[tauri::command]
+pub async fn search(
+    request: SearchRequest,
+    state: State<'_, AppState>,
+) -> AppResult<SearchResultsDto> {
+    refresh_before_search(&state).await;
+    state
+        .search_service
+        .search_with_options(request, false)
+        .await
+        .map_err(AppError::from)
+}
+
 #[tauri::command]
 pub async fn search_items_by_tags_and(
     tag_ids: Vec<i64>,
+    sort: Option<ItemSort>,
+    include_tag_count: Option<bool>,
     state: State<'_, AppState>,
 ) -> AppResult<Vec<ItemDto>> {
     refresh_before_search(&state).await;
+    let request = SearchRequest::Tags {
+        tag_ids,
+        mode: SearchMode::And,
+        sort: sort.unwrap_or_default(),
+        include_tag_count: include_tag_count.unwrap_or(false),
+    };
+
     state
         .search_service
-        .search_by_tags_and(tag_ids)
+        .search(request)
         .await
-        .map_err(|e| AppError::InvalidInput(e.to_string()))
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn search_items_by_tags_or(
     tag_ids: Vec<i64>,
+    sort: Option<ItemSort>,
+    include_tag_count: Option<bool>,
     state: State<'_, AppState>,
 ) -> AppResult<Vec<ItemDto>> {
     refresh_before_search(&state).await;
+    let request = SearchRequest::Tags {
+        tag_ids,
+        mode: SearchMode::Or,
+        sort: sort.unwrap_or_default(),
+        include_tag_count: include_tag_count.unwrap_or(false),
+    };
+
+    state
+        .search_service
+        .search(request)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_items_by_tag(tag_id: i64, state: State<'_, AppState>) -> AppResult<Vec<ItemDto>> {
+    refresh_before_search(&state).await;
+    state
+        .search_service
+        .items_by_tag(tag_id)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_similar_items(
+    item_id: i64,
+    min_shared: Option<u32>,
+    state: State<'_, AppState>,
+) -> AppResult<Vec<SimilarItemDto>> {
     state
         .search_service
-        .search_by_tags_or(tag_ids)
+        .similar_items(item_id, min_shared)
         .await
-        .map_err(|e| AppError::InvalidInput(e.to_string()))
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_recommendations(
+    item_id: i64,
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> AppResult<Vec<RecommendationDto>> {
+    state
+        .similarity_service
+        .recommend(item_id, limit.unwrap_or(20))
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn search_items_by_time_window(
+    window_hours: u32,
+    time_field: TimeField,
+    tag_ids: Option<Vec<i64>>,
+    state: State<'_, AppState>,
+) -> AppResult<Vec<ItemDto>> {
+    refresh_before_search(&state).await;
+    state
+        .search_service
+        .search_by_time_window(window_hours, time_field, tag_ids.unwrap_or_default())
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_recent_changes(hours: u32, state: State<'_, AppState>) -> AppResult<Vec<ItemDto>> {
+    search_items_by_time_window(hours, TimeField::Modified, None, state).await
 }
 
 #[tauri::command]
 pub async fn search_items_by_filename(
     query: String,
+    match_mode: Option<FilenameMatchMode>,
+    sort: Option<ItemSort>,
     state: State<'_, AppState>,
 ) -> AppResult<Vec<ItemDto>> {
     refresh_before_search(&state).await;
+    let request = SearchRequest::Filename {
+        query,
+        match_mode: match_mode.unwrap_or_default(),
+        sort: sort.unwrap_or_default(),
+    };
+
     state
         .search_service
-        .search_by_filename(&query)
+        .search(request)
         .await
-        .map_err(|e| AppError::InvalidInput(e.to_string()))
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -62,42 +164,139 @@ pub async fn search_items(
     tag_ids: Vec<i64>,
     mode: SearchMode,
     filename_query: Option<String>,
+    sort: Option<ItemSort>,
+    include_tags: Option<bool>,
     state: State<'_, AppState>,
-) -> AppResult<Vec<ItemDto>> {
+) -> AppResult<SearchResultsDto> {
     refresh_before_search(&state).await;
-    let criteria = SearchCriteriaDto {
+    let request = SearchRequest::Combined {
         tag_ids,
         mode,
         filename_query,
+        sort: sort.unwrap_or_default(),
     };
 
     state
         .search_service
-        .search(criteria)
+        .search_with_options(request, include_tags.unwrap_or(false))
         .await
-        .map_err(|e| AppError::InvalidInput(e.to_string()))
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
-pub async fn search_cql(query: String, state: State<'_, AppState>) -> AppResult<Vec<ItemDto>> {
+pub async fn search_items_excluding_tags(
+    exclude_tag_ids: Vec<i64>,
+    state: State<'_, AppState>,
+) -> AppResult<Vec<ItemDto>> {
     refresh_before_search(&state).await;
     state
         .search_service
-        .search_cql(&query)
+        .search_items_excluding_tags(exclude_tag_ids)
         .await
-        .map_err(|e| AppError::InvalidInput(e.to_string()))
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
-pub async fn get_recent_search_history(
+pub async fn search_items_mixed(
+    include_tag_ids: Vec<i64>,
+    exclude_tag_ids: Vec<i64>,
+    mode: SearchMode,
+    state: State<'_, AppState>,
+) -> AppResult<Vec<ItemDto>> {
+    refresh_before_search(&state).await;
+    state
+        .search_service
+        .search_items_mixed(include_tag_ids, exclude_tag_ids, mode)
+        .await
+        .map_err(AppError::from)
+}
+
+/// `search_items_mixed` in AND mode: items with all of `include_tag_ids`
+/// and none of `exclude_tag_ids`.
+#[tauri::command]
+pub async fn search_items_with_exclusions(
+    include_tag_ids: Vec<i64>,
+    exclude_tag_ids: Vec<i64>,
+    state: State<'_, AppState>,
+) -> AppResult<Vec<ItemDto>> {
+    search_items_mixed(include_tag_ids, exclude_tag_ids, SearchMode::And, state).await
+}
+
+#[tauri::command]
+pub async fn search_cql(
+    query: String,
+    group_by: Option<GroupBy>,
+    request_id: Option<String>,
+    include_tags: Option<bool>,
+    state: State<'_, AppState>,
+) -> AppResult<CqlSearchResultDto> {
+    refresh_before_search(&state).await;
+    state
+        .search_service
+        .search_cql(
+            &query,
+            group_by.unwrap_or_default(),
+            request_id,
+            include_tags.unwrap_or(false),
+        )
+        .await
+        .map_err(AppError::from)
+}
+
+/// Renders a filter built in the UI as a CQL string, with values quoted
+/// and escaped.
+#[tauri::command]
+pub async fn build_cql(spec: QuerySpecDto, state: State<'_, AppState>) -> AppResult<String> {
+    state
+        .search_service
+        .build_cql(&spec)
+        .map_err(AppError::from)
+}
+
+/// Splits a CQL query back into filter clauses, or reports that it can't
+/// be shown as a flat list of them.
+#[tauri::command]
+pub async fn parse_to_spec(
+    query: String,
+    state: State<'_, AppState>,
+) -> AppResult<ParsedQuerySpecDto> {
+    state
+        .search_service
+        .parse_cql_to_spec(&query)
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn cancel_search(request_id: String, state: State<'_, AppState>) -> AppResult<bool> {
+    Ok(state.search_service.cancel(&request_id))
+}
+
+#[tauri::command]
+pub async fn get_group_items(
+    query: String,
+    group_by: GroupBy,
+    group_key: String,
+    offset: usize,
     limit: usize,
     state: State<'_, AppState>,
+) -> AppResult<Vec<ItemDto>> {
+    state
+        .search_service
+        .get_group_items(&query, group_by, group_key, offset, limit)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_recent_search_history(
+    limit: Option<usize>,
+    state: State<'_, AppState>,
 ) -> AppResult<Vec<SearchHistoryDto>> {
     state
         .search_service
         .get_recent_history(limit)
         .await
-        .map_err(|e| AppError::Domain(e.to_string()))
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -106,7 +305,7 @@ pub async fn delete_search_history(id: i64, state: State<'_, AppState>) -> AppRe
         .search_service
         .delete_history(id)
         .await
-        .map_err(|e| AppError::Domain(e.to_string()))
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -115,5 +314,23 @@ pub async fn clear_search_history(state: State<'_, AppState>) -> AppResult<()> {
         .search_service
         .clear_history()
         .await
-        .map_err(|e| AppError::Domain(e.to_string()))
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_search_history_count(state: State<'_, AppState>) -> AppResult<u64> {
+    state
+        .search_service
+        .history_count()
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn set_search_history_limit(limit: u32, state: State<'_, AppState>) -> AppResult<u64> {
+    state
+        .search_service
+        .set_history_limit(limit)
+        .await
+        .map_err(AppError::from)
 }
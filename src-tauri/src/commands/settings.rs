@@ -2,18 +2,26 @@
 //!
 //! Thin adapters for settings operations that delegate to SettingsService.
 
+use crate::application::dto::{BenchmarkDto, LibraryStatsDto, SettingChangedDto, SettingDto};
+use crate::domain::entities::{SettingCategory, SettingsDefaults};
 use crate::error::{AppError, AppResult};
+use crate::infrastructure::logging::{self, LOG_LEVEL_KEY};
 use crate::state::AppState;
 use std::collections::HashMap;
-use tauri::State;
+use tauri::{AppHandle, Emitter, State};
+
+/// Event emitted for each setting whose effective value was changed by the backend.
+const SETTING_CHANGED_EVENT: &str = "setting-changed";
 
 #[tauri::command]
-pub async fn get_all_settings(state: State<'_, AppState>) -> AppResult<HashMap<String, String>> {
+pub async fn get_all_settings(
+    state: State<'_, AppState>,
+) -> AppResult<HashMap<String, SettingDto>> {
     state
         .settings_service
-        .get_all()
+        .get_all_detailed()
         .await
-        .map_err(|e| AppError::Domain(e.to_string()))
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -22,18 +30,118 @@ pub async fn update_setting(
     value: String,
     state: State<'_, AppState>,
 ) -> AppResult<()> {
+    state.settings_service.set(&key, &value).await?;
+
+    if key == LOG_LEVEL_KEY {
+        logging::apply_level(Some(&value));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn reset_setting(key: String, state: State<'_, AppState>) -> AppResult<()> {
+    state.settings_service.reset(&key).await?;
+
+    if key == LOG_LEVEL_KEY {
+        logging::apply_level(None);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn reset_settings(
+    category: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> AppResult<Vec<String>> {
+    let category = category.map(|c| c.parse::<SettingCategory>()).transpose()?;
+
+    let keys = state.settings_service.reset_category(category).await?;
+
+    if keys.iter().any(|key| key == LOG_LEVEL_KEY) {
+        logging::apply_level(None);
+    }
+
+    for key in &keys {
+        let payload = SettingChangedDto {
+            key: key.clone(),
+            value: SettingsDefaults::get(key).map(str::to_string),
+        };
+        if let Err(e) = app.emit(SETTING_CHANGED_EVENT, payload) {
+            log::warn!("Failed to emit setting change: {}", e);
+        }
+    }
+    Ok(keys)
+}
+
+#[tauri::command]
+pub async fn get_recent_logs(
+    lines: Option<usize>,
+    state: State<'_, AppState>,
+) -> AppResult<Vec<String>> {
+    let lines = lines.unwrap_or(200).min(5000);
+    let path = logging::log_file(&state.app_data_dir);
+    Ok(
+        tokio::task::spawn_blocking(move || logging::read_recent(&path, lines))
+            .await
+            .map_err(|e| AppError::Domain(e.to_string()))??,
+    )
+}
+
+#[tauri::command]
+pub async fn explain_cql(query: String, state: State<'_, AppState>) -> AppResult<Vec<String>> {
     state
-        .settings_service
-        .set(&key, &value)
+        .search_service
+        .explain_cql(&query)
         .await
-        .map_err(|e| AppError::Domain(e.to_string()))
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
-pub async fn reset_setting(key: String, state: State<'_, AppState>) -> AppResult<()> {
+pub async fn benchmark_cql(
+    query: String,
+    iterations: u32,
+    state: State<'_, AppState>,
+) -> AppResult<BenchmarkDto> {
+    state
+        .search_service
+        .benchmark_cql(&query, iterations)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn rebuild_indexes(state: State<'_, AppState>) -> AppResult<()> {
+    state
+        .search_service
+        .rebuild_indexes()
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_library_stats(state: State<'_, AppState>) -> AppResult<LibraryStatsDto> {
+    state
+        .search_service
+        .library_stats()
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_schema_version(state: State<'_, AppState>) -> AppResult<u32> {
+    state
+        .search_service
+        .schema_version()
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn reset_onboarding(state: State<'_, AppState>) -> AppResult<bool> {
     state
         .settings_service
-        .reset(&key)
+        .reset_onboarding()
         .await
-        .map_err(|e| AppError::Domain(e.to_string()))
+        .map_err(AppError::from)
 }
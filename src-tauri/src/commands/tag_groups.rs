@@ -2,7 +2,10 @@
 //!
 //! Thin adapters for tag group operations that delegate to TagGroupService.
 
-use crate::application::dto::{CreateTagGroupDto, TagGroupDto, UpdateTagGroupDto};
+use crate::application::dto::{
+    CreateTagGroupDto, ExclusiveViolationDto, TagGroupDto, UpdateTagGroupDto,
+};
+use crate::domain::entities::TagGroupSortMode;
 use crate::error::{AppError, AppResult};
 use crate::state::AppState;
 use tauri::State;
@@ -12,24 +15,50 @@ pub async fn create_tag_group(
     name: String,
     color: Option<String>,
     _display_order: Option<i32>, // Ignored - auto-assigned by service
+    exclusive: Option<bool>,
     state: State<'_, AppState>,
 ) -> AppResult<i64> {
-    let dto = CreateTagGroupDto { name, color };
+    let dto = CreateTagGroupDto {
+        name,
+        color,
+        exclusive: exclusive.unwrap_or(false),
+    };
 
     state
         .tag_group_service
         .create(dto)
         .await
-        .map_err(|e| AppError::InvalidInput(e.to_string()))
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn get_tag_groups(state: State<'_, AppState>) -> AppResult<Vec<TagGroupDto>> {
+    // Unknown or unreadable setting values fall back to display order
+    let sort_mode = state
+        .settings_service
+        .get("tag_group_sort_mode")
+        .await
+        .ok()
+        .flatten()
+        .and_then(|v| v.parse::<TagGroupSortMode>().ok())
+        .unwrap_or_default();
+
     state
         .tag_group_service
-        .get_all()
+        .get_all(sort_mode)
         .await
-        .map_err(|e| AppError::InvalidInput(e.to_string()))
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn set_tag_group_sort_mode(mode: String, state: State<'_, AppState>) -> AppResult<()> {
+    let mode = mode.parse::<TagGroupSortMode>()?;
+
+    state
+        .settings_service
+        .set("tag_group_sort_mode", mode.as_str())
+        .await
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -38,15 +67,20 @@ pub async fn update_tag_group(
     name: Option<String>,
     color: Option<String>,
     _display_order: Option<i32>, // Handled separately via reorder
+    exclusive: Option<bool>,
     state: State<'_, AppState>,
 ) -> AppResult<()> {
-    let dto = UpdateTagGroupDto { name, color };
+    let dto = UpdateTagGroupDto {
+        name,
+        color,
+        exclusive,
+    };
 
     state
         .tag_group_service
         .update(id, dto)
         .await
-        .map_err(|e| AppError::InvalidInput(e.to_string()))
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -55,7 +89,21 @@ pub async fn delete_tag_group(id: i64, state: State<'_, AppState>) -> AppResult<
         .tag_group_service
         .delete(id)
         .await
-        .map_err(|e| AppError::NotFound(e.to_string()))
+        .map_err(AppError::from)
+}
+
+/// Lists items carrying more than one tag of the group, e.g. before
+/// switching it to exclusive.
+#[tauri::command]
+pub async fn get_exclusive_violations(
+    group_id: i64,
+    state: State<'_, AppState>,
+) -> AppResult<Vec<ExclusiveViolationDto>> {
+    state
+        .tag_group_service
+        .exclusive_violations(group_id)
+        .await
+        .map_err(AppError::from)
 }
 
 #[derive(serde::Deserialize)]
@@ -78,5 +126,16 @@ pub async fn reorder_tag_groups(
         .tag_group_service
         .reorder(orders)
         .await
-        .map_err(|e| AppError::InvalidInput(e.to_string()))
+        .map_err(AppError::from)
+}
+
+/// Renumbers all groups sequentially by current order, then name, whatever
+/// state the stored orders are in. Returns the groups in their new order.
+#[tauri::command]
+pub async fn normalize_tag_group_order(state: State<'_, AppState>) -> AppResult<Vec<TagGroupDto>> {
+    state
+        .tag_group_service
+        .normalize_order()
+        .await
+        .map_err(AppError::from)
 }
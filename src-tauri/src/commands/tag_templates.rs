@@ -2,7 +2,10 @@
 //!
 //! Thin adapters for tag template operations that delegate to TagTemplateService.
 
-use crate::application::dto::{CreateTagTemplateDto, TagTemplateDto, UpdateTagTemplateDto};
+use crate::application::dto::{
+    CreateTagTemplateDto, TagSpecDto, TagTemplateDto, TemplateApplicabilityDto,
+    TemplateApplyPreviewDto, TemplatePreviewDto, UpdateTagTemplateDto,
+};
 use crate::error::{AppError, AppResult};
 use crate::state::AppState;
 use tauri::State;
@@ -11,15 +14,20 @@ use tauri::State;
 pub async fn create_tag_template(
     name: String,
     tag_ids: Vec<i64>,
+    tag_specs: Option<Vec<TagSpecDto>>,
     state: State<'_, AppState>,
 ) -> AppResult<i64> {
-    let dto = CreateTagTemplateDto { name, tag_ids };
+    let dto = CreateTagTemplateDto {
+        name,
+        tag_ids,
+        tag_specs: tag_specs.unwrap_or_default(),
+    };
 
     state
         .tag_template_service
         .create(dto)
         .await
-        .map_err(|e| AppError::InvalidInput(e.to_string()))
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -28,20 +36,59 @@ pub async fn get_tag_templates(state: State<'_, AppState>) -> AppResult<Vec<TagT
         .tag_template_service
         .get_all()
         .await
-        .map_err(|e| AppError::InvalidInput(e.to_string()))
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn apply_tag_template(
     item_id: i64,
     template_id: i64,
+    preview_token: Option<String>,
     state: State<'_, AppState>,
 ) -> AppResult<()> {
     state
         .tag_template_service
-        .apply_to_item(template_id, item_id)
+        .apply_to_item(template_id, item_id, preview_token.as_deref())
         .await
-        .map_err(|e| AppError::InvalidInput(e.to_string()))
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn preview_tag_template(
+    item_id: i64,
+    template_id: i64,
+    state: State<'_, AppState>,
+) -> AppResult<TemplatePreviewDto> {
+    state
+        .tag_template_service
+        .preview_for_item(template_id, item_id)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn preview_apply_template(
+    template_id: i64,
+    paths: Vec<String>,
+    state: State<'_, AppState>,
+) -> AppResult<TemplateApplyPreviewDto> {
+    state
+        .tag_template_service
+        .preview_apply(template_id, paths)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_applicable_templates(
+    item_id: i64,
+    state: State<'_, AppState>,
+) -> AppResult<Vec<TemplateApplicabilityDto>> {
+    state
+        .tag_template_service
+        .find_applicable_templates(item_id)
+        .await
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -50,7 +97,7 @@ pub async fn delete_tag_template(id: i64, state: State<'_, AppState>) -> AppResu
         .tag_template_service
         .delete(id)
         .await
-        .map_err(|e| AppError::NotFound(e.to_string()))
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -58,13 +105,18 @@ pub async fn update_tag_template(
     id: i64,
     name: Option<String>,
     tag_ids: Option<Vec<i64>>,
+    tag_specs: Option<Vec<TagSpecDto>>,
     state: State<'_, AppState>,
 ) -> AppResult<()> {
-    let dto = UpdateTagTemplateDto { name, tag_ids };
+    let dto = UpdateTagTemplateDto {
+        name,
+        tag_ids,
+        tag_specs,
+    };
 
     state
         .tag_template_service
         .update(id, dto)
         .await
-        .map_err(|e| AppError::InvalidInput(e.to_string()))
+        .map_err(AppError::from)
 }
@@ -2,7 +2,9 @@
 //!
 //! Thin adapters for tag operations that delegate to TagService.
 
-use crate::application::dto::{CreateTagDto, TagDto, UpdateTagDto};
+use crate::application::dto::{
+    CreateTagDto, PagedTagsDto, TagCountDto, TagDto, TagPairDto, UpdateTagDto,
+};
 use crate::error::{AppError, AppResult};
 use crate::state::AppState;
 use std::collections::HashMap;
@@ -16,11 +18,7 @@ pub async fn create_tag(
 ) -> AppResult<i64> {
     let dto = CreateTagDto { group_id, value };
 
-    state
-        .tag_service
-        .create(dto)
-        .await
-        .map_err(|e| AppError::InvalidInput(e.to_string()))
+    state.tag_service.create(dto).await.map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -32,16 +30,41 @@ pub async fn get_tags_by_group(
         .tag_service
         .get_by_group(group_id)
         .await
-        .map_err(|e| AppError::InvalidInput(e.to_string()))
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn get_all_tags(state: State<'_, AppState>) -> AppResult<Vec<TagDto>> {
+    state.tag_service.get_all().await.map_err(AppError::from)
+}
+
+/// One page of all tags plus the total, for lists that load as they scroll.
+#[tauri::command]
+pub async fn get_all_tags_paged(
+    limit: u32,
+    offset: u32,
+    state: State<'_, AppState>,
+) -> AppResult<PagedTagsDto> {
     state
         .tag_service
-        .get_all()
+        .get_all_paged(limit, offset)
         .await
-        .map_err(|e| AppError::InvalidInput(e.to_string()))
+        .map_err(AppError::from)
+}
+
+/// One page of a group's tags plus the group's total.
+#[tauri::command]
+pub async fn get_all_tags_by_group_paged(
+    group_id: i64,
+    limit: u32,
+    offset: u32,
+    state: State<'_, AppState>,
+) -> AppResult<PagedTagsDto> {
+    state
+        .tag_service
+        .get_by_group_paged(group_id, limit, offset)
+        .await
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -57,7 +80,7 @@ pub async fn update_tag(
         .tag_service
         .update(id, dto)
         .await
-        .map_err(|e| AppError::InvalidInput(e.to_string()))
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -70,16 +93,50 @@ pub async fn merge_tags(
         .tag_service
         .merge(source_id, target_id)
         .await
-        .map_err(|e| AppError::InvalidInput(e.to_string()))
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
-pub async fn delete_tag(id: i64, state: State<'_, AppState>) -> AppResult<()> {
+pub async fn split_tag(
+    id: i64,
+    into_tag_ids: Vec<i64>,
+    remove_source: bool,
+    state: State<'_, AppState>,
+) -> AppResult<usize> {
     state
         .tag_service
-        .delete(id)
+        .split_tag(id, into_tag_ids, remove_source)
         .await
-        .map_err(|e| AppError::NotFound(e.to_string()))
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn move_tag_to_group(
+    id: i64,
+    target_group_id: i64,
+    merge: bool,
+    state: State<'_, AppState>,
+) -> AppResult<i64> {
+    state
+        .tag_service
+        .move_to_group(id, target_group_id, merge)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Removes the tag from every item, keeping the tag. Returns the count removed.
+#[tauri::command]
+pub async fn clear_tag_usage(id: i64, state: State<'_, AppState>) -> AppResult<usize> {
+    state
+        .tag_service
+        .clear_usage(id)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn delete_tag(id: i64, state: State<'_, AppState>) -> AppResult<()> {
+    state.tag_service.delete(id).await.map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -88,7 +145,21 @@ pub async fn get_tag_usage_counts(state: State<'_, AppState>) -> AppResult<HashM
         .tag_service
         .get_usage_counts()
         .await
-        .map_err(|e| AppError::InvalidInput(e.to_string()))
+        .map_err(AppError::from)
+}
+
+/// Gets pairs of tags used together on at least `min_count` items, for the
+/// tag relationship graph.
+#[tauri::command]
+pub async fn get_tag_cooccurrence(
+    min_count: Option<u64>,
+    state: State<'_, AppState>,
+) -> AppResult<Vec<TagPairDto>> {
+    state
+        .tag_service
+        .get_co_occurrence(min_count)
+        .await
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -101,5 +172,42 @@ pub async fn search_tags(
         .tag_service
         .search(&query, group_id)
         .await
-        .map_err(|e| AppError::InvalidInput(e.to_string()))
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_tags_among_items(
+    item_ids: Vec<i64>,
+    state: State<'_, AppState>,
+) -> AppResult<Vec<TagCountDto>> {
+    state
+        .tag_service
+        .get_tags_among_items(&item_ids)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_tag_suggestions(
+    group_id: i64,
+    state: State<'_, AppState>,
+) -> AppResult<Vec<String>> {
+    state
+        .tag_service
+        .suggestions(group_id)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn set_tag_suggestions(
+    group_id: i64,
+    suggestions: Vec<String>,
+    state: State<'_, AppState>,
+) -> AppResult<Vec<String>> {
+    state
+        .tag_service
+        .set_suggestions(group_id, suggestions)
+        .await
+        .map_err(AppError::from)
 }
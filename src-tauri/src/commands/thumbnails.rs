@@ -2,37 +2,167 @@
 //!
 //! Thin adapters for thumbnail cache operations.
 
-use crate::application::dto::CacheStatsDto;
+use crate::application::dto::{CacheMigrationProgressDto, CacheStatsDto};
+use crate::application::services::CacheStats;
 use crate::error::{AppError, AppResult};
 use crate::state::AppState;
-use tauri::State;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use tauri::{AppHandle, Emitter, State};
+
+/// Event emitted while a relocated cache is migrated or cleared.
+const CACHE_MIGRATION_EVENT: &str = "thumbnail-cache-migration";
 
 #[tauri::command]
 pub async fn get_cache_stats(state: State<'_, AppState>) -> AppResult<CacheStatsDto> {
     let stats = state
-        .thumbnail_service
+        .thumbnail_service()
+        .await?
         .cache_stats()
         .await
         .map_err(|e| AppError::Thumbnail(e.to_string()))?;
 
-    Ok(CacheStatsDto {
-        total_size_bytes: stats.total_size_bytes,
-        file_count: stats.file_count,
-        max_size_bytes: stats.max_size_bytes,
-    })
+    Ok(to_dto(stats))
 }
 
 #[tauri::command]
 pub async fn clear_thumbnail_cache(state: State<'_, AppState>) -> AppResult<CacheStatsDto> {
     let stats = state
-        .thumbnail_service
+        .thumbnail_service()
+        .await?
         .clear_cache()
         .await
         .map_err(|e| AppError::Thumbnail(e.to_string()))?;
 
-    Ok(CacheStatsDto {
-        total_size_bytes: stats.total_size_bytes,
-        file_count: stats.file_count,
+    Ok(to_dto(stats))
+}
+
+#[tauri::command]
+pub async fn clear_thumbnail_memory_cache(state: State<'_, AppState>) -> AppResult<CacheStatsDto> {
+    let stats = state
+        .thumbnail_service()
+        .await?
+        .clear_memory_cache()
+        .await
+        .map_err(|e| AppError::Thumbnail(e.to_string()))?;
+
+    Ok(to_dto(stats))
+}
+
+/// Deletes the cached thumbnails of `path` at every size, e.g. after the
+/// file changed or was removed. Returns the number of entries removed.
+#[tauri::command]
+pub async fn invalidate_thumbnail(path: String, state: State<'_, AppState>) -> AppResult<usize> {
+    state
+        .thumbnail_service()
+        .await?
+        .invalidate(&path)
+        .await
+        .map_err(|e| AppError::Thumbnail(e.to_string()))
+}
+
+/// On-disk WebP file of the cached thumbnail of `path` at `size`, for use by
+/// external tools. `None` if no such thumbnail has been generated.
+#[tauri::command]
+pub async fn get_cached_thumbnail_path(
+    path: String,
+    size: u32,
+    state: State<'_, AppState>,
+) -> AppResult<Option<String>> {
+    let cached = state
+        .thumbnail_service()
+        .await?
+        .cached_path(&path, size)
+        .await;
+    Ok(cached.map(|p| p.to_string_lossy().to_string()))
+}
+
+/// WebP thumbnails of `path` at each of `sizes`, by size. Sizes not yet
+/// cached are rendered from one generation, so asking for a list and a
+/// preview size together costs a single decode.
+#[tauri::command]
+pub async fn get_thumbnails(
+    path: String,
+    sizes: Vec<u32>,
+    state: State<'_, AppState>,
+) -> AppResult<BTreeMap<u32, Vec<u8>>> {
+    if sizes.is_empty() {
+        return Err(AppError::InvalidInput(
+            "No thumbnail sizes given".to_string(),
+        ));
+    }
+    let metadata = std::fs::metadata(&path)?;
+    let mtime = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+
+    state
+        .thumbnail_service()
+        .await?
+        .get_thumbnails(&path, mtime, metadata.len(), sizes)
+        .await
+        .map_err(|e| AppError::Thumbnail(e.to_string()))
+}
+
+/// Moves the thumbnail disk cache to `path` (empty = default AppData location).
+/// A custom location gets its own `constellation-thumbs` subdirectory.
+///
+/// Returns the resolved directory once the new cache is active. Existing files
+/// are moved (`migrate`) or deleted in the background; progress is reported via
+/// `thumbnail-cache-migration` events.
+#[tauri::command]
+pub async fn set_cache_directory(
+    path: String,
+    migrate: bool,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> AppResult<String> {
+    let trimmed = path.trim();
+    let dir = if trimmed.is_empty() {
+        None
+    } else {
+        let dir = PathBuf::from(trimmed);
+        if !dir.is_absolute() {
+            return Err(AppError::InvalidInput(format!(
+                "Cache directory must be an absolute path: {}",
+                trimmed
+            )));
+        }
+        Some(dir)
+    };
+
+    let resolved = state
+        .thumbnail_service()
+        .await?
+        .relocate_cache(dir, migrate, move |progress| {
+            let payload = CacheMigrationProgressDto {
+                processed: progress.processed,
+                total: progress.total,
+                done: progress.done,
+                error: progress.error,
+            };
+            if let Err(e) = app.emit(CACHE_MIGRATION_EVENT, payload) {
+                log::warn!("Failed to emit cache migration progress: {}", e);
+            }
+        })
+        .await
+        .map_err(|e| AppError::Thumbnail(e.to_string()))?;
+
+    Ok(resolved.to_string_lossy().to_string())
+}
+
+fn to_dto(stats: CacheStats) -> CacheStatsDto {
+    CacheStatsDto {
+        memory_entries: stats.memory_entries,
+        memory_bytes: stats.memory_bytes,
+        disk_file_count: stats.disk_file_count,
+        disk_size_bytes: stats.disk_size_bytes,
         max_size_bytes: stats.max_size_bytes,
-    })
+        cache_dir: stats.cache_dir,
+        warming: stats.warming,
+        warmed_count: stats.warmed_count,
+    }
 }
@@ -0,0 +1,190 @@
+//! AutoTagRule Entity
+//!
+//! Applies a tag template to items registered under a directory.
+
+use crate::domain::errors::DomainError;
+use crate::domain::value_objects::FilePath;
+
+/// Applies a template's tags to every item created under `path_prefix`.
+#[derive(Debug, Clone)]
+pub struct AutoTagRule {
+    id: Option<i64>,
+    path_prefix: String,
+    template_id: i64,
+    enabled: bool,
+    created_at: Option<i64>,
+    updated_at: Option<i64>,
+}
+
+impl AutoTagRule {
+    /// Creates a new AutoTagRule (not yet persisted).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the prefix is not a valid path.
+    pub fn new(path_prefix: &str, template_id: i64, enabled: bool) -> Result<Self, DomainError> {
+        Ok(Self {
+            id: None,
+            path_prefix: Self::normalize_prefix(path_prefix)?,
+            template_id,
+            enabled,
+            created_at: None,
+            updated_at: None,
+        })
+    }
+
+    /// Reconstitutes an AutoTagRule from persistence.
+    pub fn reconstitute(
+        id: i64,
+        path_prefix: String,
+        template_id: i64,
+        enabled: bool,
+        created_at: i64,
+        updated_at: i64,
+    ) -> Self {
+        Self {
+            id: Some(id),
+            path_prefix,
+            template_id,
+            enabled,
+            created_at: Some(created_at),
+            updated_at: Some(updated_at),
+        }
+    }
+
+    /// Validates a prefix and drops trailing separators, so `D:\Inbox\` and
+    /// `D:\Inbox` name the same directory.
+    fn normalize_prefix(path_prefix: &str) -> Result<String, DomainError> {
+        let path = FilePath::new(path_prefix)?;
+        let trimmed = path.as_str().trim_end_matches(['\\', '/']);
+        if trimmed.is_empty() {
+            return Err(DomainError::InvalidFilePath(
+                "Rule prefix cannot be a bare separator".to_string(),
+            ));
+        }
+        Ok(trimmed.to_string())
+    }
+
+    // Getters
+
+    pub fn id(&self) -> Option<i64> {
+        self.id
+    }
+
+    pub fn path_prefix(&self) -> &str {
+        &self.path_prefix
+    }
+
+    pub fn template_id(&self) -> i64 {
+        self.template_id
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn created_at(&self) -> Option<i64> {
+        self.created_at
+    }
+
+    pub fn updated_at(&self) -> Option<i64> {
+        self.updated_at
+    }
+
+    // Domain behavior
+
+    /// Updates the directory the rule applies to.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the prefix is not a valid path.
+    pub fn update_path_prefix(&mut self, path_prefix: &str) -> Result<(), DomainError> {
+        self.path_prefix = Self::normalize_prefix(path_prefix)?;
+        Ok(())
+    }
+
+    /// Updates the template the rule applies.
+    pub fn update_template(&mut self, template_id: i64) {
+        self.template_id = template_id;
+    }
+
+    /// Enables or disables the rule.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Returns true if `path` is the prefix directory itself or lies under it.
+    ///
+    /// Matching is by whole path components and ignores ASCII case, like
+    /// Windows paths: `D:\Inbox` covers `d:\inbox\a.png` but not `D:\Inbox2\a.png`.
+    pub fn covers(&self, path: &str) -> bool {
+        let len = self.path_prefix.len();
+        let Some(head) = path.get(..len) else {
+            return false;
+        };
+        head.eq_ignore_ascii_case(&self.path_prefix)
+            && matches!(path.as_bytes().get(len), None | Some(b'\\') | Some(b'/'))
+    }
+
+    /// Sets the ID after persistence (used by repository).
+    pub fn set_id(&mut self, id: i64) {
+        self.id = Some(id);
+    }
+}
+
+/// Picks the enabled rule with the longest prefix covering `path`, so a rule
+/// for a subdirectory overrides one for its parent.
+pub fn select_auto_tag_rule<'a>(rules: &'a [AutoTagRule], path: &str) -> Option<&'a AutoTagRule> {
+    rules
+        .iter()
+        .filter(|rule| rule.enabled && rule.covers(path))
+        .max_by_key(|rule| rule.path_prefix.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(id: i64, prefix: &str, template_id: i64, enabled: bool) -> AutoTagRule {
+        let mut rule = AutoTagRule::new(prefix, template_id, enabled).unwrap();
+        rule.set_id(id);
+        rule
+    }
+
+    #[test]
+    fn prefixes_are_normalized_and_validated() {
+        let rule = AutoTagRule::new(" D:\\Inbox\\ ", 1, true).unwrap();
+        assert_eq!(rule.path_prefix(), "D:\\Inbox");
+        assert!(AutoTagRule::new("", 1, true).is_err());
+        assert!(AutoTagRule::new("\\", 1, true).is_err());
+        assert!(AutoTagRule::new("D:\\Inbox\\..\\Secret", 1, true).is_err());
+    }
+
+    #[test]
+    fn covers_whole_components_ignoring_case() {
+        let inbox = rule(1, "D:\\Inbox", 1, true);
+        assert!(inbox.covers("D:\\Inbox"));
+        assert!(inbox.covers("d:\\inbox\\shot.png"));
+        assert!(inbox.covers("D:\\Inbox/nested/shot.png"));
+        assert!(!inbox.covers("D:\\Inbox2\\shot.png"));
+        assert!(!inbox.covers("D:\\Inbo"));
+        assert!(!inbox.covers("C:\\Inbox\\shot.png"));
+    }
+
+    #[test]
+    fn longest_enabled_prefix_wins() {
+        let rules = vec![
+            rule(1, "D:\\Inbox", 10, true),
+            rule(2, "D:\\Inbox\\Screenshots", 20, true),
+            rule(3, "D:\\Inbox\\Screenshots\\Old", 30, false),
+        ];
+
+        let pick = |path| select_auto_tag_rule(&rules, path).map(|r| r.template_id());
+        assert_eq!(pick("D:\\Inbox\\Screenshots\\a.png"), Some(20));
+        // The deeper rule is disabled, so its parent applies
+        assert_eq!(pick("D:\\Inbox\\Screenshots\\Old\\a.png"), Some(20));
+        assert_eq!(pick("D:\\Inbox\\Downloads\\a.zip"), Some(10));
+        assert_eq!(pick("D:\\Inbox Screenshots\\a.png"), None);
+        assert_eq!(pick("E:\\a.png"), None);
+    }
+}
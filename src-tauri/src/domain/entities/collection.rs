@@ -0,0 +1,149 @@
+//! Collection Entity
+//!
+//! A named CQL query shown in the sidebar like a virtual folder.
+
+use crate::domain::errors::DomainError;
+
+/// A persisted CQL query whose matching items form a virtual folder.
+#[derive(Debug, Clone)]
+pub struct Collection {
+    id: Option<i64>,
+    name: String,
+    cql_query: String,
+    icon: Option<String>,
+    display_order: i32,
+    created_at: Option<i64>,
+    updated_at: Option<i64>,
+}
+
+impl Collection {
+    /// Creates a new Collection (not yet persisted).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name or query is empty.
+    pub fn new(
+        name: &str,
+        cql_query: &str,
+        icon: Option<String>,
+        display_order: i32,
+    ) -> Result<Self, DomainError> {
+        Ok(Self {
+            id: None,
+            name: Self::validate_name(name)?,
+            cql_query: Self::validate_query(cql_query)?,
+            icon: Self::normalize_icon(icon),
+            display_order,
+            created_at: None,
+            updated_at: None,
+        })
+    }
+
+    /// Reconstitutes a Collection from persistence.
+    pub fn reconstitute(
+        id: i64,
+        name: String,
+        cql_query: String,
+        icon: Option<String>,
+        display_order: i32,
+        created_at: i64,
+        updated_at: i64,
+    ) -> Self {
+        Self {
+            id: Some(id),
+            name,
+            cql_query,
+            icon,
+            display_order,
+            created_at: Some(created_at),
+            updated_at: Some(updated_at),
+        }
+    }
+
+    fn validate_name(name: &str) -> Result<String, DomainError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(DomainError::ValidationError(
+                "Collection name cannot be empty".to_string(),
+            ));
+        }
+        Ok(name.to_string())
+    }
+
+    fn validate_query(cql_query: &str) -> Result<String, DomainError> {
+        let cql_query = cql_query.trim();
+        if cql_query.is_empty() {
+            return Err(DomainError::ValidationError(
+                "Collection query cannot be empty".to_string(),
+            ));
+        }
+        Ok(cql_query.to_string())
+    }
+
+    /// Treats a blank icon as no icon.
+    fn normalize_icon(icon: Option<String>) -> Option<String> {
+        icon.map(|i| i.trim().to_string()).filter(|i| !i.is_empty())
+    }
+
+    // Getters
+
+    pub fn id(&self) -> Option<i64> {
+        self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn cql_query(&self) -> &str {
+        &self.cql_query
+    }
+
+    pub fn icon(&self) -> Option<&str> {
+        self.icon.as_deref()
+    }
+
+    pub fn display_order(&self) -> i32 {
+        self.display_order
+    }
+
+    pub fn created_at(&self) -> Option<i64> {
+        self.created_at
+    }
+
+    pub fn updated_at(&self) -> Option<i64> {
+        self.updated_at
+    }
+
+    // Domain behavior
+
+    /// Renames the collection.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name is empty.
+    pub fn update_name(&mut self, name: &str) -> Result<(), DomainError> {
+        self.name = Self::validate_name(name)?;
+        Ok(())
+    }
+
+    /// Replaces the collection's query.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query is empty.
+    pub fn update_query(&mut self, cql_query: &str) -> Result<(), DomainError> {
+        self.cql_query = Self::validate_query(cql_query)?;
+        Ok(())
+    }
+
+    /// Sets the icon; an empty string clears it.
+    pub fn update_icon(&mut self, icon: Option<String>) {
+        self.icon = Self::normalize_icon(icon);
+    }
+
+    /// Sets the ID after persistence (used by repository).
+    pub fn set_id(&mut self, id: i64) {
+        self.id = Some(id);
+    }
+}
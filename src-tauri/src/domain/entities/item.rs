@@ -2,8 +2,12 @@
 //!
 //! Represents a file or directory that can be tagged.
 
+use crate::domain::errors::DomainError;
 use crate::domain::value_objects::FilePath;
 
+/// Highest allowed star rating.
+pub const MAX_RATING: u8 = 5;
+
 /// Represents a file or directory item in the system.
 #[derive(Debug, Clone)]
 pub struct Item {
@@ -15,6 +19,12 @@ pub struct Item {
     file_reference_number: u64,
     created_at: Option<i64>,
     updated_at: Option<i64>,
+    /// Last time the item's tag set changed (None if never tagged).
+    tags_updated_at: Option<i64>,
+    /// Star rating from 0 to `MAX_RATING` (None if unrated).
+    rating: Option<u8>,
+    /// When the photo was taken, from EXIF (None for other files).
+    taken_at: Option<i64>,
 }
 
 impl Item {
@@ -35,10 +45,14 @@ impl Item {
             file_reference_number,
             created_at: None,
             updated_at: None,
+            tags_updated_at: None,
+            rating: None,
+            taken_at: None,
         }
     }
 
     /// Reconstitutes an Item from persistence.
+    #[allow(clippy::too_many_arguments)]
     pub fn reconstitute(
         id: i64,
         path: FilePath,
@@ -48,6 +62,9 @@ impl Item {
         file_reference_number: u64,
         created_at: i64,
         updated_at: i64,
+        tags_updated_at: Option<i64>,
+        rating: Option<u8>,
+        taken_at: Option<i64>,
     ) -> Self {
         Self {
             id: Some(id),
@@ -58,6 +75,9 @@ impl Item {
             file_reference_number,
             created_at: Some(created_at),
             updated_at: Some(updated_at),
+            tags_updated_at,
+            rating,
+            taken_at,
         }
     }
 
@@ -91,10 +111,22 @@ impl Item {
         self.updated_at
     }
 
+    pub fn tags_updated_at(&self) -> Option<i64> {
+        self.tags_updated_at
+    }
+
     pub fn file_reference_number(&self) -> u64 {
         self.file_reference_number
     }
 
+    pub fn rating(&self) -> Option<u8> {
+        self.rating
+    }
+
+    pub fn taken_at(&self) -> Option<i64> {
+        self.taken_at
+    }
+
     // Domain behavior
 
     /// Updates the item's path.
@@ -112,6 +144,23 @@ impl Item {
         self.modified_time = modified_time;
     }
 
+    /// Updates when the photo was taken.
+    pub fn update_taken_at(&mut self, taken_at: Option<i64>) {
+        self.taken_at = taken_at;
+    }
+
+    /// Sets or clears the star rating.
+    pub fn set_rating(&mut self, rating: Option<u8>) -> Result<(), DomainError> {
+        if rating.is_some_and(|r| r > MAX_RATING) {
+            return Err(DomainError::ValidationError(format!(
+                "Rating must be between 0 and {}",
+                MAX_RATING
+            )));
+        }
+        self.rating = rating;
+        Ok(())
+    }
+
     /// Updates the file reference number (e.g. after cross-volume move).
     pub fn update_file_reference_number(&mut self, frn: u64) {
         self.file_reference_number = frn;
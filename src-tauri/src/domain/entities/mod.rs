@@ -3,6 +3,8 @@
 //! Entities are objects that have a distinct identity that runs through time
 //! and different states. They encapsulate domain logic and behavior.
 
+mod auto_tag_rule;
+mod collection;
 mod item;
 mod search_history;
 mod settings;
@@ -10,9 +12,14 @@ mod tag;
 mod tag_group;
 mod tag_template;
 
+pub use auto_tag_rule::{AutoTagRule, select_auto_tag_rule};
+pub use collection::Collection;
 pub use item::Item;
 pub use search_history::{SearchCriteria, SearchHistory};
-pub use settings::SettingsDefaults;
+pub use settings::{
+    ONBOARDING_SEEDED_KEY, SettingCategory, SettingsDefaults, TAG_SUGGESTIONS_PREFIX,
+    tag_suggestions_key,
+};
 pub use tag::Tag;
-pub use tag_group::TagGroup;
-pub use tag_template::TagTemplate;
+pub use tag_group::{TagGroup, TagGroupSortMode};
+pub use tag_template::{TagSpec, TagTemplate};
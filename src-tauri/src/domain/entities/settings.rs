@@ -1,8 +1,116 @@
 //! Settings Entity
 //!
-//! Defines default values for application settings.
+//! Defines the known settings, their default values and categories.
 
+use crate::domain::errors::DomainError;
 use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Internal flag set once first-run starter data has been seeded. Not a
+/// user setting, so it has no default and is never reset with the others.
+pub const ONBOARDING_SEEDED_KEY: &str = "onboarding_seeded";
+
+/// Prefix of the per-group keys holding pre-defined tag values as a JSON
+/// array. Like the onboarding flag these are data rather than preferences.
+pub const TAG_SUGGESTIONS_PREFIX: &str = "tag_suggestions_";
+
+/// Returns the settings key holding the suggested values for a tag group.
+pub fn tag_suggestions_key(group_id: i64) -> String {
+    format!("{}{}", TAG_SUGGESTIONS_PREFIX, group_id)
+}
+
+/// Settings screen section a setting belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SettingCategory {
+    Thumbnail,
+    Usn,
+    Search,
+    Ui,
+    Diagnostics,
+}
+
+impl SettingCategory {
+    /// Returns the wire name for this category.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Thumbnail => "thumbnail",
+            Self::Usn => "usn",
+            Self::Search => "search",
+            Self::Ui => "ui",
+            Self::Diagnostics => "diagnostics",
+        }
+    }
+}
+
+impl FromStr for SettingCategory {
+    type Err = DomainError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "thumbnail" => Ok(Self::Thumbnail),
+            "usn" => Ok(Self::Usn),
+            "search" => Ok(Self::Search),
+            "ui" => Ok(Self::Ui),
+            "diagnostics" => Ok(Self::Diagnostics),
+            _ => Err(DomainError::ValidationError(format!(
+                "Unknown settings category: {}",
+                s
+            ))),
+        }
+    }
+}
+
+/// Known setting keys with their default values and categories.
+const SCHEMA: &[(&str, &str, SettingCategory)] = &[
+    ("usn_auto_refresh", "false", SettingCategory::Usn),
+    ("usn_refresh_on_missing", "true", SettingCategory::Usn),
+    ("usn_cross_volume_match", "true", SettingCategory::Usn),
+    ("usn_apply_auto_tag_rules", "false", SettingCategory::Usn),
+    ("usn_frn_backfill_limit", "200", SettingCategory::Usn),
+    (
+        "rehydrate_tags_on_path_reuse",
+        "false",
+        SettingCategory::Usn,
+    ),
+    (
+        "index_exclude_patterns",
+        r#"["node_modules", ".git", ".svn", ".hg", "__pycache__", "*.tmp", "~$*", "Thumbs.db", "desktop.ini"]"#,
+        SettingCategory::Usn,
+    ),
+    ("thumbnail_size", "256", SettingCategory::Thumbnail),
+    (
+        "thumbnail_force_shell_cache",
+        "false",
+        SettingCategory::Thumbnail,
+    ),
+    ("thumbnail_max_size", "1024", SettingCategory::Thumbnail),
+    ("thumbnail_cache_max_mb", "500", SettingCategory::Thumbnail),
+    (
+        "thumbnail_memory_cache_entries",
+        "200",
+        SettingCategory::Thumbnail,
+    ),
+    ("thumbnail_worker_count", "0", SettingCategory::Thumbnail),
+    ("thumbnail_semaphore_count", "0", SettingCategory::Thumbnail),
+    ("thumbnail_cache_dir", "", SettingCategory::Thumbnail),
+    ("thumbnail_idle_warm", "false", SettingCategory::Thumbnail),
+    (
+        "thumbnail_folder_collage",
+        "false",
+        SettingCategory::Thumbnail,
+    ),
+    ("search_timeout_secs", "30", SettingCategory::Search),
+    ("search_history_max_entries", "100", SettingCategory::Search),
+    ("cql_max_rows", "50000", SettingCategory::Search),
+    (
+        "rewrite_queries_on_tag_rename",
+        "true",
+        SettingCategory::Search,
+    ),
+    ("tag_group_sort_mode", "display_order", SettingCategory::Ui),
+    ("log_level", "info", SettingCategory::Diagnostics),
+    ("advanced_mode", "false", SettingCategory::Diagnostics),
+];
 
 /// Known setting keys and their default values.
 pub struct SettingsDefaults;
@@ -10,30 +118,25 @@ pub struct SettingsDefaults;
 impl SettingsDefaults {
     /// Returns the default value for a known setting key.
     pub fn get(key: &str) -> Option<&'static str> {
-        match key {
-            "usn_auto_refresh" => Some("false"),
-            "usn_refresh_on_missing" => Some("true"),
-            "usn_cross_volume_match" => Some("true"),
-            "thumbnail_size" => Some("256"),
-            "thumbnail_force_shell_cache" => Some("false"),
-            "thumbnail_cache_max_mb" => Some("500"),
-            "thumbnail_worker_count" => Some("0"),
-            "thumbnail_semaphore_count" => Some("0"),
-            _ => None,
-        }
+        SCHEMA
+            .iter()
+            .find(|(k, _, _)| *k == key)
+            .map(|(_, default, _)| *default)
+    }
+
+    /// Returns the category of a known setting key.
+    pub fn category(key: &str) -> Option<SettingCategory> {
+        SCHEMA
+            .iter()
+            .find(|(k, _, _)| *k == key)
+            .map(|(_, _, category)| *category)
     }
 
     /// Returns all known setting keys with their default values.
     pub fn all() -> HashMap<String, String> {
-        let mut map = HashMap::new();
-        map.insert("usn_auto_refresh".into(), "false".into());
-        map.insert("usn_refresh_on_missing".into(), "true".into());
-        map.insert("usn_cross_volume_match".into(), "true".into());
-        map.insert("thumbnail_size".into(), "256".into());
-        map.insert("thumbnail_force_shell_cache".into(), "false".into());
-        map.insert("thumbnail_cache_max_mb".into(), "500".into());
-        map.insert("thumbnail_worker_count".into(), "0".into());
-        map.insert("thumbnail_semaphore_count".into(), "0".into());
-        map
+        SCHEMA
+            .iter()
+            .map(|(key, default, _)| (key.to_string(), default.to_string()))
+            .collect()
     }
 }
@@ -4,6 +4,7 @@
 
 use crate::domain::errors::DomainError;
 use crate::domain::value_objects::Color;
+use std::str::FromStr;
 
 /// Represents a group of related tags.
 #[derive(Debug, Clone)]
@@ -12,6 +13,8 @@ pub struct TagGroup {
     name: String,
     color: Option<Color>,
     display_order: i32,
+    /// At most one tag of an exclusive group may be on an item.
+    exclusive: bool,
     created_at: Option<i64>,
     updated_at: Option<i64>,
 }
@@ -39,6 +42,7 @@ impl TagGroup {
             name,
             color,
             display_order,
+            exclusive: false,
             created_at: None,
             updated_at: None,
         })
@@ -50,6 +54,7 @@ impl TagGroup {
         name: String,
         color: Option<Color>,
         display_order: i32,
+        exclusive: bool,
         created_at: i64,
         updated_at: i64,
     ) -> Self {
@@ -58,6 +63,7 @@ impl TagGroup {
             name,
             color,
             display_order,
+            exclusive,
             created_at: Some(created_at),
             updated_at: Some(updated_at),
         }
@@ -81,6 +87,10 @@ impl TagGroup {
         self.display_order
     }
 
+    pub fn is_exclusive(&self) -> bool {
+        self.exclusive
+    }
+
     pub fn created_at(&self) -> Option<i64> {
         self.created_at
     }
@@ -112,6 +122,11 @@ impl TagGroup {
         self.color = color;
     }
 
+    /// Sets whether the group allows only one of its tags per item.
+    pub fn set_exclusive(&mut self, exclusive: bool) {
+        self.exclusive = exclusive;
+    }
+
     /// Updates the group's display order.
     #[allow(dead_code)]
     pub fn update_display_order(&mut self, order: i32) {
@@ -134,3 +149,42 @@ impl PartialEq for TagGroup {
 }
 
 impl Eq for TagGroup {}
+
+/// Ordering for listing tag groups, stored in the `tag_group_sort_mode` setting.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TagGroupSortMode {
+    /// User-defined `display_order` (drag-and-drop).
+    #[default]
+    DisplayOrder,
+    /// Case-insensitive by name.
+    Alphabetical,
+    /// Groups with the most tags first.
+    ByTagCount,
+}
+
+impl TagGroupSortMode {
+    /// Returns the setting value for this mode.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::DisplayOrder => "display_order",
+            Self::Alphabetical => "alphabetical",
+            Self::ByTagCount => "by_tag_count",
+        }
+    }
+}
+
+impl FromStr for TagGroupSortMode {
+    type Err = DomainError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "display_order" => Ok(Self::DisplayOrder),
+            "alphabetical" => Ok(Self::Alphabetical),
+            "by_tag_count" => Ok(Self::ByTagCount),
+            _ => Err(DomainError::ValidationError(format!(
+                "Unknown tag group sort mode: {}",
+                s
+            ))),
+        }
+    }
+}
@@ -3,6 +3,44 @@
 //! Represents a saved combination of tags that can be applied to items.
 
 use crate::domain::errors::DomainError;
+use crate::domain::value_objects::TagValue;
+
+/// A tag named by its group and value instead of its ID.
+///
+/// Specs are resolved when the template is applied, creating the group and
+/// tag if they do not exist, so templates carry over between databases.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TagSpec {
+    group_name: String,
+    value: String,
+}
+
+impl TagSpec {
+    /// Creates a TagSpec from a group name and tag value, both trimmed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either is empty.
+    pub fn new(group_name: String, value: String) -> Result<Self, DomainError> {
+        let group_name = group_name.trim().to_string();
+        if group_name.is_empty() {
+            return Err(DomainError::ValidationError(
+                "Tag spec group name cannot be empty".to_string(),
+            ));
+        }
+        let value = TagValue::new(value)?.as_str().to_string();
+
+        Ok(Self { group_name, value })
+    }
+
+    pub fn group_name(&self) -> &str {
+        &self.group_name
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+}
 
 /// Represents a template containing a set of tags.
 #[derive(Debug, Clone)]
@@ -10,6 +48,7 @@ pub struct TagTemplate {
     id: Option<i64>,
     name: String,
     tag_ids: Vec<i64>,
+    tag_specs: Vec<TagSpec>,
     created_at: Option<i64>,
     updated_at: Option<i64>,
 }
@@ -20,7 +59,11 @@ impl TagTemplate {
     /// # Errors
     ///
     /// Returns an error if the name is empty.
-    pub fn new(name: String, tag_ids: Vec<i64>) -> Result<Self, DomainError> {
+    pub fn new(
+        name: String,
+        tag_ids: Vec<i64>,
+        tag_specs: Vec<TagSpec>,
+    ) -> Result<Self, DomainError> {
         let name = name.trim().to_string();
         if name.is_empty() {
             return Err(DomainError::ValidationError(
@@ -32,6 +75,7 @@ impl TagTemplate {
             id: None,
             name,
             tag_ids,
+            tag_specs: dedup_specs(tag_specs),
             created_at: None,
             updated_at: None,
         })
@@ -42,6 +86,7 @@ impl TagTemplate {
         id: i64,
         name: String,
         tag_ids: Vec<i64>,
+        tag_specs: Vec<TagSpec>,
         created_at: i64,
         updated_at: i64,
     ) -> Self {
@@ -49,6 +94,7 @@ impl TagTemplate {
             id: Some(id),
             name,
             tag_ids,
+            tag_specs,
             created_at: Some(created_at),
             updated_at: Some(updated_at),
         }
@@ -68,6 +114,10 @@ impl TagTemplate {
         &self.tag_ids
     }
 
+    pub fn tag_specs(&self) -> &[TagSpec] {
+        &self.tag_specs
+    }
+
     pub fn created_at(&self) -> Option<i64> {
         self.created_at
     }
@@ -99,6 +149,11 @@ impl TagTemplate {
         self.tag_ids = tag_ids;
     }
 
+    /// Updates the template's tag specs.
+    pub fn update_specs(&mut self, tag_specs: Vec<TagSpec>) {
+        self.tag_specs = dedup_specs(tag_specs);
+    }
+
     /// Adds a tag to the template.
     #[allow(dead_code)]
     pub fn add_tag(&mut self, tag_id: i64) {
@@ -129,3 +184,14 @@ impl PartialEq for TagTemplate {
 }
 
 impl Eq for TagTemplate {}
+
+/// Drops repeated specs, keeping the first of each.
+fn dedup_specs(specs: Vec<TagSpec>) -> Vec<TagSpec> {
+    let mut unique: Vec<TagSpec> = Vec::with_capacity(specs.len());
+    for spec in specs {
+        if !unique.contains(&spec) {
+            unique.push(spec);
+        }
+    }
+    unique
+}
@@ -28,6 +28,12 @@ pub enum DomainError {
     #[error("Tag template not found: {0}")]
     TagTemplateNotFound(String),
 
+    #[error("Auto tag rule not found: {0}")]
+    AutoTagRuleNotFound(String),
+
+    #[error("Collection not found: {0}")]
+    CollectionNotFound(String),
+
     #[error("Duplicate entry: {0}")]
     DuplicateEntry(String),
 
@@ -37,6 +43,102 @@ pub enum DomainError {
     #[error("Database error: {0}")]
     DatabaseError(String),
 
+    #[error("Database is busy: {0}")]
+    DatabaseBusy(String),
+
     #[error("USN Journal error: {0}")]
     UsnJournalError(String),
+
+    #[error("Query timed out: {0}")]
+    QueryTimeout(String),
+
+    #[error("Query cancelled: {0}")]
+    QueryCancelled(String),
+
+    #[error("Item was modified concurrently: {0}")]
+    ConcurrentModification(String),
+}
+
+impl From<rusqlite::Error> for DomainError {
+    /// Unique and primary key violations become `DuplicateEntry`, interrupted
+    /// statements `QueryCancelled` and busy or locked databases
+    /// `DatabaseBusy`; anything else is a `DatabaseError`. Repositories whose
+    /// lookups signal a missing row with `QueryReturnedNoRows` map it to their
+    /// own not-found variant first.
+    fn from(e: rusqlite::Error) -> Self {
+        match &e {
+            rusqlite::Error::SqliteFailure(err, _)
+                if err.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_UNIQUE
+                    || err.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_PRIMARYKEY =>
+            {
+                DomainError::DuplicateEntry(e.to_string())
+            }
+            rusqlite::Error::SqliteFailure(err, _)
+                if err.code == rusqlite::ErrorCode::OperationInterrupted =>
+            {
+                DomainError::QueryCancelled(e.to_string())
+            }
+            rusqlite::Error::SqliteFailure(err, _)
+                if err.code == rusqlite::ErrorCode::DatabaseBusy
+                    || err.code == rusqlite::ErrorCode::DatabaseLocked =>
+            {
+                DomainError::DatabaseBusy(e.to_string())
+            }
+            _ => DomainError::DatabaseError(e.to_string()),
+        }
+    }
+}
+
+impl From<deadpool_sqlite::PoolError> for DomainError {
+    fn from(e: deadpool_sqlite::PoolError) -> Self {
+        DomainError::DatabaseError(format!("Pool error: {}", e))
+    }
+}
+
+impl From<deadpool_sqlite::InteractError> for DomainError {
+    fn from(e: deadpool_sqlite::InteractError) -> Self {
+        DomainError::DatabaseError(format!("Interaction error: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    fn sqlite_error(sql: &str) -> rusqlite::Error {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE t (id INTEGER PRIMARY KEY, name TEXT UNIQUE);")
+            .unwrap();
+        conn.execute_batch("INSERT INTO t (id, name) VALUES (1, 'a');")
+            .unwrap();
+        conn.execute_batch(sql).unwrap_err()
+    }
+
+    #[test]
+    fn sqlite_errors_map_to_domain_variants() {
+        assert!(matches!(
+            DomainError::from(sqlite_error("INSERT INTO t (id, name) VALUES (2, 'a');")),
+            DomainError::DuplicateEntry(_)
+        ));
+        assert!(matches!(
+            DomainError::from(sqlite_error("INSERT INTO t (id, name) VALUES (1, 'b');")),
+            DomainError::DuplicateEntry(_)
+        ));
+        assert!(matches!(
+            DomainError::from(sqlite_error("SELECT * FROM missing;")),
+            DomainError::DatabaseError(_)
+        ));
+        assert!(matches!(
+            DomainError::from(rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_BUSY),
+                None
+            )),
+            DomainError::DatabaseBusy(_)
+        ));
+        assert!(matches!(
+            DomainError::from(rusqlite::Error::QueryReturnedNoRows),
+            DomainError::DatabaseError(_)
+        ));
+    }
 }
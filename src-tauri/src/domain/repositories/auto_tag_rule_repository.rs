@@ -0,0 +1,27 @@
+//! AutoTagRule Repository Trait
+//!
+//! Defines the contract for AutoTagRule persistence operations.
+
+use crate::domain::entities::AutoTagRule;
+use crate::domain::errors::DomainError;
+use async_trait::async_trait;
+
+/// Repository trait for AutoTagRule persistence.
+#[async_trait]
+pub trait AutoTagRuleRepository: Send + Sync {
+    /// Saves a new rule and returns its ID. Fails with `DuplicateEntry` if a
+    /// rule for the same prefix (ignoring case) exists.
+    async fn save(&self, rule: &mut AutoTagRule) -> Result<i64, DomainError>;
+
+    /// Finds a rule by its ID.
+    async fn find_by_id(&self, id: i64) -> Result<Option<AutoTagRule>, DomainError>;
+
+    /// Gets all rules, ordered by prefix.
+    async fn find_all(&self) -> Result<Vec<AutoTagRule>, DomainError>;
+
+    /// Updates an existing rule.
+    async fn update(&self, rule: &AutoTagRule) -> Result<(), DomainError>;
+
+    /// Deletes a rule.
+    async fn delete(&self, id: i64) -> Result<(), DomainError>;
+}
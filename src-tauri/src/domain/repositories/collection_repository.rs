@@ -0,0 +1,29 @@
+//! Collection Repository Trait
+//!
+//! Defines the contract for Collection persistence operations.
+
+use crate::domain::entities::Collection;
+use crate::domain::errors::DomainError;
+use async_trait::async_trait;
+
+/// Repository trait for Collection persistence.
+#[async_trait]
+pub trait CollectionRepository: Send + Sync {
+    /// Saves a new collection and returns its ID.
+    async fn save(&self, collection: &mut Collection) -> Result<i64, DomainError>;
+
+    /// Finds a collection by its ID.
+    async fn find_by_id(&self, id: i64) -> Result<Option<Collection>, DomainError>;
+
+    /// Gets all collections ordered by display order.
+    async fn find_all(&self) -> Result<Vec<Collection>, DomainError>;
+
+    /// Updates an existing collection.
+    async fn update(&self, collection: &Collection) -> Result<(), DomainError>;
+
+    /// Deletes a collection.
+    async fn delete(&self, id: i64) -> Result<(), DomainError>;
+
+    /// Sets the display order of the given collections.
+    async fn reorder(&self, orders: Vec<(i64, i32)>) -> Result<(), DomainError>;
+}
@@ -5,16 +5,34 @@
 use crate::domain::entities::Item;
 use crate::domain::errors::DomainError;
 use async_trait::async_trait;
+use std::collections::HashMap;
 
 /// Repository trait for Item persistence.
 #[async_trait]
 pub trait ItemRepository: Send + Sync {
-    /// Saves a new item and returns its ID.
+    /// Saves a new item and returns its ID. Fails with `DuplicateEntry` if an
+    /// item with the same path already exists.
     async fn save(&self, item: &mut Item) -> Result<i64, DomainError>;
 
+    /// Like `save`, but first permanently deletes a soft-deleted item holding
+    /// the same path. If that item was deleted within the last `window_secs`
+    /// seconds, its tags are copied to the new item.
+    async fn save_reusing_path(
+        &self,
+        item: &mut Item,
+        window_secs: i64,
+    ) -> Result<i64, DomainError>;
+
+    /// Inserts the items whose path is not registered yet, in one transaction.
+    /// Returns each item's ID and whether it was created, in input order.
+    async fn save_missing(&self, items: &[Item]) -> Result<Vec<(i64, bool)>, DomainError>;
+
     /// Finds an item by its ID.
     async fn find_by_id(&self, id: i64) -> Result<Option<Item>, DomainError>;
 
+    /// Finds the items with the given IDs (batch query). Unknown IDs are skipped.
+    async fn find_by_ids(&self, ids: &[i64]) -> Result<Vec<Item>, DomainError>;
+
     /// Finds an item by its path.
     async fn find_by_path(&self, path: &str) -> Result<Option<Item>, DomainError>;
 
@@ -36,15 +54,140 @@ pub trait ItemRepository: Send + Sync {
     /// Gets all tag IDs for an item.
     async fn get_tag_ids(&self, item_id: i64) -> Result<Vec<i64>, DomainError>;
 
-    /// Replaces all tags for an item atomically.
-    async fn replace_tags(&self, item_id: i64, tag_ids: Vec<i64>) -> Result<(), DomainError>;
+    /// Counts the tags on an item.
+    async fn count_tags(&self, item_id: i64) -> Result<u64, DomainError>;
+
+    /// Replaces all tags for an item atomically and returns its new
+    /// `tags_updated_at`, 0 if its tags never changed.
+    ///
+    /// With `expected_tags_updated_at`, fails with `ConcurrentModification` if
+    /// the item's tags changed since the caller read that value.
+    async fn replace_tags(
+        &self,
+        item_id: i64,
+        tag_ids: Vec<i64>,
+        expected_tags_updated_at: Option<i64>,
+    ) -> Result<i64, DomainError>;
+
+    /// Adds a template's tags to an item in one transaction and returns its
+    /// new `tags_updated_at`. Tag specs are resolved within the same transaction,
+    /// creating missing groups and tags.
+    ///
+    /// With `expected_tags_updated_at`, fails with `ConcurrentModification`
+    /// like `replace_tags`.
+    async fn apply_template(
+        &self,
+        item_id: i64,
+        template_id: i64,
+        expected_tags_updated_at: Option<i64>,
+    ) -> Result<i64, DomainError>;
 
     /// Adds a tag to multiple items in a single transaction.
-    async fn batch_add_tag(&self, item_ids: &[i64], tag_id: i64) -> Result<(), DomainError>;
+    ///
+    /// Items listed in `expected_tags_updated_at` whose `tags_updated_at` no
+    /// longer matches are skipped; their IDs are returned.
+    async fn batch_add_tag(
+        &self,
+        item_ids: &[i64],
+        tag_id: i64,
+        expected_tags_updated_at: &HashMap<i64, i64>,
+    ) -> Result<Vec<i64>, DomainError>;
+
+    /// Removes a tag from multiple items in a single transaction, skipping
+    /// and returning stale items like `batch_add_tag`.
+    async fn batch_remove_tag(
+        &self,
+        item_ids: &[i64],
+        tag_id: i64,
+        expected_tags_updated_at: &HashMap<i64, i64>,
+    ) -> Result<Vec<i64>, DomainError>;
+
+    /// Adds many `(item_id, tag_id)` links in a single transaction, in order,
+    /// with the same exclusive-group handling as `add_tag`. Pairs naming a
+    /// missing item or tag are skipped. Returns the number of links added.
+    async fn add_tags(&self, pairs: &[(i64, i64)]) -> Result<usize, DomainError>;
+
+    /// Returns the paths among `paths` of active items carrying at least one tag.
+    async fn find_tagged_paths(&self, paths: &[String]) -> Result<Vec<String>, DomainError>;
+
+    /// Finds active items carrying none of `exclude_ids`; every active item
+    /// when it is empty. Ordered by path.
+    async fn find_excluding_tags(&self, exclude_ids: &[i64]) -> Result<Vec<Item>, DomainError>;
+
+    /// Finds active items carrying all of `include_ids` and none of
+    /// `exclude_ids`. Exclusion wins for a tag in both lists; an empty list
+    /// places no constraint. Ordered by path.
+    async fn find_with_include_exclude(
+        &self,
+        include_ids: &[i64],
+        exclude_ids: &[i64],
+    ) -> Result<Vec<Item>, DomainError>;
+
+    /// Finds the active (non-deleted) items at `directory` or anywhere under it,
+    /// matching whole path components: `D:\Photos` does not include
+    /// `D:\Photos2`. An empty `directory` finds every active item.
+    async fn find_active_under(&self, directory: &str) -> Result<Vec<Item>, DomainError>;
+
+    /// Finds the active items directly inside `directory` (given without a
+    /// trailing separator), or anywhere under it with `recursive`. Ordered by path.
+    async fn find_in_directory(
+        &self,
+        directory: &str,
+        recursive: bool,
+    ) -> Result<Vec<Item>, DomainError>;
+
+    /// Replaces `old_prefix` with `new_prefix` on every item path starting with it,
+    /// in a single transaction. Fails with `DuplicateEntry` if any rewritten path
+    /// would collide with an existing one. Returns the number of items updated.
+    async fn rebase_paths(&self, old_prefix: &str, new_prefix: &str) -> Result<usize, DomainError>;
+
+    /// Like `rebase_paths`, but only matches whole path components: rewrites the
+    /// folder `old_dir` itself and everything under `old_dir\` or `old_dir/`,
+    /// leaving siblings such as `old_dir2` untouched.
+    async fn rebase_folder(&self, old_dir: &str, new_dir: &str) -> Result<usize, DomainError>;
+
+    /// Finds active items whose tags changed most recently, newest first.
+    /// Items never tagged are excluded.
+    async fn find_recently_tagged(&self, limit: usize) -> Result<Vec<Item>, DomainError>;
+
+    /// Sets the size, modification time and capture time of each
+    /// `(item_id, size, modified_time, taken_at)` in a single transaction.
+    /// Returns the number of items updated.
+    async fn update_metadata(
+        &self,
+        updates: &[(i64, Option<i64>, Option<i64>, Option<i64>)],
+    ) -> Result<usize, DomainError>;
+
+    /// Finds up to `limit` active items with no File Reference Number recorded
+    /// and an ID above `after_id`, in ID order.
+    async fn find_missing_frn(&self, after_id: i64, limit: usize)
+    -> Result<Vec<Item>, DomainError>;
+
+    /// Records the File Reference Number of an item.
+    async fn update_file_reference_number(&self, item_id: i64, frn: u64)
+    -> Result<(), DomainError>;
+
+    /// Adds the tags of the auto tag rule covering the item's current path,
+    /// unless that rule already covered `previous_path`: an item moved within
+    /// a ruled directory keeps the tags the user left it with. Returns the
+    /// number of tag links changed (0 when no enabled rule applies).
+    async fn apply_auto_tag_rules(
+        &self,
+        item_id: i64,
+        previous_path: Option<&str>,
+    ) -> Result<usize, DomainError>;
+
+    /// Sets or clears an item's star rating.
+    async fn set_rating(&self, item_id: i64, rating: Option<u8>) -> Result<(), DomainError>;
+
+    /// Soft-deletes every active item carrying the tag. Returns the number of items affected.
+    async fn soft_delete_by_tag(&self, tag_id: i64) -> Result<u64, DomainError>;
 
-    /// Removes a tag from multiple items in a single transaction.
-    async fn batch_remove_tag(&self, item_ids: &[i64], tag_id: i64) -> Result<(), DomainError>;
+    /// Restores every soft-deleted item carrying the tag. Returns the number of items affected.
+    async fn restore_by_tag(&self, tag_id: i64) -> Result<u64, DomainError>;
 
-    /// Finds all active (non-deleted) items whose path starts with the given prefix.
-    async fn find_active_by_path_prefix(&self, prefix: &str) -> Result<Vec<Item>, DomainError>;
+    /// Rewrites every stored path to its `FilePath::normalize` form in one
+    /// transaction, merging items whose paths collapse onto the same one.
+    /// Returns the number of items renamed and the number merged away.
+    async fn normalize_paths(&self) -> Result<(usize, usize), DomainError>;
 }
@@ -3,6 +3,8 @@
 //! Repository interfaces define the contract for data persistence.
 //! The domain layer depends on these abstractions, not concrete implementations.
 
+mod auto_tag_rule_repository;
+mod collection_repository;
 mod item_repository;
 mod search_history_repository;
 mod settings_repository;
@@ -10,6 +12,8 @@ mod tag_group_repository;
 mod tag_repository;
 mod tag_template_repository;
 
+pub use auto_tag_rule_repository::AutoTagRuleRepository;
+pub use collection_repository::CollectionRepository;
 pub use item_repository::ItemRepository;
 pub use search_history_repository::SearchHistoryRepository;
 pub use settings_repository::SettingsRepository;
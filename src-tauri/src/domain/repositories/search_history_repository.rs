@@ -8,7 +8,8 @@ pub trait SearchHistoryRepository: Send + Sync {
     /// Saves a search to history.
     /// If an identical criteria exists, updates its `last_used_at`.
     /// If not, inserts a new record.
-    async fn save(&self, criteria: SearchCriteria) -> Result<(), DomainError>;
+    /// Afterwards, the least recently used entries beyond `max_entries` are removed.
+    async fn save(&self, criteria: SearchCriteria, max_entries: usize) -> Result<(), DomainError>;
 
     /// Retrieves the N most recent searches.
     async fn get_recent(&self, limit: usize) -> Result<Vec<SearchHistory>, DomainError>;
@@ -18,4 +19,11 @@ pub trait SearchHistoryRepository: Send + Sync {
 
     /// Clears all history.
     async fn clear_all(&self) -> Result<(), DomainError>;
+
+    /// Removes the least recently used entries beyond `max_entries`.
+    /// Returns the number of entries removed.
+    async fn prune(&self, max_entries: usize) -> Result<u64, DomainError>;
+
+    /// Counts all history entries.
+    async fn count(&self) -> Result<u64, DomainError>;
 }
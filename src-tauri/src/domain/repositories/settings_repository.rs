@@ -19,4 +19,13 @@ pub trait SettingsRepository: Send + Sync {
 
     /// Deletes a setting (resets to default).
     async fn delete(&self, key: &str) -> Result<(), DomainError>;
+
+    /// Deletes several settings in a single transaction.
+    async fn delete_many(&self, keys: &[String]) -> Result<(), DomainError>;
+
+    /// Clears the onboarding flag and re-runs first-run seeding.
+    ///
+    /// Returns whether starter data was inserted; nothing is inserted when
+    /// any tag group exists.
+    async fn reset_onboarding(&self) -> Result<bool, DomainError>;
 }
@@ -2,7 +2,7 @@
 //!
 //! Defines the contract for TagGroup persistence operations.
 
-use crate::domain::entities::TagGroup;
+use crate::domain::entities::{TagGroup, TagGroupSortMode};
 use crate::domain::errors::DomainError;
 use async_trait::async_trait;
 
@@ -15,8 +15,8 @@ pub trait TagGroupRepository: Send + Sync {
     /// Finds a tag group by its ID.
     async fn find_by_id(&self, id: i64) -> Result<Option<TagGroup>, DomainError>;
 
-    /// Gets all tag groups ordered by display order.
-    async fn find_all(&self) -> Result<Vec<TagGroup>, DomainError>;
+    /// Gets all tag groups in the given order.
+    async fn find_all(&self, sort_mode: TagGroupSortMode) -> Result<Vec<TagGroup>, DomainError>;
 
     /// Updates an existing tag group.
     async fn update(&self, group: &TagGroup) -> Result<(), DomainError>;
@@ -27,6 +27,18 @@ pub trait TagGroupRepository: Send + Sync {
     /// Updates the display order of multiple groups atomically.
     async fn reorder(&self, orders: Vec<(i64, i32)>) -> Result<(), DomainError>;
 
+    /// Renumbers all groups 0, 1, 2, ... by their current display order, then
+    /// name, in one transaction. Returns the number of groups whose order
+    /// changed.
+    async fn normalize_order(&self) -> Result<usize, DomainError>;
+
     /// Checks if a tag group exists.
     async fn exists(&self, id: i64) -> Result<bool, DomainError>;
+
+    /// Finds active items carrying more than one tag of the group, as
+    /// (item ID, path, tag IDs) ordered by path.
+    async fn find_exclusive_violations(
+        &self,
+        group_id: i64,
+    ) -> Result<Vec<(i64, String, Vec<i64>)>, DomainError>;
 }
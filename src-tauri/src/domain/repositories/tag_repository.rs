@@ -2,7 +2,7 @@
 //!
 //! Defines the contract for Tag persistence operations.
 
-use crate::domain::entities::Tag;
+use crate::domain::entities::{Tag, TagSpec};
 use crate::domain::errors::DomainError;
 use async_trait::async_trait;
 use std::collections::HashMap;
@@ -17,7 +17,6 @@ pub trait TagRepository: Send + Sync {
     async fn find_by_id(&self, id: i64) -> Result<Option<Tag>, DomainError>;
 
     /// Finds tags by their IDs.
-    #[allow(dead_code)]
     async fn find_by_ids(&self, ids: &[i64]) -> Result<Vec<Tag>, DomainError>;
 
     /// Gets all tags for a specific group.
@@ -26,12 +25,41 @@ pub trait TagRepository: Send + Sync {
     /// Gets all tags.
     async fn find_all(&self) -> Result<Vec<Tag>, DomainError>;
 
+    /// Gets one page of all tags, in `find_all` order, with the total number
+    /// of tags.
+    async fn find_all_paged(&self, limit: u32, offset: u32)
+    -> Result<(Vec<Tag>, u64), DomainError>;
+
+    /// Gets one page of a group's tags, in `find_by_group` order, with the
+    /// group's total number of tags.
+    async fn find_by_group_paged(
+        &self,
+        group_id: i64,
+        limit: u32,
+        offset: u32,
+    ) -> Result<(Vec<Tag>, u64), DomainError>;
+
     /// Updates an existing tag.
     async fn update(&self, tag: &Tag) -> Result<(), DomainError>;
 
     /// Deletes a tag.
     async fn delete(&self, id: i64) -> Result<(), DomainError>;
 
+    /// Finds the tag in a group whose value equals `value` exactly (case-sensitive).
+    async fn find_by_exact_value(
+        &self,
+        group_id: i64,
+        value: &str,
+    ) -> Result<Option<Tag>, DomainError>;
+
+    /// Finds the tags in any group whose value equals `value` exactly
+    /// (case-sensitive), ordered by group.
+    async fn find_by_value_across_groups(&self, value: &str) -> Result<Vec<Tag>, DomainError>;
+
+    /// Finds the existing tags named by `specs`, keyed by spec. Specs whose
+    /// group or value does not exist are left out.
+    async fn find_by_specs(&self, specs: &[TagSpec]) -> Result<HashMap<TagSpec, Tag>, DomainError>;
+
     /// Searches tags by value.
     async fn search(
         &self,
@@ -43,12 +71,34 @@ pub trait TagRepository: Send + Sync {
     /// Gets usage counts for all tags (tag_id -> count).
     async fn get_usage_counts(&self) -> Result<HashMap<i64, i64>, DomainError>;
 
+    /// Gets pairs of tags carried together by at least `min_count` active items,
+    /// as `(tag_id_a, tag_id_b, count)` with `tag_id_a < tag_id_b`. Ordered by
+    /// count (descending), then tag IDs, and capped at `limit` pairs.
+    async fn co_occurrence(
+        &self,
+        min_count: u64,
+        limit: usize,
+    ) -> Result<Vec<(i64, i64, i64)>, DomainError>;
+
     /// Gets tags for a specific item.
     async fn find_by_item(&self, item_id: i64) -> Result<Vec<Tag>, DomainError>;
 
     /// Gets tags for multiple items at once (batch query).
     async fn find_by_items(&self, item_ids: &[i64]) -> Result<HashMap<i64, Vec<Tag>>, DomainError>;
 
+    /// Gets the distinct tags of the active items under `directory` (given
+    /// without a trailing separator), looking at no more than `limit` items.
+    async fn find_under_directory(
+        &self,
+        directory: &str,
+        limit: usize,
+    ) -> Result<Vec<Tag>, DomainError>;
+
+    /// Gets every tag carried by at least one of the given items, with how many of
+    /// those items carry it. Ordered by count (descending), then value.
+    async fn find_tags_among_items(&self, item_ids: &[i64])
+    -> Result<Vec<(Tag, i64)>, DomainError>;
+
     /// Reassigns all item-tag associations from source tag to target tag.
     /// Handles deduplication (skips if target already exists for an item).
     /// Does NOT delete the source tag.
@@ -57,4 +107,29 @@ pub trait TagRepository: Send + Sync {
         source_tag_id: i64,
         target_tag_id: i64,
     ) -> Result<(), DomainError>;
+
+    /// Adds every target tag to each item carrying the source tag (skipping ones it
+    /// already has) and, if `remove_source` is set, deletes the source tag, all in a
+    /// single transaction. Returns the number of items that carried the source tag.
+    async fn split_into(
+        &self,
+        source_tag_id: i64,
+        target_tag_ids: &[i64],
+        remove_source: bool,
+    ) -> Result<usize, DomainError>;
+
+    /// Removes the tag from every item in a single statement, keeping the tag
+    /// itself. Returns the number of associations removed.
+    async fn clear_usage(&self, tag_id: i64) -> Result<usize, DomainError>;
+
+    /// Moves a tag to another group in a single transaction and returns the resulting tag ID.
+    /// If the target group already has a tag with the same value, either fails with
+    /// `DuplicateEntry` or, when `merge_on_conflict` is set, reassigns the source tag's
+    /// items to the existing tag and deletes the source.
+    async fn move_to_group(
+        &self,
+        tag_id: i64,
+        target_group_id: i64,
+        merge_on_conflict: bool,
+    ) -> Result<i64, DomainError>;
 }
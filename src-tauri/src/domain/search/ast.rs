@@ -1,6 +1,16 @@
 //! CQL Abstract Syntax Tree
 //!
-//! Types representing parsed CQL query expressions.
+//! Types representing parsed CQL query expressions, and their rendering
+//! back to canonical CQL.
+
+use super::parser::{escape_string, unix_to_ymd};
+use std::fmt;
+
+/// Bytes per unit, largest first, for rendering sizes.
+const SIZE_UNITS: [(i64, &str); 3] = [(1_073_741_824, "GB"), (1_048_576, "MB"), (1_024, "KB")];
+
+/// Seconds per day, for rendering midnight timestamps as dates.
+const SECS_PER_DAY: i64 = 86_400;
 
 /// Parsed CQL expression tree.
 #[derive(Debug, Clone, PartialEq)]
@@ -25,20 +35,51 @@ pub enum Expr {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Field {
     Tag,
+    /// Tag on the item itself or, for a directory, on any active item under it.
+    AnyTag,
     Name,
     Size,
     Modified,
+    /// When the item's tags last changed.
+    Tagged,
+    /// When the photo was taken (EXIF), falling back to the modified time.
+    Taken,
     Type,
+    /// Star rating (0-5, unrated counts as 0).
+    Rating,
+    /// Name of a tag group the item has at least one tag in.
+    Group,
 }
 
 impl Field {
+    /// Keyword of the field in CQL.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Field::Tag => "tag",
+            Field::AnyTag => "anytag",
+            Field::Name => "name",
+            Field::Size => "size",
+            Field::Modified => "modified",
+            Field::Tagged => "tagged",
+            Field::Taken => "taken",
+            Field::Type => "type",
+            Field::Rating => "rating",
+            Field::Group => "group",
+        }
+    }
+
     pub fn from_str(s: &str) -> Option<Self> {
         match s.to_lowercase().as_str() {
             "tag" => Some(Field::Tag),
+            "anytag" => Some(Field::AnyTag),
             "name" => Some(Field::Name),
             "size" => Some(Field::Size),
             "modified" => Some(Field::Modified),
+            "tagged" => Some(Field::Tagged),
+            "taken" => Some(Field::Taken),
             "type" => Some(Field::Type),
+            "rating" => Some(Field::Rating),
+            "group" => Some(Field::Group),
             _ => None,
         }
     }
@@ -64,6 +105,18 @@ pub enum ComparisonOp {
 }
 
 impl ComparisonOp {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ComparisonOp::Eq => "=",
+            ComparisonOp::NotEq => "!=",
+            ComparisonOp::Like => "~",
+            ComparisonOp::Gt => ">",
+            ComparisonOp::Lt => "<",
+            ComparisonOp::Gte => ">=",
+            ComparisonOp::Lte => "<=",
+        }
+    }
+
     pub fn from_str(s: &str) -> Option<Self> {
         match s {
             "=" => Some(ComparisonOp::Eq),
@@ -90,3 +143,100 @@ pub enum Value {
     /// Pre-converted unix timestamp (e.g. "2024-01-01" → epoch)
     Timestamp(i64),
 }
+
+impl Value {
+    /// The value as it would be typed, without quotes: sizes in their
+    /// largest exact unit and midnight timestamps as `YYYY-MM-DD`.
+    pub fn to_text(&self) -> String {
+        match self {
+            Value::String(s) => s.clone(),
+            Value::Number(n) => n.to_string(),
+            Value::SizeBytes(bytes) if *bytes < 0 => bytes.to_string(),
+            Value::SizeBytes(bytes) => SIZE_UNITS
+                .iter()
+                .find(|(unit, _)| *bytes != 0 && bytes % unit == 0)
+                .map(|(unit, name)| format!("{}{}", bytes / unit, name))
+                .unwrap_or_else(|| format!("{}B", bytes)),
+            Value::Timestamp(ts) if is_date(*ts) => {
+                let (year, month, day) = unix_to_ymd(*ts);
+                format!("{:04}-{:02}-{:02}", year, month, day)
+            }
+            Value::Timestamp(ts) => ts.to_string(),
+        }
+    }
+}
+
+fn is_date(ts: i64) -> bool {
+    ts >= 0 && ts % SECS_PER_DAY == 0
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl fmt::Display for ComparisonOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Strings and dates are quoted, escaped with `escape_string`.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let quoted = match self {
+            Value::String(_) => true,
+            Value::Timestamp(ts) => is_date(*ts),
+            Value::Number(_) | Value::SizeBytes(_) => false,
+        };
+        if quoted {
+            write!(f, "\"{}\"", escape_string(&self.to_text()))
+        } else {
+            f.write_str(&self.to_text())
+        }
+    }
+}
+
+/// Canonical CQL that parses back to the same tree. Parentheses are added
+/// only where precedence (NOT > AND > OR) or the left-to-right grouping of
+/// AND and OR needs them.
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Comparison { field, op, value } => write!(f, "{} {} {}", field, op, value),
+            Expr::InExpr { field, values } => {
+                write!(f, "{} IN (", field)?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                f.write_str(")")
+            }
+            Expr::Or(left, right) => {
+                write_operand(f, left, false)?;
+                f.write_str(" OR ")?;
+                write_operand(f, right, matches!(**right, Expr::Or(..)))
+            }
+            Expr::And(left, right) => {
+                write_operand(f, left, matches!(**left, Expr::Or(..)))?;
+                f.write_str(" AND ")?;
+                write_operand(f, right, matches!(**right, Expr::Or(..) | Expr::And(..)))
+            }
+            Expr::Not(inner) => {
+                f.write_str("NOT ")?;
+                write_operand(f, inner, matches!(**inner, Expr::Or(..) | Expr::And(..)))
+            }
+        }
+    }
+}
+
+fn write_operand(f: &mut fmt::Formatter<'_>, expr: &Expr, parens: bool) -> fmt::Result {
+    if parens {
+        write!(f, "({})", expr)
+    } else {
+        write!(f, "{}", expr)
+    }
+}
@@ -0,0 +1,117 @@
+//! Fuzzy Matching
+//!
+//! Subsequence-based scorer used to rank filename search results.
+
+/// Points for each matched character.
+const MATCH_SCORE: i64 = 16;
+/// Bonus when a match directly follows the previous one.
+const CONSECUTIVE_BONUS: i64 = 24;
+/// Bonus when a match starts a word (after a separator or at a camelCase hump).
+const WORD_START_BONUS: i64 = 20;
+/// Penalty per candidate character skipped between matches.
+const GAP_PENALTY: i64 = 2;
+/// Cap on the penalty for characters before the first match.
+const MAX_LEADING_PENALTY: i64 = 12;
+
+/// Scores `query` against `candidate` as a case-insensitive subsequence.
+///
+/// Returns `None` unless every query character appears in order. Higher is
+/// better: consecutive runs, word-start matches and shorter candidates win.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    let query: Vec<char> = query.chars().flat_map(char::to_lowercase).collect();
+    let original: Vec<char> = candidate.chars().collect();
+    let lower: Vec<char> = original
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect();
+
+    let first = *query.first()?;
+
+    // Greedy matching from the leftmost start can miss a better later run
+    // ("ab" in "a_xab"), so try every position of the first character.
+    (0..lower.len())
+        .filter(|&start| lower[start] == first)
+        .filter_map(|start| score_from(&query, &original, &lower, start))
+        .max()
+}
+
+fn score_from(query: &[char], original: &[char], lower: &[char], start: usize) -> Option<i64> {
+    let mut score = -(start as i64).min(MAX_LEADING_PENALTY);
+    let mut prev: Option<usize> = None;
+    let mut pos = start;
+
+    for &qc in query {
+        let idx = (pos..lower.len()).find(|&i| lower[i] == qc)?;
+
+        score += MATCH_SCORE;
+        if is_word_start(original, idx) {
+            score += WORD_START_BONUS;
+        }
+        match prev {
+            Some(p) if idx == p + 1 => score += CONSECUTIVE_BONUS,
+            Some(p) => score -= (idx - p - 1) as i64 * GAP_PENALTY,
+            None => {}
+        }
+
+        prev = Some(idx);
+        pos = idx + 1;
+    }
+
+    // Prefer tighter candidates: "report" beats "report_final_v2" for "report"
+    score -= (lower.len() - query.len()) as i64;
+    Some(score)
+}
+
+fn is_word_start(chars: &[char], idx: usize) -> bool {
+    if idx == 0 {
+        return true;
+    }
+    let prev = chars[idx - 1];
+    let cur = chars[idx];
+    matches!(prev, ' ' | '_' | '-' | '.' | '\\' | '/')
+        || (prev.is_lowercase() && cur.is_uppercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requires_every_character_in_order() {
+        assert!(fuzzy_score("rpt", "report.pdf").is_some());
+        assert!(fuzzy_score("tpr", "report.pdf").is_none());
+        assert!(fuzzy_score("", "report.pdf").is_none());
+    }
+
+    #[test]
+    fn is_case_insensitive() {
+        assert_eq!(
+            fuzzy_score("report", "REPORT.pdf"),
+            fuzzy_score("report", "report.pdf")
+        );
+    }
+
+    #[test]
+    fn ranks_closer_matches_higher() {
+        let exact = fuzzy_score("report", "report.pdf").unwrap();
+        let longer = fuzzy_score("report", "report_final_v2.pdf").unwrap();
+        let scattered = fuzzy_score("report", "red_pot_art.pdf").unwrap();
+        assert!(exact > longer);
+        assert!(longer > scattered);
+    }
+
+    #[test]
+    fn rewards_word_starts() {
+        let boundary = fuzzy_score("fr", "final_report.pdf").unwrap();
+        let inner = fuzzy_score("fr", "xfxrxxxxxxxxxxxx").unwrap();
+        assert!(boundary > inner);
+        assert!(fuzzy_score("fr", "FinalReport.pdf").unwrap() > inner);
+    }
+
+    #[test]
+    fn finds_best_run_not_first_occurrence() {
+        let best = fuzzy_score("ab", "a_xab").unwrap();
+        let greedy_only = fuzzy_score("ab", "a_xxb").unwrap();
+        assert!(best > greedy_only);
+    }
+}
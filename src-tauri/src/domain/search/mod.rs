@@ -1,14 +1,17 @@
 //! CQL Search Module
 //!
 //! Pest-based parser for CQL (Constellation Query Language) queries.
-//! Parses query strings into an AST for SQL generation.
+//! Parses query strings into an AST for SQL generation. Also hosts the
+//! fuzzy scorer used to rank filename searches.
 
 pub mod ast;
 pub mod error;
+pub mod fuzzy;
 pub mod parser;
 
 #[allow(unused_imports)]
 pub use ast::{ComparisonOp, Expr, Field, Value};
 #[allow(unused_imports)]
 pub use error::CqlParseError;
-pub use parser::parse_cql;
+pub use fuzzy::fuzzy_score;
+pub use parser::{parse_cql, rename_tag_value, value_from_text};
@@ -138,6 +138,7 @@ fn build_primary(pair: pest::iterators::Pair<Rule>) -> Result<Expr, CqlParseErro
         Rule::expression => build_expression(pair),
         Rule::comparison => build_comparison(pair),
         Rule::in_expr => build_in_expr(pair),
+        Rule::func_call => build_func_call(pair),
         _ => Err(CqlParseError::SyntaxError(format!(
             "Unexpected rule: {:?}",
             pair.as_rule()
@@ -169,6 +170,49 @@ fn build_comparison(pair: pest::iterators::Pair<Rule>) -> Result<Expr, CqlParseE
     Ok(Expr::Comparison { field, op, value })
 }
 
+/// Builds a string function call (`contains`, `starts_with` or `ends_with`)
+/// as a `~` comparison with the argument wrapped in `*` wildcards. `*` and
+/// `?` inside the argument still act as wildcards.
+fn build_func_call(pair: pest::iterators::Pair<Rule>) -> Result<Expr, CqlParseError> {
+    let mut inner = pair.into_inner();
+
+    let name = inner
+        .next()
+        .ok_or_else(|| CqlParseError::InternalError("Missing function name".to_string()))?
+        .as_str()
+        .to_lowercase();
+
+    let field_pair = inner
+        .next()
+        .ok_or_else(|| CqlParseError::InternalError("Missing field in function".to_string()))?;
+    let field = parse_field(field_pair.as_str())?;
+
+    let raw = inner
+        .next()
+        .and_then(|p| p.into_inner().next())
+        .ok_or_else(|| CqlParseError::InternalError("Missing function argument".to_string()))?
+        .as_str();
+    let arg = unescape_string(raw);
+
+    let pattern = match name.as_str() {
+        "contains" => format!("*{}*", arg),
+        "starts_with" => format!("{}*", arg),
+        "ends_with" => format!("*{}", arg),
+        _ => {
+            return Err(CqlParseError::SyntaxError(format!(
+                "Unknown function: {}",
+                name
+            )));
+        }
+    };
+
+    Ok(Expr::Comparison {
+        field,
+        op: ComparisonOp::Like,
+        value: Value::String(pattern),
+    })
+}
+
 /// Builds an IN expression (field IN (values...)).
 fn build_in_expr(pair: pest::iterators::Pair<Rule>) -> Result<Expr, CqlParseError> {
     let mut inner = pair.into_inner();
@@ -209,6 +253,7 @@ fn parse_value(pair: pest::iterators::Pair<Rule>, field: Field) -> Result<Value,
 
     match inner.as_rule() {
         Rule::quoted_string => {
+            let quoted = inner.as_str();
             let raw = inner
                 .into_inner()
                 .next()
@@ -216,31 +261,34 @@ fn parse_value(pair: pest::iterators::Pair<Rule>, field: Field) -> Result<Value,
                 .as_str();
             let unescaped = unescape_string(raw);
 
-            // For modified field, try to parse as date
-            if field == Field::Modified {
-                let ts = parse_date_to_timestamp(&unescaped)?;
-                Ok(Value::Timestamp(ts))
-            } else {
-                Ok(Value::String(unescaped))
+            // For date fields, try to parse as date
+            match field {
+                Field::Modified | Field::Tagged | Field::Taken => {
+                    parse_date_to_timestamp(&unescaped).map(Value::Timestamp)
+                }
+                Field::Rating => Err(invalid_rating(&unescaped)),
+                Field::Size => Err(mismatched_value(field, quoted)),
+                _ => Ok(Value::String(unescaped)),
             }
         }
-        Rule::size_literal => {
-            let bytes = parse_size_to_bytes(inner.as_str())?;
-            Ok(Value::SizeBytes(bytes))
-        }
+        Rule::size_literal => match field {
+            Field::Size => parse_size_to_bytes(inner.as_str()).map(Value::SizeBytes),
+            Field::Rating => Err(invalid_rating(inner.as_str())),
+            _ => Err(mismatched_value(field, inner.as_str())),
+        },
         Rule::number => {
             let n: f64 = inner
                 .as_str()
                 .parse()
                 .map_err(|_| CqlParseError::SyntaxError("Invalid number".to_string()))?;
 
-            // For size field, treat raw number as bytes
-            if field == Field::Size {
-                Ok(Value::SizeBytes(n as i64))
-            } else if field == Field::Modified {
-                Ok(Value::Timestamp(n as i64))
-            } else {
-                Ok(Value::Number(n))
+            match field {
+                // For size field, treat raw number as bytes
+                Field::Size => Ok(Value::SizeBytes(n as i64)),
+                Field::Modified | Field::Tagged | Field::Taken => Ok(Value::Timestamp(n as i64)),
+                Field::Rating if n.fract() != 0.0 => Err(invalid_rating(inner.as_str())),
+                Field::Rating => Ok(Value::Number(n)),
+                _ => Err(mismatched_value(field, inner.as_str())),
             }
         }
         _ => Err(CqlParseError::SyntaxError(format!(
@@ -250,6 +298,27 @@ fn parse_value(pair: pest::iterators::Pair<Rule>, field: Field) -> Result<Value,
     }
 }
 
+fn invalid_rating(raw: &str) -> CqlParseError {
+    CqlParseError::SyntaxError(format!("Rating must be a whole number: {}", raw))
+}
+
+/// Error for a value whose kind does not fit `field`, such as a number
+/// compared to a tag or a quoted string compared to a size.
+fn mismatched_value(field: Field, raw: &str) -> CqlParseError {
+    let expected = match field {
+        Field::Size => "a size or a byte count",
+        Field::Modified | Field::Tagged | Field::Taken => "a date or a timestamp",
+        Field::Rating => "a whole number",
+        Field::Tag | Field::AnyTag | Field::Name | Field::Type | Field::Group => "a quoted string",
+    };
+    CqlParseError::SyntaxError(format!(
+        "Value of '{}' must be {}: {}",
+        field.as_str(),
+        expected,
+        raw
+    ))
+}
+
 /// Unescapes a string (handles \\, \", \n, \t).
 fn unescape_string(s: &str) -> String {
     let mut result = String::with_capacity(s.len());
@@ -323,7 +392,7 @@ fn parse_date_to_timestamp(s: &str) -> Result<i64, CqlParseError> {
 
 /// Converts a date (YYYY, MM, DD) to unix timestamp (UTC midnight).
 /// Uses Howard Hinnant's civil_from_days algorithm.
-fn ymd_to_unix(year: i32, month: u32, day: u32) -> i64 {
+pub fn ymd_to_unix(year: i32, month: u32, day: u32) -> i64 {
     let y = if month <= 2 { year - 1 } else { year } as i64;
     let era = if y >= 0 { y } else { y - 399 } / 400;
     let yoe = (y - era * 400) as u32;
@@ -334,14 +403,54 @@ fn ymd_to_unix(year: i32, month: u32, day: u32) -> i64 {
     days * 86400
 }
 
+/// Converts a unix timestamp to its UTC date (YYYY, MM, DD), the inverse
+/// of `ymd_to_unix`. Uses Howard Hinnant's civil_from_days algorithm.
+pub fn unix_to_ymd(ts: i64) -> (i32, u32, u32) {
+    let z = ts.div_euclid(86400) + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year as i32, month, day)
+}
+
+/// Converts a value entered outside CQL (e.g. in a filter builder) to the
+/// value a query on `field` would hold: sizes take a unit or a byte count,
+/// dates are `YYYY-MM-DD` or a timestamp, ratings are whole numbers and
+/// everything else is a string, taken as is.
+pub fn value_from_text(field: Field, text: &str) -> Result<Value, CqlParseError> {
+    match field {
+        Field::Size => match text.trim().parse::<i64>() {
+            Ok(bytes) => Ok(Value::SizeBytes(bytes)),
+            Err(_) => parse_size_to_bytes(text.trim()).map(Value::SizeBytes),
+        },
+        Field::Modified | Field::Tagged | Field::Taken => match text.trim().parse::<i64>() {
+            Ok(ts) => Ok(Value::Timestamp(ts)),
+            Err(_) => parse_date_to_timestamp(text.trim()).map(Value::Timestamp),
+        },
+        Field::Rating => text
+            .trim()
+            .parse::<i64>()
+            .map(|n| Value::Number(n as f64))
+            .map_err(|_| invalid_rating(text)),
+        Field::Tag | Field::AnyTag | Field::Name | Field::Type | Field::Group => {
+            Ok(Value::String(text.to_string()))
+        }
+    }
+}
+
 /// Validates semantic correctness of the AST (field/operator compatibility).
 fn validate_semantics(expr: &Expr) -> Result<(), CqlParseError> {
     match expr {
         Expr::Comparison { field, op, .. } => validate_field_op(*field, *op),
         Expr::InExpr { field, .. } => {
-            // IN is only valid for tag and name
+            // IN is only valid for tag, anytag, group, name and type
             match field {
-                Field::Tag | Field::Name | Field::Type => Ok(()),
+                Field::Tag | Field::AnyTag | Field::Group | Field::Name | Field::Type => Ok(()),
                 _ => Err(CqlParseError::InvalidOperator {
                     field: format!("{:?}", field).to_lowercase(),
                     op: "IN".to_string(),
@@ -359,7 +468,7 @@ fn validate_semantics(expr: &Expr) -> Result<(), CqlParseError> {
 /// Validates that an operator is supported for a given field.
 fn validate_field_op(field: Field, op: ComparisonOp) -> Result<(), CqlParseError> {
     let valid = match field {
-        Field::Tag => matches!(
+        Field::Tag | Field::AnyTag => matches!(
             op,
             ComparisonOp::Eq | ComparisonOp::NotEq | ComparisonOp::Like
         ),
@@ -367,7 +476,7 @@ fn validate_field_op(field: Field, op: ComparisonOp) -> Result<(), CqlParseError
             op,
             ComparisonOp::Eq | ComparisonOp::NotEq | ComparisonOp::Like
         ),
-        Field::Size => matches!(
+        Field::Size | Field::Rating => matches!(
             op,
             ComparisonOp::Eq
                 | ComparisonOp::NotEq
@@ -376,7 +485,7 @@ fn validate_field_op(field: Field, op: ComparisonOp) -> Result<(), CqlParseError
                 | ComparisonOp::Gte
                 | ComparisonOp::Lte
         ),
-        Field::Modified => matches!(
+        Field::Modified | Field::Tagged | Field::Taken => matches!(
             op,
             ComparisonOp::Eq
                 | ComparisonOp::NotEq
@@ -385,7 +494,7 @@ fn validate_field_op(field: Field, op: ComparisonOp) -> Result<(), CqlParseError
                 | ComparisonOp::Gte
                 | ComparisonOp::Lte
         ),
-        Field::Type => matches!(op, ComparisonOp::Eq | ComparisonOp::NotEq),
+        Field::Type | Field::Group => matches!(op, ComparisonOp::Eq | ComparisonOp::NotEq),
     };
 
     if valid {
@@ -398,10 +507,124 @@ fn validate_field_op(field: Field, op: ComparisonOp) -> Result<(), CqlParseError
     }
 }
 
+/// Rewrites every `tag` value in `query` that is exactly `old` to `new`.
+///
+/// Only quoted values of `tag =`, `tag !=` and `tag IN (...)`, and the same
+/// forms of `anytag`, are touched, so `"old"` inside a longer value, a `name`
+/// value or a `~` glob is left alone.
+/// Returns `None` when the query does not parse or references no such value.
+pub fn rename_tag_value(query: &str, old: &str, new: &str) -> Option<String> {
+    let trimmed = query.trim();
+    let offset = query.len() - query.trim_start().len();
+    let pairs = CqlParser::parse(Rule::query, trimmed).ok()?;
+
+    let mut spans = Vec::new();
+    for pair in pairs.flatten() {
+        let values = match pair.as_rule() {
+            Rule::comparison => {
+                let mut inner = pair.into_inner();
+                let field = inner.next().map(|p| Field::from_str(p.as_str()));
+                let op = inner
+                    .next()
+                    .and_then(|p| ComparisonOp::from_str(p.as_str()));
+                if !matches!(field, Some(Some(Field::Tag | Field::AnyTag)))
+                    || !matches!(op, Some(ComparisonOp::Eq | ComparisonOp::NotEq))
+                {
+                    continue;
+                }
+                inner.collect::<Vec<_>>()
+            }
+            Rule::in_expr => {
+                let mut inner = pair.into_inner();
+                if !matches!(
+                    inner.next().and_then(|p| Field::from_str(p.as_str())),
+                    Some(Field::Tag | Field::AnyTag)
+                ) {
+                    continue;
+                }
+                inner.flat_map(|list| list.into_inner()).collect()
+            }
+            _ => continue,
+        };
+
+        for value in values {
+            let Some(string) = value
+                .into_inner()
+                .find(|p| p.as_rule() == Rule::quoted_string)
+            else {
+                continue;
+            };
+            let raw = &string.as_str()[1..string.as_str().len() - 1];
+            if unescape_string(raw) == old {
+                let span = string.as_span();
+                spans.push((offset + span.start(), offset + span.end()));
+            }
+        }
+    }
+
+    if spans.is_empty() {
+        return None;
+    }
+
+    let replacement = format!("\"{}\"", escape_string(new));
+    let mut result = query.to_string();
+    for (start, end) in spans.into_iter().rev() {
+        result.replace_range(start..end, &replacement);
+    }
+    Some(result)
+}
+
+/// Escapes `"`, `\`, newlines and tabs for use inside a CQL quoted value
+/// (inverse of `unescape_string`).
+pub(super) fn escape_string(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    for ch in s.chars() {
+        match ch {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\t' => result.push_str("\\t"),
+            _ => result.push(ch),
+        }
+    }
+    result
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn display_parses_back_to_the_same_tree() {
+        let queries = [
+            r#"tag = "say \"hi\"" AND name ~ "C:\\photos\\*""#,
+            r#"tag = "a" OR tag = "b" AND NOT (type = "image" OR size > 10MB)"#,
+            r#"(tag = "a" OR tag = "b") AND (tag = "c" AND rating >= 3)"#,
+            r#"tag = "a" OR (tag = "b" OR tag = "c")"#,
+            r#"NOT NOT anytag IN ("x", "y\tz") AND modified < "2024-02-29""#,
+            r#"taken > 1700000123 AND size <= 1536 AND size != 0 AND rating = 5"#,
+            r#"contains(name, "draft") AND group = "People""#,
+        ];
+        for query in queries {
+            let expr = parse_cql(query).unwrap();
+            let rendered = expr.to_string();
+            assert_eq!(parse_cql(&rendered).unwrap(), expr, "{}", rendered);
+        }
+
+        let expr = parse_cql(r#"size > 10485760 and (modified >= "2024-01-05" or tag = "a\"b")"#);
+        assert_eq!(
+            expr.unwrap().to_string(),
+            r#"size > 10MB AND (modified >= "2024-01-05" OR tag = "a\"b")"#
+        );
+    }
+
+    #[test]
+    fn unix_to_ymd_inverts_ymd_to_unix() {
+        for (y, m, d) in [(1970, 1, 1), (2000, 2, 29), (2024, 12, 31), (2100, 3, 1)] {
+            assert_eq!(unix_to_ymd(ymd_to_unix(y, m, d)), (y, m, d));
+        }
+    }
+
     #[test]
     fn parse_simple_tag_eq() {
         let expr = parse_cql(r#"tag = "vacation""#).unwrap();
@@ -484,6 +707,145 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_tagged_date() {
+        let expr = parse_cql(r#"tagged > "2024-09-01""#).unwrap();
+        assert_eq!(
+            expr,
+            Expr::Comparison {
+                field: Field::Tagged,
+                op: ComparisonOp::Gt,
+                value: Value::Timestamp(1725148800),
+            }
+        );
+        // "tag" is still recognized alongside "tagged"
+        assert!(parse_cql(r#"tag = "a""#).is_ok());
+        assert!(parse_cql(r#"tagged ~ "2024""#).is_err());
+    }
+
+    #[test]
+    fn parse_rating_number() {
+        let expr = parse_cql("rating >= 4").unwrap();
+        assert_eq!(
+            expr,
+            Expr::Comparison {
+                field: Field::Rating,
+                op: ComparisonOp::Gte,
+                value: Value::Number(4.0),
+            }
+        );
+        assert!(parse_cql(r#"rating = "4""#).is_err());
+        assert!(parse_cql("rating > 3.5").is_err());
+        assert!(parse_cql("rating = 4kb").is_err());
+        assert!(parse_cql("rating ~ 4").is_err());
+    }
+
+    #[test]
+    fn parse_group_name() {
+        let expr = parse_cql(r#"group != "status""#).unwrap();
+        assert_eq!(
+            expr,
+            Expr::Comparison {
+                field: Field::Group,
+                op: ComparisonOp::NotEq,
+                value: Value::String("status".to_string()),
+            }
+        );
+        assert!(parse_cql(r#"group IN ("status", "type")"#).is_ok());
+        assert!(parse_cql(r#"group ~ "sta*""#).is_err());
+    }
+
+    #[test]
+    fn parse_rejects_values_of_the_wrong_kind() {
+        for query in [
+            "group = 5",
+            "anytag = 5",
+            "tag = 5",
+            "name = 10MB",
+            r#"type IN ("image", 5)"#,
+            "tagged = 10MB",
+            "taken = 10MB",
+            "modified > 1KB",
+            r#"size > "10""#,
+        ] {
+            assert!(
+                matches!(parse_cql(query), Err(CqlParseError::SyntaxError(_))),
+                "{}",
+                query
+            );
+        }
+        assert!(parse_cql("tagged > 1700000000").is_ok());
+        assert!(parse_cql("size > 1536").is_ok());
+    }
+
+    #[test]
+    fn parse_anytag() {
+        let expr = parse_cql(r#"ANYTAG = "sunset""#).unwrap();
+        assert_eq!(
+            expr,
+            Expr::Comparison {
+                field: Field::AnyTag,
+                op: ComparisonOp::Eq,
+                value: Value::String("sunset".to_string()),
+            }
+        );
+        assert!(parse_cql(r#"anytag IN ("a", "b")"#).is_ok());
+        assert!(parse_cql(r#"anytag ~ "sun*""#).is_ok());
+        assert!(parse_cql("anytag > 1").is_err());
+    }
+
+    #[test]
+    fn parse_contains_function() {
+        assert_eq!(
+            parse_cql(r#"contains(tag, "2024")"#).unwrap(),
+            parse_cql(r#"tag ~ "*2024*""#).unwrap()
+        );
+        assert_eq!(
+            parse_cql(r#"CONTAINS(name, "say \"hi\"")"#).unwrap(),
+            Expr::Comparison {
+                field: Field::Name,
+                op: ComparisonOp::Like,
+                value: Value::String("*say \"hi\"*".to_string()),
+            }
+        );
+        assert!(parse_cql(r#"contains(size, "1")"#).is_err());
+        assert!(parse_cql(r#"contains(tag)"#).is_err());
+    }
+
+    #[test]
+    fn parse_starts_with_function() {
+        assert_eq!(
+            parse_cql(r#"starts_with(name, "IMG_")"#).unwrap(),
+            Expr::Comparison {
+                field: Field::Name,
+                op: ComparisonOp::Like,
+                value: Value::String("IMG_*".to_string()),
+            }
+        );
+        assert!(parse_cql(r#"starts_with(group, "sta")"#).is_err());
+    }
+
+    #[test]
+    fn parse_ends_with_function() {
+        let expr = parse_cql(r#"NOT ends_with(anytag, "draft") AND tag = "work""#).unwrap();
+        assert_eq!(
+            expr,
+            Expr::And(
+                Box::new(Expr::Not(Box::new(Expr::Comparison {
+                    field: Field::AnyTag,
+                    op: ComparisonOp::Like,
+                    value: Value::String("*draft".to_string()),
+                }))),
+                Box::new(Expr::Comparison {
+                    field: Field::Tag,
+                    op: ComparisonOp::Eq,
+                    value: Value::String("work".to_string()),
+                }),
+            )
+        );
+        assert!(parse_cql(r#"ends_with(tag, 5)"#).is_err());
+    }
+
     #[test]
     fn parse_date_string() {
         let expr = parse_cql(r#"modified > "2024-01-01""#).unwrap();
@@ -549,4 +911,41 @@ mod tests {
         // 1970-01-01 = 0
         assert_eq!(ymd_to_unix(1970, 1, 1), 0);
     }
+
+    #[test]
+    fn rename_tag_value_rewrites_whole_tag_values_only() {
+        let query = r#"tag = "cat" AND tag != "cats" OR tag IN ("dog", "cat") AND name = "cat""#;
+        assert_eq!(
+            rename_tag_value(query, "cat", "kitten").unwrap(),
+            r#"tag = "kitten" AND tag != "cats" OR tag IN ("dog", "kitten") AND name = "cat""#
+        );
+    }
+
+    #[test]
+    fn rename_tag_value_ignores_substrings_and_globs() {
+        assert!(rename_tag_value(r#"tag = "concat""#, "cat", "kitten").is_none());
+        assert!(rename_tag_value(r#"tag ~ "cat""#, "cat", "kitten").is_none());
+        assert!(rename_tag_value(r#"name = "cat""#, "cat", "kitten").is_none());
+        assert!(rename_tag_value("tag = ", "cat", "kitten").is_none());
+    }
+
+    #[test]
+    fn rename_tag_value_covers_anytag() {
+        assert_eq!(
+            rename_tag_value(r#"anytag = "cat" OR anytag IN ("cat")"#, "cat", "dog").unwrap(),
+            r#"anytag = "dog" OR anytag IN ("dog")"#
+        );
+    }
+
+    #[test]
+    fn rename_tag_value_handles_escapes_and_nesting() {
+        assert_eq!(
+            rename_tag_value(r#"  NOT (tag = "say \"hi\"")"#, "say \"hi\"", r"a\b").unwrap(),
+            r#"  NOT (tag = "a\\b")"#
+        );
+        assert_eq!(
+            rename_tag_value(r#"TAG = "cat""#, "cat", "dog").unwrap(),
+            r#"TAG = "dog""#
+        );
+    }
 }
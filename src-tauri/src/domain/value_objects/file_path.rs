@@ -18,6 +18,10 @@ impl FilePath {
 
     /// Creates a new FilePath after validation.
     ///
+    /// The path is normalized (see [`FilePath::normalize`]), so spellings of
+    /// the same path that differ in separators or drive letter case are stored
+    /// and looked up alike.
+    ///
     /// # Errors
     ///
     /// Returns an error if:
@@ -33,11 +37,29 @@ impl FilePath {
             ));
         }
 
-        Self::validate_no_traversal(trimmed)?;
+        let normalized = Self::normalize(trimmed);
+        Self::validate_no_traversal(&normalized)?;
+
+        Ok(Self { value: normalized })
+    }
 
-        Ok(Self {
-            value: trimmed.to_string(),
-        })
+    /// Canonical spelling of a Windows path: `/` becomes `\`, the drive
+    /// letter is uppercased and trailing separators are dropped, except the
+    /// one of a drive root. A bare drive letter becomes its root, since `C:`
+    /// alone means the current directory on that drive. The case of the rest
+    /// of the path is kept.
+    pub fn normalize(path: &str) -> String {
+        let mut normalized = path.replace('/', "\\");
+        if is_bare_drive(normalized.get(..2).unwrap_or_default()) {
+            normalized[..1].make_ascii_uppercase();
+        }
+        while normalized.len() > 1 && normalized.ends_with('\\') && !is_drive_root(&normalized) {
+            normalized.pop();
+        }
+        if is_bare_drive(&normalized) {
+            normalized.push('\\');
+        }
+        normalized
     }
 
     /// Creates a FilePath without validation.
@@ -103,6 +125,18 @@ impl FilePath {
     }
 }
 
+/// `C:`
+fn is_bare_drive(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    bytes.len() == 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':'
+}
+
+/// `C:\\`
+fn is_drive_root(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    bytes.len() == 3 && bytes[2] == b'\\' && is_bare_drive(&path[..2])
+}
+
 impl std::fmt::Display for FilePath {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.value)
@@ -142,4 +176,25 @@ mod tests {
         let path = FilePath::new("./file.txt");
         assert!(matches!(path, Err(DomainError::InvalidFilePath(_))));
     }
+
+    #[test]
+    fn spellings_of_one_path_normalize_alike() {
+        let normalized = |path: &str| FilePath::new(path).unwrap().into_string();
+        assert_eq!(normalized(r"c:\A\b"), r"C:\A\b");
+        assert_eq!(normalized(r"C:\A\b\"), r"C:\A\b");
+        assert_eq!(normalized("c:/A/b/"), r"C:\A\b");
+        assert_eq!(normalized(r"C:\A/b\\"), r"C:\A\b");
+        // Only the drive letter changes case
+        assert_eq!(normalized(r"c:\a\B"), r"C:\a\B");
+    }
+
+    #[test]
+    fn roots_and_shares_keep_their_meaning() {
+        assert_eq!(FilePath::normalize("c:"), r"C:\");
+        assert_eq!(FilePath::normalize(r"c:\"), r"C:\");
+        assert_eq!(FilePath::normalize("D:/"), r"D:\");
+        assert_eq!(FilePath::normalize(r"\\server\share\"), r"\\server\share");
+        assert_eq!(FilePath::normalize(r"\"), r"\");
+        assert_eq!(FilePath::normalize("é:"), "é:");
+    }
 }
@@ -1,3 +1,6 @@
+use crate::domain::errors::DomainError;
+use serde::ser::SerializeStruct;
+use serde_json::{Map, Value};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -32,15 +35,196 @@ pub enum AppError {
 
     #[error("USN Journal error: {0}")]
     UsnJournal(String),
+
+    /// An error returned by a service, reported with the code of its kind.
+    #[error(transparent)]
+    Service(#[from] DomainError),
+}
+
+impl AppError {
+    /// Stable identifier of the error kind that the frontend can match on;
+    /// unlike the message it never changes wording.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Database(_) | AppError::Pool(_) | AppError::Interaction(_) => {
+                "DATABASE_ERROR"
+            }
+            AppError::Io(_) => "IO_ERROR",
+            AppError::NotFound(_) => "NOT_FOUND",
+            AppError::InvalidInput(_) => "INVALID_INPUT",
+            AppError::Duplicate(_) => "DUPLICATE",
+            AppError::Domain(_) => "DOMAIN_ERROR",
+            AppError::Thumbnail(_) => "THUMBNAIL_ERROR",
+            AppError::UsnJournal(_) => "USN_JOURNAL_ERROR",
+            AppError::Service(e) => match e {
+                DomainError::ItemNotFound(_)
+                | DomainError::TagNotFound(_)
+                | DomainError::TagGroupNotFound(_)
+                | DomainError::TagTemplateNotFound(_)
+                | DomainError::AutoTagRuleNotFound(_)
+                | DomainError::CollectionNotFound(_) => "NOT_FOUND",
+                DomainError::InvalidFilePath(_)
+                | DomainError::InvalidTagValue(_)
+                | DomainError::InvalidColor(_)
+                | DomainError::ValidationError(_) => "INVALID_INPUT",
+                DomainError::DuplicateEntry(_) => "DUPLICATE",
+                DomainError::DatabaseError(_) => "DATABASE_ERROR",
+                DomainError::DatabaseBusy(_) => "DATABASE_BUSY",
+                DomainError::UsnJournalError(_) => "USN_JOURNAL_ERROR",
+                DomainError::QueryTimeout(_) => "TIMEOUT",
+                DomainError::QueryCancelled(_) => "CANCELLED",
+                DomainError::ConcurrentModification(_) => "CONFLICT",
+            },
+        }
+    }
+
+    /// Machine-readable fields about the underlying error, empty when the
+    /// message is all there is.
+    fn details(&self) -> Map<String, Value> {
+        let mut details = Map::new();
+        match self {
+            AppError::Database(rusqlite::Error::SqliteFailure(err, _)) => {
+                details.insert("sqlite_code".into(), format!("{:?}", err.code).into());
+                details.insert("extended_code".into(), err.extended_code.into());
+            }
+            AppError::Io(e) => {
+                details.insert("kind".into(), format!("{:?}", e.kind()).into());
+            }
+            _ => {}
+        }
+        details
+    }
 }
 
+/// Serialized as `{ "code": ..., "message": ..., "details": { ... } }`, which
+/// Tauri passes to the frontend as the rejection value of `invoke`.
 impl serde::Serialize for AppError {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
     {
-        serializer.serialize_str(&self.to_string())
+        let mut state = serializer.serialize_struct("AppError", 3)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("details", &self.details())?;
+        state.end()
     }
 }
 
 pub type AppResult<T> = Result<T, AppError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn to_json(error: AppError) -> Value {
+        serde_json::to_value(error).unwrap()
+    }
+
+    #[test]
+    fn string_variants_serialize_with_code_and_message() {
+        let cases = [
+            (
+                AppError::NotFound("7".into()),
+                "NOT_FOUND",
+                "Item not found: 7",
+            ),
+            (
+                AppError::InvalidInput("bad".into()),
+                "INVALID_INPUT",
+                "Invalid input: bad",
+            ),
+            (
+                AppError::Duplicate("a".into()),
+                "DUPLICATE",
+                "Duplicate entry: a",
+            ),
+            (
+                AppError::Domain("rule".into()),
+                "DOMAIN_ERROR",
+                "Domain error: rule",
+            ),
+            (
+                AppError::Thumbnail("decode".into()),
+                "THUMBNAIL_ERROR",
+                "Thumbnail error: decode",
+            ),
+            (
+                AppError::UsnJournal("C:".into()),
+                "USN_JOURNAL_ERROR",
+                "USN Journal error: C:",
+            ),
+        ];
+
+        for (error, code, message) in cases {
+            assert_eq!(
+                to_json(error),
+                json!({ "code": code, "message": message, "details": {} })
+            );
+        }
+    }
+
+    #[test]
+    fn service_errors_keep_their_message_and_get_their_own_code() {
+        let cases = [
+            (DomainError::TagNotFound("3".into()), "NOT_FOUND"),
+            (DomainError::ValidationError("x".into()), "INVALID_INPUT"),
+            (DomainError::DuplicateEntry("a".into()), "DUPLICATE"),
+            (DomainError::DatabaseError("io".into()), "DATABASE_ERROR"),
+            (DomainError::DatabaseBusy("w".into()), "DATABASE_BUSY"),
+            (DomainError::QueryTimeout("q".into()), "TIMEOUT"),
+            (DomainError::QueryCancelled("q".into()), "CANCELLED"),
+            (DomainError::ConcurrentModification("7".into()), "CONFLICT"),
+        ];
+
+        for (error, code) in cases {
+            let message = error.to_string();
+            assert_eq!(
+                to_json(AppError::from(error)),
+                json!({ "code": code, "message": message, "details": {} })
+            );
+        }
+    }
+
+    #[test]
+    fn database_errors_carry_sqlite_codes() {
+        let busy = rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_BUSY),
+            None,
+        );
+        let json = to_json(AppError::Database(busy));
+        assert_eq!(json["code"], "DATABASE_ERROR");
+        assert_eq!(
+            json["details"],
+            json!({ "sqlite_code": "DatabaseBusy", "extended_code": rusqlite::ffi::SQLITE_BUSY })
+        );
+
+        let json = to_json(AppError::Database(rusqlite::Error::QueryReturnedNoRows));
+        assert_eq!(json["code"], "DATABASE_ERROR");
+        assert_eq!(json["message"], "Database error: Query returned no rows");
+        assert_eq!(json["details"], json!({}));
+
+        for error in [
+            AppError::Pool(deadpool_sqlite::PoolError::Closed),
+            AppError::Interaction(deadpool_sqlite::InteractError::Aborted),
+        ] {
+            let json = to_json(error);
+            assert_eq!(json["code"], "DATABASE_ERROR");
+            assert_eq!(json["details"], json!({}));
+        }
+    }
+
+    #[test]
+    fn io_errors_carry_their_kind() {
+        let error = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "denied");
+        assert_eq!(
+            to_json(AppError::Io(error)),
+            json!({
+                "code": "IO_ERROR",
+                "message": "IO error: denied",
+                "details": { "kind": "PermissionDenied" },
+            })
+        );
+    }
+}
@@ -0,0 +1,142 @@
+//! Directory Scan
+//!
+//! Lists the files under a directory for bulk registration.
+
+use glob::{MatchOptions, Pattern};
+use std::fs::Metadata;
+use std::path::Path;
+use walkdir::{DirEntry, WalkDir};
+
+/// `FILE_ATTRIBUTE_HIDDEN`
+const HIDDEN_ATTRIBUTE: u32 = 0x2;
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: false,
+    require_literal_separator: false,
+    require_literal_leading_dot: false,
+};
+
+/// Glob patterns for files and directories a scan skips, compiled once per
+/// scan.
+///
+/// A pattern without a path separator is matched against an entry's name, so
+/// `node_modules` or `*.tmp` apply at any depth; one with a separator is
+/// matched against the full path. Matching ignores case.
+#[derive(Debug, Default)]
+pub struct ExcludePatterns {
+    names: Vec<Pattern>,
+    paths: Vec<Pattern>,
+}
+
+impl ExcludePatterns {
+    /// Compiles `patterns`, skipping blank and invalid ones with a warning.
+    pub fn new(patterns: &[String]) -> Self {
+        let mut compiled = Self::default();
+        for raw in patterns.iter().map(|p| p.trim()).filter(|p| !p.is_empty()) {
+            match Pattern::new(raw) {
+                Ok(pattern) if raw.contains(['/', '\\']) => compiled.paths.push(pattern),
+                Ok(pattern) => compiled.names.push(pattern),
+                Err(e) => log::warn!("Ignoring invalid exclude pattern {:?}: {}", raw, e),
+            }
+        }
+        compiled
+    }
+
+    /// Whether `path` matches any of the patterns.
+    pub fn matches(&self, path: &Path) -> bool {
+        let name_matches = path.file_name().is_some_and(|name| {
+            let name = name.to_string_lossy();
+            self.names
+                .iter()
+                .any(|p| p.matches_with(&name, MATCH_OPTIONS))
+        });
+        name_matches
+            || self
+                .paths
+                .iter()
+                .any(|p| p.matches_path_with(path, MATCH_OPTIONS))
+    }
+}
+
+/// Files found by [`scan_directory`].
+#[derive(Debug, Default)]
+pub struct DirectoryScan {
+    /// File paths, sorted.
+    pub paths: Vec<String>,
+    /// Files and directories skipped by an exclude pattern; the contents of
+    /// a skipped directory are not counted.
+    pub excluded_count: usize,
+}
+
+/// Lists the files under `root`, sorted.
+///
+/// Only `root`'s direct children are listed unless `recursive` is set.
+/// Without `include_hidden`, hidden files are skipped and hidden directories
+/// are not entered. Entries matching `exclude` are skipped the same way and
+/// counted. Symlinks are not followed, and entries that can't be read are
+/// skipped rather than failing the scan.
+pub fn scan_directory(
+    root: &Path,
+    recursive: bool,
+    include_hidden: bool,
+    exclude: &ExcludePatterns,
+) -> DirectoryScan {
+    let mut walker = WalkDir::new(root).min_depth(1).sort_by_file_name();
+    if !recursive {
+        walker = walker.max_depth(1);
+    }
+
+    let mut excluded_count = 0;
+    let paths = walker
+        .into_iter()
+        .filter_entry(|entry| {
+            if !include_hidden && is_hidden(entry) {
+                return false;
+            }
+            if exclude.matches(entry.path()) {
+                excluded_count += 1;
+                return false;
+            }
+            true
+        })
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.path().to_string_lossy().to_string())
+        .collect();
+
+    DirectoryScan {
+        paths,
+        excluded_count,
+    }
+}
+
+fn is_hidden(entry: &DirEntry) -> bool {
+    entry.metadata().is_ok_and(|m| has_hidden_attribute(&m))
+}
+
+fn has_hidden_attribute(metadata: &Metadata) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    metadata.file_attributes() & HIDDEN_ATTRIBUTE != 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exclude_patterns_match_names_at_any_depth_and_full_paths() {
+        let exclude = ExcludePatterns::new(&[
+            "node_modules".to_string(),
+            "*.TMP".to_string(),
+            "C:\\data\\build\\*".to_string(),
+            "[".to_string(),
+            " ".to_string(),
+        ]);
+
+        assert!(exclude.matches(Path::new("C:\\code\\app\\node_modules")));
+        assert!(exclude.matches(Path::new("C:\\code\\scratch.tmp")));
+        assert!(exclude.matches(Path::new("C:\\data\\build\\out.bin")));
+        assert!(!exclude.matches(Path::new("C:\\code\\node_modules.txt")));
+        assert!(!exclude.matches(Path::new("C:\\data\\photos\\a.jpg")));
+    }
+}
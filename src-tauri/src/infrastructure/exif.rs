@@ -0,0 +1,130 @@
+//! EXIF Metadata
+//!
+//! Reads the capture date of photos from their EXIF data.
+
+use crate::domain::search::parser::ymd_to_unix;
+use exif::{DateTime, In, Reader, Tag, Value};
+use std::io::BufReader;
+
+/// Extensions of image formats that can carry EXIF data.
+const EXIF_EXTENSIONS: &[&str] = &[
+    "jpg", "jpeg", "jpe", "tif", "tiff", "png", "webp", "heic", "heif", "avif", "dng",
+];
+
+/// Reads when a photo was taken from `DateTimeOriginal`, falling back to
+/// `DateTimeDigitized`.
+///
+/// EXIF stores the camera's wall-clock time without a zone, so the result
+/// reads that time as UTC, the same way CQL date literals are read. Returns
+/// None for other file types, unreadable files and missing or blank dates.
+pub fn read_taken_at(path: &str) -> Option<i64> {
+    let extension = std::path::Path::new(path)
+        .extension()?
+        .to_string_lossy()
+        .to_lowercase();
+    if !EXIF_EXTENSIONS.contains(&extension.as_str()) {
+        return None;
+    }
+
+    let file = std::fs::File::open(path).ok()?;
+    let exif = Reader::new()
+        .read_from_container(&mut BufReader::new(file))
+        .ok()?;
+
+    [Tag::DateTimeOriginal, Tag::DateTimeDigitized]
+        .into_iter()
+        .find_map(|tag| match &exif.get_field(tag, In::PRIMARY)?.value {
+            Value::Ascii(values) => parse_exif_datetime(values.first()?),
+            _ => None,
+        })
+}
+
+/// Parses an EXIF `YYYY:MM:DD HH:MM:SS` value into a unix timestamp.
+fn parse_exif_datetime(raw: &[u8]) -> Option<i64> {
+    let dt = DateTime::from_ascii(raw).ok()?;
+    let valid = dt.year >= 1970
+        && (1..=12).contains(&dt.month)
+        && (1..=31).contains(&dt.day)
+        && dt.hour < 24
+        && dt.minute < 60
+        && dt.second < 61;
+    if !valid {
+        return None;
+    }
+
+    Some(
+        ymd_to_unix(dt.year.into(), dt.month.into(), dt.day.into())
+            + i64::from(dt.hour) * 3600
+            + i64::from(dt.minute) * 60
+            + i64::from(dt.second),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a minimal little-endian JPEG whose EXIF block holds only
+    /// `DateTimeOriginal`.
+    fn jpeg_with_date(date: &[u8; 19]) -> Vec<u8> {
+        let mut tiff = Vec::new();
+        tiff.extend_from_slice(b"II*\0");
+        tiff.extend_from_slice(&8u32.to_le_bytes());
+        // IFD0 at 8: one entry pointing at the Exif IFD (at 26)
+        tiff.extend_from_slice(&1u16.to_le_bytes());
+        tiff.extend_from_slice(&0x8769u16.to_le_bytes());
+        tiff.extend_from_slice(&4u16.to_le_bytes());
+        tiff.extend_from_slice(&1u32.to_le_bytes());
+        tiff.extend_from_slice(&26u32.to_le_bytes());
+        tiff.extend_from_slice(&0u32.to_le_bytes());
+        // Exif IFD at 26: DateTimeOriginal, 20 ASCII bytes at 44
+        tiff.extend_from_slice(&1u16.to_le_bytes());
+        tiff.extend_from_slice(&0x9003u16.to_le_bytes());
+        tiff.extend_from_slice(&2u16.to_le_bytes());
+        tiff.extend_from_slice(&20u32.to_le_bytes());
+        tiff.extend_from_slice(&44u32.to_le_bytes());
+        tiff.extend_from_slice(&0u32.to_le_bytes());
+        tiff.extend_from_slice(date);
+        tiff.push(0);
+
+        let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE1];
+        jpeg.extend_from_slice(&((tiff.len() + 8) as u16).to_be_bytes());
+        jpeg.extend_from_slice(b"Exif\0\0");
+        jpeg.extend_from_slice(&tiff);
+        jpeg.extend_from_slice(&[0xFF, 0xD9]);
+        jpeg
+    }
+
+    #[test]
+    fn reads_date_time_original_from_jpeg() {
+        let dir = tempfile::tempdir().unwrap();
+        let photo = dir.path().join("IMG_0001.JPG");
+        std::fs::write(&photo, jpeg_with_date(b"2023:07:14 18:30:05")).unwrap();
+
+        // 2023-07-14 00:00:00 UTC is 1689292800
+        assert_eq!(
+            read_taken_at(photo.to_str().unwrap()),
+            Some(1_689_292_800 + 18 * 3600 + 30 * 60 + 5)
+        );
+
+        // Same bytes under a non-image extension are not read
+        let text = dir.path().join("notes.txt");
+        std::fs::write(&text, jpeg_with_date(b"2023:07:14 18:30:05")).unwrap();
+        assert_eq!(read_taken_at(text.to_str().unwrap()), None);
+    }
+
+    #[test]
+    fn blank_or_invalid_dates_are_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let blank = dir.path().join("blank.jpg");
+        std::fs::write(&blank, jpeg_with_date(b"    :  :     :  :  ")).unwrap();
+        assert_eq!(read_taken_at(blank.to_str().unwrap()), None);
+
+        let missing = dir.path().join("missing.jpg");
+        assert_eq!(read_taken_at(missing.to_str().unwrap()), None);
+
+        assert_eq!(parse_exif_datetime(b"2023:13:01 00:00:00"), None);
+        assert_eq!(parse_exif_datetime(b"1969:12:31 23:59:59"), None);
+        assert_eq!(parse_exif_datetime(b"1970:01:02 00:00:01"), Some(86_401));
+    }
+}
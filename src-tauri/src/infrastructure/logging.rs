@@ -0,0 +1,267 @@
+//! File Logging
+//!
+//! `log` backend that appends to `{app_data}/logs/constellation.log`, so
+//! USN and thumbnail problems can be diagnosed without a console attached.
+//! `tracing` spans and events are written here too, through its `log`
+//! feature: a span shows up as a `name; field=value` line when it is created.
+
+use log::{LevelFilter, Log, Metadata, Record};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Setting key holding the minimum level written to the log.
+pub const LOG_LEVEL_KEY: &str = "log_level";
+
+/// Environment variable overriding the `log_level` setting, e.g.
+/// `CONSTELLATION_LOG=debug`.
+pub const LOG_LEVEL_ENV: &str = "CONSTELLATION_LOG";
+
+/// Level used until the `log_level` setting has been read.
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::Info;
+
+/// A log larger than this at startup is moved to `constellation.log.1`.
+const MAX_LOG_BYTES: u64 = 5 * 1024 * 1024;
+
+struct FileLogger {
+    file: File,
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let line = format!(
+            "{} {:<5} [{}] {}\n",
+            format_timestamp(unix_now()),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+        // Append-mode writes of a whole line don't interleave, so no lock is needed
+        let _ = (&self.file).write_all(line.as_bytes());
+        if cfg!(debug_assertions) {
+            eprint!("{}", line);
+        }
+    }
+
+    fn flush(&self) {
+        let _ = (&self.file).flush();
+    }
+}
+
+/// Path of the active log file under `app_data_dir`.
+pub fn log_file(app_data_dir: &Path) -> PathBuf {
+    app_data_dir.join("logs").join("constellation.log")
+}
+
+/// Installs the file logger at the default level, or the one in
+/// `CONSTELLATION_LOG`. Call once at startup.
+pub fn init(app_data_dir: &Path) -> io::Result<()> {
+    let path = log_file(app_data_dir);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    if std::fs::metadata(&path).is_ok_and(|m| m.len() > MAX_LOG_BYTES) {
+        std::fs::rename(&path, path.with_extension("log.1"))?;
+    }
+
+    let file = OpenOptions::new().create(true).append(true).open(&path)?;
+    log::set_boxed_logger(Box::new(FileLogger { file }))
+        .map_err(|e| io::Error::new(io::ErrorKind::AlreadyExists, e))?;
+    apply_level(None);
+    Ok(())
+}
+
+/// Applies a `log_level` setting value. `CONSTELLATION_LOG` takes precedence
+/// when set; unknown values fall back to the default.
+pub fn apply_level(value: Option<&str>) {
+    let level = env_level()
+        .or_else(|| value.and_then(parse_level))
+        .unwrap_or(DEFAULT_LEVEL);
+    log::set_max_level(level);
+}
+
+fn env_level() -> Option<LevelFilter> {
+    std::env::var(LOG_LEVEL_ENV)
+        .ok()
+        .as_deref()
+        .and_then(parse_level)
+}
+
+/// Parses `off`, `error`, `warn`, `info`, `debug` or `trace`, ignoring case.
+pub fn parse_level(value: &str) -> Option<LevelFilter> {
+    value.trim().parse().ok()
+}
+
+/// Returns the last `lines` lines of the log file, oldest first. A missing
+/// file reads as empty.
+pub fn read_recent(path: &Path, lines: usize) -> io::Result<Vec<String>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut tail = std::collections::VecDeque::with_capacity(lines);
+    for line in BufReader::new(file).lines() {
+        if tail.len() == lines {
+            tail.pop_front();
+        }
+        tail.push_back(line?);
+    }
+    Ok(tail.into_iter().take(lines).collect())
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Formats a unix timestamp as `YYYY-MM-DDTHH:MM:SSZ`.
+fn format_timestamp(secs: i64) -> String {
+    let days = secs.div_euclid(86400);
+    let rem = secs.rem_euclid(86400);
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::marker::PhantomData;
+    use std::sync::Once;
+
+    thread_local! {
+        /// Records of the thread's active [`capture`], as `(level, message)`.
+        static CAPTURED: RefCell<Option<Vec<(log::Level, String)>>> =
+            const { RefCell::new(None) };
+    }
+
+    /// `log` backend for tests, keeping the records of threads that are
+    /// capturing.
+    struct CaptureLogger;
+
+    impl Log for CaptureLogger {
+        fn enabled(&self, _metadata: &Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &Record) {
+            CAPTURED.with(|captured| {
+                if let Some(records) = captured.borrow_mut().as_mut() {
+                    records.push((record.level(), record.args().to_string()));
+                }
+            });
+        }
+
+        fn flush(&self) {}
+    }
+
+    /// Records captured on this thread until dropped.
+    pub(crate) struct Captured {
+        _thread_bound: PhantomData<*const ()>,
+    }
+
+    impl Captured {
+        pub(crate) fn records(&self) -> Vec<(log::Level, String)> {
+            CAPTURED.with(|captured| captured.borrow().clone().unwrap_or_default())
+        }
+    }
+
+    impl Drop for Captured {
+        fn drop(&mut self) {
+            CAPTURED.with(|captured| *captured.borrow_mut() = None);
+        }
+    }
+
+    /// Records every `log` record up to `Debug` emitted on this thread, for
+    /// asserting what an operation logged. `tracing` events and spans arrive
+    /// the same way they do in the app. Async tests must stay on one thread.
+    pub(crate) fn capture() -> Captured {
+        static INSTALL: Once = Once::new();
+        INSTALL.call_once(|| {
+            if log::set_boxed_logger(Box::new(CaptureLogger)).is_ok() {
+                log::set_max_level(LevelFilter::Debug);
+            }
+        });
+        CAPTURED.with(|captured| *captured.borrow_mut() = Some(Vec::new()));
+        Captured {
+            _thread_bound: PhantomData,
+        }
+    }
+
+    #[test]
+    fn tracing_events_reach_the_log_backend() {
+        let captured = capture();
+        tracing::warn!(count = 2, "Journal {}", "reset");
+        tracing::trace!("Filtered out");
+
+        let records = captured.records();
+        assert_eq!(records.len(), 1);
+        let (level, message) = &records[0];
+        assert_eq!(*level, log::Level::Warn);
+        assert!(message.contains("Journal reset"));
+        assert!(message.contains("count=2"));
+    }
+
+    #[test]
+    fn formats_utc_timestamps() {
+        assert_eq!(format_timestamp(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_timestamp(1704067200), "2024-01-01T00:00:00Z");
+        assert_eq!(format_timestamp(1709210096), "2024-02-29T12:34:56Z");
+    }
+
+    #[test]
+    fn parses_levels_case_insensitively() {
+        assert_eq!(parse_level("WARN"), Some(LevelFilter::Warn));
+        assert_eq!(parse_level(" debug "), Some(LevelFilter::Debug));
+        assert_eq!(parse_level("off"), Some(LevelFilter::Off));
+        assert_eq!(parse_level("verbose"), None);
+    }
+
+    #[test]
+    fn read_recent_returns_the_tail_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.log");
+        std::fs::write(&path, "one\ntwo\nthree\nfour\n").unwrap();
+
+        assert_eq!(read_recent(&path, 2).unwrap(), vec!["three", "four"]);
+        assert_eq!(read_recent(&path, 10).unwrap().len(), 4);
+        assert!(read_recent(&path, 0).unwrap().is_empty());
+        assert!(
+            read_recent(&dir.path().join("missing.log"), 5)
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
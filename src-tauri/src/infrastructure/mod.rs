@@ -3,6 +3,9 @@
 //! This module contains implementations of domain interfaces
 //! and external concerns (database, file system, etc.)
 
+pub mod directory_scan;
+pub mod exif;
+pub mod logging;
 pub mod persistence;
 pub mod thumbnail;
 pub mod usn_journal;
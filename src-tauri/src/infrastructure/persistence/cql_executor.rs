@@ -53,10 +53,15 @@ fn build_comparison_sql(
 ) -> String {
     match field {
         Field::Tag => build_tag_comparison_sql(op, value, counter, params),
+        Field::AnyTag => build_anytag_comparison_sql(op, value, counter, params),
         Field::Name => build_name_sql(op, value, params),
         Field::Size => build_size_sql(op, value, params),
         Field::Modified => build_modified_sql(op, value, params),
+        Field::Tagged => build_tagged_sql(op, value, params),
+        Field::Taken => build_taken_sql(op, value, params),
         Field::Type => build_type_sql(op, value, params),
+        Field::Rating => build_rating_sql(op, value, params),
+        Field::Group => build_group_comparison_sql(op, value, counter, params),
     }
 }
 
@@ -120,14 +125,128 @@ fn build_tag_in_sql(
     )
 }
 
+fn build_anytag_comparison_sql(
+    op: ComparisonOp,
+    value: &Value,
+    counter: &mut usize,
+    params: &mut Vec<rusqlite::types::Value>,
+) -> String {
+    let (condition, pattern) = match op {
+        // Neither the item nor anything under it has the tag
+        ComparisonOp::NotEq => {
+            let matches = build_anytag_comparison_sql(ComparisonOp::Eq, value, counter, params);
+            return format!("NOT {}", matches);
+        }
+        ComparisonOp::Eq => ("= ?", extract_string(value)),
+        ComparisonOp::Like => ("LIKE ? ESCAPE '\\'", glob_to_like(&extract_string(value))),
+        // SAFETY: Parser semantic validation ensures only Eq/NotEq/Like reach here for anytag field
+        _ => unreachable!("Invalid operator for anytag field"),
+    };
+
+    let own = build_tag_comparison_sql(op, value, counter, params);
+    params.push(rusqlite::types::Value::Text(pattern));
+    format!("({} OR {})", own, descendant_tag_sql(condition, counter))
+}
+
+/// Condition matching directories with an active item somewhere under them
+/// that has a tag whose value satisfies `condition`.
+fn descendant_tag_sql(condition: &str, counter: &mut usize) -> String {
+    let idx = *counter;
+    *counter += 1;
+
+    format!(
+        "(i.is_directory = 1 AND EXISTS (SELECT 1 FROM items c_{} \
+         JOIN item_tags it_{} ON it_{}.item_id = c_{}.id JOIN tags t_{} ON it_{}.tag_id = t_{}.id \
+         WHERE c_{}.is_deleted = 0 AND {} AND t_{}.value {}))",
+        idx,
+        idx,
+        idx,
+        idx,
+        idx,
+        idx,
+        idx,
+        idx,
+        descendant_path_sql(&format!("c_{}.path", idx), "RTRIM(i.path, '\\/')"),
+        idx,
+        condition
+    )
+}
+
+fn build_group_comparison_sql(
+    op: ComparisonOp,
+    value: &Value,
+    counter: &mut usize,
+    params: &mut Vec<rusqlite::types::Value>,
+) -> String {
+    let prefix = match op {
+        ComparisonOp::Eq => "EXISTS",
+        ComparisonOp::NotEq => "NOT EXISTS",
+        // SAFETY: Parser semantic validation ensures only Eq/NotEq reach here for group field
+        _ => unreachable!("Invalid operator for group field"),
+    };
+    params.push(rusqlite::types::Value::Text(extract_string(value)));
+    group_exists_sql(prefix, "= ?", counter)
+}
+
+fn build_group_in_sql(
+    values: &[Value],
+    counter: &mut usize,
+    params: &mut Vec<rusqlite::types::Value>,
+) -> String {
+    let placeholders: Vec<&str> = values.iter().map(|_| "?").collect();
+    for v in values {
+        params.push(rusqlite::types::Value::Text(extract_string(v)));
+    }
+    group_exists_sql(
+        "EXISTS",
+        &format!("IN ({})", placeholders.join(", ")),
+        counter,
+    )
+}
+
+/// EXISTS subquery matching items with a tag whose group name satisfies `condition`.
+fn group_exists_sql(prefix: &str, condition: &str, counter: &mut usize) -> String {
+    let idx = *counter;
+    *counter += 1;
+
+    format!(
+        "{} (SELECT 1 FROM item_tags it_{} JOIN tags t_{} ON it_{}.tag_id = t_{}.id \
+         JOIN tag_groups g_{} ON t_{}.group_id = g_{}.id \
+         WHERE it_{}.item_id = i.id AND g_{}.name {})",
+        prefix, idx, idx, idx, idx, idx, idx, idx, idx, idx, condition
+    )
+}
+
 /// SQL expression that extracts the filename from `i.path`.
 ///
 /// Uses SQLite RTRIM trick: strips all non-separator characters from the right,
 /// leaving the directory prefix up to the last `\` or `/`. SUBSTR from there gives
 /// the filename. Handles both Windows and Unix separators.
-const FILENAME_EXPR: &str =
+///
+/// Backed by the `idx_items_file_name` expression index, so equality and range
+/// comparisons against it are index lookups. `LOWER` only folds ASCII letters,
+/// so values compared against it are lowercased with `to_ascii_lowercase`.
+pub(super) const FILENAME_EXPR: &str =
     "LOWER(SUBSTR(i.path, LENGTH(RTRIM(i.path, REPLACE(REPLACE(i.path, '\\', ''), '/', ''))) + 1))";
 
+/// SQL condition matching the paths in `path_col` that lie under the directory
+/// `dir_expr`, which must not end in a separator. Either separator counts.
+///
+/// Written as two ranges instead of `LIKE` so the `path` index can serve it:
+/// `]` and `0` are the characters right after the backslash and `/`.
+pub(super) fn descendant_path_sql(path_col: &str, dir_expr: &str) -> String {
+    format!(
+        "(({p} > {d} || '\\' AND {p} < {d} || ']') OR ({p} > {d} || '/' AND {p} < {d} || '0'))",
+        p = path_col,
+        d = dir_expr
+    )
+}
+
+/// SQL expression for an item's rating, with unrated items counting as 0.
+///
+/// Backed by the `idx_items_rating` expression index.
+pub(super) const RATING_EXPR: &str = "COALESCE(i.rating, 0)";
+
 fn build_name_sql(
     op: ComparisonOp,
     value: &Value,
@@ -136,15 +255,15 @@ fn build_name_sql(
     let s = extract_string(value);
     match op {
         ComparisonOp::Eq => {
-            params.push(rusqlite::types::Value::Text(s.to_lowercase()));
+            params.push(rusqlite::types::Value::Text(s.to_ascii_lowercase()));
             format!("{} = ?", FILENAME_EXPR)
         }
         ComparisonOp::NotEq => {
-            params.push(rusqlite::types::Value::Text(s.to_lowercase()));
+            params.push(rusqlite::types::Value::Text(s.to_ascii_lowercase()));
             format!("{} != ?", FILENAME_EXPR)
         }
         ComparisonOp::Like => {
-            let like_pattern = glob_to_like(&s).to_lowercase();
+            let like_pattern = glob_to_like(&s).to_ascii_lowercase();
             params.push(rusqlite::types::Value::Text(like_pattern));
             format!("{} LIKE ? ESCAPE '\\'", FILENAME_EXPR)
         }
@@ -175,6 +294,41 @@ fn build_modified_sql(
     format!("COALESCE(i.modified_time, 0) {} ?", sql_op)
 }
 
+fn build_tagged_sql(
+    op: ComparisonOp,
+    value: &Value,
+    params: &mut Vec<rusqlite::types::Value>,
+) -> String {
+    let ts = extract_timestamp(value);
+    params.push(rusqlite::types::Value::Integer(ts));
+    let sql_op = comparison_op_to_sql(op);
+    // No COALESCE: never-tagged items (NULL) match no date comparison
+    format!("i.tags_updated_at {} ?", sql_op)
+}
+
+fn build_taken_sql(
+    op: ComparisonOp,
+    value: &Value,
+    params: &mut Vec<rusqlite::types::Value>,
+) -> String {
+    let ts = extract_timestamp(value);
+    params.push(rusqlite::types::Value::Integer(ts));
+    let sql_op = comparison_op_to_sql(op);
+    // Files without an EXIF capture time compare by their modified time
+    format!("COALESCE(i.taken_at, i.modified_time, 0) {} ?", sql_op)
+}
+
+fn build_rating_sql(
+    op: ComparisonOp,
+    value: &Value,
+    params: &mut Vec<rusqlite::types::Value>,
+) -> String {
+    let rating = extract_number(value);
+    params.push(rusqlite::types::Value::Integer(rating));
+    let sql_op = comparison_op_to_sql(op);
+    format!("{} {} ?", RATING_EXPR, sql_op)
+}
+
 fn build_type_sql(
     op: ComparisonOp,
     value: &Value,
@@ -227,35 +381,59 @@ fn build_in_sql(
 ) -> String {
     match field {
         Field::Tag => build_tag_in_sql(values, counter, params),
+        Field::AnyTag => {
+            let own = build_tag_in_sql(values, counter, params);
+            let placeholders: Vec<&str> = values.iter().map(|_| "?").collect();
+            for v in values {
+                params.push(rusqlite::types::Value::Text(extract_string(v)));
+            }
+            let under = descendant_tag_sql(&format!("IN ({})", placeholders.join(", ")), counter);
+            format!("({} OR {})", own, under)
+        }
+        Field::Group => build_group_in_sql(values, counter, params),
         Field::Name => {
             let placeholders: Vec<&str> = values.iter().map(|_| "?").collect();
             for v in values {
                 let s = extract_string(v);
-                params.push(rusqlite::types::Value::Text(s.to_lowercase()));
+                params.push(rusqlite::types::Value::Text(s.to_ascii_lowercase()));
             }
             format!("{} IN ({})", FILENAME_EXPR, placeholders.join(", "))
         }
         Field::Type => {
-            let mut all_conditions = Vec::new();
+            // Extensions only identify files, so a directory named like a file
+            // matches only through "directory"
+            let mut include_directories = false;
+            let mut extension_conditions = Vec::new();
             for v in values {
                 let type_name = extract_string(v).to_lowercase();
                 if type_name == "directory" {
-                    all_conditions.push("i.is_directory = 1".to_string());
+                    include_directories = true;
                 } else {
                     let extensions = type_to_extensions(&type_name);
                     for ext in extensions {
                         params.push(rusqlite::types::Value::Text(format!("%{}", ext)));
-                        all_conditions.push("LOWER(i.path) LIKE ?".to_string());
+                        extension_conditions.push("LOWER(i.path) LIKE ?".to_string());
                     }
                 }
             }
-            if all_conditions.is_empty() {
-                "0".to_string()
-            } else {
-                format!("({})", all_conditions.join(" OR "))
+
+            let mut branches = Vec::new();
+            if include_directories {
+                branches.push("i.is_directory = 1".to_string());
+            }
+            if !extension_conditions.is_empty() {
+                branches.push(format!(
+                    "(i.is_directory = 0 AND ({}))",
+                    extension_conditions.join(" OR ")
+                ));
+            }
+            match branches.len() {
+                0 => "0".to_string(),
+                1 => branches.remove(0),
+                _ => format!("({})", branches.join(" OR ")),
             }
         }
-        // SAFETY: Parser semantic validation ensures only Tag/Name/Type fields reach here for IN expr
+        // SAFETY: Parser semantic validation ensures only Tag/AnyTag/Group/Name/Type fields reach here for IN expr
         _ => unreachable!("IN not supported for this field"),
     }
 }
@@ -336,6 +514,13 @@ fn extract_size(value: &Value) -> i64 {
     }
 }
 
+fn extract_number(value: &Value) -> i64 {
+    match value {
+        Value::Number(n) => *n as i64,
+        _ => unreachable!("Expected numeric value"),
+    }
+}
+
 fn extract_timestamp(value: &Value) -> i64 {
     match value {
         Value::Timestamp(ts) => *ts,
@@ -402,6 +587,20 @@ mod tests {
         assert_eq!(frag.params.len(), 2);
     }
 
+    #[test]
+    fn anytag_neq_negates_own_and_descendant_matches() {
+        let expr = crate::domain::search::parse_cql(r#"anytag != "sunset""#).unwrap();
+        let frag = expr_to_sql(&expr);
+        assert!(frag.sql.starts_with("NOT (EXISTS"));
+        assert!(
+            frag.sql
+                .contains("i.is_directory = 1 AND EXISTS (SELECT 1 FROM items c_1")
+        );
+        assert!(frag.sql.contains("c_1.path > RTRIM(i.path, '\\/') || '\\'"));
+        assert!(frag.sql.contains("t_1.value = ?"));
+        assert_eq!(frag.params.len(), 2);
+    }
+
     #[test]
     fn name_like_glob() {
         let expr = Expr::Comparison {
@@ -444,6 +643,94 @@ mod tests {
         assert!(frag.sql.contains("COALESCE(i.modified_time, 0) > ?"));
     }
 
+    #[test]
+    fn tagged_gt() {
+        let expr = Expr::Comparison {
+            field: Field::Tagged,
+            op: ComparisonOp::Gt,
+            value: Value::Timestamp(1725148800),
+        };
+        let frag = expr_to_sql(&expr);
+        assert_eq!(frag.sql, "i.tags_updated_at > ?");
+        match &frag.params[0] {
+            rusqlite::types::Value::Integer(n) => assert_eq!(*n, 1725148800),
+            _ => panic!("Expected integer param"),
+        }
+    }
+
+    #[test]
+    fn taken_falls_back_to_modified_time() {
+        let expr = crate::domain::search::parse_cql(r#"taken < "2024-01-01""#).unwrap();
+        let frag = expr_to_sql(&expr);
+        assert_eq!(frag.sql, "COALESCE(i.taken_at, i.modified_time, 0) < ?");
+        match &frag.params[0] {
+            rusqlite::types::Value::Integer(n) => assert_eq!(*n, 1704067200),
+            _ => panic!("Expected integer param"),
+        }
+    }
+
+    #[test]
+    fn rating_gte() {
+        let expr = Expr::Comparison {
+            field: Field::Rating,
+            op: ComparisonOp::Gte,
+            value: Value::Number(4.0),
+        };
+        let frag = expr_to_sql(&expr);
+        assert_eq!(frag.sql, "COALESCE(i.rating, 0) >= ?");
+        match &frag.params[0] {
+            rusqlite::types::Value::Integer(n) => assert_eq!(*n, 4),
+            _ => panic!("Expected integer param"),
+        }
+    }
+
+    #[test]
+    fn group_eq_is_exists_on_group_name() {
+        let expr = Expr::Comparison {
+            field: Field::Group,
+            op: ComparisonOp::Eq,
+            value: Value::String("status".to_string()),
+        };
+        let frag = expr_to_sql(&expr);
+        assert_eq!(
+            frag.sql,
+            "EXISTS (SELECT 1 FROM item_tags it_0 JOIN tags t_0 ON it_0.tag_id = t_0.id \
+             JOIN tag_groups g_0 ON t_0.group_id = g_0.id \
+             WHERE it_0.item_id = i.id AND g_0.name = ?)"
+        );
+        assert_eq!(frag.params.len(), 1);
+    }
+
+    #[test]
+    fn group_neq_is_not_exists() {
+        let expr = Expr::Comparison {
+            field: Field::Group,
+            op: ComparisonOp::NotEq,
+            value: Value::String("status".to_string()),
+        };
+        let frag = expr_to_sql(&expr);
+        assert!(
+            frag.sql
+                .starts_with("NOT EXISTS (SELECT 1 FROM item_tags it_0")
+        );
+        assert!(frag.sql.ends_with("g_0.name = ?)"));
+    }
+
+    #[test]
+    fn group_in() {
+        let expr = Expr::InExpr {
+            field: Field::Group,
+            values: vec![
+                Value::String("status".to_string()),
+                Value::String("type".to_string()),
+            ],
+        };
+        let frag = expr_to_sql(&expr);
+        assert!(frag.sql.starts_with("EXISTS"));
+        assert!(frag.sql.contains("g_0.name IN (?, ?)"));
+        assert_eq!(frag.params.len(), 2);
+    }
+
     #[test]
     fn type_image() {
         let expr = Expr::Comparison {
@@ -470,6 +757,30 @@ mod tests {
         assert_eq!(frag.params.len(), 0);
     }
 
+    #[test]
+    fn type_in_requires_files_for_extension_types() {
+        let expr = Expr::InExpr {
+            field: Field::Type,
+            values: vec![
+                Value::String("directory".to_string()),
+                Value::String("audio".to_string()),
+            ],
+        };
+        let frag = expr_to_sql(&expr);
+        assert!(
+            frag.sql.starts_with(
+                "(i.is_directory = 1 OR (i.is_directory = 0 AND (LOWER(i.path) LIKE ?"
+            )
+        );
+        assert_eq!(frag.params.len(), 7);
+
+        let expr = Expr::InExpr {
+            field: Field::Type,
+            values: vec![Value::String("directory".to_string())],
+        };
+        assert_eq!(expr_to_sql(&expr).sql, "i.is_directory = 1");
+    }
+
     #[test]
     fn and_expression() {
         let expr = Expr::And(
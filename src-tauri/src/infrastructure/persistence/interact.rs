@@ -0,0 +1,218 @@
+//! Connection Helpers
+//!
+//! Runs blocking SQLite work on a pooled connection, mapping pool,
+//! interaction and SQLite errors to `DomainError`. Each call runs in a
+//! `db` span naming the closure it ran, and failures are logged there.
+
+use crate::domain::errors::DomainError;
+use deadpool_sqlite::Pool;
+use rusqlite::Connection;
+use std::time::Duration;
+use tracing::Instrument;
+
+/// Attempts a write makes after its first one fails with `DatabaseBusy`.
+const BUSY_RETRIES: u32 = 4;
+
+/// Delay before the first retry; doubled for each one after it.
+const BUSY_BACKOFF: Duration = Duration::from_millis(50);
+
+/// Runs `f` on a connection from `pool`, converting every error with
+/// `DomainError::from`.
+pub(crate) async fn interact<T, F>(pool: &Pool, f: F) -> Result<T, DomainError>
+where
+    F: FnOnce(&mut Connection) -> rusqlite::Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    interact_with(pool, f, DomainError::from).await
+}
+
+/// Like [`interact`], but maps SQLite errors from `f` with `map_err`, such
+/// as a [`not_found`] adapter.
+pub(crate) async fn interact_with<T, F, M>(pool: &Pool, f: F, map_err: M) -> Result<T, DomainError>
+where
+    F: FnOnce(&mut Connection) -> rusqlite::Result<T> + Send + 'static,
+    T: Send + 'static,
+    M: FnOnce(rusqlite::Error) -> DomainError,
+{
+    run(pool, f, map_err)
+        .instrument(tracing::debug_span!("db", call = caller::<F>()))
+        .await
+        .inspect_err(|e| tracing::debug!(error = %e, "Database call failed"))
+}
+
+/// Like [`interact_with`], for write transactions: runs `f` again with
+/// backoff while it fails with `DatabaseBusy`.
+///
+/// A transaction `f` leaves open after an error is rolled back, so each
+/// attempt starts on a clean connection.
+pub(crate) async fn interact_write_with<T, F, M>(
+    pool: &Pool,
+    f: F,
+    map_err: M,
+) -> Result<T, DomainError>
+where
+    F: FnOnce(&mut Connection) -> rusqlite::Result<T> + Clone + Send + 'static,
+    T: Send + 'static,
+    M: Fn(rusqlite::Error) -> DomainError,
+{
+    let span = tracing::debug_span!("db", call = caller::<F>());
+    let mut backoff = BUSY_BACKOFF;
+    let mut retries = 0;
+    let result = loop {
+        let f = f.clone();
+        let attempt = move |conn: &mut Connection| {
+            let result = f(conn);
+            if result.is_err() && !conn.is_autocommit() {
+                let _ = conn.execute("ROLLBACK", []);
+            }
+            result
+        };
+        match run(pool, attempt, &map_err).instrument(span.clone()).await {
+            Err(DomainError::DatabaseBusy(_)) if retries < BUSY_RETRIES => {
+                span.in_scope(|| tracing::debug!(retries, "Database busy, retrying"));
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+                retries += 1;
+            }
+            result => break result,
+        }
+    };
+    result.inspect_err(|e| span.in_scope(|| tracing::debug!(error = %e, "Database call failed")))
+}
+
+/// Error mapper for calls that signal a missing row with
+/// `QueryReturnedNoRows`: that becomes the repository's own not-found error
+/// from `not_found`, anything else goes through `DomainError::from`.
+pub(crate) fn not_found<N>(not_found: N) -> impl Fn(rusqlite::Error) -> DomainError + Copy
+where
+    N: Fn() -> DomainError + Copy,
+{
+    move |e| match e {
+        rusqlite::Error::QueryReturnedNoRows => not_found(),
+        e => e.into(),
+    }
+}
+
+async fn run<T, F, M>(pool: &Pool, f: F, map_err: M) -> Result<T, DomainError>
+where
+    F: FnOnce(&mut Connection) -> rusqlite::Result<T> + Send + 'static,
+    T: Send + 'static,
+    M: FnOnce(rusqlite::Error) -> DomainError,
+{
+    let conn = pool.get().await?;
+    conn.interact(f).await?.map_err(map_err)
+}
+
+/// The function a closure was written in, e.g.
+/// `...SqliteItemRepository>::save`, so a `db` span says which repository
+/// call it belongs to.
+fn caller<F>() -> &'static str {
+    let name = std::any::type_name::<F>();
+    let name = name.split("::{{closure}}").next().unwrap_or(name);
+    name.strip_prefix("constellation_lib::").unwrap_or(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::logging::tests::capture;
+    use crate::infrastructure::persistence::open_database;
+
+    #[tokio::test]
+    async fn errors_keep_their_domain_variants() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = open_database(&dir.path().join("test.db")).await.unwrap();
+
+        let count = interact(&pool, |conn| {
+            conn.execute("INSERT INTO tag_groups (name) VALUES ('Group')", [])?;
+            conn.query_row("SELECT COUNT(*) FROM tag_groups", [], |row| {
+                row.get::<_, i64>(0)
+            })
+        })
+        .await
+        .unwrap();
+        assert_eq!(count, 1);
+
+        let duplicate = interact(&pool, |conn| {
+            conn.execute("INSERT INTO tag_groups (name) VALUES ('Group')", [])
+        })
+        .await;
+        assert!(matches!(duplicate, Err(DomainError::DuplicateEntry(_))));
+
+        let invalid = interact(&pool, |conn| conn.execute("SELECT * FROM missing", [])).await;
+        assert!(matches!(invalid, Err(DomainError::DatabaseError(_))));
+
+        let missing = interact_with(
+            &pool,
+            |conn| {
+                conn.query_row("SELECT id FROM tag_groups WHERE id = 99", [], |row| {
+                    row.get::<_, i64>(0)
+                })
+            },
+            not_found(|| DomainError::TagGroupNotFound("99".to_string())),
+        )
+        .await;
+        assert!(matches!(missing, Err(DomainError::TagGroupNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn failed_calls_are_logged_in_a_db_span() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = open_database(&dir.path().join("test.db")).await.unwrap();
+
+        let captured = capture();
+        let invalid = interact(&pool, |conn| conn.execute("SELECT * FROM missing", [])).await;
+        assert!(invalid.is_err());
+
+        let records = captured.records();
+        assert!(records.iter().any(|(_, message)| {
+            message.starts_with("db;")
+                && message.contains("infrastructure::persistence::interact::tests::")
+        }));
+        let (level, message) = records
+            .iter()
+            .rev()
+            .find(|(_, message)| message.contains("Database call failed"))
+            .expect("failure was not logged");
+        assert_eq!(*level, log::Level::Debug);
+        assert!(message.contains("no such table: missing"));
+    }
+
+    #[tokio::test]
+    async fn writes_retry_while_another_connection_holds_the_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.db");
+        let pool = open_database(&path).await.unwrap();
+
+        // Fail fast instead of waiting on the pool's busy timeout
+        let write = |conn: &mut Connection| {
+            conn.busy_timeout(Duration::ZERO)?;
+            conn.execute("BEGIN IMMEDIATE", [])?;
+            conn.execute("INSERT INTO tag_groups (name) VALUES ('Group')", [])?;
+            conn.execute("COMMIT", [])
+        };
+
+        let blocker = Connection::open(&path).unwrap();
+        blocker.execute("BEGIN IMMEDIATE", []).unwrap();
+
+        let busy = interact(&pool, write).await;
+        assert!(matches!(busy, Err(DomainError::DatabaseBusy(_))));
+
+        let release = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(100));
+            blocker.execute("COMMIT", []).unwrap();
+        });
+        let written = interact_write_with(&pool, write, DomainError::from).await;
+        release.join().unwrap();
+        assert!(written.is_ok());
+
+        let count = interact(&pool, |conn| {
+            conn.query_row("SELECT COUNT(*) FROM tag_groups", [], |row| {
+                row.get::<_, i64>(0)
+            })
+        })
+        .await
+        .unwrap();
+        assert_eq!(count, 1);
+    }
+}
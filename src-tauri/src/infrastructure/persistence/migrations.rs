@@ -0,0 +1,369 @@
+//! Schema Migrations
+//!
+//! Ordered schema changes applied on top of `initialize_schema`, tracked by
+//! `PRAGMA user_version`.
+
+use super::cql_executor::{FILENAME_EXPR, RATING_EXPR};
+use super::sqlite_item_repository::normalize_item_paths;
+use rusqlite::{Connection, Result};
+
+/// How a migration changes the schema.
+pub enum MigrationStep {
+    /// A batch of SQL statements.
+    Sql(&'static str),
+    /// Code for changes that need to inspect the database first.
+    Fn(fn(&Connection) -> Result<()>),
+}
+
+/// A schema migration that upgrades a database to `version`.
+pub struct Migration {
+    pub version: u32,
+    pub up: MigrationStep,
+}
+
+/// Ordered schema migrations. Append new ones with the next version; never
+/// reorder, renumber or remove entries.
+///
+/// Databases created before versioning report version 0 and run every entry
+/// once, which is safe because these early migrations check before changing.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        up: MigrationStep::Sql(TAG_GROUP_ORDER_SQL),
+    },
+    Migration {
+        version: 2,
+        up: MigrationStep::Fn(migrate_add_file_reference_number),
+    },
+    Migration {
+        version: 3,
+        up: MigrationStep::Fn(migrate_add_parent_dir),
+    },
+    Migration {
+        version: 4,
+        up: MigrationStep::Fn(migrate_add_tags_updated_at),
+    },
+    Migration {
+        version: 5,
+        up: MigrationStep::Fn(migrate_add_file_name_index),
+    },
+    Migration {
+        version: 6,
+        up: MigrationStep::Fn(migrate_add_rating),
+    },
+    Migration {
+        version: 7,
+        up: MigrationStep::Fn(migrate_add_tag_group_exclusive),
+    },
+    Migration {
+        version: 8,
+        up: MigrationStep::Fn(migrate_add_taken_at),
+    },
+    Migration {
+        version: 9,
+        up: MigrationStep::Fn(migrate_normalize_item_paths),
+    },
+];
+
+/// Schema version of a fully migrated database, stored in `PRAGMA user_version`.
+pub const SCHEMA_VERSION: u32 = MIGRATIONS[MIGRATIONS.len() - 1].version;
+
+/// Gives tag groups sequential display_order values (by name) when more than
+/// one still has the default 0, as groups created before ordering existed do.
+const TAG_GROUP_ORDER_SQL: &str = "
+    UPDATE tag_groups
+    SET display_order = (
+        SELECT COUNT(*)
+        FROM tag_groups t2
+        WHERE t2.name < tag_groups.name
+           OR (t2.name = tag_groups.name AND t2.id < tag_groups.id)
+    ),
+    updated_at = unixepoch()
+    WHERE (SELECT COUNT(*) FROM tag_groups WHERE display_order = 0) > 1";
+
+/// Reads the schema version (`PRAGMA user_version`) of a database.
+pub fn schema_version(conn: &Connection) -> Result<u32> {
+    conn.pragma_query_value(None, "user_version", |row| row.get(0))
+}
+
+/// Applies the migrations newer than the database's schema version, each in
+/// its own transaction together with the `user_version` bump. Returns the
+/// number of migrations applied.
+pub fn run_migrations(conn: &Connection) -> Result<u32> {
+    let current = schema_version(conn)?;
+    let mut applied = 0;
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        let tx = conn.unchecked_transaction()?;
+        match migration.up {
+            MigrationStep::Sql(sql) => tx.execute_batch(sql)?,
+            MigrationStep::Fn(up) => up(&tx)?,
+        }
+        tx.pragma_update(None, "user_version", migration.version)?;
+        tx.commit()?;
+        applied += 1;
+    }
+
+    Ok(applied)
+}
+
+/// Adds the file_reference_number column to existing items tables.
+fn migrate_add_file_reference_number(conn: &Connection) -> Result<()> {
+    let has_column: bool = conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info('items') WHERE name = 'file_reference_number'",
+        [],
+        |row| row.get::<_, i64>(0).map(|c| c > 0),
+    )?;
+
+    if !has_column {
+        conn.execute(
+            "ALTER TABLE items ADD COLUMN file_reference_number INTEGER",
+            [],
+        )?;
+    }
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_items_file_reference_number ON items(file_reference_number)",
+        [],
+    )?;
+
+    Ok(())
+}
+
+/// Adds the parent_dir generated column (path up to the last separator) used for
+/// grouping search results by directory.
+fn migrate_add_parent_dir(conn: &Connection) -> Result<()> {
+    // Generated columns are hidden from table_info, so check table_xinfo
+    let has_column: bool = conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_xinfo('items') WHERE name = 'parent_dir'",
+        [],
+        |row| row.get::<_, i64>(0).map(|c| c > 0),
+    )?;
+
+    if !has_column {
+        conn.execute(
+            "ALTER TABLE items ADD COLUMN parent_dir TEXT GENERATED ALWAYS AS (
+                rtrim(rtrim(path, replace(replace(path, '\\', ''), '/', '')), '\\/')
+            ) VIRTUAL",
+            [],
+        )?;
+    }
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_items_parent_dir ON items(parent_dir)",
+        [],
+    )?;
+
+    Ok(())
+}
+
+/// Adds the tags_updated_at column, backfilled from the newest item_tags row per item.
+fn migrate_add_tags_updated_at(conn: &Connection) -> Result<()> {
+    let has_column: bool = conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info('items') WHERE name = 'tags_updated_at'",
+        [],
+        |row| row.get::<_, i64>(0).map(|c| c > 0),
+    )?;
+
+    if !has_column {
+        conn.execute("ALTER TABLE items ADD COLUMN tags_updated_at INTEGER", [])?;
+        conn.execute(
+            "UPDATE items SET tags_updated_at = (
+                SELECT MAX(created_at) FROM item_tags WHERE item_tags.item_id = items.id
+             )",
+            [],
+        )?;
+    }
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_items_tags_updated_at ON items(tags_updated_at)",
+        [],
+    )?;
+
+    Ok(())
+}
+
+/// Adds an expression index on the lowercased file name, matching the
+/// `FILENAME_EXPR` used by searches so prefix/exact name lookups can use it.
+fn migrate_add_file_name_index(conn: &Connection) -> Result<()> {
+    // Index expressions may not use table aliases; SQLite still matches the
+    // aliased form in queries since both resolve to items.path.
+    conn.execute(
+        &format!(
+            "CREATE INDEX IF NOT EXISTS idx_items_file_name ON items({})",
+            FILENAME_EXPR.replace("i.path", "path")
+        ),
+        [],
+    )?;
+    Ok(())
+}
+
+/// Adds the nullable 0-5 rating column and an index on `RATING_EXPR`, the
+/// form CQL rating comparisons use.
+fn migrate_add_rating(conn: &Connection) -> Result<()> {
+    let has_column: bool = conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info('items') WHERE name = 'rating'",
+        [],
+        |row| row.get::<_, i64>(0).map(|c| c > 0),
+    )?;
+
+    if !has_column {
+        conn.execute(
+            "ALTER TABLE items ADD COLUMN rating INTEGER CHECK (rating BETWEEN 0 AND 5)",
+            [],
+        )?;
+    }
+
+    conn.execute(
+        &format!(
+            "CREATE INDEX IF NOT EXISTS idx_items_rating ON items({})",
+            RATING_EXPR.replace("i.rating", "rating")
+        ),
+        [],
+    )?;
+
+    Ok(())
+}
+
+/// Adds the exclusive flag to tag groups; existing groups stay non-exclusive.
+fn migrate_add_tag_group_exclusive(conn: &Connection) -> Result<()> {
+    let has_column: bool = conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info('tag_groups') WHERE name = 'exclusive'",
+        [],
+        |row| row.get::<_, i64>(0).map(|c| c > 0),
+    )?;
+
+    if !has_column {
+        conn.execute(
+            "ALTER TABLE tag_groups ADD COLUMN exclusive BOOLEAN NOT NULL DEFAULT 0",
+            [],
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Adds the nullable EXIF capture time; existing items get it on the next
+/// metadata refresh.
+fn migrate_add_taken_at(conn: &Connection) -> Result<()> {
+    let has_column: bool = conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info('items') WHERE name = 'taken_at'",
+        [],
+        |row| row.get::<_, i64>(0).map(|c| c > 0),
+    )?;
+
+    if !has_column {
+        conn.execute("ALTER TABLE items ADD COLUMN taken_at INTEGER", [])?;
+    }
+
+    Ok(())
+}
+
+/// Normalizes the paths of items created before `FilePath` normalized them,
+/// merging items that turn out to share a path.
+fn migrate_normalize_item_paths(conn: &Connection) -> Result<()> {
+    normalize_item_paths(conn).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::persistence::schema::initialize_schema;
+
+    fn fresh() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        initialize_schema(&conn).unwrap();
+        run_migrations(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn versions_are_sequential_from_one() {
+        for (index, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version, index as u32 + 1);
+        }
+    }
+
+    fn has_index(conn: &Connection, name: &str) -> bool {
+        conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'index' AND name = ?1)",
+            [name],
+            |row| row.get(0),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn fresh_database_is_at_latest_version() {
+        let conn = fresh();
+        assert_eq!(schema_version(&conn).unwrap(), SCHEMA_VERSION);
+        assert_eq!(run_migrations(&conn).unwrap(), 0);
+    }
+
+    #[test]
+    fn migrations_step_up_from_stored_version_only() {
+        let conn = fresh();
+        conn.execute_batch("DROP INDEX idx_items_rating; DROP INDEX idx_items_file_name;")
+            .unwrap();
+        // Version 5 is the file name index, version 6 the rating column
+        conn.pragma_update(None, "user_version", 5).unwrap();
+
+        assert_eq!(run_migrations(&conn).unwrap(), SCHEMA_VERSION - 5);
+        assert!(has_index(&conn, "idx_items_rating"));
+        assert!(!has_index(&conn, "idx_items_file_name"));
+        assert_eq!(schema_version(&conn).unwrap(), SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn unversioned_database_runs_every_migration_once() {
+        let conn = fresh();
+        conn.pragma_update(None, "user_version", 0).unwrap();
+
+        assert_eq!(run_migrations(&conn).unwrap(), SCHEMA_VERSION);
+        assert_eq!(run_migrations(&conn).unwrap(), 0);
+        assert_eq!(schema_version(&conn).unwrap(), SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn tag_group_order_numbers_unordered_groups_by_name() {
+        let conn = fresh();
+        conn.execute_batch(
+            "INSERT INTO tag_groups (name) VALUES ('b'), ('c'), ('a');
+             PRAGMA user_version = 0;",
+        )
+        .unwrap();
+
+        run_migrations(&conn).unwrap();
+
+        let mut stmt = conn
+            .prepare("SELECT name FROM tag_groups ORDER BY display_order")
+            .unwrap();
+        let names: Vec<String> = stmt
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(names, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn tag_group_order_keeps_existing_order() {
+        let conn = fresh();
+        conn.execute_batch(
+            "INSERT INTO tag_groups (name, display_order) VALUES ('b', 0), ('a', 1);
+             PRAGMA user_version = 0;",
+        )
+        .unwrap();
+
+        run_migrations(&conn).unwrap();
+
+        let first: String = conn
+            .query_row(
+                "SELECT name FROM tag_groups ORDER BY display_order LIMIT 1",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(first, "b");
+    }
+}
@@ -3,7 +3,12 @@
 //! SQLite implementations of repository interfaces.
 
 mod cql_executor;
+mod interact;
+mod migrations;
 mod schema;
+mod sql_console;
+mod sqlite_auto_tag_rule_repository;
+mod sqlite_collection_repository;
 mod sqlite_item_repository;
 mod sqlite_search_history_repository;
 mod sqlite_search_repository;
@@ -13,6 +18,11 @@ mod sqlite_tag_repository;
 mod sqlite_tag_template_repository;
 
 pub use schema::init_database;
+#[cfg(test)]
+pub use schema::open_database;
+pub use sql_console::run_readonly_query;
+pub use sqlite_auto_tag_rule_repository::SqliteAutoTagRuleRepository;
+pub use sqlite_collection_repository::SqliteCollectionRepository;
 pub use sqlite_item_repository::SqliteItemRepository;
 pub use sqlite_search_history_repository::SqliteSearchHistoryRepository;
 pub use sqlite_search_repository::SqliteSearchRepository;
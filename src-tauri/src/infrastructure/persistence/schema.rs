@@ -2,13 +2,41 @@
 //!
 //! Defines the database schema and initialization logic.
 
-use deadpool_sqlite::{Config, Pool, Runtime};
+use super::migrations::{SCHEMA_VERSION, run_migrations, schema_version};
+use crate::domain::entities::ONBOARDING_SEEDED_KEY;
+use deadpool_sqlite::{Config, Hook, HookError, Pool, Runtime};
 use rusqlite::{Connection, Result};
 use std::path::Path;
+use std::time::Duration;
 
-/// Initializes the database and returns a connection pool.
+/// How long a pooled connection waits on a locked database before a
+/// statement fails with `SQLITE_BUSY`.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Starter tag groups (name, color, example tags) for a brand new database.
+const STARTER_GROUPS: &[(&str, &str, &[&str])] = &[
+    ("Status", "#3B82F6", &["To Do", "In Progress", "Done"]),
+    ("Type", "#10B981", &["Document", "Image"]),
+    ("Project", "#F59E0B", &["Example"]),
+];
+
+/// Initializes the database, seeds first-run data and returns a connection pool.
 pub async fn init_database(
     db_path: &Path,
+) -> std::result::Result<Pool, Box<dyn std::error::Error>> {
+    let pool = open_database(db_path).await?;
+
+    let conn = pool.get().await?;
+    conn.interact(|conn: &mut Connection| seed_default_data(conn))
+        .await??;
+
+    Ok(pool)
+}
+
+/// Opens the database, creates missing tables and applies pending migrations,
+/// without seeding.
+pub async fn open_database(
+    db_path: &Path,
 ) -> std::result::Result<Pool, Box<dyn std::error::Error>> {
     // Create database file if it doesn't exist
     if !db_path.exists() {
@@ -23,22 +51,83 @@ pub async fn init_database(
     let pool = cfg
         .builder(Runtime::Tokio1)
         .expect("Failed to create pool builder")
+        .post_create(Hook::async_fn(|conn, _| {
+            Box::pin(async move {
+                conn.interact(|conn: &mut Connection| conn.busy_timeout(BUSY_TIMEOUT))
+                    .await
+                    .map_err(|e| HookError::message(e.to_string()))?
+                    .map_err(HookError::Backend)
+            })
+        }))
         .build()
         .map_err(|e| Box::new(e) as Box<dyn std::error::Error>)?;
 
     // Initialize schema on first connection
     let conn = pool.get().await?;
-    conn.interact(|conn: &mut Connection| {
-        initialize_schema(conn)?;
-        migrate_tag_group_order(conn)?;
-        migrate_add_file_reference_number(conn)?;
-        Ok::<(), rusqlite::Error>(())
-    })
-    .await??;
+    let version = conn
+        .interact(|conn: &mut Connection| {
+            initialize_schema(conn)?;
+            run_migrations(conn)?;
+            schema_version(conn)
+        })
+        .await??;
+
+    if version > SCHEMA_VERSION {
+        return Err(format!(
+            "Database schema version {} is newer than this app supports ({})",
+            version, SCHEMA_VERSION
+        )
+        .into());
+    }
 
     Ok(pool)
 }
 
+/// Creates the starter tag groups and tags on first run.
+///
+/// Runs at most once per database: the `onboarding_seeded` flag is set even
+/// when seeding is skipped because tag groups already exist. Returns whether
+/// anything was inserted.
+pub fn seed_default_data(conn: &mut Connection) -> Result<bool> {
+    let tx = conn.transaction()?;
+
+    let already_seeded: bool = tx.query_row(
+        "SELECT EXISTS(SELECT 1 FROM settings WHERE key = ?1)",
+        [ONBOARDING_SEEDED_KEY],
+        |row| row.get(0),
+    )?;
+    if already_seeded {
+        return Ok(false);
+    }
+
+    let has_groups: bool = tx.query_row("SELECT EXISTS(SELECT 1 FROM tag_groups)", [], |row| {
+        row.get(0)
+    })?;
+    if !has_groups {
+        for (order, (name, color, tags)) in STARTER_GROUPS.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO tag_groups (name, color, display_order) VALUES (?1, ?2, ?3)",
+                rusqlite::params![name, color, order as i64],
+            )?;
+            let group_id = tx.last_insert_rowid();
+            for value in *tags {
+                tx.execute(
+                    "INSERT INTO tags (group_id, value) VALUES (?1, ?2)",
+                    rusqlite::params![group_id, value],
+                )?;
+            }
+        }
+    }
+
+    tx.execute(
+        "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, 'true')",
+        [ONBOARDING_SEEDED_KEY],
+    )?;
+    tx.commit()?;
+
+    Ok(!has_groups)
+}
+
 /// Initializes the database schema.
 pub fn initialize_schema(conn: &Connection) -> Result<()> {
     // Tag Groups table
@@ -48,6 +137,7 @@ pub fn initialize_schema(conn: &Connection) -> Result<()> {
             name TEXT NOT NULL UNIQUE,
             color TEXT,
             display_order INTEGER NOT NULL DEFAULT 0,
+            exclusive BOOLEAN NOT NULL DEFAULT 0,
             created_at INTEGER NOT NULL DEFAULT (unixepoch()),
             updated_at INTEGER NOT NULL DEFAULT (unixepoch())
         )",
@@ -80,7 +170,10 @@ pub fn initialize_schema(conn: &Connection) -> Result<()> {
             created_at INTEGER NOT NULL DEFAULT (unixepoch()),
             updated_at INTEGER NOT NULL DEFAULT (unixepoch()),
             is_deleted BOOLEAN NOT NULL DEFAULT 0,
-            deleted_at INTEGER
+            deleted_at INTEGER,
+            tags_updated_at INTEGER,
+            rating INTEGER CHECK (rating BETWEEN 0 AND 5),
+            taken_at INTEGER
         )",
         [],
     )?;
@@ -121,6 +214,47 @@ pub fn initialize_schema(conn: &Connection) -> Result<()> {
         [],
     )?;
 
+    // Template tags named by group and value, resolved when applied
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS template_tag_specs (
+            template_id INTEGER NOT NULL,
+            group_name TEXT NOT NULL,
+            value TEXT NOT NULL,
+            position INTEGER NOT NULL,
+            PRIMARY KEY (template_id, group_name, value),
+            FOREIGN KEY (template_id) REFERENCES tag_templates(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // Auto tag rules: apply a template to items created under a directory
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS auto_tag_rules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            path_prefix TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            template_id INTEGER NOT NULL,
+            enabled BOOLEAN NOT NULL DEFAULT 1,
+            created_at INTEGER NOT NULL DEFAULT (unixepoch()),
+            updated_at INTEGER NOT NULL DEFAULT (unixepoch()),
+            FOREIGN KEY (template_id) REFERENCES tag_templates(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // Collections: saved CQL queries shown as sidebar folders
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS collections (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            cql_query TEXT NOT NULL,
+            icon TEXT,
+            display_order INTEGER NOT NULL DEFAULT 0,
+            created_at INTEGER NOT NULL DEFAULT (unixepoch()),
+            updated_at INTEGER NOT NULL DEFAULT (unixepoch())
+        )",
+        [],
+    )?;
+
     // Search History table
     conn.execute(
         "CREATE TABLE IF NOT EXISTS search_histories (
@@ -178,7 +312,7 @@ pub fn initialize_schema(conn: &Connection) -> Result<()> {
         "CREATE INDEX IF NOT EXISTS idx_items_is_deleted ON items(is_deleted)",
         [],
     )?;
-    // Note: idx_items_file_reference_number is created in migrate_add_file_reference_number()
+    // Note: idx_items_file_reference_number is created in migrations::migrate_add_file_reference_number()
     // to avoid failure on existing databases where the column doesn't exist yet.
     conn.execute(
         "CREATE INDEX IF NOT EXISTS idx_tags_group_id ON tags(group_id)",
@@ -204,50 +338,79 @@ pub fn initialize_schema(conn: &Connection) -> Result<()> {
     Ok(())
 }
 
-/// Migrates existing tag groups to have sequential display_order values.
-pub fn migrate_tag_group_order(conn: &Connection) -> Result<()> {
-    let needs_migration: bool = conn.query_row(
-        "SELECT COUNT(*) > 1 FROM tag_groups WHERE display_order = 0",
-        [],
-        |row| row.get::<_, i64>(0).map(|count| count > 1),
-    )?;
-
-    if needs_migration {
-        conn.execute(
-            "UPDATE tag_groups
-             SET display_order = (
-                 SELECT COUNT(*)
-                 FROM tag_groups t2
-                 WHERE t2.name < tag_groups.name
-                    OR (t2.name = tag_groups.name AND t2.id < tag_groups.id)
-             ),
-             updated_at = unixepoch()",
-            [],
-        )?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fresh() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        initialize_schema(&conn).unwrap();
+        run_migrations(&conn).unwrap();
+        conn
     }
 
-    Ok(())
-}
+    fn count(conn: &Connection, table: &str) -> i64 {
+        conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| {
+            row.get(0)
+        })
+        .unwrap()
+    }
 
-/// Adds the file_reference_number column to existing items tables.
-pub fn migrate_add_file_reference_number(conn: &Connection) -> Result<()> {
-    let has_column: bool = conn.query_row(
-        "SELECT COUNT(*) FROM pragma_table_info('items') WHERE name = 'file_reference_number'",
-        [],
-        |row| row.get::<_, i64>(0).map(|c| c > 0),
-    )?;
+    #[test]
+    fn seeds_fresh_database_exactly_once() {
+        let mut conn = fresh();
 
-    if !has_column {
-        conn.execute(
-            "ALTER TABLE items ADD COLUMN file_reference_number INTEGER",
-            [],
-        )?;
+        assert!(seed_default_data(&mut conn).unwrap());
+        assert_eq!(count(&conn, "tag_groups"), STARTER_GROUPS.len() as i64);
+        assert_eq!(count(&conn, "tags"), 6);
+
+        // Deleting the starter data does not bring it back on the next start
+        conn.execute("DELETE FROM tag_groups", []).unwrap();
+        assert!(!seed_default_data(&mut conn).unwrap());
+        assert_eq!(count(&conn, "tag_groups"), 0);
     }
 
-    conn.execute(
-        "CREATE INDEX IF NOT EXISTS idx_items_file_reference_number ON items(file_reference_number)",
-        [],
-    )?;
+    #[test]
+    fn skips_seeding_when_groups_exist() {
+        let mut conn = fresh();
+        conn.execute("INSERT INTO tag_groups (name) VALUES ('Mine')", [])
+            .unwrap();
 
-    Ok(())
+        assert!(!seed_default_data(&mut conn).unwrap());
+        assert_eq!(count(&conn, "tag_groups"), 1);
+        assert_eq!(count(&conn, "tags"), 0);
+
+        // Marked as done, so emptying the database later does not seed
+        conn.execute("DELETE FROM tag_groups", []).unwrap();
+        assert!(!seed_default_data(&mut conn).unwrap());
+        assert_eq!(count(&conn, "tag_groups"), 0);
+    }
+
+    #[tokio::test]
+    async fn refuses_database_from_newer_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.db");
+        let conn = Connection::open(&path).unwrap();
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION + 1)
+            .unwrap();
+        drop(conn);
+
+        assert!(open_database(&path).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn init_database_seeds_on_first_open_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.db");
+
+        for _ in 0..2 {
+            let pool = init_database(&path).await.unwrap();
+            let conn = pool.get().await.unwrap();
+            let groups = conn
+                .interact(|conn: &mut Connection| count(conn, "tag_groups"))
+                .await
+                .unwrap();
+            assert_eq!(groups, STARTER_GROUPS.len() as i64);
+        }
+    }
 }
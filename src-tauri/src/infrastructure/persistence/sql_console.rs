@@ -0,0 +1,237 @@
+//! SQL Console
+//!
+//! Runs a single read-only SELECT against the library database on a
+//! dedicated connection, for power users inspecting their own data.
+
+use crate::application::dto::SqlQueryResultDto;
+use crate::domain::errors::DomainError;
+use rusqlite::types::ValueRef;
+use rusqlite::{Batch, Connection, OpenFlags, Statement};
+use std::path::Path;
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// Runs `sql` on a new read-only connection to `db_path` and returns at most
+/// `max_rows` rows.
+///
+/// `sql` must be a single SELECT or WITH statement that SQLite reports as
+/// read-only; anything else is rejected after preparing and before it runs.
+/// The connection is opened read-only with `query_only` on as a second guard.
+/// A query still running after `timeout` is interrupted with `QueryTimeout`.
+pub fn run_readonly_query(
+    db_path: &Path,
+    sql: &str,
+    max_rows: usize,
+    timeout: Duration,
+) -> Result<SqlQueryResultDto, DomainError> {
+    let conn = Connection::open_with_flags(
+        db_path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )?;
+    conn.pragma_update(None, "query_only", true)?;
+    conn.busy_timeout(timeout)?;
+
+    let mut stmt = prepare_readonly(&conn, sql)?;
+    let columns: Vec<String> = stmt
+        .column_names()
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+
+    // Interrupt the query unless it finishes (dropping `done`) in time
+    let (done, finished) = mpsc::channel::<()>();
+    let interrupt = conn.get_interrupt_handle();
+    let timer = std::thread::spawn(move || {
+        if finished.recv_timeout(timeout) == Err(mpsc::RecvTimeoutError::Timeout) {
+            interrupt.interrupt();
+        }
+    });
+
+    let result = (|| {
+        let mut rows = Vec::new();
+        let mut truncated = false;
+        let mut query = stmt.query([])?;
+        while let Some(row) = query.next()? {
+            if rows.len() == max_rows {
+                truncated = true;
+                break;
+            }
+            let values = (0..columns.len())
+                .map(|i| row.get_ref(i).map(to_json))
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            rows.push(values);
+        }
+        Ok::<_, rusqlite::Error>((rows, truncated))
+    })();
+    drop(done);
+    let _ = timer.join();
+
+    match result {
+        Ok((rows, truncated)) => Ok(SqlQueryResultDto {
+            columns,
+            rows,
+            truncated,
+        }),
+        Err(rusqlite::Error::SqliteFailure(err, _))
+            if err.code == rusqlite::ErrorCode::OperationInterrupted =>
+        {
+            Err(DomainError::QueryTimeout(format!(
+                "Query exceeded {} seconds",
+                timeout.as_secs()
+            )))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Prepares `sql`, rejecting anything but a single read-only SELECT or WITH.
+fn prepare_readonly<'conn>(
+    conn: &'conn Connection,
+    sql: &str,
+) -> Result<Statement<'conn>, DomainError> {
+    let keyword = leading_keyword(sql);
+    if keyword != "SELECT" && keyword != "WITH" {
+        return Err(DomainError::ValidationError(
+            "Only SELECT and WITH queries are allowed".to_string(),
+        ));
+    }
+
+    let mut batch = Batch::new(conn, sql);
+    let stmt = batch
+        .next()?
+        .ok_or_else(|| DomainError::ValidationError("Query is empty".to_string()))?;
+    if !matches!(batch.next(), Ok(None)) {
+        return Err(DomainError::ValidationError(
+            "Only a single statement is allowed".to_string(),
+        ));
+    }
+    if !stmt.readonly() {
+        return Err(DomainError::ValidationError(
+            "Query would modify the database".to_string(),
+        ));
+    }
+
+    Ok(stmt)
+}
+
+/// First keyword of `sql` in upper case, skipping whitespace and comments.
+fn leading_keyword(sql: &str) -> String {
+    let mut rest = sql.trim_start();
+    loop {
+        if let Some(comment) = rest.strip_prefix("--") {
+            rest = comment.split_once('\n').map_or("", |(_, r)| r).trim_start();
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            rest = comment.split_once("*/").map_or("", |(_, r)| r).trim_start();
+        } else {
+            break;
+        }
+    }
+
+    rest.chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .collect::<String>()
+        .to_ascii_uppercase()
+}
+
+fn to_json(value: ValueRef) -> serde_json::Value {
+    match value {
+        ValueRef::Null => serde_json::Value::Null,
+        ValueRef::Integer(i) => i.into(),
+        ValueRef::Real(f) => serde_json::Number::from_f64(f)
+            .map_or(serde_json::Value::Null, serde_json::Value::Number),
+        ValueRef::Text(text) => String::from_utf8_lossy(text).into_owned().into(),
+        ValueRef::Blob(blob) => format!("<blob: {} bytes>", blob.len()).into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::persistence::open_database;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    async fn setup() -> (tempfile::TempDir, std::path::PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("test.db");
+        open_database(&path).await.unwrap();
+
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch("INSERT INTO tag_groups (name) VALUES ('A'), ('B'), ('C');")
+            .unwrap();
+        (dir, path)
+    }
+
+    fn group_count(path: &Path) -> i64 {
+        let conn = Connection::open(path).unwrap();
+        conn.query_row("SELECT COUNT(*) FROM tag_groups", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn select_returns_columns_and_limited_rows() {
+        let (_dir, path) = setup().await;
+
+        let result = run_readonly_query(
+            &path,
+            "-- groups\nSELECT name, 1.5 AS ratio, NULL AS nothing FROM tag_groups ORDER BY name",
+            2,
+            TIMEOUT,
+        )
+        .unwrap();
+
+        assert_eq!(result.columns, vec!["name", "ratio", "nothing"]);
+        assert_eq!(
+            result.rows,
+            vec![
+                vec!["A".into(), 1.5.into(), serde_json::Value::Null],
+                vec!["B".into(), 1.5.into(), serde_json::Value::Null],
+            ]
+        );
+        assert!(result.truncated);
+    }
+
+    #[tokio::test]
+    async fn mutations_are_rejected_before_running() {
+        let (_dir, path) = setup().await;
+
+        for sql in [
+            "DELETE FROM tag_groups",
+            "SELECT 1; DELETE FROM tag_groups",
+            "SELECT 1; SELECT 2",
+            "WITH doomed AS (SELECT id FROM tag_groups) DELETE FROM tag_groups WHERE id IN doomed",
+            "PRAGMA user_version = 99",
+            "PRAGMA query_only = OFF",
+            "ATTACH DATABASE ':memory:' AS other",
+            "/* hidden */ DROP TABLE tag_groups",
+            "",
+        ] {
+            let result = run_readonly_query(&path, sql, 10, TIMEOUT);
+            assert!(
+                matches!(result, Err(DomainError::ValidationError(_))),
+                "{sql:?} was not rejected: {result:?}"
+            );
+        }
+
+        assert_eq!(group_count(&path), 3);
+        let conn = Connection::open(&path).unwrap();
+        let version: i64 = conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap();
+        assert_ne!(version, 99);
+    }
+
+    #[tokio::test]
+    async fn long_queries_time_out() {
+        let (_dir, path) = setup().await;
+
+        let result = run_readonly_query(
+            &path,
+            "WITH RECURSIVE n(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM n) SELECT MAX(x) FROM n",
+            10,
+            Duration::from_millis(50),
+        );
+
+        assert!(matches!(result, Err(DomainError::QueryTimeout(_))));
+    }
+}
@@ -0,0 +1,159 @@
+//! SQLite AutoTagRule Repository
+//!
+//! Implementation of AutoTagRuleRepository for SQLite.
+
+use super::interact::{interact, interact_with};
+use crate::domain::entities::AutoTagRule;
+use crate::domain::errors::DomainError;
+use crate::domain::repositories::AutoTagRuleRepository;
+use async_trait::async_trait;
+use deadpool_sqlite::Pool;
+use rusqlite::{Connection, OptionalExtension, Row};
+use std::sync::Arc;
+
+/// SQLite implementation of AutoTagRuleRepository.
+pub struct SqliteAutoTagRuleRepository {
+    pool: Arc<Pool>,
+}
+
+impl SqliteAutoTagRuleRepository {
+    pub fn new(pool: Arc<Pool>) -> Self {
+        Self { pool }
+    }
+
+    fn map_row_to_rule(row: &Row) -> rusqlite::Result<AutoTagRule> {
+        Ok(AutoTagRule::reconstitute(
+            row.get(0)?,
+            row.get(1)?,
+            row.get(2)?,
+            row.get(3)?,
+            row.get(4)?,
+            row.get(5)?,
+        ))
+    }
+
+    /// Loads the enabled rules on an open connection, so item writes can
+    /// apply them inside their own transaction.
+    pub(super) fn load_enabled(conn: &Connection) -> rusqlite::Result<Vec<AutoTagRule>> {
+        conn.prepare(
+            "SELECT id, path_prefix, template_id, enabled, created_at, updated_at
+             FROM auto_tag_rules WHERE enabled = 1",
+        )?
+        .query_map([], Self::map_row_to_rule)?
+        .collect()
+    }
+}
+
+#[async_trait]
+impl AutoTagRuleRepository for SqliteAutoTagRuleRepository {
+    async fn save(&self, rule: &mut AutoTagRule) -> Result<i64, DomainError> {
+        let path_prefix = rule.path_prefix().to_string();
+        let template_id = rule.template_id();
+        let enabled = rule.enabled();
+
+        let id = interact_with(
+            &self.pool,
+            move |conn: &mut Connection| {
+                conn.execute(
+                    "INSERT INTO auto_tag_rules (path_prefix, template_id, enabled) VALUES (?1, ?2, ?3)",
+                    (&path_prefix, template_id, enabled),
+                )?;
+                Ok::<i64, rusqlite::Error>(conn.last_insert_rowid())
+            },
+            |e| map_write_error(e, rule.path_prefix()),
+        )
+        .await?;
+
+        rule.set_id(id);
+        Ok(id)
+    }
+
+    async fn find_by_id(&self, id: i64) -> Result<Option<AutoTagRule>, DomainError> {
+        interact(&self.pool, move |conn: &mut Connection| {
+            conn.query_row(
+                "SELECT id, path_prefix, template_id, enabled, created_at, updated_at
+                 FROM auto_tag_rules WHERE id = ?1",
+                [id],
+                Self::map_row_to_rule,
+            )
+            .optional()
+        })
+        .await
+    }
+
+    async fn find_all(&self) -> Result<Vec<AutoTagRule>, DomainError> {
+        interact(&self.pool, |conn: &mut Connection| {
+            let mut stmt = conn.prepare(
+                "SELECT id, path_prefix, template_id, enabled, created_at, updated_at
+                 FROM auto_tag_rules ORDER BY path_prefix COLLATE NOCASE ASC",
+            )?;
+
+            let rules = stmt
+                .query_map([], Self::map_row_to_rule)?
+                .collect::<Result<Vec<AutoTagRule>, _>>()?;
+
+            Ok::<Vec<AutoTagRule>, rusqlite::Error>(rules)
+        })
+        .await
+    }
+
+    async fn update(&self, rule: &AutoTagRule) -> Result<(), DomainError> {
+        let id = rule.id().ok_or_else(|| {
+            DomainError::ValidationError("Cannot update rule without ID".to_string())
+        })?;
+
+        let path_prefix = rule.path_prefix().to_string();
+        let template_id = rule.template_id();
+        let enabled = rule.enabled();
+
+        let updated = interact_with(
+            &self.pool,
+            move |conn: &mut Connection| {
+                conn.execute(
+                    "UPDATE auto_tag_rules
+                     SET path_prefix = ?1, template_id = ?2, enabled = ?3, updated_at = unixepoch()
+                     WHERE id = ?4",
+                    (&path_prefix, template_id, enabled, id),
+                )
+            },
+            |e| map_write_error(e, rule.path_prefix()),
+        )
+        .await?;
+
+        if updated == 0 {
+            return Err(DomainError::AutoTagRuleNotFound(id.to_string()));
+        }
+
+        Ok(())
+    }
+
+    async fn delete(&self, id: i64) -> Result<(), DomainError> {
+        let deleted = interact(&self.pool, move |conn: &mut Connection| {
+            conn.execute("DELETE FROM auto_tag_rules WHERE id = ?1", [id])
+        })
+        .await?;
+
+        if deleted == 0 {
+            return Err(DomainError::AutoTagRuleNotFound(id.to_string()));
+        }
+
+        Ok(())
+    }
+}
+
+/// Maps insert/update failures, reporting a taken prefix as a duplicate and
+/// an unknown template as not found.
+fn map_write_error(e: rusqlite::Error, path_prefix: &str) -> DomainError {
+    if let rusqlite::Error::SqliteFailure(err, _) = &e {
+        if err.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_UNIQUE {
+            return DomainError::DuplicateEntry(format!(
+                "A rule for {} already exists",
+                path_prefix
+            ));
+        }
+        if err.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_FOREIGNKEY {
+            return DomainError::TagTemplateNotFound("Tag template not found".to_string());
+        }
+    }
+    e.into()
+}
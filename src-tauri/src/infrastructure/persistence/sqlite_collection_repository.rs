@@ -0,0 +1,160 @@
+//! SQLite Collection Repository
+//!
+//! Implementation of CollectionRepository for SQLite.
+
+use super::interact::{interact, interact_write_with};
+use crate::domain::entities::Collection;
+use crate::domain::errors::DomainError;
+use crate::domain::repositories::CollectionRepository;
+use async_trait::async_trait;
+use deadpool_sqlite::Pool;
+use rusqlite::{Connection, OptionalExtension, Row};
+use std::sync::Arc;
+
+/// SQLite implementation of CollectionRepository.
+pub struct SqliteCollectionRepository {
+    pool: Arc<Pool>,
+}
+
+impl SqliteCollectionRepository {
+    pub fn new(pool: Arc<Pool>) -> Self {
+        Self { pool }
+    }
+
+    fn map_row_to_collection(row: &Row) -> rusqlite::Result<Collection> {
+        Ok(Collection::reconstitute(
+            row.get(0)?,
+            row.get(1)?,
+            row.get(2)?,
+            row.get(3)?,
+            row.get(4)?,
+            row.get(5)?,
+            row.get(6)?,
+        ))
+    }
+}
+
+#[async_trait]
+impl CollectionRepository for SqliteCollectionRepository {
+    async fn save(&self, collection: &mut Collection) -> Result<i64, DomainError> {
+        let name = collection.name().to_string();
+        let cql_query = collection.cql_query().to_string();
+        let icon = collection.icon().map(str::to_string);
+        let display_order = collection.display_order();
+
+        let id = interact(
+            &self.pool,
+            move |conn: &mut Connection| {
+                conn.execute(
+                    "INSERT INTO collections (name, cql_query, icon, display_order) VALUES (?1, ?2, ?3, ?4)",
+                    (&name, &cql_query, &icon, display_order),
+                )?;
+                Ok::<i64, rusqlite::Error>(conn.last_insert_rowid())
+            },
+        )
+        .await?;
+
+        collection.set_id(id);
+        Ok(id)
+    }
+
+    async fn find_by_id(&self, id: i64) -> Result<Option<Collection>, DomainError> {
+        interact(&self.pool, move |conn: &mut Connection| {
+            conn.query_row(
+                "SELECT id, name, cql_query, icon, display_order, created_at, updated_at
+                 FROM collections WHERE id = ?1",
+                [id],
+                Self::map_row_to_collection,
+            )
+            .optional()
+        })
+        .await
+    }
+
+    async fn find_all(&self) -> Result<Vec<Collection>, DomainError> {
+        interact(&self.pool, |conn: &mut Connection| {
+            let mut stmt = conn.prepare(
+                "SELECT id, name, cql_query, icon, display_order, created_at, updated_at
+                 FROM collections ORDER BY display_order ASC, id ASC",
+            )?;
+
+            let collections = stmt
+                .query_map([], Self::map_row_to_collection)?
+                .collect::<Result<Vec<Collection>, _>>()?;
+
+            Ok::<Vec<Collection>, rusqlite::Error>(collections)
+        })
+        .await
+    }
+
+    async fn update(&self, collection: &Collection) -> Result<(), DomainError> {
+        let id = collection.id().ok_or_else(|| {
+            DomainError::ValidationError("Cannot update collection without ID".to_string())
+        })?;
+
+        let name = collection.name().to_string();
+        let cql_query = collection.cql_query().to_string();
+        let icon = collection.icon().map(str::to_string);
+
+        let updated = interact(&self.pool, move |conn: &mut Connection| {
+            conn.execute(
+                "UPDATE collections
+                 SET name = ?1, cql_query = ?2, icon = ?3, updated_at = unixepoch()
+                 WHERE id = ?4",
+                (&name, &cql_query, &icon, id),
+            )
+        })
+        .await?;
+
+        if updated == 0 {
+            return Err(DomainError::CollectionNotFound(id.to_string()));
+        }
+
+        Ok(())
+    }
+
+    async fn delete(&self, id: i64) -> Result<(), DomainError> {
+        let deleted = interact(&self.pool, move |conn: &mut Connection| {
+            conn.execute("DELETE FROM collections WHERE id = ?1", [id])
+        })
+        .await?;
+
+        if deleted == 0 {
+            return Err(DomainError::CollectionNotFound(id.to_string()));
+        }
+
+        Ok(())
+    }
+
+    async fn reorder(&self, orders: Vec<(i64, i32)>) -> Result<(), DomainError> {
+        interact_write_with(
+            &self.pool,
+            move |conn: &mut Connection| {
+                conn.execute("BEGIN IMMEDIATE", [])?;
+
+                let result = (|| {
+                    for (id, order) in orders {
+                        conn.execute(
+                            "UPDATE collections SET display_order = ?1, updated_at = unixepoch() WHERE id = ?2",
+                            (order, id),
+                        )?;
+                    }
+                    Ok::<(), rusqlite::Error>(())
+                })();
+
+                match result {
+                    Ok(_) => {
+                        conn.execute("COMMIT", [])?;
+                        Ok(())
+                    }
+                    Err(e) => {
+                        conn.execute("ROLLBACK", [])?;
+                        Err(e)
+                    }
+                }
+            },
+            DomainError::from,
+        )
+        .await
+    }
+}
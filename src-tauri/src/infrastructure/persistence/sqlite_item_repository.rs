@@ -2,13 +2,17 @@
 //!
 //! Implementation of ItemRepository for SQLite.
 
-use crate::domain::entities::Item;
+use super::cql_executor::descendant_path_sql;
+use super::interact::{interact_with, interact_write_with, not_found};
+use super::sqlite_auto_tag_rule_repository::SqliteAutoTagRuleRepository;
+use crate::domain::entities::{AutoTagRule, Item, select_auto_tag_rule};
 use crate::domain::errors::DomainError;
 use crate::domain::repositories::ItemRepository;
 use crate::domain::value_objects::FilePath;
 use async_trait::async_trait;
 use deadpool_sqlite::Pool;
 use rusqlite::{Connection, OptionalExtension};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 /// SQLite implementation of ItemRepository.
@@ -21,6 +25,34 @@ impl SqliteItemRepository {
         Self { pool }
     }
 
+    /// Runs `f` on a pooled connection, reporting a missing row as not found.
+    async fn interact<T, F>(&self, f: F) -> Result<T, DomainError>
+    where
+        F: FnOnce(&mut Connection) -> rusqlite::Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        interact_with(
+            &self.pool,
+            f,
+            not_found(|| DomainError::ItemNotFound("Item not found".to_string())),
+        )
+        .await
+    }
+
+    /// Like `interact`, retrying a write transaction while the database is busy.
+    async fn interact_write<T, F>(&self, f: F) -> Result<T, DomainError>
+    where
+        F: FnOnce(&mut Connection) -> rusqlite::Result<T> + Clone + Send + 'static,
+        T: Send + 'static,
+    {
+        interact_write_with(
+            &self.pool,
+            f,
+            not_found(|| DomainError::ItemNotFound("Item not found".to_string())),
+        )
+        .await
+    }
+
     fn map_row_to_item(row: &rusqlite::Row) -> rusqlite::Result<Item> {
         let path_str: String = row.get(1)?;
         // Use safe fallback for corrupted database data
@@ -39,44 +71,344 @@ impl SqliteItemRepository {
             frn,
             row.get(6)?,
             row.get(7)?,
+            row.get(8)?,
+            row.get(9)?,
+            row.get(10)?,
         ))
     }
-}
 
-#[async_trait]
-impl ItemRepository for SqliteItemRepository {
-    async fn save(&self, item: &mut Item) -> Result<i64, DomainError> {
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
+    /// Marks an item's tag set as changed.
+    ///
+    /// `tags_updated_at` is the version tag writes check and template preview
+    /// tokens record. Only tag changes move it, and always forward, even for
+    /// two changes within the same second.
+    fn touch_tags(conn: &Connection, item_id: i64) -> rusqlite::Result<()> {
+        conn.execute(
+            "UPDATE items SET tags_updated_at = MAX(unixepoch(), COALESCE(tags_updated_at, 0) + 1),
+                 updated_at = unixepoch()
+             WHERE id = ?1",
+            [item_id],
+        )?;
+        Ok(())
+    }
+
+    /// Reads the tag version of an item; 0 until its tags first change.
+    fn tags_version(conn: &Connection, item_id: i64) -> rusqlite::Result<i64> {
+        conn.query_row(
+            "SELECT COALESCE(tags_updated_at, 0) FROM items WHERE id = ?1",
+            [item_id],
+            |row| row.get(0),
+        )
+    }
+
+    /// Removes the item's other tags from `tag_id`'s group when that group is
+    /// exclusive. Returns the number of tags removed.
+    fn drop_exclusive_siblings(
+        conn: &Connection,
+        item_id: i64,
+        tag_id: i64,
+    ) -> rusqlite::Result<usize> {
+        conn.execute(
+            "DELETE FROM item_tags
+             WHERE item_id = ?1 AND tag_id <> ?2 AND tag_id IN (
+                 SELECT t.id FROM tags t
+                 INNER JOIN tags added ON added.group_id = t.group_id
+                 INNER JOIN tag_groups g ON g.id = added.group_id
+                 WHERE added.id = ?2 AND g.exclusive = 1
+             )",
+            (item_id, tag_id),
+        )
+    }
+
+    /// Adds the tags of the auto tag rule covering `path`, if any, to a newly
+    /// created item. Returns the number of tag links changed.
+    fn apply_auto_tag_rule(
+        conn: &Connection,
+        rules: &[AutoTagRule],
+        item_id: i64,
+        path: &str,
+    ) -> rusqlite::Result<usize> {
+        let Some(rule) = select_auto_tag_rule(rules, path) else {
+            return Ok(0);
+        };
+
+        Self::add_template_tags(conn, item_id, rule.template_id())
+    }
 
+    /// Adds a template's tags to an item, marking its tags changed if any
+    /// link changed. Returns the number of tag links changed.
+    fn add_template_tags(
+        conn: &Connection,
+        item_id: i64,
+        template_id: i64,
+    ) -> rusqlite::Result<usize> {
+        let mut changed = 0;
+        for tag_id in Self::resolve_template_tags(conn, template_id)? {
+            changed += Self::drop_exclusive_siblings(conn, item_id, tag_id)?;
+            changed += conn.execute(
+                "INSERT OR IGNORE INTO item_tags (item_id, tag_id) VALUES (?1, ?2)",
+                (item_id, tag_id),
+            )?;
+        }
+        if changed > 0 {
+            Self::touch_tags(conn, item_id)?;
+        }
+        Ok(changed)
+    }
+
+    /// Returns a template's tag IDs followed by its specs resolved to tags.
+    ///
+    /// Spec groups and tags that do not exist yet are created, new groups
+    /// after the existing ones in display order.
+    fn resolve_template_tags(conn: &Connection, template_id: i64) -> rusqlite::Result<Vec<i64>> {
+        let mut tag_ids: Vec<i64> = conn
+            .prepare("SELECT tag_id FROM template_tags WHERE template_id = ?1 ORDER BY tag_id")?
+            .query_map([template_id], |row| row.get(0))?
+            .collect::<Result<Vec<i64>, _>>()?;
+        let specs: Vec<(String, String)> = conn
+            .prepare(
+                "SELECT group_name, value FROM template_tag_specs
+                 WHERE template_id = ?1 ORDER BY position",
+            )?
+            .query_map([template_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for (group_name, value) in specs {
+            conn.execute(
+                "INSERT OR IGNORE INTO tag_groups (name, display_order)
+                 SELECT ?1, COALESCE(MAX(display_order) + 1, 0) FROM tag_groups",
+                [&group_name],
+            )?;
+            let group_id: i64 = conn.query_row(
+                "SELECT id FROM tag_groups WHERE name = ?1",
+                [&group_name],
+                |row| row.get(0),
+            )?;
+            conn.execute(
+                "INSERT OR IGNORE INTO tags (group_id, value) VALUES (?1, ?2)",
+                (group_id, &value),
+            )?;
+            let tag_id: i64 = conn.query_row(
+                "SELECT id FROM tags WHERE group_id = ?1 AND value = ?2",
+                (group_id, &value),
+                |row| row.get(0),
+            )?;
+            if !tag_ids.contains(&tag_id) {
+                tag_ids.push(tag_id);
+            }
+        }
+        Ok(tag_ids)
+    }
+
+    /// Splits `item_ids` into the items still at their expected version and
+    /// the stale ones. Items without an expected version are always current.
+    fn split_stale(
+        conn: &Connection,
+        item_ids: &[i64],
+        expected_tags_updated_at: &HashMap<i64, i64>,
+    ) -> rusqlite::Result<(Vec<i64>, Vec<i64>)> {
+        let mut current = Vec::with_capacity(item_ids.len());
+        let mut stale = Vec::new();
+        for &item_id in item_ids {
+            let Some(&expected) = expected_tags_updated_at.get(&item_id) else {
+                current.push(item_id);
+                continue;
+            };
+            if Self::tags_version(conn, item_id).optional()? == Some(expected) {
+                current.push(item_id);
+            } else {
+                stale.push(item_id);
+            }
+        }
+        Ok((current, stale))
+    }
+
+    fn sorted_tag_ids(conn: &Connection, item_id: i64) -> rusqlite::Result<Vec<i64>> {
+        let mut ids: Vec<i64> = conn
+            .prepare("SELECT tag_id FROM item_tags WHERE item_id = ?1")?
+            .query_map([item_id], |row| row.get(0))?
+            .collect::<Result<Vec<i64>, _>>()?;
+        ids.sort_unstable();
+        Ok(ids)
+    }
+
+    /// Permanently deletes the soft-deleted item at `path`, if any. Returns
+    /// its tag IDs when it was deleted within the last `window_secs` seconds.
+    fn take_deleted_path(
+        conn: &Connection,
+        path: &str,
+        window_secs: i64,
+    ) -> rusqlite::Result<Vec<i64>> {
+        let deleted: Option<(i64, bool)> = conn
+            .query_row(
+                "SELECT id, COALESCE(deleted_at, 0) >= unixepoch() - ?2
+                 FROM items WHERE path = ?1 AND is_deleted = 1",
+                (path, window_secs),
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        let Some((item_id, recent)) = deleted else {
+            return Ok(Vec::new());
+        };
+
+        let tag_ids = if recent {
+            Self::sorted_tag_ids(conn, item_id)?
+        } else {
+            Vec::new()
+        };
+        conn.execute("DELETE FROM item_tags WHERE item_id = ?1", [item_id])?;
+        conn.execute("DELETE FROM items WHERE id = ?1", [item_id])?;
+        Ok(tag_ids)
+    }
+
+    /// Inserts a new item and applies its auto tag rule. With `reuse_window`,
+    /// a soft-deleted item at the same path is replaced first and its recent
+    /// tags are copied over after the rule's, so they win in exclusive groups.
+    async fn insert(&self, item: &mut Item, reuse_window: Option<i64>) -> Result<i64, DomainError> {
         let path = item.path().to_string();
         let is_directory = item.is_directory();
         let size = item.size();
         let modified_time = item.modified_time();
         let frn = item.file_reference_number() as i64;
+        let taken_at = item.taken_at();
 
-        let id = conn
-            .interact(move |conn: &mut Connection| {
-                conn.execute(
-                    "INSERT INTO items (path, is_directory, size, modified_time, file_reference_number) VALUES (?1, ?2, ?3, ?4, ?5)",
-                    (&path, &is_directory, &size, &modified_time, frn),
-                )?;
-                Ok::<i64, rusqlite::Error>(conn.last_insert_rowid())
+        let id = self
+            .interact_write(move |conn: &mut Connection| {
+                conn.execute("BEGIN IMMEDIATE", [])?;
+
+                let result = (|| {
+                    let inherited = match reuse_window {
+                        Some(window_secs) => Self::take_deleted_path(conn, &path, window_secs)?,
+                        None => Vec::new(),
+                    };
+                    conn.execute(
+                        "INSERT INTO items (path, is_directory, size, modified_time, file_reference_number, taken_at)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                        (&path, &is_directory, &size, &modified_time, frn, taken_at),
+                    )?;
+                    let id = conn.last_insert_rowid();
+                    let rules = SqliteAutoTagRuleRepository::load_enabled(conn)?;
+                    Self::apply_auto_tag_rule(conn, &rules, id, &path)?;
+
+                    let mut changed = 0;
+                    for tag_id in inherited {
+                        changed += Self::drop_exclusive_siblings(conn, id, tag_id)?;
+                        changed += conn.execute(
+                            "INSERT OR IGNORE INTO item_tags (item_id, tag_id) VALUES (?1, ?2)",
+                            (id, tag_id),
+                        )?;
+                    }
+                    if changed > 0 {
+                        Self::touch_tags(conn, id)?;
+                    }
+                    Ok::<i64, rusqlite::Error>(id)
+                })();
+
+                match result {
+                    Ok(id) => {
+                        conn.execute("COMMIT", [])?;
+                        Ok(id)
+                    }
+                    Err(e) => {
+                        conn.execute("ROLLBACK", [])?;
+                        Err(e)
+                    }
+                }
             })
             .await
-            .map_err(map_interact_error)?
-            .map_err(map_db_error)?;
+            .map_err(|e| match e {
+                // Another writer (e.g. a USN refresh) may have created the path first
+                DomainError::DuplicateEntry(_) => {
+                    DomainError::DuplicateEntry(format!("Item already exists: {}", item.path()))
+                }
+                e => e,
+            })?;
 
         item.set_id(id);
         Ok(id)
     }
+}
 
-    async fn find_by_id(&self, id: i64) -> Result<Option<Item>, DomainError> {
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
+#[async_trait]
+impl ItemRepository for SqliteItemRepository {
+    async fn save(&self, item: &mut Item) -> Result<i64, DomainError> {
+        self.insert(item, None).await
+    }
 
-        conn.interact(move |conn: &mut Connection| {
+    async fn save_reusing_path(
+        &self,
+        item: &mut Item,
+        window_secs: i64,
+    ) -> Result<i64, DomainError> {
+        self.insert(item, Some(window_secs)).await
+    }
+
+    async fn save_missing(&self, items: &[Item]) -> Result<Vec<(i64, bool)>, DomainError> {
+        if items.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let rows: Vec<(String, bool, Option<i64>, Option<i64>, i64, Option<i64>)> = items
+            .iter()
+            .map(|item| {
+                (
+                    item.path().to_string(),
+                    item.is_directory(),
+                    item.size(),
+                    item.modified_time(),
+                    item.file_reference_number() as i64,
+                    item.taken_at(),
+                )
+            })
+            .collect();
+
+        self.interact_write(move |conn: &mut Connection| {
+            conn.execute("BEGIN IMMEDIATE", [])?;
+
+            let result = (|| {
+                let rules = SqliteAutoTagRuleRepository::load_enabled(conn)?;
+                let mut saved = Vec::with_capacity(rows.len());
+                for (path, is_directory, size, modified_time, frn, taken_at) in &rows {
+                    let inserted = conn.execute(
+                        "INSERT OR IGNORE INTO items (path, is_directory, size, modified_time, file_reference_number, taken_at)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                        (path, is_directory, size, modified_time, frn, taken_at),
+                    )?;
+                    if inserted > 0 {
+                        let id = conn.last_insert_rowid();
+                        Self::apply_auto_tag_rule(conn, &rules, id, path)?;
+                        saved.push((id, true));
+                    } else {
+                        let id = conn.query_row(
+                            "SELECT id FROM items WHERE path = ?1",
+                            [path],
+                            |row| row.get(0),
+                        )?;
+                        saved.push((id, false));
+                    }
+                }
+                Ok::<Vec<(i64, bool)>, rusqlite::Error>(saved)
+            })();
+
+            match result {
+                Ok(saved) => {
+                    conn.execute("COMMIT", [])?;
+                    Ok(saved)
+                }
+                Err(e) => {
+                    conn.execute("ROLLBACK", [])?;
+                    Err(e)
+                }
+            }
+        })
+        .await
+    }
+
+    async fn find_by_id(&self, id: i64) -> Result<Option<Item>, DomainError> {
+        self.interact(move |conn: &mut Connection| {
             let result = conn
                 .query_row(
-                    "SELECT id, path, is_directory, size, modified_time, file_reference_number, created_at, updated_at
+                    "SELECT id, path, is_directory, size, modified_time, file_reference_number, created_at, updated_at, tags_updated_at, rating, taken_at
                      FROM items WHERE id = ?1",
                     [id],
                     Self::map_row_to_item,
@@ -85,18 +417,48 @@ impl ItemRepository for SqliteItemRepository {
             Ok::<Option<Item>, rusqlite::Error>(result)
         })
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
+    }
+
+    async fn find_by_ids(&self, ids: &[i64]) -> Result<Vec<Item>, DomainError> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let ids = ids.to_vec();
+
+        self.interact(move |conn: &mut Connection| {
+            let mut all_items = Vec::new();
+
+            // SQLite has a limit of ~999 bound parameters, chunk to stay safe
+            const CHUNK_SIZE: usize = 500;
+            for chunk in ids.chunks(CHUNK_SIZE) {
+                let placeholders: Vec<&str> = chunk.iter().map(|_| "?").collect();
+                let sql = format!(
+                    "SELECT id, path, is_directory, size, modified_time, file_reference_number, created_at, updated_at, tags_updated_at, rating, taken_at
+                     FROM items WHERE id IN ({})",
+                    placeholders.join(", ")
+                );
+
+                let mut stmt = conn.prepare(&sql)?;
+                let items = stmt
+                    .query_map(rusqlite::params_from_iter(chunk), Self::map_row_to_item)?
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                all_items.extend(items);
+            }
+
+            Ok::<Vec<Item>, rusqlite::Error>(all_items)
+        })
+        .await
     }
 
     async fn find_by_path(&self, path: &str) -> Result<Option<Item>, DomainError> {
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
-        let path = path.to_string();
+        let path = FilePath::normalize(path);
 
-        conn.interact(move |conn: &mut Connection| {
+        self.interact(move |conn: &mut Connection| {
             let result = conn
                 .query_row(
-                    "SELECT id, path, is_directory, size, modified_time, file_reference_number, created_at, updated_at
+                    "SELECT id, path, is_directory, size, modified_time, file_reference_number, created_at, updated_at, tags_updated_at, rating, taken_at
                      FROM items WHERE path = ?1",
                     [&path],
                     Self::map_row_to_item,
@@ -105,8 +467,6 @@ impl ItemRepository for SqliteItemRepository {
             Ok::<Option<Item>, rusqlite::Error>(result)
         })
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
     }
 
     async fn find_by_paths(&self, paths: &[String]) -> Result<Vec<Item>, DomainError> {
@@ -114,10 +474,9 @@ impl ItemRepository for SqliteItemRepository {
             return Ok(Vec::new());
         }
 
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
-        let paths = paths.to_vec();
+        let paths: Vec<String> = paths.iter().map(|p| FilePath::normalize(p)).collect();
 
-        conn.interact(move |conn: &mut Connection| {
+        self.interact(move |conn: &mut Connection| {
             let mut all_items = Vec::new();
 
             // SQLite has a limit of ~999 bound parameters, chunk to stay safe
@@ -125,7 +484,7 @@ impl ItemRepository for SqliteItemRepository {
             for chunk in paths.chunks(CHUNK_SIZE) {
                 let placeholders: Vec<&str> = chunk.iter().map(|_| "?").collect();
                 let sql = format!(
-                    "SELECT id, path, is_directory, size, modified_time, file_reference_number, created_at, updated_at
+                    "SELECT id, path, is_directory, size, modified_time, file_reference_number, created_at, updated_at, tags_updated_at, rating, taken_at
                      FROM items WHERE path IN ({})",
                     placeholders.join(", ")
                 );
@@ -144,8 +503,120 @@ impl ItemRepository for SqliteItemRepository {
             Ok::<Vec<Item>, rusqlite::Error>(all_items)
         })
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
+    }
+
+    async fn add_tags(&self, pairs: &[(i64, i64)]) -> Result<usize, DomainError> {
+        if pairs.is_empty() {
+            return Ok(0);
+        }
+
+        let pairs = pairs.to_vec();
+
+        self.interact_write(move |conn: &mut Connection| {
+            conn.execute("BEGIN IMMEDIATE", [])?;
+
+            let result = (|| {
+                let mut added = 0;
+                let mut changed = HashSet::new();
+                let mut insert = conn.prepare_cached(
+                    "INSERT OR IGNORE INTO item_tags (item_id, tag_id)
+                     SELECT ?1, ?2
+                     WHERE EXISTS (SELECT 1 FROM items WHERE id = ?1)
+                       AND EXISTS (SELECT 1 FROM tags WHERE id = ?2)",
+                )?;
+                for &(item_id, tag_id) in &pairs {
+                    let removed = Self::drop_exclusive_siblings(conn, item_id, tag_id)?;
+                    let inserted = insert.execute((item_id, tag_id))?;
+                    if inserted + removed > 0 {
+                        changed.insert(item_id);
+                    }
+                    added += inserted;
+                }
+                for item_id in changed {
+                    Self::touch_tags(conn, item_id)?;
+                }
+                Ok::<usize, rusqlite::Error>(added)
+            })();
+
+            match result {
+                Ok(added) => {
+                    conn.execute("COMMIT", [])?;
+                    Ok(added)
+                }
+                Err(e) => {
+                    conn.execute("ROLLBACK", [])?;
+                    Err(e)
+                }
+            }
+        })
+        .await
+    }
+
+    async fn find_excluding_tags(&self, exclude_ids: &[i64]) -> Result<Vec<Item>, DomainError> {
+        self.find_with_include_exclude(&[], exclude_ids).await
+    }
+
+    async fn find_with_include_exclude(
+        &self,
+        include_ids: &[i64],
+        exclude_ids: &[i64],
+    ) -> Result<Vec<Item>, DomainError> {
+        let (filters, params) = tag_filter_conditions(include_ids, exclude_ids, true);
+        let mut conditions = vec!["i.is_deleted = 0".to_string()];
+        conditions.extend(filters);
+
+        self.interact(move |conn: &mut Connection| {
+            let sql = format!(
+                "SELECT id, path, is_directory, size, modified_time, file_reference_number,
+                        created_at, updated_at, tags_updated_at, rating, taken_at
+                 FROM items i
+                 WHERE {}
+                 ORDER BY i.path ASC",
+                conditions.join(" AND ")
+            );
+            let mut stmt = conn.prepare(&sql)?;
+            let items = stmt
+                .query_map(rusqlite::params_from_iter(params), Self::map_row_to_item)?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok::<Vec<Item>, rusqlite::Error>(items)
+        })
+        .await
+    }
+
+    async fn find_tagged_paths(&self, paths: &[String]) -> Result<Vec<String>, DomainError> {
+        if paths.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let paths = paths.to_vec();
+
+        self.interact(move |conn: &mut Connection| {
+            let mut tagged = Vec::new();
+
+            const CHUNK_SIZE: usize = 500;
+            for chunk in paths.chunks(CHUNK_SIZE) {
+                let placeholders: Vec<&str> = chunk.iter().map(|_| "?").collect();
+                let sql = format!(
+                    "SELECT path FROM items
+                     WHERE path IN ({}) AND is_deleted = 0
+                       AND EXISTS (SELECT 1 FROM item_tags WHERE item_id = items.id)",
+                    placeholders.join(", ")
+                );
+
+                let mut stmt = conn.prepare(&sql)?;
+                let params: Vec<&dyn rusqlite::ToSql> =
+                    chunk.iter().map(|p| p as &dyn rusqlite::ToSql).collect();
+
+                let rows = stmt
+                    .query_map(params.as_slice(), |row| row.get::<_, String>(0))?
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                tagged.extend(rows);
+            }
+
+            Ok::<Vec<String>, rusqlite::Error>(tagged)
+        })
+        .await
     }
 
     async fn update(&self, item: &Item) -> Result<(), DomainError> {
@@ -153,14 +624,13 @@ impl ItemRepository for SqliteItemRepository {
             DomainError::ValidationError("Cannot update item without ID".to_string())
         })?;
 
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
-
         let path = item.path().to_string();
         let size = item.size();
         let modified_time = item.modified_time();
         let frn = item.file_reference_number() as i64;
+        let taken_at = item.taken_at();
 
-        conn.interact(move |conn: &mut Connection| {
+        self.interact_write(move |conn: &mut Connection| {
             conn.execute("BEGIN IMMEDIATE", [])?;
 
             let result = (|| {
@@ -175,8 +645,8 @@ impl ItemRepository for SqliteItemRepository {
                 }
 
                 conn.execute(
-                    "UPDATE items SET path = ?1, size = ?2, modified_time = ?3, file_reference_number = ?4, updated_at = unixepoch() WHERE id = ?5",
-                    (&path, &size, &modified_time, frn, id),
+                    "UPDATE items SET path = ?1, size = ?2, modified_time = ?3, file_reference_number = ?4, taken_at = ?5, updated_at = unixepoch() WHERE id = ?6",
+                    (&path, &size, &modified_time, frn, taken_at, id),
                 )?;
 
                 Ok::<(), rusqlite::Error>(())
@@ -194,21 +664,15 @@ impl ItemRepository for SqliteItemRepository {
             }
         })
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
     }
 
     async fn delete(&self, id: i64) -> Result<(), DomainError> {
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
-
-        let deleted = conn
+        let deleted = self
             .interact(move |conn: &mut Connection| {
                 let rows = conn.execute("DELETE FROM items WHERE id = ?1", [id])?;
                 Ok::<usize, rusqlite::Error>(rows)
             })
-            .await
-            .map_err(map_interact_error)?
-            .map_err(map_db_error)?;
+            .await?;
 
         if deleted == 0 {
             return Err(DomainError::ItemNotFound(id.to_string()));
@@ -218,39 +682,40 @@ impl ItemRepository for SqliteItemRepository {
     }
 
     async fn add_tag(&self, item_id: i64, tag_id: i64) -> Result<(), DomainError> {
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
-
-        conn.interact(move |conn: &mut Connection| {
-            conn.execute(
+        self.interact(move |conn: &mut Connection| {
+            let tx = conn.transaction()?;
+            let removed = Self::drop_exclusive_siblings(&tx, item_id, tag_id)?;
+            let inserted = tx.execute(
                 "INSERT OR IGNORE INTO item_tags (item_id, tag_id) VALUES (?1, ?2)",
                 (item_id, tag_id),
             )?;
+            if inserted + removed > 0 {
+                Self::touch_tags(&tx, item_id)?;
+            }
+            tx.commit()?;
             Ok::<(), rusqlite::Error>(())
         })
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
     }
 
     async fn remove_tag(&self, item_id: i64, tag_id: i64) -> Result<(), DomainError> {
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
-
-        conn.interact(move |conn: &mut Connection| {
-            conn.execute(
+        self.interact(move |conn: &mut Connection| {
+            let tx = conn.transaction()?;
+            let deleted = tx.execute(
                 "DELETE FROM item_tags WHERE item_id = ?1 AND tag_id = ?2",
                 (item_id, tag_id),
             )?;
+            if deleted > 0 {
+                Self::touch_tags(&tx, item_id)?;
+            }
+            tx.commit()?;
             Ok::<(), rusqlite::Error>(())
         })
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
     }
 
     async fn get_tag_ids(&self, item_id: i64) -> Result<Vec<i64>, DomainError> {
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
-
-        conn.interact(move |conn: &mut Connection| {
+        self.interact(move |conn: &mut Connection| {
             let mut stmt = conn.prepare("SELECT tag_id FROM item_tags WHERE item_id = ?1")?;
             let ids = stmt
                 .query_map([item_id], |row| row.get(0))?
@@ -258,43 +723,152 @@ impl ItemRepository for SqliteItemRepository {
             Ok::<Vec<i64>, rusqlite::Error>(ids)
         })
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
     }
 
-    async fn replace_tags(&self, item_id: i64, tag_ids: Vec<i64>) -> Result<(), DomainError> {
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
+    async fn count_tags(&self, item_id: i64) -> Result<u64, DomainError> {
+        self.interact(move |conn: &mut Connection| {
+            conn.query_row(
+                "SELECT COUNT(*) FROM item_tags WHERE item_id = ?1",
+                [item_id],
+                |row| row.get::<_, i64>(0),
+            )
+        })
+        .await
+        .map(|count| count as u64)
+    }
 
-        conn.interact(move |conn: &mut Connection| {
-            conn.execute("BEGIN IMMEDIATE", [])?;
+    async fn replace_tags(
+        &self,
+        item_id: i64,
+        tag_ids: Vec<i64>,
+        expected_tags_updated_at: Option<i64>,
+    ) -> Result<i64, DomainError> {
+        // None when the item's tags changed since the caller read the version
+        let version = self
+            .interact_write(move |conn: &mut Connection| {
+                conn.execute("BEGIN IMMEDIATE", [])?;
+
+                let result = (|| {
+                    let version = Self::tags_version(conn, item_id)?;
+
+                    if expected_tags_updated_at.is_some_and(|expected| expected != version) {
+                        return Ok(None);
+                    }
+
+                    let current = Self::sorted_tag_ids(conn, item_id)?;
+
+                    conn.execute("DELETE FROM item_tags WHERE item_id = ?1", [item_id])?;
+
+                    // Within an exclusive group the last requested tag wins
+                    for tag_id in tag_ids {
+                        Self::drop_exclusive_siblings(conn, item_id, tag_id)?;
+                        conn.execute(
+                            "INSERT INTO item_tags (item_id, tag_id) VALUES (?1, ?2)",
+                            (item_id, tag_id),
+                        )?;
+                    }
+
+                    if current == Self::sorted_tag_ids(conn, item_id)? {
+                        return Ok(Some(version));
+                    }
+
+                    Self::touch_tags(conn, item_id)?;
+                    Self::tags_version(conn, item_id).map(Some)
+                })();
+
+                match result {
+                    Ok(version) => {
+                        conn.execute("COMMIT", [])?;
+                        Ok(version)
+                    }
+                    Err(e) => {
+                        conn.execute("ROLLBACK", [])?;
+                        Err(e)
+                    }
+                }
+            })
+            .await?;
 
-            let result = (|| {
-                let exists: bool = conn.query_row(
-                    "SELECT COUNT(*) FROM items WHERE id = ?1",
-                    [item_id],
-                    |row| row.get::<_, i64>(0).map(|count| count > 0),
-                )?;
+        version.ok_or_else(|| DomainError::ConcurrentModification(item_id.to_string()))
+    }
 
-                if !exists {
-                    return Err(rusqlite::Error::QueryReturnedNoRows);
+    async fn apply_template(
+        &self,
+        item_id: i64,
+        template_id: i64,
+        expected_tags_updated_at: Option<i64>,
+    ) -> Result<i64, DomainError> {
+        // None when the item's tags changed since the caller read the version
+        let version = self
+            .interact_write(move |conn: &mut Connection| {
+                conn.execute("BEGIN IMMEDIATE", [])?;
+
+                let result = (|| {
+                    let version = Self::tags_version(conn, item_id)?;
+
+                    if expected_tags_updated_at.is_some_and(|expected| expected != version) {
+                        return Ok(None);
+                    }
+
+                    if Self::add_template_tags(conn, item_id, template_id)? == 0 {
+                        return Ok(Some(version));
+                    }
+
+                    Self::tags_version(conn, item_id).map(Some)
+                })();
+
+                match result {
+                    Ok(version) => {
+                        conn.execute("COMMIT", [])?;
+                        Ok(version)
+                    }
+                    Err(e) => {
+                        conn.execute("ROLLBACK", [])?;
+                        Err(e)
+                    }
                 }
+            })
+            .await?;
+
+        version.ok_or_else(|| DomainError::ConcurrentModification(item_id.to_string()))
+    }
+
+    async fn batch_add_tag(
+        &self,
+        item_ids: &[i64],
+        tag_id: i64,
+        expected_tags_updated_at: &HashMap<i64, i64>,
+    ) -> Result<Vec<i64>, DomainError> {
+        if item_ids.is_empty() {
+            return Ok(Vec::new());
+        }
 
-                conn.execute("DELETE FROM item_tags WHERE item_id = ?1", [item_id])?;
+        let item_ids = item_ids.to_vec();
+        let expected_tags_updated_at = expected_tags_updated_at.clone();
 
-                for tag_id in tag_ids {
-                    conn.execute(
-                        "INSERT INTO item_tags (item_id, tag_id) VALUES (?1, ?2)",
-                        (item_id, tag_id),
+        self.interact_write(move |conn: &mut Connection| {
+            conn.execute("BEGIN IMMEDIATE", [])?;
+
+            let result = (|| {
+                let (item_ids, stale) =
+                    Self::split_stale(conn, &item_ids, &expected_tags_updated_at)?;
+                for item_id in &item_ids {
+                    let removed = Self::drop_exclusive_siblings(conn, *item_id, tag_id)?;
+                    let inserted = conn.execute(
+                        "INSERT OR IGNORE INTO item_tags (item_id, tag_id) VALUES (?1, ?2)",
+                        (*item_id, tag_id),
                     )?;
+                    if inserted + removed > 0 {
+                        Self::touch_tags(conn, *item_id)?;
+                    }
                 }
-
-                Ok::<(), rusqlite::Error>(())
+                Ok::<Vec<i64>, rusqlite::Error>(stale)
             })();
 
             match result {
-                Ok(_) => {
+                Ok(stale) => {
                     conn.execute("COMMIT", [])?;
-                    Ok(())
+                    Ok(stale)
                 }
                 Err(e) => {
                     conn.execute("ROLLBACK", [])?;
@@ -303,35 +877,65 @@ impl ItemRepository for SqliteItemRepository {
             }
         })
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
     }
 
-    async fn batch_add_tag(&self, item_ids: &[i64], tag_id: i64) -> Result<(), DomainError> {
+    async fn batch_remove_tag(
+        &self,
+        item_ids: &[i64],
+        tag_id: i64,
+        expected_tags_updated_at: &HashMap<i64, i64>,
+    ) -> Result<Vec<i64>, DomainError> {
         if item_ids.is_empty() {
-            return Ok(());
+            return Ok(Vec::new());
         }
 
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
         let item_ids = item_ids.to_vec();
+        let expected_tags_updated_at = expected_tags_updated_at.clone();
 
-        conn.interact(move |conn: &mut Connection| {
+        self.interact_write(move |conn: &mut Connection| {
             conn.execute("BEGIN IMMEDIATE", [])?;
 
             let result = (|| {
-                for item_id in &item_ids {
+                let (item_ids, stale) =
+                    Self::split_stale(conn, &item_ids, &expected_tags_updated_at)?;
+                // Chunk for SQLite parameter limit safety
+                const CHUNK_SIZE: usize = 500;
+                for chunk in item_ids.chunks(CHUNK_SIZE) {
+                    let placeholders: Vec<&str> = chunk.iter().map(|_| "?").collect();
+                    let placeholders = placeholders.join(", ");
+
+                    let mut params: Vec<&dyn rusqlite::ToSql> = vec![&tag_id];
+                    params.extend(chunk.iter().map(|id| id as &dyn rusqlite::ToSql));
+
+                    // Touch only items that actually carry the tag, before it is removed
                     conn.execute(
-                        "INSERT OR IGNORE INTO item_tags (item_id, tag_id) VALUES (?1, ?2)",
-                        (*item_id, tag_id),
+                        &format!(
+                            "UPDATE items SET tags_updated_at =
+                                     MAX(unixepoch(), COALESCE(tags_updated_at, 0) + 1),
+                                 updated_at = unixepoch()
+                             WHERE id IN (
+                                 SELECT item_id FROM item_tags
+                                 WHERE tag_id = ?1 AND item_id IN ({})
+                             )",
+                            placeholders
+                        ),
+                        params.as_slice(),
+                    )?;
+                    conn.execute(
+                        &format!(
+                            "DELETE FROM item_tags WHERE tag_id = ?1 AND item_id IN ({})",
+                            placeholders
+                        ),
+                        params.as_slice(),
                     )?;
                 }
-                Ok::<(), rusqlite::Error>(())
+                Ok::<Vec<i64>, rusqlite::Error>(stale)
             })();
 
             match result {
-                Ok(_) => {
+                Ok(stale) => {
                     conn.execute("COMMIT", [])?;
-                    Ok(())
+                    Ok(stale)
                 }
                 Err(e) => {
                     conn.execute("ROLLBACK", [])?;
@@ -340,43 +944,139 @@ impl ItemRepository for SqliteItemRepository {
             }
         })
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
     }
 
-    async fn batch_remove_tag(&self, item_ids: &[i64], tag_id: i64) -> Result<(), DomainError> {
-        if item_ids.is_empty() {
-            return Ok(());
+    async fn find_active_under(&self, directory: &str) -> Result<Vec<Item>, DomainError> {
+        // A drive root keeps its separator; the descendant ranges need it stripped
+        let (condition, params) = if directory.is_empty() {
+            ("1".to_string(), Vec::new())
+        } else {
+            let trimmed = directory.trim_end_matches(['\\', '/']).to_string();
+            (
+                format!("(path = ?1 OR {})", descendant_path_sql("path", "?2")),
+                vec![directory.to_string(), trimmed],
+            )
+        };
+
+        self.interact(move |conn: &mut Connection| {
+            let sql = format!(
+                "SELECT id, path, is_directory, size, modified_time, file_reference_number,
+                        created_at, updated_at, tags_updated_at, rating, taken_at
+                 FROM items WHERE {} AND is_deleted = 0",
+                condition
+            );
+            let mut stmt = conn.prepare(&sql)?;
+            let items = stmt
+                .query_map(rusqlite::params_from_iter(params), Self::map_row_to_item)?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok::<Vec<Item>, rusqlite::Error>(items)
+        })
+        .await
+    }
+
+    async fn find_in_directory(
+        &self,
+        directory: &str,
+        recursive: bool,
+    ) -> Result<Vec<Item>, DomainError> {
+        let directory = directory.to_string();
+
+        self.interact(move |conn: &mut Connection| {
+            // parent_dir has no trailing separator, matching `directory`
+            let condition = if recursive {
+                descendant_path_sql("path", "?1")
+            } else {
+                "parent_dir = ?1".to_string()
+            };
+            let sql = format!(
+                "SELECT id, path, is_directory, size, modified_time, file_reference_number,
+                        created_at, updated_at, tags_updated_at, rating, taken_at
+                 FROM items WHERE {} AND is_deleted = 0
+                 ORDER BY path ASC",
+                condition
+            );
+            let mut stmt = conn.prepare(&sql)?;
+            let items = stmt
+                .query_map([&directory], Self::map_row_to_item)?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok::<Vec<Item>, rusqlite::Error>(items)
+        })
+        .await
+    }
+
+    async fn find_recently_tagged(&self, limit: usize) -> Result<Vec<Item>, DomainError> {
+        self.interact(move |conn: &mut Connection| {
+            let mut stmt = conn.prepare(
+                "SELECT id, path, is_directory, size, modified_time, file_reference_number,
+                        created_at, updated_at, tags_updated_at, rating, taken_at
+                 FROM items
+                 WHERE tags_updated_at IS NOT NULL AND is_deleted = 0
+                 ORDER BY tags_updated_at DESC, id DESC
+                 LIMIT ?1",
+            )?;
+            let items = stmt
+                .query_map([limit as i64], Self::map_row_to_item)?
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok::<Vec<Item>, rusqlite::Error>(items)
+        })
+        .await
+    }
+
+    async fn rebase_paths(&self, old_prefix: &str, new_prefix: &str) -> Result<usize, DomainError> {
+        let pattern = format!("{}%", escape_like(old_prefix));
+        self.rewrite_prefix(
+            "path LIKE ?3 ESCAPE '!'",
+            vec![pattern],
+            old_prefix,
+            new_prefix,
+        )
+        .await
+    }
+
+    async fn rebase_folder(&self, old_dir: &str, new_dir: &str) -> Result<usize, DomainError> {
+        let escaped = escape_like(old_dir);
+        self.rewrite_prefix(
+            "(path = ?3 OR path LIKE ?4 ESCAPE '!' OR path LIKE ?5 ESCAPE '!')",
+            vec![
+                old_dir.to_string(),
+                format!("{}\\%", escaped),
+                format!("{}/%", escaped),
+            ],
+            old_dir,
+            new_dir,
+        )
+        .await
+    }
+
+    async fn update_metadata(
+        &self,
+        updates: &[(i64, Option<i64>, Option<i64>, Option<i64>)],
+    ) -> Result<usize, DomainError> {
+        if updates.is_empty() {
+            return Ok(0);
         }
 
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
-        let item_ids = item_ids.to_vec();
+        let updates = updates.to_vec();
 
-        conn.interact(move |conn: &mut Connection| {
+        self.interact_write(move |conn: &mut Connection| {
             conn.execute("BEGIN IMMEDIATE", [])?;
 
             let result = (|| {
-                // Chunk for SQLite parameter limit safety
-                const CHUNK_SIZE: usize = 500;
-                for chunk in item_ids.chunks(CHUNK_SIZE) {
-                    let placeholders: Vec<&str> = chunk.iter().map(|_| "?").collect();
-                    let sql = format!(
-                        "DELETE FROM item_tags WHERE tag_id = ?1 AND item_id IN ({})",
-                        placeholders.join(", ")
-                    );
-
-                    let mut params: Vec<&dyn rusqlite::ToSql> = vec![&tag_id];
-                    params.extend(chunk.iter().map(|id| id as &dyn rusqlite::ToSql));
-
-                    conn.execute(&sql, params.as_slice())?;
+                let mut updated = 0;
+                for (item_id, size, modified_time, taken_at) in &updates {
+                    updated += conn.execute(
+                        "UPDATE items SET size = ?1, modified_time = ?2, taken_at = ?3, updated_at = unixepoch()
+                         WHERE id = ?4",
+                        (size, modified_time, taken_at, item_id),
+                    )?;
                 }
-                Ok::<(), rusqlite::Error>(())
+                Ok::<usize, rusqlite::Error>(updated)
             })();
 
             match result {
-                Ok(_) => {
+                Ok(updated) => {
                     conn.execute("COMMIT", [])?;
-                    Ok(())
+                    Ok(updated)
                 }
                 Err(e) => {
                     conn.execute("ROLLBACK", [])?;
@@ -385,45 +1085,892 @@ impl ItemRepository for SqliteItemRepository {
             }
         })
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
     }
 
-    async fn find_active_by_path_prefix(&self, prefix: &str) -> Result<Vec<Item>, DomainError> {
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
-        let pattern = format!("{}%", prefix);
-
-        conn.interact(move |conn: &mut Connection| {
+    async fn find_missing_frn(
+        &self,
+        after_id: i64,
+        limit: usize,
+    ) -> Result<Vec<Item>, DomainError> {
+        self.interact(move |conn: &mut Connection| {
             let mut stmt = conn.prepare(
                 "SELECT id, path, is_directory, size, modified_time, file_reference_number,
-                        created_at, updated_at
-                 FROM items WHERE path LIKE ?1 AND is_deleted = 0",
+                        created_at, updated_at, tags_updated_at, rating, taken_at
+                 FROM items
+                 WHERE COALESCE(file_reference_number, 0) = 0 AND is_deleted = 0 AND id > ?1
+                 ORDER BY id ASC
+                 LIMIT ?2",
             )?;
             let items = stmt
-                .query_map([&pattern], Self::map_row_to_item)?
+                .query_map((after_id, limit as i64), Self::map_row_to_item)?
                 .collect::<Result<Vec<_>, _>>()?;
             Ok::<Vec<Item>, rusqlite::Error>(items)
         })
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
+    }
+
+    async fn update_file_reference_number(
+        &self,
+        item_id: i64,
+        frn: u64,
+    ) -> Result<(), DomainError> {
+        let updated = self
+            .interact(move |conn: &mut Connection| {
+                conn.execute(
+                    "UPDATE items SET file_reference_number = ?1 WHERE id = ?2",
+                    (frn as i64, item_id),
+                )
+            })
+            .await?;
+
+        if updated == 0 {
+            return Err(DomainError::ItemNotFound(item_id.to_string()));
+        }
+
+        Ok(())
+    }
+
+    async fn apply_auto_tag_rules(
+        &self,
+        item_id: i64,
+        previous_path: Option<&str>,
+    ) -> Result<usize, DomainError> {
+        let previous_path = previous_path.map(str::to_string);
+        self.interact_write(move |conn: &mut Connection| {
+            conn.execute("BEGIN IMMEDIATE", [])?;
+
+            let result = (|| {
+                let path: Option<String> = conn
+                    .query_row(
+                        "SELECT path FROM items WHERE id = ?1 AND is_deleted = 0",
+                        [item_id],
+                        |row| row.get(0),
+                    )
+                    .optional()?;
+                let Some(path) = path else {
+                    return Ok(0);
+                };
+                let rules = SqliteAutoTagRuleRepository::load_enabled(conn)?;
+                let Some(rule) = select_auto_tag_rule(&rules, &path) else {
+                    return Ok(0);
+                };
+                if previous_path.as_deref().is_some_and(|old| rule.covers(old)) {
+                    return Ok(0);
+                }
+                Self::add_template_tags(conn, item_id, rule.template_id())
+            })();
+
+            match result {
+                Ok(changed) => {
+                    conn.execute("COMMIT", [])?;
+                    Ok(changed)
+                }
+                Err(e) => {
+                    conn.execute("ROLLBACK", [])?;
+                    Err(e)
+                }
+            }
+        })
+        .await
+    }
+
+    async fn set_rating(&self, item_id: i64, rating: Option<u8>) -> Result<(), DomainError> {
+        let updated = self
+            .interact(move |conn: &mut Connection| {
+                let rows = conn.execute(
+                    "UPDATE items SET rating = ?1, updated_at = unixepoch() WHERE id = ?2",
+                    (rating, item_id),
+                )?;
+                Ok::<usize, rusqlite::Error>(rows)
+            })
+            .await?;
+
+        if updated == 0 {
+            return Err(DomainError::ItemNotFound(item_id.to_string()));
+        }
+
+        Ok(())
+    }
+
+    async fn soft_delete_by_tag(&self, tag_id: i64) -> Result<u64, DomainError> {
+        self.update_by_tag(
+            "UPDATE items
+             SET is_deleted = 1, deleted_at = unixepoch(), updated_at = unixepoch()
+             WHERE is_deleted = 0
+             AND id IN (SELECT item_id FROM item_tags WHERE tag_id = ?1)",
+            tag_id,
+        )
+        .await
+    }
+
+    async fn restore_by_tag(&self, tag_id: i64) -> Result<u64, DomainError> {
+        self.update_by_tag(
+            "UPDATE items
+             SET is_deleted = 0, deleted_at = NULL, updated_at = unixepoch()
+             WHERE is_deleted = 1
+             AND id IN (SELECT item_id FROM item_tags WHERE tag_id = ?1)",
+            tag_id,
+        )
+        .await
+    }
+
+    async fn normalize_paths(&self) -> Result<(usize, usize), DomainError> {
+        self.interact_write(move |conn: &mut Connection| {
+            conn.execute("BEGIN IMMEDIATE", [])?;
+
+            match normalize_item_paths(conn) {
+                Ok(counts) => {
+                    conn.execute("COMMIT", [])?;
+                    Ok(counts)
+                }
+                Err(e) => {
+                    conn.execute("ROLLBACK", [])?;
+                    Err(e)
+                }
+            }
+        })
+        .await
+    }
+}
+
+impl SqliteItemRepository {
+    /// Runs a single `UPDATE` bound to `?1 = tag_id` in a transaction and
+    /// returns the number of rows changed.
+    async fn update_by_tag(&self, sql: &'static str, tag_id: i64) -> Result<u64, DomainError> {
+        self.interact(move |conn: &mut Connection| {
+            let tx = conn.transaction()?;
+            let updated = tx.execute(sql, [tag_id])?;
+            tx.commit()?;
+            Ok::<u64, rusqlite::Error>(updated as u64)
+        })
+        .await
+    }
+
+    /// Replaces the first `old_prefix.len()` characters with `new_prefix` on every
+    /// item matched by `filter`, in a single transaction.
+    ///
+    /// `filter` references the unaliased `path` column; `?1` and `?2` are reserved
+    /// for the new prefix and its length, so `filter_params` bind from `?3`.
+    async fn rewrite_prefix(
+        &self,
+        filter: &'static str,
+        filter_params: Vec<String>,
+        old_prefix: &str,
+        new_prefix: &str,
+    ) -> Result<usize, DomainError> {
+        let prefix_len = old_prefix.chars().count() as i64;
+        let new_prefix = new_prefix.to_string();
+
+        let (collisions, updated) = self
+            .interact_write(move |conn: &mut Connection| {
+                let mut params: Vec<&dyn rusqlite::ToSql> = vec![&new_prefix, &prefix_len];
+                params.extend(filter_params.iter().map(|p| p as &dyn rusqlite::ToSql));
+
+                conn.execute("BEGIN IMMEDIATE", [])?;
+
+                let result = (|| {
+                    let collisions: i64 = conn.query_row(
+                        &format!(
+                            "SELECT COUNT(*) FROM items
+                             WHERE {}
+                             AND EXISTS (
+                                 SELECT 1 FROM items o
+                                 WHERE o.path = ?1 || substr(items.path, ?2 + 1)
+                                 AND o.id != items.id
+                             )",
+                            filter
+                        ),
+                        params.as_slice(),
+                        |row| row.get(0),
+                    )?;
+
+                    if collisions > 0 {
+                        return Ok::<(i64, usize), rusqlite::Error>((collisions, 0));
+                    }
+
+                    let updated = conn.execute(
+                        &format!(
+                            "UPDATE items
+                             SET path = ?1 || substr(path, ?2 + 1), updated_at = unixepoch()
+                             WHERE {}",
+                            filter
+                        ),
+                        params.as_slice(),
+                    )?;
+
+                    Ok((0, updated))
+                })();
+
+                match result {
+                    Ok((0, updated)) => {
+                        conn.execute("COMMIT", [])?;
+                        Ok((0, updated))
+                    }
+                    Ok(conflict) => {
+                        conn.execute("ROLLBACK", [])?;
+                        Ok(conflict)
+                    }
+                    Err(e) => {
+                        conn.execute("ROLLBACK", [])?;
+                        Err(e)
+                    }
+                }
+            })
+            .await?;
+
+        if collisions > 0 {
+            return Err(DomainError::DuplicateEntry(format!(
+                "{} item path(s) would collide with existing items",
+                collisions
+            )));
+        }
+
+        Ok(updated)
     }
 }
 
-// Error mapping helpers
-fn map_pool_error(e: deadpool_sqlite::PoolError) -> DomainError {
-    DomainError::ValidationError(format!("Database pool error: {}", e))
+/// SQL conditions on `i.id` selecting items that carry `include_ids` (all of
+/// them with `match_all`, any otherwise) and none of `exclude_ids`, with
+/// their parameters in order. An empty list adds no condition.
+pub(super) fn tag_filter_conditions(
+    include_ids: &[i64],
+    exclude_ids: &[i64],
+    match_all: bool,
+) -> (Vec<String>, Vec<i64>) {
+    let placeholders = |ids: &[i64]| vec!["?"; ids.len()].join(", ");
+    let mut conditions = Vec::new();
+    let mut params = Vec::new();
+
+    if !include_ids.is_empty() {
+        let mut include_ids = include_ids.to_vec();
+        include_ids.sort_unstable();
+        include_ids.dedup();
+        conditions.push(if match_all {
+            format!(
+                "i.id IN (
+                     SELECT item_id FROM item_tags WHERE tag_id IN ({})
+                     GROUP BY item_id HAVING COUNT(DISTINCT tag_id) = ?
+                 )",
+                placeholders(&include_ids)
+            )
+        } else {
+            format!(
+                "EXISTS (
+                     SELECT 1 FROM item_tags WHERE item_id = i.id AND tag_id IN ({})
+                 )",
+                placeholders(&include_ids)
+            )
+        });
+        params.extend(&include_ids);
+        if match_all {
+            params.push(include_ids.len() as i64);
+        }
+    }
+
+    if !exclude_ids.is_empty() {
+        conditions.push(format!(
+            "i.id NOT IN (SELECT DISTINCT item_id FROM item_tags WHERE tag_id IN ({}))",
+            placeholders(exclude_ids)
+        ));
+        params.extend(exclude_ids);
+    }
+
+    (conditions, params)
 }
 
-fn map_interact_error(e: deadpool_sqlite::InteractError) -> DomainError {
-    DomainError::ValidationError(format!("Database interaction error: {}", e))
+/// Escapes LIKE wildcards using `!` as the escape character.
+/// Backslash is not used because it appears in every Windows path.
+fn escape_like(value: &str) -> String {
+    value
+        .replace('!', "!!")
+        .replace('%', "!%")
+        .replace('_', "!_")
 }
 
-fn map_db_error(e: rusqlite::Error) -> DomainError {
-    match e {
-        rusqlite::Error::QueryReturnedNoRows => {
-            DomainError::ItemNotFound("Item not found".to_string())
+/// Rewrites every item path to its `FilePath::normalize` form; the caller
+/// provides the transaction. Items whose paths collapse onto the same one
+/// are merged into a single survivor, preferring an active item, then the
+/// most recently updated: it gains the others' tags and any rating, capture
+/// time or file id it lacks, and the others are deleted.
+///
+/// Returns the number of items renamed and the number merged away.
+pub(super) fn normalize_item_paths(conn: &Connection) -> rusqlite::Result<(usize, usize)> {
+    let rows = conn
+        .prepare("SELECT id, path FROM items ORDER BY is_deleted ASC, updated_at DESC, id ASC")?
+        .query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Items by normalized path, survivor first
+    let mut groups: HashMap<String, Vec<(i64, String)>> = HashMap::new();
+    for (id, path) in rows {
+        groups
+            .entry(FilePath::normalize(&path))
+            .or_default()
+            .push((id, path));
+    }
+
+    let mut renamed = 0;
+    let mut merged = 0;
+    for (normalized, items) in groups {
+        let Some(((survivor, path), duplicates)) = items.split_first() else {
+            continue;
+        };
+
+        for (duplicate, _) in duplicates {
+            let gained = conn.execute(
+                "INSERT OR IGNORE INTO item_tags (item_id, tag_id, created_at)
+                 SELECT ?1, tag_id, created_at FROM item_tags WHERE item_id = ?2",
+                (survivor, duplicate),
+            )?;
+            conn.execute(
+                "UPDATE items SET
+                    rating = COALESCE(rating, (SELECT rating FROM items WHERE id = ?2)),
+                    taken_at = COALESCE(taken_at, (SELECT taken_at FROM items WHERE id = ?2)),
+                    file_reference_number = COALESCE(
+                        NULLIF(file_reference_number, 0),
+                        (SELECT file_reference_number FROM items WHERE id = ?2)
+                    ),
+                    tags_updated_at = CASE WHEN ?3 > 0
+                        THEN MAX(unixepoch(), COALESCE(tags_updated_at, 0) + 1)
+                        ELSE tags_updated_at END,
+                    updated_at = unixepoch()
+                 WHERE id = ?1",
+                (survivor, duplicate, gained),
+            )?;
+            conn.execute("DELETE FROM item_tags WHERE item_id = ?1", [duplicate])?;
+            conn.execute("DELETE FROM items WHERE id = ?1", [duplicate])?;
+            merged += 1;
         }
-        _ => DomainError::ValidationError(format!("Database error: {}", e)),
+
+        if *path != normalized {
+            conn.execute(
+                "UPDATE items SET path = ?1, updated_at = unixepoch() WHERE id = ?2",
+                (&normalized, survivor),
+            )?;
+            renamed += 1;
+        }
+    }
+
+    Ok((renamed, merged))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::persistence::open_database;
+
+    async fn setup() -> (tempfile::TempDir, SqliteItemRepository) {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = open_database(&dir.path().join("test.db")).await.unwrap();
+        let conn = pool.get().await.unwrap();
+        conn.interact(|conn: &mut Connection| {
+            conn.execute_batch(
+                "INSERT INTO tag_groups (id, name) VALUES (1, 'Group');
+                 INSERT INTO tags (id, group_id, value) VALUES (1, 1, 'a'), (2, 1, 'b');
+                 INSERT INTO items (id, path, is_directory) VALUES
+                    (1, 'C:\\a.txt', 0), (2, 'C:\\b.txt', 0);",
+            )
+        })
+        .await
+        .unwrap()
+        .unwrap();
+        (dir, SqliteItemRepository::new(Arc::new(pool)))
+    }
+
+    /// Sets every item's tags_updated_at to a sentinel so later bumps are visible.
+    async fn reset_tags_updated_at(repo: &SqliteItemRepository) {
+        let conn = repo.pool.get().await.unwrap();
+        conn.interact(|conn: &mut Connection| {
+            conn.execute("UPDATE items SET tags_updated_at = 1", [])
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    }
+
+    async fn tags_updated_at(repo: &SqliteItemRepository, id: i64) -> Option<i64> {
+        repo.find_by_id(id)
+            .await
+            .unwrap()
+            .unwrap()
+            .tags_updated_at()
+    }
+
+    async fn deleted_state(repo: &SqliteItemRepository, id: i64) -> (bool, Option<i64>) {
+        let conn = repo.pool.get().await.unwrap();
+        conn.interact(move |conn: &mut Connection| {
+            conn.query_row(
+                "SELECT is_deleted, deleted_at FROM items WHERE id = ?1",
+                [id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+        })
+        .await
+        .unwrap()
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn tag_changes_bump_tags_updated_at() {
+        let (_dir, repo) = setup().await;
+        assert_eq!(tags_updated_at(&repo, 1).await, None);
+
+        repo.add_tag(1, 1).await.unwrap();
+        assert!(tags_updated_at(&repo, 1).await > Some(1));
+
+        reset_tags_updated_at(&repo).await;
+        repo.remove_tag(1, 1).await.unwrap();
+        assert!(tags_updated_at(&repo, 1).await > Some(1));
+
+        reset_tags_updated_at(&repo).await;
+        repo.replace_tags(1, vec![1, 2], None).await.unwrap();
+        assert!(tags_updated_at(&repo, 1).await > Some(1));
+
+        reset_tags_updated_at(&repo).await;
+        repo.batch_remove_tag(&[1, 2], 2, &HashMap::new())
+            .await
+            .unwrap();
+        assert!(tags_updated_at(&repo, 1).await > Some(1));
+        assert_eq!(tags_updated_at(&repo, 2).await, Some(1));
+    }
+
+    #[tokio::test]
+    async fn find_tagged_paths_returns_only_tagged_active_items() {
+        let (_dir, repo) = setup().await;
+        repo.add_tag(1, 1).await.unwrap();
+        let paths = vec![
+            "C:\\a.txt".to_string(),
+            "C:\\b.txt".to_string(),
+            "C:\\missing.txt".to_string(),
+        ];
+
+        assert_eq!(
+            repo.find_tagged_paths(&paths).await.unwrap(),
+            vec!["C:\\a.txt".to_string()]
+        );
+
+        repo.soft_delete_by_tag(1).await.unwrap();
+        assert!(repo.find_tagged_paths(&paths).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn exclusion_wins_over_inclusion() {
+        let (_dir, repo) = setup().await;
+        let conn = repo.pool.get().await.unwrap();
+        conn.interact(|conn: &mut Connection| {
+            conn.execute_batch(
+                "INSERT INTO tags (id, group_id, value) VALUES (3, 1, 'c');
+                 INSERT INTO items (id, path, is_directory, is_deleted) VALUES
+                    (3, 'C:\\c.txt', 0, 0),
+                    (4, 'C:\\d.txt', 0, 1);
+                 INSERT INTO item_tags (item_id, tag_id) VALUES
+                    (1, 1), (1, 2), (2, 1), (2, 2), (2, 3), (3, 1);",
+            )
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+        let ids = |items: Vec<Item>| items.iter().filter_map(|i| i.id()).collect::<Vec<_>>();
+        assert_eq!(
+            ids(repo.find_excluding_tags(&[]).await.unwrap()),
+            vec![1, 2, 3]
+        );
+        assert_eq!(
+            ids(repo.find_excluding_tags(&[3]).await.unwrap()),
+            vec![1, 3]
+        );
+        assert_eq!(
+            ids(repo.find_with_include_exclude(&[1, 2], &[3]).await.unwrap()),
+            vec![1]
+        );
+        assert_eq!(
+            ids(repo.find_with_include_exclude(&[1], &[2]).await.unwrap()),
+            vec![3]
+        );
+        assert!(
+            repo.find_with_include_exclude(&[1, 3], &[3])
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    async fn tag_version(repo: &SqliteItemRepository, id: i64) -> i64 {
+        tags_updated_at(repo, id).await.unwrap_or(0)
+    }
+
+    #[tokio::test]
+    async fn replace_tags_rejects_stale_tag_version() {
+        let (_dir, repo) = setup().await;
+        let read = tag_version(&repo, 1).await;
+
+        let written = repo.replace_tags(1, vec![1], Some(read)).await.unwrap();
+        assert!(written > read);
+        assert_eq!(tag_version(&repo, 1).await, written);
+
+        // A second writer still holding the old value is refused
+        let result = repo.replace_tags(1, vec![2], Some(read)).await;
+        assert!(matches!(
+            result,
+            Err(DomainError::ConcurrentModification(_))
+        ));
+        assert_eq!(repo.get_tag_ids(1).await.unwrap(), vec![1]);
+
+        // Other tag writes move the version forward too
+        repo.add_tag(1, 2).await.unwrap();
+        let result = repo.replace_tags(1, vec![], Some(written)).await;
+        assert!(matches!(
+            result,
+            Err(DomainError::ConcurrentModification(_))
+        ));
+
+        // No-op replaces keep the version
+        let current = tag_version(&repo, 1).await;
+        assert_eq!(
+            repo.replace_tags(1, vec![1, 2], Some(current))
+                .await
+                .unwrap(),
+            current
+        );
+    }
+
+    #[tokio::test]
+    async fn concurrent_replace_tags_with_same_version_lets_one_win() {
+        let (_dir, repo) = setup().await;
+        let read = tag_version(&repo, 1).await;
+
+        let (a, b) = tokio::join!(
+            repo.replace_tags(1, vec![1], Some(read)),
+            repo.replace_tags(1, vec![2], Some(read)),
+        );
+
+        let (winner, tag) = match (a, b) {
+            (Ok(v), Err(DomainError::ConcurrentModification(_))) => (v, 1),
+            (Err(DomainError::ConcurrentModification(_)), Ok(v)) => (v, 2),
+            other => panic!("expected exactly one writer to win: {:?}", other),
+        };
+        assert_eq!(tag_version(&repo, 1).await, winner);
+        assert_eq!(repo.get_tag_ids(1).await.unwrap(), vec![tag]);
+    }
+
+    #[tokio::test]
+    async fn other_item_writes_leave_the_tag_version_alone() {
+        let (_dir, repo) = setup().await;
+        repo.add_tag(1, 1).await.unwrap();
+        let read = tag_version(&repo, 1).await;
+
+        // A second tag write in the same second, then a refresh and a rating
+        repo.add_tag(1, 2).await.unwrap();
+        let current = tag_version(&repo, 1).await;
+        assert!(current > read);
+        repo.update_metadata(&[(1, Some(42), None, None)])
+            .await
+            .unwrap();
+        repo.set_rating(1, Some(3)).await.unwrap();
+        assert_eq!(tag_version(&repo, 1).await, current);
+
+        // The refresh did not bring the version back to the one read first
+        let result = repo.replace_tags(1, vec![], Some(read)).await;
+        assert!(matches!(
+            result,
+            Err(DomainError::ConcurrentModification(_))
+        ));
+        assert_eq!(repo.get_tag_ids(1).await.unwrap(), vec![1, 2]);
+
+        // Nor does it make a writer holding the current version conflict
+        repo.replace_tags(1, vec![1], Some(current)).await.unwrap();
+        assert_eq!(repo.get_tag_ids(1).await.unwrap(), vec![1]);
+    }
+
+    #[tokio::test]
+    async fn batch_tag_writes_skip_items_at_a_stale_version() {
+        let (_dir, repo) = setup().await;
+        let read_1 = tag_version(&repo, 1).await;
+        let read_2 = tag_version(&repo, 2).await;
+        // Another window tags item 1 after it was read
+        repo.add_tag(1, 2).await.unwrap();
+
+        let expected = HashMap::from([(1, read_1), (2, read_2)]);
+        let stale = repo.batch_add_tag(&[1, 2], 1, &expected).await.unwrap();
+        assert_eq!(stale, vec![1]);
+        assert_eq!(repo.get_tag_ids(1).await.unwrap(), vec![2]);
+        assert_eq!(repo.get_tag_ids(2).await.unwrap(), vec![1]);
+
+        // Item 2 moved on with the add; with fresh versions both are removed
+        let stale = repo.batch_remove_tag(&[1, 2], 1, &expected).await.unwrap();
+        assert_eq!(stale, vec![1, 2]);
+        assert_eq!(repo.get_tag_ids(2).await.unwrap(), vec![1]);
+
+        let fresh = HashMap::from([
+            (1, tag_version(&repo, 1).await),
+            (2, tag_version(&repo, 2).await),
+        ]);
+        let stale = repo.batch_remove_tag(&[1, 2], 2, &fresh).await.unwrap();
+        assert!(stale.is_empty());
+        assert!(repo.get_tag_ids(1).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn save_rejects_existing_path_as_duplicate() {
+        let (_dir, repo) = setup().await;
+        let path = FilePath::new("C:\\a.txt").unwrap();
+        let mut item = Item::new(path, false, None, None, 0);
+
+        let result = repo.save(&mut item).await;
+
+        assert!(matches!(result, Err(DomainError::DuplicateEntry(_))));
+        assert_eq!(item.id(), None);
+        assert_eq!(
+            repo.find_by_path("C:\\a.txt").await.unwrap().unwrap().id(),
+            Some(1)
+        );
+    }
+
+    #[tokio::test]
+    async fn exclusive_group_keeps_one_tag_per_item() {
+        let (_dir, repo) = setup().await;
+        let conn = repo.pool.get().await.unwrap();
+        conn.interact(|conn: &mut Connection| {
+            conn.execute_batch(
+                "INSERT INTO tag_groups (id, name) VALUES (2, 'Open');
+                 INSERT INTO tags (id, group_id, value) VALUES (3, 1, 'c'), (4, 2, 'x'), (5, 2, 'y');
+                 UPDATE tag_groups SET exclusive = 1 WHERE id = 1;",
+            )
+        })
+        .await
+        .unwrap()
+        .unwrap();
+        let tags = |id| {
+            let repo = &repo;
+            async move {
+                let mut ids = repo.get_tag_ids(id).await.unwrap();
+                ids.sort_unstable();
+                ids
+            }
+        };
+
+        repo.add_tag(1, 1).await.unwrap();
+        repo.add_tag(1, 4).await.unwrap();
+        repo.add_tag(1, 5).await.unwrap();
+        repo.add_tag(1, 2).await.unwrap();
+        assert_eq!(tags(1).await, vec![2, 4, 5]);
+
+        repo.batch_add_tag(&[1, 2], 3, &HashMap::new())
+            .await
+            .unwrap();
+        assert_eq!(tags(1).await, vec![3, 4, 5]);
+        assert_eq!(tags(2).await, vec![3]);
+
+        repo.replace_tags(2, vec![1, 4, 2], None).await.unwrap();
+        assert_eq!(tags(2).await, vec![2, 4]);
+    }
+
+    #[tokio::test]
+    async fn exclusive_swap_bumps_tags_updated_at() {
+        let (_dir, repo) = setup().await;
+        let conn = repo.pool.get().await.unwrap();
+        conn.interact(|conn: &mut Connection| {
+            conn.execute_batch(
+                "UPDATE tag_groups SET exclusive = 1 WHERE id = 1;
+                 INSERT INTO item_tags (item_id, tag_id) VALUES (1, 1), (1, 2);",
+            )
+        })
+        .await
+        .unwrap()
+        .unwrap();
+        reset_tags_updated_at(&repo).await;
+
+        // Tag 2 is already present, but adding it again resolves the violation
+        repo.add_tag(1, 2).await.unwrap();
+        assert_eq!(repo.get_tag_ids(1).await.unwrap(), vec![2]);
+        assert!(tags_updated_at(&repo, 1).await > Some(1));
+    }
+
+    #[tokio::test]
+    async fn recently_tagged_orders_by_last_tag_change() {
+        let (_dir, repo) = setup().await;
+        let conn = repo.pool.get().await.unwrap();
+        conn.interact(|conn: &mut Connection| {
+            conn.execute_batch(
+                "INSERT INTO items (id, path, is_directory) VALUES
+                    (3, 'C:\\c.txt', 0), (4, 'C:\\d.txt', 0);
+                 UPDATE items SET tags_updated_at = 100 WHERE id = 1;
+                 UPDATE items SET tags_updated_at = 200 WHERE id = 2;
+                 UPDATE items SET tags_updated_at = 300, is_deleted = 1 WHERE id = 3;",
+            )
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+        let ids = |items: Vec<Item>| items.iter().filter_map(|i| i.id()).collect::<Vec<_>>();
+        // Deleted and never-tagged items are left out
+        assert_eq!(
+            ids(repo.find_recently_tagged(10).await.unwrap()),
+            vec![2, 1]
+        );
+
+        repo.add_tag(1, 1).await.unwrap();
+        assert_eq!(
+            ids(repo.find_recently_tagged(10).await.unwrap()),
+            vec![1, 2]
+        );
+        assert_eq!(ids(repo.find_recently_tagged(1).await.unwrap()), vec![1]);
+    }
+
+    #[tokio::test]
+    async fn no_op_tag_changes_and_metadata_updates_keep_tags_updated_at() {
+        let (_dir, repo) = setup().await;
+        repo.batch_add_tag(&[1], 1, &HashMap::new()).await.unwrap();
+        reset_tags_updated_at(&repo).await;
+
+        repo.add_tag(1, 1).await.unwrap();
+        repo.remove_tag(1, 2).await.unwrap();
+        repo.replace_tags(1, vec![1], None).await.unwrap();
+        repo.batch_add_tag(&[1], 1, &HashMap::new()).await.unwrap();
+
+        let mut item = repo.find_by_id(1).await.unwrap().unwrap();
+        item.update_size(Some(42));
+        repo.update(&item).await.unwrap();
+
+        assert_eq!(tags_updated_at(&repo, 1).await, Some(1));
+    }
+
+    #[tokio::test]
+    async fn soft_delete_and_restore_by_tag_only_touch_tagged_items() {
+        let (_dir, repo) = setup().await;
+        let conn = repo.pool.get().await.unwrap();
+        conn.interact(|conn: &mut Connection| {
+            conn.execute_batch(
+                "INSERT INTO items (id, path, is_directory) VALUES (3, 'C:\\c.txt', 0);
+                 INSERT INTO item_tags (item_id, tag_id) VALUES (1, 1), (2, 1), (2, 2), (3, 2);
+                 UPDATE items SET is_deleted = 1, deleted_at = 100 WHERE id = 2;",
+            )
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+        // Item 2 is already deleted, so only item 1 changes
+        assert_eq!(repo.soft_delete_by_tag(1).await.unwrap(), 1);
+        let (deleted, deleted_at) = deleted_state(&repo, 1).await;
+        assert!(deleted && deleted_at.is_some());
+        assert_eq!(deleted_state(&repo, 3).await, (false, None));
+
+        assert_eq!(repo.restore_by_tag(1).await.unwrap(), 2);
+        assert_eq!(deleted_state(&repo, 1).await, (false, None));
+        assert_eq!(deleted_state(&repo, 2).await, (false, None));
+
+        assert_eq!(repo.restore_by_tag(1).await.unwrap(), 0);
+        assert_eq!(repo.soft_delete_by_tag(99).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn find_missing_frn_pages_through_active_items_without_one() {
+        let (_dir, repo) = setup().await;
+        let conn = repo.pool.get().await.unwrap();
+        conn.interact(|conn: &mut Connection| {
+            conn.execute_batch(
+                "UPDATE items SET file_reference_number = NULL WHERE id = 1;
+                 INSERT INTO items (id, path, is_directory, file_reference_number, is_deleted) VALUES
+                    (3, 'C:\\c.txt', 0, 42, 0),
+                    (4, 'C:\\d.txt', 0, 0, 1),
+                    (5, 'C:\\e.txt', 0, 0, 0);",
+            )
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+        let ids = |items: Vec<Item>| items.iter().filter_map(|i| i.id()).collect::<Vec<_>>();
+        assert_eq!(ids(repo.find_missing_frn(0, 2).await.unwrap()), vec![1, 2]);
+        assert_eq!(ids(repo.find_missing_frn(2, 2).await.unwrap()), vec![5]);
+
+        repo.update_file_reference_number(2, 7).await.unwrap();
+        assert_eq!(
+            repo.find_by_id(2)
+                .await
+                .unwrap()
+                .unwrap()
+                .file_reference_number(),
+            7
+        );
+        assert_eq!(ids(repo.find_missing_frn(0, 10).await.unwrap()), vec![1, 5]);
+        assert!(matches!(
+            repo.update_file_reference_number(99, 1).await,
+            Err(DomainError::ItemNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn normalize_paths_renames_and_merges_duplicates() {
+        let (_dir, repo) = setup().await;
+        let conn = repo.pool.get().await.unwrap();
+        conn.interact(|conn: &mut Connection| {
+            conn.execute_batch(
+                "INSERT INTO items (id, path, is_directory, rating, is_deleted) VALUES
+                    (3, 'c:\\a.txt', 0, 4, 0),
+                    (4, 'C:/b.txt', 0, NULL, 1),
+                    (5, 'd:\\Photos\\', 1, NULL, 0);
+                 INSERT INTO item_tags (item_id, tag_id) VALUES (1, 1), (3, 1), (3, 2), (4, 2);",
+            )
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+        // 3 folds into 1 and 4 into the active 2; 5 is only renamed
+        assert_eq!(repo.normalize_paths().await.unwrap(), (1, 2));
+        let first = repo.find_by_id(1).await.unwrap().unwrap();
+        assert_eq!(first.rating(), Some(4));
+        let mut tags = repo.get_tag_ids(1).await.unwrap();
+        tags.sort();
+        assert_eq!(tags, vec![1, 2]);
+        assert_eq!(repo.get_tag_ids(2).await.unwrap(), vec![2]);
+        assert!(repo.find_by_id(3).await.unwrap().is_none());
+        assert!(repo.find_by_id(4).await.unwrap().is_none());
+        assert_eq!(
+            repo.find_by_id(5).await.unwrap().unwrap().path().as_str(),
+            "D:\\Photos"
+        );
+
+        // Lookups normalize too
+        let found = repo.find_by_path("c:/a.txt").await.unwrap().unwrap();
+        assert_eq!(found.id(), Some(1));
+        assert_eq!(repo.normalize_paths().await.unwrap(), (0, 0));
+    }
+
+    #[tokio::test]
+    async fn auto_tag_rule_applies_only_when_moving_into_its_directory() {
+        let (_dir, repo) = setup().await;
+        let conn = repo.pool.get().await.unwrap();
+        conn.interact(|conn: &mut Connection| {
+            conn.execute_batch(
+                "INSERT INTO tag_templates (id, name) VALUES (1, 'Inbox');
+                 INSERT INTO template_tags (template_id, tag_id) VALUES (1, 1);
+                 INSERT INTO auto_tag_rules (path_prefix, template_id) VALUES ('C:\\Inbox', 1);
+                 UPDATE items SET path = 'C:\\Inbox\\a.txt' WHERE id = 1;",
+            )
+        })
+        .await
+        .unwrap()
+        .unwrap();
+        drop(conn);
+
+        // Moved within the ruled directory: the user's tags are left alone
+        let changed = repo
+            .apply_auto_tag_rules(1, Some("C:\\Inbox\\old.txt"))
+            .await
+            .unwrap();
+        assert_eq!(changed, 0);
+
+        let changed = repo
+            .apply_auto_tag_rules(1, Some("C:\\Elsewhere\\a.txt"))
+            .await
+            .unwrap();
+        assert_eq!(changed, 1);
     }
 }
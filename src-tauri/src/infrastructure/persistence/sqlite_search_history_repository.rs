@@ -2,6 +2,7 @@
 //!
 //! Implementation of the SearchHistoryRepository trait using SQLite.
 
+use super::interact::interact;
 use crate::application::dto::SearchMode;
 use crate::domain::entities::{SearchCriteria, SearchHistory};
 use crate::domain::errors::DomainError;
@@ -24,11 +25,10 @@ impl SqliteSearchHistoryRepository {
 
 #[async_trait]
 impl SearchHistoryRepository for SqliteSearchHistoryRepository {
-    async fn save(&self, criteria: SearchCriteria) -> Result<(), DomainError> {
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
+    async fn save(&self, criteria: SearchCriteria, max_entries: usize) -> Result<(), DomainError> {
         let criteria = Arc::new(criteria);
 
-        conn.interact(move |conn: &mut Connection| {
+        interact(&self.pool, move |conn: &mut Connection| {
             let tx = conn.transaction()?;
 
             // 1. Try to find existing history specifically matching these criteria.
@@ -94,18 +94,16 @@ impl SearchHistoryRepository for SqliteSearchHistoryRepository {
                 }
             }
 
+            prune_oldest(&tx, max_entries)?;
+
             tx.commit()?;
             Ok::<(), rusqlite::Error>(())
         })
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
     }
 
     async fn get_recent(&self, limit: usize) -> Result<Vec<SearchHistory>, DomainError> {
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
-
-        conn.interact(move |conn: &mut Connection| {
+        interact(&self.pool, move |conn: &mut Connection| {
             let mut stmt = conn.prepare(
                 "SELECT id, text_query, search_mode, last_used_at 
                  FROM search_histories 
@@ -154,43 +152,137 @@ impl SearchHistoryRepository for SqliteSearchHistoryRepository {
             Ok::<Vec<SearchHistory>, rusqlite::Error>(result)
         })
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
     }
 
     async fn delete(&self, id: i64) -> Result<(), DomainError> {
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
-
-        conn.interact(move |conn: &mut Connection| {
+        interact(&self.pool, move |conn: &mut Connection| {
             conn.execute("DELETE FROM search_histories WHERE id = ?", [id])?;
             Ok::<(), rusqlite::Error>(())
         })
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
     }
 
     async fn clear_all(&self) -> Result<(), DomainError> {
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
-
-        conn.interact(move |conn: &mut Connection| {
+        interact(&self.pool, move |conn: &mut Connection| {
             conn.execute("DELETE FROM search_histories", [])?;
             Ok::<(), rusqlite::Error>(())
         })
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
     }
-}
 
-fn map_pool_error(e: deadpool_sqlite::PoolError) -> DomainError {
-    DomainError::DatabaseError(format!("Database pool error: {}", e))
+    async fn prune(&self, max_entries: usize) -> Result<u64, DomainError> {
+        interact(&self.pool, move |conn: &mut Connection| {
+            prune_oldest(conn, max_entries)
+        })
+        .await
+    }
+
+    async fn count(&self) -> Result<u64, DomainError> {
+        interact(&self.pool, move |conn: &mut Connection| {
+            conn.query_row("SELECT COUNT(*) FROM search_histories", [], |row| {
+                row.get::<_, i64>(0)
+            })
+            .map(|count| count as u64)
+        })
+        .await
+    }
 }
 
-fn map_interact_error(e: deadpool_sqlite::InteractError) -> DomainError {
-    DomainError::DatabaseError(format!("Database interaction error: {}", e))
+/// Deletes the least recently used entries beyond `max_entries`; their tags
+/// go with them via `ON DELETE CASCADE`. Returns the number removed.
+fn prune_oldest(conn: &Connection, max_entries: usize) -> rusqlite::Result<u64> {
+    let count: i64 = conn.query_row("SELECT COUNT(*) FROM search_histories", [], |row| {
+        row.get(0)
+    })?;
+    let excess = count - max_entries as i64;
+    if excess <= 0 {
+        return Ok(0);
+    }
+
+    // Ties on last_used_at (second resolution) drop the older insert first
+    let removed = conn.execute(
+        "DELETE FROM search_histories WHERE id IN (
+             SELECT id FROM search_histories ORDER BY last_used_at ASC, id ASC LIMIT ?
+         )",
+        [excess],
+    )?;
+    Ok(removed as u64)
 }
 
-fn map_db_error(e: rusqlite::Error) -> DomainError {
-    DomainError::DatabaseError(format!("Database error: {}", e))
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::persistence::open_database;
+
+    async fn setup() -> (tempfile::TempDir, SqliteSearchHistoryRepository) {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = open_database(&dir.path().join("test.db")).await.unwrap();
+        let conn = pool.get().await.unwrap();
+        conn.interact(|conn: &mut Connection| {
+            conn.execute_batch(
+                "INSERT INTO tag_groups (id, name) VALUES (1, 'Group');
+                 INSERT INTO tags (id, group_id, value) VALUES (1, 1, 'a');",
+            )
+        })
+        .await
+        .unwrap()
+        .unwrap();
+        (dir, SqliteSearchHistoryRepository::new(Arc::new(pool)))
+    }
+
+    fn query(text: &str) -> SearchCriteria {
+        SearchCriteria::new(Some(text.to_string()), vec![1], SearchMode::And)
+    }
+
+    async fn queries(repo: &SqliteSearchHistoryRepository) -> Vec<String> {
+        repo.get_recent(100)
+            .await
+            .unwrap()
+            .into_iter()
+            .filter_map(|h| h.criteria.text_query)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn save_prunes_least_recently_used_entries() {
+        let (_dir, repo) = setup().await;
+        for text in ["one", "two", "three", "four"] {
+            repo.save(query(text), 100).await.unwrap();
+        }
+        // Spread the timestamps so recency is unambiguous; "one" is most recent
+        let conn = repo.pool.get().await.unwrap();
+        conn.interact(|conn: &mut Connection| {
+            conn.execute_batch(
+                "UPDATE search_histories SET last_used_at = 400 WHERE text_query = 'one';
+                 UPDATE search_histories SET last_used_at = 100 WHERE text_query = 'two';
+                 UPDATE search_histories SET last_used_at = 200 WHERE text_query = 'three';
+                 UPDATE search_histories SET last_used_at = 300 WHERE text_query = 'four';",
+            )
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+        repo.save(query("five"), 3).await.unwrap();
+
+        assert_eq!(repo.count().await.unwrap(), 3);
+        assert_eq!(queries(&repo).await, vec!["five", "one", "four"]);
+        let orphaned: i64 = conn
+            .interact(|conn: &mut Connection| {
+                conn.query_row(
+                    "SELECT COUNT(*) FROM search_history_tags
+                     WHERE search_history_id NOT IN (SELECT id FROM search_histories)",
+                    [],
+                    |row| row.get(0),
+                )
+            })
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(orphaned, 0);
+
+        assert_eq!(repo.prune(1).await.unwrap(), 2);
+        assert_eq!(queries(&repo).await, vec!["five"]);
+        assert_eq!(repo.prune(1).await.unwrap(), 0);
+    }
 }
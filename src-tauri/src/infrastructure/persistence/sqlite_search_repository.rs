@@ -2,22 +2,155 @@
 //!
 //! Specialized repository for search operations.
 
-use super::cql_executor::expr_to_sql;
-use crate::application::dto::{ItemDto, SearchMode};
+use super::cql_executor::{FILENAME_EXPR, expr_to_sql};
+use super::interact::{interact, interact_write_with};
+use super::migrations::schema_version;
+use super::sqlite_item_repository::tag_filter_conditions;
+use crate::application::dto::{
+    BenchmarkDto, FilenameMatchMode, GroupBy, ItemDto, ItemSort, LibraryStatsDto, ResultGroupDto,
+    SearchMode, SimilarItemDto, SortDirection, SortField, TimeField,
+};
 use crate::domain::errors::DomainError;
-use crate::domain::search::parse_cql;
+use crate::domain::search::{fuzzy_score, parse_cql};
+use dashmap::DashMap;
+use dashmap::mapref::entry::Entry;
 use deadpool_sqlite::Pool;
-use rusqlite::Connection;
+use rusqlite::{Connection, ErrorCode, InterruptHandle};
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+
+/// Maximum rows scored by fuzzy filename search.
+const FUZZY_CANDIDATE_LIMIT: usize = 2000;
 
 /// SQLite repository for search operations.
 pub struct SqliteSearchRepository {
     pool: Arc<Pool>,
+    /// Interrupt handles of running interruptible queries, keyed by request ID.
+    running: Arc<DashMap<String, InterruptHandle>>,
+    /// Source of registry keys for queries without a caller-supplied request ID.
+    next_internal_id: AtomicU64,
 }
 
 impl SqliteSearchRepository {
     pub fn new(pool: Arc<Pool>) -> Self {
-        Self { pool }
+        Self {
+            pool,
+            running: Arc::new(DashMap::new()),
+            next_internal_id: AtomicU64::new(0),
+        }
+    }
+
+    /// Interrupts the running query registered under `request_id`.
+    /// Returns false if no such query is running.
+    pub fn cancel(&self, request_id: &str) -> bool {
+        match self.running.get(request_id) {
+            Some(handle) => {
+                handle.interrupt();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Runs `f` on a pooled connection whose interrupt handle is registered under
+    /// `request_id` for the duration of the call, so it can be cancelled. If
+    /// `timeout` elapses first the query is interrupted and `QueryTimeout` returned.
+    /// The timeout counts from the moment the handle is registered, so waiting
+    /// for a pooled connection does not use it up. A `request_id` that is
+    /// already running is rejected rather than replacing the earlier query.
+    /// This takes its own connection instead of going through `interact`,
+    /// since it needs the interrupt handle, so it runs without a `db` span.
+    async fn run_interruptible<T, F>(
+        &self,
+        request_id: Option<String>,
+        timeout: Option<Duration>,
+        f: F,
+    ) -> Result<T, DomainError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> rusqlite::Result<T> + Send + 'static,
+    {
+        let key = request_id.unwrap_or_else(|| {
+            let id = self.next_internal_id.fetch_add(1, Ordering::Relaxed);
+            format!("internal-{}", id)
+        });
+
+        let conn = self.pool.get().await?;
+
+        let (started_tx, started_rx) = oneshot::channel::<()>();
+        let timed_out = Arc::new(AtomicBool::new(false));
+        let timer = timeout.map(|timeout| {
+            let running = self.running.clone();
+            let key = key.clone();
+            let timed_out = timed_out.clone();
+            tokio::spawn(async move {
+                if started_rx.await.is_err() {
+                    return;
+                }
+                tokio::time::sleep(timeout).await;
+                if let Some(handle) = running.get(&key) {
+                    timed_out.store(true, Ordering::SeqCst);
+                    handle.interrupt();
+                }
+            })
+        });
+
+        let running = self.running.clone();
+        let registered_key = key.clone();
+        let outcome = conn
+            .interact(move |conn: &mut Connection| {
+                match running.entry(registered_key.clone()) {
+                    Entry::Occupied(_) => return None,
+                    Entry::Vacant(slot) => {
+                        slot.insert(conn.get_interrupt_handle());
+                    }
+                }
+                let _ = started_tx.send(());
+
+                let result = f(conn);
+                running.remove(&registered_key);
+
+                if result.as_ref().is_err_and(is_interrupted) {
+                    // Leave the pooled connection clean: end any open
+                    // transaction and make sure it still answers queries.
+                    if let Err(e) = reset_interrupted(conn) {
+                        return Some(Err(e));
+                    }
+                }
+
+                Some(result)
+            })
+            .await?;
+
+        if let Some(timer) = timer {
+            timer.abort();
+        }
+
+        let Some(result) = outcome else {
+            return Err(DomainError::ValidationError(format!(
+                "Search request '{}' is already running",
+                key
+            )));
+        };
+
+        match result {
+            Err(e) if is_interrupted(&e) => {
+                if timed_out.load(Ordering::SeqCst) {
+                    Err(DomainError::QueryTimeout(format!(
+                        "Search exceeded {} seconds",
+                        timeout.map(|t| t.as_secs()).unwrap_or(0)
+                    )))
+                } else {
+                    Err(DomainError::QueryCancelled(
+                        "Search was cancelled".to_string(),
+                    ))
+                }
+            }
+            other => other.map_err(DomainError::from),
+        }
     }
 
     fn map_row_to_item_dto(row: &rusqlite::Row) -> rusqlite::Result<ItemDto> {
@@ -29,28 +162,47 @@ impl SqliteSearchRepository {
             modified_time: row.get(4)?,
             created_at: row.get(5)?,
             updated_at: row.get(6)?,
+            tags_updated_at: row.get(7)?,
+            rating: row.get(8)?,
+            tag_count: None,
+            file_reference_number: None,
+            frn_status: None,
         })
     }
 
+    /// Like `map_row_to_item_dto`, also reading the `tag_count_column` at index 9.
+    fn map_row_to_item_dto_with_tag_count(row: &rusqlite::Row) -> rusqlite::Result<ItemDto> {
+        let mut item = Self::map_row_to_item_dto(row)?;
+        item.tag_count = row.get::<_, Option<i64>>(9)?.map(|count| count as u64);
+        Ok(item)
+    }
+
     /// Searches items by tags with AND logic (must have ALL specified tags).
-    pub async fn search_by_tags_and(&self, tag_ids: Vec<i64>) -> Result<Vec<ItemDto>, DomainError> {
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
+    /// With `include_tag_count`, each result also carries its total tag count.
+    pub async fn search_by_tags_and(
+        &self,
+        tag_ids: Vec<i64>,
+        sort: ItemSort,
+        include_tag_count: bool,
+    ) -> Result<Vec<ItemDto>, DomainError> {
         let tag_count = tag_ids.len() as i64;
 
-        conn.interact(move |conn: &mut Connection| {
+        interact(&self.pool, move |conn: &mut Connection| {
             let placeholders: Vec<String> = tag_ids.iter().map(|_| "?".to_string()).collect();
             let placeholders_str = placeholders.join(", ");
 
             let sql = format!(
                 "SELECT i.id, i.path, i.is_directory, i.size, i.modified_time,
-                        i.created_at, i.updated_at
+                        i.created_at, i.updated_at, i.tags_updated_at, i.rating, {}
                  FROM items i
                  INNER JOIN item_tags it ON i.id = it.item_id
                  WHERE i.is_deleted = 0 AND it.tag_id IN ({})
                  GROUP BY i.id
                  HAVING COUNT(DISTINCT it.tag_id) = ?
-                 ORDER BY i.path ASC",
-                placeholders_str
+                 ORDER BY {}",
+                tag_count_column(include_tag_count),
+                placeholders_str,
+                order_by_clause(sort)
             );
 
             let mut stmt = conn.prepare(&sql)?;
@@ -65,32 +217,92 @@ impl SqliteSearchRepository {
                 params.iter().map(|p| p.as_ref()).collect();
 
             let items = stmt
-                .query_map(params_refs.as_slice(), Self::map_row_to_item_dto)?
+                .query_map(
+                    params_refs.as_slice(),
+                    Self::map_row_to_item_dto_with_tag_count,
+                )?
                 .collect::<Result<Vec<ItemDto>, _>>()?;
 
             Ok::<Vec<ItemDto>, rusqlite::Error>(items)
         })
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
     }
 
-    /// Searches items by tags with OR logic (must have ANY of the specified tags).
-    pub async fn search_by_tags_or(&self, tag_ids: Vec<i64>) -> Result<Vec<ItemDto>, DomainError> {
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
+    /// Lists items whose `time_field` falls within the last `window_hours`,
+    /// most recent first. A non-empty `tag_ids` also requires ALL those tags.
+    pub async fn search_by_time_window(
+        &self,
+        window_hours: u32,
+        time_field: TimeField,
+        tag_ids: Vec<i64>,
+    ) -> Result<Vec<ItemDto>, DomainError> {
+        let column = match time_field {
+            TimeField::Modified => "COALESCE(i.modified_time, 0)",
+            TimeField::Created => "i.created_at",
+        };
+
+        interact(&self.pool, move |conn: &mut Connection| {
+            let mut params: Vec<i64> = vec![window_hours as i64 * 3600];
+            let tag_filter = if tag_ids.is_empty() {
+                String::new()
+            } else {
+                let placeholders: Vec<&str> = tag_ids.iter().map(|_| "?").collect();
+                params.extend(&tag_ids);
+                params.push(tag_ids.len() as i64);
+                format!(
+                    "AND i.id IN (
+                         SELECT item_id FROM item_tags
+                         WHERE tag_id IN ({})
+                         GROUP BY item_id
+                         HAVING COUNT(DISTINCT tag_id) = ?
+                     )",
+                    placeholders.join(", ")
+                )
+            };
+
+            let sql = format!(
+                "SELECT i.id, i.path, i.is_directory, i.size, i.modified_time,
+                        i.created_at, i.updated_at, i.tags_updated_at, i.rating
+                 FROM items i
+                 WHERE i.is_deleted = 0 AND {column} >= unixepoch() - ? {tag_filter}
+                 ORDER BY {column} DESC, i.path ASC"
+            );
+
+            let mut stmt = conn.prepare(&sql)?;
+            let items = stmt
+                .query_map(
+                    rusqlite::params_from_iter(params.iter()),
+                    Self::map_row_to_item_dto,
+                )?
+                .collect::<Result<Vec<ItemDto>, _>>()?;
+
+            Ok::<Vec<ItemDto>, rusqlite::Error>(items)
+        })
+        .await
+    }
 
-        conn.interact(move |conn: &mut Connection| {
+    /// Searches items by tags with OR logic (must have ANY of the specified tags).
+    /// With `include_tag_count`, each result also carries its total tag count.
+    pub async fn search_by_tags_or(
+        &self,
+        tag_ids: Vec<i64>,
+        sort: ItemSort,
+        include_tag_count: bool,
+    ) -> Result<Vec<ItemDto>, DomainError> {
+        interact(&self.pool, move |conn: &mut Connection| {
             let placeholders: Vec<String> = tag_ids.iter().map(|_| "?".to_string()).collect();
             let placeholders_str = placeholders.join(", ");
 
             let sql = format!(
                 "SELECT DISTINCT i.id, i.path, i.is_directory, i.size, i.modified_time,
-                        i.created_at, i.updated_at
+                        i.created_at, i.updated_at, i.tags_updated_at, i.rating, {}
                  FROM items i
                  INNER JOIN item_tags it ON i.id = it.item_id
                  WHERE i.is_deleted = 0 AND it.tag_id IN ({})
-                 ORDER BY i.path ASC",
-                placeholders_str
+                 ORDER BY {}",
+                tag_count_column(include_tag_count),
+                placeholders_str,
+                order_by_clause(sort)
             );
 
             let mut stmt = conn.prepare(&sql)?;
@@ -104,39 +316,232 @@ impl SqliteSearchRepository {
                 params.iter().map(|p| p.as_ref()).collect();
 
             let items = stmt
-                .query_map(params_refs.as_slice(), Self::map_row_to_item_dto)?
+                .query_map(
+                    params_refs.as_slice(),
+                    Self::map_row_to_item_dto_with_tag_count,
+                )?
                 .collect::<Result<Vec<ItemDto>, _>>()?;
 
             Ok::<Vec<ItemDto>, rusqlite::Error>(items)
         })
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
     }
 
-    /// Searches items by filename (LIKE query on path).
-    pub async fn search_by_filename(&self, query: &str) -> Result<Vec<ItemDto>, DomainError> {
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
-        let pattern = format!("%{}%", query);
+    /// Lists the active items carrying a single tag, ordered by path.
+    pub async fn items_by_tag(&self, tag_id: i64) -> Result<Vec<ItemDto>, DomainError> {
+        self.search_by_tags_or(vec![tag_id], ItemSort::default(), false)
+            .await
+    }
+
+    /// Lists active items sharing at least `min_shared` tags with `item_id`,
+    /// most shared tags first, then by path. The item itself is left out.
+    pub async fn similar_items(
+        &self,
+        item_id: i64,
+        min_shared: u32,
+    ) -> Result<Vec<SimilarItemDto>, DomainError> {
+        interact(&self.pool, move |conn: &mut Connection| {
+            let mut stmt = conn.prepare(
+                "SELECT i.id, i.path, i.is_directory, i.size, i.modified_time,
+                        i.created_at, i.updated_at, i.tags_updated_at, i.rating,
+                        COUNT(*) AS shared
+                 FROM item_tags src
+                 INNER JOIN item_tags it ON it.tag_id = src.tag_id AND it.item_id <> src.item_id
+                 INNER JOIN items i ON i.id = it.item_id
+                 WHERE src.item_id = ?1 AND i.is_deleted = 0
+                 GROUP BY i.id
+                 HAVING COUNT(*) >= ?2
+                 ORDER BY shared DESC, i.path ASC",
+            )?;
+
+            let items = stmt
+                .query_map((item_id, min_shared), |row| {
+                    Ok(SimilarItemDto {
+                        item: Self::map_row_to_item_dto(row)?,
+                        shared_tags: row.get(9)?,
+                    })
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Ok::<Vec<SimilarItemDto>, rusqlite::Error>(items)
+        })
+        .await
+    }
 
-        conn.interact(move |conn: &mut Connection| {
+    /// Returns every live item sharing at least one tag with the item,
+    /// together with the ids of the tags it shares.
+    pub async fn co_tagged_items(
+        &self,
+        item_id: i64,
+    ) -> Result<Vec<(ItemDto, Vec<i64>)>, DomainError> {
+        interact(&self.pool, move |conn: &mut Connection| {
             let mut stmt = conn.prepare(
-                "SELECT id, path, is_directory, size, modified_time,
-                        created_at, updated_at
-                 FROM items
-                 WHERE is_deleted = 0 AND path LIKE ?1
-                 ORDER BY path ASC",
+                "SELECT i.id, i.path, i.is_directory, i.size, i.modified_time,
+                        i.created_at, i.updated_at, i.tags_updated_at, i.rating,
+                        GROUP_CONCAT(it.tag_id)
+                 FROM item_tags src
+                 INNER JOIN item_tags it ON it.tag_id = src.tag_id AND it.item_id <> src.item_id
+                 INNER JOIN items i ON i.id = it.item_id
+                 WHERE src.item_id = ?1 AND i.is_deleted = 0
+                 GROUP BY i.id",
             )?;
 
             let items = stmt
-                .query_map([&pattern], Self::map_row_to_item_dto)?
+                .query_map([item_id], |row| {
+                    let tag_ids: String = row.get(9)?;
+                    Ok((
+                        Self::map_row_to_item_dto(row)?,
+                        tag_ids
+                            .split(',')
+                            .filter_map(|id| id.parse().ok())
+                            .collect(),
+                    ))
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Ok::<Vec<(ItemDto, Vec<i64>)>, rusqlite::Error>(items)
+        })
+        .await
+    }
+
+    /// Searches items by filename.
+    ///
+    /// `Contains` is a LIKE scan over the full path. `Prefix` and `Exact` compare
+    /// the lowercased file name (`FILENAME_EXPR`) by range/equality, which the
+    /// `idx_items_file_name` expression index can serve.
+    pub async fn search_by_filename(
+        &self,
+        query: &str,
+        match_mode: FilenameMatchMode,
+        sort: ItemSort,
+    ) -> Result<Vec<ItemDto>, DomainError> {
+        let (condition, params) = filename_condition(query, match_mode);
+
+        interact(&self.pool, move |conn: &mut Connection| {
+            let sql = format!(
+                "SELECT i.id, i.path, i.is_directory, i.size, i.modified_time,
+                        i.created_at, i.updated_at, i.tags_updated_at, i.rating
+                 FROM items i
+                 WHERE {}
+                 ORDER BY {}",
+                condition,
+                order_by_clause(sort)
+            );
+            let mut stmt = conn.prepare(&sql)?;
+
+            let items = stmt
+                .query_map(
+                    rusqlite::params_from_iter(params),
+                    Self::map_row_to_item_dto,
+                )?
+                .collect::<Result<Vec<ItemDto>, _>>()?;
+
+            Ok::<Vec<ItemDto>, rusqlite::Error>(items)
+        })
+        .await
+    }
+
+    /// Searches items by filename and ranks them with a fuzzy scorer.
+    ///
+    /// Rows whose path contains every whitespace-separated token as a
+    /// subsequence are fetched, shortest paths first and capped at
+    /// `FUZZY_CANDIDATE_LIMIT`. Every token must then fuzzy-match the file name
+    /// or, at half weight, the full path. Results are ordered by score, best first.
+    pub async fn search_filename_fuzzy(&self, query: &str) -> Result<Vec<ItemDto>, DomainError> {
+        let tokens: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+        if tokens.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let candidates = interact(&self.pool, {
+            let tokens = tokens.clone();
+            move |conn: &mut Connection| {
+                let conditions: Vec<&str> =
+                    tokens.iter().map(|_| "i.path LIKE ? ESCAPE '!'").collect();
+                let sql = format!(
+                    "SELECT i.id, i.path, i.is_directory, i.size, i.modified_time,
+                            i.created_at, i.updated_at, i.tags_updated_at, i.rating
+                     FROM items i
+                     WHERE i.is_deleted = 0 AND {}
+                     ORDER BY LENGTH(i.path) ASC, i.path ASC
+                     LIMIT {}",
+                    conditions.join(" AND "),
+                    FUZZY_CANDIDATE_LIMIT
+                );
+
+                let mut stmt = conn.prepare(&sql)?;
+                let patterns: Vec<String> = tokens
+                    .iter()
+                    .map(|t| subsequence_like(t.as_str()))
+                    .collect();
+                let params: Vec<&dyn rusqlite::ToSql> =
+                    patterns.iter().map(|p| p as &dyn rusqlite::ToSql).collect();
+
+                let items = stmt
+                    .query_map(params.as_slice(), Self::map_row_to_item_dto)?
+                    .collect::<Result<Vec<ItemDto>, _>>()?;
+
+                Ok::<Vec<ItemDto>, rusqlite::Error>(items)
+            }
+        })
+        .await?;
+
+        let mut scored: Vec<(i64, ItemDto)> = candidates
+            .into_iter()
+            .filter_map(|item| {
+                let name = item.path.rsplit(['\\', '/']).next().unwrap_or(&item.path);
+                let score = tokens.iter().try_fold(0, |total, token| {
+                    let score = fuzzy_score(token, name)
+                        .or_else(|| fuzzy_score(token, &item.path).map(|s| s / 2))?;
+                    Some(total + score)
+                })?;
+                Some((score, item))
+            })
+            .collect();
+
+        scored.sort_by(|(a_score, a), (b_score, b)| {
+            b_score.cmp(a_score).then_with(|| a.path.cmp(&b.path))
+        });
+
+        Ok(scored.into_iter().map(|(_, item)| item).collect())
+    }
+
+    /// Searches items carrying none of `exclude_tag_ids`, optionally restricted to
+    /// items matching `include_tag_ids` under `mode`. With no include tags every
+    /// active item is a candidate. Ordered by path.
+    pub async fn search_excluding_tags(
+        &self,
+        include_tag_ids: Vec<i64>,
+        exclude_tag_ids: Vec<i64>,
+        mode: SearchMode,
+    ) -> Result<Vec<ItemDto>, DomainError> {
+        interact(&self.pool, move |conn: &mut Connection| {
+            let (filters, params) =
+                tag_filter_conditions(&include_tag_ids, &exclude_tag_ids, mode == SearchMode::And);
+            let mut conditions = vec!["i.is_deleted = 0".to_string()];
+            conditions.extend(filters);
+
+            let sql = format!(
+                "SELECT i.id, i.path, i.is_directory, i.size, i.modified_time,
+                        i.created_at, i.updated_at, i.tags_updated_at, i.rating
+                 FROM items i
+                 WHERE {}
+                 ORDER BY {}",
+                conditions.join(" AND "),
+                order_by_clause(ItemSort::default())
+            );
+
+            let mut stmt = conn.prepare(&sql)?;
+            let items = stmt
+                .query_map(
+                    rusqlite::params_from_iter(params),
+                    Self::map_row_to_item_dto,
+                )?
                 .collect::<Result<Vec<ItemDto>, _>>()?;
 
             Ok::<Vec<ItemDto>, rusqlite::Error>(items)
         })
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
     }
 
     /// Combined search with tags and optional filename filter.
@@ -145,9 +550,8 @@ impl SqliteSearchRepository {
         tag_ids: Vec<i64>,
         mode: SearchMode,
         filename_query: Option<String>,
+        sort: ItemSort,
     ) -> Result<Vec<ItemDto>, DomainError> {
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
-
         let filename_pattern = filename_query
             .as_ref()
             .filter(|q| !q.trim().is_empty())
@@ -157,7 +561,8 @@ impl SqliteSearchRepository {
         let has_filename = filename_pattern.is_some();
         let tag_count = tag_ids.len() as i64;
 
-        conn.interact(move |conn: &mut Connection| {
+        interact(&self.pool, move |conn: &mut Connection| {
+            let order_by = order_by_clause(sort);
             let sql = if has_tags && has_filename {
                 let placeholders: Vec<String> = tag_ids.iter().map(|_| "?".to_string()).collect();
                 let placeholders_str = placeholders.join(", ");
@@ -165,23 +570,23 @@ impl SqliteSearchRepository {
                 match mode {
                     SearchMode::And => format!(
                         "SELECT i.id, i.path, i.is_directory, i.size, i.modified_time,
-                                i.created_at, i.updated_at
+                                i.created_at, i.updated_at, i.tags_updated_at, i.rating
                          FROM items i
                          INNER JOIN item_tags it ON i.id = it.item_id
                          WHERE i.is_deleted = 0 AND it.tag_id IN ({}) AND i.path LIKE ?
                          GROUP BY i.id
                          HAVING COUNT(DISTINCT it.tag_id) = ?
-                         ORDER BY i.path ASC",
-                        placeholders_str
+                         ORDER BY {}",
+                        placeholders_str, order_by
                     ),
                     SearchMode::Or => format!(
                         "SELECT DISTINCT i.id, i.path, i.is_directory, i.size, i.modified_time,
-                                i.created_at, i.updated_at
+                                i.created_at, i.updated_at, i.tags_updated_at, i.rating
                          FROM items i
                          INNER JOIN item_tags it ON i.id = it.item_id
                          WHERE i.is_deleted = 0 AND it.tag_id IN ({}) AND i.path LIKE ?
-                         ORDER BY i.path ASC",
-                        placeholders_str
+                         ORDER BY {}",
+                        placeholders_str, order_by
                     ),
                 }
             } else if has_tags {
@@ -191,32 +596,34 @@ impl SqliteSearchRepository {
                 match mode {
                     SearchMode::And => format!(
                         "SELECT i.id, i.path, i.is_directory, i.size, i.modified_time,
-                                i.created_at, i.updated_at
+                                i.created_at, i.updated_at, i.tags_updated_at, i.rating
                          FROM items i
                          INNER JOIN item_tags it ON i.id = it.item_id
                          WHERE i.is_deleted = 0 AND it.tag_id IN ({})
                          GROUP BY i.id
                          HAVING COUNT(DISTINCT it.tag_id) = ?
-                         ORDER BY i.path ASC",
-                        placeholders_str
+                         ORDER BY {}",
+                        placeholders_str, order_by
                     ),
                     SearchMode::Or => format!(
                         "SELECT DISTINCT i.id, i.path, i.is_directory, i.size, i.modified_time,
-                                i.created_at, i.updated_at
+                                i.created_at, i.updated_at, i.tags_updated_at, i.rating
                          FROM items i
                          INNER JOIN item_tags it ON i.id = it.item_id
                          WHERE i.is_deleted = 0 AND it.tag_id IN ({})
-                         ORDER BY i.path ASC",
-                        placeholders_str
+                         ORDER BY {}",
+                        placeholders_str, order_by
                     ),
                 }
             } else {
-                "SELECT id, path, is_directory, size, modified_time,
-                        created_at, updated_at
-                 FROM items
-                 WHERE is_deleted = 0 AND path LIKE ?
-                 ORDER BY path ASC"
-                    .to_string()
+                format!(
+                    "SELECT i.id, i.path, i.is_directory, i.size, i.modified_time,
+                            i.created_at, i.updated_at, i.tags_updated_at, i.rating
+                     FROM items i
+                     WHERE i.is_deleted = 0 AND i.path LIKE ?
+                     ORDER BY {}",
+                    order_by
+                )
             };
 
             let mut stmt = conn.prepare(&sql)?;
@@ -247,26 +654,22 @@ impl SqliteSearchRepository {
             Ok::<Vec<ItemDto>, rusqlite::Error>(items)
         })
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
     }
 
-    /// Searches items using a CQL query string.
-    pub async fn search_cql(&self, query: &str) -> Result<Vec<ItemDto>, DomainError> {
+    /// Searches items using a CQL query string, reading every match. Only
+    /// tests use it; the app always bounds results with `search_cql_limited`.
+    #[cfg(test)]
+    pub async fn search_cql(
+        &self,
+        query: &str,
+        request_id: Option<String>,
+        timeout: Option<Duration>,
+    ) -> Result<Vec<ItemDto>, DomainError> {
         let expr = parse_cql(query).map_err(|e| DomainError::ValidationError(e.to_string()))?;
         let fragment = expr_to_sql(&expr);
 
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
-
-        conn.interact(move |conn: &mut Connection| {
-            let sql = format!(
-                "SELECT DISTINCT i.id, i.path, i.is_directory, i.size, i.modified_time, \
-                        i.created_at, i.updated_at \
-                 FROM items i \
-                 WHERE i.is_deleted = 0 AND ({}) \
-                 ORDER BY i.path ASC",
-                fragment.sql
-            );
+        self.run_interruptible(request_id, timeout, move |conn: &mut Connection| {
+            let sql = cql_select_sql(&fragment.sql);
 
             let mut stmt = conn.prepare(&sql)?;
 
@@ -283,19 +686,1394 @@ impl SqliteSearchRepository {
             Ok::<Vec<ItemDto>, rusqlite::Error>(items)
         })
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
+    }
+
+    /// Searches items using a CQL query string, reading at most `max_rows`
+    /// items. When more match, the total number of matches is returned with
+    /// the first `max_rows`, so the caller can report the cut-off.
+    ///
+    /// The query can be cancelled via [`Self::cancel`] with `request_id`, and is
+    /// interrupted with `QueryTimeout` once `timeout` elapses.
+    pub async fn search_cql_limited(
+        &self,
+        query: &str,
+        request_id: Option<String>,
+        timeout: Option<Duration>,
+        max_rows: usize,
+    ) -> Result<(Vec<ItemDto>, Option<u64>), DomainError> {
+        let expr = parse_cql(query).map_err(|e| DomainError::ValidationError(e.to_string()))?;
+        let fragment = expr_to_sql(&expr);
+
+        self.run_interruptible(request_id, timeout, move |conn: &mut Connection| {
+            let params_refs: Vec<&dyn rusqlite::ToSql> = fragment
+                .params
+                .iter()
+                .map(|p| p as &dyn rusqlite::ToSql)
+                .collect();
+
+            // One row past the cap tells whether anything was cut off
+            let limit = i64::try_from(max_rows).unwrap_or(i64::MAX - 1) + 1;
+            let mut items = {
+                let sql = format!("{} LIMIT ?", cql_select_sql(&fragment.sql));
+                let mut stmt = conn.prepare(&sql)?;
+                let mut limited = params_refs.clone();
+                limited.push(&limit);
+                stmt.query_map(limited.as_slice(), Self::map_row_to_item_dto)?
+                    .collect::<Result<Vec<ItemDto>, _>>()?
+            };
+            if items.len() <= max_rows {
+                return Ok((items, None));
+            }
+
+            items.truncate(max_rows);
+            let total: i64 = conn.query_row(
+                &cql_count_sql(&fragment.sql),
+                params_refs.as_slice(),
+                |row| row.get(0),
+            )?;
+            Ok::<_, rusqlite::Error>((items, Some(total as u64)))
+        })
+        .await
+    }
+
+    /// Counts the items matching each CQL query, running them one after
+    /// another on a single connection.
+    ///
+    /// Results are returned in input order. A query that fails to parse or
+    /// execute yields its error message in place of a count, without
+    /// affecting the others.
+    pub async fn count_cql_batch(
+        &self,
+        queries: Vec<String>,
+    ) -> Result<Vec<Result<u64, String>>, DomainError> {
+        let fragments: Vec<Result<_, String>> = queries
+            .iter()
+            .map(|query| {
+                parse_cql(query)
+                    .map(|expr| expr_to_sql(&expr))
+                    .map_err(|e| e.to_string())
+            })
+            .collect();
+
+        interact(&self.pool, move |conn: &mut Connection| {
+            let counts = fragments
+                .into_iter()
+                .map(|fragment| {
+                    let fragment = fragment?;
+                    conn.query_row(
+                        &cql_count_sql(&fragment.sql),
+                        rusqlite::params_from_iter(fragment.params.iter()),
+                        |row| row.get::<_, i64>(0),
+                    )
+                    .map(|count| count as u64)
+                    .map_err(|e| e.to_string())
+                })
+                .collect();
+            Ok::<Vec<Result<u64, String>>, rusqlite::Error>(counts)
+        })
+        .await
+    }
+
+    /// Returns the `EXPLAIN QUERY PLAN` rows for the statement a CQL search
+    /// would run, one detail string per row, indented by nesting depth.
+    pub async fn explain_cql(&self, query: &str) -> Result<Vec<String>, DomainError> {
+        let expr = parse_cql(query).map_err(|e| DomainError::ValidationError(e.to_string()))?;
+        let fragment = expr_to_sql(&expr);
+
+        interact(&self.pool, move |conn: &mut Connection| {
+            let sql = format!("EXPLAIN QUERY PLAN {}", cql_select_sql(&fragment.sql));
+            let mut stmt = conn.prepare(&sql)?;
+            let rows = stmt
+                .query_map(rusqlite::params_from_iter(fragment.params.iter()), |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, i64>(1)?,
+                        row.get::<_, String>(3)?,
+                    ))
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+
+            // Rows reference their parent by id; depth follows the chain
+            let mut depths: HashMap<i64, usize> = HashMap::new();
+            let plan = rows
+                .into_iter()
+                .map(|(id, parent, detail)| {
+                    let depth = depths.get(&parent).map_or(0, |d| d + 1);
+                    depths.insert(id, depth);
+                    format!("{}{}", "  ".repeat(depth), detail)
+                })
+                .collect();
+            Ok::<Vec<String>, rusqlite::Error>(plan)
+        })
+        .await
+    }
+
+    /// Runs a CQL query `iterations` times and reports how long execution took.
+    ///
+    /// The query is parsed and prepared once; each run steps the statement
+    /// through every row. All runs happen on one connection held for the whole
+    /// benchmark, so waiting on the pool never lands inside a measurement.
+    pub async fn benchmark_cql(
+        &self,
+        query: &str,
+        iterations: u32,
+    ) -> Result<BenchmarkDto, DomainError> {
+        let expr = parse_cql(query).map_err(|e| DomainError::ValidationError(e.to_string()))?;
+        let fragment = expr_to_sql(&expr);
+
+        interact(&self.pool, move |conn: &mut Connection| {
+            let mut stmt = conn.prepare(&cql_select_sql(&fragment.sql))?;
+            let mut timings = Vec::with_capacity(iterations as usize);
+            let mut row_count = 0;
+
+            for _ in 0..iterations {
+                let start = Instant::now();
+                let mut rows = stmt.query(rusqlite::params_from_iter(fragment.params.iter()))?;
+                let mut count = 0u64;
+                while rows.next()?.is_some() {
+                    count += 1;
+                }
+                timings.push(start.elapsed().as_secs_f64() * 1000.0);
+                row_count = count;
+            }
+
+            timings.sort_by(f64::total_cmp);
+            let mid = timings.len() / 2;
+            let median_ms = match timings.len() {
+                0 => 0.0,
+                n if n % 2 == 0 => (timings[mid - 1] + timings[mid]) / 2.0,
+                _ => timings[mid],
+            };
+            Ok::<BenchmarkDto, rusqlite::Error>(BenchmarkDto {
+                iterations,
+                row_count,
+                min_ms: timings.first().copied().unwrap_or(0.0),
+                median_ms,
+                max_ms: timings.last().copied().unwrap_or(0.0),
+            })
+        })
+        .await
+    }
+
+    /// Rebuilds every index and refreshes the planner statistics.
+    ///
+    /// Runs inside an immediate transaction so writers on other connections
+    /// wait until the rebuild finishes instead of interleaving with it.
+    pub async fn rebuild_indexes(&self) -> Result<(), DomainError> {
+        interact_write_with(
+            &self.pool,
+            move |conn: &mut Connection| {
+                conn.execute("BEGIN IMMEDIATE", [])?;
+                match conn.execute_batch("REINDEX; ANALYZE;") {
+                    Ok(()) => {
+                        conn.execute("COMMIT", [])?;
+                        Ok(())
+                    }
+                    Err(e) => {
+                        conn.execute("ROLLBACK", [])?;
+                        Err(e)
+                    }
+                }
+            },
+            DomainError::from,
+        )
+        .await
+    }
+
+    /// Returns the database's schema version (`PRAGMA user_version`).
+    pub async fn schema_version(&self) -> Result<u32, DomainError> {
+        interact(&self.pool, |conn: &mut Connection| schema_version(conn)).await
+    }
+
+    /// Returns item, tag and group counts plus the database size.
+    ///
+    /// The size is `page_count * page_size`, i.e. the main database file
+    /// without any pending WAL.
+    pub async fn library_stats(&self) -> Result<LibraryStatsDto, DomainError> {
+        interact(&self.pool, move |conn: &mut Connection| {
+            conn.query_row(
+                "SELECT
+                    (SELECT COUNT(*) FROM items WHERE is_deleted = 0),
+                    (SELECT COUNT(*) FROM items i WHERE i.is_deleted = 0
+                        AND EXISTS (SELECT 1 FROM item_tags it WHERE it.item_id = i.id)),
+                    (SELECT COUNT(*) FROM items WHERE is_deleted = 1),
+                    (SELECT COUNT(*) FROM tags),
+                    (SELECT COUNT(*) FROM tag_groups),
+                    (SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size())",
+                [],
+                |row| {
+                    let total_items = row.get::<_, i64>(0)? as u64;
+                    let tagged_items = row.get::<_, i64>(1)? as u64;
+                    Ok(LibraryStatsDto {
+                        total_items,
+                        tagged_items,
+                        untagged_items: total_items - tagged_items,
+                        deleted_items: row.get::<_, i64>(2)? as u64,
+                        total_tags: row.get::<_, i64>(3)? as u64,
+                        total_groups: row.get::<_, i64>(4)? as u64,
+                        db_size_bytes: row.get::<_, i64>(5)? as u64,
+                    })
+                },
+            )
+        })
+        .await
+    }
+
+    /// Searches items using a CQL query string and groups the results.
+    ///
+    /// Returns one entry per group with its total count and the first
+    /// `sample_size` items (by path). Returns an empty list for `GroupBy::None`.
+    /// Cancellation and timeout work as in [`search_cql`](Self::search_cql).
+    pub async fn search_cql_grouped(
+        &self,
+        query: &str,
+        group_by: GroupBy,
+        sample_size: usize,
+        request_id: Option<String>,
+        timeout: Option<Duration>,
+    ) -> Result<Vec<ResultGroupDto>, DomainError> {
+        let expr = parse_cql(query).map_err(|e| DomainError::ValidationError(e.to_string()))?;
+        let fragment = expr_to_sql(&expr);
+        let Some(source) = GroupSource::new(group_by) else {
+            return Ok(Vec::new());
+        };
+
+        self.run_interruptible(request_id, timeout, move |conn: &mut Connection| {
+            let sql = format!(
+                "SELECT id, path, is_directory, size, modified_time, created_at, updated_at, \
+                        tags_updated_at, rating, \
+                        group_key, group_count \
+                 FROM ( \
+                     SELECT i.id, i.path, i.is_directory, i.size, i.modified_time, \
+                            i.created_at, i.updated_at, i.tags_updated_at, i.rating, \
+                            {key} AS group_key, \
+                            ROW_NUMBER() OVER (PARTITION BY {key} ORDER BY i.path ASC) AS rn, \
+                            COUNT(*) OVER (PARTITION BY {key}) AS group_count \
+                     FROM items i {join} \
+                     WHERE i.is_deleted = 0 AND ({where_sql}) \
+                 ) \
+                 WHERE rn <= ? \
+                 ORDER BY group_key = '' ASC, group_key ASC, path ASC",
+                key = source.key,
+                join = source.join,
+                where_sql = fragment.sql
+            );
+
+            let mut stmt = conn.prepare(&sql)?;
+
+            let mut params: Vec<rusqlite::types::Value> = source.params;
+            params.extend(fragment.params);
+            params.push(rusqlite::types::Value::Integer(sample_size as i64));
+            let params_refs: Vec<&dyn rusqlite::ToSql> =
+                params.iter().map(|p| p as &dyn rusqlite::ToSql).collect();
+
+            let mut groups: Vec<ResultGroupDto> = Vec::new();
+            let mut rows = stmt.query(params_refs.as_slice())?;
+            while let Some(row) = rows.next()? {
+                let item = Self::map_row_to_item_dto(row)?;
+                let key: String = row.get(9)?;
+                let count: i64 = row.get(10)?;
+
+                match groups.last_mut() {
+                    Some(group) if group.key == key => group.sample_items.push(item),
+                    _ => groups.push(ResultGroupDto {
+                        key,
+                        count,
+                        sample_items: vec![item],
+                    }),
+                }
+            }
+
+            Ok::<Vec<ResultGroupDto>, rusqlite::Error>(groups)
+        })
+        .await
+    }
+
+    /// Returns a page of items from a single group of a grouped CQL search.
+    pub async fn search_cql_group_items(
+        &self,
+        query: &str,
+        group_by: GroupBy,
+        group_key: String,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<ItemDto>, DomainError> {
+        let expr = parse_cql(query).map_err(|e| DomainError::ValidationError(e.to_string()))?;
+        let fragment = expr_to_sql(&expr);
+        let Some(source) = GroupSource::new(group_by) else {
+            return Err(DomainError::ValidationError(
+                "A grouping must be specified to expand a group".to_string(),
+            ));
+        };
+
+        interact(&self.pool, move |conn: &mut Connection| {
+            let sql = format!(
+                "SELECT i.id, i.path, i.is_directory, i.size, i.modified_time, \
+                        i.created_at, i.updated_at, i.tags_updated_at, i.rating \
+                 FROM items i {join} \
+                 WHERE i.is_deleted = 0 AND ({where_sql}) AND {key} = ? \
+                 ORDER BY i.path ASC \
+                 LIMIT ? OFFSET ?",
+                key = source.key,
+                join = source.join,
+                where_sql = fragment.sql
+            );
+
+            let mut stmt = conn.prepare(&sql)?;
+
+            let mut params: Vec<rusqlite::types::Value> = source.params;
+            params.extend(fragment.params);
+            params.push(rusqlite::types::Value::Text(group_key));
+            params.push(rusqlite::types::Value::Integer(limit as i64));
+            params.push(rusqlite::types::Value::Integer(offset as i64));
+            let params_refs: Vec<&dyn rusqlite::ToSql> =
+                params.iter().map(|p| p as &dyn rusqlite::ToSql).collect();
+
+            let items = stmt
+                .query_map(params_refs.as_slice(), Self::map_row_to_item_dto)?
+                .collect::<Result<Vec<ItemDto>, _>>()?;
+
+            Ok::<Vec<ItemDto>, rusqlite::Error>(items)
+        })
+        .await
     }
 }
 
-fn map_pool_error(e: deadpool_sqlite::PoolError) -> DomainError {
-    DomainError::ValidationError(format!("Database pool error: {}", e))
+/// SQL pieces that derive a group key for each item row (alias `i`).
+struct GroupSource {
+    /// Extra JOIN clause (may be empty).
+    join: &'static str,
+    /// Expression producing the group key.
+    key: &'static str,
+    /// Parameters bound by `join`, which precede the WHERE parameters.
+    params: Vec<rusqlite::types::Value>,
 }
 
-fn map_interact_error(e: deadpool_sqlite::InteractError) -> DomainError {
-    DomainError::ValidationError(format!("Database interaction error: {}", e))
+impl GroupSource {
+    fn new(group_by: GroupBy) -> Option<Self> {
+        match group_by {
+            GroupBy::None => None,
+            GroupBy::ParentDir => Some(Self {
+                join: "",
+                key: "COALESCE(i.parent_dir, '')",
+                params: Vec::new(),
+            }),
+            // Items carrying several tags of the group appear once per tag;
+            // items without any tag of the group fall into the '' group.
+            GroupBy::TopTagGroup(group_id) => Some(Self {
+                join: "LEFT JOIN (SELECT it_g.item_id, t_g.value \
+                       FROM item_tags it_g \
+                       INNER JOIN tags t_g ON t_g.id = it_g.tag_id \
+                       WHERE t_g.group_id = ?) g ON g.item_id = i.id",
+                key: "COALESCE(g.value, '')",
+                params: vec![rusqlite::types::Value::Integer(group_id)],
+            }),
+        }
+    }
 }
 
-fn map_db_error(e: rusqlite::Error) -> DomainError {
-    DomainError::ValidationError(format!("Database error: {}", e))
+/// Builds the WHERE clause (with the deleted filter) and parameters for a filename search.
+///
+/// The deleted filter is written `+i.is_deleted` so the planner never prefers the
+/// low-selectivity `idx_items_is_deleted` over `idx_items_file_name`. The
+/// query is lowercased with ASCII rules only, matching SQLite's `LOWER`.
+fn filename_condition(query: &str, match_mode: FilenameMatchMode) -> (String, Vec<String>) {
+    match match_mode {
+        FilenameMatchMode::Contains => (
+            "+i.is_deleted = 0 AND i.path LIKE ?1".to_string(),
+            vec![format!("%{}%", query)],
+        ),
+        FilenameMatchMode::Prefix => {
+            let prefix = query.to_ascii_lowercase();
+            // Every string starting with `prefix` sorts in [prefix, prefix + U+10FFFF)
+            let upper = format!("{}{}", prefix, char::MAX);
+            (
+                format!(
+                    "+i.is_deleted = 0 AND {0} >= ?1 AND {0} < ?2",
+                    FILENAME_EXPR
+                ),
+                vec![prefix, upper],
+            )
+        }
+        FilenameMatchMode::Exact => (
+            format!("+i.is_deleted = 0 AND {} = ?1", FILENAME_EXPR),
+            vec![query.to_ascii_lowercase()],
+        ),
+    }
+}
+
+/// The statement a flat CQL search runs for a WHERE fragment from [`expr_to_sql`].
+fn cql_select_sql(condition: &str) -> String {
+    format!(
+        "SELECT DISTINCT i.id, i.path, i.is_directory, i.size, i.modified_time, \
+                i.created_at, i.updated_at, i.tags_updated_at, i.rating \
+         FROM items i \
+         WHERE i.is_deleted = 0 AND ({}) \
+         ORDER BY i.path ASC",
+        condition
+    )
+}
+
+/// The statement counting the items a CQL search would return.
+fn cql_count_sql(condition: &str) -> String {
+    format!(
+        "SELECT COUNT(DISTINCT i.id) FROM items i WHERE i.is_deleted = 0 AND ({})",
+        condition
+    )
+}
+
+/// The tag count SELECT column: a correlated subquery, or `NULL` when not wanted.
+///
+/// The subquery is an index lookup on `item_tags(item_id)` per result row.
+fn tag_count_column(include_tag_count: bool) -> &'static str {
+    if include_tag_count {
+        "(SELECT COUNT(*) FROM item_tags tc WHERE tc.item_id = i.id)"
+    } else {
+        "NULL"
+    }
+}
+
+/// Builds an ORDER BY clause from whitelisted columns on the `i` items alias.
+///
+/// NULL sizes/mtimes (directories, unscanned files) are coalesced to -1 so they
+/// sort first ascending and last descending. Path is appended as a tiebreaker
+/// to keep ordering stable.
+fn order_by_clause(sort: ItemSort) -> String {
+    let direction = match sort.direction {
+        SortDirection::Asc => "ASC",
+        SortDirection::Desc => "DESC",
+    };
+
+    match sort.field {
+        SortField::Path => format!("i.path {}", direction),
+        SortField::Size => format!("COALESCE(i.size, -1) {}, i.path ASC", direction),
+        SortField::Modified => format!("COALESCE(i.modified_time, -1) {}, i.path ASC", direction),
+        SortField::Created => format!("i.created_at {}, i.path ASC", direction),
+        // File name = everything after the last path separator
+        SortField::Name => format!(
+            "substr(i.path, length(rtrim(i.path, replace(replace(i.path, '\\', ''), '/', ''))) + 1) \
+             COLLATE NOCASE {}, i.path ASC",
+            direction
+        ),
+    }
+}
+
+fn is_interrupted(e: &rusqlite::Error) -> bool {
+    e.sqlite_error_code() == Some(ErrorCode::OperationInterrupted)
+}
+
+/// LIKE pattern matching paths that contain `token` as a subsequence
+/// (`rpt` becomes `%r%p%t%`), with `!` as the escape character. LIKE only
+/// folds ASCII case, so non-ASCII characters match any one character and the
+/// fuzzy scorer decides.
+fn subsequence_like(token: &str) -> String {
+    let mut pattern = String::from("%");
+    for ch in token.chars() {
+        match ch {
+            '!' | '%' | '_' => {
+                pattern.push('!');
+                pattern.push(ch);
+            }
+            _ if !ch.is_ascii() => pattern.push('_'),
+            _ => pattern.push(ch),
+        }
+        pattern.push('%');
+    }
+    pattern
+}
+
+/// Rolls back whatever an interrupted query left open and checks that the
+/// connection still answers queries.
+fn reset_interrupted(conn: &Connection) -> rusqlite::Result<()> {
+    if !conn.is_autocommit() {
+        conn.execute_batch("ROLLBACK")?;
+    }
+    conn.query_row("SELECT 1", [], |_| Ok(()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::persistence::cql_executor::RATING_EXPR;
+    use crate::infrastructure::persistence::open_database;
+
+    async fn setup() -> (tempfile::TempDir, SqliteSearchRepository) {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = open_database(&dir.path().join("test.db")).await.unwrap();
+        let conn = pool.get().await.unwrap();
+        conn.interact(|conn: &mut Connection| {
+            conn.execute_batch(
+                "INSERT INTO tag_groups (id, name) VALUES (1, 'Group');
+                 INSERT INTO tags (id, group_id, value) VALUES (1, 1, 'a'), (2, 1, 'b');
+                 INSERT INTO items (id, path, is_directory, size, modified_time) VALUES
+                    (1, 'C:\\z\\beta.txt', 0, 300, 10),
+                    (2, 'C:\\a\\Gamma.txt', 0, 100, 30),
+                    (3, 'C:\\m\\alpha', 1, NULL, NULL);
+                 INSERT INTO item_tags (item_id, tag_id) VALUES
+                    (1, 1), (1, 2), (2, 1), (2, 2), (3, 1), (3, 2);",
+            )
+        })
+        .await
+        .unwrap()
+        .unwrap();
+        (dir, SqliteSearchRepository::new(Arc::new(pool)))
+    }
+
+    async fn exec(repo: &SqliteSearchRepository, sql: &'static str) {
+        let conn = repo.pool.get().await.unwrap();
+        conn.interact(move |conn: &mut Connection| conn.execute_batch(sql))
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    fn ids(items: &[ItemDto]) -> Vec<i64> {
+        items.iter().map(|i| i.id).collect()
+    }
+
+    fn sort(field: SortField, direction: SortDirection) -> ItemSort {
+        ItemSort { field, direction }
+    }
+
+    #[tokio::test]
+    async fn default_sort_is_path_ascending() {
+        let (_dir, repo) = setup().await;
+        let items = repo
+            .search_by_tags_and(vec![1, 2], ItemSort::default(), false)
+            .await
+            .unwrap();
+        assert_eq!(ids(&items), vec![2, 3, 1]);
+    }
+
+    #[tokio::test]
+    async fn tag_counts_are_only_selected_when_requested() {
+        let (_dir, repo) = setup().await;
+        exec(
+            &repo,
+            "DELETE FROM item_tags WHERE item_id = 2 AND tag_id = 2",
+        )
+        .await;
+
+        let items = repo
+            .search_by_tags_or(vec![1], ItemSort::default(), true)
+            .await
+            .unwrap();
+        let counts: Vec<_> = items.iter().map(|i| (i.id, i.tag_count)).collect();
+        assert_eq!(counts, vec![(2, Some(1)), (3, Some(2)), (1, Some(2))]);
+
+        let items = repo
+            .search_by_tags_and(vec![1, 2], ItemSort::default(), true)
+            .await
+            .unwrap();
+        assert!(items.iter().all(|i| i.tag_count == Some(2)));
+
+        let items = repo
+            .search_by_tags_and(vec![1, 2], ItemSort::default(), false)
+            .await
+            .unwrap();
+        assert!(items.iter().all(|i| i.tag_count.is_none()));
+    }
+
+    #[tokio::test]
+    async fn sorts_by_size_with_nulls_first_ascending() {
+        let (_dir, repo) = setup().await;
+        let asc = repo
+            .search_by_tags_or(vec![1, 2], sort(SortField::Size, SortDirection::Asc), false)
+            .await
+            .unwrap();
+        assert_eq!(ids(&asc), vec![3, 2, 1]);
+
+        let desc = repo
+            .search_by_tags_or(
+                vec![1, 2],
+                sort(SortField::Size, SortDirection::Desc),
+                false,
+            )
+            .await
+            .unwrap();
+        assert_eq!(ids(&desc), vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn sorts_by_modified_descending() {
+        let (_dir, repo) = setup().await;
+        let items = repo
+            .search_by_tags_and(
+                vec![1],
+                sort(SortField::Modified, SortDirection::Desc),
+                false,
+            )
+            .await
+            .unwrap();
+        assert_eq!(ids(&items), vec![2, 1, 3]);
+    }
+
+    #[tokio::test]
+    async fn sorts_by_file_name_case_insensitively() {
+        let (_dir, repo) = setup().await;
+        let items = repo
+            .search_by_tags_or(vec![1], sort(SortField::Name, SortDirection::Asc), false)
+            .await
+            .unwrap();
+        assert_eq!(ids(&items), vec![3, 1, 2]);
+    }
+
+    #[tokio::test]
+    async fn items_by_tag_returns_exactly_the_tagged_items() {
+        let (_dir, repo) = setup().await;
+        exec(
+            &repo,
+            "INSERT INTO tags (id, group_id, value) VALUES (3, 1, 'c');
+             INSERT INTO items (id, path, is_directory, is_deleted) VALUES
+                (4, 'C:\\b\\deleted.txt', 0, 1);
+             INSERT INTO item_tags (item_id, tag_id) VALUES (1, 3), (2, 3), (4, 3);",
+        )
+        .await;
+
+        let items = repo.items_by_tag(3).await.unwrap();
+        assert_eq!(ids(&items), vec![2, 1]);
+        assert!(repo.items_by_tag(99).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn search_excluding_tags_combines_include_and_exclude() {
+        let (_dir, repo) = setup().await;
+        exec(
+            &repo,
+            "INSERT INTO tags (id, group_id, value) VALUES (3, 1, 'archived');
+             INSERT INTO items (id, path, is_directory, is_deleted) VALUES
+                (4, 'C:\\untagged.txt', 0, 0),
+                (5, 'C:\\deleted.txt', 0, 1);
+             DELETE FROM item_tags WHERE item_id = 3 AND tag_id = 2;
+             INSERT INTO item_tags (item_id, tag_id) VALUES (2, 3);",
+        )
+        .await;
+
+        let excluded = repo
+            .search_excluding_tags(vec![], vec![3], SearchMode::And)
+            .await
+            .unwrap();
+        assert_eq!(ids(&excluded), vec![3, 4, 1]);
+
+        let and = repo
+            .search_excluding_tags(vec![1, 2], vec![3], SearchMode::And)
+            .await
+            .unwrap();
+        assert_eq!(ids(&and), vec![1]);
+
+        let or = repo
+            .search_excluding_tags(vec![1, 2], vec![3], SearchMode::Or)
+            .await
+            .unwrap();
+        assert_eq!(ids(&or), vec![3, 1]);
+
+        let include_only = repo
+            .search_excluding_tags(vec![2], vec![], SearchMode::And)
+            .await
+            .unwrap();
+        assert_eq!(ids(&include_only), vec![2, 1]);
+    }
+
+    #[tokio::test]
+    async fn filename_match_modes() {
+        let (_dir, repo) = setup().await;
+        exec(
+            &repo,
+            "INSERT INTO items (id, path, is_directory) VALUES
+                (4, 'C:\\reports\\Summary.pdf', 0),
+                (5, 'C:\\docs\\Report-2024.pdf', 0),
+                (6, 'C:\\docs\\report', 0),
+                (7, 'C:\\docs\\old_report.txt', 0),
+                (8, 'C:\\docs\\Émile.txt', 0);",
+        )
+        .await;
+        let search = |mode| repo.search_by_filename("Report", mode, ItemSort::default());
+
+        // Contains matches anywhere in the path, including directory names
+        assert_eq!(
+            ids(&search(FilenameMatchMode::Contains).await.unwrap()),
+            vec![5, 7, 6, 4]
+        );
+        // Prefix and Exact only look at the file name, case-insensitively
+        assert_eq!(
+            ids(&search(FilenameMatchMode::Prefix).await.unwrap()),
+            vec![5, 6]
+        );
+        assert_eq!(
+            ids(&search(FilenameMatchMode::Exact).await.unwrap()),
+            vec![6]
+        );
+
+        // SQLite's LOWER leaves non-ASCII letters alone, and so does the query
+        for mode in [FilenameMatchMode::Prefix, FilenameMatchMode::Exact] {
+            let found = repo
+                .search_by_filename("ÉMILE.txt", mode, ItemSort::default())
+                .await
+                .unwrap();
+            assert_eq!(ids(&found), vec![8]);
+        }
+    }
+
+    #[tokio::test]
+    async fn prefix_and_exact_filename_search_use_the_file_name_index() {
+        let (_dir, repo) = setup().await;
+        let conn = repo.pool.get().await.unwrap();
+        let plans = conn
+            .interact(|conn: &mut Connection| {
+                [FilenameMatchMode::Prefix, FilenameMatchMode::Exact]
+                    .into_iter()
+                    .map(|mode| {
+                        let (condition, params) = filename_condition("report", mode);
+                        let sql = format!(
+                            "EXPLAIN QUERY PLAN SELECT i.id FROM items i WHERE {}",
+                            condition
+                        );
+                        let mut stmt = conn.prepare(&sql)?;
+                        let details = stmt
+                            .query_map(rusqlite::params_from_iter(params), |row| {
+                                row.get::<_, String>(3)
+                            })?
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Ok(details.join("; "))
+                    })
+                    .collect::<Result<Vec<String>, rusqlite::Error>>()
+            })
+            .await
+            .unwrap()
+            .unwrap();
+
+        for plan in plans {
+            assert!(plan.contains("idx_items_file_name"), "{}", plan);
+        }
+    }
+
+    #[test]
+    fn order_by_clause_uses_whitelisted_expressions() {
+        let cases = [
+            (SortField::Path, SortDirection::Asc, "i.path ASC"),
+            (SortField::Path, SortDirection::Desc, "i.path DESC"),
+            (
+                SortField::Modified,
+                SortDirection::Desc,
+                "COALESCE(i.modified_time, -1) DESC, i.path ASC",
+            ),
+            (
+                SortField::Size,
+                SortDirection::Desc,
+                "COALESCE(i.size, -1) DESC, i.path ASC",
+            ),
+            (
+                SortField::Created,
+                SortDirection::Desc,
+                "i.created_at DESC, i.path ASC",
+            ),
+        ];
+
+        for (field, direction, expected) in cases {
+            assert_eq!(order_by_clause(sort(field, direction)), expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn sorts_filename_and_combined_results_by_created() {
+        let (_dir, repo) = setup().await;
+        exec(
+            &repo,
+            "UPDATE items SET created_at = 100 WHERE id = 1;
+             UPDATE items SET created_at = 300 WHERE id = 2;
+             UPDATE items SET created_at = 200 WHERE id = 3;",
+        )
+        .await;
+        let newest_first = sort(SortField::Created, SortDirection::Desc);
+
+        let items = repo
+            .search_by_filename("C:", FilenameMatchMode::Contains, newest_first)
+            .await
+            .unwrap();
+        assert_eq!(ids(&items), vec![2, 3, 1]);
+
+        let items = repo
+            .search_combined(vec![1], SearchMode::Or, Some("a".to_string()), newest_first)
+            .await
+            .unwrap();
+        assert_eq!(ids(&items), vec![2, 3, 1]);
+    }
+
+    #[tokio::test]
+    async fn count_cql_batch_reports_errors_per_query() {
+        let (_dir, repo) = setup().await;
+        exec(&repo, "UPDATE items SET is_deleted = 1 WHERE id = 1").await;
+
+        let counts = repo
+            .count_cql_batch(vec![
+                r#"tag = "a""#.to_string(),
+                "tag =".to_string(),
+                r#"type = "directory""#.to_string(),
+                "group = 5".to_string(),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(counts[0], Ok(2));
+        assert!(counts[1].is_err());
+        assert_eq!(counts[2], Ok(1));
+        assert!(counts[3].is_err());
+    }
+
+    #[tokio::test]
+    async fn explain_cql_reports_the_items_scan() {
+        let (_dir, repo) = setup().await;
+
+        let plan = repo
+            .explain_cql(r#"tag = "a" AND name = "*.txt""#)
+            .await
+            .unwrap();
+        assert!(!plan.is_empty());
+        assert!(plan.iter().any(|row| row.contains("items")), "{:?}", plan);
+
+        assert!(matches!(
+            repo.explain_cql("tag =").await,
+            Err(DomainError::ValidationError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn benchmark_cql_reports_ordered_timings() {
+        let (_dir, repo) = setup().await;
+
+        let result = repo.benchmark_cql(r#"tag = "a""#, 5).await.unwrap();
+        let expected = repo.search_cql(r#"tag = "a""#, None, None).await.unwrap();
+        assert_eq!(result.iterations, 5);
+        assert_eq!(result.row_count, expected.len() as u64);
+        assert!(result.min_ms <= result.median_ms);
+        assert!(result.median_ms <= result.max_ms);
+
+        assert!(matches!(
+            repo.benchmark_cql("tag =", 5).await,
+            Err(DomainError::ValidationError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn search_cql_limited_reports_the_total_past_the_cap() {
+        let (_dir, repo) = setup().await;
+        let query = r#"tag = "a""#;
+
+        let (items, total) = repo.search_cql_limited(query, None, None, 2).await.unwrap();
+        assert_eq!(ids(&items), vec![2, 3]);
+        assert_eq!(total, Some(3));
+
+        let (items, total) = repo.search_cql_limited(query, None, None, 3).await.unwrap();
+        assert_eq!(ids(&items), vec![2, 3, 1]);
+        assert_eq!(total, None);
+    }
+
+    #[tokio::test]
+    async fn rebuild_indexes_refreshes_statistics() {
+        let (_dir, repo) = setup().await;
+
+        repo.rebuild_indexes().await.unwrap();
+
+        let conn = repo.pool.get().await.unwrap();
+        let stats: i64 = conn
+            .interact(|conn: &mut Connection| {
+                conn.query_row(
+                    "SELECT COUNT(*) FROM sqlite_stat1 WHERE tbl = 'items'",
+                    [],
+                    |row| row.get(0),
+                )
+            })
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(stats > 0);
+        // Still usable afterwards
+        assert_eq!(
+            ids(&repo.search_cql(r#"tag = "a""#, None, None).await.unwrap()),
+            vec![2, 3, 1]
+        );
+    }
+
+    #[tokio::test]
+    async fn library_stats_counts_fixture_data() {
+        let (_dir, repo) = setup().await;
+        exec(
+            &repo,
+            "INSERT INTO tag_groups (id, name) VALUES (2, 'Empty');
+             INSERT INTO tags (id, group_id, value) VALUES (3, 2, 'c');
+             INSERT INTO items (id, path, is_directory) VALUES (4, 'C:\\untagged.txt', 0);
+             INSERT INTO items (id, path, is_directory, is_deleted) VALUES
+                (5, 'C:\\gone.txt', 0, 1);
+             INSERT INTO item_tags (item_id, tag_id) VALUES (5, 1);",
+        )
+        .await;
+
+        let stats = repo.library_stats().await.unwrap();
+        assert_eq!(stats.total_items, 4);
+        assert_eq!(stats.tagged_items, 3);
+        assert_eq!(stats.untagged_items, 1);
+        assert_eq!(stats.deleted_items, 1);
+        assert_eq!(stats.total_tags, 3);
+        assert_eq!(stats.total_groups, 2);
+        assert!(stats.db_size_bytes > 0);
+    }
+
+    #[tokio::test]
+    async fn cql_rating_treats_unrated_as_zero() {
+        let (_dir, repo) = setup().await;
+        exec(
+            &repo,
+            "UPDATE items SET rating = 5 WHERE id = 1;
+             UPDATE items SET rating = 3 WHERE id = 2;",
+        )
+        .await;
+
+        let items = repo.search_cql("rating >= 4", None, None).await.unwrap();
+        assert_eq!(ids(&items), vec![1]);
+        assert_eq!(items[0].rating, Some(5));
+        let items = repo.search_cql("rating < 4", None, None).await.unwrap();
+        assert_eq!(ids(&items), vec![2, 3]);
+    }
+
+    #[tokio::test]
+    async fn cql_anytag_matches_directories_by_tagged_descendants() {
+        let (_dir, repo) = setup().await;
+        exec(
+            &repo,
+            "INSERT INTO tags (id, group_id, value) VALUES (3, 1, 'sunset');
+             INSERT INTO items (id, path, is_directory) VALUES
+                (4, 'C:\\photos', 1),
+                (5, 'C:\\photos\\2024\\beach.jpg', 0),
+                (6, 'C:\\photos2', 1),
+                (7, 'C:\\photos2\\gone.jpg', 0),
+                (8, 'C:\\photos.jpg', 0);
+             INSERT INTO item_tags (item_id, tag_id) VALUES (5, 3), (7, 3), (8, 3);
+             UPDATE items SET is_deleted = 1 WHERE id = 7;",
+        )
+        .await;
+
+        let cql = |q: &'static str| {
+            let repo = &repo;
+            async move { ids(&repo.search_cql(q, None, None).await.unwrap()) }
+        };
+        // Sibling paths sharing the prefix and deleted descendants do not count
+        assert_eq!(cql(r#"anytag = "sunset""#).await, vec![4, 8, 5]);
+        assert_eq!(cql(r#"anytag ~ "sun*""#).await, vec![4, 8, 5]);
+        assert_eq!(cql(r#"anytag IN ("sunset")"#).await, vec![4, 8, 5]);
+        assert_eq!(cql(r#"tag = "sunset""#).await, vec![8, 5]);
+        assert!(!cql(r#"anytag != "sunset""#).await.contains(&4));
+    }
+
+    #[tokio::test]
+    async fn type_in_does_not_match_directories_by_extension() {
+        let (_dir, repo) = setup().await;
+        exec(
+            &repo,
+            "INSERT INTO items (id, path, is_directory) VALUES
+                (4, 'C:\\clips\\movie.mp4', 1),
+                (5, 'C:\\v\\clip.mp4', 0);",
+        )
+        .await;
+
+        let cql = |q: &'static str| {
+            let repo = &repo;
+            async move { ids(&repo.search_cql(q, None, None).await.unwrap()) }
+        };
+        assert_eq!(cql(r#"type IN ("video")"#).await, vec![5]);
+        assert_eq!(cql(r#"type = "video""#).await, vec![5]);
+        assert_eq!(
+            cql(r#"type IN ("video", "directory")"#).await,
+            vec![4, 3, 5]
+        );
+    }
+
+    #[tokio::test]
+    async fn similar_items_rank_by_shared_tag_count() {
+        let (_dir, repo) = setup().await;
+        exec(
+            &repo,
+            "INSERT INTO tags (id, group_id, value) VALUES (3, 1, 'c');
+             INSERT INTO items (id, path, is_directory) VALUES (4, 'C:\\gone.txt', 0);
+             INSERT INTO items (id, path, is_directory) VALUES (5, 'C:\\one.txt', 0);
+             UPDATE items SET is_deleted = 1 WHERE id = 4;
+             INSERT INTO item_tags (item_id, tag_id) VALUES
+                (1, 3), (2, 3), (4, 1), (4, 2), (4, 3), (5, 1);",
+        )
+        .await;
+
+        let similar = |id, min| {
+            let repo = &repo;
+            async move {
+                repo.similar_items(id, min)
+                    .await
+                    .unwrap()
+                    .iter()
+                    .map(|s| (s.item.id, s.shared_tags))
+                    .collect::<Vec<_>>()
+            }
+        };
+        assert_eq!(similar(1, 1).await, vec![(2, 3), (3, 2), (5, 1)]);
+        assert_eq!(similar(1, 3).await, vec![(2, 3)]);
+        // Ties fall back to path order
+        assert_eq!(similar(5, 1).await, vec![(2, 1), (3, 1), (1, 1)]);
+        assert!(similar(99, 1).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn time_window_includes_the_boundary_second() {
+        let (_dir, repo) = setup().await;
+        exec(
+            &repo,
+            "INSERT INTO items (id, path, is_directory, is_deleted) VALUES
+                (4, 'C:\\gone.txt', 0, 1);
+             INSERT INTO item_tags (item_id, tag_id) VALUES (4, 1);
+             DELETE FROM item_tags WHERE item_id = 2 AND tag_id = 2;
+             UPDATE items SET created_at = 0;",
+        )
+        .await;
+
+        let now = || async {
+            let conn = repo.pool.get().await.unwrap();
+            conn.interact(|conn: &mut Connection| {
+                conn.query_row("SELECT unixepoch()", [], |row| row.get::<_, i64>(0))
+            })
+            .await
+            .unwrap()
+            .unwrap()
+        };
+
+        // Retried if the clock ticks between setup and query, which would move the boundary
+        for _ in 0..3 {
+            let before = now().await;
+            exec(
+                &repo,
+                "UPDATE items SET modified_time = unixepoch() - 3600 WHERE id IN (1, 4);
+                 UPDATE items SET modified_time = unixepoch() - 3601 WHERE id = 2;
+                 UPDATE items SET created_at = unixepoch() - 60 WHERE id = 2;",
+            )
+            .await;
+            let modified = repo
+                .search_by_time_window(1, TimeField::Modified, Vec::new())
+                .await
+                .unwrap();
+            let tagged = repo
+                .search_by_time_window(1, TimeField::Modified, vec![1, 2])
+                .await
+                .unwrap();
+            let created = repo
+                .search_by_time_window(1, TimeField::Created, Vec::new())
+                .await
+                .unwrap();
+            if now().await != before {
+                continue;
+            }
+
+            // Item 3 has no modified time, item 4 is deleted
+            assert_eq!(ids(&modified), vec![1]);
+            assert_eq!(ids(&tagged), vec![1]);
+            assert_eq!(ids(&created), vec![2]);
+            assert_eq!(
+                ids(&repo
+                    .search_by_time_window(2, TimeField::Modified, vec![1])
+                    .await
+                    .unwrap()),
+                vec![1, 2]
+            );
+            return;
+        }
+        panic!("clock kept ticking during the test");
+    }
+
+    #[tokio::test]
+    async fn co_tagged_items_list_the_shared_tag_ids() {
+        let (_dir, repo) = setup().await;
+        exec(
+            &repo,
+            "INSERT INTO tags (id, group_id, value) VALUES (3, 1, 'c');
+             INSERT INTO items (id, path, is_directory, is_deleted) VALUES
+                (4, 'C:\\gone.txt', 0, 1);
+             INSERT INTO item_tags (item_id, tag_id) VALUES (1, 3), (4, 1);
+             DELETE FROM item_tags WHERE item_id = 3 AND tag_id = 2;",
+        )
+        .await;
+
+        let mut items: Vec<(i64, Vec<i64>)> = repo
+            .co_tagged_items(1)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(item, mut tag_ids)| {
+                tag_ids.sort();
+                (item.id, tag_ids)
+            })
+            .collect();
+        items.sort();
+        assert_eq!(items, vec![(2, vec![1, 2]), (3, vec![1])]);
+        assert!(repo.co_tagged_items(99).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn cql_group_matches_items_by_group_membership() {
+        let (_dir, repo) = setup().await;
+        exec(
+            &repo,
+            "INSERT INTO tag_groups (id, name) VALUES (2, 'Status');
+             INSERT INTO tags (id, group_id, value) VALUES (3, 2, 'done');
+             INSERT INTO item_tags (item_id, tag_id) VALUES (1, 3);",
+        )
+        .await;
+
+        let search = |q: &'static str| {
+            let repo = &repo;
+            async move { ids(&repo.search_cql(q, None, None).await.unwrap()) }
+        };
+        assert_eq!(search(r#"group = "Status""#).await, vec![1]);
+        assert_eq!(search(r#"group != "Status""#).await, vec![2, 3]);
+        assert_eq!(
+            search(r#"group IN ("Status", "Group")"#).await,
+            vec![2, 3, 1]
+        );
+    }
+
+    #[tokio::test]
+    async fn rating_comparisons_can_use_the_rating_index() {
+        let (_dir, repo) = setup().await;
+        let conn = repo.pool.get().await.unwrap();
+        let plan = conn
+            .interact(|conn: &mut Connection| {
+                let sql = format!(
+                    "EXPLAIN QUERY PLAN SELECT i.id FROM items i WHERE {} >= ?",
+                    RATING_EXPR
+                );
+                let mut stmt = conn.prepare(&sql)?;
+                let details = stmt
+                    .query_map([4], |row| row.get::<_, String>(3))?
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok::<String, rusqlite::Error>(details.join("; "))
+            })
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(plan.contains("idx_items_rating"), "{}", plan);
+    }
+
+    #[tokio::test]
+    async fn cql_tagged_matches_recently_tagged_items() {
+        let (_dir, repo) = setup().await;
+        // 2024-08-01 and 2024-10-01; item 3 was never tagged
+        exec(
+            &repo,
+            "UPDATE items SET tags_updated_at = 1722470400 WHERE id = 1;
+             UPDATE items SET tags_updated_at = 1727740800 WHERE id = 2;
+             UPDATE items SET tags_updated_at = NULL WHERE id = 3;",
+        )
+        .await;
+
+        let items = repo
+            .search_cql(r#"tagged > "2024-09-01""#, None, None)
+            .await
+            .unwrap();
+        assert_eq!(ids(&items), vec![2]);
+        assert_eq!(items[0].tags_updated_at, Some(1727740800));
+
+        let items = repo
+            .search_cql(r#"tagged < "2024-09-01""#, None, None)
+            .await
+            .unwrap();
+        assert_eq!(ids(&items), vec![1]);
+    }
+
+    #[tokio::test]
+    async fn fuzzy_filename_search_ranks_closer_matches_first() {
+        let (_dir, repo) = setup().await;
+        exec(
+            &repo,
+            "INSERT INTO items (id, path, is_directory) VALUES
+                (10, 'C:\\docs\\report_final_v2.pdf', 0),
+                (11, 'C:\\docs\\report.pdf', 0),
+                (12, 'C:\\report\\notes.txt', 0),
+                (13, 'C:\\docs\\summary.pdf', 0);",
+        )
+        .await;
+
+        let items = repo.search_filename_fuzzy("report").await.unwrap();
+        assert_eq!(ids(&items), vec![11, 10, 12]);
+
+        let items = repo.search_filename_fuzzy("rep pdf").await.unwrap();
+        assert_eq!(ids(&items), vec![11, 10]);
+
+        // Abbreviations are not substrings but still reach the scorer
+        let items = repo.search_filename_fuzzy("rprt").await.unwrap();
+        assert_eq!(ids(&items), vec![11, 10, 12]);
+    }
+
+    #[test]
+    fn subsequence_pattern_escapes_wildcards() {
+        assert_eq!(subsequence_like("rpt"), "%r%p%t%");
+        assert_eq!(subsequence_like("a_%!"), "%a%!_%!%%!!%");
+        assert_eq!(subsequence_like("é1"), "%_%1%");
+    }
+
+    #[tokio::test]
+    async fn groups_by_parent_dir_with_counts_and_samples() {
+        let (_dir, repo) = setup().await;
+        exec(
+            &repo,
+            "INSERT INTO items (id, path, is_directory) VALUES (4, 'C:\\a\\delta.txt', 0);
+             INSERT INTO item_tags (item_id, tag_id) VALUES (4, 1);",
+        )
+        .await;
+
+        let groups = repo
+            .search_cql_grouped("tag = \"a\"", GroupBy::ParentDir, 1, None, None)
+            .await
+            .unwrap();
+
+        let keys: Vec<&str> = groups.iter().map(|g| g.key.as_str()).collect();
+        assert_eq!(keys, vec!["C:\\a", "C:\\m", "C:\\z"]);
+        assert_eq!(groups[0].count, 2);
+        assert_eq!(ids(&groups[0].sample_items), vec![2]);
+    }
+
+    #[tokio::test]
+    async fn groups_by_tag_group_with_untagged_last() {
+        let (_dir, repo) = setup().await;
+        exec(
+            &repo,
+            "INSERT INTO tag_groups (id, name) VALUES (2, 'Other');
+             INSERT INTO tags (id, group_id, value) VALUES (3, 2, 'x');
+             INSERT INTO item_tags (item_id, tag_id) VALUES (1, 3);",
+        )
+        .await;
+
+        let groups = repo
+            .search_cql_grouped("tag = \"a\"", GroupBy::TopTagGroup(2), 5, None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].key, "x");
+        assert_eq!(ids(&groups[0].sample_items), vec![1]);
+        assert_eq!(groups[1].key, "");
+        assert_eq!(groups[1].count, 2);
+        assert_eq!(ids(&groups[1].sample_items), vec![2, 3]);
+    }
+
+    #[tokio::test]
+    async fn expands_group_with_offset_and_limit() {
+        let (_dir, repo) = setup().await;
+        exec(
+            &repo,
+            "INSERT INTO items (id, path, is_directory) VALUES (4, 'C:\\a\\delta.txt', 0);
+             INSERT INTO item_tags (item_id, tag_id) VALUES (4, 1);",
+        )
+        .await;
+
+        let items = repo
+            .search_cql_group_items(
+                "tag = \"a\"",
+                GroupBy::ParentDir,
+                "C:\\a".to_string(),
+                1,
+                10,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(ids(&items), vec![4]);
+    }
+
+    /// Never terminates on its own; only an interrupt stops it.
+    const ENDLESS_QUERY: &str = "WITH RECURSIVE c(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM c) \
+                                 SELECT COUNT(*) FROM c";
+
+    /// Repository over a single-connection pool so follow-up queries reuse
+    /// the interrupted connection.
+    async fn single_connection_repo() -> (tempfile::TempDir, Arc<SqliteSearchRepository>) {
+        pooled_repo(1).await
+    }
+
+    async fn pooled_repo(max_size: usize) -> (tempfile::TempDir, Arc<SqliteSearchRepository>) {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = deadpool_sqlite::Config::new(dir.path().join("test.db"))
+            .builder(deadpool_sqlite::Runtime::Tokio1)
+            .unwrap()
+            .max_size(max_size)
+            .build()
+            .unwrap();
+        (dir, Arc::new(SqliteSearchRepository::new(Arc::new(pool))))
+    }
+
+    async fn endless(
+        repo: &SqliteSearchRepository,
+        request_id: Option<String>,
+        timeout: Option<Duration>,
+    ) -> Result<i64, DomainError> {
+        repo.run_interruptible(request_id, timeout, |conn: &mut Connection| {
+            conn.query_row(ENDLESS_QUERY, [], |row| row.get(0))
+        })
+        .await
+    }
+
+    async fn assert_connection_reusable(repo: &SqliteSearchRepository) {
+        let value: i64 = repo
+            .run_interruptible(None, None, |conn: &mut Connection| {
+                conn.query_row("SELECT 42", [], |row| row.get(0))
+            })
+            .await
+            .unwrap();
+        assert_eq!(value, 42);
+    }
+
+    #[tokio::test]
+    async fn slow_query_times_out() {
+        let (_dir, repo) = single_connection_repo().await;
+
+        let result = endless(&repo, None, Some(Duration::from_millis(100))).await;
+
+        assert!(matches!(result, Err(DomainError::QueryTimeout(_))));
+        assert!(repo.running.is_empty());
+        assert_connection_reusable(&repo).await;
+    }
+
+    #[tokio::test]
+    async fn slow_query_can_be_cancelled() {
+        let (_dir, repo) = single_connection_repo().await;
+
+        let task = {
+            let repo = repo.clone();
+            tokio::spawn(async move { endless(&repo, Some("job-1".to_string()), None).await })
+        };
+
+        // Wait until the query has registered its interrupt handle
+        while !repo.running.contains_key("job-1") {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        assert!(repo.cancel("job-1"));
+
+        let result = task.await.unwrap();
+        assert!(matches!(result, Err(DomainError::QueryCancelled(_))));
+        assert!(!repo.cancel("job-1"));
+        assert_connection_reusable(&repo).await;
+    }
+
+    #[tokio::test]
+    async fn running_request_id_is_not_reused() {
+        let (_dir, repo) = pooled_repo(2).await;
+
+        let task = {
+            let repo = repo.clone();
+            tokio::spawn(async move { endless(&repo, Some("job-1".to_string()), None).await })
+        };
+        while !repo.running.contains_key("job-1") {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+
+        let duplicate = endless(&repo, Some("job-1".to_string()), None).await;
+        assert!(matches!(duplicate, Err(DomainError::ValidationError(_))));
+
+        // The first query is still the one registered, and still cancellable
+        assert!(repo.cancel("job-1"));
+        let result = task.await.unwrap();
+        assert!(matches!(result, Err(DomainError::QueryCancelled(_))));
+    }
 }
@@ -2,8 +2,11 @@
 //!
 //! Implementation of SettingsRepository for SQLite.
 
+use crate::domain::entities::ONBOARDING_SEEDED_KEY;
 use crate::domain::errors::DomainError;
 use crate::domain::repositories::SettingsRepository;
+use crate::infrastructure::persistence::interact::interact;
+use crate::infrastructure::persistence::schema::seed_default_data;
 use async_trait::async_trait;
 use deadpool_sqlite::Pool;
 use rusqlite::Connection;
@@ -23,10 +26,9 @@ impl SqliteSettingsRepository {
 #[async_trait]
 impl SettingsRepository for SqliteSettingsRepository {
     async fn get(&self, key: &str) -> Result<Option<String>, DomainError> {
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
         let key = key.to_string();
 
-        conn.interact(move |conn: &mut Connection| {
+        interact(&self.pool, move |conn: &mut Connection| {
             let result = conn
                 .query_row("SELECT value FROM settings WHERE key = ?1", [&key], |row| {
                     row.get::<_, String>(0)
@@ -38,14 +40,10 @@ impl SettingsRepository for SqliteSettingsRepository {
             }
         })
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
     }
 
     async fn get_all(&self) -> Result<Vec<(String, String)>, DomainError> {
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
-
-        conn.interact(move |conn: &mut Connection| {
+        interact(&self.pool, move |conn: &mut Connection| {
             let mut stmt = conn.prepare("SELECT key, value FROM settings")?;
             let pairs = stmt
                 .query_map([], |row| {
@@ -55,16 +53,13 @@ impl SettingsRepository for SqliteSettingsRepository {
             Ok::<Vec<(String, String)>, rusqlite::Error>(pairs)
         })
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
     }
 
     async fn set(&self, key: &str, value: &str) -> Result<(), DomainError> {
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
         let key = key.to_string();
         let value = value.to_string();
 
-        conn.interact(move |conn: &mut Connection| {
+        interact(&self.pool, move |conn: &mut Connection| {
             conn.execute(
                 "INSERT INTO settings (key, value) VALUES (?1, ?2)
                  ON CONFLICT(key) DO UPDATE SET value = excluded.value",
@@ -73,34 +68,48 @@ impl SettingsRepository for SqliteSettingsRepository {
             Ok::<(), rusqlite::Error>(())
         })
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
     }
 
     async fn delete(&self, key: &str) -> Result<(), DomainError> {
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
         let key = key.to_string();
 
-        conn.interact(move |conn: &mut Connection| {
+        interact(&self.pool, move |conn: &mut Connection| {
             conn.execute("DELETE FROM settings WHERE key = ?1", [&key])?;
             Ok::<(), rusqlite::Error>(())
         })
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
     }
-}
 
-use rusqlite::OptionalExtension;
+    async fn delete_many(&self, keys: &[String]) -> Result<(), DomainError> {
+        if keys.is_empty() {
+            return Ok(());
+        }
+        let keys = keys.to_vec();
 
-fn map_pool_error(e: deadpool_sqlite::PoolError) -> DomainError {
-    DomainError::ValidationError(format!("Database pool error: {}", e))
-}
+        interact(&self.pool, move |conn: &mut Connection| {
+            let tx = conn.transaction()?;
+            {
+                let mut stmt = tx.prepare("DELETE FROM settings WHERE key = ?1")?;
+                for key in &keys {
+                    stmt.execute([key])?;
+                }
+            }
+            tx.commit()?;
+            Ok::<(), rusqlite::Error>(())
+        })
+        .await
+    }
 
-fn map_interact_error(e: deadpool_sqlite::InteractError) -> DomainError {
-    DomainError::ValidationError(format!("Database interaction error: {}", e))
+    async fn reset_onboarding(&self) -> Result<bool, DomainError> {
+        interact(&self.pool, |conn: &mut Connection| {
+            conn.execute(
+                "DELETE FROM settings WHERE key = ?1",
+                [ONBOARDING_SEEDED_KEY],
+            )?;
+            seed_default_data(conn)
+        })
+        .await
+    }
 }
 
-fn map_db_error(e: rusqlite::Error) -> DomainError {
-    DomainError::DatabaseError(format!("Database error: {}", e))
-}
+use rusqlite::OptionalExtension;
@@ -2,7 +2,8 @@
 //!
 //! Implementation of TagGroupRepository for SQLite.
 
-use crate::domain::entities::TagGroup;
+use super::interact::{interact, interact_write_with, not_found};
+use crate::domain::entities::{TagGroup, TagGroupSortMode};
 use crate::domain::errors::DomainError;
 use crate::domain::repositories::TagGroupRepository;
 use crate::domain::value_objects::Color;
@@ -30,6 +31,7 @@ impl SqliteTagGroupRepository {
             row.get(1)?,
             color,
             row.get(3)?,
+            row.get(6)?,
             row.get(4)?,
             row.get(5)?,
         ))
@@ -39,35 +41,32 @@ impl SqliteTagGroupRepository {
 #[async_trait]
 impl TagGroupRepository for SqliteTagGroupRepository {
     async fn save(&self, group: &mut TagGroup) -> Result<i64, DomainError> {
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
-
         let name = group.name().to_string();
         let color = group.color().map(|c| c.to_string());
         let display_order = group.display_order();
+        let exclusive = group.is_exclusive();
 
-        let id = conn
-            .interact(move |conn: &mut Connection| {
+        let id = interact(
+            &self.pool,
+            move |conn: &mut Connection| {
                 conn.execute(
-                    "INSERT INTO tag_groups (name, color, display_order) VALUES (?1, ?2, ?3)",
-                    (&name, &color, display_order),
+                    "INSERT INTO tag_groups (name, color, display_order, exclusive) VALUES (?1, ?2, ?3, ?4)",
+                    (&name, &color, display_order, exclusive),
                 )?;
                 Ok::<i64, rusqlite::Error>(conn.last_insert_rowid())
-            })
-            .await
-            .map_err(map_interact_error)?
-            .map_err(map_db_error)?;
+            },
+        )
+        .await?;
 
         group.set_id(id);
         Ok(id)
     }
 
     async fn find_by_id(&self, id: i64) -> Result<Option<TagGroup>, DomainError> {
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
-
-        conn.interact(move |conn: &mut Connection| {
+        interact(&self.pool, move |conn: &mut Connection| {
             let result = conn
                 .query_row(
-                    "SELECT id, name, color, display_order, created_at, updated_at
+                    "SELECT id, name, color, display_order, created_at, updated_at, exclusive
                      FROM tag_groups WHERE id = ?1",
                     [id],
                     Self::map_row_to_group,
@@ -79,28 +78,39 @@ impl TagGroupRepository for SqliteTagGroupRepository {
             }
         })
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
     }
 
-    async fn find_all(&self) -> Result<Vec<TagGroup>, DomainError> {
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
-
-        conn.interact(move |conn: &mut Connection| {
-            let mut stmt = conn.prepare(
-                "SELECT id, name, color, display_order, created_at, updated_at
-                 FROM tag_groups ORDER BY display_order ASC",
-            )?;
-
-            let groups = stmt
-                .query_map([], Self::map_row_to_group)?
-                .collect::<Result<Vec<TagGroup>, _>>()?;
-
-            Ok::<Vec<TagGroup>, rusqlite::Error>(groups)
-        })
+    async fn find_all(&self, sort_mode: TagGroupSortMode) -> Result<Vec<TagGroup>, DomainError> {
+        interact(
+            &self.pool,
+            move |conn: &mut Connection| {
+                let sql = match sort_mode {
+                    TagGroupSortMode::DisplayOrder => {
+                        "SELECT id, name, color, display_order, created_at, updated_at, exclusive
+                         FROM tag_groups ORDER BY display_order ASC"
+                    }
+                    TagGroupSortMode::Alphabetical => {
+                        "SELECT id, name, color, display_order, created_at, updated_at, exclusive
+                         FROM tag_groups ORDER BY name COLLATE NOCASE ASC, id ASC"
+                    }
+                    TagGroupSortMode::ByTagCount => {
+                        "SELECT g.id, g.name, g.color, g.display_order, g.created_at, g.updated_at, g.exclusive
+                         FROM tag_groups g
+                         LEFT JOIN tags t ON t.group_id = g.id
+                         GROUP BY g.id
+                         ORDER BY COUNT(t.id) DESC, g.display_order ASC"
+                    }
+                };
+                let mut stmt = conn.prepare(sql)?;
+
+                let groups = stmt
+                    .query_map([], Self::map_row_to_group)?
+                    .collect::<Result<Vec<TagGroup>, _>>()?;
+
+                Ok::<Vec<TagGroup>, rusqlite::Error>(groups)
+            },
+        )
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
     }
 
     async fn update(&self, group: &TagGroup) -> Result<(), DomainError> {
@@ -108,61 +118,57 @@ impl TagGroupRepository for SqliteTagGroupRepository {
             DomainError::ValidationError("Cannot update group without ID".to_string())
         })?;
 
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
-
         let name = group.name().to_string();
         let color = group.color().map(|c| c.to_string());
         let display_order = group.display_order();
+        let exclusive = group.is_exclusive();
+
+        interact_write_with(
+            &self.pool,
+            move |conn: &mut Connection| {
+                conn.execute("BEGIN IMMEDIATE", [])?;
+
+                let result = (|| {
+                    let exists: bool = conn.query_row(
+                        "SELECT COUNT(*) FROM tag_groups WHERE id = ?1",
+                        [id],
+                        |row| row.get::<_, i64>(0).map(|count| count > 0),
+                    )?;
 
-        conn.interact(move |conn: &mut Connection| {
-            conn.execute("BEGIN IMMEDIATE", [])?;
-
-            let result = (|| {
-                let exists: bool = conn.query_row(
-                    "SELECT COUNT(*) FROM tag_groups WHERE id = ?1",
-                    [id],
-                    |row| row.get::<_, i64>(0).map(|count| count > 0),
-                )?;
-
-                if !exists {
-                    return Err(rusqlite::Error::QueryReturnedNoRows);
-                }
-
-                conn.execute(
-                    "UPDATE tag_groups SET name = ?1, color = ?2, display_order = ?3, updated_at = unixepoch() WHERE id = ?4",
-                    (&name, &color, display_order, id),
-                )?;
+                    if !exists {
+                        return Err(rusqlite::Error::QueryReturnedNoRows);
+                    }
 
-                Ok::<(), rusqlite::Error>(())
-            })();
+                    conn.execute(
+                        "UPDATE tag_groups SET name = ?1, color = ?2, display_order = ?3, exclusive = ?4, updated_at = unixepoch() WHERE id = ?5",
+                        (&name, &color, display_order, exclusive, id),
+                    )?;
 
-            match result {
-                Ok(_) => {
-                    conn.execute("COMMIT", [])?;
-                    Ok(())
+                    Ok::<(), rusqlite::Error>(())
+                })();
+
+                match result {
+                    Ok(_) => {
+                        conn.execute("COMMIT", [])?;
+                        Ok(())
+                    }
+                    Err(e) => {
+                        conn.execute("ROLLBACK", [])?;
+                        Err(e)
+                    }
                 }
-                Err(e) => {
-                    conn.execute("ROLLBACK", [])?;
-                    Err(e)
-                }
-            }
-        })
+            },
+            not_found(|| DomainError::TagGroupNotFound("Tag group not found".to_string())),
+        )
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
     }
 
     async fn delete(&self, id: i64) -> Result<(), DomainError> {
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
-
-        let deleted = conn
-            .interact(move |conn: &mut Connection| {
-                let rows = conn.execute("DELETE FROM tag_groups WHERE id = ?1", [id])?;
-                Ok::<usize, rusqlite::Error>(rows)
-            })
-            .await
-            .map_err(map_interact_error)?
-            .map_err(map_db_error)?;
+        let deleted = interact(&self.pool, move |conn: &mut Connection| {
+            let rows = conn.execute("DELETE FROM tag_groups WHERE id = ?1", [id])?;
+            Ok::<usize, rusqlite::Error>(rows)
+        })
+        .await?;
 
         if deleted == 0 {
             return Err(DomainError::TagGroupNotFound(id.to_string()));
@@ -172,41 +178,70 @@ impl TagGroupRepository for SqliteTagGroupRepository {
     }
 
     async fn reorder(&self, orders: Vec<(i64, i32)>) -> Result<(), DomainError> {
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
-
-        conn.interact(move |conn: &mut Connection| {
-            conn.execute("BEGIN IMMEDIATE", [])?;
+        interact_write_with(
+            &self.pool,
+            move |conn: &mut Connection| {
+                conn.execute("BEGIN IMMEDIATE", [])?;
+
+                let result = (|| {
+                    for (id, order) in orders {
+                        conn.execute(
+                            "UPDATE tag_groups SET display_order = ?1, updated_at = unixepoch() WHERE id = ?2",
+                            (order, id),
+                        )?;
+                    }
+                    Ok::<(), rusqlite::Error>(())
+                })();
+
+                match result {
+                    Ok(_) => {
+                        conn.execute("COMMIT", [])?;
+                        Ok(())
+                    }
+                    Err(e) => {
+                        conn.execute("ROLLBACK", [])?;
+                        Err(e)
+                    }
+                }
+            },
+            DomainError::from,
+        )
+        .await
+    }
 
-            let result = (|| {
-                for (id, order) in orders {
-                    conn.execute(
-                        "UPDATE tag_groups SET display_order = ?1, updated_at = unixepoch() WHERE id = ?2",
-                        (order, id),
+    async fn normalize_order(&self) -> Result<usize, DomainError> {
+        interact_write_with(
+            &self.pool,
+            move |conn: &mut Connection| {
+                // On error `interact_write_with` rolls the transaction back
+                conn.execute("BEGIN IMMEDIATE", [])?;
+
+                let ids = conn
+                    .prepare(
+                        "SELECT id FROM tag_groups ORDER BY display_order ASC, name ASC, id ASC",
+                    )?
+                    .query_map([], |row| row.get::<_, i64>(0))?
+                    .collect::<Result<Vec<i64>, _>>()?;
+
+                let mut changed = 0;
+                for (order, id) in ids.into_iter().enumerate() {
+                    changed += conn.execute(
+                        "UPDATE tag_groups SET display_order = ?1, updated_at = unixepoch()
+                         WHERE id = ?2 AND display_order != ?1",
+                        (order as i32, id),
                     )?;
                 }
-                Ok::<(), rusqlite::Error>(())
-            })();
 
-            match result {
-                Ok(_) => {
-                    conn.execute("COMMIT", [])?;
-                    Ok(())
-                }
-                Err(e) => {
-                    conn.execute("ROLLBACK", [])?;
-                    Err(e)
-                }
-            }
-        })
+                conn.execute("COMMIT", [])?;
+                Ok(changed)
+            },
+            DomainError::from,
+        )
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
     }
 
     async fn exists(&self, id: i64) -> Result<bool, DomainError> {
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
-
-        conn.interact(move |conn: &mut Connection| {
+        interact(&self.pool, move |conn: &mut Connection| {
             let exists: bool = conn.query_row(
                 "SELECT COUNT(*) FROM tag_groups WHERE id = ?1",
                 [id],
@@ -215,26 +250,141 @@ impl TagGroupRepository for SqliteTagGroupRepository {
             Ok::<bool, rusqlite::Error>(exists)
         })
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
+    }
+
+    async fn find_exclusive_violations(
+        &self,
+        group_id: i64,
+    ) -> Result<Vec<(i64, String, Vec<i64>)>, DomainError> {
+        interact(&self.pool, move |conn: &mut Connection| {
+            let mut stmt = conn.prepare(
+                "SELECT i.id, i.path, GROUP_CONCAT(it.tag_id)
+                 FROM item_tags it
+                 INNER JOIN tags t ON t.id = it.tag_id
+                 INNER JOIN items i ON i.id = it.item_id
+                 WHERE t.group_id = ?1 AND i.is_deleted = 0
+                 GROUP BY i.id
+                 HAVING COUNT(*) > 1
+                 ORDER BY i.path",
+            )?;
+            let rows = stmt.query_map([group_id], |row| {
+                let tag_ids: String = row.get(2)?;
+                let mut tag_ids: Vec<i64> = tag_ids
+                    .split(',')
+                    .filter_map(|id| id.parse().ok())
+                    .collect();
+                tag_ids.sort_unstable();
+                Ok((row.get(0)?, row.get(1)?, tag_ids))
+            })?;
+            rows.collect::<Result<Vec<_>, _>>()
+        })
+        .await
     }
 }
 
 use rusqlite::OptionalExtension;
 
-fn map_pool_error(e: deadpool_sqlite::PoolError) -> DomainError {
-    DomainError::ValidationError(format!("Database pool error: {}", e))
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::persistence::open_database;
+
+    async fn setup() -> (tempfile::TempDir, SqliteTagGroupRepository) {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = open_database(&dir.path().join("test.db")).await.unwrap();
+        let conn = pool.get().await.unwrap();
+        conn.interact(|conn: &mut Connection| {
+            conn.execute_batch(
+                "INSERT INTO tag_groups (id, name, display_order) VALUES
+                    (1, 'beta', 0), (2, 'Alpha', 1), (3, 'gamma', 2);
+                 INSERT INTO tags (group_id, value) VALUES
+                    (3, 'a'), (3, 'b'), (2, 'c');",
+            )
+        })
+        .await
+        .unwrap()
+        .unwrap();
+        (dir, SqliteTagGroupRepository::new(Arc::new(pool)))
+    }
 
-fn map_interact_error(e: deadpool_sqlite::InteractError) -> DomainError {
-    DomainError::ValidationError(format!("Database interaction error: {}", e))
-}
+    async fn ids(repo: &SqliteTagGroupRepository, sort_mode: TagGroupSortMode) -> Vec<i64> {
+        repo.find_all(sort_mode)
+            .await
+            .unwrap()
+            .iter()
+            .filter_map(|g| g.id())
+            .collect()
+    }
 
-fn map_db_error(e: rusqlite::Error) -> DomainError {
-    match e {
-        rusqlite::Error::QueryReturnedNoRows => {
-            DomainError::TagGroupNotFound("Tag group not found".to_string())
-        }
-        _ => DomainError::ValidationError(format!("Database error: {}", e)),
+    #[tokio::test]
+    async fn find_all_by_display_order() {
+        let (_dir, repo) = setup().await;
+        assert_eq!(
+            ids(&repo, TagGroupSortMode::DisplayOrder).await,
+            vec![1, 2, 3]
+        );
+    }
+
+    #[tokio::test]
+    async fn normalize_order_renumbers_by_order_then_name() {
+        let (_dir, repo) = setup().await;
+        repo.reorder(vec![(1, 5), (2, 5), (3, -1)]).await.unwrap();
+
+        assert_eq!(repo.normalize_order().await.unwrap(), 3);
+        let groups = repo.find_all(TagGroupSortMode::DisplayOrder).await.unwrap();
+        let orders: Vec<(i64, i32)> = groups
+            .iter()
+            .map(|g| (g.id().unwrap(), g.display_order()))
+            .collect();
+        assert_eq!(orders, vec![(3, 0), (2, 1), (1, 2)]);
+
+        // Already sequential: nothing to change
+        assert_eq!(repo.normalize_order().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn find_all_alphabetical_ignores_case() {
+        let (_dir, repo) = setup().await;
+        assert_eq!(
+            ids(&repo, TagGroupSortMode::Alphabetical).await,
+            vec![2, 1, 3]
+        );
+    }
+
+    #[tokio::test]
+    async fn find_all_by_tag_count_includes_empty_groups() {
+        let (_dir, repo) = setup().await;
+        assert_eq!(
+            ids(&repo, TagGroupSortMode::ByTagCount).await,
+            vec![3, 2, 1]
+        );
+    }
+
+    #[tokio::test]
+    async fn exclusive_violations_list_items_with_several_group_tags() {
+        let (_dir, repo) = setup().await;
+        let conn = repo.pool.get().await.unwrap();
+        conn.interact(|conn: &mut Connection| {
+            conn.execute_batch(
+                "INSERT INTO items (id, path, is_directory, is_deleted) VALUES
+                    (1, 'C:\\b.txt', 0, 0), (2, 'C:\\a.txt', 0, 0),
+                    (3, 'C:\\c.txt', 0, 0), (4, 'C:\\d.txt', 0, 1);
+                 INSERT INTO item_tags (item_id, tag_id) VALUES
+                    (1, 1), (1, 2), (2, 2), (2, 1), (2, 3), (3, 1), (3, 3), (4, 1), (4, 2);",
+            )
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+        let violations = repo.find_exclusive_violations(3).await.unwrap();
+        assert_eq!(
+            violations,
+            vec![
+                (2, "C:\\a.txt".to_string(), vec![1, 2]),
+                (1, "C:\\b.txt".to_string(), vec![1, 2]),
+            ]
+        );
+        assert!(repo.find_exclusive_violations(2).await.unwrap().is_empty());
     }
 }
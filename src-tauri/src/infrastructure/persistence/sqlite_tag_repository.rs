@@ -2,7 +2,9 @@
 //!
 //! Implementation of TagRepository for SQLite.
 
-use crate::domain::entities::Tag;
+use super::cql_executor::descendant_path_sql;
+use super::interact::{interact_with, interact_write_with, not_found};
+use crate::domain::entities::{Tag, TagSpec};
 use crate::domain::errors::DomainError;
 use crate::domain::repositories::TagRepository;
 use crate::domain::value_objects::TagValue;
@@ -12,6 +14,14 @@ use rusqlite::{Connection, OptionalExtension};
 use std::collections::HashMap;
 use std::sync::Arc;
 
+/// Outcome of a group move inside the transaction.
+enum MoveOutcome {
+    /// Tag now lives in the target group under this ID.
+    Moved(i64),
+    /// Target group already has the value and merging was not requested.
+    Conflict(String),
+}
+
 /// SQLite implementation of TagRepository.
 pub struct SqliteTagRepository {
     pool: Arc<Pool>,
@@ -22,6 +32,34 @@ impl SqliteTagRepository {
         Self { pool }
     }
 
+    /// Runs `f` on a pooled connection, reporting a missing row as not found.
+    async fn interact<T, F>(&self, f: F) -> Result<T, DomainError>
+    where
+        F: FnOnce(&mut Connection) -> rusqlite::Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        interact_with(
+            &self.pool,
+            f,
+            not_found(|| DomainError::TagNotFound("Tag not found".to_string())),
+        )
+        .await
+    }
+
+    /// Like `interact`, retrying a write transaction while the database is busy.
+    async fn interact_write<T, F>(&self, f: F) -> Result<T, DomainError>
+    where
+        F: FnOnce(&mut Connection) -> rusqlite::Result<T> + Clone + Send + 'static,
+        T: Send + 'static,
+    {
+        interact_write_with(
+            &self.pool,
+            f,
+            not_found(|| DomainError::TagNotFound("Tag not found".to_string())),
+        )
+        .await
+    }
+
     fn map_row_to_tag(row: &rusqlite::Row) -> rusqlite::Result<Tag> {
         let value_str: String = row.get(2)?;
         // Use safe fallback for corrupted database data
@@ -40,12 +78,10 @@ impl SqliteTagRepository {
 #[async_trait]
 impl TagRepository for SqliteTagRepository {
     async fn save(&self, tag: &mut Tag) -> Result<i64, DomainError> {
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
-
         let group_id = tag.group_id();
         let value = tag.value().to_string();
 
-        let id = conn
+        let id = self
             .interact(move |conn: &mut Connection| {
                 // Check if group exists
                 let group_exists: bool = conn.query_row(
@@ -55,33 +91,24 @@ impl TagRepository for SqliteTagRepository {
                 )?;
 
                 if !group_exists {
-                    return Err(rusqlite::Error::InvalidQuery);
+                    return Ok(None);
                 }
 
                 conn.execute(
                     "INSERT INTO tags (group_id, value) VALUES (?1, ?2)",
                     (&group_id, &value),
                 )?;
-                Ok::<i64, rusqlite::Error>(conn.last_insert_rowid())
+                Ok::<Option<i64>, rusqlite::Error>(Some(conn.last_insert_rowid()))
             })
-            .await
-            .map_err(map_interact_error)?
-            .map_err(|e| {
-                if matches!(e, rusqlite::Error::InvalidQuery) {
-                    DomainError::TagGroupNotFound(tag.group_id().to_string())
-                } else {
-                    map_db_error(e)
-                }
-            })?;
+            .await?
+            .ok_or_else(|| DomainError::TagGroupNotFound(group_id.to_string()))?;
 
         tag.set_id(id);
         Ok(id)
     }
 
     async fn find_by_id(&self, id: i64) -> Result<Option<Tag>, DomainError> {
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
-
-        conn.interact(move |conn: &mut Connection| {
+        self.interact(move |conn: &mut Connection| {
             let result = conn
                 .query_row(
                     "SELECT id, group_id, value, created_at, updated_at FROM tags WHERE id = ?1",
@@ -92,8 +119,6 @@ impl TagRepository for SqliteTagRepository {
             Ok::<Option<Tag>, rusqlite::Error>(result)
         })
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
     }
 
     async fn find_by_ids(&self, ids: &[i64]) -> Result<Vec<Tag>, DomainError> {
@@ -101,10 +126,9 @@ impl TagRepository for SqliteTagRepository {
             return Ok(Vec::new());
         }
 
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
         let ids = ids.to_vec();
 
-        conn.interact(move |conn: &mut Connection| {
+        self.interact(move |conn: &mut Connection| {
             let placeholders: Vec<String> = ids.iter().map(|_| "?".to_string()).collect();
             let sql = format!(
                 "SELECT id, group_id, value, created_at, updated_at FROM tags WHERE id IN ({})",
@@ -126,14 +150,10 @@ impl TagRepository for SqliteTagRepository {
             Ok::<Vec<Tag>, rusqlite::Error>(tags)
         })
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
     }
 
     async fn find_by_group(&self, group_id: i64) -> Result<Vec<Tag>, DomainError> {
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
-
-        conn.interact(move |conn: &mut Connection| {
+        self.interact(move |conn: &mut Connection| {
             let mut stmt = conn.prepare(
                 "SELECT id, group_id, value, created_at, updated_at
                  FROM tags WHERE group_id = ?1 ORDER BY value ASC",
@@ -146,14 +166,78 @@ impl TagRepository for SqliteTagRepository {
             Ok::<Vec<Tag>, rusqlite::Error>(tags)
         })
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
     }
 
-    async fn find_all(&self) -> Result<Vec<Tag>, DomainError> {
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
+    async fn find_by_exact_value(
+        &self,
+        group_id: i64,
+        value: &str,
+    ) -> Result<Option<Tag>, DomainError> {
+        let value = value.to_string();
+
+        self.interact(move |conn: &mut Connection| {
+            // Served by the UNIQUE(group_id, value) index
+            let result = conn
+                .query_row(
+                    "SELECT id, group_id, value, created_at, updated_at
+                     FROM tags WHERE group_id = ?1 AND value = ?2",
+                    rusqlite::params![group_id, value],
+                    Self::map_row_to_tag,
+                )
+                .optional()?;
+            Ok::<Option<Tag>, rusqlite::Error>(result)
+        })
+        .await
+    }
 
-        conn.interact(|conn: &mut Connection| {
+    async fn find_by_specs(&self, specs: &[TagSpec]) -> Result<HashMap<TagSpec, Tag>, DomainError> {
+        if specs.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let specs = specs.to_vec();
+
+        self.interact(move |conn: &mut Connection| {
+            let mut stmt = conn.prepare(
+                "SELECT t.id, t.group_id, t.value, t.created_at, t.updated_at
+                 FROM tags t
+                 INNER JOIN tag_groups g ON g.id = t.group_id
+                 WHERE g.name = ?1 AND t.value = ?2",
+            )?;
+
+            let mut found = HashMap::new();
+            for spec in specs {
+                let tag = stmt
+                    .query_row((spec.group_name(), spec.value()), Self::map_row_to_tag)
+                    .optional()?;
+                if let Some(tag) = tag {
+                    found.insert(spec, tag);
+                }
+            }
+            Ok::<HashMap<TagSpec, Tag>, rusqlite::Error>(found)
+        })
+        .await
+    }
+
+    async fn find_by_value_across_groups(&self, value: &str) -> Result<Vec<Tag>, DomainError> {
+        let value = value.to_string();
+
+        self.interact(move |conn: &mut Connection| {
+            let mut stmt = conn.prepare(
+                "SELECT id, group_id, value, created_at, updated_at
+                 FROM tags WHERE value = ?1 ORDER BY group_id ASC",
+            )?;
+
+            let tags = stmt
+                .query_map([value], Self::map_row_to_tag)?
+                .collect::<Result<Vec<Tag>, _>>()?;
+
+            Ok::<Vec<Tag>, rusqlite::Error>(tags)
+        })
+        .await
+    }
+
+    async fn find_all(&self) -> Result<Vec<Tag>, DomainError> {
+        self.interact(|conn: &mut Connection| {
             let mut stmt = conn.prepare(
                 "SELECT id, group_id, value, created_at, updated_at
                  FROM tags ORDER BY group_id ASC, value ASC",
@@ -166,8 +250,58 @@ impl TagRepository for SqliteTagRepository {
             Ok::<Vec<Tag>, rusqlite::Error>(tags)
         })
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
+    }
+
+    async fn find_all_paged(
+        &self,
+        limit: u32,
+        offset: u32,
+    ) -> Result<(Vec<Tag>, u64), DomainError> {
+        self.interact(move |conn: &mut Connection| {
+            let mut stmt = conn.prepare(
+                "SELECT id, group_id, value, created_at, updated_at
+                 FROM tags ORDER BY group_id ASC, value ASC
+                 LIMIT ?1 OFFSET ?2",
+            )?;
+            let tags = stmt
+                .query_map([limit, offset], Self::map_row_to_tag)?
+                .collect::<Result<Vec<Tag>, _>>()?;
+
+            let total: i64 = conn.query_row("SELECT COUNT(*) FROM tags", [], |row| row.get(0))?;
+
+            Ok::<(Vec<Tag>, u64), rusqlite::Error>((tags, total as u64))
+        })
+        .await
+    }
+
+    async fn find_by_group_paged(
+        &self,
+        group_id: i64,
+        limit: u32,
+        offset: u32,
+    ) -> Result<(Vec<Tag>, u64), DomainError> {
+        self.interact(move |conn: &mut Connection| {
+            let mut stmt = conn.prepare(
+                "SELECT id, group_id, value, created_at, updated_at
+                 FROM tags WHERE group_id = ?1 ORDER BY value ASC
+                 LIMIT ?2 OFFSET ?3",
+            )?;
+            let tags = stmt
+                .query_map(
+                    rusqlite::params![group_id, limit, offset],
+                    Self::map_row_to_tag,
+                )?
+                .collect::<Result<Vec<Tag>, _>>()?;
+
+            let total: i64 = conn.query_row(
+                "SELECT COUNT(*) FROM tags WHERE group_id = ?1",
+                [group_id],
+                |row| row.get(0),
+            )?;
+
+            Ok::<(Vec<Tag>, u64), rusqlite::Error>((tags, total as u64))
+        })
+        .await
     }
 
     async fn update(&self, tag: &Tag) -> Result<(), DomainError> {
@@ -175,12 +309,10 @@ impl TagRepository for SqliteTagRepository {
             DomainError::ValidationError("Cannot update tag without ID".to_string())
         })?;
 
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
-
         let value = tag.value().to_string();
         let group_id = tag.group_id();
 
-        conn.interact(move |conn: &mut Connection| {
+        self.interact_write(move |conn: &mut Connection| {
             conn.execute("BEGIN IMMEDIATE", [])?;
 
             let result = (|| {
@@ -213,21 +345,15 @@ impl TagRepository for SqliteTagRepository {
             }
         })
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
     }
 
     async fn delete(&self, id: i64) -> Result<(), DomainError> {
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
-
-        let deleted = conn
+        let deleted = self
             .interact(move |conn: &mut Connection| {
                 let rows = conn.execute("DELETE FROM tags WHERE id = ?1", [id])?;
                 Ok::<usize, rusqlite::Error>(rows)
             })
-            .await
-            .map_err(map_interact_error)?
-            .map_err(map_db_error)?;
+            .await?;
 
         if deleted == 0 {
             return Err(DomainError::TagNotFound(id.to_string()));
@@ -247,9 +373,7 @@ impl TagRepository for SqliteTagRepository {
             return Ok(Vec::new());
         }
 
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
-
-        conn.interact(move |conn: &mut Connection| {
+        self.interact(move |conn: &mut Connection| {
             let sql = if let Some(_gid) = group_id {
                 "SELECT id, group_id, value, created_at, updated_at
                  FROM tags WHERE group_id = ?1 AND value LIKE ?2
@@ -274,14 +398,10 @@ impl TagRepository for SqliteTagRepository {
             Ok::<Vec<Tag>, rusqlite::Error>(tags)
         })
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
     }
 
     async fn get_usage_counts(&self) -> Result<HashMap<i64, i64>, DomainError> {
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
-
-        conn.interact(|conn: &mut Connection| {
+        self.interact(|conn: &mut Connection| {
             let mut stmt = conn.prepare(
                 "SELECT it.tag_id, COUNT(*) as count
                  FROM item_tags it
@@ -302,14 +422,39 @@ impl TagRepository for SqliteTagRepository {
             Ok::<HashMap<i64, i64>, rusqlite::Error>(map)
         })
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
     }
 
-    async fn find_by_item(&self, item_id: i64) -> Result<Vec<Tag>, DomainError> {
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
+    async fn co_occurrence(
+        &self,
+        min_count: u64,
+        limit: usize,
+    ) -> Result<Vec<(i64, i64, i64)>, DomainError> {
+        self.interact(move |conn: &mut Connection| {
+            let mut stmt = conn.prepare(
+                "SELECT a.tag_id, b.tag_id, COUNT(*) AS shared
+                 FROM item_tags a
+                 INNER JOIN item_tags b ON b.item_id = a.item_id AND b.tag_id > a.tag_id
+                 INNER JOIN items i ON i.id = a.item_id
+                 WHERE i.is_deleted = 0
+                 GROUP BY a.tag_id, b.tag_id
+                 HAVING shared >= ?1
+                 ORDER BY shared DESC, a.tag_id ASC, b.tag_id ASC
+                 LIMIT ?2",
+            )?;
 
-        conn.interact(move |conn: &mut Connection| {
+            let pairs = stmt
+                .query_map((min_count as i64, limit as i64), |row| {
+                    Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Ok::<Vec<(i64, i64, i64)>, rusqlite::Error>(pairs)
+        })
+        .await
+    }
+
+    async fn find_by_item(&self, item_id: i64) -> Result<Vec<Tag>, DomainError> {
+        self.interact(move |conn: &mut Connection| {
             let mut stmt = conn.prepare(
                 "SELECT t.id, t.group_id, t.value, t.created_at, t.updated_at
                  FROM tags t
@@ -325,8 +470,37 @@ impl TagRepository for SqliteTagRepository {
             Ok::<Vec<Tag>, rusqlite::Error>(tags)
         })
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
+    }
+
+    async fn find_under_directory(
+        &self,
+        directory: &str,
+        limit: usize,
+    ) -> Result<Vec<Tag>, DomainError> {
+        let directory = directory.to_string();
+
+        self.interact(move |conn: &mut Connection| {
+            let sql = format!(
+                "SELECT DISTINCT t.id, t.group_id, t.value, t.created_at, t.updated_at
+                 FROM (
+                     SELECT id FROM items
+                     WHERE {} AND is_deleted = 0
+                     LIMIT ?2
+                 ) c
+                 INNER JOIN item_tags it ON it.item_id = c.id
+                 INNER JOIN tags t ON t.id = it.tag_id
+                 ORDER BY t.value ASC",
+                descendant_path_sql("path", "?1")
+            );
+            let mut stmt = conn.prepare(&sql)?;
+
+            let tags = stmt
+                .query_map((&directory, limit as i64), Self::map_row_to_tag)?
+                .collect::<Result<Vec<Tag>, _>>()?;
+
+            Ok::<Vec<Tag>, rusqlite::Error>(tags)
+        })
+        .await
     }
 
     async fn find_by_items(&self, item_ids: &[i64]) -> Result<HashMap<i64, Vec<Tag>>, DomainError> {
@@ -334,10 +508,9 @@ impl TagRepository for SqliteTagRepository {
             return Ok(HashMap::new());
         }
 
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
         let ids = item_ids.to_vec();
 
-        conn.interact(move |conn: &mut Connection| {
+        self.interact(move |conn: &mut Connection| {
             let mut map: HashMap<i64, Vec<Tag>> = HashMap::new();
 
             // SQLite has a limit of ~999 bound parameters, chunk to stay safe
@@ -381,8 +554,65 @@ impl TagRepository for SqliteTagRepository {
             Ok::<HashMap<i64, Vec<Tag>>, rusqlite::Error>(map)
         })
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
+    }
+
+    async fn find_tags_among_items(
+        &self,
+        item_ids: &[i64],
+    ) -> Result<Vec<(Tag, i64)>, DomainError> {
+        if item_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut ids = item_ids.to_vec();
+        // Duplicate IDs would be counted once per chunk they land in
+        ids.sort_unstable();
+        ids.dedup();
+
+        self.interact(move |conn: &mut Connection| {
+            let mut counts: HashMap<i64, (Tag, i64)> = HashMap::new();
+
+            // SQLite has a limit of ~999 bound parameters, chunk to stay safe
+            const CHUNK_SIZE: usize = 500;
+            for chunk in ids.chunks(CHUNK_SIZE) {
+                let placeholders: Vec<String> = chunk.iter().map(|_| "?".to_string()).collect();
+                let sql = format!(
+                    "SELECT t.id, t.group_id, t.value, t.created_at, t.updated_at,
+                            COUNT(DISTINCT it.item_id)
+                     FROM item_tags it
+                     INNER JOIN tags t ON t.id = it.tag_id
+                     WHERE it.item_id IN ({})
+                     GROUP BY t.id",
+                    placeholders.join(", ")
+                );
+
+                let mut stmt = conn.prepare(&sql)?;
+                let params: Vec<Box<dyn rusqlite::ToSql>> = chunk
+                    .iter()
+                    .map(|id| Box::new(*id) as Box<dyn rusqlite::ToSql>)
+                    .collect();
+                let params_refs: Vec<&dyn rusqlite::ToSql> =
+                    params.iter().map(|p| p.as_ref()).collect();
+
+                let mut rows = stmt.query(params_refs.as_slice())?;
+                while let Some(row) = rows.next()? {
+                    let tag = Self::map_row_to_tag(row)?;
+                    let count: i64 = row.get(5)?;
+                    let tag_id: i64 = row.get(0)?;
+                    counts.entry(tag_id).or_insert((tag, 0)).1 += count;
+                }
+            }
+
+            let mut tags: Vec<(Tag, i64)> = counts.into_values().collect();
+            tags.sort_by(|(a, a_count), (b, b_count)| {
+                b_count
+                    .cmp(a_count)
+                    .then_with(|| a.value().as_str().cmp(b.value().as_str()))
+            });
+
+            Ok::<Vec<(Tag, i64)>, rusqlite::Error>(tags)
+        })
+        .await
     }
 
     async fn reassign_items(
@@ -390,9 +620,7 @@ impl TagRepository for SqliteTagRepository {
         source_tag_id: i64,
         target_tag_id: i64,
     ) -> Result<(), DomainError> {
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
-
-        conn.interact(move |conn: &mut Connection| {
+        self.interact_write(move |conn: &mut Connection| {
             conn.execute("BEGIN IMMEDIATE", [])?;
 
             let result = (|| {
@@ -428,24 +656,175 @@ impl TagRepository for SqliteTagRepository {
             }
         })
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
     }
-}
 
-fn map_pool_error(e: deadpool_sqlite::PoolError) -> DomainError {
-    DomainError::ValidationError(format!("Database pool error: {}", e))
-}
+    async fn split_into(
+        &self,
+        source_tag_id: i64,
+        target_tag_ids: &[i64],
+        remove_source: bool,
+    ) -> Result<usize, DomainError> {
+        let target_tag_ids = target_tag_ids.to_vec();
 
-fn map_interact_error(e: deadpool_sqlite::InteractError) -> DomainError {
-    DomainError::ValidationError(format!("Database interaction error: {}", e))
-}
+        self.interact_write(move |conn: &mut Connection| {
+            conn.execute("BEGIN IMMEDIATE", [])?;
+
+            let result = (|| {
+                let affected: i64 = conn.query_row(
+                    "SELECT COUNT(*) FROM item_tags WHERE tag_id = ?1",
+                    [source_tag_id],
+                    |row| row.get(0),
+                )?;
+
+                let mut inserted = 0;
+                for target_tag_id in &target_tag_ids {
+                    inserted += conn.execute(
+                        "INSERT OR IGNORE INTO item_tags (item_id, tag_id)
+                         SELECT item_id, ?1 FROM item_tags WHERE tag_id = ?2",
+                        [*target_tag_id, source_tag_id],
+                    )?;
+                }
+
+                if inserted > 0 || remove_source {
+                    conn.execute(
+                        "UPDATE items
+                         SET tags_updated_at = MAX(unixepoch(), COALESCE(tags_updated_at, 0) + 1)
+                         WHERE id IN (SELECT item_id FROM item_tags WHERE tag_id = ?1)",
+                        [source_tag_id],
+                    )?;
+                }
+
+                if remove_source {
+                    conn.execute("DELETE FROM item_tags WHERE tag_id = ?1", [source_tag_id])?;
+                    conn.execute("DELETE FROM tags WHERE id = ?1", [source_tag_id])?;
+                }
+
+                Ok::<usize, rusqlite::Error>(affected as usize)
+            })();
+
+            match result {
+                Ok(affected) => {
+                    conn.execute("COMMIT", [])?;
+                    Ok(affected)
+                }
+                Err(e) => {
+                    conn.execute("ROLLBACK", [])?;
+                    Err(e)
+                }
+            }
+        })
+        .await
+    }
+
+    async fn clear_usage(&self, tag_id: i64) -> Result<usize, DomainError> {
+        self.interact_write(move |conn: &mut Connection| {
+            conn.execute("BEGIN IMMEDIATE", [])?;
+
+            let result = (|| {
+                conn.execute(
+                    "UPDATE items
+                     SET tags_updated_at = MAX(unixepoch(), COALESCE(tags_updated_at, 0) + 1),
+                         updated_at = unixepoch()
+                     WHERE id IN (SELECT item_id FROM item_tags WHERE tag_id = ?1)",
+                    [tag_id],
+                )?;
+                conn.execute("DELETE FROM item_tags WHERE tag_id = ?1", [tag_id])
+            })();
+
+            match result {
+                Ok(removed) => {
+                    conn.execute("COMMIT", [])?;
+                    Ok(removed)
+                }
+                Err(e) => {
+                    conn.execute("ROLLBACK", [])?;
+                    Err(e)
+                }
+            }
+        })
+        .await
+    }
+
+    async fn move_to_group(
+        &self,
+        tag_id: i64,
+        target_group_id: i64,
+        merge_on_conflict: bool,
+    ) -> Result<i64, DomainError> {
+        let outcome = self
+            .interact_write(move |conn: &mut Connection| {
+                conn.execute("BEGIN IMMEDIATE", [])?;
+
+                let result = (|| {
+                    let value: String =
+                        conn.query_row("SELECT value FROM tags WHERE id = ?1", [tag_id], |row| {
+                            row.get(0)
+                        })?;
+
+                    let existing: Option<i64> = conn
+                        .query_row(
+                            "SELECT id FROM tags WHERE group_id = ?1 AND value = ?2 AND id != ?3",
+                            (target_group_id, &value, tag_id),
+                            |row| row.get(0),
+                        )
+                        .optional()?;
+
+                    match existing {
+                        None => {
+                            conn.execute(
+                                "UPDATE tags SET group_id = ?1, updated_at = unixepoch() WHERE id = ?2",
+                                [target_group_id, tag_id],
+                            )?;
+                            Ok::<MoveOutcome, rusqlite::Error>(MoveOutcome::Moved(tag_id))
+                        }
+                        Some(_) if !merge_on_conflict => Ok(MoveOutcome::Conflict(value)),
+                        Some(target_tag_id) => {
+                            // Same dedup-then-reassign as reassign_items
+                            conn.execute(
+                                "DELETE FROM item_tags
+                                 WHERE tag_id = ?1
+                                 AND item_id IN (
+                                     SELECT item_id FROM item_tags WHERE tag_id = ?2
+                                 )",
+                                [tag_id, target_tag_id],
+                            )?;
+                            conn.execute(
+                                "UPDATE item_tags SET tag_id = ?1 WHERE tag_id = ?2",
+                                [target_tag_id, tag_id],
+                            )?;
+                            conn.execute("DELETE FROM tags WHERE id = ?1", [tag_id])?;
+                            Ok(MoveOutcome::Moved(target_tag_id))
+                        }
+                    }
+                })();
+
+                match result {
+                    Ok(MoveOutcome::Moved(id)) => {
+                        conn.execute("COMMIT", [])?;
+                        Ok(MoveOutcome::Moved(id))
+                    }
+                    Ok(conflict) => {
+                        conn.execute("ROLLBACK", [])?;
+                        Ok(conflict)
+                    }
+                    Err(e) => {
+                        conn.execute("ROLLBACK", [])?;
+                        Err(e)
+                    }
+                }
+            })
+            .await
+            .map_err(|e| match e {
+                DomainError::TagNotFound(_) => DomainError::TagNotFound(tag_id.to_string()),
+                e => e,
+            })?;
 
-fn map_db_error(e: rusqlite::Error) -> DomainError {
-    match e {
-        rusqlite::Error::QueryReturnedNoRows => {
-            DomainError::TagNotFound("Tag not found".to_string())
+        match outcome {
+            MoveOutcome::Moved(id) => Ok(id),
+            MoveOutcome::Conflict(value) => Err(DomainError::DuplicateEntry(format!(
+                "Tag '{}' already exists in target group",
+                value
+            ))),
         }
-        _ => DomainError::ValidationError(format!("Database error: {}", e)),
     }
 }
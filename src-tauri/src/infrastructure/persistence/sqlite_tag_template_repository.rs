@@ -2,7 +2,8 @@
 //!
 //! Implementation of TagTemplateRepository for SQLite.
 
-use crate::domain::entities::TagTemplate;
+use super::interact::{interact, interact_write_with, not_found};
+use crate::domain::entities::{TagSpec, TagTemplate};
 use crate::domain::errors::DomainError;
 use crate::domain::repositories::TagTemplateRepository;
 use async_trait::async_trait;
@@ -19,18 +20,56 @@ impl SqliteTagTemplateRepository {
     pub fn new(pool: Arc<Pool>) -> Self {
         Self { pool }
     }
+
+    fn insert_specs(
+        conn: &Connection,
+        template_id: i64,
+        tag_specs: &[TagSpec],
+    ) -> rusqlite::Result<()> {
+        for (position, spec) in tag_specs.iter().enumerate() {
+            conn.execute(
+                "INSERT INTO template_tag_specs (template_id, group_name, value, position)
+                 VALUES (?1, ?2, ?3, ?4)",
+                (
+                    template_id,
+                    spec.group_name(),
+                    spec.value(),
+                    position as i64,
+                ),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn load_specs(conn: &Connection, template_id: i64) -> rusqlite::Result<Vec<TagSpec>> {
+        let rows = conn
+            .prepare(
+                "SELECT group_name, value FROM template_tag_specs
+                 WHERE template_id = ?1 ORDER BY position",
+            )?
+            .query_map([template_id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Rows were validated on the way in; anything else is skipped
+        Ok(rows
+            .into_iter()
+            .filter_map(|(group_name, value)| TagSpec::new(group_name, value).ok())
+            .collect())
+    }
 }
 
 #[async_trait]
 impl TagTemplateRepository for SqliteTagTemplateRepository {
     async fn save(&self, template: &mut TagTemplate) -> Result<i64, DomainError> {
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
-
         let name = template.name().to_string();
         let tag_ids = template.tag_ids().to_vec();
+        let tag_specs = template.tag_specs().to_vec();
 
-        let id = conn
-            .interact(move |conn: &mut Connection| {
+        let id = interact_write_with(
+            &self.pool,
+            move |conn: &mut Connection| {
                 conn.execute("BEGIN IMMEDIATE", [])?;
 
                 let result = (|| {
@@ -43,6 +82,7 @@ impl TagTemplateRepository for SqliteTagTemplateRepository {
                             (template_id, tag_id),
                         )?;
                     }
+                    Self::insert_specs(conn, template_id, &tag_specs)?;
 
                     Ok::<i64, rusqlite::Error>(template_id)
                 })();
@@ -57,19 +97,17 @@ impl TagTemplateRepository for SqliteTagTemplateRepository {
                         Err(e)
                     }
                 }
-            })
-            .await
-            .map_err(map_interact_error)?
-            .map_err(map_db_error)?;
+            },
+            DomainError::from,
+        )
+        .await?;
 
         template.set_id(id);
         Ok(id)
     }
 
     async fn find_by_id(&self, id: i64) -> Result<Option<TagTemplate>, DomainError> {
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
-
-        conn.interact(move |conn: &mut Connection| {
+        interact(&self.pool, move |conn: &mut Connection| {
             let template_data = conn
                 .query_row(
                     "SELECT id, name, created_at, updated_at FROM tag_templates WHERE id = ?1",
@@ -91,23 +129,20 @@ impl TagTemplateRepository for SqliteTagTemplateRepository {
                 let tag_ids = stmt
                     .query_map([id], |row| row.get(0))?
                     .collect::<Result<Vec<i64>, _>>()?;
+                let tag_specs = Self::load_specs(conn, id)?;
 
                 Ok(Some(TagTemplate::reconstitute(
-                    id, name, tag_ids, created_at, updated_at,
+                    id, name, tag_ids, tag_specs, created_at, updated_at,
                 )))
             } else {
                 Ok(None)
             }
         })
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
     }
 
     async fn find_all(&self) -> Result<Vec<TagTemplate>, DomainError> {
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
-
-        conn.interact(|conn: &mut Connection| {
+        interact(&self.pool, |conn: &mut Connection| {
             let mut stmt = conn.prepare(
                 "SELECT id, name, created_at, updated_at FROM tag_templates ORDER BY name ASC",
             )?;
@@ -130,17 +165,16 @@ impl TagTemplateRepository for SqliteTagTemplateRepository {
                 let tag_ids = tag_stmt
                     .query_map([id], |row| row.get(0))?
                     .collect::<Result<Vec<i64>, _>>()?;
+                let tag_specs = Self::load_specs(conn, id)?;
 
                 templates.push(TagTemplate::reconstitute(
-                    id, name, tag_ids, created_at, updated_at,
+                    id, name, tag_ids, tag_specs, created_at, updated_at,
                 ));
             }
 
             Ok::<Vec<TagTemplate>, rusqlite::Error>(templates)
         })
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
     }
 
     async fn update(&self, template: &TagTemplate) -> Result<(), DomainError> {
@@ -148,69 +182,71 @@ impl TagTemplateRepository for SqliteTagTemplateRepository {
             DomainError::ValidationError("Cannot update template without ID".to_string())
         })?;
 
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
-
         let name = template.name().to_string();
         let tag_ids = template.tag_ids().to_vec();
+        let tag_specs = template.tag_specs().to_vec();
 
-        conn.interact(move |conn: &mut Connection| {
-            conn.execute("BEGIN IMMEDIATE", [])?;
+        interact_write_with(
+            &self.pool,
+            move |conn: &mut Connection| {
+                conn.execute("BEGIN IMMEDIATE", [])?;
 
-            let result = (|| {
-                let exists: bool = conn.query_row(
-                    "SELECT COUNT(*) FROM tag_templates WHERE id = ?1",
-                    [id],
-                    |row| row.get::<_, i64>(0).map(|count| count > 0),
-                )?;
+                let result = (|| {
+                    let exists: bool = conn.query_row(
+                        "SELECT COUNT(*) FROM tag_templates WHERE id = ?1",
+                        [id],
+                        |row| row.get::<_, i64>(0).map(|count| count > 0),
+                    )?;
 
-                if !exists {
-                    return Err(rusqlite::Error::QueryReturnedNoRows);
-                }
+                    if !exists {
+                        return Err(rusqlite::Error::QueryReturnedNoRows);
+                    }
 
-                conn.execute(
-                    "UPDATE tag_templates SET name = ?1, updated_at = unixepoch() WHERE id = ?2",
-                    (&name, id),
-                )?;
+                    conn.execute(
+                        "UPDATE tag_templates SET name = ?1, updated_at = unixepoch() WHERE id = ?2",
+                        (&name, id),
+                    )?;
 
-                conn.execute("DELETE FROM template_tags WHERE template_id = ?1", [id])?;
+                    conn.execute("DELETE FROM template_tags WHERE template_id = ?1", [id])?;
+
+                    for tag_id in &tag_ids {
+                        conn.execute(
+                            "INSERT INTO template_tags (template_id, tag_id) VALUES (?1, ?2)",
+                            (id, tag_id),
+                        )?;
+                    }
 
-                for tag_id in &tag_ids {
                     conn.execute(
-                        "INSERT INTO template_tags (template_id, tag_id) VALUES (?1, ?2)",
-                        (id, tag_id),
+                        "DELETE FROM template_tag_specs WHERE template_id = ?1",
+                        [id],
                     )?;
-                }
+                    Self::insert_specs(conn, id, &tag_specs)?;
 
-                Ok::<(), rusqlite::Error>(())
-            })();
+                    Ok::<(), rusqlite::Error>(())
+                })();
 
-            match result {
-                Ok(_) => {
-                    conn.execute("COMMIT", [])?;
-                    Ok(())
-                }
-                Err(e) => {
-                    conn.execute("ROLLBACK", [])?;
-                    Err(e)
+                match result {
+                    Ok(_) => {
+                        conn.execute("COMMIT", [])?;
+                        Ok(())
+                    }
+                    Err(e) => {
+                        conn.execute("ROLLBACK", [])?;
+                        Err(e)
+                    }
                 }
-            }
-        })
+            },
+            not_found(|| DomainError::TagTemplateNotFound("Tag template not found".to_string())),
+        )
         .await
-        .map_err(map_interact_error)?
-        .map_err(map_db_error)
     }
 
     async fn delete(&self, id: i64) -> Result<(), DomainError> {
-        let conn = self.pool.get().await.map_err(map_pool_error)?;
-
-        let deleted = conn
-            .interact(move |conn: &mut Connection| {
-                let rows = conn.execute("DELETE FROM tag_templates WHERE id = ?1", [id])?;
-                Ok::<usize, rusqlite::Error>(rows)
-            })
-            .await
-            .map_err(map_interact_error)?
-            .map_err(map_db_error)?;
+        let deleted = interact(&self.pool, move |conn: &mut Connection| {
+            let rows = conn.execute("DELETE FROM tag_templates WHERE id = ?1", [id])?;
+            Ok::<usize, rusqlite::Error>(rows)
+        })
+        .await?;
 
         if deleted == 0 {
             return Err(DomainError::TagTemplateNotFound(id.to_string()));
@@ -219,20 +255,3 @@ impl TagTemplateRepository for SqliteTagTemplateRepository {
         Ok(())
     }
 }
-
-fn map_pool_error(e: deadpool_sqlite::PoolError) -> DomainError {
-    DomainError::ValidationError(format!("Database pool error: {}", e))
-}
-
-fn map_interact_error(e: deadpool_sqlite::InteractError) -> DomainError {
-    DomainError::ValidationError(format!("Database interaction error: {}", e))
-}
-
-fn map_db_error(e: rusqlite::Error) -> DomainError {
-    match e {
-        rusqlite::Error::QueryReturnedNoRows => {
-            DomainError::TagTemplateNotFound("Tag template not found".to_string())
-        }
-        _ => DomainError::ValidationError(format!("Database error: {}", e)),
-    }
-}
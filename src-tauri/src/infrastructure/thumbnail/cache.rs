@@ -2,19 +2,96 @@
 //!
 //! Stores generated thumbnails as WebP files in AppData.
 //! Uses blake3 hashing for cache keys and LRU eviction by mtime.
+//!
+//! Total size and file count are kept as running counters persisted to a
+//! small metadata file, so stats and eviction don't walk the whole tree.
+//! Writes caused by new entries are throttled, with the rest saved by
+//! `flush` or when the cache is dropped. Counters lost to a crash are caught
+//! on open by comparing them with a sample of the prefix directories.
+//!
+//! Since keys hash the source's mtime and size, the source path of each entry
+//! is recorded in an append-only index, so one file's thumbnails can be
+//! invalidated without knowing the metadata they were generated with. The
+//! index counts toward the size limit and is compacted whenever the cache is
+//! evicted.
 
+use std::collections::HashSet;
 use std::fs;
+use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Metadata file in the cache root holding `"{size_bytes} {file_count} {format}"`.
+const META_FILE: &str = "cache.meta";
+
+/// Index file in the cache root with one `"{hash}\t{source path}"` line per
+/// recorded entry. Eviction rewrites it with one line per remaining entry.
+const SOURCES_FILE: &str = "sources.log";
+
+/// Minimum time between counter writes caused by new or removed entries.
+const PERSIST_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Prefix directories whose entries are counted on open to check the
+/// persisted file count.
+const SAMPLED_PREFIX_DIRS: usize = 8;
+
+/// Files the estimate from the sample may differ from the persisted count
+/// by, at least, before the cache recounts. Past that the tolerance is a
+/// tenth of the persisted count.
+const RECOUNT_MIN_DIFFERENCE: u64 = 64;
+
+/// Cache key scheme version. Version 1 keyed every entry by path; version 2
+/// keys by file identity where available. Caches from an older version are
+/// purged once, since their path-keyed entries would never be hit again.
+const CACHE_FORMAT_VERSION: u32 = 2;
 
 /// Manages a disk-based thumbnail cache.
 pub struct ThumbnailCache {
     base_dir: PathBuf,
+    size_bytes: AtomicU64,
+    file_count: AtomicU64,
+    /// Set when the counters can't be trusted (missing or unreadable metadata)
+    /// and must be rebuilt by a directory walk before use.
+    stale: AtomicBool,
+    /// Key scheme the files on disk were written with.
+    format_version: AtomicU32,
+    /// Serializes appends to and rewrites of the sources index.
+    sources_lock: Mutex<()>,
+    /// The counters changed since they were last written.
+    dirty: AtomicBool,
+    /// When the counters were last written, if ever.
+    last_persist: Mutex<Option<Instant>>,
 }
 
 impl ThumbnailCache {
     /// Create a new cache at the given base directory.
+    ///
+    /// Loads the persisted counters; if they are missing, or the file count
+    /// is far from an estimate sampled from the prefix directories, the next
+    /// size query recounts. An absent directory is simply an empty cache.
+    /// Only metadata that records an older key scheme marks the cache for
+    /// purging; missing metadata says nothing about the files, so they are
+    /// kept.
     pub fn new(base_dir: PathBuf) -> Self {
-        Self { base_dir }
+        let meta = if base_dir.exists() {
+            read_meta(&base_dir.join(META_FILE))
+        } else {
+            Some((0, 0, CACHE_FORMAT_VERSION))
+        };
+        let (size_bytes, file_count, format_version) = meta.unwrap_or((0, 0, CACHE_FORMAT_VERSION));
+        let stale = meta.is_none() || count_disagrees(&base_dir, file_count);
+        Self {
+            base_dir,
+            size_bytes: AtomicU64::new(size_bytes),
+            file_count: AtomicU64::new(file_count),
+            stale: AtomicBool::new(stale),
+            format_version: AtomicU32::new(format_version),
+            sources_lock: Mutex::new(()),
+            dirty: AtomicBool::new(false),
+            last_persist: Mutex::new(None),
+        }
     }
 
     /// Compute a cache key from file metadata and thumbnail size.
@@ -31,6 +108,67 @@ impl ThumbnailCache {
         hasher.finalize().to_hex().to_string()
     }
 
+    /// Compute a cache key for a folder collage.
+    ///
+    /// Keyed by the directory's modification time, which changes when files
+    /// are added, removed or renamed inside it.
+    pub fn collage_key(dir: &str, mtime: i64, thumb_size: u32) -> String {
+        let mut hasher = blake3::Hasher::new();
+        // Domain-separate from the folder's shell icon key
+        hasher.update(b"collage:");
+        hasher.update(dir.as_bytes());
+        hasher.update(&mtime.to_le_bytes());
+        hasher.update(&thumb_size.to_le_bytes());
+        hasher.finalize().to_hex().to_string()
+    }
+
+    /// The directory this cache stores thumbnails in.
+    pub fn base_dir(&self) -> &Path {
+        &self.base_dir
+    }
+
+    /// Check that `dir` can host a cache by creating it and writing a probe file.
+    pub fn ensure_writable(dir: &Path) -> Result<(), std::io::Error> {
+        fs::create_dir_all(dir)?;
+        let probe = dir.join(".constellation-write-test");
+        fs::write(&probe, b"")?;
+        fs::remove_file(&probe)
+    }
+
+    /// Compute a cache key from a file's identity rather than its path.
+    ///
+    /// Keys from the volume serial number and File Reference Number survive
+    /// renames and moves within a volume; mtime, size and thumbnail size still
+    /// invalidate the entry when the file changes.
+    pub fn identity_key(
+        volume_serial: u32,
+        frn: u64,
+        mtime: i64,
+        file_size: u64,
+        thumb_size: u32,
+    ) -> String {
+        let mut hasher = blake3::Hasher::new();
+        // Domain-separate from path keys
+        hasher.update(b"frn:");
+        hasher.update(&volume_serial.to_le_bytes());
+        hasher.update(&frn.to_le_bytes());
+        hasher.update(&mtime.to_le_bytes());
+        hasher.update(&file_size.to_le_bytes());
+        hasher.update(&thumb_size.to_le_bytes());
+        hasher.finalize().to_hex().to_string()
+    }
+
+    /// Delete the cache if it was written with an older key scheme.
+    ///
+    /// Returns whether anything was purged.
+    pub fn purge_outdated_format(&self) -> Result<bool, std::io::Error> {
+        if self.format_version.load(Ordering::Relaxed) == CACHE_FORMAT_VERSION {
+            return Ok(false);
+        }
+        self.clear()?;
+        Ok(true)
+    }
+
     /// Get the filesystem path for a given cache key.
     fn cache_path(&self, hash: &str) -> PathBuf {
         let prefix = &hash[..2];
@@ -54,52 +192,318 @@ impl ThumbnailCache {
         Ok(Some(data))
     }
 
+    /// On-disk path of the entry `hash`, if it is cached. Unlike `get`, does
+    /// not count as a use for eviction.
+    pub fn entry_path(&self, hash: &str) -> Option<PathBuf> {
+        let path = self.cache_path(hash);
+        path.is_file().then_some(path)
+    }
+
     /// Store WebP bytes in the cache.
     pub fn put(&self, hash: &str, webp_data: &[u8]) -> Result<(), std::io::Error> {
         let path = self.cache_path(hash);
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent)?;
         }
-        fs::write(&path, webp_data)
+        let previous = fs::metadata(&path).ok().map(|m| m.len());
+        fs::write(&path, webp_data)?;
+
+        match previous {
+            Some(len) => saturating_sub(&self.size_bytes, len),
+            None => {
+                self.file_count.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.size_bytes
+            .fetch_add(webp_data.len() as u64, Ordering::Relaxed);
+        self.counters_changed();
+        Ok(())
+    }
+
+    /// Record that the entry `hash` is a thumbnail of `source`, so
+    /// `remove_for_path` can find it.
+    pub fn record_source(&self, hash: &str, source: &str) -> Result<(), std::io::Error> {
+        let _guard = self.lock_sources();
+        fs::create_dir_all(&self.base_dir)?;
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.base_dir.join(SOURCES_FILE))?;
+        file.write_all(format!("{}\t{}\n", hash, source).as_bytes())
+    }
+
+    /// Delete every entry recorded for `source` (compared case-insensitively),
+    /// whatever mtime, size or thumbnail size it was generated with.
+    ///
+    /// Returns the keys of the removed entries. Entries cached before their
+    /// source was recorded are not found.
+    pub fn remove_for_path(&self, source: &str) -> Result<Vec<String>, std::io::Error> {
+        let _guard = self.lock_sources();
+        let index = self.base_dir.join(SOURCES_FILE);
+        let contents = match fs::read_to_string(&index) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+
+        let source = source.to_lowercase();
+        let mut hashes: Vec<String> = Vec::new();
+        let mut kept = String::new();
+        for line in contents.lines() {
+            let Some((hash, path)) = line.split_once('\t') else {
+                continue;
+            };
+            if hash.len() < 2 || !hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+                continue;
+            }
+            if path.to_lowercase() == source {
+                if !hashes.iter().any(|h| h == hash) {
+                    hashes.push(hash.to_string());
+                }
+            } else {
+                kept.push_str(line);
+                kept.push('\n');
+            }
+        }
+
+        let mut removed = Vec::new();
+        for hash in hashes {
+            let path = self.cache_path(&hash);
+            let Ok(meta) = fs::metadata(&path) else {
+                continue;
+            };
+            if fs::remove_file(&path).is_ok() {
+                saturating_sub(&self.size_bytes, meta.len());
+                saturating_sub(&self.file_count, 1);
+                if let Some(parent) = path.parent() {
+                    let _ = fs::remove_dir(parent);
+                }
+                removed.push(hash);
+            }
+        }
+
+        fs::write(&index, kept)?;
+        self.counters_changed();
+        Ok(removed)
+    }
+
+    /// Rewrite the sources index with one line per entry in `live`, the
+    /// latest recorded one. Lines of entries no longer cached are dropped.
+    fn compact_sources(&self, live: &HashSet<String>) -> Result<(), std::io::Error> {
+        let _guard = self.lock_sources();
+        let index = self.base_dir.join(SOURCES_FILE);
+        let contents = match fs::read_to_string(&index) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        // A key can be recorded again for a moved file; the last line wins
+        let mut seen: HashSet<&str> = HashSet::new();
+        let mut kept: Vec<&str> = contents
+            .lines()
+            .rev()
+            .filter(|line| {
+                line.split_once('\t')
+                    .is_some_and(|(hash, _)| live.contains(hash) && seen.insert(hash))
+            })
+            .collect();
+        kept.reverse();
+
+        let mut compacted = kept.join("\n");
+        if !compacted.is_empty() {
+            compacted.push('\n');
+        }
+        fs::write(&index, compacted)
+    }
+
+    /// Current size of the sources index in bytes.
+    fn sources_size(&self) -> u64 {
+        fs::metadata(self.base_dir.join(SOURCES_FILE))
+            .map(|m| m.len())
+            .unwrap_or(0)
+    }
+
+    fn lock_sources(&self) -> std::sync::MutexGuard<'_, ()> {
+        self.sources_lock.lock().unwrap_or_else(|e| e.into_inner())
     }
 
     /// Delete all cached thumbnails. Returns the number of bytes freed.
+    ///
+    /// Only files in the `{prefix}/{hash}.webp` layout and the cache's own
+    /// metadata are removed; anything else in the directory is left alone.
     pub fn clear(&self) -> Result<u64, std::io::Error> {
         let size = self.total_size()?;
-        if self.base_dir.exists() {
-            fs::remove_dir_all(&self.base_dir)?;
+        let mut files: Vec<PathBuf> = Vec::new();
+        visit_files(&self.base_dir, &mut |path: &Path| {
+            files.push(path.to_path_buf())
+        })?;
+        files.push(self.base_dir.join(META_FILE));
+        files.push(self.base_dir.join(SOURCES_FILE));
+        for file in &files {
+            match fs::remove_file(file) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
         }
+        cleanup_empty_dirs(&self.base_dir)?;
+        let _ = fs::remove_dir(&self.base_dir);
+        self.size_bytes.store(0, Ordering::Relaxed);
+        self.file_count.store(0, Ordering::Relaxed);
+        self.stale.store(false, Ordering::Relaxed);
+        self.format_version
+            .store(CACHE_FORMAT_VERSION, Ordering::Relaxed);
         Ok(size)
     }
 
     /// Get the total cache size in bytes.
     pub fn total_size(&self) -> Result<u64, std::io::Error> {
-        if !self.base_dir.exists() {
-            return Ok(0);
-        }
-        dir_size(&self.base_dir)
+        self.ensure_counters()?;
+        Ok(self.size_bytes.load(Ordering::Relaxed))
     }
 
     /// Count the number of cached files.
     pub fn file_count(&self) -> Result<u64, std::io::Error> {
+        self.ensure_counters()?;
+        Ok(self.file_count.load(Ordering::Relaxed))
+    }
+
+    /// Rebuild the counters from a full directory walk and persist them.
+    pub fn recount(&self) -> Result<(), std::io::Error> {
+        let mut size = 0u64;
+        let mut count = 0u64;
+        visit_files(&self.base_dir, &mut |path: &Path| {
+            if let Ok(meta) = fs::metadata(path) {
+                size += meta.len();
+                count += 1;
+            }
+        })?;
+        self.size_bytes.store(size, Ordering::Relaxed);
+        self.file_count.store(count, Ordering::Relaxed);
+        self.stale.store(false, Ordering::Relaxed);
+        self.persist_counters();
+        Ok(())
+    }
+
+    fn ensure_counters(&self) -> Result<(), std::io::Error> {
+        if self.stale.load(Ordering::Relaxed) {
+            self.recount()?;
+        }
+        Ok(())
+    }
+
+    /// Write the counters if they changed since the last write.
+    pub fn flush(&self) {
+        if self.dirty.load(Ordering::Relaxed) {
+            self.persist_counters();
+        }
+    }
+
+    /// Note a change to the counters, writing them unless they were written
+    /// within `PERSIST_INTERVAL`.
+    fn counters_changed(&self) {
+        self.dirty.store(true, Ordering::Relaxed);
+        let due = self
+            .last_persist
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_none_or(|last| last.elapsed() >= PERSIST_INTERVAL);
+        if due {
+            self.persist_counters();
+        }
+    }
+
+    /// Best-effort write of the counters; a lost write only costs a recount later.
+    fn persist_counters(&self) {
+        self.dirty.store(false, Ordering::Relaxed);
+        *self.last_persist.lock().unwrap_or_else(|e| e.into_inner()) = Some(Instant::now());
+        if !self.base_dir.exists() {
+            return;
+        }
+        let contents = format!(
+            "{} {} {}",
+            self.size_bytes.load(Ordering::Relaxed),
+            self.file_count.load(Ordering::Relaxed),
+            self.format_version.load(Ordering::Relaxed)
+        );
+        let _ = fs::write(self.base_dir.join(META_FILE), contents);
+    }
+
+    /// Move every cached file into `target`, keeping the `{prefix}/{hash}.webp` layout.
+    ///
+    /// Files already present in `target` are dropped instead of overwritten,
+    /// since the same key always maps to the same thumbnail. Entries from an
+    /// older key scheme are moved like any other and age out through eviction
+    /// once `target` fills up. `on_progress`
+    /// receives `(processed, total)` after each file. Returns the number of
+    /// files moved; the emptied source directory is removed afterwards.
+    pub fn migrate_into(
+        &self,
+        target: &ThumbnailCache,
+        on_progress: &mut dyn FnMut(u64, u64),
+    ) -> Result<u64, std::io::Error> {
         if !self.base_dir.exists() {
             return Ok(0);
         }
-        let mut count = 0u64;
-        visit_files(&self.base_dir, &mut |_| {
-            count += 1;
+
+        let mut files: Vec<PathBuf> = Vec::new();
+        visit_files(&self.base_dir, &mut |path: &Path| {
+            files.push(path.to_path_buf())
         })?;
-        Ok(count)
+
+        let total = files.len() as u64;
+        let mut moved = 0u64;
+        for (index, source) in files.iter().enumerate() {
+            let relative = source.strip_prefix(&self.base_dir).unwrap_or(source);
+            let dest = target.base_dir.join(relative);
+            if dest.exists() {
+                let _ = fs::remove_file(source);
+            } else {
+                if let Some(parent) = dest.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                let len = fs::metadata(source).map(|m| m.len()).unwrap_or(0);
+                move_file(source, &dest)?;
+                target.size_bytes.fetch_add(len, Ordering::Relaxed);
+                target.file_count.fetch_add(1, Ordering::Relaxed);
+                moved += 1;
+            }
+            on_progress(index as u64 + 1, total);
+        }
+        target.persist_counters();
+
+        // Carry the sources index over; lines of dropped files are harmless
+        let index = self.base_dir.join(SOURCES_FILE);
+        if let Ok(lines) = fs::read_to_string(&index) {
+            let _guard = target.lock_sources();
+            fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(target.base_dir.join(SOURCES_FILE))?
+                .write_all(lines.as_bytes())?;
+        }
+
+        self.size_bytes.store(0, Ordering::Relaxed);
+        self.file_count.store(0, Ordering::Relaxed);
+        let _ = fs::remove_file(self.base_dir.join(META_FILE));
+        let _ = fs::remove_file(index);
+        cleanup_empty_dirs(&self.base_dir)?;
+        let _ = fs::remove_dir(&self.base_dir);
+        Ok(moved)
     }
 
-    /// Evict oldest entries until the cache is under `max_bytes`.
+    /// Evict oldest entries until the cache, sources index included, is under
+    /// `max_bytes`.
+    ///
+    /// The directory is only walked when the counter reports the cache over
+    /// the limit; that walk also resyncs the counters with what is on disk and
+    /// compacts the sources index to the entries that remain.
     pub fn evict_to_limit(&self, max_bytes: u64) -> Result<u64, std::io::Error> {
         if !self.base_dir.exists() || max_bytes == 0 {
             return Ok(0);
         }
 
-        let current = self.total_size()?;
-        if current <= max_bytes {
+        if self.total_size()? + self.sources_size() <= max_bytes {
             return Ok(0);
         }
 
@@ -112,28 +516,124 @@ impl ThumbnailCache {
             }
         })?;
 
-        // Sort by mtime ascending (oldest first)
+        let current: u64 = entries.iter().map(|(_, size, _)| size).sum();
+        self.size_bytes.store(current, Ordering::Relaxed);
+        self.file_count
+            .store(entries.len() as u64, Ordering::Relaxed);
+
+        // Sort by mtime ascending (oldest first). When the counter had drifted
+        // high the target is zero and the correction is all that was needed.
         entries.sort_by_key(|(_, _, mtime)| *mtime);
+        let target = (current + self.sources_size()).saturating_sub(max_bytes);
 
         let mut freed = 0u64;
-        let target = current - max_bytes;
-
+        let mut removed = 0u64;
+        let mut touched_dirs: HashSet<&Path> = HashSet::new();
+        let mut live: HashSet<String> = HashSet::new();
         for (path, size, _) in &entries {
-            if freed >= target {
-                break;
-            }
-            if fs::remove_file(path).is_ok() {
+            if freed < target && fs::remove_file(path).is_ok() {
                 freed += size;
+                removed += 1;
+                if let Some(parent) = path.parent() {
+                    touched_dirs.insert(parent);
+                }
+            } else if let Some(hash) = path.file_stem().and_then(|s| s.to_str()) {
+                live.insert(hash.to_string());
             }
         }
 
-        // Clean up empty subdirectories
-        cleanup_empty_dirs(&self.base_dir)?;
+        // Only the prefix directories we deleted from can have become empty
+        for dir in touched_dirs {
+            let _ = fs::remove_dir(dir);
+        }
+
+        saturating_sub(&self.size_bytes, freed);
+        saturating_sub(&self.file_count, removed);
+        self.persist_counters();
+        self.compact_sources(&live)?;
 
         Ok(freed)
     }
 }
 
+impl Drop for ThumbnailCache {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+/// Whether `file_count` is further from an estimate of the entries on disk
+/// than the tolerance allows.
+///
+/// Keys are uniformly distributed hashes, so the entries in up to
+/// `SAMPLED_PREFIX_DIRS` evenly spaced prefix directories, scaled by the
+/// number of prefix directories, estimate the total. Unreadable directories
+/// count as agreeing; the counters are resynced by the next eviction anyway.
+fn count_disagrees(base_dir: &Path, file_count: u64) -> bool {
+    let Ok(dirs) = prefix_dirs(base_dir) else {
+        return false;
+    };
+    if dirs.is_empty() {
+        return file_count > RECOUNT_MIN_DIFFERENCE;
+    }
+
+    let step = dirs.len().div_ceil(SAMPLED_PREFIX_DIRS);
+    let mut sampled_dirs = 0u64;
+    let mut sampled_files = 0u64;
+    for dir in dirs.iter().step_by(step) {
+        let (Some(prefix), Ok(entries)) =
+            (dir.file_name().and_then(|n| n.to_str()), fs::read_dir(dir))
+        else {
+            return false;
+        };
+        sampled_dirs += 1;
+        sampled_files += entries
+            .filter_map(Result::ok)
+            .filter(|entry| {
+                entry
+                    .file_name()
+                    .to_str()
+                    .is_some_and(|name| is_entry_file_name(name, prefix))
+            })
+            .count() as u64;
+    }
+
+    let estimate = sampled_files * dirs.len() as u64 / sampled_dirs;
+    let tolerance = RECOUNT_MIN_DIFFERENCE.max(file_count / 10);
+    estimate.abs_diff(file_count) > tolerance
+}
+
+/// Move a file, falling back to copy + delete across volumes.
+fn move_file(from: &Path, to: &Path) -> Result<(), std::io::Error> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    fs::copy(from, to)?;
+    fs::remove_file(from)
+}
+
+/// Parse the persisted `"{size_bytes} {file_count} {format}"` metadata.
+///
+/// Metadata without a format field predates versioning and reads as version 1.
+fn read_meta(path: &Path) -> Option<(u64, u64, u32)> {
+    let contents = fs::read_to_string(path).ok()?;
+    let mut parts = contents.split_whitespace();
+    let size = parts.next()?.parse().ok()?;
+    let count = parts.next()?.parse().ok()?;
+    let format = match parts.next() {
+        Some(v) => v.parse().ok()?,
+        None => 1,
+    };
+    Some((size, count, format))
+}
+
+/// Subtract from a counter without wrapping below zero.
+fn saturating_sub(counter: &AtomicU64, value: u64) {
+    let _ = counter.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |current| {
+        Some(current.saturating_sub(value))
+    });
+}
+
 /// Touch file mtime to current time for LRU tracking.
 fn touch_mtime(path: &Path) -> Result<(), std::io::Error> {
     let file = fs::OpenOptions::new().write(true).open(path)?;
@@ -141,47 +641,391 @@ fn touch_mtime(path: &Path) -> Result<(), std::io::Error> {
     file.set_times(times)
 }
 
-/// Recursively calculate directory size.
-fn dir_size(dir: &Path) -> Result<u64, std::io::Error> {
-    let mut total = 0u64;
-    visit_files(dir, &mut |path: &Path| {
-        if let Ok(meta) = fs::metadata(path) {
-            total += meta.len();
+/// Visit every cached thumbnail under `dir`, i.e. each `{hash}.webp` file in
+/// the two-character prefix directory of its hash.
+///
+/// Anything outside that layout is never visited, so eviction, migration and
+/// clearing can't touch unrelated files in a user-chosen directory.
+fn visit_files(dir: &Path, f: &mut dyn FnMut(&Path)) -> Result<(), std::io::Error> {
+    for prefix_dir in prefix_dirs(dir)? {
+        let Some(prefix) = prefix_dir.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        for entry in fs::read_dir(&prefix_dir)? {
+            let path = entry?.path();
+            let is_entry = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|name| is_entry_file_name(name, prefix));
+            if is_entry && path.is_file() {
+                f(&path);
+            }
         }
-    })?;
-    Ok(total)
+    }
+    Ok(())
 }
 
-/// Visit all files in a directory recursively.
-fn visit_files(dir: &Path, f: &mut dyn FnMut(&Path)) -> Result<(), std::io::Error> {
+/// Prefix directories (two hex digits) directly under `dir`.
+fn prefix_dirs(dir: &Path) -> Result<Vec<PathBuf>, std::io::Error> {
     if !dir.is_dir() {
-        return Ok(());
+        return Ok(Vec::new());
     }
+    let mut dirs = Vec::new();
     for entry in fs::read_dir(dir)? {
-        let entry = entry?;
-        let path = entry.path();
-        if path.is_dir() {
-            visit_files(&path, f)?;
-        } else {
-            f(&path);
+        let path = entry?.path();
+        let is_prefix = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|name| name.len() == 2 && is_hex(name));
+        if is_prefix && path.is_dir() {
+            dirs.push(path);
         }
     }
-    Ok(())
+    Ok(dirs)
+}
+
+/// Whether `name` is a `{hash}.webp` file belonging in prefix directory `prefix`.
+fn is_entry_file_name(name: &str, prefix: &str) -> bool {
+    name.strip_suffix(".webp")
+        .is_some_and(|hash| hash.starts_with(prefix) && is_hex(hash))
+}
+
+fn is_hex(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(|b| b.is_ascii_hexdigit())
 }
 
-/// Remove empty subdirectories.
+/// Remove prefix directories left empty.
 fn cleanup_empty_dirs(dir: &Path) -> Result<(), std::io::Error> {
-    if !dir.is_dir() {
-        return Ok(());
+    for prefix_dir in prefix_dirs(dir)? {
+        // Fails silently if not empty
+        let _ = fs::remove_dir(&prefix_dir);
     }
-    for entry in fs::read_dir(dir)? {
-        let entry = entry?;
-        let path = entry.path();
-        if path.is_dir() {
-            cleanup_empty_dirs(&path)?;
-            // Try to remove — fails silently if not empty
-            let _ = fs::remove_dir(&path);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Size and count as seen by a full directory walk.
+    fn disk_totals(cache: &ThumbnailCache) -> (u64, u64) {
+        let (mut size, mut count) = (0, 0);
+        visit_files(cache.base_dir(), &mut |path: &Path| {
+            size += fs::metadata(path).unwrap().len();
+            count += 1;
+        })
+        .unwrap();
+        (size, count)
+    }
+
+    fn counters(cache: &ThumbnailCache) -> (u64, u64) {
+        (cache.total_size().unwrap(), cache.file_count().unwrap())
+    }
+
+    #[test]
+    fn counters_stay_consistent_across_put_evict_and_clear() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ThumbnailCache::new(dir.path().join("thumbs"));
+        assert_eq!(counters(&cache), (0, 0));
+
+        let keys: Vec<String> = (0..4)
+            .map(|i| ThumbnailCache::cache_key(&format!("C:\\{}.jpg", i), 1, 10, 256))
+            .collect();
+        for key in &keys {
+            cache.put(key, &[0u8; 100]).unwrap();
         }
+        // Overwriting replaces the old size instead of adding to it
+        cache.put(&keys[0], &[0u8; 40]).unwrap();
+        assert_eq!(counters(&cache), (340, 4));
+        assert_eq!(counters(&cache), disk_totals(&cache));
+
+        let freed = cache.evict_to_limit(150).unwrap();
+        assert!(freed >= 190);
+        assert!(cache.total_size().unwrap() <= 150);
+        assert_eq!(counters(&cache), disk_totals(&cache));
+
+        // Under the limit: nothing to do
+        assert_eq!(cache.evict_to_limit(150).unwrap(), 0);
+
+        cache.clear().unwrap();
+        assert_eq!(counters(&cache), (0, 0));
+        cache.put(&keys[1], &[0u8; 7]).unwrap();
+        assert_eq!(counters(&cache), (7, 1));
+        assert_eq!(counters(&cache), disk_totals(&cache));
+    }
+
+    #[test]
+    fn counters_persist_and_recount_when_metadata_is_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("thumbs");
+        let key = ThumbnailCache::cache_key("C:\\a.jpg", 1, 10, 256);
+        ThumbnailCache::new(base.clone())
+            .put(&key, &[0u8; 25])
+            .unwrap();
+
+        // A file added behind the cache's back is invisible to the persisted counter...
+        let stray = ThumbnailCache::cache_key("C:\\b.jpg", 1, 10, 256);
+        fs::create_dir_all(base.join(&stray[..2])).unwrap();
+        fs::write(
+            base.join(&stray[..2]).join(format!("{}.webp", stray)),
+            [0u8; 5],
+        )
+        .unwrap();
+        assert_eq!(counters(&ThumbnailCache::new(base.clone())), (25, 1));
+
+        // ...until the metadata is gone and the cache recounts
+        fs::remove_file(base.join(META_FILE)).unwrap();
+        let cache = ThumbnailCache::new(base.clone());
+        assert_eq!(counters(&cache), (30, 2));
+        // Lost metadata is only recounted, never taken for an outdated format
+        assert!(!cache.purge_outdated_format().unwrap());
+        assert_eq!(
+            read_meta(&base.join(META_FILE)),
+            Some((30, 2, CACHE_FORMAT_VERSION))
+        );
+    }
+
+    #[test]
+    fn counter_writes_are_throttled_until_flushed() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("thumbs");
+        let cache = ThumbnailCache::new(base.clone());
+        let keys: Vec<String> = (0..3)
+            .map(|i| ThumbnailCache::cache_key(&format!("C:\\{}.jpg", i), 1, 10, 256))
+            .collect();
+
+        cache.put(&keys[0], &[0u8; 10]).unwrap();
+        cache.put(&keys[1], &[0u8; 10]).unwrap();
+        let meta = || read_meta(&base.join(META_FILE)).unwrap();
+        assert_eq!(meta(), (10, 1, CACHE_FORMAT_VERSION));
+
+        cache.flush();
+        assert_eq!(meta(), (20, 2, CACHE_FORMAT_VERSION));
+
+        cache.put(&keys[2], &[0u8; 10]).unwrap();
+        drop(cache);
+        assert_eq!(meta(), (30, 3, CACHE_FORMAT_VERSION));
+    }
+
+    #[test]
+    fn counters_far_from_the_disk_are_recounted_on_open() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("thumbs");
+        // 100 entries spread evenly over ten prefix directories
+        for d in 0..10 {
+            let prefix = format!("{:02x}", d);
+            fs::create_dir_all(base.join(&prefix)).unwrap();
+            for i in 0..10 {
+                let name = format!("{}{:062x}.webp", prefix, i);
+                fs::write(base.join(&prefix).join(name), [0u8; 3]).unwrap();
+            }
+        }
+
+        // A few entries lost from the counters are within the tolerance...
+        let meta = |size: u64, count: u64| {
+            fs::write(
+                base.join(META_FILE),
+                format!("{} {} {}", size, count, CACHE_FORMAT_VERSION),
+            )
+            .unwrap()
+        };
+        meta(291, 97);
+        assert_eq!(counters(&ThumbnailCache::new(base.clone())), (291, 97));
+
+        // ...but counters missing most of the cache are rebuilt
+        meta(30, 10);
+        assert_eq!(counters(&ThumbnailCache::new(base.clone())), (300, 100));
+    }
+
+    #[test]
+    fn eviction_resyncs_a_drifted_counter() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ThumbnailCache::new(dir.path().join("thumbs"));
+        let key = ThumbnailCache::cache_key("C:\\a.jpg", 1, 10, 256);
+        cache.put(&key, &[0u8; 10]).unwrap();
+        cache.size_bytes.store(1_000, Ordering::Relaxed);
+
+        assert_eq!(cache.evict_to_limit(100).unwrap(), 0);
+        assert_eq!(counters(&cache), (10, 1));
+        assert!(cache.get(&key).unwrap().is_some());
+    }
+
+    #[test]
+    fn migrate_moves_files_and_removes_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = ThumbnailCache::new(dir.path().join("old"));
+        let new = ThumbnailCache::new(dir.path().join("new"));
+        let a = ThumbnailCache::cache_key("C:\\a.jpg", 1, 10, 256);
+        let b = ThumbnailCache::cache_key("C:\\b.jpg", 1, 10, 256);
+        old.put(&a, b"aaa").unwrap();
+        old.put(&b, b"bbb").unwrap();
+
+        let mut progress = Vec::new();
+        let moved = old
+            .migrate_into(&new, &mut |done, total| progress.push((done, total)))
+            .unwrap();
+
+        assert_eq!(moved, 2);
+        assert_eq!(progress, vec![(1, 2), (2, 2)]);
+        assert_eq!(new.get(&a).unwrap().unwrap(), b"aaa");
+        assert_eq!(new.get(&b).unwrap().unwrap(), b"bbb");
+        assert!(!old.base_dir().exists());
+    }
+
+    #[test]
+    fn migrate_keeps_existing_target_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = ThumbnailCache::new(dir.path().join("old"));
+        let new = ThumbnailCache::new(dir.path().join("new"));
+        let key = ThumbnailCache::cache_key("C:\\a.jpg", 1, 10, 256);
+        old.put(&key, b"old").unwrap();
+        new.put(&key, b"new").unwrap();
+
+        let moved = old.migrate_into(&new, &mut |_, _| {}).unwrap();
+
+        assert_eq!(moved, 0);
+        assert_eq!(new.get(&key).unwrap().unwrap(), b"new");
+        assert_eq!(old.file_count().unwrap(), 0);
+    }
+
+    #[test]
+    fn migrate_moves_entries_of_an_older_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let old_base = dir.path().join("old");
+        ThumbnailCache::new(old_base.clone())
+            .put("aabbcc", b"old")
+            .unwrap();
+        fs::write(old_base.join(META_FILE), "3 1 1").unwrap();
+        let old = ThumbnailCache::new(old_base);
+        let new = ThumbnailCache::new(dir.path().join("new"));
+
+        assert_eq!(old.migrate_into(&new, &mut |_, _| {}).unwrap(), 1);
+        assert_eq!(new.get("aabbcc").unwrap(), Some(b"old".to_vec()));
+    }
+
+    #[test]
+    fn remove_for_path_deletes_all_sizes_of_one_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ThumbnailCache::new(dir.path().join("thumbs"));
+        let small = ThumbnailCache::cache_key("C:\\a.jpg", 1, 10, 128);
+        let large = ThumbnailCache::cache_key("C:\\a.jpg", 1, 10, 512);
+        let other = ThumbnailCache::cache_key("C:\\b.jpg", 1, 10, 128);
+        for (key, source) in [
+            (&small, "C:\\a.jpg"),
+            (&large, "C:\\a.jpg"),
+            (&other, "C:\\b.jpg"),
+        ] {
+            cache.put(key, &[0u8; 10]).unwrap();
+            cache.record_source(key, source).unwrap();
+        }
+
+        let mut removed = cache.remove_for_path("c:\\A.JPG").unwrap();
+        removed.sort();
+        let mut expected = vec![small.clone(), large.clone()];
+        expected.sort();
+        assert_eq!(removed, expected);
+
+        assert_eq!(cache.get(&small).unwrap(), None);
+        assert_eq!(cache.get(&large).unwrap(), None);
+        assert!(cache.get(&other).unwrap().is_some());
+        assert_eq!(counters(&cache), (10, 1));
+        assert_eq!(counters(&cache), disk_totals(&cache));
+        assert_eq!(
+            fs::read_to_string(cache.base_dir().join(SOURCES_FILE)).unwrap(),
+            format!("{}\tC:\\b.jpg\n", other)
+        );
+
+        assert!(cache.remove_for_path("C:\\a.jpg").unwrap().is_empty());
+    }
+
+    #[test]
+    fn eviction_compacts_the_sources_index_and_counts_it() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ThumbnailCache::new(dir.path().join("thumbs"));
+        let keys: Vec<String> = (0..3)
+            .map(|i| ThumbnailCache::cache_key(&format!("C:\\{}.jpg", i), 1, 10, 256))
+            .collect();
+        for key in &keys {
+            cache.put(key, &[0u8; 100]).unwrap();
+            cache.record_source(key, "C:\\old.jpg").unwrap();
+            cache.record_source(key, "C:\\new.jpg").unwrap();
+        }
+        let index = cache.base_dir().join(SOURCES_FILE);
+        let log_size = fs::metadata(&index).unwrap().len();
+
+        // The entries alone fit, but not together with the index
+        cache.evict_to_limit(300 + log_size - 1).unwrap();
+        assert_eq!(counters(&cache), (200, 2));
+
+        // One line per remaining entry, the latest recorded
+        let contents = fs::read_to_string(&index).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), 2);
+        for line in lines {
+            let (hash, source) = line.split_once('\t').unwrap();
+            assert_eq!(source, "C:\\new.jpg");
+            assert!(cache.entry_path(hash).is_some());
+        }
+    }
+
+    #[test]
+    fn clear_and_evict_leave_unrelated_files_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("thumbs");
+        fs::create_dir_all(base.join("ab")).unwrap();
+        fs::create_dir_all(base.join("docs")).unwrap();
+        fs::write(base.join("notes.txt"), b"keep").unwrap();
+        fs::write(base.join("docs").join("report.webp"), b"keep").unwrap();
+        // Right directory, but not a cache entry name
+        fs::write(base.join("ab").join("holiday.webp"), b"keep").unwrap();
+
+        let cache = ThumbnailCache::new(base.clone());
+        let keys: Vec<String> = (0..3)
+            .map(|i| ThumbnailCache::cache_key(&format!("C:\\{}.jpg", i), 1, 10, 256))
+            .collect();
+        for key in &keys {
+            cache.put(key, &[0u8; 100]).unwrap();
+        }
+        assert_eq!(counters(&cache), (300, 3));
+
+        cache.evict_to_limit(150).unwrap();
+        assert_eq!(counters(&cache), disk_totals(&cache));
+        cache.clear().unwrap();
+
+        assert_eq!(disk_totals(&cache), (0, 0));
+        assert!(base.join("notes.txt").is_file());
+        assert!(base.join("docs").join("report.webp").is_file());
+        assert!(base.join("ab").join("holiday.webp").is_file());
+    }
+
+    #[test]
+    fn ensure_writable_creates_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("nested").join("thumbs");
+
+        ThumbnailCache::ensure_writable(&target).unwrap();
+
+        assert!(target.is_dir());
+        assert_eq!(fs::read_dir(&target).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn purges_cache_from_older_key_format_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("thumbs");
+        let cache = ThumbnailCache::new(base.clone());
+        cache.put("aabbcc", b"old").unwrap();
+        // Metadata as written before the format field existed
+        fs::write(base.join(META_FILE), "3 1").unwrap();
+
+        let reopened = ThumbnailCache::new(base.clone());
+        assert!(reopened.purge_outdated_format().unwrap());
+        assert_eq!(reopened.get("aabbcc").unwrap(), None);
+        assert_eq!(counters(&reopened), (0, 0));
+
+        reopened.put("ddeeff", b"new").unwrap();
+        let current = ThumbnailCache::new(base);
+        assert!(!current.purge_outdated_format().unwrap());
+        assert_eq!(current.get("ddeeff").unwrap(), Some(b"new".to_vec()));
     }
-    Ok(())
 }
@@ -0,0 +1,109 @@
+//! Folder Collage
+//!
+//! Composes up to four image thumbnails into a 2×2 collage that stands in
+//! for a folder's shell icon.
+
+use super::com_worker::encode_webp;
+use super::generator::ThumbnailError;
+use image::{ImageFormat, RgbaImage, imageops};
+use std::path::{Path, PathBuf};
+
+/// Number of cells in a collage.
+pub const COLLAGE_TILES: usize = 4;
+
+/// Extensions of files a collage picks from.
+const IMAGE_EXTENSIONS: &[&str] = &[
+    "jpg", "jpeg", "jpe", "png", "gif", "bmp", "webp", "tif", "tiff", "heic", "heif", "avif",
+];
+
+/// Lists the image files directly inside `dir`, ordered by path.
+///
+/// Unreadable directories and entries are treated as empty.
+pub fn list_images(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut images: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().map(|t| t.is_file()).unwrap_or(false))
+        .map(|entry| entry.path())
+        .filter(|path| is_image(path))
+        .collect();
+    images.sort();
+    images
+}
+
+fn is_image(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| IMAGE_EXTENSIONS.contains(&ext.to_string_lossy().to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Composes WebP-encoded tiles into a `size`×`size` collage.
+///
+/// Cells fill left to right, top to bottom; each tile is scaled to fit its
+/// cell and centered. Cells without a tile stay transparent. Returns WebP bytes.
+pub fn compose_collage(tiles: &[Vec<u8>], size: u32) -> Result<Vec<u8>, ThumbnailError> {
+    let cell = (size / 2).max(1);
+    let mut canvas = RgbaImage::new(cell * 2, cell * 2);
+
+    for (index, tile) in tiles.iter().take(COLLAGE_TILES).enumerate() {
+        let image = image::load_from_memory_with_format(tile, ImageFormat::WebP)
+            .map_err(|e| ThumbnailError::Encoding(e.to_string()))?
+            .thumbnail(cell, cell)
+            .to_rgba8();
+        let index = index as u32;
+        let x = (index % 2) * cell + cell.saturating_sub(image.width()) / 2;
+        let y = (index / 2) * cell + cell.saturating_sub(image.height()) / 2;
+        imageops::overlay(&mut canvas, &image, x.into(), y.into());
+    }
+
+    encode_webp(canvas.as_raw(), canvas.width(), canvas.height())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    fn solid_tile(width: u32, height: u32, color: [u8; 4]) -> Vec<u8> {
+        let image = RgbaImage::from_pixel(width, height, Rgba(color));
+        encode_webp(image.as_raw(), width, height).unwrap()
+    }
+
+    #[test]
+    fn tiles_fill_cells_in_order_and_leave_the_rest_transparent() {
+        let red = [255, 0, 0, 255];
+        let blue = [0, 0, 255, 255];
+        let tiles = vec![solid_tile(8, 8, red), solid_tile(16, 8, blue)];
+
+        let collage = compose_collage(&tiles, 32).unwrap();
+        let image = image::load_from_memory_with_format(&collage, ImageFormat::WebP)
+            .unwrap()
+            .to_rgba8();
+
+        assert_eq!(image.dimensions(), (32, 32));
+        assert_eq!(image.get_pixel(8, 8).0, red);
+        // The wide tile is centered vertically in its cell
+        assert_eq!(image.get_pixel(24, 8).0, blue);
+        assert_eq!(image.get_pixel(24, 1).0[3], 0);
+        assert_eq!(image.get_pixel(8, 24).0[3], 0);
+    }
+
+    #[test]
+    fn list_images_skips_other_files_and_subdirectories() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["b.PNG", "a.jpg", "notes.txt"] {
+            std::fs::write(dir.path().join(name), b"").unwrap();
+        }
+        std::fs::create_dir(dir.path().join("c.jpg")).unwrap();
+
+        let names: Vec<_> = list_images(dir.path())
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+
+        assert_eq!(names, vec!["a.jpg", "b.PNG"]);
+    }
+}
@@ -40,7 +40,7 @@ impl ComWorker {
                 unsafe {
                     let hr = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
                     if let Err(e) = hr.ok() {
-                        eprintln!("COM worker {} failed to initialize: {}", index, e);
+                        log::error!("COM worker {} failed to initialize: {}", index, e);
                         // Drain remaining requests with error
                         while let Some(req) = rx.blocking_recv() {
                             let _ = req.response.send(Err(ThumbnailError::Com(e.clone())));
@@ -92,7 +92,11 @@ fn process_request(path: &PathBuf, size: u32) -> Result<Vec<u8>, ThumbnailError>
 }
 
 /// Encode RGBA pixel data as WebP.
-fn encode_webp(rgba_data: &[u8], width: u32, height: u32) -> Result<Vec<u8>, ThumbnailError> {
+pub(super) fn encode_webp(
+    rgba_data: &[u8],
+    width: u32,
+    height: u32,
+) -> Result<Vec<u8>, ThumbnailError> {
     let img: ImageBuffer<image::Rgba<u8>, _> =
         ImageBuffer::from_raw(width, height, rgba_data.to_vec())
             .ok_or_else(|| ThumbnailError::Encoding("Invalid image dimensions".into()))?;
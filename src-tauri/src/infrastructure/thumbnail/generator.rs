@@ -35,6 +35,12 @@ pub enum ThumbnailError {
 
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
+
+    #[error("Thumbnail cache relocation already in progress")]
+    RelocationInProgress,
+
+    #[error("Settings error: {0}")]
+    Settings(String),
 }
 
 /// Generate a thumbnail for the given file path at the specified size.
@@ -4,10 +4,14 @@
 //! disk caching, and a COM worker thread for async operation.
 
 mod cache;
+mod collage;
 mod com_worker;
 mod com_worker_pool;
 mod generator;
+mod resize;
 
 pub use cache::ThumbnailCache;
+pub use collage::{COLLAGE_TILES, compose_collage, list_images};
 pub use com_worker_pool::ComWorkerPool;
 pub use generator::ThumbnailError;
+pub use resize::downscale_webp;
@@ -0,0 +1,26 @@
+//! Thumbnail Resizing
+//!
+//! Derives smaller thumbnails from one that was already generated, so a
+//! request for several sizes only decodes its source once.
+
+use super::com_worker::encode_webp;
+use super::generator::ThumbnailError;
+use image::ImageFormat;
+
+/// Scales a WebP thumbnail to fit each of `sizes`, decoding it once.
+///
+/// Sizes larger than the source keep its dimensions. Returns WebP bytes
+/// per size, in the order given.
+pub fn downscale_webp(source: &[u8], sizes: &[u32]) -> Result<Vec<(u32, Vec<u8>)>, ThumbnailError> {
+    let image = image::load_from_memory_with_format(source, ImageFormat::WebP)
+        .map_err(|e| ThumbnailError::Encoding(e.to_string()))?;
+
+    sizes
+        .iter()
+        .map(|&size| {
+            let scaled = image.thumbnail(size, size).to_rgba8();
+            let webp = encode_webp(scaled.as_raw(), scaled.width(), scaled.height())?;
+            Ok((size, webp))
+        })
+        .collect()
+}
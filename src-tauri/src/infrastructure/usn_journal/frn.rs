@@ -10,10 +10,25 @@ use windows::Win32::Storage::FileSystem::{
 };
 use windows::core::HSTRING;
 
+/// Volume serial number and File Reference Number, which together identify a
+/// file independently of its path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileIdentity {
+    pub volume_serial: u32,
+    pub frn: u64,
+}
+
 /// Gets the NTFS File Reference Number for a file path.
 ///
 /// Returns `Ok(None)` if the file does not exist.
 pub fn get_file_reference_number(path: &str) -> Result<Option<u64>, DomainError> {
+    Ok(get_file_identity(path)?.map(|id| id.frn))
+}
+
+/// Gets the volume serial number and File Reference Number for a file path.
+///
+/// Returns `Ok(None)` if the file does not exist.
+pub fn get_file_identity(path: &str) -> Result<Option<FileIdentity>, DomainError> {
     let path_wide = HSTRING::from(path);
 
     // Open with minimal access — dwDesiredAccess = 0
@@ -46,5 +61,8 @@ pub fn get_file_reference_number(path: &str) -> Result<Option<u64>, DomainError>
     }
 
     let frn = (info.nFileIndexHigh as u64) << 32 | info.nFileIndexLow as u64;
-    Ok(Some(frn))
+    Ok(Some(FileIdentity {
+        volume_serial: info.dwVolumeSerialNumber,
+        frn,
+    }))
 }
@@ -7,7 +7,9 @@ mod path_resolver;
 mod reader;
 mod volume;
 
-pub use frn::get_file_reference_number;
+pub use frn::{FileIdentity, get_file_identity, get_file_reference_number};
 pub use path_resolver::resolve_path_by_frn;
 pub use reader::{RawUsnRecord, read_journal_records};
-pub use volume::{VolumeHandle, is_ntfs};
+pub use volume::{
+    JournalConfig, VolumeHandle, filesystem_name, is_ntfs, is_usn_capable, usn_capable_drives,
+};
@@ -4,17 +4,24 @@
 
 use crate::domain::errors::DomainError;
 use std::mem::MaybeUninit;
-use windows::Win32::Foundation::{CloseHandle, HANDLE};
+use windows::Win32::Foundation::{CloseHandle, GENERIC_READ, GENERIC_WRITE, HANDLE};
 use windows::Win32::Storage::FileSystem::{
     CreateFileW, FILE_SHARE_READ, FILE_SHARE_WRITE, GetVolumeInformationW, OPEN_EXISTING,
 };
 use windows::Win32::System::IO::DeviceIoControl;
-use windows::Win32::System::Ioctl::{FSCTL_QUERY_USN_JOURNAL, USN_JOURNAL_DATA_V0};
+use windows::Win32::System::Ioctl::{
+    CREATE_USN_JOURNAL_DATA, FSCTL_CREATE_USN_JOURNAL, FSCTL_QUERY_USN_JOURNAL, USN_JOURNAL_DATA_V0,
+};
 use windows::core::HSTRING;
 
 /// Minimum access right for opening a volume handle without admin privileges.
 const FILE_TRAVERSE: u32 = 0x20;
 
+/// Accepted journal maximum sizes, in MiB.
+const JOURNAL_MAX_SIZE_MB: std::ops::RangeInclusive<u32> = 4..=512;
+
+const MIB: u64 = 1024 * 1024;
+
 /// RAII wrapper for an NTFS volume handle.
 pub struct VolumeHandle {
     handle: HANDLE,
@@ -39,11 +46,69 @@ pub struct UsnJournalInfo {
     pub journal_id: u64,
     pub first_usn: i64,
     pub next_usn: i64,
+    /// Size the journal is trimmed back to, in bytes.
+    pub maximum_size: u64,
+    /// Size added to or trimmed from the journal at a time, in bytes.
+    pub allocation_delta: u64,
+}
+
+/// Validated sizes for creating (or resizing) a USN Journal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JournalConfig {
+    max_size_mb: u32,
+    allocation_delta_mb: u32,
+}
+
+impl JournalConfig {
+    /// Default maximum size, in MiB.
+    pub const DEFAULT_MAX_SIZE_MB: u32 = 32;
+    /// Default allocation delta, in MiB.
+    pub const DEFAULT_ALLOCATION_DELTA_MB: u32 = 8;
+
+    /// Validates that the maximum size is 4-512 MiB and the allocation delta
+    /// is non-zero and smaller than it.
+    pub fn new(max_size_mb: u32, allocation_delta_mb: u32) -> Result<Self, DomainError> {
+        if !JOURNAL_MAX_SIZE_MB.contains(&max_size_mb) {
+            return Err(DomainError::ValidationError(format!(
+                "Journal size must be between {} and {} MB",
+                JOURNAL_MAX_SIZE_MB.start(),
+                JOURNAL_MAX_SIZE_MB.end()
+            )));
+        }
+        if allocation_delta_mb == 0 || allocation_delta_mb >= max_size_mb {
+            return Err(DomainError::ValidationError(format!(
+                "Allocation delta must be between 1 and {} MB",
+                max_size_mb - 1
+            )));
+        }
+        Ok(Self {
+            max_size_mb,
+            allocation_delta_mb,
+        })
+    }
+
+    pub fn max_size_bytes(&self) -> u64 {
+        u64::from(self.max_size_mb) * MIB
+    }
+
+    pub fn allocation_delta_bytes(&self) -> u64 {
+        u64::from(self.allocation_delta_mb) * MIB
+    }
 }
 
 impl VolumeHandle {
     /// Opens a volume handle with FILE_TRAVERSE access (no admin required).
     pub fn open(drive_letter: char) -> Result<Self, DomainError> {
+        Self::open_with_access(drive_letter, FILE_TRAVERSE)
+    }
+
+    /// Opens a volume handle with read/write access, as creating a journal
+    /// requires. Fails unless the process runs elevated.
+    pub fn open_writable(drive_letter: char) -> Result<Self, DomainError> {
+        Self::open_with_access(drive_letter, GENERIC_READ.0 | GENERIC_WRITE.0)
+    }
+
+    fn open_with_access(drive_letter: char, access: u32) -> Result<Self, DomainError> {
         let drive = drive_letter.to_ascii_uppercase();
         let path = format!("\\\\.\\{}:", drive);
         let path_wide = HSTRING::from(&path);
@@ -51,7 +116,7 @@ impl VolumeHandle {
         let handle = unsafe {
             CreateFileW(
                 &path_wide,
-                FILE_TRAVERSE,
+                access,
                 FILE_SHARE_READ | FILE_SHARE_WRITE,
                 None,
                 OPEN_EXISTING,
@@ -71,50 +136,40 @@ impl VolumeHandle {
 
     /// Queries the USN Journal state for this volume.
     pub fn query_journal(&self) -> Result<UsnJournalInfo, DomainError> {
-        let mut journal_data = MaybeUninit::<USN_JOURNAL_DATA_V0>::uninit();
-        let mut bytes_returned = 0u32;
-
-        let result = unsafe {
-            DeviceIoControl(
-                self.handle,
-                FSCTL_QUERY_USN_JOURNAL,
-                None,
-                0,
-                Some(journal_data.as_mut_ptr() as *mut _),
-                std::mem::size_of::<USN_JOURNAL_DATA_V0>() as u32,
-                Some(&mut bytes_returned),
-                None,
-            )
-        };
-
-        if let Err(e) = result {
-            let code = e.code().0 as u32;
-            // HRESULT for ERROR_JOURNAL_NOT_ACTIVE (1179) = 0x8007049B
-            // HRESULT for ERROR_JOURNAL_DELETE_IN_PROGRESS (1178) = 0x8007049A
-            if code == 0x8007_049B {
-                return Err(DomainError::UsnJournalError(format!(
-                    "Journal not active on {}:",
-                    self.drive_letter
-                )));
+        query_journal_with(self.drive_letter, |journal_data| {
+            let mut bytes_returned = 0u32;
+            unsafe {
+                DeviceIoControl(
+                    self.handle,
+                    FSCTL_QUERY_USN_JOURNAL,
+                    None,
+                    0,
+                    Some(journal_data as *mut USN_JOURNAL_DATA_V0 as *mut _),
+                    std::mem::size_of::<USN_JOURNAL_DATA_V0>() as u32,
+                    Some(&mut bytes_returned),
+                    None,
+                )
             }
-            if code == 0x8007_049A {
-                return Err(DomainError::UsnJournalError(format!(
-                    "Journal deletion in progress on {}:",
-                    self.drive_letter
-                )));
-            }
-            return Err(DomainError::UsnJournalError(format!(
-                "Failed to query USN Journal on {}: {}",
-                self.drive_letter, e
-            )));
-        }
-
-        let data = unsafe { journal_data.assume_init() };
+        })
+    }
 
-        Ok(UsnJournalInfo {
-            journal_id: data.UsnJournalID,
-            first_usn: data.FirstUsn,
-            next_usn: data.NextUsn,
+    /// Creates the USN Journal with the given sizes, or resizes an active one.
+    /// The handle must come from [`Self::open_writable`].
+    pub fn create_journal_with_config(&self, config: &JournalConfig) -> Result<(), DomainError> {
+        create_journal_with(self.drive_letter, config, |data| {
+            let mut bytes_returned = 0u32;
+            unsafe {
+                DeviceIoControl(
+                    self.handle,
+                    FSCTL_CREATE_USN_JOURNAL,
+                    Some(data as *const CREATE_USN_JOURNAL_DATA as *const _),
+                    std::mem::size_of::<CREATE_USN_JOURNAL_DATA>() as u32,
+                    None,
+                    0,
+                    Some(&mut bytes_returned),
+                    None,
+                )
+            }
         })
     }
 
@@ -124,8 +179,78 @@ impl VolumeHandle {
     }
 }
 
+/// Runs `FSCTL_QUERY_USN_JOURNAL` through `ioctl`, which fills in the
+/// journal data, and maps its result.
+fn query_journal_with<F>(drive_letter: char, ioctl: F) -> Result<UsnJournalInfo, DomainError>
+where
+    F: FnOnce(&mut USN_JOURNAL_DATA_V0) -> windows::core::Result<()>,
+{
+    let mut journal_data = MaybeUninit::<USN_JOURNAL_DATA_V0>::zeroed();
+
+    // SAFETY: all-zero bytes are a valid USN_JOURNAL_DATA_V0 (plain integers)
+    if let Err(e) = ioctl(unsafe { journal_data.assume_init_mut() }) {
+        let code = e.code().0 as u32;
+        // HRESULT for ERROR_JOURNAL_NOT_ACTIVE (1179) = 0x8007049B
+        // HRESULT for ERROR_JOURNAL_DELETE_IN_PROGRESS (1178) = 0x8007049A
+        if code == 0x8007_049B {
+            return Err(DomainError::UsnJournalError(format!(
+                "Journal not active on {}:",
+                drive_letter
+            )));
+        }
+        if code == 0x8007_049A {
+            return Err(DomainError::UsnJournalError(format!(
+                "Journal deletion in progress on {}:",
+                drive_letter
+            )));
+        }
+        return Err(DomainError::UsnJournalError(format!(
+            "Failed to query USN Journal on {}: {}",
+            drive_letter, e
+        )));
+    }
+
+    // SAFETY: zero-initialized above, and the ioctl only writes whole fields
+    let data = unsafe { journal_data.assume_init() };
+
+    Ok(UsnJournalInfo {
+        journal_id: data.UsnJournalID,
+        first_usn: data.FirstUsn,
+        next_usn: data.NextUsn,
+        maximum_size: data.MaximumSize,
+        allocation_delta: data.AllocationDelta,
+    })
+}
+
+/// Runs `FSCTL_CREATE_USN_JOURNAL` through `ioctl` with the sizes of `config`.
+fn create_journal_with<F>(
+    drive_letter: char,
+    config: &JournalConfig,
+    ioctl: F,
+) -> Result<(), DomainError>
+where
+    F: FnOnce(&CREATE_USN_JOURNAL_DATA) -> windows::core::Result<()>,
+{
+    let data = CREATE_USN_JOURNAL_DATA {
+        MaximumSize: config.max_size_bytes(),
+        AllocationDelta: config.allocation_delta_bytes(),
+    };
+    ioctl(&data).map_err(|e| {
+        DomainError::UsnJournalError(format!(
+            "Failed to create USN Journal on {}: {}",
+            drive_letter, e
+        ))
+    })
+}
+
 /// Checks whether the given drive letter hosts an NTFS (or ReFS) file system.
 pub fn is_ntfs(drive_letter: char) -> Result<bool, DomainError> {
+    Ok(filesystem_name(drive_letter).is_some_and(|fs| is_usn_capable(&fs)))
+}
+
+/// Returns the file system name of a drive (e.g. `NTFS`, `FAT32`), or None if
+/// the drive does not exist or is inaccessible.
+pub fn filesystem_name(drive_letter: char) -> Option<String> {
     let drive = drive_letter.to_ascii_uppercase();
     let root = format!("{}:\\", drive);
     let root_wide = HSTRING::from(&root);
@@ -136,15 +261,98 @@ pub fn is_ntfs(drive_letter: char) -> Result<bool, DomainError> {
     };
 
     if ok.is_err() {
-        // Drive may not exist or be inaccessible
-        return Ok(false);
+        return None;
     }
 
     let len = fs_name_buf
         .iter()
         .position(|&c| c == 0)
         .unwrap_or(fs_name_buf.len());
-    let fs_name = String::from_utf16_lossy(&fs_name_buf[..len]);
+    Some(String::from_utf16_lossy(&fs_name_buf[..len]))
+}
 
-    Ok(fs_name == "NTFS" || fs_name == "ReFS")
+/// Returns true if volumes with this file system keep a USN Journal.
+pub fn is_usn_capable(filesystem: &str) -> bool {
+    filesystem == "NTFS" || filesystem == "ReFS"
+}
+
+/// Returns the letters of all drives that keep a USN Journal.
+pub fn usn_capable_drives() -> Vec<char> {
+    ('A'..='Z')
+        .filter(|&c| is_ntfs(c).unwrap_or(false))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use windows::core::{Error, HRESULT};
+
+    #[test]
+    fn only_ntfs_and_refs_are_usn_capable() {
+        assert!(is_usn_capable("NTFS"));
+        assert!(is_usn_capable("ReFS"));
+        assert!(!is_usn_capable("FAT32"));
+        assert!(!is_usn_capable("exFAT"));
+        assert!(!is_usn_capable(""));
+    }
+
+    #[test]
+    fn journal_config_validates_sizes() {
+        let config = JournalConfig::new(
+            JournalConfig::DEFAULT_MAX_SIZE_MB,
+            JournalConfig::DEFAULT_ALLOCATION_DELTA_MB,
+        )
+        .unwrap();
+        assert_eq!(config.max_size_bytes(), 32 * MIB);
+        assert_eq!(config.allocation_delta_bytes(), 8 * MIB);
+
+        assert!(JournalConfig::new(4, 1).is_ok());
+        assert!(JournalConfig::new(512, 511).is_ok());
+        assert!(JournalConfig::new(3, 1).is_err());
+        assert!(JournalConfig::new(513, 8).is_err());
+        assert!(JournalConfig::new(32, 32).is_err());
+        assert!(JournalConfig::new(32, 0).is_err());
+    }
+
+    #[test]
+    fn create_passes_sizes_in_bytes_and_maps_failures() {
+        let config = JournalConfig::new(64, 16).unwrap();
+        let mut sent = None;
+        create_journal_with('C', &config, |data| {
+            sent = Some((data.MaximumSize, data.AllocationDelta));
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(sent, Some((64 * MIB, 16 * MIB)));
+
+        // ERROR_ACCESS_DENIED
+        let denied = create_journal_with('C', &config, |_| {
+            Err(Error::from_hresult(HRESULT(0x8007_0005_u32 as i32)))
+        });
+        assert!(matches!(denied, Err(DomainError::UsnJournalError(m)) if m.contains("C:")));
+    }
+
+    #[test]
+    fn query_reports_sizes_and_inactive_journal() {
+        let info = query_journal_with('D', |data| {
+            data.UsnJournalID = 7;
+            data.NextUsn = 100;
+            data.MaximumSize = 32 * MIB;
+            data.AllocationDelta = 8 * MIB;
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(info.journal_id, 7);
+        assert_eq!(info.next_usn, 100);
+        assert_eq!(info.maximum_size, 32 * MIB);
+        assert_eq!(info.allocation_delta, 8 * MIB);
+
+        let inactive = query_journal_with('D', |_| {
+            Err(Error::from_hresult(HRESULT(0x8007_049B_u32 as i32)))
+        });
+        assert!(
+            matches!(inactive, Err(DomainError::UsnJournalError(m)) if m.contains("not active"))
+        );
+    }
 }
@@ -9,7 +9,10 @@ mod error;
 mod infrastructure;
 mod state;
 
+use application::cancellation::CancellationToken;
+use application::services::{THUMB_SIZE_CEILING, clamp_thumb_size};
 use infrastructure::persistence::init_database;
+use infrastructure::thumbnail::ThumbnailError;
 use state::{AppConfig, AppState};
 use tauri::Manager;
 use tauri::http::Response;
@@ -37,6 +40,13 @@ pub fn run() {
                 "Failed to create app data directory - insufficient permissions or disk full",
             );
 
+            // Logging is best-effort: the app still runs without a log file.
+            // With no `tracing` subscriber installed, spans and events reach
+            // the same file through `tracing`'s `log` feature.
+            if let Err(e) = infrastructure::logging::init(&app_data_dir) {
+                eprintln!("Failed to initialize logging: {}", e);
+            }
+
             let db_path = app_data_dir.join("constellation.db");
 
             // Initialize database pool
@@ -53,45 +63,34 @@ pub fn run() {
                 db_path: db_path.to_string_lossy().to_string(),
             };
 
-            // Create and manage app state
-            // SAFETY: AppState initialization is critical for application functionality.
-            // It reads settings from DB and spawns COM worker threads.
-            // If this fails, the application cannot proceed.
-            let app_state = tauri::async_runtime::block_on(async {
-                AppState::new(pool, config, app_data_dir.clone()).await
-            })
-            .expect("Failed to initialize application state - check database and system resources");
-            app.manage(app_state);
-
-            // Spawn background cache eviction on startup
-            let thumb_service = app.state::<AppState>().thumbnail_service.clone();
-            tauri::async_runtime::spawn(async move {
-                if let Err(e) = thumb_service.evict_cache().await {
-                    eprintln!("Background cache eviction failed: {}", e);
-                }
-            });
+            // Create and manage app state. The thumbnail service (COM worker
+            // threads, cache eviction) is created on the first thumbnail request.
+            app.manage(AppState::new(pool, config, app_data_dir.clone()));
+
+            let log_level = tauri::async_runtime::block_on(
+                app.state::<AppState>()
+                    .settings_service
+                    .get(infrastructure::logging::LOG_LEVEL_KEY),
+            )
+            .ok()
+            .flatten();
+            infrastructure::logging::apply_level(log_level.as_deref());
+            log::info!("Constellation {} started", env!("CARGO_PKG_VERSION"));
 
             // Auto-refresh USN index on startup if enabled
-            let refresh_service = app.state::<AppState>().usn_refresh_service.clone();
+            let app_handle = app.handle().clone();
             let settings = app.state::<AppState>().settings_service.clone();
 
             tauri::async_runtime::spawn(async move {
-                let auto_refresh = settings
-                    .get("usn_auto_refresh")
-                    .await
-                    .ok()
-                    .flatten()
-                    .unwrap_or_else(|| "false".to_string());
-
-                if auto_refresh == "true" {
-                    let drives: Vec<char> = ('A'..='Z')
-                        .filter(|&c| {
-                            crate::infrastructure::usn_journal::is_ntfs(c).unwrap_or(false)
-                        })
-                        .collect();
-
-                    if let Err(e) = refresh_service.refresh(&drives).await {
-                        eprintln!("Auto USN refresh failed: {}", e);
+                if settings.get_bool("usn_auto_refresh", false).await {
+                    let refresh_service = app_handle.state::<AppState>().usn_refresh_service();
+                    let drives = crate::infrastructure::usn_journal::usn_capable_drives();
+
+                    if let Err(e) = refresh_service
+                        .refresh(&drives, &CancellationToken::new())
+                        .await
+                    {
+                        log::error!("Auto USN refresh failed: {}", e);
                     }
                 }
             });
@@ -102,77 +101,172 @@ pub fn run() {
             // Tag Group commands
             commands::tag_groups::create_tag_group,
             commands::tag_groups::get_tag_groups,
+            commands::tag_groups::set_tag_group_sort_mode,
             commands::tag_groups::update_tag_group,
             commands::tag_groups::delete_tag_group,
             commands::tag_groups::reorder_tag_groups,
+            commands::tag_groups::normalize_tag_group_order,
+            commands::tag_groups::get_exclusive_violations,
             // Tag commands
             commands::tags::create_tag,
             commands::tags::get_tags_by_group,
             commands::tags::get_all_tags,
+            commands::tags::get_all_tags_paged,
+            commands::tags::get_all_tags_by_group_paged,
             commands::tags::update_tag,
             commands::tags::delete_tag,
+            commands::tags::clear_tag_usage,
             commands::tags::get_tag_usage_counts,
+            commands::tags::get_tag_cooccurrence,
             commands::tags::search_tags,
+            commands::tags::get_tags_among_items,
+            commands::tags::get_tag_suggestions,
+            commands::tags::set_tag_suggestions,
             commands::tags::merge_tags,
+            commands::tags::split_tag,
+            commands::tags::move_tag_to_group,
             // Item commands
             commands::items::create_item,
+            commands::items::create_items,
+            commands::items::refresh_item_metadata,
             commands::items::get_item,
+            commands::items::get_item_with_tag_count,
             commands::items::get_item_by_path,
             commands::items::get_items_by_paths,
+            commands::items::check_items_by_paths,
+            commands::items::rebase_item_paths,
+            commands::items::bulk_rename_path_prefix,
+            commands::items::normalize_item_paths,
+            commands::items::soft_delete_items_by_tag,
+            commands::items::restore_items_by_tag,
             commands::items::update_item,
+            commands::items::set_rating,
+            commands::items::refresh_item_frn,
+            commands::items::get_recently_tagged_items,
             commands::items::delete_item,
             commands::items::add_tag_to_item,
+            commands::items::enqueue_tag_for_item,
+            commands::items::enqueue_create_item,
+            commands::items::flush_item_writes,
             commands::items::remove_tag_from_item,
             commands::items::get_tags_for_item,
             commands::items::get_tags_for_items,
+            commands::items::get_items_with_tags,
+            commands::items::get_items_in_directory_with_tags,
+            commands::items::get_inherited_tags,
             commands::items::update_item_tags,
             commands::items::batch_add_tag_to_items,
+            commands::items::tag_directory,
             commands::items::batch_remove_tag_from_items,
             commands::items::get_common_tags_for_paths,
             // Tag Template commands
             commands::tag_templates::create_tag_template,
             commands::tag_templates::get_tag_templates,
             commands::tag_templates::apply_tag_template,
+            commands::tag_templates::preview_tag_template,
+            commands::tag_templates::preview_apply_template,
+            commands::tag_templates::get_applicable_templates,
             commands::tag_templates::delete_tag_template,
             commands::tag_templates::update_tag_template,
+            commands::auto_tag_rules::get_auto_tag_rules,
+            commands::auto_tag_rules::create_auto_tag_rule,
+            commands::auto_tag_rules::update_auto_tag_rule,
+            commands::auto_tag_rules::delete_auto_tag_rule,
+            // Collection commands
+            commands::collections::create_collection,
+            commands::collections::get_collections,
+            commands::collections::get_collections_with_counts,
+            commands::collections::update_collection,
+            commands::collections::delete_collection,
+            commands::collections::reorder_collections,
             // File System commands
             commands::filesystem::get_drives,
+            commands::filesystem::get_indexable_drives,
             commands::filesystem::read_directory,
+            commands::filesystem::get_directory_size,
+            commands::filesystem::get_directory_entry_count,
             commands::filesystem::get_file_metadata,
             commands::filesystem::open_file_external,
             commands::filesystem::reveal_in_explorer,
             // Search commands
+            commands::search::search,
             commands::search::search_items_by_tags_and,
             commands::search::search_items_by_tags_or,
             commands::search::search_items_by_filename,
+            commands::search::get_items_by_tag,
+            commands::search::get_similar_items,
+            commands::search::get_recommendations,
             commands::search::search_items,
+            commands::search::search_items_excluding_tags,
+            commands::search::search_items_mixed,
+            commands::search::search_items_with_exclusions,
+            commands::search::search_items_by_time_window,
+            commands::search::get_recent_changes,
             commands::search::search_cql,
+            commands::search::get_group_items,
+            commands::search::build_cql,
+            commands::search::parse_to_spec,
+            commands::search::cancel_search,
             commands::search::get_recent_search_history,
             commands::search::delete_search_history,
             commands::search::clear_search_history,
+            commands::search::get_search_history_count,
+            commands::search::set_search_history_limit,
             // Settings commands
             commands::settings::get_all_settings,
             commands::settings::update_setting,
             commands::settings::reset_setting,
+            commands::settings::reset_settings,
+            commands::settings::get_recent_logs,
+            commands::settings::explain_cql,
+            commands::settings::benchmark_cql,
+            commands::settings::rebuild_indexes,
+            commands::settings::reset_onboarding,
+            commands::settings::get_library_stats,
+            commands::settings::get_schema_version,
             // Thumbnail commands
             commands::thumbnails::get_cache_stats,
             commands::thumbnails::clear_thumbnail_cache,
+            commands::thumbnails::clear_thumbnail_memory_cache,
+            commands::thumbnails::invalidate_thumbnail,
+            commands::thumbnails::get_cached_thumbnail_path,
+            commands::thumbnails::get_thumbnails,
+            commands::thumbnails::set_cache_directory,
             // File Monitor commands
             commands::file_monitor::refresh_file_index,
+            commands::file_monitor::backfill_file_reference_numbers,
             commands::file_monitor::check_usn_support,
             commands::file_monitor::get_usn_drive_status,
             commands::file_monitor::enable_usn_journal,
+            commands::file_monitor::enable_usn_journal_with_config,
+            commands::file_monitor::get_journal_config,
+            // Operation commands
+            commands::operations::cancel_operation,
+            commands::data::export_tag_vocabulary,
+            commands::data::export_html_gallery,
+            // Diagnostics commands
+            commands::diagnostics::execute_readonly_sql,
         ])
-        .run(tauri::generate_context!())
+        .build(tauri::generate_context!())
         // SAFETY: This is the main entry point. If Tauri runtime fails to start,
         // there is no recovery path - the application cannot run.
-        .expect("error while running tauri application");
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            // The thumbnail cache writes its counters lazily; save the last changes
+            if let tauri::RunEvent::Exit = event {
+                app.state::<AppState>().flush_thumbnail_cache();
+            }
+        });
 }
 
+/// Header naming why a `thumb://` request failed; see [`ThumbError`].
+const THUMB_ERROR_HEADER: &str = "X-Thumb-Error";
+
 /// Handle `thumb://localhost/{encoded_path}?size={size}` requests.
 ///
 /// URL format: `thumb://localhost/{url_encoded_path}?size={thumb_size}`
-/// Returns WebP image bytes with aggressive caching headers.
+/// Returns WebP image bytes with aggressive caching headers. Failures carry
+/// a plain-text message and an `X-Thumb-Error` code.
 async fn handle_thumb_request(
     app: &tauri::AppHandle,
     request: &tauri::http::Request<Vec<u8>>,
@@ -182,13 +276,13 @@ async fn handle_thumb_request(
     // Parse path and query from URI
     let parsed = match parse_thumb_uri(&uri) {
         Some(p) => p,
-        None => return thumb_error_response(400, "Invalid thumbnail URL"),
+        None => return thumb_error_response(ThumbError::InvalidUrl, "Invalid thumbnail URL"),
     };
 
     // Read file metadata for cache key
     let metadata = match std::fs::metadata(&parsed.path) {
         Ok(m) => m,
-        Err(_) => return thumb_error_response(404, "File not found"),
+        Err(_) => return thumb_error_response(ThumbError::NotFound, "File not found"),
     };
 
     let mtime = metadata
@@ -202,8 +296,14 @@ async fn handle_thumb_request(
     // Get thumbnail service from app state
     let state = app.state::<AppState>();
 
-    match state
-        .thumbnail_service
+    let thumbnail_service = match state.thumbnail_service().await {
+        Ok(service) => service,
+        Err(_) => {
+            return thumb_error_response(ThumbError::Unavailable, "Thumbnail service unavailable");
+        }
+    };
+
+    match thumbnail_service
         .get_thumbnail(&parsed.path, mtime, file_size, parsed.size)
         .await
     {
@@ -212,8 +312,13 @@ async fn handle_thumb_request(
             .header("Content-Type", "image/webp")
             .header("Cache-Control", "public, max-age=31536000, immutable")
             .body(webp_bytes)
-            .unwrap_or_else(|_| thumb_error_response(500, "Failed to build response")),
-        Err(_) => thumb_error_response(404, "Failed to generate thumbnail"),
+            .unwrap_or_else(|_| {
+                thumb_error_response(ThumbError::GenerateFailed, "Failed to build response")
+            }),
+        Err(ThumbnailError::FileNotFound(_)) => {
+            thumb_error_response(ThumbError::NotFound, "File not found")
+        }
+        Err(_) => thumb_error_response(ThumbError::GenerateFailed, "Failed to generate thumbnail"),
     }
 }
 
@@ -224,6 +329,8 @@ struct ThumbUriParsed {
 
 /// Parse thumb URI into path and size.
 /// Handles both `http://thumb.localhost/` (WebView2) and `thumb://localhost/` formats.
+/// The size is clamped to [`THUMB_SIZE_CEILING`]; the service clamps it further
+/// to the `thumbnail_max_size` setting.
 fn parse_thumb_uri(uri: &str) -> Option<ThumbUriParsed> {
     let after_scheme = uri
         .strip_prefix("http://thumb.localhost/")
@@ -246,6 +353,7 @@ fn parse_thumb_uri(uri: &str) -> Option<ThumbUriParsed> {
     let size = parse_query_param(query, "size")
         .and_then(|s| s.parse::<u32>().ok())
         .unwrap_or(256);
+    let size = clamp_thumb_size(size, THUMB_SIZE_CEILING);
 
     Some(ThumbUriParsed { path, size })
 }
@@ -282,10 +390,70 @@ fn parse_query_param<'a>(query: &'a str, key: &str) -> Option<&'a str> {
     None
 }
 
-fn thumb_error_response(status: u16, msg: &str) -> Response<Vec<u8>> {
+/// Why a thumbnail request failed, sent as the `X-Thumb-Error` header so the
+/// UI can pick a placeholder without parsing the message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ThumbError {
+    InvalidUrl,
+    NotFound,
+    Unavailable,
+    GenerateFailed,
+}
+
+impl ThumbError {
+    fn status(self) -> u16 {
+        match self {
+            Self::InvalidUrl => 400,
+            Self::NotFound => 404,
+            Self::Unavailable => 503,
+            Self::GenerateFailed => 500,
+        }
+    }
+
+    fn code(self) -> &'static str {
+        match self {
+            Self::InvalidUrl => "invalid_url",
+            Self::NotFound => "not_found",
+            Self::Unavailable => "unavailable",
+            Self::GenerateFailed => "generate_failed",
+        }
+    }
+}
+
+fn thumb_error_response(error: ThumbError, msg: &str) -> Response<Vec<u8>> {
     Response::builder()
-        .status(status)
+        .status(error.status())
         .header("Content-Type", "text/plain")
+        .header(THUMB_ERROR_HEADER, error.code())
+        // Lets the WebView read the header from `fetch` responses
+        .header("Access-Control-Expose-Headers", THUMB_ERROR_HEADER)
         .body(msg.as_bytes().to_vec())
         .unwrap_or_else(|_| Response::builder().status(500).body(Vec::new()).unwrap())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thumb_errors_carry_status_and_code() {
+        let response = thumb_error_response(ThumbError::NotFound, "File not found");
+        assert_eq!(response.status(), 404);
+        assert_eq!(response.headers()[THUMB_ERROR_HEADER], "not_found");
+        assert_eq!(response.body(), b"File not found");
+
+        assert!(parse_thumb_uri("http://thumb.localhost/?size=256").is_none());
+    }
+
+    #[test]
+    fn thumb_uri_size_is_clamped() {
+        let size = |uri: &str| parse_thumb_uri(uri).unwrap().size;
+        assert_eq!(size("http://thumb.localhost/C%3A%5Ca.jpg?size=512"), 512);
+        assert_eq!(size("http://thumb.localhost/C%3A%5Ca.jpg"), 256);
+        assert_eq!(
+            size("thumb://localhost/C%3A%5Ca.jpg?size=100000"),
+            THUMB_SIZE_CEILING
+        );
+        assert_eq!(size("http://thumb.localhost/C%3A%5Ca.jpg?size=0"), 1);
+    }
+}
@@ -2,21 +2,26 @@
 //!
 //! Holds all services and configuration for the application.
 
+use crate::application::cancellation::CancellationRegistry;
+use crate::application::recent_directories::RecentDirectories;
 use crate::application::services::{
-    ItemService, SearchService, SettingsService, TagGroupService, TagService, TagTemplateService,
+    AutoTagRuleService, CollectionService, GalleryExportService, ItemService, SearchService,
+    SettingsService, SimilarityService, TagGroupService, TagService, TagTemplateService,
     ThumbnailService, UsnRefreshService,
 };
 use crate::domain::repositories::{
-    ItemRepository, SettingsRepository, TagGroupRepository, TagRepository, TagTemplateRepository,
+    AutoTagRuleRepository, CollectionRepository, ItemRepository, SettingsRepository,
+    TagGroupRepository, TagRepository, TagTemplateRepository,
 };
 use crate::error::AppError;
 use crate::infrastructure::persistence::{
-    SqliteItemRepository, SqliteSearchHistoryRepository, SqliteSearchRepository,
-    SqliteSettingsRepository, SqliteTagGroupRepository, SqliteTagRepository,
-    SqliteTagTemplateRepository,
+    SqliteAutoTagRuleRepository, SqliteCollectionRepository, SqliteItemRepository,
+    SqliteSearchHistoryRepository, SqliteSearchRepository, SqliteSettingsRepository,
+    SqliteTagGroupRepository, SqliteTagRepository, SqliteTagTemplateRepository,
 };
 use deadpool_sqlite::Pool;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
+use tokio::sync::OnceCell;
 
 #[derive(Clone)]
 #[allow(dead_code)]
@@ -35,22 +40,32 @@ pub struct AppState {
     pub tag_service: Arc<TagService>,
     pub tag_group_service: Arc<TagGroupService>,
     pub tag_template_service: Arc<TagTemplateService>,
+    pub auto_tag_rule_service: Arc<AutoTagRuleService>,
+    /// Also caches collection counts for a few seconds
+    pub collection_service: Arc<CollectionService>,
     pub search_service: Arc<SearchService>,
+    pub similarity_service: Arc<SimilarityService>,
+    pub gallery_export_service: Arc<GalleryExportService>,
     pub settings_service: Arc<SettingsService>,
-    pub thumbnail_service: Arc<ThumbnailService>,
-    pub usn_refresh_service: Arc<UsnRefreshService>,
+
+    /// Running cancellable operations, see `cancel_operation`
+    pub cancellation_registry: CancellationRegistry,
+    /// Directories opened by `read_directory`, warmed by the thumbnail service
+    pub recent_directories: RecentDirectories,
+
+    // Created on first use, see the accessors below
+    pool: Arc<Pool>,
+    item_repo: Arc<dyn ItemRepository>,
+    thumbnail_service: OnceCell<Arc<ThumbnailService>>,
+    usn_refresh_service: OnceLock<Arc<UsnRefreshService>>,
 }
 
 impl AppState {
-    /// Create application state with all services.
+    /// Create application state.
     ///
-    /// Async because ThumbnailService reads settings to configure
-    /// worker pool size and concurrency limits.
-    pub async fn new(
-        pool: Pool,
-        config: AppConfig,
-        app_data_dir: std::path::PathBuf,
-    ) -> Result<Self, AppError> {
+    /// The thumbnail and USN refresh services are not built here; they are
+    /// created by their accessors the first time a command needs them.
+    pub fn new(pool: Pool, config: AppConfig, app_data_dir: std::path::PathBuf) -> Self {
         let pool = Arc::new(pool);
 
         // Create repositories
@@ -60,43 +75,161 @@ impl AppState {
             Arc::new(SqliteTagGroupRepository::new(pool.clone()));
         let tag_template_repo: Arc<dyn TagTemplateRepository> =
             Arc::new(SqliteTagTemplateRepository::new(pool.clone()));
+        let auto_tag_rule_repo: Arc<dyn AutoTagRuleRepository> =
+            Arc::new(SqliteAutoTagRuleRepository::new(pool.clone()));
+        let collection_repo: Arc<dyn CollectionRepository> =
+            Arc::new(SqliteCollectionRepository::new(pool.clone()));
         let search_repo = Arc::new(SqliteSearchRepository::new(pool.clone()));
         let search_history_repo = Arc::new(SqliteSearchHistoryRepository::new(pool.clone()));
         let settings_repo: Arc<dyn SettingsRepository> =
             Arc::new(SqliteSettingsRepository::new(pool.clone()));
 
         // Create application services
-        let item_service = Arc::new(ItemService::new(item_repo.clone(), tag_repo.clone()));
-        let tag_service = Arc::new(TagService::new(tag_repo.clone(), tag_group_repo.clone()));
+        let settings_service = Arc::new(SettingsService::new(settings_repo));
+        let item_service = Arc::new(ItemService::new(
+            item_repo.clone(),
+            tag_repo.clone(),
+            settings_service.clone(),
+        ));
         let tag_group_service = Arc::new(TagGroupService::new(tag_group_repo.clone()));
+        let auto_tag_rule_service = Arc::new(AutoTagRuleService::new(
+            auto_tag_rule_repo,
+            tag_template_repo.clone(),
+        ));
         let tag_template_service = Arc::new(TagTemplateService::new(
             tag_template_repo,
             item_repo.clone(),
+            tag_repo.clone(),
         ));
-        let search_service = Arc::new(SearchService::new(search_repo, search_history_repo));
-        let settings_service = Arc::new(SettingsService::new(settings_repo));
-        let usn_refresh_service = Arc::new(UsnRefreshService::new(
-            pool.clone(),
-            item_repo.clone(),
+        let tag_service = Arc::new(TagService::new(
+            tag_repo.clone(),
+            tag_group_repo.clone(),
+            collection_repo.clone(),
             settings_service.clone(),
         ));
-        let thumbnail_service = Arc::new(
-            ThumbnailService::new(app_data_dir.clone(), settings_service.clone())
-                .await
-                .map_err(|e| AppError::Thumbnail(e.to_string()))?,
-        );
+        let similarity_service = Arc::new(SimilarityService::new(
+            search_repo.clone(),
+            tag_repo.clone(),
+        ));
+        let collection_service =
+            Arc::new(CollectionService::new(collection_repo, search_repo.clone()));
+        let search_service = Arc::new(SearchService::new(
+            search_repo,
+            search_history_repo,
+            tag_repo.clone(),
+            settings_service.clone(),
+        ));
+        let gallery_export_service = Arc::new(GalleryExportService::new(search_service.clone()));
 
-        Ok(Self {
+        Self {
             config,
             app_data_dir,
             item_service,
             tag_service,
             tag_group_service,
             tag_template_service,
+            auto_tag_rule_service,
+            collection_service,
             search_service,
+            similarity_service,
+            gallery_export_service,
             settings_service,
-            thumbnail_service,
-            usn_refresh_service,
-        })
+            cancellation_registry: CancellationRegistry::new(),
+            recent_directories: RecentDirectories::new(),
+            pool,
+            item_repo,
+            thumbnail_service: OnceCell::new(),
+            usn_refresh_service: OnceLock::new(),
+        }
+    }
+
+    /// Writes pending thumbnail cache counters, if the service was created.
+    pub fn flush_thumbnail_cache(&self) {
+        if let Some(service) = self.thumbnail_service.get() {
+            service.flush_cache();
+        }
+    }
+
+    /// Returns the thumbnail service, creating it on first use.
+    ///
+    /// Creation reads the thumbnail settings and spawns the COM worker
+    /// threads. Concurrent first calls wait for a single initialization, and a
+    /// failed one is retried by the next call. Once created, the disk cache is
+    /// evicted in the background and the idle warmer is started.
+    pub async fn thumbnail_service(&self) -> Result<Arc<ThumbnailService>, AppError> {
+        self.thumbnail_service
+            .get_or_try_init(|| async {
+                let service = Arc::new(
+                    ThumbnailService::new(
+                        self.app_data_dir.clone(),
+                        self.settings_service.clone(),
+                        self.item_repo.clone(),
+                    )
+                    .await
+                    .map_err(|e| AppError::Thumbnail(e.to_string()))?,
+                );
+
+                let evicting = service.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = evicting.evict_cache().await {
+                        log::warn!("Background cache eviction failed: {}", e);
+                    }
+                });
+
+                service.spawn_idle_warmer(self.recent_directories.clone());
+
+                Ok(service)
+            })
+            .await
+            .cloned()
+    }
+
+    /// Returns the USN refresh service, creating it on first use.
+    pub fn usn_refresh_service(&self) -> Arc<UsnRefreshService> {
+        self.usn_refresh_service
+            .get_or_init(|| {
+                Arc::new(UsnRefreshService::new(
+                    self.pool.clone(),
+                    self.item_repo.clone(),
+                    self.settings_service.clone(),
+                ))
+            })
+            .clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::persistence::open_database;
+
+    /// Compares building the state with creating the lazy services on first
+    /// use. Run with `cargo test bench_lazy_services -- --ignored --nocapture`.
+    #[tokio::test]
+    #[ignore = "benchmark"]
+    async fn bench_lazy_services() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("test.db");
+        let pool = open_database(&db_path).await.unwrap();
+        let config = AppConfig {
+            db_path: db_path.to_string_lossy().to_string(),
+        };
+
+        let start = std::time::Instant::now();
+        let state = AppState::new(pool, config, dir.path().to_path_buf());
+        let new_elapsed = start.elapsed();
+
+        let start = std::time::Instant::now();
+        state.thumbnail_service().await.unwrap();
+        let thumbnail_elapsed = start.elapsed();
+
+        let start = std::time::Instant::now();
+        state.usn_refresh_service();
+        let usn_elapsed = start.elapsed();
+
+        eprintln!(
+            "AppState::new {:?}, first thumbnail_service {:?}, first usn_refresh_service {:?}",
+            new_elapsed, thumbnail_elapsed, usn_elapsed
+        );
     }
 }
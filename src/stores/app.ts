@@ -78,6 +78,17 @@ export const useAppStore = defineStore('app', () => {
     }
   }
 
+  async function getSearchHistoryCount() {
+    return await invoke<number>('get_search_history_count')
+  }
+
+  /** Cap stored history at `limit` entries; returns how many were pruned. */
+  async function setSearchHistoryLimit(limit: number) {
+    const pruned = await invoke<number>('set_search_history_limit', { limit })
+    await loadSearchHistory()
+    return pruned
+  }
+
   return {
     leftPanelMode,
     displayMode,
@@ -94,5 +105,7 @@ export const useAppStore = defineStore('app', () => {
     loadSearchHistory,
     deleteSearchHistory,
     clearSearchHistory,
+    getSearchHistoryCount,
+    setSearchHistoryLimit,
   }
 })
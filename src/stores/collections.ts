@@ -0,0 +1,88 @@
+import { defineStore } from 'pinia'
+import { ref } from 'vue'
+import { invoke } from '@tauri-apps/api/core'
+import { errorMessage } from '@/utils'
+import type { CollectionWithCount } from '@/types'
+
+export const useCollectionsStore = defineStore('collections', () => {
+  const collections = ref<CollectionWithCount[]>([])
+  const loading = ref(false)
+  const error = ref<string | null>(null)
+
+  /** Counts are cached briefly by the backend, so this is cheap to call on every sidebar render. */
+  async function loadCollections() {
+    loading.value = true
+    error.value = null
+    try {
+      collections.value = await invoke<CollectionWithCount[]>('get_collections_with_counts')
+    } catch (e) {
+      error.value = errorMessage(e)
+      console.error('Failed to load collections:', e)
+    } finally {
+      loading.value = false
+    }
+  }
+
+  async function createCollection(name: string, cqlQuery: string, icon?: string) {
+    try {
+      const id = await invoke<number>('create_collection', { name, cqlQuery, icon })
+      await loadCollections()
+      return id
+    } catch (e) {
+      error.value = errorMessage(e)
+      console.error('Failed to create collection:', e)
+      throw e
+    }
+  }
+
+  /** An empty `icon` clears it. */
+  async function updateCollection(id: number, changes: { name?: string; cqlQuery?: string; icon?: string }) {
+    try {
+      await invoke('update_collection', { id, ...changes })
+      await loadCollections()
+    } catch (e) {
+      error.value = errorMessage(e)
+      console.error('Failed to update collection:', e)
+      throw e
+    }
+  }
+
+  async function deleteCollection(id: number) {
+    try {
+      await invoke('delete_collection', { id })
+      collections.value = collections.value.filter(c => c.collection.id !== id)
+    } catch (e) {
+      error.value = errorMessage(e)
+      console.error('Failed to delete collection:', e)
+      throw e
+    }
+  }
+
+  /** Persists the given order of collection IDs. */
+  async function reorderCollections(orderedIds: number[]) {
+    try {
+      const orders = orderedIds.map((id, index) => ({
+        id,
+        display_order: index,
+      }))
+
+      await invoke('reorder_collections', { orders })
+      await loadCollections()
+    } catch (e) {
+      error.value = errorMessage(e)
+      console.error('Failed to reorder collections:', e)
+      throw e
+    }
+  }
+
+  return {
+    collections,
+    loading,
+    error,
+    loadCollections,
+    createCollection,
+    updateCollection,
+    deleteCollection,
+    reorderCollections,
+  }
+})
@@ -2,7 +2,8 @@ import { defineStore } from 'pinia'
 import { ref } from 'vue'
 import { invoke } from '@tauri-apps/api/core'
 import { useAppStore } from './app'
-import type { DriveInfo, FileEntry, FileMetadata } from '@/types'
+import { errorMessage } from '@/utils'
+import type { DirectoryCount, DriveInfo, FileEntry, FileMetadata } from '@/types'
 
 export const useFileExplorerStore = defineStore('fileExplorer', () => {
   const currentPath = ref<string>('')
@@ -25,7 +26,7 @@ export const useFileExplorerStore = defineStore('fileExplorer', () => {
       drives.value = await invoke<DriveInfo[]>('get_drives')
       return drives.value
     } catch (e) {
-      error.value = e as string
+      error.value = errorMessage(e)
       console.error('Failed to get drives:', e)
       throw e
     } finally {
@@ -61,7 +62,7 @@ export const useFileExplorerStore = defineStore('fileExplorer', () => {
       appStore.setCurrentPath(path)
       return currentFiles.value
     } catch (e) {
-      error.value = e as string
+      error.value = errorMessage(e)
       console.error('Failed to read directory:', e)
       throw e
     } finally {
@@ -74,17 +75,38 @@ export const useFileExplorerStore = defineStore('fileExplorer', () => {
       const metadata = await invoke<FileMetadata>('get_file_metadata', { path })
       return metadata
     } catch (e) {
-      error.value = e as string
+      error.value = errorMessage(e)
       console.error('Failed to get file metadata:', e)
       throw e
     }
   }
 
+  /** `maxDepth` 0 counts only direct children; omit it to walk the whole tree. */
+  async function getDirectorySize(path: string, maxDepth?: number) {
+    try {
+      return await invoke<number>('get_directory_size', { path, maxDepth })
+    } catch (e) {
+      error.value = errorMessage(e)
+      console.error('Failed to get directory size:', e)
+      throw e
+    }
+  }
+
+  async function getDirectoryEntryCount(path: string, maxDepth?: number) {
+    try {
+      return await invoke<DirectoryCount>('get_directory_entry_count', { path, maxDepth })
+    } catch (e) {
+      error.value = errorMessage(e)
+      console.error('Failed to count directory entries:', e)
+      throw e
+    }
+  }
+
   async function openFileExternal(path: string) {
     try {
       await invoke('open_file_external', { path })
     } catch (e) {
-      error.value = e as string
+      error.value = errorMessage(e)
       console.error('Failed to open file:', e)
       throw e
     }
@@ -94,7 +116,7 @@ export const useFileExplorerStore = defineStore('fileExplorer', () => {
     try {
       await invoke('reveal_in_explorer', { path })
     } catch (e) {
-      error.value = e as string
+      error.value = errorMessage(e)
       console.error('Failed to reveal in explorer:', e)
       throw e
     }
@@ -150,6 +172,8 @@ export const useFileExplorerStore = defineStore('fileExplorer', () => {
     getDrives,
     readDirectory,
     getFileMetadata,
+    getDirectorySize,
+    getDirectoryEntryCount,
     openFileExternal,
     revealInExplorer,
     navigateUp,
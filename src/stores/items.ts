@@ -2,7 +2,8 @@ import { defineStore } from 'pinia'
 import { ref } from 'vue'
 import { invoke } from '@tauri-apps/api/core'
 import { useTagsStore } from '@/stores/tags'
-import type { BatchTagResult, Item, Tag } from '@/types'
+import { errorMessage } from '@/utils'
+import type { BatchTagResult, BulkUpdateResult, CreateItemsResult, DirectoryTagResult, Item, ItemWithTags, MetadataRefreshResult, PathCheck, PathNormalization, Tag, TagUpdateResult } from '@/types'
 
 export const useItemsStore = defineStore('items', () => {
   const items = ref<Item[]>([])
@@ -24,18 +25,29 @@ export const useItemsStore = defineStore('items', () => {
       })
       return id
     } catch (e) {
-      error.value = e as string
+      error.value = errorMessage(e)
       console.error('Failed to create item:', e)
       throw e
     }
   }
 
+  /** Register paths as items without tagging them; existing paths are skipped. */
+  async function createItems(paths: string[]) {
+    try {
+      return await invoke<CreateItemsResult>('create_items', { paths })
+    } catch (e) {
+      error.value = errorMessage(e)
+      console.error('Failed to create items:', e)
+      throw e
+    }
+  }
+
   async function getItem(id: number) {
     try {
       const item = await invoke<Item>('get_item', { id })
       return item
     } catch (e) {
-      error.value = e as string
+      error.value = errorMessage(e)
       console.error('Failed to get item:', e)
       throw e
     }
@@ -46,7 +58,7 @@ export const useItemsStore = defineStore('items', () => {
       const item = await invoke<Item | null>('get_item_by_path', { path })
       return item
     } catch (e) {
-      error.value = e as string
+      error.value = errorMessage(e)
       console.error('Failed to get item by path:', e)
       throw e
     }
@@ -57,12 +69,133 @@ export const useItemsStore = defineStore('items', () => {
       const items = await invoke<Item[]>('get_items_by_paths', { paths })
       return items
     } catch (e) {
-      error.value = e as string
+      error.value = errorMessage(e)
       console.error('Failed to get items by paths:', e)
       throw e
     }
   }
 
+  async function checkItemsByPaths(paths: string[]) {
+    try {
+      return await invoke<PathCheck[]>('check_items_by_paths', { paths })
+    } catch (e) {
+      error.value = errorMessage(e)
+      console.error('Failed to check items by paths:', e)
+      throw e
+    }
+  }
+
+  async function rebaseItemPaths(oldPrefix: string, newPrefix: string) {
+    try {
+      return await invoke<number>('rebase_item_paths', { oldPrefix, newPrefix })
+    } catch (e) {
+      error.value = errorMessage(e)
+      console.error('Failed to rebase item paths:', e)
+      throw e
+    }
+  }
+
+  async function bulkRenamePathPrefix(oldPrefix: string, newPrefix: string) {
+    try {
+      return await invoke<BulkUpdateResult>('bulk_rename_path_prefix', { oldPrefix, newPrefix })
+    } catch (e) {
+      error.value = errorMessage(e)
+      console.error('Failed to rename path prefix:', e)
+      throw e
+    }
+  }
+
+  /**
+   * Rewrite stored paths to their normalized form (uppercase drive letter,
+   * `\` separators, no trailing separator), merging items that collapse.
+   */
+  async function normalizeItemPaths() {
+    try {
+      const result = await invoke<PathNormalization>('normalize_item_paths')
+      if (result.merged_count > 0) {
+        const tagsStore = useTagsStore()
+        await tagsStore.loadUsageCounts()
+        tagsStore.itemTagsVersion++
+      }
+      return result
+    } catch (e) {
+      error.value = errorMessage(e)
+      console.error('Failed to normalize item paths:', e)
+      throw e
+    }
+  }
+
+  /**
+   * Re-read size and modification time from disk for items in the directory `prefix` (all items if omitted).
+   * Pass an `operationId` to be able to stop it with `cancelOperation`.
+   */
+  async function refreshItemMetadata(prefix?: string, operationId?: string) {
+    try {
+      return await invoke<MetadataRefreshResult>('refresh_item_metadata', { prefix, operationId })
+    } catch (e) {
+      error.value = errorMessage(e)
+      console.error('Failed to refresh item metadata:', e)
+      throw e
+    }
+  }
+
+  /** Stop a running operation started with `operationId`; false if none is running. */
+  async function cancelOperation(operationId: string): Promise<boolean> {
+    return await invoke<boolean>('cancel_operation', { operationId })
+  }
+
+  async function setRating(id: number, rating: number | null) {
+    try {
+      await invoke('set_rating', { id, rating })
+    } catch (e) {
+      error.value = errorMessage(e)
+      console.error('Failed to set rating:', e)
+      throw e
+    }
+  }
+
+  /** Re-read an item's NTFS file ID from disk so renames can be tracked; returns the updated item */
+  async function refreshItemFrn(itemId: number) {
+    try {
+      return await invoke<Item>('refresh_item_frn', { itemId })
+    } catch (e) {
+      error.value = errorMessage(e)
+      console.error('Failed to refresh file ID:', e)
+      throw e
+    }
+  }
+
+  /** Items whose tags changed most recently, newest first (backend default: 50). */
+  async function getRecentlyTaggedItems(limit?: number) {
+    try {
+      return await invoke<Item[]>('get_recently_tagged_items', { limit })
+    } catch (e) {
+      error.value = errorMessage(e)
+      console.error('Failed to get recently tagged items:', e)
+      throw e
+    }
+  }
+
+  async function softDeleteItemsByTag(tagId: number) {
+    try {
+      return await invoke<number>('soft_delete_items_by_tag', { tagId })
+    } catch (e) {
+      error.value = errorMessage(e)
+      console.error('Failed to soft-delete items by tag:', e)
+      throw e
+    }
+  }
+
+  async function restoreItemsByTag(tagId: number) {
+    try {
+      return await invoke<number>('restore_items_by_tag', { tagId })
+    } catch (e) {
+      error.value = errorMessage(e)
+      console.error('Failed to restore items by tag:', e)
+      throw e
+    }
+  }
+
   async function addTagToItem(itemId: number, tagId: number) {
     try {
       await invoke('add_tag_to_item', {
@@ -73,12 +206,39 @@ export const useItemsStore = defineStore('items', () => {
       await tagsStore.loadUsageCounts()
       tagsStore.itemTagsVersion++
     } catch (e) {
-      error.value = e as string
+      error.value = errorMessage(e)
       console.error('Failed to add tag to item:', e)
       throw e
     }
   }
 
+  /**
+   * Queue adding a tag to an item. Queued writes are batched and land within
+   * a short window; call `flushItemWrites` before reading them back.
+   */
+  async function enqueueTagForItem(itemId: number, tagId: number): Promise<void> {
+    await invoke('enqueue_tag_for_item', { itemId, tagId })
+  }
+
+  /** Queue registering a path as an item, batched like `enqueueTagForItem`. */
+  async function enqueueCreateItem(path: string): Promise<void> {
+    await invoke('enqueue_create_item', { path })
+  }
+
+  /** Write all queued items and tags, then refresh tag usage counts. */
+  async function flushItemWrites(): Promise<void> {
+    try {
+      await invoke('flush_item_writes')
+      const tagsStore = useTagsStore()
+      await tagsStore.loadUsageCounts()
+      tagsStore.itemTagsVersion++
+    } catch (e) {
+      error.value = errorMessage(e)
+      console.error('Failed to write queued items:', e)
+      throw e
+    }
+  }
+
   async function removeTagFromItem(itemId: number, tagId: number) {
     try {
       await invoke('remove_tag_from_item', {
@@ -89,7 +249,7 @@ export const useItemsStore = defineStore('items', () => {
       await tagsStore.loadUsageCounts()
       tagsStore.itemTagsVersion++
     } catch (e) {
-      error.value = e as string
+      error.value = errorMessage(e)
       console.error('Failed to remove tag from item:', e)
       throw e
     }
@@ -100,7 +260,7 @@ export const useItemsStore = defineStore('items', () => {
       const tags = await invoke<Tag[]>('get_tags_for_item', { itemId })
       return tags
     } catch (e) {
-      error.value = e as string
+      error.value = errorMessage(e)
       console.error('Failed to get tags for item:', e)
       throw e
     }
@@ -111,54 +271,152 @@ export const useItemsStore = defineStore('items', () => {
       const tagsMap = await invoke<Record<number, Tag[]>>('get_tags_for_items', { itemIds })
       return tagsMap
     } catch (e) {
-      error.value = e as string
+      error.value = errorMessage(e)
       console.error('Failed to get tags for items:', e)
       throw e
     }
   }
 
-  async function updateItemTags(itemId: number, tagIds: number[]) {
+  /** Items and their tags in one call, in `itemIds` order */
+  async function getItemsWithTags(itemIds: number[]) {
+    try {
+      return await invoke<ItemWithTags[]>('get_items_with_tags', { itemIds })
+    } catch (e) {
+      error.value = errorMessage(e)
+      console.error('Failed to get items with tags:', e)
+      throw e
+    }
+  }
+
+  /** Registered items in a directory (or its whole tree) with their tags */
+  async function getItemsInDirectoryWithTags(directory: string, recursive = false) {
+    try {
+      return await invoke<ItemWithTags[]>('get_items_in_directory_with_tags', {
+        directory,
+        recursive,
+      })
+    } catch (e) {
+      error.value = errorMessage(e)
+      console.error('Failed to get directory items with tags:', e)
+      throw e
+    }
+  }
+
+  /**
+   * Tags carried by the items under a directory. They are shown as the
+   * directory's inherited tags and are not part of its own tags.
+   */
+  async function getInheritedTags(directoryPath: string) {
     try {
-      await invoke('update_item_tags', { itemId, tagIds })
+      return await invoke<Tag[]>('get_inherited_tags', { directoryPath })
+    } catch (e) {
+      error.value = errorMessage(e)
+      console.error('Failed to get inherited tags:', e)
+      throw e
+    }
+  }
+
+  /**
+   * Replace an item's tags. Passing the `tags_updated_at` last seen (0 if null)
+   * turns the call into a `conflict` carrying the current tags if another
+   * window changed the item's tags in the meantime.
+   */
+  async function updateItemTags(itemId: number, tagIds: number[], expectedTagsUpdatedAt?: number) {
+    try {
+      const result = await invoke<TagUpdateResult>('update_item_tags', {
+        itemId,
+        tagIds,
+        expectedTagsUpdatedAt: expectedTagsUpdatedAt ?? null,
+      })
       const tagsStore = useTagsStore()
       await tagsStore.loadUsageCounts()
       tagsStore.itemTagsVersion++
+      return result
     } catch (e) {
-      error.value = e as string
+      error.value = errorMessage(e)
       console.error('Failed to update item tags:', e)
       throw e
     }
   }
 
-  async function batchAddTagToItems(paths: string[], tagId: number) {
+  /**
+   * Items in `expectedTagsUpdatedAt` (by path) whose tags changed since are skipped and listed in `conflicts`.
+   * Progress arrives as `batch-tag-progress` events; pass `operationId` to allow `cancel_operation`.
+   */
+  async function batchAddTagToItems(
+    paths: string[],
+    tagId: number,
+    expectedTagsUpdatedAt?: Record<string, number>,
+    operationId?: string,
+  ) {
     try {
       const result = await invoke<BatchTagResult>('batch_add_tag_to_items', {
         paths,
         tagId,
+        expectedTagsUpdatedAt: expectedTagsUpdatedAt ?? null,
+        operationId: operationId ?? null,
       })
       const tagsStore = useTagsStore()
       await tagsStore.loadUsageCounts()
       tagsStore.itemTagsVersion++
       return result
     } catch (e) {
-      error.value = e as string
+      error.value = errorMessage(e)
       console.error('Failed to batch add tag:', e)
       throw e
     }
   }
 
-  async function batchRemoveTagFromItems(paths: string[], tagId: number) {
+  /**
+   * Tags every file under `path`; subfolders are included unless `recursive` is false.
+   * Pass an `operationId` to be able to stop it with `cancelOperation`.
+   */
+  async function tagDirectory(
+    path: string,
+    tagId: number,
+    recursive = true,
+    includeHidden = false,
+    operationId?: string
+  ) {
+    try {
+      const result = await invoke<DirectoryTagResult>('tag_directory', {
+        path,
+        tagId,
+        recursive,
+        includeHidden,
+        operationId: operationId ?? null,
+      })
+      const tagsStore = useTagsStore()
+      await tagsStore.loadUsageCounts()
+      tagsStore.itemTagsVersion++
+      return result
+    } catch (e) {
+      error.value = errorMessage(e)
+      console.error('Failed to tag directory:', e)
+      throw e
+    }
+  }
+
+  /** Same conflicts, progress events and cancellation as `batchAddTagToItems` */
+  async function batchRemoveTagFromItems(
+    paths: string[],
+    tagId: number,
+    expectedTagsUpdatedAt?: Record<string, number>,
+    operationId?: string,
+  ) {
     try {
       const result = await invoke<BatchTagResult>('batch_remove_tag_from_items', {
         paths,
         tagId,
+        expectedTagsUpdatedAt: expectedTagsUpdatedAt ?? null,
+        operationId: operationId ?? null,
       })
       const tagsStore = useTagsStore()
       await tagsStore.loadUsageCounts()
       tagsStore.itemTagsVersion++
       return result
     } catch (e) {
-      error.value = e as string
+      error.value = errorMessage(e)
       console.error('Failed to batch remove tag:', e)
       throw e
     }
@@ -169,7 +427,7 @@ export const useItemsStore = defineStore('items', () => {
       const tags = await invoke<Tag[]>('get_common_tags_for_paths', { paths })
       return tags
     } catch (e) {
-      error.value = e as string
+      error.value = errorMessage(e)
       console.error('Failed to get common tags:', e)
       return []
     }
@@ -180,15 +438,34 @@ export const useItemsStore = defineStore('items', () => {
     loading,
     error,
     createItem,
+    createItems,
     getItem,
     getItemByPath,
     getItemsByPaths,
+    checkItemsByPaths,
+    rebaseItemPaths,
+    bulkRenamePathPrefix,
+    normalizeItemPaths,
+    refreshItemMetadata,
+    cancelOperation,
+    setRating,
+    refreshItemFrn,
+    getRecentlyTaggedItems,
+    softDeleteItemsByTag,
+    restoreItemsByTag,
     addTagToItem,
+    enqueueTagForItem,
+    enqueueCreateItem,
+    flushItemWrites,
     removeTagFromItem,
     getTagsForItem,
     getTagsForItems,
+    getItemsWithTags,
+    getItemsInDirectoryWithTags,
+    getInheritedTags,
     updateItemTags,
     batchAddTagToItems,
+    tagDirectory,
     batchRemoveTagFromItems,
     getCommonTagsForPaths,
   }
@@ -1,7 +1,27 @@
 import { defineStore } from 'pinia'
 import { ref, computed } from 'vue'
 import { invoke } from '@tauri-apps/api/core'
-import type { Item, SearchMode, SearchInputMode } from '@/types'
+import { errorMessage } from '@/utils'
+import type { Item, ItemWithTags, SimilarItem, Recommendation, GalleryExportJob, ItemSort, FilenameMatchMode, TimeField, SearchMode, SearchInputMode, SearchRequest, GroupBy, GroupedResults, TruncatedResults, QuerySpec, ParsedQuerySpec } from '@/types'
+
+/** File whose thumbnail could not be exported to a gallery. */
+export interface GalleryExportFailure {
+  path: string
+  error: string
+}
+
+/** Payload of the `gallery-export-progress` event. */
+export interface GalleryExportProgress {
+  job_id: number
+  processed: number
+  total: number
+  failed: number
+  done: boolean
+  cancelled: boolean
+  /** Filled in on the final event only. */
+  failures: GalleryExportFailure[]
+  error: string | null
+}
 
 export const useSearchStore = defineStore('search', () => {
   const results = ref<Item[]>([])
@@ -10,11 +30,14 @@ export const useSearchStore = defineStore('search', () => {
   const mode = ref<SearchMode>('and')
   const selectedTagIds = ref<number[]>([])
   const filenameQuery = ref('')
+  const sort = ref<ItemSort>({ field: 'path', direction: 'asc' })
 
   // CQL mode state
   const searchInputMode = ref<SearchInputMode>('simple')
   const cqlQuery = ref('')
   const cqlError = ref<string | null>(null)
+  /** Match count of the last CQL search when it hit `cql_max_rows` and only the first rows are shown */
+  const cqlTruncatedTotal = ref<number | null>(null)
 
   const hasSearchCriteria = computed(() => {
     // Must have at least one tag selected to search
@@ -23,33 +46,172 @@ export const useSearchStore = defineStore('search', () => {
 
   const resultCount = computed(() => results.value.length)
 
-  async function searchByTagsAnd(tagIds: number[]): Promise<Item[]> {
+  /** Run a search of any mode; CQL queries past `cql_max_rows` come back truncated with the total */
+  async function search(request: SearchRequest): Promise<Item[] | TruncatedResults<Item>> {
+    return await invoke<Item[] | TruncatedResults<Item>>('search', { request })
+  }
+
+  async function searchByTagsAnd(tagIds: number[], sort?: ItemSort): Promise<Item[]> {
     try {
-      return await invoke<Item[]>('search_items_by_tags_and', { tagIds })
+      return await invoke<Item[]>('search_items_by_tags_and', { tagIds, sort })
     } catch (e) {
       console.error('Failed to search by tags (AND):', e)
       return []
     }
   }
 
-  async function searchByTagsOr(tagIds: number[]): Promise<Item[]> {
+  async function searchByTagsOr(tagIds: number[], sort?: ItemSort): Promise<Item[]> {
     try {
-      return await invoke<Item[]>('search_items_by_tags_or', { tagIds })
+      return await invoke<Item[]>('search_items_by_tags_or', { tagIds, sort })
     } catch (e) {
       console.error('Failed to search by tags (OR):', e)
       return []
     }
   }
 
-  async function searchByFilename(query: string): Promise<Item[]> {
+  async function searchExcludingTags(excludeTagIds: number[]): Promise<Item[]> {
+    try {
+      return await invoke<Item[]>('search_items_excluding_tags', { excludeTagIds })
+    } catch (e) {
+      console.error('Failed to search excluding tags:', e)
+      return []
+    }
+  }
+
+  async function searchMixed(
+    includeTagIds: number[],
+    excludeTagIds: number[],
+    searchMode: SearchMode
+  ): Promise<Item[]> {
+    try {
+      return await invoke<Item[]>('search_items_mixed', {
+        includeTagIds,
+        excludeTagIds,
+        mode: searchMode,
+      })
+    } catch (e) {
+      console.error('Failed to search with included/excluded tags:', e)
+      return []
+    }
+  }
+
+  /**
+   * Items with all of `includeTagIds` and none of `excludeTagIds`; exclusion wins on overlap.
+   * Same as `searchMixed` in AND mode, so both lists empty returns nothing.
+   */
+  async function searchWithExclusions(includeTagIds: number[], excludeTagIds: number[]): Promise<Item[]> {
+    try {
+      return await invoke<Item[]>('search_items_with_exclusions', { includeTagIds, excludeTagIds })
+    } catch (e) {
+      console.error('Failed to search with exclusions:', e)
+      return []
+    }
+  }
+
+  async function getItemsByTag(tagId: number): Promise<Item[]> {
+    try {
+      return await invoke<Item[]>('get_items_by_tag', { tagId })
+    } catch (e) {
+      console.error('Failed to get items by tag:', e)
+      return []
+    }
+  }
+
+  /** Items sharing at least `minShared` tags (default 1) with the item, most shared first. */
+  async function getSimilarItems(itemId: number, minShared?: number): Promise<SimilarItem[]> {
     try {
-      return await invoke<Item[]>('search_items_by_filename', { query })
+      return await invoke<SimilarItem[]>('get_similar_items', { itemId, minShared })
+    } catch (e) {
+      console.error('Failed to get similar items:', e)
+      return []
+    }
+  }
+
+  /** Up to `limit` items (default 20) ranked by shared tags, rare tags weighing more. */
+  async function getRecommendations(itemId: number, limit?: number): Promise<Recommendation[]> {
+    try {
+      return await invoke<Recommendation[]>('get_recommendations', { itemId, limit })
+    } catch (e) {
+      console.error('Failed to get recommendations:', e)
+      return []
+    }
+  }
+
+  /** Items whose `timeField` is within the last `windowHours`, newest first, optionally with all `tagIds`. */
+  async function searchByTimeWindow(
+    windowHours: number,
+    timeField: TimeField,
+    tagIds?: number[]
+  ): Promise<Item[]> {
+    try {
+      return await invoke<Item[]>('search_items_by_time_window', { windowHours, timeField, tagIds })
+    } catch (e) {
+      console.error('Failed to search by time window:', e)
+      return []
+    }
+  }
+
+  /**
+   * Export the results of a CQL query as `index.html` plus thumbnails in
+   * `outputDir`, at most `maxItems` files (default 500). Runs in the
+   * background; listen for `gallery-export-progress` to follow it and pass
+   * an `operationId` to be able to stop it with `cancel_operation`.
+   */
+  async function exportHtmlGallery(
+    query: string,
+    outputDir: string,
+    thumbSize: number,
+    maxItems?: number,
+    operationId?: string
+  ): Promise<GalleryExportJob> {
+    return await invoke<GalleryExportJob>('export_html_gallery', {
+      query,
+      outputDir,
+      thumbSize,
+      maxItems,
+      operationId: operationId ?? null,
+    })
+  }
+
+  /** Items modified within the last `hours`, newest first. */
+  async function getRecentChanges(hours: number): Promise<Item[]> {
+    try {
+      return await invoke<Item[]>('get_recent_changes', { hours })
+    } catch (e) {
+      console.error('Failed to get recent changes:', e)
+      return []
+    }
+  }
+
+  async function searchByFilename(
+    query: string,
+    sort?: ItemSort,
+    matchMode?: FilenameMatchMode
+  ): Promise<Item[]> {
+    try {
+      return await invoke<Item[]>('search_items_by_filename', { query, matchMode, sort })
     } catch (e) {
       console.error('Failed to search by filename:', e)
       return []
     }
   }
 
+  /** Combined tag/filename search returning each result with its tags. */
+  async function searchItemsWithTags(
+    tagIds: number[],
+    searchMode: SearchMode,
+    query: string | null,
+    itemSort?: ItemSort
+  ): Promise<ItemWithTags[]> {
+    return await invoke<ItemWithTags[]>('search_items', {
+      tagIds,
+      mode: searchMode,
+      filenameQuery: query,
+      sort: itemSort,
+      includeTags: true,
+    })
+  }
+
   async function executeSearch() {
     if (!hasSearchCriteria.value) {
       results.value = []
@@ -65,9 +227,10 @@ export const useSearchStore = defineStore('search', () => {
         tagIds: selectedTagIds.value,
         mode: mode.value,
         filenameQuery: filenameQueryValue,
+        sort: sort.value,
       })
     } catch (e) {
-      error.value = e as string
+      error.value = errorMessage(e)
       console.error('Failed to execute search:', e)
       results.value = []
     } finally {
@@ -79,6 +242,10 @@ export const useSearchStore = defineStore('search', () => {
     mode.value = newMode
   }
 
+  function setSort(newSort: ItemSort) {
+    sort.value = newSort
+  }
+
   function toggleTag(tagId: number) {
     const index = selectedTagIds.value.indexOf(tagId)
     if (index === -1) {
@@ -115,6 +282,7 @@ export const useSearchStore = defineStore('search', () => {
     results.value = []
     error.value = null
     cqlError.value = null
+    cqlTruncatedTotal.value = null
   }
 
   function setCqlQuery(query: string) {
@@ -130,19 +298,61 @@ export const useSearchStore = defineStore('search', () => {
 
     loading.value = true
     cqlError.value = null
+    cqlTruncatedTotal.value = null
     error.value = null
 
     try {
-      results.value = await invoke<Item[]>('search_cql', { query })
+      const response = await invoke<Item[] | TruncatedResults<Item>>('search_cql', { query })
+      if (Array.isArray(response)) {
+        results.value = response
+      } else {
+        results.value = response.items
+        cqlTruncatedTotal.value = response.total_count
+      }
     } catch (e) {
-      const errMsg = typeof e === 'string' ? e : String(e)
-      cqlError.value = errMsg
+      cqlError.value = errorMessage(e)
       results.value = []
     } finally {
       loading.value = false
     }
   }
 
+  /**
+   * Run a CQL query and return each result with its tags in the same call.
+   * Past the `cql_max_rows` setting only the first rows come back, with the total.
+   */
+  async function searchCqlWithTags(query: string): Promise<ItemWithTags[] | TruncatedResults<ItemWithTags>> {
+    return await invoke<ItemWithTags[] | TruncatedResults<ItemWithTags>>('search_cql', { query, includeTags: true })
+  }
+
+  async function searchCqlGrouped(query: string, groupBy: GroupBy): Promise<GroupedResults> {
+    return await invoke<GroupedResults>('search_cql', { query, groupBy })
+  }
+
+  async function getGroupItems(
+    query: string,
+    groupBy: GroupBy,
+    groupKey: string,
+    offset: number,
+    limit: number
+  ): Promise<Item[]> {
+    return await invoke<Item[]>('get_group_items', { query, groupBy, groupKey, offset, limit })
+  }
+
+  /** Render a filter as CQL; the backend quotes and escapes values */
+  async function buildCql(spec: QuerySpec): Promise<string> {
+    return await invoke<string>('build_cql', { spec })
+  }
+
+  /** Split a CQL query into filter clauses, when it is a plain AND of them */
+  async function parseToSpec(query: string): Promise<ParsedQuerySpec> {
+    return await invoke<ParsedQuerySpec>('parse_to_spec', { query })
+  }
+
+  async function cancelSearch(requestId: string): Promise<boolean> {
+    return await invoke<boolean>('cancel_search', { requestId })
+  }
+
   function clearSearch() {
     results.value = []
     selectedTagIds.value = []
@@ -150,6 +360,7 @@ export const useSearchStore = defineStore('search', () => {
     cqlQuery.value = ''
     error.value = null
     cqlError.value = null
+    cqlTruncatedTotal.value = null
   }
 
   return {
@@ -159,13 +370,26 @@ export const useSearchStore = defineStore('search', () => {
     mode,
     selectedTagIds,
     filenameQuery,
+    sort,
     hasSearchCriteria,
     resultCount,
+    search,
     searchByTagsAnd,
     searchByTagsOr,
     searchByFilename,
+    getItemsByTag,
+    getSimilarItems,
+    getRecommendations,
+    searchByTimeWindow,
+    getRecentChanges,
+    exportHtmlGallery,
+    searchExcludingTags,
+    searchMixed,
+    searchWithExclusions,
+    searchItemsWithTags,
     executeSearch,
     setMode,
+    setSort,
     toggleTag,
     selectTag,
     deselectTag,
@@ -174,9 +398,16 @@ export const useSearchStore = defineStore('search', () => {
     searchInputMode,
     cqlQuery,
     cqlError,
+    cqlTruncatedTotal,
     setSearchInputMode,
     setCqlQuery,
     executeCqlSearch,
+    searchCqlWithTags,
+    searchCqlGrouped,
+    getGroupItems,
+    cancelSearch,
+    buildCql,
+    parseToSpec,
     clearSearch,
   }
 })
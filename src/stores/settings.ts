@@ -1,33 +1,158 @@
 import { defineStore } from 'pinia'
 import { ref, computed } from 'vue'
 import { invoke } from '@tauri-apps/api/core'
+import type { TagGroupSortMode } from '@/types'
 
 export interface AppSettings {
   usn_auto_refresh: boolean
   usn_refresh_on_missing: boolean
   usn_cross_volume_match: boolean
+  usn_apply_auto_tag_rules: boolean
+  /** Items without a file ID that one USN refresh records one for */
+  usn_frn_backfill_limit: number
+  rehydrate_tags_on_path_reuse: boolean
+  /** Globs `tag_directory` and FRN backfills skip, stored as a JSON list */
+  index_exclude_patterns: string[]
   thumbnail_size: number
+  /** Largest thumbnail size served; bigger requests are scaled down to it */
+  thumbnail_max_size: number
   thumbnail_force_shell_cache: boolean
   thumbnail_cache_max_mb: number
+  thumbnail_memory_cache_entries: number
   thumbnail_worker_count: number
   thumbnail_semaphore_count: number
+  thumbnail_cache_dir: string
+  thumbnail_folder_collage: boolean
+  /** Pre-generate thumbnails of recently opened folders while idle */
+  thumbnail_idle_warm: boolean
+  search_timeout_secs: number
+  search_history_max_entries: number
+  /** Ceiling on flat CQL results; past it only the first rows are returned */
+  cql_max_rows: number
+  /** Renaming a tag rewrites collection queries that name its old value */
+  rewrite_queries_on_tag_rename: boolean
+  tag_group_sort_mode: TagGroupSortMode
+  log_level: LogLevel
+  advanced_mode: boolean
 }
 
+/** Minimum severity written to the log file (`log_level` setting) */
+export type LogLevel = 'off' | 'error' | 'warn' | 'info' | 'debug' | 'trace'
+
 export interface CacheStats {
-  total_size_bytes: number
-  file_count: number
+  memory_entries: number
+  memory_bytes: number
+  disk_file_count: number
+  disk_size_bytes: number
   max_size_bytes: number
+  cache_dir: string
+  /** Idle warming is generating thumbnails right now */
+  warming: boolean
+  /** Thumbnails generated by idle warming since startup */
+  warmed_count: number
+}
+
+/** Item counts exclude soft-deleted items, which are in `deleted_items`. */
+export interface LibraryStats {
+  total_items: number
+  tagged_items: number
+  untagged_items: number
+  deleted_items: number
+  total_tags: number
+  total_groups: number
+  db_size_bytes: number
+}
+
+/** Result of `benchmark_cql`, in milliseconds, excluding parse time. */
+export interface CqlBenchmark {
+  iterations: number
+  row_count: number
+  min_ms: number
+  median_ms: number
+  max_ms: number
+}
+
+/**
+ * Result of `execute_readonly_sql`. Blobs come back as `"<blob: N bytes>"`;
+ * `truncated` is set when rows beyond the limit were dropped.
+ */
+export interface SqlQueryResult {
+  columns: string[]
+  rows: (string | number | null)[][]
+  truncated: boolean
+}
+
+export type SettingCategory = 'thumbnail' | 'usn' | 'search' | 'ui' | 'diagnostics'
+
+/** A setting as returned by `get_all_settings`. */
+export interface SettingEntry {
+  value: string
+  /** `null` for stored keys the backend schema doesn't know. */
+  category: SettingCategory | null
+  is_default: boolean
+}
+
+/** Payload of the `setting-changed` event. */
+export interface SettingChanged {
+  key: string
+  value: string | null
+}
+
+/** Payload of the `thumbnail-cache-migration` event. */
+export interface CacheMigrationProgress {
+  processed: number
+  total: number
+  done: boolean
+  error: string | null
+}
+
+const DEFAULT_EXCLUDE_PATTERNS = [
+  'node_modules',
+  '.git',
+  '.svn',
+  '.hg',
+  '__pycache__',
+  '*.tmp',
+  '~$*',
+  'Thumbs.db',
+  'desktop.ini',
+]
+
+function parsePatterns(raw: string | undefined): string[] {
+  if (raw === undefined) return DEFAULT_EXCLUDE_PATTERNS
+  try {
+    const parsed = JSON.parse(raw)
+    return Array.isArray(parsed) ? parsed.filter((p) => typeof p === 'string') : []
+  } catch {
+    return []
+  }
 }
 
 const DEFAULTS: AppSettings = {
   usn_auto_refresh: false,
   usn_refresh_on_missing: true,
   usn_cross_volume_match: true,
+  usn_apply_auto_tag_rules: false,
+  usn_frn_backfill_limit: 200,
+  rehydrate_tags_on_path_reuse: false,
+  index_exclude_patterns: DEFAULT_EXCLUDE_PATTERNS,
   thumbnail_size: 256,
+  thumbnail_max_size: 1024,
   thumbnail_force_shell_cache: false,
   thumbnail_cache_max_mb: 500,
+  thumbnail_memory_cache_entries: 200,
   thumbnail_worker_count: 0,
   thumbnail_semaphore_count: 0,
+  thumbnail_cache_dir: '',
+  thumbnail_folder_collage: false,
+  thumbnail_idle_warm: false,
+  search_timeout_secs: 30,
+  search_history_max_entries: 100,
+  cql_max_rows: 50000,
+  rewrite_queries_on_tag_rename: true,
+  tag_group_sort_mode: 'display_order',
+  log_level: 'info',
+  advanced_mode: false,
 }
 
 function parseSettings(raw: Record<string, string>): AppSettings {
@@ -35,16 +160,33 @@ function parseSettings(raw: Record<string, string>): AppSettings {
     usn_auto_refresh: raw.usn_auto_refresh === 'true',
     usn_refresh_on_missing: raw.usn_refresh_on_missing !== 'false',
     usn_cross_volume_match: raw.usn_cross_volume_match !== 'false',
+    usn_apply_auto_tag_rules: raw.usn_apply_auto_tag_rules === 'true',
+    usn_frn_backfill_limit: parseInt(raw.usn_frn_backfill_limit || '200', 10),
+    rehydrate_tags_on_path_reuse: raw.rehydrate_tags_on_path_reuse === 'true',
+    index_exclude_patterns: parsePatterns(raw.index_exclude_patterns),
     thumbnail_size: parseInt(raw.thumbnail_size || '256', 10),
+    thumbnail_max_size: parseInt(raw.thumbnail_max_size || '1024', 10),
     thumbnail_force_shell_cache: raw.thumbnail_force_shell_cache === 'true',
     thumbnail_cache_max_mb: parseInt(raw.thumbnail_cache_max_mb || '500', 10),
+    thumbnail_memory_cache_entries: parseInt(raw.thumbnail_memory_cache_entries || '200', 10),
     thumbnail_worker_count: parseInt(raw.thumbnail_worker_count || '0', 10),
     thumbnail_semaphore_count: parseInt(raw.thumbnail_semaphore_count || '0', 10),
+    thumbnail_cache_dir: raw.thumbnail_cache_dir || '',
+    thumbnail_folder_collage: raw.thumbnail_folder_collage === 'true',
+    thumbnail_idle_warm: raw.thumbnail_idle_warm === 'true',
+    search_timeout_secs: parseInt(raw.search_timeout_secs || '30', 10),
+    search_history_max_entries: parseInt(raw.search_history_max_entries || '100', 10),
+    cql_max_rows: parseInt(raw.cql_max_rows || '50000', 10),
+    rewrite_queries_on_tag_rename: raw.rewrite_queries_on_tag_rename !== 'false',
+    tag_group_sort_mode: (raw.tag_group_sort_mode as TagGroupSortMode) || 'display_order',
+    log_level: (raw.log_level as LogLevel) || 'info',
+    advanced_mode: raw.advanced_mode === 'true',
   }
 }
 
 export const useSettingsStore = defineStore('settings', () => {
   const raw = ref<Record<string, string>>({})
+  const entries = ref<Record<string, SettingEntry>>({})
   const loading = ref(false)
 
   const settings = computed<AppSettings>(() => {
@@ -52,10 +194,17 @@ export const useSettingsStore = defineStore('settings', () => {
     return parseSettings(raw.value)
   })
 
+  async function fetchEntries() {
+    entries.value = await invoke<Record<string, SettingEntry>>('get_all_settings')
+    raw.value = Object.fromEntries(
+      Object.entries(entries.value).map(([key, entry]) => [key, entry.value]),
+    )
+  }
+
   async function loadSettings() {
     loading.value = true
     try {
-      raw.value = await invoke<Record<string, string>>('get_all_settings')
+      await fetchEntries()
     } catch (e) {
       console.error('Failed to load settings:', e)
     } finally {
@@ -66,7 +215,7 @@ export const useSettingsStore = defineStore('settings', () => {
   async function updateSetting(key: string, value: string) {
     try {
       await invoke('update_setting', { key, value })
-      raw.value = { ...raw.value, [key]: value }
+      await fetchEntries()
     } catch (e) {
       console.error('Failed to update setting:', e)
       throw e
@@ -76,30 +225,148 @@ export const useSettingsStore = defineStore('settings', () => {
   async function resetSetting(key: string) {
     try {
       await invoke('reset_setting', { key })
-      const updated = { ...raw.value }
-      delete updated[key]
-      raw.value = updated
+      await fetchEntries()
     } catch (e) {
       console.error('Failed to reset setting:', e)
       throw e
     }
   }
 
+  /**
+   * Reset every setting in `category`, or all settings when omitted.
+   * The backend emits `setting-changed` for each key it reset.
+   */
+  async function resetSettings(category?: SettingCategory): Promise<string[]> {
+    try {
+      const keys = await invoke<string[]>('reset_settings', { category: category ?? null })
+      await fetchEntries()
+      return keys
+    } catch (e) {
+      console.error('Failed to reset settings:', e)
+      throw e
+    }
+  }
+
+  /** Whether a setting differs from its default, for "modified" badges. */
+  function isModified(key: string): boolean {
+    const entry = entries.value[key]
+    return entry !== undefined && !entry.is_default
+  }
+
   async function getCacheStats(): Promise<CacheStats> {
     return await invoke<CacheStats>('get_cache_stats')
   }
 
+  async function getLibraryStats(): Promise<LibraryStats> {
+    return await invoke<LibraryStats>('get_library_stats')
+  }
+
+  /** Schema version of the open database (`PRAGMA user_version`). */
+  async function getSchemaVersion(): Promise<number> {
+    return await invoke<number>('get_schema_version')
+  }
+
   async function clearThumbnailCache(): Promise<CacheStats> {
     return await invoke<CacheStats>('clear_thumbnail_cache')
   }
 
+  async function clearThumbnailMemoryCache(): Promise<CacheStats> {
+    return await invoke<CacheStats>('clear_thumbnail_memory_cache')
+  }
+
+  /** Delete the cached thumbnails of one file (all sizes); returns how many were removed. */
+  async function invalidateThumbnail(path: string): Promise<number> {
+    return await invoke<number>('invalidate_thumbnail', { path })
+  }
+
+  /** Disk path of the cached WebP thumbnail of `path` at `size`, or null if none was generated. */
+  async function getCachedThumbnailPath(path: string, size: number): Promise<string | null> {
+    return await invoke<string | null>('get_cached_thumbnail_path', { path, size })
+  }
+
+  /**
+   * WebP thumbnails of `path` at each of `sizes`, keyed by size. Uncached
+   * sizes share one generation, so a list and a preview size cost one decode.
+   */
+  async function getThumbnails(path: string, sizes: number[]): Promise<Record<number, Uint8Array>> {
+    const raw = await invoke<Record<string, number[]>>('get_thumbnails', { path, sizes })
+    return Object.fromEntries(
+      Object.entries(raw).map(([size, bytes]) => [Number(size), new Uint8Array(bytes)]),
+    )
+  }
+
+  /** Last `lines` lines of the log file (default 200), oldest first. */
+  async function getRecentLogs(lines?: number): Promise<string[]> {
+    return await invoke<string[]>('get_recent_logs', { lines })
+  }
+
+  async function explainCql(query: string): Promise<string[]> {
+    return await invoke<string[]>('explain_cql', { query })
+  }
+
+  /** Run a CQL query `iterations` times (1-1000) and report min/median/max. */
+  async function benchmarkCql(query: string, iterations: number): Promise<CqlBenchmark> {
+    return await invoke<CqlBenchmark>('benchmark_cql', { query, iterations })
+  }
+
+  /**
+   * Run a single read-only SELECT/WITH query against the library database.
+   * Requires `advanced_mode`; returns up to `maxRows` rows (default 1000).
+   */
+  async function executeReadonlySql(sql: string, maxRows?: number): Promise<SqlQueryResult> {
+    return await invoke<SqlQueryResult>('execute_readonly_sql', { sql, maxRows })
+  }
+
+  /** Rebuild all database indexes and refresh query planner statistics. */
+  async function rebuildIndexes(): Promise<void> {
+    await invoke('rebuild_indexes')
+  }
+
+  /**
+   * Re-run first-run seeding of the starter tag groups. Returns whether
+   * anything was created (only when no tag groups exist); reload the tags
+   * store afterwards.
+   */
+  async function resetOnboarding(): Promise<boolean> {
+    return await invoke<boolean>('reset_onboarding')
+  }
+
+  /**
+   * Move the thumbnail disk cache. Empty path = default AppData location;
+   * a custom path gets a `constellation-thumbs` subdirectory, and the
+   * resolved directory is returned.
+   * Existing files are moved (`migrate`) or deleted in the background;
+   * listen for `thumbnail-cache-migration` to follow progress.
+   */
+  async function setCacheDirectory(path: string, migrate: boolean): Promise<string> {
+    const resolved = await invoke<string>('set_cache_directory', { path, migrate })
+    await loadSettings()
+    return resolved
+  }
+
   return {
     settings,
+    entries,
     loading,
     loadSettings,
     updateSetting,
     resetSetting,
+    resetSettings,
+    isModified,
     getCacheStats,
+    getLibraryStats,
+    getSchemaVersion,
     clearThumbnailCache,
+    clearThumbnailMemoryCache,
+    invalidateThumbnail,
+    getCachedThumbnailPath,
+    getThumbnails,
+    setCacheDirectory,
+    getRecentLogs,
+    explainCql,
+    benchmarkCql,
+    executeReadonlySql,
+    rebuildIndexes,
+    resetOnboarding,
   }
 })
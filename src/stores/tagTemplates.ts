@@ -1,10 +1,13 @@
 import { defineStore } from 'pinia'
 import { ref } from 'vue'
 import { invoke } from '@tauri-apps/api/core'
-import type { TagTemplate } from '@/types'
+import { useTagsStore } from '@/stores/tags'
+import { errorMessage } from '@/utils'
+import type { AutoTagRule, TagSpec, TagTemplate, TemplateApplicability, TemplateApplyPreview, TemplatePreview } from '@/types'
 
 export const useTagTemplatesStore = defineStore('tagTemplates', () => {
   const templates = ref<TagTemplate[]>([])
+  const rules = ref<AutoTagRule[]>([])
   const loading = ref(false)
   const error = ref<string | null>(null)
 
@@ -14,65 +17,153 @@ export const useTagTemplatesStore = defineStore('tagTemplates', () => {
     try {
       templates.value = await invoke<TagTemplate[]>('get_tag_templates')
     } catch (e) {
-      error.value = e as string
+      error.value = errorMessage(e)
       console.error('Failed to load tag templates:', e)
     } finally {
       loading.value = false
     }
   }
 
-  async function createTemplate(name: string, tagIds: number[]) {
+  /** `tagSpecs` name tags by group and value; missing ones are created when the template is applied */
+  async function createTemplate(name: string, tagIds: number[], tagSpecs: TagSpec[] = []) {
     try {
-      const id = await invoke<number>('create_tag_template', { name, tagIds })
+      const id = await invoke<number>('create_tag_template', { name, tagIds, tagSpecs })
       await loadTemplates()
       return id
     } catch (e) {
-      error.value = e as string
+      error.value = errorMessage(e)
       console.error('Failed to create tag template:', e)
       throw e
     }
   }
 
-  async function applyTemplate(itemId: number, templateId: number) {
+  /** With a `previewToken` from `previewApply`, fails if tags changed since the preview. */
+  async function applyTemplate(itemId: number, templateId: number, previewToken?: string) {
     try {
-      await invoke('apply_tag_template', { itemId, templateId })
+      await invoke('apply_tag_template', { itemId, templateId, previewToken })
+      // Applying may have created groups and tags from the template's specs
+      const tagsStore = useTagsStore()
+      await Promise.all([tagsStore.loadTagGroups(true), tagsStore.loadTags(true)])
     } catch (e) {
-      error.value = e as string
+      error.value = errorMessage(e)
       console.error('Failed to apply tag template:', e)
       throw e
     }
   }
 
+  async function previewTemplate(itemId: number, templateId: number) {
+    try {
+      return await invoke<TemplatePreview>('preview_tag_template', { itemId, templateId })
+    } catch (e) {
+      error.value = errorMessage(e)
+      console.error('Failed to preview tag template:', e)
+      throw e
+    }
+  }
+
+  async function previewApply(templateId: number, paths: string[]) {
+    try {
+      return await invoke<TemplateApplyPreview>('preview_apply_template', { templateId, paths })
+    } catch (e) {
+      error.value = errorMessage(e)
+      console.error('Failed to preview tag template application:', e)
+      throw e
+    }
+  }
+
+  async function getApplicableTemplates(itemId: number) {
+    try {
+      return await invoke<TemplateApplicability[]>('get_applicable_templates', { itemId })
+    } catch (e) {
+      error.value = errorMessage(e)
+      console.error('Failed to get applicable tag templates:', e)
+      throw e
+    }
+  }
+
   async function deleteTemplate(id: number) {
     try {
       await invoke('delete_tag_template', { id })
       await loadTemplates()
+      // Rules for the template are deleted with it
+      rules.value = rules.value.filter(r => r.template_id !== id)
     } catch (e) {
-      error.value = e as string
+      error.value = errorMessage(e)
       console.error('Failed to delete tag template:', e)
       throw e
     }
   }
 
-  async function updateTemplate(id: number, name?: string, tagIds?: number[]) {
+  async function updateTemplate(id: number, name?: string, tagIds?: number[], tagSpecs?: TagSpec[]) {
     try {
-      await invoke('update_tag_template', { id, name, tagIds })
+      await invoke('update_tag_template', { id, name, tagIds, tagSpecs })
       await loadTemplates()
     } catch (e) {
-      error.value = e as string
+      error.value = errorMessage(e)
       console.error('Failed to update tag template:', e)
       throw e
     }
   }
 
+  async function loadRules() {
+    try {
+      rules.value = await invoke<AutoTagRule[]>('get_auto_tag_rules')
+    } catch (e) {
+      error.value = errorMessage(e)
+      console.error('Failed to load auto tag rules:', e)
+    }
+  }
+
+  async function createRule(pathPrefix: string, templateId: number, enabled = true) {
+    try {
+      const id = await invoke<number>('create_auto_tag_rule', { pathPrefix, templateId, enabled })
+      await loadRules()
+      return id
+    } catch (e) {
+      error.value = errorMessage(e)
+      console.error('Failed to create auto tag rule:', e)
+      throw e
+    }
+  }
+
+  async function updateRule(id: number, changes: { pathPrefix?: string; templateId?: number; enabled?: boolean }) {
+    try {
+      await invoke('update_auto_tag_rule', { id, ...changes })
+      await loadRules()
+    } catch (e) {
+      error.value = errorMessage(e)
+      console.error('Failed to update auto tag rule:', e)
+      throw e
+    }
+  }
+
+  async function deleteRule(id: number) {
+    try {
+      await invoke('delete_auto_tag_rule', { id })
+      await loadRules()
+    } catch (e) {
+      error.value = errorMessage(e)
+      console.error('Failed to delete auto tag rule:', e)
+      throw e
+    }
+  }
+
   return {
     templates,
+    rules,
     loading,
     error,
     loadTemplates,
     createTemplate,
     applyTemplate,
+    previewTemplate,
+    previewApply,
+    getApplicableTemplates,
     deleteTemplate,
     updateTemplate,
+    loadRules,
+    createRule,
+    updateRule,
+    deleteRule,
   }
 })
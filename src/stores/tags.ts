@@ -1,7 +1,19 @@
 import { defineStore } from 'pinia'
 import { ref } from 'vue'
 import { invoke } from '@tauri-apps/api/core'
-import type { Tag, TagGroup } from '@/types'
+import { errorMessage } from '@/utils'
+import { useCollectionsStore } from '@/stores/collections'
+import type {
+  ExclusiveViolation,
+  PagedTags,
+  Tag,
+  TagCount,
+  TagGroup,
+  TagGroupSortMode,
+  TagPair,
+  VocabularyExport,
+  VocabularyFormat,
+} from '@/types'
 
 export const useTagsStore = defineStore('tags', () => {
   const tagGroups = ref<TagGroup[]>([])
@@ -20,7 +32,7 @@ export const useTagsStore = defineStore('tags', () => {
     try {
       tagGroups.value = await invoke<TagGroup[]>('get_tag_groups')
     } catch (e) {
-      error.value = e as string
+      error.value = errorMessage(e)
       console.error('Failed to load tag groups:', e)
     } finally {
       if (!silent) {
@@ -37,7 +49,7 @@ export const useTagsStore = defineStore('tags', () => {
     try {
       tags.value = await invoke<Tag[]>('get_all_tags')
     } catch (e) {
-      error.value = e as string
+      error.value = errorMessage(e)
       console.error('Failed to load tags:', e)
     } finally {
       if (!silent) {
@@ -54,6 +66,27 @@ export const useTagsStore = defineStore('tags', () => {
     }
   }
 
+  /**
+   * One page of tags, for lists that load more as they scroll; with `groupId`
+   * only that group's tags. Does not touch `tags`.
+   */
+  async function loadTagsPage(limit: number, offset: number, groupId?: number): Promise<PagedTags> {
+    if (groupId === undefined) {
+      return await invoke<PagedTags>('get_all_tags_paged', { limit, offset })
+    }
+    return await invoke<PagedTags>('get_all_tags_by_group_paged', { groupId, limit, offset })
+  }
+
+  /** Pairs of tags used together on at least `minCount` items, most frequent first */
+  async function getTagCooccurrence(minCount?: number): Promise<TagPair[]> {
+    try {
+      return await invoke<TagPair[]>('get_tag_cooccurrence', { minCount })
+    } catch (e) {
+      console.error('Failed to load tag co-occurrence:', e)
+      return []
+    }
+  }
+
   async function searchTags(query: string, groupId?: number): Promise<Tag[]> {
     try {
       return await invoke<Tag[]>('search_tags', { query, groupId })
@@ -63,17 +96,32 @@ export const useTagsStore = defineStore('tags', () => {
     }
   }
 
-  async function createTagGroup(name: string, color: string | null = null, displayOrder: number = 0) {
+  async function getTagsAmongItems(itemIds: number[]): Promise<TagCount[]> {
+    try {
+      return await invoke<TagCount[]>('get_tags_among_items', { itemIds })
+    } catch (e) {
+      console.error('Failed to load tags for selection:', e)
+      return []
+    }
+  }
+
+  async function createTagGroup(
+    name: string,
+    color: string | null = null,
+    displayOrder: number = 0,
+    exclusive: boolean = false,
+  ) {
     try {
       const id = await invoke<number>('create_tag_group', {
         name,
         color,
         displayOrder,
+        exclusive,
       })
       await loadTagGroups(true)
       return id
     } catch (e) {
-      error.value = e as string
+      error.value = errorMessage(e)
       console.error('Failed to create tag group:', e)
       throw e
     }
@@ -89,22 +137,23 @@ export const useTagsStore = defineStore('tags', () => {
       await loadUsageCounts()
       return id
     } catch (e) {
-      error.value = e as string
+      error.value = errorMessage(e)
       console.error('Failed to create tag:', e)
       throw e
     }
   }
 
-  async function updateTagGroup(id: number, name?: string, color?: string) {
+  async function updateTagGroup(id: number, name?: string, color?: string, exclusive?: boolean) {
     try {
       await invoke('update_tag_group', {
         id,
         name: name || null,
         color: color || null,
+        exclusive: exclusive ?? null,
       })
       await loadTagGroups(true)
     } catch (e) {
-      error.value = e as string
+      error.value = errorMessage(e)
       console.error('Failed to update tag group:', e)
       throw e
     }
@@ -119,8 +168,10 @@ export const useTagsStore = defineStore('tags', () => {
       })
       await loadTags(true)
       await loadUsageCounts()
+      // A rename may have rewritten collection queries
+      if (value) await useCollectionsStore().loadCollections()
     } catch (e) {
-      error.value = e as string
+      error.value = errorMessage(e)
       console.error('Failed to update tag:', e)
       throw e
     }
@@ -133,12 +184,40 @@ export const useTagsStore = defineStore('tags', () => {
       await loadUsageCounts()
       itemTagsVersion.value++
     } catch (e) {
-      error.value = e as string
+      error.value = errorMessage(e)
       console.error('Failed to merge tags:', e)
       throw e
     }
   }
 
+  async function splitTag(id: number, intoTagIds: number[], removeSource = false): Promise<number> {
+    try {
+      const affected = await invoke<number>('split_tag', { id, intoTagIds, removeSource })
+      await loadTags(true)
+      await loadUsageCounts()
+      itemTagsVersion.value++
+      return affected
+    } catch (e) {
+      error.value = errorMessage(e)
+      console.error('Failed to split tag:', e)
+      throw e
+    }
+  }
+
+  async function moveTagToGroup(id: number, targetGroupId: number, merge = false): Promise<number> {
+    try {
+      const resultId = await invoke<number>('move_tag_to_group', { id, targetGroupId, merge })
+      await loadTags(true)
+      await loadUsageCounts()
+      itemTagsVersion.value++
+      return resultId
+    } catch (e) {
+      error.value = errorMessage(e)
+      console.error('Failed to move tag:', e)
+      throw e
+    }
+  }
+
   function getTagsByGroup(groupId: number): Tag[] {
     return tags.value.filter(tag => tag.group_id === groupId)
   }
@@ -153,12 +232,35 @@ export const useTagsStore = defineStore('tags', () => {
       await invoke('reorder_tag_groups', { orders })
       await loadTagGroups(true)
     } catch (e) {
-      error.value = e as string
+      error.value = errorMessage(e)
       console.error('Failed to reorder tag groups:', e)
       throw e
     }
   }
 
+  /** Renumber all groups 0..n by current order, then name, repairing duplicates and gaps. */
+  async function normalizeTagGroupOrder() {
+    try {
+      await invoke<TagGroup[]>('normalize_tag_group_order')
+      await loadTagGroups(true)
+    } catch (e) {
+      error.value = errorMessage(e)
+      console.error('Failed to normalize tag group order:', e)
+      throw e
+    }
+  }
+
+  async function setTagGroupSortMode(mode: TagGroupSortMode) {
+    try {
+      await invoke('set_tag_group_sort_mode', { mode })
+      await loadTagGroups(true)
+    } catch (e) {
+      error.value = errorMessage(e)
+      console.error('Failed to set tag group sort mode:', e)
+      throw e
+    }
+  }
+
   async function deleteTagGroup(id: number) {
     try {
       await invoke('delete_tag_group', { id })
@@ -167,7 +269,7 @@ export const useTagsStore = defineStore('tags', () => {
       await loadUsageCounts()
       itemTagsVersion.value++
     } catch (e) {
-      error.value = e as string
+      error.value = errorMessage(e)
       console.error('Failed to delete tag group:', e)
       throw e
     }
@@ -180,12 +282,51 @@ export const useTagsStore = defineStore('tags', () => {
       await loadUsageCounts()
       itemTagsVersion.value++
     } catch (e) {
-      error.value = e as string
+      error.value = errorMessage(e)
       console.error('Failed to delete tag:', e)
       throw e
     }
   }
 
+  /** Remove a tag from every item, keeping the tag. Returns the count removed. */
+  async function clearTagUsage(id: number): Promise<number> {
+    try {
+      const removed = await invoke<number>('clear_tag_usage', { id })
+      await loadUsageCounts()
+      itemTagsVersion.value++
+      return removed
+    } catch (e) {
+      error.value = errorMessage(e)
+      console.error('Failed to clear tag usage:', e)
+      throw e
+    }
+  }
+
+  /** Write all groups and tags with usage counts to `path` as Markdown or CSV. */
+  async function exportTagVocabulary(path: string, format: VocabularyFormat): Promise<VocabularyExport> {
+    return await invoke<VocabularyExport>('export_tag_vocabulary', { path, format })
+  }
+
+  /** Pre-defined values for a group first, then its existing tag values. */
+  async function getTagSuggestions(groupId: number): Promise<string[]> {
+    try {
+      return await invoke<string[]>('get_tag_suggestions', { groupId })
+    } catch (e) {
+      console.error('Failed to load tag suggestions:', e)
+      return []
+    }
+  }
+
+  /** Replace a group's pre-defined values. An empty list clears them. */
+  async function setTagSuggestions(groupId: number, suggestions: string[]): Promise<string[]> {
+    return await invoke<string[]>('set_tag_suggestions', { groupId, suggestions })
+  }
+
+  /** List items carrying more than one tag of the group. */
+  async function getExclusiveViolations(groupId: number): Promise<ExclusiveViolation[]> {
+    return await invoke<ExclusiveViolation[]>('get_exclusive_violations', { groupId })
+  }
+
   return {
     tagGroups,
     tags,
@@ -195,16 +336,28 @@ export const useTagsStore = defineStore('tags', () => {
     itemTagsVersion,
     loadTagGroups,
     loadTags,
+    loadTagsPage,
     loadUsageCounts,
     searchTags,
+    getTagsAmongItems,
     createTagGroup,
     updateTagGroup,
     createTag,
     updateTag,
     mergeTags,
+    splitTag,
+    moveTagToGroup,
     getTagsByGroup,
     reorderTagGroups,
+    normalizeTagGroupOrder,
+    setTagGroupSortMode,
     deleteTagGroup,
     deleteTag,
+    clearTagUsage,
+    getTagCooccurrence,
+    exportTagVocabulary,
+    getExclusiveViolations,
+    getTagSuggestions,
+    setTagSuggestions,
   }
 })
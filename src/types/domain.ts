@@ -3,6 +3,8 @@
  * These types represent the core business entities.
  */
 
+import type { FilenameMatchMode, ItemSort } from './ui'
+
 /** Database item representing a tagged file or directory */
 export interface Item {
   id: number
@@ -12,6 +14,37 @@ export interface Item {
   modified_time: number | null
   created_at: number
   updated_at: number
+  /** Last time the item's tags changed (null if never tagged) */
+  tags_updated_at: number | null
+  /** Star rating 0-5 (null if unrated) */
+  rating: number | null
+  /** Number of tags, only filled in when requested */
+  tag_count?: number | null
+  /** NTFS file ID as a decimal string (exceeds safe integers); only filled in by item commands */
+  file_reference_number?: string | null
+  /** Whether USN refresh can follow renames; only filled in by `get_item` and `refresh_item_frn` */
+  frn_status?: FrnStatus | null
+}
+
+/** `tracked`: file ID recorded; `untracked`: on NTFS without one; `not_ntfs`: none available */
+export type FrnStatus = 'tracked' | 'untracked' | 'not_ntfs'
+
+/** A requested path paired with its item record (null if not registered) */
+export interface PathCheck {
+  path: string
+  item: Item | null
+}
+
+/** Result of a bulk path prefix update */
+export interface BulkUpdateResult {
+  updated_count: number
+}
+
+/** Result of `normalize_item_paths` */
+export interface PathNormalization {
+  renamed_count: number
+  /** Items deleted after their tags moved to the item with the same path */
+  merged_count: number
 }
 
 /** Tag group for organizing tags */
@@ -19,11 +52,25 @@ export interface TagGroup {
   id: number
   name: string
   color: string | null
+  /** CSS name of `color` when it is exactly a named color */
+  color_name: string | null
   display_order: number
+  /** Items may carry at most one tag of an exclusive group */
+  exclusive: boolean
   created_at: number
   updated_at: number
 }
 
+/** Item carrying more than one tag of a group */
+export interface ExclusiveViolation {
+  item_id: number
+  path: string
+  tag_ids: number[]
+}
+
+/** Ordering for the tag group list (`tag_group_sort_mode` setting) */
+export type TagGroupSortMode = 'display_order' | 'alphabetical' | 'by_tag_count'
+
 /** Tag that can be applied to items */
 export interface Tag {
   id: number
@@ -33,15 +80,162 @@ export interface Tag {
   updated_at: number
 }
 
+/** One page of tags; `total_count` counts every page */
+export interface PagedTags {
+  tags: Tag[]
+  total_count: number
+}
+
+/** Tag with the number of selected items carrying it */
+export interface TagCount {
+  tag: Tag
+  count: number
+}
+
+/** Two tags used together, with the number of items carrying both */
+export interface TagPair {
+  tag_id_a: number
+  tag_id_b: number
+  count: number
+}
+
+/** File format for the tag vocabulary export */
+export type VocabularyFormat = 'markdown' | 'csv'
+
+/** Number of groups and tags written by a vocabulary export */
+export interface VocabularyExport {
+  groups: number
+  tags: number
+}
+
+/** Returned by `export_html_gallery` once the search has run */
+export interface GalleryExportJob {
+  job_id: number
+  /** Items being exported, after the cap */
+  item_count: number
+  /** Items the query matched before the cap */
+  matched_count: number
+}
+
+/** Item returned together with its tags (`include_tags` searches, listing views) */
+export interface ItemWithTags {
+  item: Item
+  tags: Tag[]
+}
+
+/** Item sharing tags with another item, with the number of tags shared */
+export interface SimilarItem {
+  item: Item
+  shared_tags: number
+}
+
+/** Recommended item; each shared tag adds 1 / (items carrying it) to the score */
+export interface Recommendation {
+  item: Item
+  score: number
+}
+
+/** Tag named by group and value; applying a template creates it if missing */
+export interface TagSpec {
+  group_name: string
+  value: string
+}
+
 /** Tag template for applying multiple tags at once */
 export interface TagTemplate {
   id: number
   name: string
   tag_ids: number[]
+  tag_specs: TagSpec[]
   created_at: number
   updated_at: number
 }
 
+/** Applies a template's tags to items created under `path_prefix`; the longest enabled prefix wins */
+export interface AutoTagRule {
+  id: number
+  path_prefix: string
+  template_id: number
+  enabled: boolean
+  created_at: number
+  updated_at: number
+}
+
+/** Saved CQL query shown in the sidebar as a virtual folder */
+export interface Collection {
+  id: number
+  name: string
+  cql_query: string
+  icon: string | null
+  display_order: number
+  created_at: number
+  updated_at: number
+}
+
+/** Collection with its current item count; `count` is null and `error` set when its query fails */
+export interface CollectionWithCount {
+  collection: Collection
+  count: number | null
+  error: string | null
+}
+
+/** Effect of applying a template to an item, split by whether the item already has each tag */
+export interface TemplatePreview {
+  to_add: Tag[]
+  already_present: Tag[]
+  /** Spec tags that don't exist yet and would be created */
+  to_create: TagSpec[]
+}
+
+export interface TemplateApplicability {
+  template_id: number
+  name: string
+  would_add: Tag[]
+  already_has: Tag[]
+  would_create: TagSpec[]
+  fully_applied: boolean
+}
+
+/** One path of a template apply preview */
+export interface PathApplyPreview {
+  path: string
+  exists: boolean
+  /** `null` when the path is not registered */
+  item_id: number | null
+  /** Template tags the item doesn't carry yet, in template order */
+  missing_tag_ids: number[]
+}
+
+/** Template preview across paths; pass `token` to `applyTemplate` to reject concurrent changes */
+export interface TemplateApplyPreview {
+  token: string
+  items: PathApplyPreview[]
+  /** Spec tags that don't exist yet; they are created and added to every item */
+  to_create: TagSpec[]
+}
+
+
+/** A CQL query in structured form: clauses joined with AND */
+export interface QuerySpec {
+  clauses: QueryClause[]
+}
+
+/** One clause of a `QuerySpec` */
+export interface QueryClause {
+  /** CQL field keyword, e.g. `tag` or `size` */
+  field: string
+  /** `=`, `!=`, `~`, `>`, `<`, `>=`, `<=` or `in` */
+  op: string
+  /** Unquoted values: one, or several for `in`. Sizes may take a unit (`10MB`), dates are `YYYY-MM-DD` */
+  values: string[]
+  negated?: boolean
+}
+
+/** Result of `parse_to_spec`; `spec` is null when the query needs OR or nesting */
+export interface ParsedQuerySpec {
+  spec: QuerySpec | null
+  not_representable: boolean
+}
 
 /** Search criteria */
 export interface SearchCriteria {
@@ -50,6 +244,14 @@ export interface SearchCriteria {
   filename_query: string | null
 }
 
+/** A search request for the unified `search` command */
+export type SearchRequest =
+  | { type: 'tags'; tag_ids: number[]; mode: 'and' | 'or'; sort?: ItemSort }
+  | { type: 'filename'; query: string; match_mode?: FilenameMatchMode; sort?: ItemSort }
+  | { type: 'fuzzy_filename'; query: string }
+  | { type: 'combined'; tag_ids: number[]; mode: 'and' | 'or'; filename_query: string | null; sort?: ItemSort }
+  | { type: 'cql'; query: string }
+
 /** Search history entry */
 export interface SearchHistory {
   id: number
@@ -57,9 +259,113 @@ export interface SearchHistory {
   last_used_at: number
 }
 
+/** Result of `create_items`: one id per input path, created or already registered */
+export interface CreateItemsResult {
+  created_count: number
+  skipped_count: number
+  item_ids: number[]
+}
+
+/** Result of `refresh_item_metadata`; missing files are counted but left untouched */
+export interface MetadataRefreshResult {
+  checked: number
+  updated: number
+  missing: number
+  /** Stopped through `cancelOperation`; the counts cover the items processed */
+  cancelled: boolean
+}
+
 /** Result of a batch tag operation */
 export interface BatchTagResult {
   success_count: number
+  /** Items skipped because they changed since the versions passed in */
   failed_count: number
   created_count: number
+  conflicts: TagConflict[]
+  /** Paths that could not be tagged: invalid, or with no item to tag */
+  skipped_paths: string[]
+  /** Set when cancelled before every item was written */
+  cancelled: boolean
+}
+
+/** Payload of the `batch-tag-progress` event, sent every 100 items */
+export interface BatchProgress {
+  operation_id: string | null
+  operation: 'add' | 'remove'
+  processed: number
+  total: number
+}
+
+/** Item a batch tag write skipped, with its current `tags_updated_at` */
+export interface TagConflict {
+  item_id: number
+  path: string
+  tags_updated_at: number
+}
+
+/** Outcome of `update_item_tags`; on conflict nothing was written and the current tags are returned for merging */
+export type TagUpdateResult =
+  | { status: 'updated'; tags_updated_at: number }
+  | { status: 'conflict'; tags_updated_at: number; current_tags: Tag[] }
+
+/** Counts from tagging every file under a directory */
+export interface DirectoryTagResult {
+  scanned_count: number
+  /** Files and directories skipped by `index_exclude_patterns` */
+  excluded_count: number
+  created_count: number
+  tagged_count: number
+  /** Cancelled; batches tagged before that stay tagged */
+  cancelled: boolean
+}
+
+/** Grouping applied to CQL search results */
+export type GroupBy =
+  | { type: 'none' }
+  | { type: 'parent_dir' }
+  | { type: 'top_tag_group'; group_id: number }
+
+/** One group of CQL search results */
+export interface ResultGroup {
+  /** Parent directory, or tag value ('' for items without a tag in the group) */
+  key: string
+  count: number
+  sample_items: Item[]
+}
+
+/** Grouped CQL search results */
+export interface GroupedResults {
+  groups: ResultGroup[]
+}
+
+/** Flat CQL results cut off at the `cql_max_rows` setting */
+export interface TruncatedResults<T> {
+  /** The first matches, in path order */
+  items: T[]
+  truncated: true
+  /** Number of items the query matched */
+  total_count: number
+}
+
+/** Stable error kinds reported by backend commands */
+export type AppErrorCode =
+  | 'DATABASE_ERROR'
+  | 'IO_ERROR'
+  | 'NOT_FOUND'
+  | 'INVALID_INPUT'
+  | 'DUPLICATE'
+  | 'DOMAIN_ERROR'
+  | 'THUMBNAIL_ERROR'
+  | 'USN_JOURNAL_ERROR'
+  | 'DATABASE_BUSY'
+  | 'TIMEOUT'
+  | 'CANCELLED'
+  | 'CONFLICT'
+
+/** Rejection value of a failed command; switch on `code`, show `message` */
+export interface AppError {
+  code: AppErrorCode
+  message: string
+  /** Machine-readable specifics, e.g. `sqlite_code` for database errors or `kind` for IO errors */
+  details: Record<string, unknown>
 }
@@ -12,6 +12,17 @@ export interface DriveInfo {
   available_space: number | null
 }
 
+/** Present drive with its file system; `usn_capable` drives (NTFS, ReFS) can be refreshed via the USN Journal */
+export interface IndexableDrive {
+  letter: string
+  label: string | null
+  drive_type: string
+  filesystem: string | null
+  usn_capable: boolean
+  total_space: number | null
+  available_space: number | null
+}
+
 /** File or directory entry in a directory listing */
 export interface FileEntry {
   name: string
@@ -22,6 +33,15 @@ export interface FileEntry {
   is_hidden: boolean
 }
 
+/** Recursive entry counts for a directory */
+export interface DirectoryCount {
+  file_count: number
+  directory_count: number
+  total_size_bytes: number
+  /** True when `max_depth` stopped the walk above a non-empty directory */
+  truncated: boolean
+}
+
 /** Detailed file metadata */
 export interface FileMetadata {
   path: string
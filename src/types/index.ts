@@ -3,10 +3,10 @@
  */
 
 // Domain types
-export type { Item, Tag, TagGroup, TagTemplate, SearchHistory, SearchCriteria, BatchTagResult } from './domain'
+export type { Item, FrnStatus, ItemWithTags, SimilarItem, Recommendation, PathCheck, BulkUpdateResult, PathNormalization, Tag, PagedTags, TagCount, TagPair, TagGroup, ExclusiveViolation, TagGroupSortMode, VocabularyFormat, VocabularyExport, GalleryExportJob, TagSpec, TagTemplate, AutoTagRule, Collection, CollectionWithCount, TemplatePreview, TemplateApplicability, PathApplyPreview, TemplateApplyPreview, SearchHistory, QuerySpec, QueryClause, ParsedQuerySpec, SearchCriteria, SearchRequest, BatchTagResult, BatchProgress, TagConflict, TagUpdateResult, DirectoryTagResult, CreateItemsResult, MetadataRefreshResult, GroupBy, ResultGroup, GroupedResults, TruncatedResults, AppError, AppErrorCode } from './domain'
 
 // File system types
-export type { DriveInfo, FileEntry, FileMetadata } from './filesystem'
+export type { DriveInfo, IndexableDrive, FileEntry, FileMetadata, DirectoryCount } from './filesystem'
 
 // UI types
-export type { ViewMode, DisplayMode, SearchMode, SearchInputMode } from './ui'
+export type { ViewMode, DisplayMode, SearchMode, SearchInputMode, SortField, SortDirection, ItemSort, FilenameMatchMode, TimeField } from './ui'
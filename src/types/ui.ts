@@ -12,5 +12,23 @@ export type DisplayMode = 'detail' | 'large-icons'
 /** Search mode for tag-based searches */
 export type SearchMode = 'and' | 'or'
 
+/** Column used to order search results */
+export type SortField = 'path' | 'size' | 'modified' | 'name' | 'created'
+
+/** How a filename query is matched: anywhere in the path, or against the file name */
+export type FilenameMatchMode = 'contains' | 'prefix' | 'exact'
+
+/** Timestamp a time window search filters on: file modification or registration */
+export type TimeField = 'modified' | 'created'
+
+/** Direction for ordering search results */
+export type SortDirection = 'asc' | 'desc'
+
+/** Ordering for search results (backend defaults to path ascending) */
+export interface ItemSort {
+  field: SortField
+  direction: SortDirection
+}
+
 /** Search input mode: simple filter UI or CQL query language */
 export type SearchInputMode = 'simple' | 'cql'
@@ -0,0 +1,29 @@
+/**
+ * Command error utility functions.
+ */
+
+import type { AppError, AppErrorCode } from '@/types'
+
+/**
+ * Checks whether a caught value is a structured backend error.
+ * @param e - The caught value
+ * @param code - Only match errors with this code
+ * @returns True if `e` is an `AppError` (with `code`, when given)
+ */
+export function isAppError(e: unknown, code?: AppErrorCode): e is AppError {
+  if (typeof e !== 'object' || e === null) return false
+  const candidate = e as Partial<AppError>
+  if (typeof candidate.code !== 'string' || typeof candidate.message !== 'string') return false
+  return code === undefined || candidate.code === code
+}
+
+/**
+ * Extracts a displayable message from a caught value.
+ * @param e - A backend `AppError`, an `Error` or anything else
+ * @returns The error message
+ */
+export function errorMessage(e: unknown): string {
+  if (isAppError(e)) return e.message
+  if (e instanceof Error) return e.message
+  return String(e)
+}
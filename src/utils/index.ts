@@ -3,8 +3,10 @@
  */
 
 export { formatBytes, formatDate, formatDateTime, formatRelativeDate } from './format'
+export { errorMessage, isAppError } from './error'
 export { getFileName, getParentPath, isDriveRoot } from './path'
 export { fuzzyMatch, getHighlightRanges } from './search'
 export { getTagTextWidth } from './text'
 export { getFileIcon } from './fileIcons'
-export { getThumbnailUrl, getAssetUrl, isImageFile, isVideoFile, isMediaFile } from './media'
+export { getThumbnailUrl, getThumbnailError, getAssetUrl, isImageFile, isVideoFile, isMediaFile } from './media'
+export type { ThumbErrorCode } from './media'
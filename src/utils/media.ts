@@ -48,6 +48,23 @@ export function getThumbnailUrl(filePath: string, size: number = 256): string {
   return `http://thumb.localhost/${encoded}?size=${size}`
 }
 
+/** `X-Thumb-Error` value of a failed thumbnail request */
+export type ThumbErrorCode = 'invalid_url' | 'not_found' | 'unavailable' | 'generate_failed'
+
+/**
+ * Why a thumbnail URL failed to load, for choosing a placeholder after an
+ * `<img>` error event. Returns null if the request now succeeds.
+ */
+export async function getThumbnailError(url: string): Promise<ThumbErrorCode | null> {
+  try {
+    const response = await fetch(url)
+    if (response.ok) return null
+    return (response.headers.get('X-Thumb-Error') as ThumbErrorCode | null) ?? 'generate_failed'
+  } catch {
+    return 'unavailable'
+  }
+}
+
 /**
  * Get full-resolution asset URL for a file path.
  * Uses Tauri's built-in asset protocol for streaming files to WebView.